@@ -140,6 +140,10 @@ fn nonstandard_features_string() -> String {
         s.push("-idna");
     }
 
+    if ! feature_enabled("WITH_DNSSEC") {
+        s.push("-dnssec");
+    }
+
     if ! feature_enabled("WITH_TLS") {
         s.push("-tls");
     }
@@ -148,6 +152,14 @@ fn nonstandard_features_string() -> String {
         s.push("-https");
     }
 
+    if ! feature_enabled("WITH_QUIC") {
+        s.push("-quic");
+    }
+
+    if ! feature_enabled("WITH_HTTP3") {
+        s.push("-http3");
+    }
+
     s.join(", ")
 }
 
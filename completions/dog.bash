@@ -28,6 +28,11 @@ _dog()
             return
             ;;
 
+        --format)
+            COMPREPLY=( $( compgen -W 'text zone' -- "$cur" ) )
+            return
+            ;;
+
         --color|--colour)
             COMPREPLY=( $( compgen -W 'always automatic never' -- $cur ) )
             return
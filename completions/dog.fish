@@ -5,9 +5,11 @@ complete -c dog -s '?' -l 'help'    -d "Show list of command-line options"
 # Query options
 complete -c dog -x -a "(__fish_print_hostnames) A AAAA CAA CNAME HINFO MX NS PTR SOA SRV TXT IN CH HS"
 complete -c dog -s 'q' -l 'query'      -d "Host name or domain name to query" -x -a "(__fish_print_hostnames)"
+complete -c dog        -l 'expand'     -d "Query every name a pattern expands to" -x
 complete -c dog -s 't' -l 'type'       -d "Type of the DNS record being queried" -x -a "A AAAA CAA CNAME HINFO MX NS PTR SOA SRV TXT"
 complete -c dog -s 'n' -l 'nameserver' -d "Address of the nameserver to send packets to" -x -a "(__fish_print_hostnames)"
 complete -c dog        -l 'class'      -d "Network class of the DNS record being queried" -x -a "IN CH HS"
+complete -c dog        -l 'fastest'    -d "Send each query to the fastest nameserver, not all of them"
 
 # Sending options
 complete -c dog        -l 'edns'       -d "Whether to OPT in to EDNS" -x -a "
@@ -16,6 +18,31 @@ complete -c dog        -l 'edns'       -d "Whether to OPT in to EDNS" -x -a "
     show\t'Send an OPT query, and show the result'
 "
 complete -c dog        -l 'txid'       -d "Set the transaction ID to a specific value" -x
+complete -c dog        -l 'edns-version' -d "Send EDNS version N instead of 0" -x
+complete -c dog        -l 'bufsize'    -d "Set the EDNS UDP payload size, retrying smaller sizes if it fails" -x
+complete -c dog        -l 'entropy-check' -d "Send many queries and report on their local port and ID randomness" -x
+complete -c dog        -l 'nsid'       -d "Ask the server to identify itself with an NSID"
+complete -c dog        -l 'expire'     -d "Ask how long until the server's copy of the zone expires"
+complete -c dog        -l 'cookie'     -d "Send a DNS cookie, and display the server's cookie"
+complete -c dog        -l 'filter'     -d "Forward DNS messages from stdin, writing responses to stdout"
+complete -c dog        -l 'filter-check' -d "Check whether the nameserver filters commonly blocked domains"
+complete -c dog        -l 'probe-port'   -d "Check which protocols the nameserver answers DNS over"
+complete -c dog        -l 'snoop'        -d "Find out which domains the resolver already has cached"
+complete -c dog        -l 'multi-question' -d "Send every query as a question in one message"
+complete -c dog        -l 'zone-diff'    -d "Compare the records in a zone file with the nameserver's" -r -F
+complete -c dog        -l 'axfr-all'     -d "Transfer the zone from every nameserver and compare them"
+complete -c dog        -l 'serve-doh'    -d "Answer DNS-over-HTTP queries from the records in a zone file" -r -F
+complete -c dog        -l 'listen'       -d "Address for --serve-doh to listen on" -x
+complete -c dog        -l 'monitor'      -d "Send the queries again every interval until stopped" -x
+complete -c dog        -l 'pidfile'      -d "Write the process ID to a file while monitoring" -r -F
+complete -c dog        -l 'stats-file'   -d "Write statistics to a file when monitoring stops" -r -F
+complete -c dog        -l 'pad'        -d "Pad every request to hide its length"
+complete -c dog        -l 'no-pad'     -d "Do not pad requests"
+complete -c dog        -l 'chain'      -d "Ask for the DNSSEC chain from a trust point, or the root"
+complete -c dog        -l 'validate'   -d "Validate DNSSEC signatures from the root trust anchor down"
+complete -c dog        -l 'subnet'     -d "Send an EDNS Client Subnet for the given network" -x
+complete -c dog        -l 'tsig'       -d "Sign requests with a TSIG key" -x
+complete -c dog        -l 'sig0'       -d "Sign requests with a SIG(0) key pair" -r
 complete -c dog -s 'Z'                 -d "Configure uncommon protocol-level tweaks" -x -a "
     aa\t'Set the AA (Authoritative Answers) query bit'
     ad\t'Set the AD (Authentic Data) query bit'
@@ -28,10 +55,31 @@ complete -c dog -s 'U' -l 'udp'        -d "Use the DNS protocol over UDP"
 complete -c dog -s 'T' -l 'tcp'        -d "Use the DNS protocol over TCP"
 complete -c dog -s 'S' -l 'tls'        -d "Use the DNS-over-TLS protocol"
 complete -c dog -s 'H' -l 'https'      -d "Use the DNS-over-HTTPS protocol"
+complete -c dog        -l 'http3'      -d "Use the DNS-over-HTTPS protocol over HTTP/3"
+complete -c dog        -l 'quic'       -d "Use the DNS-over-QUIC protocol"
+complete -c dog        -l 'user-agent' -d "Send a specific User-Agent header over HTTPS" -x
+complete -c dog        -l 'header'     -d "Send an extra header with each DNS-over-HTTPS request" -x
+complete -c dog        -l 'json-api'   -d "Ask a DNS-over-HTTPS JSON API instead"
+complete -c dog        -l 'doh-method' -d "Send DNS-over-HTTPS requests with POST or GET" -x -a "POST GET"
+complete -c dog        -l 'vanilla'    -d "Send as few HTTPS headers as possible, like a browser"
+complete -c dog        -l 'ignore-tc'  -d "Keep truncated UDP responses instead of retrying over TCP"
+complete -c dog        -l 'retries'    -d "Send a query again up to N times if it times out" -x
+complete -c dog        -l 'retry-backoff' -d "Wait before the first retry, doubling each time" -x
+complete -c dog        -l 'timeout'    -d "Give up waiting for each response after this long" -x
+complete -c dog        -l 'connect-timeout' -d "Give up connecting and handshaking after this long" -x
+complete -c dog -s 'b' -l 'bind'       -d "Send queries from this local address" -x
+complete -c dog        -l 'interface'  -d "Send queries out of this network interface" -x -a "(__fish_print_interfaces)"
+complete -c dog        -l 'trust-anything' -d "Accept responses that do not match the request"
 
 # Output options
 complete -c dog -s '1' -l 'short'      -d "Display nothing but the first result"
+complete -c dog        -l 'quiet'      -d "Display one tab-separated line per query"
+complete -c dog        -l 'format'     -d "How to display the records" -x -a "
+    text\t'Tables of records'
+    zone\t'Zone file lines, like dig'
+"
 complete -c dog -s 'J' -l 'json'       -d "Display the output as JSON"
+complete -c dog        -l 'json-pretty' -d "Display the output as indented JSON"
 complete -c dog        -l 'color'      -d "When to colorise the output" -x -a "
     always\t'Always use colors'
     automatic\t'Use colors when printing to a terminal'
@@ -43,4 +91,26 @@ complete -c dog        -l 'colour'     -d "When to colourise the output" -x -a "
     never\t'Never use colours'
 "
 complete -c dog        -l 'seconds'    -d "Do not format durations, display them as seconds"
+complete -c dog        -l 'max-answers' -d "Display at most N records from each record set" -x
+complete -c dog        -l 'lang'       -d "Language for labels and messages" -x -a "
+    en\t'English'
+    de\t'German'
+"
+complete -c dog        -l 'txt'        -d "How to decode TXT records" -x -a "
+    escape\t'ASCII, with other bytes escaped'
+    strict\t'UTF-8, or hex if invalid'
+    lossy\t'UTF-8, replacing invalid bytes'
+    hex\t'Always hex'
+"
+complete -c dog        -l 'coordinates' -d "How to format LOC positions" -x -a "
+    dms\t'Degrees, minutes, and seconds'
+    decimal\t'Signed decimal degrees'
+    both\t'Both formats'
+"
 complete -c dog        -l 'time'       -d "Print how long the response took to arrive"
+complete -c dog        -l 'show-resolvers' -d "Print the nameservers that were discovered, and where from"
+complete -c dog        -l 'hide-signatures' -d "Do not display RRSIG records"
+complete -c dog        -l 'lenient' -d "Skip over records in responses that fail to parse"
+complete -c dog        -l 'verbose' -d "Print every attempt to send each query"
+complete -c dog        -l 'html'       -d "Also write the results as an HTML report to FILE" -r -F
+complete -c dog        -l 'rpz'        -d "Display the records as response policy zone rules"
@@ -38,13 +38,33 @@ Register-ArgumentCompleter -Native -CommandName 'dog' -ScriptBlock {
     # complete option value
     switch -Regex ($previousArg) {
         '^(-q|--query)'       { $isOptionValue = $true }
+        '^(--expand)'         { $isOptionValue = $true }
         '^(-t|--type)'        { $isOptionValue = $true; $completions += $dnsTypeValues }
         '^(-n|--nameserver)'  { $isOptionValue = $true }
         '^(--class)'          { $isOptionValue = $true; $completions += @('IN', 'CH', 'HS') }
         '^(--edns)'           { $isOptionValue = $true; $completions += @('disable', 'hide', 'show') }
         '^(--txid)'           { $isOptionValue = $true }
+        '^(--edns-version)'   { $isOptionValue = $true }
+        '^(--bufsize)'        { $isOptionValue = $true }
+        '^(--subnet)'         { $isOptionValue = $true }
+        '^(--tsig)'           { $isOptionValue = $true }
+        '^(--sig0)'           { $isOptionValue = $true }
+        '^(--user-agent)'     { $isOptionValue = $true }
+        '^(--entropy-check)'  { $isOptionValue = $true }
         '^(-Z)'               { $isOptionValue = $true; $completions += @('aa', 'ad', 'bufsize=', 'cd') }
         '^(--color|--colour)' { $isOptionValue = $true; $completions += @('always', 'automatic', 'never') }
+        '^(--format)'         { $isOptionValue = $true; $completions += @('text', 'zone') }
+        '^(--coordinates)'    { $isOptionValue = $true; $completions += @('dms', 'decimal', 'both') }
+        '^(--max-answers)'    { $isOptionValue = $true }
+        '^(--lang)'           { $isOptionValue = $true; $completions += @('en', 'de') }
+        '^(--txt)'            { $isOptionValue = $true; $completions += @('escape', 'strict', 'lossy', 'hex') }
+        '^(--html)'           { $isOptionValue = $true }
+        '^(--zone-diff)'      { $isOptionValue = $true }
+        '^(--serve-doh)'      { $isOptionValue = $true }
+        '^(--listen)'         { $isOptionValue = $true }
+        '^(--monitor)'        { $isOptionValue = $true }
+        '^(--pidfile)'        { $isOptionValue = $true }
+        '^(--stats-file)'     { $isOptionValue = $true }
     }
 
     # detect whether to complete option value
@@ -61,21 +81,75 @@ Register-ArgumentCompleter -Native -CommandName 'dog' -ScriptBlock {
         # complete option name
         [string[]]$allOptions = @(
             '-q', '--query',
+            '--expand',
             '-t', '--type',
             '-n', '--nameserver',
             '--class',
+            '--fastest',
             '--edns',
             '--txid',
+            '--edns-version',
+            '--bufsize',
+            '--entropy-check',
             '-Z',
+            '--nsid',
+            '--expire',
+            '--cookie',
+            '--pad', '--no-pad',
+            '--chain',
+            '--validate',
+            '--subnet',
+            '--tsig',
+            '--sig0',
+            '--filter',
+            '--filter-check',
+            '--probe-port',
+            '--snoop',
+            '--multi-question',
+            '--zone-diff',
+            '--axfr-all',
+            '--serve-doh',
+            '--listen',
+            '--monitor',
+            '--pidfile',
+            '--stats-file',
             '-U', '--udp',
             '-T', '--tcp',
             '-S', '--tls',
             '-H', '--https',
+            '--http3',
+            '--quic',
+            '--user-agent',
+            '--header',
+            '--json-api',
+            '--doh-method',
+            '--vanilla',
+            '--ignore-tc',
+            '--retries',
+            '--retry-backoff',
+            '--timeout',
+            '--connect-timeout',
+            '-b', '--bind',
+            '--interface',
+            '--trust-anything',
             '-1', '--short',
+            '--quiet',
+            '--format',
             '-J', '--json',
+            '--json-pretty',
             '--color', '--colour',
             '--seconds',
+            '--coordinates',
+            '--max-answers',
+            '--lang',
+            '--txt',
             '--time',
+            '--show-resolvers',
+            '--hide-signatures',
+            '--lenient',
+            '--verbose',
+            '--html',
+            '--rpz',
             '-?', '--help',
             '-v', '--version'
         ) | Sort-Object
@@ -0,0 +1,192 @@
+//! Colours, colour schemes, and terminal styling.
+
+use ansi_term::Style;
+use ansi_term::Color::*;
+
+
+/// The **colours** are used to paint the input. There is one style for the
+/// query name, one for each section of a response, one for each record type
+/// that gets its own colour, and one for each kind of JSON value.
+#[derive(Debug, Default)]
+pub struct Colours {
+
+    /// The style for the domain name in each question and record.
+    pub qname: Style,
+
+    /// The style for records in the answer section.
+    pub answer: Style,
+
+    /// The style for records in the authority section.
+    pub authority: Style,
+
+    /// The style for records in the additional section.
+    pub additional: Style,
+
+    /// The style for `A` records.
+    pub a: Style,
+
+    /// The style for `AAAA` records.
+    pub aaaa: Style,
+
+    /// The style for `AMTRELAY` records.
+    pub amtrelay: Style,
+
+    /// The style for `CAA` records.
+    pub caa: Style,
+
+    /// The style for `CNAME` records.
+    pub cname: Style,
+
+    /// The style for `DLV` records.
+    pub dlv: Style,
+
+    /// The style for `DNSKEY` records.
+    pub dnskey: Style,
+
+    /// The style for `DOA` records.
+    pub doa: Style,
+
+    /// The style for `DS` records.
+    pub ds: Style,
+
+    /// The style for `EUI48` records.
+    pub eui48: Style,
+
+    /// The style for `EUI64` records.
+    pub eui64: Style,
+
+    /// The style for `HINFO` records.
+    pub hinfo: Style,
+
+    /// The style for `HIP` records.
+    pub hip: Style,
+
+    /// The style for `HTTPS` records.
+    pub https: Style,
+
+    /// The style for `LOC` records.
+    pub loc: Style,
+
+    /// The style for `MX` records.
+    pub mx: Style,
+
+    /// The style for `NS` records.
+    pub ns: Style,
+
+    /// The style for `NAPTR` records.
+    pub naptr: Style,
+
+    /// The style for `NSEC3` records.
+    pub nsec3: Style,
+
+    /// The style for `OPENPGPKEY` records.
+    pub openpgpkey: Style,
+
+    /// The style for `OPT` pseudo-records.
+    pub opt: Style,
+
+    /// The style for `PTR` records.
+    pub ptr: Style,
+
+    /// The style for `RRSIG` records.
+    pub rrsig: Style,
+
+    /// The style for `SSHFP` records.
+    pub sshfp: Style,
+
+    /// The style for `SOA` records.
+    pub soa: Style,
+
+    /// The style for `SRV` records.
+    pub srv: Style,
+
+    /// The style for `SVCB` records.
+    pub svcb: Style,
+
+    /// The style for `TA` records.
+    pub ta: Style,
+
+    /// The style for `TLSA` records.
+    pub tlsa: Style,
+
+    /// The style for `TXT` records.
+    pub txt: Style,
+
+    /// The style for `URI` records.
+    pub uri: Style,
+
+    /// The style for records of any type without a style of its own.
+    pub unknown: Style,
+
+    /// The style for keys in JSON objects.
+    pub json_key: Style,
+
+    /// The style for string values in JSON output.
+    pub json_string: Style,
+
+    /// The style for number values in JSON output.
+    pub json_number: Style,
+
+    /// The style for `true`, `false`, and `null` in JSON output.
+    pub json_literal: Style,
+}
+
+impl Colours {
+
+    /// Create a new colour palette that has a variety of different styles
+    /// defined. This is used by default.
+    pub fn pretty() -> Self {
+        Self {
+            qname: Blue.bold(),
+
+            answer: Style::default(),
+            authority: Cyan.normal(),
+            additional: Green.normal(),
+
+            a: Green.bold(),
+            aaaa: Green.bold(),
+            amtrelay: Green.normal(),
+            caa: Red.normal(),
+            cname: Yellow.normal(),
+            dlv: Purple.normal(),
+            dnskey: Purple.normal(),
+            doa: Cyan.normal(),
+            ds: Purple.normal(),
+            eui48: Yellow.normal(),
+            eui64: Yellow.bold(),
+            hinfo: Yellow.normal(),
+            hip: Yellow.normal(),
+            https: Purple.normal(),
+            loc: Yellow.normal(),
+            mx: Cyan.normal(),
+            naptr: Green.normal(),
+            ns: Red.normal(),
+            nsec3: Purple.normal(),
+            openpgpkey: Cyan.normal(),
+            opt: Purple.normal(),
+            ptr: Red.normal(),
+            rrsig: Purple.normal(),
+            sshfp: Cyan.normal(),
+            soa: Purple.normal(),
+            srv: Cyan.normal(),
+            svcb: Purple.normal(),
+            ta: Purple.normal(),
+            tlsa: Yellow.normal(),
+            txt: Yellow.normal(),
+            uri: Yellow.normal(),
+            unknown: White.on(Red),
+
+            json_key: Blue.bold(),
+            json_string: Green.normal(),
+            json_number: Cyan.normal(),
+            json_literal: Yellow.normal(),
+        }
+    }
+
+    /// Create a new colour palette where no styles are defined, causing
+    /// output to be rendered as plain text without any formatting.
+    /// This is used when output is not to a terminal.
+    pub fn plain() -> Self {
+        Self::default()
+    }
+}
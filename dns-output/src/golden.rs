@@ -0,0 +1,164 @@
+//! Regression tests that run captured DNS messages through the parser and
+//! the output formats, checking the results against golden files.
+//!
+//! Each message in the `corpus` directory is a `.bin` file holding a whole
+//! response as it came off the wire. Next to it are a `.json` file with its
+//! pretty-printed JSON output and a `.txt` file with its table output, both
+//! without colours. After a change to the output on purpose, these files can
+//! be rewritten from the current code with `just regen-golden`, which sets
+//! the `DOG_REGEN_GOLDEN` environment variable, and the differences checked
+//! before they get committed.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use dns::{Request, Response};
+use json::JsonValue;
+use pretty_assertions::assert_eq;
+
+use crate::colours::Colours;
+use crate::messages::Language;
+use crate::output::{json_response, CoordinateFormat, JsonFormat, TextFormat, UseColours};
+use crate::table::{Section, Table};
+
+
+/// Returns the paths to every message in the corpus, in order.
+fn corpus() -> Vec<PathBuf> {
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("corpus");
+    let mut paths = fs::read_dir(&directory)
+        .expect("failed to read the corpus directory")
+        .map(|entry| entry.expect("failed to read the corpus directory").path())
+        .filter(|path| path.extension().map_or(false, |e| e == "bin"))
+        .collect::<Vec<_>>();
+
+    paths.sort();
+    assert!(! paths.is_empty(), "no messages in {}", directory.display());
+    paths
+}
+
+/// Reads and parses the message at the given path.
+fn read_message(path: &Path) -> Response {
+    let bytes = fs::read(path).expect("failed to read message");
+    Response::from_bytes(&bytes)
+        .unwrap_or_else(|e| panic!("failed to parse {}: {:?}", path.display(), e))
+}
+
+/// Renders the response the way `--json` prints it, with fractional
+/// numbers rounded so the golden files do not depend on their last digits.
+fn json_output(response: &Response) -> String {
+    let mut value = json_response(response.clone(), &response.edns_options());
+    round_fractions(&mut value);
+    JsonFormat::Pretty(UseColours::Never).render(&value, &Colours::plain()) + "\n"
+}
+
+/// Rounds every number with a fractional part to six decimal places,
+/// leaving whole numbers alone.
+fn round_fractions(value: &mut JsonValue) {
+    match value {
+        JsonValue::Number(number) => {
+            let float = f64::from(*number);
+            if float.fract() != 0.0 {
+                *value = ((float * 1e6).round() / 1e6).into();
+            }
+        }
+        JsonValue::Array(values) => {
+            values.iter_mut().for_each(round_fractions);
+        }
+        JsonValue::Object(object) => {
+            for (_, value) in object.iter_mut() {
+                round_fractions(value);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Renders the response as the table dog prints by default, in English,
+/// with every record and every section shown.
+fn text_output(response: &Response) -> String {
+    let text_format = TextFormat {
+        format_durations: true,
+        coordinates: CoordinateFormat::DegreesMinutesSeconds,
+        max_answers: None,
+        language: Some(Language::English),
+        txt_decoding: None,
+    };
+
+    let (answer_section, authority_section) = Section::for_opcode(response.flags.opcode);
+
+    let mut table = Table::new(Colours::plain(), text_format);
+    for answer in response.answers.iter().cloned() {
+        table.add_row(answer, answer_section);
+    }
+    for answer in response.authorities.iter().cloned() {
+        table.add_row(answer, authority_section);
+    }
+    for answer in response.additionals.iter().cloned() {
+        table.add_row(answer, Section::Additional);
+    }
+
+    table.render(None)
+}
+
+/// Checks the output against the golden file with the given extension, or
+/// overwrites the file with it when regenerating them.
+fn check_golden(message_path: &Path, extension: &str, output: &str) {
+    let golden_path = message_path.with_extension(extension);
+
+    if std::env::var_os("DOG_REGEN_GOLDEN").is_some() {
+        fs::write(&golden_path, output).expect("failed to write golden file");
+        return;
+    }
+
+    let expected = fs::read_to_string(&golden_path)
+        .unwrap_or_else(|e| panic!("failed to read {} (run `just regen-golden` to create it): {}", golden_path.display(), e));
+
+    assert_eq!(expected, output, "output differs from {}", golden_path.display());
+}
+
+
+#[test]
+fn round_trips() {
+    for path in corpus() {
+        let response = read_message(&path);
+
+        let mut builder = Request::builder()
+            .transaction_id(response.transaction_id)
+            .flags(response.flags);
+
+        for query in response.queries.iter().cloned() {
+            builder = builder.query(query);
+        }
+        for answer in response.answers.iter().cloned() {
+            builder = builder.answer(answer);
+        }
+        for answer in response.authorities.iter().cloned() {
+            builder = builder.authority(answer);
+        }
+        for answer in response.additionals.iter().cloned() {
+            builder = builder.additional(answer);
+        }
+
+        let bytes = builder.to_bytes().expect("failed to write message");
+        let reparsed = Response::from_bytes(&bytes)
+            .unwrap_or_else(|e| panic!("failed to parse {} after writing it: {:?}", path.display(), e));
+
+        assert_eq!(response, reparsed, "{} changed after being written", path.display());
+    }
+}
+
+#[test]
+fn json_golden_files() {
+    for path in corpus() {
+        let response = read_message(&path);
+        check_golden(&path, "json", &json_output(&response));
+    }
+}
+
+#[test]
+fn text_golden_files() {
+    for path in corpus() {
+        let response = read_message(&path);
+        check_golden(&path, "txt", &text_output(&response));
+    }
+}
@@ -0,0 +1,39 @@
+#![warn(deprecated_in_future)]
+#![warn(future_incompatible)]
+#![warn(missing_copy_implementations)]
+#![warn(missing_docs)]
+#![warn(nonstandard_style)]
+#![warn(rust_2018_compatibility)]
+#![warn(rust_2018_idioms)]
+#![warn(single_use_lifetimes)]
+#![warn(trivial_casts, trivial_numeric_casts)]
+#![warn(unused)]
+
+#![warn(clippy::all, clippy::pedantic)]
+#![allow(clippy::doc_markdown)]
+#![allow(clippy::enum_glob_use)]
+#![allow(clippy::missing_errors_doc)]
+#![allow(clippy::module_name_repetitions)]
+#![allow(clippy::must_use_candidate)]
+#![allow(clippy::option_if_let_else)]
+#![allow(clippy::struct_excessive_bools)]
+#![allow(clippy::too_many_lines)]
+#![allow(clippy::upper_case_acronyms)]
+#![allow(clippy::wildcard_imports)]
+
+#![deny(unsafe_code)]
+
+
+//! The output crate turns the DNS responses and errors from the `dns` and
+//! `dns-transport` crates into text, tables, and JSON, exactly as dog
+//! prints them, so other DNS tools can render records the same way.
+
+
+pub mod colours;
+pub mod messages;
+pub mod output;
+pub mod rpz;
+pub mod table;
+
+#[cfg(test)]
+mod golden;
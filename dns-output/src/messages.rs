@@ -0,0 +1,224 @@
+//! The catalogue of labels and messages in dog’s human-readable output, in
+//! each language it has been translated into.
+//!
+//! Only the text and short output formats get translated. JSON output and
+//! the quiet format are meant for scripts to read, so they keep using the
+//! same English keys and words whatever the language.
+
+use std::env;
+use std::fmt;
+
+
+/// A language that the labels and messages can be displayed in.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum Language {
+
+    /// English, which is the default.
+    English,
+
+    /// German.
+    German,
+}
+
+/// A label or message in the human-readable output. Messages with a `{}`
+/// in them have a value filled in there.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum Message {
+
+    /// The label in front of a response’s status.
+    Status,
+
+    /// The label in front of a message’s opcode, when it is not a query.
+    Opcode,
+
+    /// The label before each question, in a message with more than one.
+    Question,
+
+    /// The label in front of an error message.
+    Error,
+
+    /// How long the queries took, in milliseconds.
+    RanIn,
+
+    /// How many more records there were than were displayed.
+    AndMore,
+
+    /// That no responses had any answers.
+    NoResults,
+
+    /// That no responses had any policy rules.
+    NoPolicyRules,
+
+    /// The highest EDNS version that the server supports.
+    EdnsVersionSupported,
+
+    /// The `FORMERR` status.
+    FormatError,
+
+    /// The `SERVFAIL` status.
+    ServerFailure,
+
+    /// The `NXDOMAIN` status.
+    NXDomain,
+
+    /// The `NOTIMP` status.
+    NotImplemented,
+
+    /// The `REFUSED` status.
+    QueryRefused,
+
+    /// The `BADVERS` status.
+    BadVersion,
+
+    /// The `BADCOOKIE` status.
+    BadCookie,
+
+    /// A status in the range for private use, with its number.
+    PrivateReason,
+
+    /// Any other status, with its number.
+    OtherFailure,
+}
+
+
+impl Language {
+
+    /// Returns the language with the given tag, such as `de`, or a locale
+    /// name that starts with one, such as `de_DE.UTF-8`. The C and POSIX
+    /// locales are English. Returns `None` if no translation exists for
+    /// the language.
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        let language = tag.split(['_', '-', '.', '@']).next()?;
+
+        match &*language.to_ascii_lowercase() {
+            "en" | "c" | "posix"  => Some(Self::English),
+            "de"                  => Some(Self::German),
+            _                     => None,
+        }
+    }
+
+    /// Picks the language from the locale environment variables, checking
+    /// `LC_ALL`, then `LC_MESSAGES`, then `LANG`, as the first of them to
+    /// be set decides the locale. Falls back to English if the locale is
+    /// in a language without a translation.
+    pub fn from_environment() -> Self {
+        ["LC_ALL", "LC_MESSAGES", "LANG"].iter()
+            .filter_map(|name| env::var(name).ok())
+            .find(|value| ! value.is_empty())
+            .and_then(|value| Self::from_tag(&value))
+            .unwrap_or(Self::English)
+    }
+
+    /// Returns the text of a message in this language, with any `{}` left
+    /// in place.
+    pub fn text(self, message: Message) -> &'static str {
+        match self {
+            Self::English  => english(message),
+            Self::German   => german(message),
+        }
+    }
+
+    /// Returns the text of a message in this language, with the value
+    /// filled in where the `{}` is.
+    pub fn fill(self, message: Message, value: impl fmt::Display) -> String {
+        self.text(message).replacen("{}", &value.to_string(), 1)
+    }
+}
+
+fn english(message: Message) -> &'static str {
+    match message {
+        Message::Status                => "Status",
+        Message::Opcode                => "Opcode",
+        Message::Question              => "Question",
+        Message::Error                 => "Error",
+        Message::RanIn                 => "Ran in {}ms",
+        Message::AndMore               => "… and {} more",
+        Message::NoResults             => "No results",
+        Message::NoPolicyRules         => "No policy rules",
+        Message::EdnsVersionSupported  => "EDNS version: server supports up to version {}",
+        Message::FormatError           => "Format Error",
+        Message::ServerFailure         => "Server Failure",
+        Message::NXDomain              => "NXDomain",
+        Message::NotImplemented        => "Not Implemented",
+        Message::QueryRefused          => "Query Refused",
+        Message::BadVersion            => "Bad Version",
+        Message::BadCookie             => "Bad Cookie",
+        Message::PrivateReason         => "Private Reason ({})",
+        Message::OtherFailure          => "Other Failure ({})",
+    }
+}
+
+fn german(message: Message) -> &'static str {
+    match message {
+        Message::Status                => "Status",
+        Message::Opcode                => "Opcode",
+        Message::Question              => "Frage",
+        Message::Error                 => "Fehler",
+        Message::RanIn                 => "Dauer: {}ms",
+        Message::AndMore               => "… und {} weitere",
+        Message::NoResults             => "Keine Ergebnisse",
+        Message::NoPolicyRules         => "Keine Richtlinienregeln",
+        Message::EdnsVersionSupported  => "EDNS-Version: Server unterstützt bis Version {}",
+        Message::FormatError           => "Formatfehler",
+        Message::ServerFailure         => "Serverfehler",
+        Message::NXDomain              => "Domain existiert nicht",
+        Message::NotImplemented        => "Nicht implementiert",
+        Message::QueryRefused          => "Anfrage abgelehnt",
+        Message::BadVersion            => "Falsche Version",
+        Message::BadCookie             => "Falscher Cookie",
+        Message::PrivateReason         => "Privater Grund ({})",
+        Message::OtherFailure          => "Anderer Fehler ({})",
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn tags() {
+        assert_eq!(Language::from_tag("en"), Some(Language::English));
+        assert_eq!(Language::from_tag("de"), Some(Language::German));
+        assert_eq!(Language::from_tag("DE"), Some(Language::German));
+    }
+
+    #[test]
+    fn locale_names() {
+        assert_eq!(Language::from_tag("de_DE.UTF-8"), Some(Language::German));
+        assert_eq!(Language::from_tag("de-AT"), Some(Language::German));
+        assert_eq!(Language::from_tag("en_GB.UTF-8@euro"), Some(Language::English));
+        assert_eq!(Language::from_tag("C.UTF-8"), Some(Language::English));
+        assert_eq!(Language::from_tag("POSIX"), Some(Language::English));
+    }
+
+    #[test]
+    fn untranslated() {
+        assert_eq!(Language::from_tag("tlh"), None);
+        assert_eq!(Language::from_tag(""), None);
+    }
+
+    #[test]
+    fn filling_in() {
+        assert_eq!(Language::English.fill(Message::AndMore, 3), "… and 3 more");
+        assert_eq!(Language::German.fill(Message::RanIn, 45), "Dauer: 45ms");
+    }
+
+    #[test]
+    fn every_message_translated() {
+        let messages = [
+            Message::Status, Message::Opcode, Message::Question, Message::Error, Message::RanIn, Message::AndMore,
+            Message::NoResults, Message::NoPolicyRules, Message::EdnsVersionSupported,
+            Message::FormatError, Message::ServerFailure, Message::NXDomain,
+            Message::NotImplemented, Message::QueryRefused, Message::BadVersion,
+            Message::BadCookie, Message::PrivateReason, Message::OtherFailure,
+        ];
+
+        for message in &messages {
+            let english = Language::English.text(*message);
+            let german = Language::German.text(*message);
+            assert_eq!(english.contains("{}"), german.contains("{}"), "{:?}", message);
+        }
+    }
+}
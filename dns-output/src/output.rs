@@ -0,0 +1,2397 @@
+//! Text and JSON output.
+
+use std::fmt;
+use std::time::Duration;
+use std::env;
+
+use dns::{Response, Query, Answer, QClass, ErrorCode, Opcode, WireError, MandatedLength, NameLimit, Labels, signature_timestamp};
+use dns::record::{Record, RecordType, UnknownQtype, OPT, TextDecoding, DecodedMessage, EdnsOption, ExtendedError, edns_option_name, CaaProperty, Relay, RegexError, enum_number, SVCB, SvcParam, EchConfigList, svc_param_key_name};
+use dns_transport::Error as TransportError;
+use json::{object, JsonValue};
+use log::*;
+
+use crate::colours::Colours;
+use crate::messages::{Language, Message};
+use crate::rpz::{self, PolicyRule, Action};
+use crate::table::{Table, Section};
+
+
+/// How to format the output data.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum OutputFormat {
+
+    /// Format the output as plain text, optionally adding ANSI colours.
+    Text(UseColours, TextFormat),
+
+    /// Format the output as one line of plain text.
+    Short(TextFormat),
+
+    /// Format each response as exactly one line of tab-separated plain
+    /// text, for scripts to read.
+    Quiet(TextFormat),
+
+    /// Format the entries as JSON.
+    JSON(JsonFormat),
+
+    /// Format each record as a line of a zone file, with its data in
+    /// presentation format, the way dig does.
+    Zone(TextFormat),
+}
+
+
+/// How to lay out JSON output.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum JsonFormat {
+
+    /// Put everything on one line, with no whitespace.
+    Compact,
+
+    /// Indent every member of every object and array on its own line,
+    /// optionally adding ANSI colours.
+    Pretty(UseColours),
+}
+
+
+/// When to use colours in the output.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum UseColours {
+
+    /// Always use colours.
+    Always,
+
+    /// Use colours if output is to a terminal; otherwise, do not.
+    Automatic,
+
+    /// Never use colours.
+    Never,
+}
+
+/// Options that govern how text should be rendered in record summaries.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub struct TextFormat {
+
+    /// Whether to format TTLs as hours, minutes, and seconds.
+    pub format_durations: bool,
+
+    /// How to format the positions in LOC records.
+    pub coordinates: CoordinateFormat,
+
+    /// The most records to display from each record set, with the rest
+    /// being summarised by a count. If `None`, every record is displayed.
+    pub max_answers: Option<usize>,
+
+    /// The language to display labels and messages in. If `None`, it gets
+    /// picked from the locale environment variables.
+    pub language: Option<Language>,
+
+    /// How to decode the messages in TXT records. If `None`, they are
+    /// displayed as ASCII, with any other bytes escaped.
+    pub txt_decoding: Option<TextDecoding>,
+}
+
+/// How to format the latitude and longitude of a location.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum CoordinateFormat {
+
+    /// Use degrees, minutes, and seconds, with a compass direction.
+    DegreesMinutesSeconds,
+
+    /// Use signed decimal degrees.
+    Decimal,
+
+    /// Use both formats, one after the other.
+    Both,
+}
+
+impl UseColours {
+
+    /// Whether we should use colours or not. This checks whether the user has
+    /// overridden the colour setting, and if not, whether output is to a
+    /// terminal.
+    pub fn should_use_colours(self) -> bool {
+        self == Self::Always || (atty::is(atty::Stream::Stdout) && env::var("NO_COLOR").is_err() && self != Self::Never)
+    }
+
+    /// Creates a palette of colours depending on the user’s wishes or whether
+    /// output is to a terminal.
+    pub fn palette(self) -> Colours {
+        if self.should_use_colours() {
+            Colours::pretty()
+        }
+        else {
+            Colours::plain()
+        }
+    }
+}
+
+
+impl OutputFormat {
+
+    /// Prints the entirety of the output, formatted according to the
+    /// settings. If the duration has been measured, it should also be
+    /// printed. OPT pseudo-records are only printed if `show_opt` is set,
+    /// though any options in them that explain the response, such as
+    /// extended errors, are always printed. Returns `false` if there were no
+    /// results to print, and `true` otherwise.
+    pub fn print(self, mut responses: Vec<Response>, duration: Option<Duration>, show_opt: bool) -> bool {
+        let edns_options = responses.iter().map(Response::edns_options).collect::<Vec<_>>();
+
+        if ! show_opt {
+            for response in &mut responses {
+                response.answers.retain(Answer::is_standard);
+                response.authorities.retain(Answer::is_standard);
+                response.additionals.retain(Answer::is_standard);
+            }
+        }
+
+        match self {
+            Self::Short(tf) => {
+                let all_answers = responses.into_iter().flat_map(|r| r.answers).collect::<Vec<_>>();
+
+                if all_answers.is_empty() {
+                    eprintln!("{}", tf.language().text(Message::NoResults));
+
+                    for note in edns_options.iter().flatten().filter_map(edns_option_summary) {
+                        eprintln!("{}", note);
+                    }
+
+                    return false;
+                }
+
+                let hidden = tf.max_answers.map_or(0, |max| all_answers.len().saturating_sub(max));
+                let shown = all_answers.len() - hidden;
+
+                for answer in all_answers.into_iter().take(shown) {
+                    match answer {
+                        Answer::Standard { record, qname, .. } => {
+                            let enum_note = enum_summary(&qname, &record);
+                            println!("{}{}", tf.record_payload_summary(record), enum_note.unwrap_or_default())
+                        }
+                        Answer::Pseudo { opt, .. } => {
+                            println!("{}", tf.pseudo_record_payload_summary(opt))
+                        }
+                    }
+
+                }
+
+                if hidden > 0 {
+                    println!("{}", tf.language().fill(Message::AndMore, hidden));
+                }
+            }
+            Self::Quiet(tf) => {
+                for response in responses {
+                    println!("{}", tf.quiet_line(response));
+                }
+            }
+            Self::Zone(_) => {
+                for response in responses {
+                    let rcode = response.flags.error_code;
+
+                    if response.flags.opcode != Opcode::Query {
+                        println!("; opcode: {}", opcode_mnemonic(response.flags.opcode));
+                    }
+
+                    if response.queries.len() > 1 {
+                        for query in &response.queries {
+                            println!("; question: {}", question_line(query));
+                        }
+                    }
+
+                    if rcode.is_some() {
+                        println!("; status: {}", status_mnemonic(rcode));
+                    }
+
+                    let answers = response.answers.into_iter()
+                        .chain(response.authorities)
+                        .chain(response.additionals);
+
+                    for line in answers.filter_map(zone_line) {
+                        println!("{}", line);
+                    }
+                }
+            }
+            Self::JSON(jf) => {
+                let rs = responses.into_iter().zip(edns_options)
+                                  .map(|(response, options)| json_response(response, &options))
+                                  .collect::<Vec<_>>();
+
+                if let Some(duration) = duration {
+                    let object = object! {
+                        "responses": rs,
+                        "duration": {
+                            "secs": duration.as_secs(),
+                            "millis": duration.subsec_millis(),
+                        },
+                    };
+
+                    jf.println(&object);
+                }
+                else {
+                    let object = object! {
+                        "responses": rs,
+                    };
+
+                    jf.println(&object);
+                }
+            }
+            Self::Text(uc, tf) => {
+                let mut table = Table::new(uc.palette(), tf);
+
+                for (response, options) in responses.into_iter().zip(edns_options) {
+                    if response.flags.opcode != Opcode::Query {
+                        println!("{}: {}", tf.language().text(Message::Opcode), opcode_mnemonic(response.flags.opcode));
+                    }
+
+                    if response.queries.len() > 1 {
+                        for query in &response.queries {
+                            println!("{}: {}", tf.language().text(Message::Question), question_line(query));
+                        }
+                    }
+
+                    if let Some(rcode) = response.flags.error_code {
+                        print_error_code(rcode, tf.language());
+                    }
+
+                    if let Some(version) = response.bad_version() {
+                        println!("{}", tf.language().fill(Message::EdnsVersionSupported, version));
+                    }
+
+                    for note in options.iter().filter_map(edns_option_summary) {
+                        println!("{}", note);
+                    }
+
+                    let (answer_section, authority_section) = Section::for_opcode(response.flags.opcode);
+
+                    for a in response.answers {
+                        table.add_row(a, answer_section);
+                    }
+
+                    for a in response.authorities {
+                        table.add_row(a, authority_section);
+                    }
+
+                    for a in response.additionals {
+                        table.add_row(a, Section::Additional);
+                    }
+                }
+
+                table.print(duration);
+            }
+        }
+
+        true
+    }
+
+    /// Prints the records in the responses as the response policy zone
+    /// rules they encode, treating the domain each response was for as the
+    /// name of the zone. Returns whether there were any rules to print.
+    pub fn print_policy_rules(self, responses: Vec<Response>) -> bool {
+        let rules = responses.into_iter().flat_map(|response| {
+            let zone = response.queries.first().map_or_else(Labels::root, |query| query.qname.clone());
+            rpz::rules(&zone, response.answers)
+        }).collect::<Vec<_>>();
+
+        match self {
+            Self::Short(tf) | Self::Quiet(tf) | Self::Zone(tf) | Self::Text(_, tf) => {
+                if rules.is_empty() {
+                    eprintln!("{}", tf.language().text(Message::NoPolicyRules));
+                    return false;
+                }
+
+                for rule in rules {
+                    println!("{}", tf.policy_rule_summary(rule));
+                }
+            }
+            Self::JSON(jf) => {
+                let rules = rules.into_iter().map(json_policy_rule).collect::<Vec<_>>();
+                jf.println(&object! { "rules": rules });
+            }
+        }
+
+        true
+    }
+
+    /// Print an error that’s ocurred while sending or receiving DNS packets
+    /// to standard error.
+    pub fn print_error(self, error: TransportError) {
+        self.print_error_json(&json_error(error));
+    }
+
+    /// Print an error that has already been serialised by `json_error` to
+    /// standard error.
+    pub fn print_error_json(self, error: &JsonValue) {
+        self.print_query_error_json(None, error);
+    }
+
+    /// Print an error that has already been serialised by `json_error` to
+    /// standard error, along with the query that failed, if it is known.
+    /// Only quiet mode shows the query, as part of its one-line format.
+    pub fn print_query_error_json(self, query: Option<&Query>, error: &JsonValue) {
+        match self {
+            Self::Short(tf) | Self::Text(_, tf) => {
+                eprintln!("{} [{}]: {}", tf.language().text(Message::Error), error["error_phase"].as_str().unwrap_or_default(), error["error_message"].as_str().unwrap_or_default());
+            }
+
+            Self::Quiet(tf) => {
+                eprintln!("{}", tf.quiet_error_line(query, error));
+            }
+
+            Self::Zone(..) => {
+                eprintln!("Error [{}]: {}", error["error_phase"].as_str().unwrap_or_default(), error["error_message"].as_str().unwrap_or_default());
+            }
+
+            Self::JSON(jf) => {
+                eprintln!("{}", jf.render(error, &Colours::plain()));
+            }
+        }
+    }
+}
+
+impl JsonFormat {
+
+    /// Prints a JSON value to standard output in this format, using colours
+    /// if the user wants them.
+    pub fn println(self, value: &JsonValue) {
+        let colours = match self {
+            Self::Compact          => Colours::plain(),
+            Self::Pretty(uc)       => uc.palette(),
+        };
+
+        println!("{}", self.render(value, &colours));
+    }
+
+    /// Renders a JSON value as a string in this format. Compact JSON is
+    /// never coloured.
+    pub fn render(self, value: &JsonValue, colours: &Colours) -> String {
+        match self {
+            Self::Compact => {
+                value.dump()
+            }
+            Self::Pretty(_) => {
+                let mut out = String::new();
+                write_pretty_json(&mut out, value, 0, colours);
+                out
+            }
+        }
+    }
+}
+
+/// Writes a JSON value to the string with two spaces of indentation for
+/// each level of nesting, painting keys and values with the given colours.
+#[allow(clippy::match_wildcard_for_single_variants)]  // strings can be short or long
+fn write_pretty_json(out: &mut String, value: &JsonValue, depth: usize, colours: &Colours) {
+    let indent = |depth: usize| "  ".repeat(depth);
+
+    match value {
+        JsonValue::Object(object) if object.is_empty() => {
+            out.push_str("{}");
+        }
+        JsonValue::Object(object) => {
+            out.push_str("{\n");
+            for (index, (key, member)) in object.iter().enumerate() {
+                if index > 0 {
+                    out.push_str(",\n");
+                }
+
+                out.push_str(&indent(depth + 1));
+                out.push_str(&colours.json_key.paint(JsonValue::from(key).dump()).to_string());
+                out.push_str(": ");
+                write_pretty_json(out, member, depth + 1, colours);
+            }
+            out.push('\n');
+            out.push_str(&indent(depth));
+            out.push('}');
+        }
+        JsonValue::Array(array) if array.is_empty() => {
+            out.push_str("[]");
+        }
+        JsonValue::Array(array) => {
+            out.push_str("[\n");
+            for (index, member) in array.iter().enumerate() {
+                if index > 0 {
+                    out.push_str(",\n");
+                }
+
+                out.push_str(&indent(depth + 1));
+                write_pretty_json(out, member, depth + 1, colours);
+            }
+            out.push('\n');
+            out.push_str(&indent(depth));
+            out.push(']');
+        }
+        JsonValue::Number(_) => {
+            out.push_str(&colours.json_number.paint(value.dump()).to_string());
+        }
+        JsonValue::Boolean(_) | JsonValue::Null => {
+            out.push_str(&colours.json_literal.paint(value.dump()).to_string());
+        }
+        _ => {
+            out.push_str(&colours.json_string.paint(value.dump()).to_string());
+        }
+    }
+}
+
+
+impl TextFormat {
+
+    /// The language to display labels and messages in, which comes from
+    /// the locale if it has not been chosen.
+    pub fn language(self) -> Language {
+        self.language.unwrap_or_else(Language::from_environment)
+    }
+
+    /// Formats a summary of a record in a received DNS response. Each record
+    /// type contains wildly different data, so the format of the summary
+    /// depends on what record it’s for.
+    pub fn record_payload_summary(self, record: Record) -> String {
+        match record {
+            Record::A(a) => {
+                format!("{}", a.address)
+            }
+            Record::AAAA(aaaa) => {
+                format!("{}", aaaa.address)
+            }
+            Record::AMTRELAY(amtrelay) => {
+                let relay = match &amtrelay.relay {
+                    Relay::Domain(domain)  => format!("{:?}", domain.to_string()),
+                    otherwise              => otherwise.to_string(),
+                };
+
+                format!("{} {} {} {}",
+                    amtrelay.precedence,
+                    u8::from(amtrelay.discovery_optional),
+                    amtrelay.relay.relay_type(),
+                    relay,
+                )
+            }
+            Record::CAA(caa) => {
+                let value = match caa.property() {
+                    Some(CaaProperty::Issue(issue)) if ! issue.parameters.is_empty() => {
+                        let issuer = issue.issuer.unwrap_or_default();
+                        let mut parts = vec![ Ascii(issuer.as_bytes()).to_string() ];
+
+                        for (tag, value) in &issue.parameters {
+                            parts.push(format!("{}={}", tag, Ascii(value.as_bytes())));
+                        }
+
+                        parts.join(" ")
+                    }
+                    _ => {
+                        Ascii(&caa.value).to_string()
+                    }
+                };
+
+                if ! caa.critical {
+                    format!("{} {} (non-critical)", Ascii(&caa.tag), value)
+                }
+                else if caa.has_known_tag() {
+                    format!("{} {} (critical)", Ascii(&caa.tag), value)
+                }
+                else {
+                    format!("{} {} (critical, unknown tag)", Ascii(&caa.tag), value)
+                }
+            }
+            Record::CNAME(cname) => {
+                format!("{:?}", cname.domain.to_string())
+            }
+            Record::DLV(dlv) => {
+                self.record_payload_summary(Record::DS(dlv.0))
+            }
+            Record::DNSKEY(dnskey) => {
+                format!("{} {} {} {:?} (key tag {})",
+                    dnskey.flags,
+                    dnskey.protocol,
+                    dnskey.algorithm,
+                    dnskey.base64_key(),
+                    dnskey.key_tag(),
+                )
+            }
+            Record::DOA(doa) => {
+                format!("{} {} {} {} {:?}",
+                    doa.enterprise,
+                    doa.doa_type,
+                    doa.location,
+                    Ascii(&doa.media_type),
+                    doa.base64_data(),
+                )
+            }
+            Record::DS(ds) => {
+                format!("{} {} {} {}",
+                    ds.key_tag,
+                    ds.algorithm,
+                    ds.digest_type,
+                    ds.hex_digest(),
+                )
+            }
+            Record::EUI48(eui48) => {
+                format!("{:?}", eui48.formatted_address())
+            }
+            Record::EUI64(eui64) => {
+                format!("{:?}", eui64.formatted_address())
+            }
+            Record::HINFO(hinfo) => {
+                format!("{} {}", Ascii(&hinfo.cpu), Ascii(&hinfo.os))
+            }
+            Record::HTTPS(https) => {
+                svcb_summary(&https.0)
+            }
+            Record::HIP(hip) => {
+                let mut parts = vec![
+                    hip.algorithm.to_string(),
+                    hip.hex_hit(),
+                    format!("{:?}", hip.base64_public_key()),
+                ];
+
+                for server in &hip.rendezvous_servers {
+                    parts.push(format!("{:?}", server.to_string()));
+                }
+
+                parts.join(" ")
+            }
+            Record::LOC(loc) => {
+                let dms = format!("({}, {}, {})",
+                    loc.latitude .map_or_else(|| "Out of range".into(), |e| e.to_string()),
+                    loc.longitude.map_or_else(|| "Out of range".into(), |e| e.to_string()),
+                    loc.altitude,
+                );
+
+                let decimal = format!("{} {} {}",
+                    loc.latitude .map_or_else(|| "Out of range".into(), |e| format!("{:.6}", e.decimal_degrees())),
+                    loc.longitude.map_or_else(|| "Out of range".into(), |e| format!("{:.6}", e.decimal_degrees())),
+                    loc.altitude,
+                );
+
+                let point = match self.coordinates {
+                    CoordinateFormat::DegreesMinutesSeconds  => dms,
+                    CoordinateFormat::Decimal                => decimal,
+                    CoordinateFormat::Both                   => format!("{} {}", dms, decimal),
+                };
+
+                format!("{} ({}, {}) {}",
+                    loc.size,
+                    loc.horizontal_precision,
+                    loc.vertical_precision,
+                    point,
+                )
+            }
+            Record::MX(mx) => {
+                format!("{} {:?}", mx.preference, mx.exchange.to_string())
+            }
+            Record::NAPTR(naptr) => {
+                let summary = format!("{} {} {} {} {} {:?}",
+                    naptr.order,
+                    naptr.preference,
+                    Ascii(&naptr.flags),
+                    Ascii(&naptr.service),
+                    Ascii(&naptr.regex),
+                    naptr.replacement.to_string(),
+                );
+
+                match naptr.substitution() {
+                    Ok(_)   => summary,
+                    Err(e)  => format!("{} (invalid regex: {})", summary, regex_error_message(e)),
+                }
+            }
+            Record::NS(ns) => {
+                format!("{:?}", ns.nameserver.to_string())
+            }
+            Record::NSEC(nsec) => {
+                let types = nsec.types.iter()
+                    .map(|t| record_type_name(*t))
+                    .collect::<Vec<_>>();
+
+                format!("{:?} {}",
+                    nsec.next_domain.to_string(),
+                    types.join(" "),
+                ).trim_end().into()
+            }
+            Record::NSEC3(nsec3) => {
+                let types = nsec3.types.iter()
+                    .map(|t| record_type_name(*t))
+                    .collect::<Vec<_>>();
+
+                format!("{} {} {} {} {:?} {}",
+                    nsec3.hash_algorithm,
+                    nsec3.flags,
+                    nsec3.iterations,
+                    nsec3.hex_salt(),
+                    nsec3.base32_next_hashed_owner(),
+                    types.join(" "),
+                ).trim_end().into()
+            }
+            Record::NSEC3PARAM(param) => {
+                format!("{} {} {} {}",
+                    param.hash_algorithm,
+                    param.flags,
+                    param.iterations,
+                    param.hex_salt(),
+                )
+            }
+            Record::OPENPGPKEY(opgp) => {
+                format!("{:?}", opgp.base64_key())
+            }
+            Record::PTR(ptr) => {
+                format!("{:?}", ptr.cname.to_string())
+            }
+            Record::RRSIG(rrsig) => {
+                format!("{} {} {} {} {} {} {} {:?} {:?}",
+                    record_type_name(rrsig.type_covered),
+                    rrsig.algorithm,
+                    rrsig.labels,
+                    self.format_duration(rrsig.original_ttl),
+                    signature_timestamp(rrsig.signature_expiration),
+                    signature_timestamp(rrsig.signature_inception),
+                    rrsig.key_tag,
+                    rrsig.signer_name.to_string(),
+                    rrsig.base64_signature(),
+                )
+            }
+            Record::SSHFP(sshfp) => {
+                format!("{} {} {}",
+                    sshfp.algorithm,
+                    sshfp.fingerprint_type,
+                    sshfp.hex_fingerprint(),
+                )
+            }
+            Record::SOA(soa) => {
+                format!("{:?} {:?} {} {} {} {} {}",
+                    soa.mname.to_string(),
+                    soa.rname.to_string(),
+                    soa.serial,
+                    self.format_duration(soa.refresh_interval),
+                    self.format_duration(soa.retry_interval),
+                    self.format_duration(soa.expire_limit),
+                    self.format_duration(soa.minimum_ttl),
+                )
+            }
+            Record::SRV(srv) => {
+                format!("{} {} {:?}:{}", srv.priority, srv.weight, srv.target.to_string(), srv.port)
+            }
+            Record::SVCB(svcb) => {
+                svcb_summary(&svcb)
+            }
+            Record::TA(ta) => {
+                self.record_payload_summary(Record::DS(ta.0))
+            }
+            Record::TLSA(tlsa) => {
+                format!("{} {} {} {:?}",
+                    tlsa.certificate_usage,
+                    tlsa.selector,
+                    tlsa.matching_type,
+                    tlsa.hex_certificate_data(),
+                )
+            }
+            Record::TXT(txt) => {
+                let messages = match self.txt_decoding {
+                    Some(decoding) => {
+                        txt.decode(decoding).into_iter().map(|message| match message {
+                            DecodedMessage::Text(text)  => format!("{:?}", text),
+                            DecodedMessage::Hex(hex)    => format!("0x{}", hex),
+                        }).collect::<Vec<_>>()
+                    }
+                    None => {
+                        txt.messages.iter().map(|t| Ascii(t).to_string()).collect::<Vec<_>>()
+                    }
+                };
+                messages.join(", ")
+            }
+            Record::URI(uri) => {
+                format!("{} {} {}", uri.priority, uri.weight, Ascii(&uri.target))
+            }
+            Record::Other { bytes, .. } => {
+                dns::presentation::generic(&bytes)
+            }
+        }
+    }
+
+    /// Formats a response as the single line that quiet mode prints: its
+    /// status, the name and type that were queried, and the data of every
+    /// record in the answer section. Tabs and line breaks in the data are
+    /// turned into spaces, so the line can always be split on tabs.
+    pub fn quiet_line(self, response: Response) -> String {
+        let rcode = response.flags.error_code;
+
+        let (qname, qtype) = match response.queries.first() {
+            Some(query)  => (query.qname.to_string(), record_type_name(query.qtype.type_number())),
+            None         => (String::new(), String::new()),
+        };
+
+        let data = response.answers.into_iter()
+            .filter_map(|answer| match answer {
+                Answer::Standard { record, .. }  => Some(self.record_payload_summary(record)),
+                Answer::Pseudo { .. }            => None,
+            })
+            .map(|summary| summary.replace(['\t', '\n', '\r'], " "))
+            .collect::<Vec<_>>();
+
+        format!("{}\t{}\t{}\t{}", status_mnemonic(rcode), qname, qtype, data.join(", "))
+    }
+
+    /// Formats an error that has already been serialised by `json_error` as
+    /// the single line that quiet mode prints for it. This has the same
+    /// fields as `quiet_line`, with a status of `ERROR` and the phase and
+    /// message of the error in place of the data.
+    pub fn quiet_error_line(self, query: Option<&Query>, error: &JsonValue) -> String {
+        let (qname, qtype) = match query {
+            Some(query)  => (query.qname.to_string(), record_type_name(query.qtype.type_number())),
+            None         => (String::new(), String::new()),
+        };
+
+        let message = error["error_message"].as_str().unwrap_or_default().replace(['\t', '\n', '\r'], " ");
+        format!("ERROR\t{}\t{}\t{}: {}", qname, qtype, error["error_phase"].as_str().unwrap_or_default(), message)
+    }
+
+    /// Formats a summary of an OPT pseudo-record. Pseudo-records have a different
+    /// structure than standard ones.
+    pub fn pseudo_record_payload_summary(self, opt: OPT) -> String {
+        format!("{} {} {} {} {:?}",
+            opt.udp_payload_size,
+            opt.higher_bits,
+            opt.edns0_version,
+            opt.flags,
+            opt.data)
+    }
+
+    /// Formats a response policy zone rule as its trigger followed by its
+    /// action, and the record to answer with if it is local data.
+    pub fn policy_rule_summary(self, rule: PolicyRule) -> String {
+        match rule.action {
+            Action::LocalData(record) => {
+                let rtype = json_record_name(&record);
+                let rtype = rtype.as_str().map_or_else(|| rtype.to_string(), String::from);
+                format!("{} → LOCAL-DATA {} {}", rule.trigger, rtype, self.record_payload_summary(record))
+            }
+            action => {
+                format!("{} → {}", rule.trigger, action.name())
+            }
+        }
+    }
+
+    /// Formats a duration depending on whether it should be displayed as
+    /// seconds, or as computed units.
+    pub fn format_duration(self, seconds: u32) -> String {
+        if self.format_durations {
+            format_duration_hms(seconds)
+        }
+        else {
+            format!("{}", seconds)
+        }
+    }
+}
+
+/// Describes what an ENUM record means, for a NAPTR record under
+/// `e164.arpa` with ENUM services: the telephone number it is for, and a
+/// friendlier name for each of the services. Returns `None` for all other
+/// records.
+pub fn enum_summary(qname: &Labels, record: &Record) -> Option<String> {
+    let naptr = match record {
+        Record::NAPTR(naptr)  => naptr,
+        _                     => return None,
+    };
+
+    let number = enum_number(qname)?;
+    let services = naptr.enum_services()?.iter()
+                        .map(|service| enum_service_description(service))
+                        .collect::<Vec<_>>();
+
+    Some(format!(" (ENUM {}: {})", number, services.join(", ")))
+}
+
+/// Returns the mnemonic for a class, such as `IN`. Classes without one use
+/// the generic ‘CLASS’ form.
+fn class_mnemonic(qclass: QClass) -> String {
+    match qclass {
+        QClass::IN        => "IN".into(),
+        QClass::CH        => "CH".into(),
+        QClass::HS        => "HS".into(),
+        QClass::Other(n)  => format!("CLASS{}", n),
+    }
+}
+
+/// Formats a question as its name, class, and type, in the order they
+/// appear in a zone file, for messages with more than one question.
+pub fn question_line(query: &Query) -> String {
+    format!("{} {} {}", query.qname, class_mnemonic(query.qclass), record_type_name(query.qtype.type_number()))
+}
+
+/// Formats a record as a line of a zone file: its owner, TTL, class, type,
+/// and data in presentation format, separated by tabs. Returns `None` for
+/// OPT pseudo-records, which do not appear in zone files.
+pub fn zone_line(answer: Answer) -> Option<String> {
+    match answer {
+        Answer::Standard { qname, qclass, ttl, record } => {
+            let owner = if qname.len() == 0 { ".".into() } else { qname.to_string() };
+            Some(format!("{}\t{}\t{}\t{}\t{}", owner, ttl, class_mnemonic(qclass), record.record_type().mnemonic(), record.to_presentation()))
+        }
+        Answer::Pseudo { .. } => {
+            None
+        }
+    }
+}
+
+/// Returns a friendlier description of an ENUM service, such as `voice:tel`,
+/// which is a type followed by optional subtypes.
+fn enum_service_description(service: &str) -> String {
+    let mut parts = service.split(':');
+    let service_type = parts.next().unwrap_or_default();
+
+    let description = match service_type {
+        "email"    => "e-mail",
+        "fax"      => "fax",
+        "ft"       => "file transfer",
+        "h323"     => "H.323",
+        "iax"      => "IAX",
+        "im"       => "instant messaging",
+        "mms"      => "MMS",
+        "pres"     => "presence",
+        "sip"      => "SIP",
+        "sms"      => "SMS",
+        "unifmsg"  => "unified messaging",
+        "video"    => "video call",
+        "voice"    => "voice call",
+        "vpim"     => "voice mail",
+        "web"      => "web page",
+        "xmpp"     => "XMPP",
+        other      => other,
+    };
+
+    let subtypes = parts.collect::<Vec<_>>();
+    if subtypes.is_empty() {
+        description.into()
+    }
+    else {
+        format!("{} ({})", description, subtypes.join(", "))
+    }
+}
+
+/// Formats a SVCB or HTTPS record, with its parameters in the same
+/// `key=value` form they are written in zone files.
+fn svcb_summary(svcb: &SVCB) -> String {
+    let mut parts = vec![
+        svcb.priority.to_string(),
+        format!("{:?}", svcb.target.to_string()),
+    ];
+
+    for param in &svcb.parameters {
+        parts.push(svc_param_summary(param));
+    }
+
+    parts.join(" ")
+}
+
+/// Formats one SVCB parameter. The configurations in the `ech` parameter are
+/// decoded and described after their base64 form, if possible.
+fn svc_param_summary(param: &SvcParam) -> String {
+    let key = svc_param_key_name(param.key());
+
+    match param {
+        SvcParam::Mandatory(keys) => {
+            let names = keys.iter().map(|k| svc_param_key_name(*k)).collect::<Vec<_>>();
+            format!("{}={}", key, names.join(","))
+        }
+        SvcParam::Alpn(protocols) => {
+            let protocols = protocols.iter().map(|p| &p[..]).collect::<Vec<_>>();
+            format!("{}={}", key, Ascii(&protocols.join(&b',')))
+        }
+        SvcParam::NoDefaultAlpn => {
+            key
+        }
+        SvcParam::Port(port) => {
+            format!("{}={}", key, port)
+        }
+        SvcParam::Ipv4Hint(addresses) => {
+            let addresses = addresses.iter().map(ToString::to_string).collect::<Vec<_>>();
+            format!("{}={}", key, addresses.join(","))
+        }
+        SvcParam::Ech(list) => {
+            format!("{}={:?}{}", key, list.base64(), ech_summary(list))
+        }
+        SvcParam::Ipv6Hint(addresses) => {
+            let addresses = addresses.iter().map(ToString::to_string).collect::<Vec<_>>();
+            format!("{}={}", key, addresses.join(","))
+        }
+        SvcParam::DohPath(path) => {
+            format!("{}={}", key, Ascii(path))
+        }
+        SvcParam::Other { value, .. } => {
+            format!("{}={}", key, Ascii(value))
+        }
+    }
+}
+
+/// Describes each of the configurations in an ECH configuration list, or
+/// returns nothing if the list could not be decoded.
+fn ech_summary(list: &EchConfigList) -> String {
+    let configs = match list.configs() {
+        Ok(configs) => configs,
+        Err(e) => {
+            warn!("Failed to decode ECH configuration list: {}", wire_error_message(e));
+            return String::new();
+        }
+    };
+
+    let descriptions = configs.into_iter().map(|config| {
+        match config.contents {
+            Some(contents) => {
+                let suites = contents.cipher_suites.iter()
+                                     .map(|s| format!("{}/{}", hpke_kdf_name(s.kdf_id), hpke_aead_name(s.aead_id)))
+                                     .collect::<Vec<_>>();
+
+                format!(" [config {}: {}, {}, public name {}]",
+                    contents.config_id,
+                    hpke_kem_name(contents.kem_id),
+                    suites.join(" "),
+                    Ascii(&contents.public_name),
+                )
+            }
+            None => {
+                format!(" [version {:#06x}]", config.version)
+            }
+        }
+    }).collect::<Vec<_>>();
+
+    descriptions.concat()
+}
+
+/// Returns the name of an HPKE key encapsulation mechanism.
+fn hpke_kem_name(kem_id: u16) -> String {
+    match kem_id {
+        0x0010 => "DHKEM(P-256)".into(),
+        0x0011 => "DHKEM(P-384)".into(),
+        0x0012 => "DHKEM(P-521)".into(),
+        0x0020 => "DHKEM(X25519)".into(),
+        0x0021 => "DHKEM(X448)".into(),
+        _      => format!("KEM {:#06x}", kem_id),
+    }
+}
+
+/// Returns the name of an HPKE key derivation function.
+fn hpke_kdf_name(kdf_id: u16) -> String {
+    match kdf_id {
+        0x0001 => "HKDF-SHA256".into(),
+        0x0002 => "HKDF-SHA384".into(),
+        0x0003 => "HKDF-SHA512".into(),
+        _      => format!("KDF {:#06x}", kdf_id),
+    }
+}
+
+/// Returns the name of an HPKE authenticated encryption algorithm.
+fn hpke_aead_name(aead_id: u16) -> String {
+    match aead_id {
+        0x0001 => "AES-128-GCM".into(),
+        0x0002 => "AES-256-GCM".into(),
+        0x0003 => "ChaCha20Poly1305".into(),
+        0xffff => "export-only".into(),
+        _      => format!("AEAD {:#06x}", aead_id),
+    }
+}
+
+/// Formats a duration as days, hours, minutes, and seconds, skipping leading
+/// zero units.
+/// Returns the name of the record type with the given number, or the
+/// number itself if it has no name.
+pub fn record_type_name(type_number: u16) -> String {
+    match json_record_type_name(RecordType::from(type_number)).as_str() {
+        Some(name)  => name.into(),
+        None        => type_number.to_string(),
+    }
+}
+
+fn format_duration_hms(seconds: u32) -> String {
+    if seconds < 60 {
+        format!("{}s", seconds)
+    }
+    else if seconds < 60 * 60 {
+        format!("{}m{:02}s",
+            seconds / 60,
+            seconds % 60)
+    }
+    else if seconds < 60 * 60 * 24 {
+        format!("{}h{:02}m{:02}s",
+            seconds / 3600,
+            (seconds % 3600) / 60,
+            seconds % 60)
+    }
+    else {
+        format!("{}d{}h{:02}m{:02}s",
+            seconds / 86400,
+            (seconds % 86400) / 3600,
+            (seconds % 3600) / 60,
+            seconds % 60)
+    }
+}
+
+/// Serialises multiple DNS queries as a JSON value.
+/// Serialises a response, along with the EDNS options it carried, as a JSON
+/// value. The options are passed in separately so they can be read before
+/// the OPT record gets hidden.
+pub fn json_response(response: Response, options: &[EdnsOption]) -> JsonValue {
+    let opcode = response.flags.opcode;
+    let answers = json_answers(response.answers);
+    let authorities = json_answers(response.authorities);
+    let additionals = json_answers(response.additionals);
+
+    let rrsets = object! {
+        "answers": json_rrsets(&answers),
+        "authorities": json_rrsets(&authorities),
+        "additionals": json_rrsets(&additionals),
+    };
+
+    let mut json = object! {
+        "queries": json_queries(response.queries),
+        "answers": answers,
+        "authorities": authorities,
+        "additionals": additionals,
+        "rrsets": rrsets,
+        "extended_errors": json_extended_errors(options),
+        "nsid": json_nsid(options),
+        "expire": json_expire(options),
+        "cookie": json_cookie(options),
+        "client_subnet": json_client_subnet(options),
+        "report_channel": json_report_channel(options),
+        "other_options": json_other_options(options),
+    };
+
+    if opcode != Opcode::Query {
+        json["opcode"] = opcode_mnemonic(opcode).into();
+    }
+
+    // The sections of an UPDATE message hold the zone, the prerequisites,
+    // and the updates, rather than queries, answers, and authorities, so
+    // they are named after what they hold (RFC 2136 §2).
+    if opcode == Opcode::Update {
+        for (from, to) in &[ ("answers", "prerequisites"), ("authorities", "updates") ] {
+            json[*to] = json.remove(from);
+            json["rrsets"][*to] = json["rrsets"].remove(from);
+        }
+
+        json["zone"] = json.remove("queries");
+    }
+
+    json
+}
+
+/// Serialises an error that’s ocurred while sending or receiving DNS
+/// packets as a JSON value.
+pub fn json_error(error: TransportError) -> JsonValue {
+    object! {
+        "error": true,
+        "error_phase": erroneous_phase(&error),
+        "error_message": error_message(error),
+    }
+}
+
+fn json_queries(queries: Vec<Query>) -> JsonValue {
+    let queries = queries.iter().map(|q| {
+        object! {
+            "name": q.qname.to_string(),
+            "class": json_class(q.qclass),
+            "type": json_record_type_name(q.qtype),
+        }
+    }).collect::<Vec<_>>();
+
+    queries.into()
+}
+
+/// Serialises multiple received DNS answers as a JSON value.
+fn json_answers(answers: Vec<Answer>) -> JsonValue {
+    let answers = answers.into_iter().map(|a| {
+        match a {
+            Answer::Standard { qname, qclass, ttl, record } => {
+                if let (Some(number), Record::NAPTR(naptr)) = (enum_number(&qname), &record) {
+                    if let Some(services) = naptr.enum_services() {
+                        return object! {
+                            "name": qname.to_string(),
+                            "class": json_class(qclass),
+                            "ttl": ttl,
+                            "type": json_record_name(&record),
+                            "data": json_record_data(record),
+                            "enum": {
+                                "number": number,
+                                "services": services,
+                            },
+                        };
+                    }
+                }
+
+                object! {
+                    "name": qname.to_string(),
+                    "class": json_class(qclass),
+                    "ttl": ttl,
+                    "type": json_record_name(&record),
+                    "data": json_record_data(record),
+                }
+            }
+            Answer::Pseudo { qname, opt } => {
+                object! {
+                    "name": qname.to_string(),
+                    "type": "OPT",
+                    "data": {
+                        "version": opt.edns0_version,
+                        "data": opt.data,
+                    },
+                }
+            }
+        }
+    }).collect::<Vec<_>>();
+
+    answers.into()
+}
+
+
+/// Groups the JSON answers into record sets, which are the records with the
+/// same name, type, and class. Each set lists the data of its records, and
+/// the lowest TTL among them. OPT pseudo-records are left out.
+///
+/// The data of the RRSIG records covering each set are listed alongside it
+/// as its signatures, rather than in a set of their own.
+fn json_rrsets(answers: &JsonValue) -> JsonValue {
+    let mut rrsets: Vec<(&JsonValue, u32, Vec<JsonValue>)> = Vec::new();
+
+    for answer in answers.members() {
+        if answer["type"].as_str() == Some("OPT") {
+            continue;
+        }
+
+        if answer["type"].as_str() == Some("RRSIG") && answers.members().any(|a| json_is_covered_by(a, answer)) {
+            continue;
+        }
+
+        let ttl = answer["ttl"].as_u32().unwrap_or_default();
+        let existing = rrsets.iter_mut().find(|(first, _, _)| {
+            json_same_owner(first, answer) && first["type"] == answer["type"]
+        });
+
+        match existing {
+            Some((_, set_ttl, records)) => {
+                *set_ttl = (*set_ttl).min(ttl);
+                records.push(answer["data"].clone());
+            }
+            None => {
+                rrsets.push((answer, ttl, vec![ answer["data"].clone() ]));
+            }
+        }
+    }
+
+    let rrsets = rrsets.into_iter().map(|(first, ttl, records)| {
+        let signatures = answers.members()
+                                .filter(|a| a["type"].as_str() == Some("RRSIG") && json_is_covered_by(first, a))
+                                .map(|a| a["data"].clone())
+                                .collect::<Vec<_>>();
+
+        object! {
+            "name": first["name"].clone(),
+            "class": first["class"].clone(),
+            "type": first["type"].clone(),
+            "ttl": ttl,
+            "records": records,
+            "signatures": signatures,
+        }
+    }).collect::<Vec<_>>();
+
+    rrsets.into()
+}
+
+/// Whether two JSON answers have the same name and class.
+fn json_same_owner(one: &JsonValue, other: &JsonValue) -> bool {
+    one["name"].as_str().map(str::to_ascii_lowercase) == other["name"].as_str().map(str::to_ascii_lowercase)
+        && one["class"] == other["class"]
+}
+
+/// Whether a JSON answer is in the set covered by the given RRSIG answer.
+fn json_is_covered_by(answer: &JsonValue, signature: &JsonValue) -> bool {
+    answer["type"].as_str() != Some("RRSIG")
+        && answer["type"] == signature["data"]["type_covered"]
+        && json_same_owner(answer, signature)
+}
+
+
+fn json_class(class: QClass) -> JsonValue {
+    match class {
+        QClass::IN        => "IN".into(),
+        QClass::CH        => "CH".into(),
+        QClass::HS        => "HS".into(),
+        QClass::Other(n)  => n.into(),
+    }
+}
+
+
+/// Serialises a DNS record type name.
+pub fn json_record_type_name(record: RecordType) -> JsonValue {
+    match record {
+        RecordType::A           => "A".into(),
+        RecordType::AAAA        => "AAAA".into(),
+        RecordType::AMTRELAY    => "AMTRELAY".into(),
+        RecordType::CAA         => "CAA".into(),
+        RecordType::CNAME       => "CNAME".into(),
+        RecordType::DLV         => "DLV".into(),
+        RecordType::DNSKEY      => "DNSKEY".into(),
+        RecordType::DOA         => "DOA".into(),
+        RecordType::DS          => "DS".into(),
+        RecordType::EUI48       => "EUI48".into(),
+        RecordType::EUI64       => "EUI64".into(),
+        RecordType::HINFO       => "HINFO".into(),
+        RecordType::HIP         => "HIP".into(),
+        RecordType::HTTPS       => "HTTPS".into(),
+        RecordType::LOC         => "LOC".into(),
+        RecordType::MX          => "MX".into(),
+        RecordType::NAPTR       => "NAPTR".into(),
+        RecordType::NS          => "NS".into(),
+        RecordType::NSEC        => "NSEC".into(),
+        RecordType::NSEC3       => "NSEC3".into(),
+        RecordType::NSEC3PARAM  => "NSEC3PARAM".into(),
+        RecordType::OPENPGPKEY  => "OPENPGPKEY".into(),
+        RecordType::PTR         => "PTR".into(),
+        RecordType::RRSIG       => "RRSIG".into(),
+        RecordType::SOA         => "SOA".into(),
+        RecordType::SRV         => "SRV".into(),
+        RecordType::SSHFP       => "SSHFP".into(),
+        RecordType::SVCB        => "SVCB".into(),
+        RecordType::TA          => "TA".into(),
+        RecordType::TLSA        => "TLSA".into(),
+        RecordType::TXT         => "TXT".into(),
+        RecordType::URI         => "URI".into(),
+        RecordType::Other(unknown) => {
+            match unknown {
+                UnknownQtype::HeardOf(name, _)  => (*name).into(),
+                UnknownQtype::UnheardOf(num)    => (num).into(),
+            }
+        }
+    }
+}
+
+/// Serialises a DNS record type name.
+fn json_record_name(record: &Record) -> JsonValue {
+    match record.type_name() {
+        Some(name)  => name.into(),
+        None        => record.type_number().into(),
+    }
+}
+
+
+/// Serialises a received DNS record as a JSON value.
+
+/// Even though DNS doesn’t specify a character encoding, strings are still
+/// converted from UTF-8, because JSON specifies UTF-8.
+fn json_record_data(record: Record) -> JsonValue {
+    match record {
+        Record::A(a) => {
+            object! {
+                "address": a.address.to_string(),
+            }
+        }
+        Record::AAAA(aaaa) => {
+            object! {
+                "address": aaaa.address.to_string(),
+            }
+        }
+        Record::AMTRELAY(amtrelay) => {
+            let relay_type = amtrelay.relay.relay_type();
+            let relay = match amtrelay.relay {
+                Relay::Empty                => JsonValue::Null,
+                Relay::Other { bytes, .. }  => bytes.into(),
+                otherwise                   => otherwise.to_string().into(),
+            };
+            object! {
+                "precedence": amtrelay.precedence,
+                "discovery_optional": amtrelay.discovery_optional,
+                "relay_type": relay_type,
+                "relay": relay,
+            }
+        }
+        Record::CAA(caa) => {
+            let property = caa.property();
+            let tag = String::from_utf8_lossy(&caa.tag).to_string();
+            let value = String::from_utf8_lossy(&caa.value).to_string();
+
+            if let Some(CaaProperty::Issue(issue)) = property {
+                let parameters = issue.parameters.into_iter().map(|(tag, value)| object! {
+                    "tag": tag,
+                    "value": value,
+                }).collect::<Vec<_>>();
+
+                object! {
+                    "critical": caa.critical,
+                    "tag": tag,
+                    "value": value,
+                    "issuer": issue.issuer,
+                    "parameters": parameters,
+                }
+            }
+            else {
+                object! {
+                    "critical": caa.critical,
+                    "tag": tag,
+                    "value": value,
+                }
+            }
+        }
+        Record::CNAME(cname) => {
+            object! {
+                "domain": cname.domain.to_string(),
+            }
+        }
+        Record::DLV(dlv) => {
+            json_record_data(Record::DS(dlv.0))
+        }
+        Record::DNSKEY(dnskey) => {
+            object! {
+                "flags": dnskey.flags,
+                "protocol": dnskey.protocol,
+                "algorithm": dnskey.algorithm,
+                "public_key": dnskey.base64_key(),
+                "key_tag": dnskey.key_tag(),
+            }
+        }
+        Record::DOA(doa) => {
+            object! {
+                "enterprise": doa.enterprise,
+                "type": doa.doa_type,
+                "location": doa.location,
+                "media_type": String::from_utf8_lossy(&doa.media_type).to_string(),
+                "data": doa.base64_data(),
+            }
+        }
+        Record::DS(ds) => {
+            object! {
+                "key_tag": ds.key_tag,
+                "algorithm": ds.algorithm,
+                "digest_type": ds.digest_type,
+                "digest": ds.hex_digest(),
+            }
+        }
+        Record::EUI48(eui48) => {
+            object! {
+                "identifier": eui48.formatted_address(),
+            }
+        }
+        Record::EUI64(eui64) => {
+            object! {
+                "identifier": eui64.formatted_address(),
+            }
+        }
+        Record::HINFO(hinfo) => {
+            object! {
+                "cpu": String::from_utf8_lossy(&hinfo.cpu).to_string(),
+                "os": String::from_utf8_lossy(&hinfo.os).to_string(),
+            }
+        }
+        Record::HTTPS(https) => {
+            json_svcb(https.0)
+        }
+        Record::HIP(hip) => {
+            let servers = hip.rendezvous_servers.iter()
+                             .map(|server| server.to_string())
+                             .collect::<Vec<_>>();
+            object! {
+                "algorithm": hip.algorithm,
+                "hit": hip.hex_hit(),
+                "public_key": hip.base64_public_key(),
+                "rendezvous_servers": servers,
+            }
+        }
+        Record::LOC(loc) => {
+            object! {
+                "size": loc.size.to_string(),
+                "precision": {
+                    "horizontal": loc.horizontal_precision.to_string(),
+                    "vertical": loc.vertical_precision.to_string(),
+                },
+                "point": {
+                    "latitude": loc.latitude.map(|e| e.to_string()),
+                    "longitude": loc.longitude.map(|e| e.to_string()),
+                    "altitude": loc.altitude.to_string(),
+                },
+                "metres": {
+                    "size": loc.size.in_metres(),
+                    "horizontal_precision": loc.horizontal_precision.in_metres(),
+                    "vertical_precision": loc.vertical_precision.in_metres(),
+                    "altitude": loc.altitude.in_metres(),
+                },
+                "decimal": {
+                    "latitude": loc.latitude.map(|e| e.decimal_degrees()),
+                    "longitude": loc.longitude.map(|e| e.decimal_degrees()),
+                },
+            }
+        }
+        Record::MX(mx) => {
+            object! {
+                "preference": mx.preference,
+                "exchange": mx.exchange.to_string(),
+            }
+        }
+        Record::NAPTR(naptr) => {
+            object! {
+                "order": naptr.order,
+                "flags": String::from_utf8_lossy(&naptr.flags).to_string(),
+                "service": String::from_utf8_lossy(&naptr.service).to_string(),
+                "regex": String::from_utf8_lossy(&naptr.regex).to_string(),
+                "regex_error": naptr.substitution().err().map(regex_error_message),
+                "replacement": naptr.replacement.to_string(),
+            }
+        }
+        Record::NS(ns) => {
+            object! {
+                "nameserver": ns.nameserver.to_string(),
+            }
+        }
+        Record::NSEC(nsec) => {
+            object! {
+                "next_domain": nsec.next_domain.to_string(),
+                "types": nsec.types.iter().map(|t| json_record_type_name(RecordType::from(*t))).collect::<Vec<_>>(),
+            }
+        }
+        Record::NSEC3(nsec3) => {
+            object! {
+                "hash_algorithm": nsec3.hash_algorithm,
+                "flags": nsec3.flags,
+                "opt_out": nsec3.opt_out(),
+                "iterations": nsec3.iterations,
+                "salt": nsec3.hex_salt(),
+                "next_hashed_owner": nsec3.base32_next_hashed_owner(),
+                "types": nsec3.types.iter().map(|t| json_record_type_name(RecordType::from(*t))).collect::<Vec<_>>(),
+            }
+        }
+        Record::NSEC3PARAM(param) => {
+            object! {
+                "hash_algorithm": param.hash_algorithm,
+                "flags": param.flags,
+                "iterations": param.iterations,
+                "salt": param.hex_salt(),
+            }
+        }
+        Record::OPENPGPKEY(opgp) => {
+            object! {
+                "key": opgp.base64_key(),
+            }
+        }
+        Record::PTR(ptr) => {
+            object! {
+                "cname": ptr.cname.to_string(),
+            }
+        }
+        Record::RRSIG(rrsig) => {
+            object! {
+                "type_covered": json_record_type_name(RecordType::from(rrsig.type_covered)),
+                "algorithm": rrsig.algorithm,
+                "labels": rrsig.labels,
+                "original_ttl": rrsig.original_ttl,
+                "expiration": signature_timestamp(rrsig.signature_expiration),
+                "inception": signature_timestamp(rrsig.signature_inception),
+                "key_tag": rrsig.key_tag,
+                "signer_name": rrsig.signer_name.to_string(),
+                "signature": rrsig.base64_signature(),
+            }
+        }
+        Record::SSHFP(sshfp) => {
+            object! {
+                "algorithm": sshfp.algorithm,
+                "fingerprint_type": sshfp.fingerprint_type,
+                "fingerprint": sshfp.hex_fingerprint(),
+            }
+        }
+        Record::SOA(soa) => {
+            object! {
+                "mname": soa.mname.to_string(),
+            }
+        }
+        Record::SRV(srv) => {
+            object! {
+                "priority": srv.priority,
+                "weight": srv.weight,
+                "port": srv.port,
+                "target": srv.target.to_string(),
+            }
+        }
+        Record::SVCB(svcb) => {
+            json_svcb(svcb)
+        }
+        Record::TA(ta) => {
+            json_record_data(Record::DS(ta.0))
+        }
+        Record::TLSA(tlsa) => {
+            object! {
+                "certificate_usage": tlsa.certificate_usage,
+                "selector": tlsa.selector,
+                "matching_type": tlsa.matching_type,
+                "certificate_data": tlsa.hex_certificate_data(),
+            }
+        }
+        Record::TXT(txt) => {
+            let ms = txt.messages.into_iter()
+                        .map(|txt| String::from_utf8_lossy(&txt).to_string())
+                        .collect::<Vec<_>>();
+            object! {
+                "messages": ms,
+            }
+        }
+        Record::URI(uri) => {
+            object! {
+                "priority": uri.priority,
+                "weight": uri.weight,
+                "target": String::from_utf8_lossy(&uri.target).to_string(),
+            }
+        }
+        Record::Other { bytes, .. } => {
+            object! {
+                "bytes": bytes,
+            }
+        }
+    }
+}
+
+
+/// Converts a SVCB or HTTPS record to JSON, with each parameter listed under
+/// its presentation name.
+fn json_svcb(svcb: SVCB) -> JsonValue {
+    let parameters = svcb.parameters.into_iter().map(|param| {
+        let key = svc_param_key_name(param.key());
+        let value: JsonValue = match param {
+            SvcParam::Mandatory(keys) => {
+                keys.into_iter().map(svc_param_key_name).collect::<Vec<_>>().into()
+            }
+            SvcParam::Alpn(protocols) => {
+                protocols.iter().map(|p| String::from_utf8_lossy(p).to_string()).collect::<Vec<_>>().into()
+            }
+            SvcParam::NoDefaultAlpn => {
+                JsonValue::Null
+            }
+            SvcParam::Port(port) => {
+                port.into()
+            }
+            SvcParam::Ipv4Hint(addresses) => {
+                addresses.iter().map(ToString::to_string).collect::<Vec<_>>().into()
+            }
+            SvcParam::Ech(list) => {
+                json_ech(&list)
+            }
+            SvcParam::Ipv6Hint(addresses) => {
+                addresses.iter().map(ToString::to_string).collect::<Vec<_>>().into()
+            }
+            SvcParam::DohPath(path) => {
+                String::from_utf8_lossy(&path).to_string().into()
+            }
+            SvcParam::Other { value, .. } => {
+                value.into()
+            }
+        };
+
+        object! {
+            "key": key,
+            "value": value,
+        }
+    }).collect::<Vec<_>>();
+
+    object! {
+        "priority": svcb.priority,
+        "target": svcb.target.to_string(),
+        "parameters": parameters,
+    }
+}
+
+/// Converts an ECH configuration list to JSON. The decoded configurations are
+/// null if the list could not be decoded.
+fn json_ech(list: &EchConfigList) -> JsonValue {
+    let configs = list.configs().ok().map(|configs| {
+        configs.into_iter().map(|config| {
+            match config.contents {
+                Some(contents) => {
+                    let suites = contents.cipher_suites.iter().map(|s| object! {
+                        "kdf": hpke_kdf_name(s.kdf_id),
+                        "aead": hpke_aead_name(s.aead_id),
+                    }).collect::<Vec<_>>();
+
+                    object! {
+                        "version": config.version,
+                        "config_id": contents.config_id,
+                        "kem": hpke_kem_name(contents.kem_id),
+                        "public_key": contents.base64_public_key(),
+                        "cipher_suites": suites,
+                        "maximum_name_length": contents.maximum_name_length,
+                        "public_name": String::from_utf8_lossy(&contents.public_name).to_string(),
+                    }
+                }
+                None => {
+                    object! {
+                        "version": config.version,
+                    }
+                }
+            }
+        }).collect::<Vec<_>>()
+    });
+
+    object! {
+        "base64": list.base64(),
+        "configs": configs,
+    }
+}
+
+
+/// A wrapper around displaying characters that escapes quotes and
+/// backslashes, and writes control and upper-bit bytes as their number rather
+/// than their character. This is needed because even though such characters
+/// are not allowed in domain names, packets can contain anything, and we need
+/// a way to display the response, whatever it is.
+struct Ascii<'a>(&'a [u8]);
+
+impl fmt::Display for Ascii<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "\"")?;
+
+        for byte in self.0.iter().copied() {
+            if byte < 32 || byte >= 128 {
+                write!(f, "\\{}", byte)?;
+            }
+            else if byte == b'"' {
+                write!(f, "\\\"")?;
+            }
+            else if byte == b'\\' {
+                write!(f, "\\\\")?;
+            }
+            else {
+                write!(f, "{}", byte as char)?;
+            }
+        }
+
+        write!(f, "\"")
+    }
+}
+
+
+/// Prints a message describing the “error code” field of a DNS packet. This
+/// happens when the packet was received correctly, but the server indicated
+/// an error.
+pub fn print_error_code(rcode: ErrorCode, language: Language) {
+    println!("{}: {}", language.text(Message::Status), describe_error_code(rcode, language));
+}
+
+/// Returns the mnemonic for a response’s status, such as `NOERROR` or
+/// `NXDOMAIN`, as it appears in zone files and in other tools’ output.
+pub fn status_mnemonic(rcode: Option<ErrorCode>) -> String {
+    match rcode {
+        None                              => "NOERROR".into(),
+        Some(ErrorCode::FormatError)      => "FORMERR".into(),
+        Some(ErrorCode::ServerFailure)    => "SERVFAIL".into(),
+        Some(ErrorCode::NXDomain)         => "NXDOMAIN".into(),
+        Some(ErrorCode::NotImplemented)   => "NOTIMP".into(),
+        Some(ErrorCode::QueryRefused)     => "REFUSED".into(),
+        Some(ErrorCode::BadVersion)       => "BADVERS".into(),
+        Some(ErrorCode::BadCookie)        => "BADCOOKIE".into(),
+        Some(ErrorCode::Private(num))     => format!("RCODE{}", num),
+        Some(ErrorCode::Other(num))       => format!("RCODE{}", num),
+    }
+}
+
+/// Returns the mnemonic for a message’s opcode, such as `QUERY` or
+/// `UPDATE`. Opcodes without one use the generic ‘OPCODE’ form.
+pub fn opcode_mnemonic(opcode: Opcode) -> String {
+    match opcode {
+        Opcode::Query       => "QUERY".into(),
+        Opcode::Status      => "STATUS".into(),
+        Opcode::Notify      => "NOTIFY".into(),
+        Opcode::Update      => "UPDATE".into(),
+        Opcode::Dso         => "DSO".into(),
+        Opcode::Other(num)  => format!("OPCODE{}", num),
+    }
+}
+
+/// Describes a response’s error code in words.
+pub fn error_code_description(rcode: ErrorCode) -> String {
+    describe_error_code(rcode, Language::English)
+}
+
+/// Describes a response’s error code in words, in the given language.
+pub fn describe_error_code(rcode: ErrorCode, language: Language) -> String {
+    match rcode {
+        ErrorCode::FormatError     => language.text(Message::FormatError).into(),
+        ErrorCode::ServerFailure   => language.text(Message::ServerFailure).into(),
+        ErrorCode::NXDomain        => language.text(Message::NXDomain).into(),
+        ErrorCode::NotImplemented  => language.text(Message::NotImplemented).into(),
+        ErrorCode::QueryRefused    => language.text(Message::QueryRefused).into(),
+        ErrorCode::BadVersion      => language.text(Message::BadVersion).into(),
+        ErrorCode::BadCookie       => language.text(Message::BadCookie).into(),
+        ErrorCode::Private(num)    => language.fill(Message::PrivateReason, num),
+        ErrorCode::Other(num)      => language.fill(Message::OtherFailure, num),
+    }
+}
+
+/// Formats an EDNS option from a response as a line to print alongside the
+/// response status, if it is one that helps explain the response.
+fn edns_option_summary(option: &EdnsOption) -> Option<String> {
+    match option {
+        EdnsOption::Nsid(nsid) => {
+            Some(format!("NSID: {} ({:?})", nsid.hex(), nsid.text()))
+        }
+        EdnsOption::Expire(expire) => {
+            match expire.timer {
+                Some(timer)  => Some(format!("Expire: zone expires in {} ({} seconds)", format_duration_hms(timer), timer)),
+                None         => Some("Expire: no expire timer".into()),
+            }
+        }
+        EdnsOption::Cookie(cookie) => {
+            match cookie.server_hex() {
+                Some(server)  => Some(format!("Cookie: server {} (client {})", server, cookie.client_hex())),
+                None          => Some(format!("Cookie: no server cookie (client {})", cookie.client_hex())),
+            }
+        }
+        EdnsOption::ClientSubnet(ecs) => {
+            match ecs.ip_address() {
+                Some(address)  => Some(format!("Client subnet: {}/{}, scope /{}", address, ecs.source_prefix, ecs.scope_prefix)),
+                None           => Some(format!("Client subnet: unknown family {}, scope /{}", ecs.family, ecs.scope_prefix)),
+            }
+        }
+        EdnsOption::ExtendedError(error) => {
+            Some(extended_error_summary(error))
+        }
+        EdnsOption::TcpKeepalive(tk) => {
+            match tk.timeout {
+                Some(timeout)  => Some(format!("TCP keepalive: idle timeout {}.{}s", timeout / 10, timeout % 10)),
+                None           => Some("TCP keepalive: no idle timeout".into()),
+            }
+        }
+        EdnsOption::Chain(chain) if chain.trust_point == Labels::root() => {
+            Some("Chain: from the root".into())
+        }
+        EdnsOption::Chain(chain) => {
+            Some(format!("Chain: from trust point {}", chain.trust_point))
+        }
+        EdnsOption::ReportChannel(rc) => {
+            Some(format!("Report channel: agent domain {}", rc.agent_domain))
+        }
+        EdnsOption::Other { code, data } => {
+            Some(other_option_summary(*code, data))
+        }
+        EdnsOption::Padding(_) => {
+            None
+        }
+    }
+}
+
+/// Formats an option that dog does not interpret, giving its code, the
+/// name IANA has registered for it, if it has one, and its data in
+/// hexadecimal.
+fn other_option_summary(code: u16, data: &[u8]) -> String {
+    let name = edns_option_name(code).unwrap_or("unknown");
+    if data.is_empty() {
+        format!("Option {} ({}): empty", code, name)
+    }
+    else {
+        format!("Option {} ({}): {}", code, name, Hex(data))
+    }
+}
+
+/// Formats an extended error, giving the name of its info code, if it has
+/// one, and any extra text the server sent with it.
+fn extended_error_summary(error: &ExtendedError) -> String {
+    let reason = match error.info_code_name() {
+        Some(name)  => format!("Extended error: {} ({})", name, error.info_code),
+        None        => format!("Extended error: Unknown ({})", error.info_code),
+    };
+
+    if error.extra_text.is_empty() {
+        reason
+    }
+    else {
+        format!("{}: {}", reason, Ascii(&error.extra_text))
+    }
+}
+
+/// Serialises a response policy zone rule as a JSON value. Rules with
+/// local data carry its record type and data, which are `null` otherwise.
+fn json_policy_rule(rule: PolicyRule) -> JsonValue {
+    let (trigger, matches) = match rule.trigger {
+        rpz::Trigger::QName(name)                 => ("qname", name),
+        rpz::Trigger::ResponseIp(address, prefix) => ("ip", format!("{}/{}", address, prefix)),
+        rpz::Trigger::ClientIp(address, prefix)   => ("client_ip", format!("{}/{}", address, prefix)),
+        rpz::Trigger::NsDname(name)               => ("nsdname", name),
+        rpz::Trigger::NsIp(address, prefix)       => ("nsip", format!("{}/{}", address, prefix)),
+    };
+
+    let action = rule.action.name().to_ascii_lowercase().replace('-', "_");
+    let (rtype, data) = match rule.action {
+        Action::LocalData(record)  => (json_record_name(&record), json_record_data(record)),
+        _                          => (JsonValue::Null, JsonValue::Null),
+    };
+
+    object! {
+        "trigger": trigger,
+        "match": matches,
+        "action": action,
+        "type": rtype,
+        "data": data,
+    }
+}
+
+/// Serialises the extended errors among a response’s EDNS options as a
+/// JSON value.
+fn json_extended_errors(options: &[EdnsOption]) -> JsonValue {
+    let errors = options.iter().filter_map(|option| match option {
+        EdnsOption::ExtendedError(error) => Some(object! {
+            "info_code": error.info_code,
+            "name": error.info_code_name(),
+            "extra_text": String::from_utf8_lossy(&error.extra_text).to_string(),
+        }),
+        _ => None,
+    }).collect::<Vec<_>>();
+
+    errors.into()
+}
+
+/// Serialises the agent domain of the Report-Channel option among a
+/// response’s EDNS options as a JSON value, or `null` if there is none.
+fn json_report_channel(options: &[EdnsOption]) -> JsonValue {
+    options.iter().find_map(|option| match option {
+        EdnsOption::ReportChannel(rc) => Some(rc.agent_domain.to_string()),
+        _ => None,
+    }).into()
+}
+
+/// Serialises the EDNS options in a response that dog does not interpret
+/// as a JSON value, giving the code, name, and data of each.
+fn json_other_options(options: &[EdnsOption]) -> JsonValue {
+    let others = options.iter().filter_map(|option| match option {
+        EdnsOption::Other { code, data } => Some(object! {
+            "code": *code,
+            "name": edns_option_name(*code),
+            "data": Hex(data).to_string(),
+        }),
+        _ => None,
+    }).collect::<Vec<_>>();
+
+    others.into()
+}
+
+/// Serialises the NSID among a response’s EDNS options as a JSON value, or
+/// `null` if there is none.
+fn json_nsid(options: &[EdnsOption]) -> JsonValue {
+    options.iter().find_map(|option| match option {
+        EdnsOption::Nsid(nsid) => Some(object! {
+            "hex": nsid.hex(),
+            "text": nsid.text(),
+        }),
+        _ => None,
+    }).into()
+}
+
+/// Serialises the cookies among a response’s EDNS options as a JSON value,
+/// or `null` if there are none.
+fn json_cookie(options: &[EdnsOption]) -> JsonValue {
+    options.iter().find_map(|option| match option {
+        EdnsOption::Cookie(cookie) => Some(object! {
+            "client": cookie.client_hex(),
+            "server": cookie.server_hex(),
+        }),
+        _ => None,
+    }).into()
+}
+
+/// Serialises the expire timer among a response’s EDNS options as a JSON
+/// value, or `null` if there is none.
+fn json_expire(options: &[EdnsOption]) -> JsonValue {
+    options.iter().find_map(|option| match option {
+        EdnsOption::Expire(expire) => expire.timer,
+        _ => None,
+    }).into()
+}
+
+/// Serialises the client subnet among a response’s EDNS options as a JSON
+/// value, or `null` if there is none.
+fn json_client_subnet(options: &[EdnsOption]) -> JsonValue {
+    options.iter().find_map(|option| match option {
+        EdnsOption::ClientSubnet(ecs) => Some(object! {
+            "family": ecs.family,
+            "address": ecs.ip_address().map(|a| a.to_string()),
+            "source_prefix": ecs.source_prefix,
+            "scope_prefix": ecs.scope_prefix,
+        }),
+        _ => None,
+    }).into()
+}
+
+/// Returns the “phase” of operation where an error occurred. This gets shown
+/// to the user so they can debug what went wrong.
+fn erroneous_phase(error: &TransportError) -> &'static str {
+    match error {
+        TransportError::WireError(_)          => "protocol",
+        TransportError::TruncatedResponse     |
+        TransportError::NetworkError(_)       => "network",
+        #[cfg(feature = "with_nativetls")]
+        TransportError::TlsError(_)           |
+        TransportError::TlsHandshakeError(_)  => "tls",
+        #[cfg(feature = "with_rustls")]
+        TransportError::RustlsInvalidDnsNameError(_) => "tls", // TODO: Actually wrong, could be https
+        #[cfg(feature = "with_https")]
+        TransportError::HttpError(_)          |
+        TransportError::WrongHttpStatus(_,_)  |
+        TransportError::Http2Error(_)         |
+        TransportError::JsonApiError(_)       => "http",
+        #[cfg(feature = "with_http3")]
+        TransportError::Http3Error(_)         => "http",
+        #[cfg(feature = "with_quic")]
+        TransportError::QuicConnectError(_)    |
+        TransportError::QuicConnectionError(_) |
+        TransportError::QuicWriteError(_)      |
+        TransportError::QuicReadError(_)       => "quic",
+        TransportError::TsigError(_)          => "tsig",
+        TransportError::DsoError(_)           => "protocol",
+        TransportError::Mismatch(_)           => "protocol",
+    }
+}
+
+/// Formats an error into its human-readable message.
+pub fn error_message(error: TransportError) -> String {
+    match error {
+        TransportError::WireError(e)          => wire_error_message(e),
+        TransportError::TruncatedResponse     => "Truncated response".into(),
+        TransportError::NetworkError(e)       => e.to_string(),
+        #[cfg(feature = "with_nativetls")]
+        TransportError::TlsError(e)           => e.to_string(),
+        #[cfg(feature = "with_nativetls")]
+        TransportError::TlsHandshakeError(e)  => e.to_string(),
+        #[cfg(any(feature = "with_rustls"))]
+        TransportError::RustlsInvalidDnsNameError(e) => e.to_string(),
+        #[cfg(feature = "with_https")]
+        TransportError::HttpError(e)          => e.to_string(),
+        #[cfg(feature = "with_https")]
+        TransportError::WrongHttpStatus(t,r)  => format!("Nameserver returned HTTP {} ({})", t, r.unwrap_or_else(|| "No reason".into())),
+        #[cfg(feature = "with_https")]
+        TransportError::Http2Error(e)         => e.to_string(),
+        #[cfg(feature = "with_https")]
+        TransportError::JsonApiError(e)       => format!("Invalid JSON API response: {}", e),
+        #[cfg(feature = "with_http3")]
+        TransportError::Http3Error(e)         => e.to_string(),
+        #[cfg(feature = "with_quic")]
+        TransportError::QuicConnectError(e)   => e.to_string(),
+        #[cfg(feature = "with_quic")]
+        TransportError::QuicConnectionError(e) => e.to_string(),
+        #[cfg(feature = "with_quic")]
+        TransportError::QuicWriteError(e)     => e.to_string(),
+        #[cfg(feature = "with_quic")]
+        TransportError::QuicReadError(e)      => e.to_string(),
+        TransportError::TsigError(e)          => e.to_string(),
+        TransportError::DsoError(e)           => e.to_string(),
+        TransportError::Mismatch(m)           => format!("Response does not match the request: {}", m),
+    }
+}
+
+/// Formats a wire error into its human-readable message, describing what was
+/// wrong with the packet we received.
+fn regex_error_message(error: RegexError) -> String {
+    match error {
+        RegexError::NotUtf8                  => "not valid UTF-8".into(),
+        RegexError::BadDelimiter(c)          => format!("{:?} cannot be a delimiter", c),
+        RegexError::WrongDelimiterCount(n)   => format!("expected 3 parts, found {}", n),
+        RegexError::UnknownFlag(c)           => format!("unknown flag {:?}", c),
+        RegexError::BothRegexAndReplacement  => "regex and replacement both set".into(),
+    }
+}
+
+fn wire_error_message(error: WireError) -> String {
+    match error {
+        WireError::IO => {
+            "Malformed packet: insufficient data".into()
+        }
+        WireError::WrongRecordLength { stated_length, mandated_length: MandatedLength::Exactly(len) } => {
+            format!("Malformed packet: record length should be {}, got {}", len, stated_length )
+        }
+        WireError::WrongRecordLength { stated_length, mandated_length: MandatedLength::AtLeast(len) } => {
+            format!("Malformed packet: record length should be at least {}, got {}", len, stated_length )
+        }
+        WireError::WrongLabelLength { stated_length, length_after_labels } => {
+            format!("Malformed packet: length {} was specified, but read {} bytes", stated_length, length_after_labels)
+        }
+        WireError::TooMuchRecursion(indices) => {
+            format!("Malformed packet: too much recursion: {:?}", indices)
+        }
+        WireError::OutOfBounds(index) => {
+            format!("Malformed packet: out of bounds ({})", index)
+        }
+        WireError::NameLimit(NameLimit::ForwardPointer { position, offset }) => {
+            format!("Malformed packet: name pointer at {} points forward to {}", position, offset)
+        }
+        WireError::NameLimit(NameLimit::TooLong) => {
+            "Malformed packet: name longer than 255 bytes".into()
+        }
+        WireError::WrongVersion { stated_version, maximum_supported_version } => {
+            format!("Malformed packet: record specifies version {}, expected up to {}", stated_version, maximum_supported_version)
+        }
+    }
+}
+
+
+/// A wrapper around displaying some bytes in lowercase hexadecimal.
+struct Hex<'a>(&'a [u8]);
+
+impl fmt::Display for Hex<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in self.0 {
+            write!(f, "{:02x}", byte)?;
+        }
+
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn escape_quotes() {
+        assert_eq!(Ascii(b"Mallard \"The Duck\" Fillmore").to_string(),
+                   "\"Mallard \\\"The Duck\\\" Fillmore\"");
+    }
+
+    #[test]
+    fn escape_backslashes() {
+        assert_eq!(Ascii(b"\\").to_string(),
+                   "\"\\\\\"");
+    }
+
+    #[test]
+    fn escape_lows() {
+        assert_eq!(Ascii(b"\n\r\t").to_string(),
+                   "\"\\10\\13\\9\"");
+    }
+
+    #[test]
+    fn escape_highs() {
+        assert_eq!(Ascii("pâté".as_bytes()).to_string(),
+                   "\"p\\195\\162t\\195\\169\"");
+    }
+
+    #[test]
+    fn ech_described() {
+        let list = EchConfigList(vec![
+            0x00, 0x1c, 0xfe, 0x0d, 0x00, 0x18, 0x2a, 0x00, 0x20, 0x00, 0x02, 0xab, 0xcd,
+            0x00, 0x04, 0x00, 0x01, 0x00, 0x01, 0x00,
+            0x07, 0x65, 0x78, 0x61, 0x6d, 0x70, 0x6c, 0x65, 0x00, 0x00,
+        ]);
+
+        assert_eq!(ech_summary(&list),
+                   " [config 42: DHKEM(X25519), HKDF-SHA256/AES-128-GCM, public name \"example\"]");
+    }
+
+    #[test]
+    fn ech_undecodable() {
+        let list = EchConfigList(vec![ 0x00, 0x10, 0xfe ]);
+        assert_eq!(ech_summary(&list), "");
+    }
+
+    fn quiet_response(error_code: Option<ErrorCode>, records: Vec<Record>) -> Response {
+        let mut flags = dns::Flags::standard_response();
+        flags.error_code = error_code;
+
+        Response {
+            transaction_id: 0x1234,
+            flags,
+            queries: vec![ Query { qname: Labels::encode("lookup.dog").unwrap(), qclass: QClass::IN, qtype: RecordType::TXT } ],
+            answers: records.into_iter().map(|record| Answer::Standard {
+                qname: Labels::encode("lookup.dog").unwrap(),
+                qclass: QClass::IN,
+                ttl: 300,
+                record,
+            }).collect(),
+            authorities: vec![],
+            additionals: vec![],
+        }
+    }
+
+    #[test]
+    fn quiet_line_joins_answers() {
+        let tf = TextFormat { format_durations: false, coordinates: CoordinateFormat::DegreesMinutesSeconds, max_answers: None, language: None, txt_decoding: None };
+        let records = vec![
+            Record::TXT(dns::record::TXT { messages: vec![ Box::new(*b"one\ttwo") ] }),
+            Record::TXT(dns::record::TXT { messages: vec![ Box::new(*b"three") ] }),
+        ];
+
+        let line = tf.quiet_line(quiet_response(None, records));
+        assert_eq!(line.split('\t').count(), 4);
+        assert!(line.starts_with("NOERROR\tlookup.dog.\tTXT\t"));
+        assert!(! line.contains('\n'));
+    }
+
+    #[test]
+    fn quiet_line_without_answers() {
+        let tf = TextFormat { format_durations: false, coordinates: CoordinateFormat::DegreesMinutesSeconds, max_answers: None, language: None, txt_decoding: None };
+        assert_eq!(tf.quiet_line(quiet_response(Some(ErrorCode::NXDomain), vec![])),
+                   "NXDOMAIN\tlookup.dog.\tTXT\t");
+    }
+
+    #[test]
+    fn quiet_error_line_has_the_same_fields() {
+        let tf = TextFormat { format_durations: false, coordinates: CoordinateFormat::DegreesMinutesSeconds, max_answers: None, language: None, txt_decoding: None };
+        let query = Query { qname: Labels::encode("lookup.dog").unwrap(), qclass: QClass::IN, qtype: RecordType::TXT };
+        let error = object! { "error": true, "error_phase": "network", "error_message": "Connection\nrefused" };
+
+        assert_eq!(tf.quiet_error_line(Some(&query), &error),
+                   "ERROR\tlookup.dog.\tTXT\tnetwork: Connection refused");
+    }
+
+    #[test]
+    fn quiet_error_line_without_query() {
+        let tf = TextFormat { format_durations: false, coordinates: CoordinateFormat::DegreesMinutesSeconds, max_answers: None, language: None, txt_decoding: None };
+        let error = object! { "error": true, "error_phase": "protocol", "error_message": "Malformed packet" };
+
+        assert_eq!(tf.quiet_error_line(None, &error),
+                   "ERROR\t\t\tprotocol: Malformed packet");
+    }
+
+    #[test]
+    fn status_mnemonics() {
+        assert_eq!(status_mnemonic(None), "NOERROR");
+        assert_eq!(status_mnemonic(Some(ErrorCode::ServerFailure)), "SERVFAIL");
+        assert_eq!(status_mnemonic(Some(ErrorCode::BadCookie)), "BADCOOKIE");
+        assert_eq!(status_mnemonic(Some(ErrorCode::Other(12))), "RCODE12");
+    }
+
+    #[test]
+    fn question_lines() {
+        let query = Query { qname: Labels::encode("lookup.dog").unwrap(), qclass: QClass::CH, qtype: RecordType::TXT };
+        assert_eq!(question_line(&query), "lookup.dog. CH TXT");
+    }
+
+    #[test]
+    fn opcode_mnemonics() {
+        assert_eq!(opcode_mnemonic(Opcode::Query), "QUERY");
+        assert_eq!(opcode_mnemonic(Opcode::Update), "UPDATE");
+        assert_eq!(opcode_mnemonic(Opcode::Dso), "DSO");
+        assert_eq!(opcode_mnemonic(Opcode::Other(7)), "OPCODE7");
+    }
+
+    #[test]
+    fn update_sections_in_json() {
+        let mut response = quiet_response(None, vec![ Record::TXT(dns::record::TXT { messages: vec![ Box::new(*b"new") ] }) ]);
+        response.flags.opcode = Opcode::Update;
+        response.authorities = response.answers.split_off(0);
+
+        let json = json_response(response, &[]);
+        assert_eq!(json["opcode"], "UPDATE");
+        assert_eq!(json["zone"][0]["name"], "lookup.dog.");
+        assert_eq!(json["prerequisites"].len(), 0);
+        assert_eq!(json["updates"].len(), 1);
+        assert_eq!(json["rrsets"]["updates"].len(), 1);
+        assert!(json["answers"].is_null());
+        assert!(json["queries"].is_null());
+    }
+
+    #[test]
+    fn query_json_has_no_opcode() {
+        let json = json_response(quiet_response(None, vec![]), &[]);
+        assert!(json["opcode"].is_null());
+        assert!(json["answers"].is_array());
+    }
+
+    #[test]
+    fn zone_lines() {
+        let answer = Answer::Standard {
+            qname: Labels::encode("lookup.dog").unwrap(),
+            qclass: QClass::IN,
+            ttl: 300,
+            record: Record::MX(dns::record::MX { preference: 10, exchange: Labels::encode("mail.lookup.dog").unwrap() }),
+        };
+
+        assert_eq!(zone_line(answer), Some("lookup.dog.\t300\tIN\tMX\t10 mail.lookup.dog.".into()));
+    }
+
+    #[test]
+    fn zone_line_for_unknown_type_and_class() {
+        let answer = Answer::Standard {
+            qname: Labels::root(),
+            qclass: QClass::Other(42),
+            ttl: 0,
+            record: Record::Other { type_number: UnknownQtype::UnheardOf(65280), bytes: vec![ 0x01, 0x02 ] },
+        };
+
+        assert_eq!(zone_line(answer), Some(".\t0\tCLASS42\tTYPE65280\t\\# 2 0102".into()));
+    }
+
+    #[test]
+    fn unknown_record_summary() {
+        let record = Record::Other { type_number: UnknownQtype::UnheardOf(65280), bytes: vec![ 0x0a, 0x00, 0x00, 0xfe ] };
+
+        let tf = TextFormat { format_durations: false, coordinates: CoordinateFormat::DegreesMinutesSeconds, max_answers: None, language: None, txt_decoding: None };
+        assert_eq!(tf.record_payload_summary(record),
+                   "\\# 4 0A0000FE");
+    }
+
+    #[test]
+    fn nsec3_summary() {
+        let record = Record::NSEC3(dns::record::NSEC3 {
+            hash_algorithm: 1,
+            flags: 1,
+            iterations: 0,
+            salt: vec![],
+            next_hashed_owner: b"foobar".to_vec(),
+            types: vec![ 1, 46 ],
+        });
+
+        let tf = TextFormat { format_durations: false, coordinates: CoordinateFormat::DegreesMinutesSeconds, max_answers: None, language: None, txt_decoding: None };
+        assert_eq!(tf.record_payload_summary(record),
+                   "1 1 0 - \"cpnmuoj1e8\" A RRSIG");
+    }
+
+    #[test]
+    fn nsec_summary() {
+        let record = Record::NSEC(dns::record::NSEC {
+            next_domain: Labels::encode("www.lookup.dog").unwrap(),
+            types: vec![ 1, 46, 47 ],
+        });
+
+        let tf = TextFormat { format_durations: false, coordinates: CoordinateFormat::DegreesMinutesSeconds, max_answers: None, language: None, txt_decoding: None };
+        assert_eq!(tf.record_payload_summary(record),
+                   "\"www.lookup.dog.\" A RRSIG NSEC");
+    }
+
+    #[test]
+    fn enum_described() {
+        let qname = Labels::encode("4.3.2.1.5.5.5.0.2.1.4.4.e164.arpa").unwrap();
+        let record = Record::NAPTR(dns::record::NAPTR {
+            order: 100,
+            preference: 10,
+            flags: Box::new(*b"u"),
+            service: Box::new(*b"E2U+voice:tel+sip"),
+            regex: Box::new(*b"!^.*$!tel:+441205551234!"),
+            replacement: Labels::root(),
+        });
+
+        assert_eq!(enum_summary(&qname, &record),
+                   Some(" (ENUM +441205551234: voice call (tel), SIP)".into()));
+    }
+
+    #[test]
+    fn enum_outside_e164_arpa() {
+        let qname = Labels::encode("naptr.example").unwrap();
+        let record = Record::NAPTR(dns::record::NAPTR {
+            order: 100,
+            preference: 10,
+            flags: Box::new(*b"u"),
+            service: Box::new(*b"E2U+sip"),
+            regex: Box::new(*b""),
+            replacement: Labels::root(),
+        });
+
+        assert_eq!(enum_summary(&qname, &record), None);
+    }
+
+    #[test]
+    fn type_names() {
+        assert_eq!(record_type_name(1), "A");
+        assert_eq!(record_type_name(50), "NSEC3");
+        assert_eq!(record_type_name(4444), "4444");
+    }
+
+    #[test]
+    fn extended_error_named() {
+        let error = ExtendedError { info_code: 6, extra_text: b"validation failed".to_vec() };
+        assert_eq!(extended_error_summary(&error),
+                   "Extended error: DNSSEC Bogus (6): \"validation failed\"");
+    }
+
+    #[test]
+    fn extended_error_unknown() {
+        let error = ExtendedError { info_code: 4000, extra_text: vec![] };
+        assert_eq!(extended_error_summary(&error),
+                   "Extended error: Unknown (4000)");
+    }
+
+    #[test]
+    fn nsid_summary() {
+        let option = EdnsOption::Nsid(dns::record::Nsid(b"lon1".to_vec()));
+        assert_eq!(edns_option_summary(&option),
+                   Some("NSID: 6c6f6e31 (\"lon1\")".into()));
+    }
+
+    #[test]
+    fn expire_summary() {
+        let option = EdnsOption::Expire(dns::record::Expire { timer: Some(90061) });
+        assert_eq!(edns_option_summary(&option),
+                   Some("Expire: zone expires in 1d1h01m01s (90061 seconds)".into()));
+    }
+
+    #[test]
+    fn cookie_summary() {
+        let option = EdnsOption::Cookie(dns::record::Cookie { client: [ 0x01; 8 ], server: vec![ 0x02; 8 ] });
+        assert_eq!(edns_option_summary(&option),
+                   Some("Cookie: server 0202020202020202 (client 0101010101010101)".into()));
+    }
+
+    #[test]
+    fn cookie_summary_without_server() {
+        let option = EdnsOption::Cookie(dns::record::Cookie::new([ 0x01; 8 ]));
+        assert_eq!(edns_option_summary(&option),
+                   Some("Cookie: no server cookie (client 0101010101010101)".into()));
+    }
+
+    #[test]
+    fn client_subnet_summary() {
+        let ecs = dns::record::ClientSubnet { family: 1, source_prefix: 24, scope_prefix: 16, address: vec![ 192, 0, 2 ] };
+        assert_eq!(edns_option_summary(&EdnsOption::ClientSubnet(ecs)),
+                   Some("Client subnet: 192.0.2.0/24, scope /16".into()));
+    }
+
+    #[test]
+    fn tcp_keepalive_summary() {
+        let option = EdnsOption::TcpKeepalive(dns::record::TcpKeepalive { timeout: Some(125) });
+        assert_eq!(edns_option_summary(&option),
+                   Some("TCP keepalive: idle timeout 12.5s".into()));
+    }
+
+    #[test]
+    fn chain_summary() {
+        let option = EdnsOption::Chain(dns::record::Chain { trust_point: dns::Labels::encode("dog").unwrap() });
+        assert_eq!(edns_option_summary(&option),
+                   Some("Chain: from trust point dog.".into()));
+    }
+
+    #[test]
+    fn chain_from_root_summary() {
+        let option = EdnsOption::Chain(dns::record::Chain { trust_point: dns::Labels::root() });
+        assert_eq!(edns_option_summary(&option),
+                   Some("Chain: from the root".into()));
+    }
+
+    #[test]
+    fn report_channel_summary() {
+        let option = EdnsOption::ReportChannel(dns::record::ReportChannel { agent_domain: dns::Labels::encode("agent.dog").unwrap() });
+        assert_eq!(edns_option_summary(&option),
+                   Some("Report channel: agent domain agent.dog.".into()));
+    }
+
+    #[test]
+    fn other_option_summary() {
+        let option = EdnsOption::Other { code: 20292, data: vec![ 0x0a, 0x00, 0xff ] };
+        assert_eq!(edns_option_summary(&option),
+                   Some("Option 20292 (Umbrella Ident): 0a00ff".into()));
+    }
+
+    #[test]
+    fn unknown_option_summary() {
+        let option = EdnsOption::Other { code: 65001, data: vec![] };
+        assert_eq!(edns_option_summary(&option),
+                   Some("Option 65001 (unknown): empty".into()));
+    }
+
+    #[test]
+    fn compact_json() {
+        let value = object! { "one": 1, "two": [ true, JsonValue::Null ] };
+        assert_eq!(JsonFormat::Compact.render(&value, &Colours::plain()),
+                   "{\"one\":1,\"two\":[true,null]}");
+    }
+
+    #[test]
+    fn pretty_json() {
+        let value = object! { "one": 1, "two": [ "three", { "four": JsonValue::Null } ], "five": [], "six": {} };
+        assert_eq!(JsonFormat::Pretty(UseColours::Never).render(&value, &Colours::plain()),
+                   "{\n  \"one\": 1,\n  \"two\": [\n    \"three\",\n    {\n      \"four\": null\n    }\n  ],\n  \"five\": [],\n  \"six\": {}\n}");
+    }
+
+    #[test]
+    fn pretty_json_coloured() {
+        let value = object! { "one": 1 };
+        let colours = Colours::pretty();
+        assert_eq!(JsonFormat::Pretty(UseColours::Always).render(&value, &colours),
+                   format!("{{\n  {}: {}\n}}", colours.json_key.paint("\"one\""), colours.json_number.paint("1")));
+    }
+}
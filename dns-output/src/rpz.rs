@@ -0,0 +1,301 @@
+//! Interpreting the records in a response policy zone (RPZ) as the rules
+//! they encode, for inspecting the zones that filtering resolvers load
+//! their blocklists from.
+//!
+//! An RPZ is an ordinary zone whose owner names say what to match and
+//! whose records say what to do about it. Owner names under special labels
+//! such as `rpz-ip` match on addresses rather than on query names, and
+//! `CNAME` records pointing at special targets such as `.` or
+//! `rpz-passthru.` stand for actions rather than for other names.
+//!
+//! # References
+//!
+//! - [draft-vixie-dnsop-dns-rpz](https://tools.ietf.org/html/draft-vixie-dnsop-dns-rpz-00) —
+//!   DNS Response Policy Zones (June 2017)
+
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use dns::{Answer, Labels};
+use dns::record::Record;
+
+
+/// One rule from a response policy zone, which is made from one record.
+#[derive(PartialEq, Debug)]
+pub struct PolicyRule {
+
+    /// What a query or response has to match for the rule to apply.
+    pub trigger: Trigger,
+
+    /// What the resolver does when the rule applies.
+    pub action: Action,
+}
+
+/// The part of a query or response that a rule matches on, which is
+/// encoded in the owner name of its record.
+#[derive(PartialEq, Debug)]
+pub enum Trigger {
+
+    /// The name being queried, which may be a wildcard.
+    QName(String),
+
+    /// An address in the answer to the query.
+    ResponseIp(IpAddr, u8),
+
+    /// The address of the client that sent the query.
+    ClientIp(IpAddr, u8),
+
+    /// The name of a nameserver for the domain being queried.
+    NsDname(String),
+
+    /// The address of a nameserver for the domain being queried.
+    NsIp(IpAddr, u8),
+}
+
+/// What a resolver does when a rule applies, which is encoded in the data
+/// of its record.
+#[derive(PartialEq, Debug)]
+pub enum Action {
+
+    /// Answer that the domain does not exist.
+    NxDomain,
+
+    /// Answer that the domain exists but has no records of the type asked
+    /// for.
+    NoData,
+
+    /// Answer as normal, exempting the query from any rules after this one.
+    Passthru,
+
+    /// Send no answer at all.
+    Drop,
+
+    /// Answer UDP queries with a truncated response, so only clients that
+    /// retry over TCP get an answer.
+    TcpOnly,
+
+    /// Answer with this record instead of the real data.
+    LocalData(Record),
+}
+
+
+/// Interprets the records in the given answers as rules in the response
+/// policy zone with the given name. Records outside the zone, and the
+/// records at its apex, such as its `SOA` and `NS` records, are not rules,
+/// and get skipped.
+pub fn rules(zone: &Labels, answers: Vec<Answer>) -> Vec<PolicyRule> {
+    let zone = zone.to_string().to_ascii_lowercase();
+
+    answers.into_iter().filter_map(|answer| match answer {
+        Answer::Standard { qname, record, .. } => {
+            let trigger = trigger(&zone, &qname)?;
+            Some(PolicyRule { trigger, action: action(record) })
+        }
+        Answer::Pseudo { .. } => None,
+    }).collect()
+}
+
+/// Decodes the trigger in an owner name, relative to the zone name.
+fn trigger(zone: &str, owner: &Labels) -> Option<Trigger> {
+    let owner = owner.to_string().to_ascii_lowercase();
+    let relative = owner.strip_suffix(zone)?.strip_suffix('.')?;
+
+    if let Some(network) = relative.strip_suffix(".rpz-ip") {
+        let (address, prefix) = network_from_labels(network)?;
+        Some(Trigger::ResponseIp(address, prefix))
+    }
+    else if let Some(network) = relative.strip_suffix(".rpz-client-ip") {
+        let (address, prefix) = network_from_labels(network)?;
+        Some(Trigger::ClientIp(address, prefix))
+    }
+    else if let Some(network) = relative.strip_suffix(".rpz-nsip") {
+        let (address, prefix) = network_from_labels(network)?;
+        Some(Trigger::NsIp(address, prefix))
+    }
+    else if let Some(name) = relative.strip_suffix(".rpz-nsdname") {
+        Some(Trigger::NsDname(name.into()))
+    }
+    else {
+        Some(Trigger::QName(relative.into()))
+    }
+}
+
+/// Decodes a network from the labels that an RPZ uses to encode it: the
+/// prefix length, followed by the parts of the address in reverse order.
+/// IPv4 addresses have four decimal octets, and IPv6 addresses have
+/// hexadecimal groups with `zz` standing in for the longest run of zeroes.
+fn network_from_labels(labels: &str) -> Option<(IpAddr, u8)> {
+    let mut parts = labels.split('.');
+    let prefix = parts.next()?.parse::<u8>().ok()?;
+    let mut parts = parts.collect::<Vec<_>>();
+    parts.reverse();
+
+    if parts.len() == 4 && prefix <= 32 {
+        if let Ok(address) = parts.join(".").parse::<Ipv4Addr>() {
+            return Some((IpAddr::V4(address), prefix));
+        }
+    }
+
+    if prefix > 128 {
+        return None;
+    }
+
+    let mut address = parts.iter()
+        .map(|part| if *part == "zz" { "" } else { part })
+        .collect::<Vec<_>>()
+        .join(":");
+
+    if address.starts_with(':') {
+        address.insert(0, ':');
+    }
+
+    if address.ends_with(':') {
+        address.push(':');
+    }
+
+    let address = address.parse::<Ipv6Addr>().ok()?;
+    Some((IpAddr::V6(address), prefix))
+}
+
+/// Decodes the action in the data of a record. `CNAME` records with the
+/// special targets stand for actions, and everything else is local data.
+fn action(record: Record) -> Action {
+    if let Record::CNAME(cname) = &record {
+        match cname.domain.to_string().to_ascii_lowercase().as_str() {
+            ""                => return Action::NxDomain,   // the root
+            "*."              => return Action::NoData,
+            "rpz-passthru."   => return Action::Passthru,
+            "rpz-drop."       => return Action::Drop,
+            "rpz-tcp-only."   => return Action::TcpOnly,
+            _                 => {},
+        }
+    }
+
+    Action::LocalData(record)
+}
+
+
+impl fmt::Display for Trigger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::QName(name)                 => write!(f, "QNAME {}", name),
+            Self::ResponseIp(address, prefix) => write!(f, "IP {}/{}", address, prefix),
+            Self::ClientIp(address, prefix)   => write!(f, "CLIENT-IP {}/{}", address, prefix),
+            Self::NsDname(name)               => write!(f, "NSDNAME {}", name),
+            Self::NsIp(address, prefix)       => write!(f, "NSIP {}/{}", address, prefix),
+        }
+    }
+}
+
+impl Action {
+
+    /// The name of this action, as RPZ documentation refers to it.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::NxDomain      => "NXDOMAIN",
+            Self::NoData        => "NODATA",
+            Self::Passthru      => "PASSTHRU",
+            Self::Drop          => "DROP",
+            Self::TcpOnly       => "TCP-ONLY",
+            Self::LocalData(_)  => "LOCAL-DATA",
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use dns::QClass;
+    use dns::record::{A, CNAME};
+    use pretty_assertions::assert_eq;
+
+    fn answer(qname: &str, record: Record) -> Answer {
+        Answer::Standard {
+            qname: Labels::encode(qname).unwrap(),
+            qclass: QClass::IN,
+            ttl: 300,
+            record,
+        }
+    }
+
+    fn cname(target: &str) -> Record {
+        let domain = if target == "." { Labels::root() } else { Labels::encode(target).unwrap() };
+        Record::CNAME(CNAME { domain })
+    }
+
+    fn zone() -> Labels {
+        Labels::encode("rpz.example").unwrap()
+    }
+
+    #[test]
+    fn qname_nxdomain() {
+        assert_eq!(rules(&zone(), vec![ answer("bad.example.rpz.example", cname(".")) ]),
+                   vec![ PolicyRule { trigger: Trigger::QName("bad.example".into()), action: Action::NxDomain } ]);
+    }
+
+    #[test]
+    fn wildcard_nodata() {
+        assert_eq!(rules(&zone(), vec![ answer("*.bad.example.rpz.example", cname("*")) ]),
+                   vec![ PolicyRule { trigger: Trigger::QName("*.bad.example".into()), action: Action::NoData } ]);
+    }
+
+    #[test]
+    fn response_ipv4_drop() {
+        assert_eq!(rules(&zone(), vec![ answer("24.0.2.0.192.rpz-ip.rpz.example", cname("rpz-drop")) ]),
+                   vec![ PolicyRule { trigger: Trigger::ResponseIp("192.0.2.0".parse().unwrap(), 24), action: Action::Drop } ]);
+    }
+
+    #[test]
+    fn client_ipv6_passthru() {
+        assert_eq!(rules(&zone(), vec![ answer("48.zz.db8.2001.rpz-client-ip.rpz.example", cname("rpz-passthru")) ]),
+                   vec![ PolicyRule { trigger: Trigger::ClientIp("2001:db8::".parse().unwrap(), 48), action: Action::Passthru } ]);
+    }
+
+    #[test]
+    fn nsip_ipv6_in_the_middle() {
+        assert_eq!(rules(&zone(), vec![ answer("128.1.zz.db8.2001.rpz-nsip.rpz.example", cname("rpz-tcp-only")) ]),
+                   vec![ PolicyRule { trigger: Trigger::NsIp("2001:db8::1".parse().unwrap(), 128), action: Action::TcpOnly } ]);
+    }
+
+    #[test]
+    fn nsdname_local_data() {
+        let record = Record::A(A { address: "198.51.100.1".parse().unwrap() });
+        assert_eq!(rules(&zone(), vec![ answer("ns.evil.example.rpz-nsdname.rpz.example", record) ]),
+                   vec![ PolicyRule {
+                       trigger: Trigger::NsDname("ns.evil.example".into()),
+                       action: Action::LocalData(Record::A(A { address: "198.51.100.1".parse().unwrap() })),
+                   } ]);
+    }
+
+    #[test]
+    fn redirect_is_local_data() {
+        assert_eq!(rules(&zone(), vec![ answer("bad.example.rpz.example", cname("walled.garden")) ]),
+                   vec![ PolicyRule {
+                       trigger: Trigger::QName("bad.example".into()),
+                       action: Action::LocalData(cname("walled.garden")),
+                   } ]);
+    }
+
+    #[test]
+    fn apex_and_other_zones_skipped() {
+        assert_eq!(rules(&zone(), vec![
+                       answer("rpz.example", cname(".")),
+                       answer("bad.example.other.example", cname(".")),
+                       answer("notrpz.example", cname(".")),
+                   ]),
+                   vec![]);
+    }
+
+    #[test]
+    fn invalid_network_skipped() {
+        assert_eq!(rules(&zone(), vec![ answer("99.0.2.0.192.rpz-ip.rpz.example", cname(".")) ]),
+                   vec![]);
+    }
+
+    #[test]
+    fn trigger_display() {
+        assert_eq!(Trigger::ResponseIp("192.0.2.0".parse().unwrap(), 24).to_string(),
+                   "IP 192.0.2.0/24");
+    }
+}
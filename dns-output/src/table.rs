@@ -0,0 +1,618 @@
+//! Rendering tables of DNS response results.
+
+use std::fmt::Write;
+use std::time::Duration;
+
+use ansi_term::ANSIString;
+
+use dns::{Answer, Opcode, QClass};
+use dns::record::Record;
+
+use crate::colours::Colours;
+use crate::messages::Message;
+use crate::output::{self, TextFormat};
+
+
+/// A **table** is built up from all the response records present in a DNS
+/// packet. It then gets displayed to the user.
+#[derive(Debug)]
+pub struct Table {
+    colours: Colours,
+    text_format: TextFormat,
+    rows: Vec<Row>,
+}
+
+/// A row of the table. This contains all the fields
+#[derive(Debug)]
+pub struct Row {
+    qtype: ANSIString<'static>,
+    qname: String,
+    qclass: Option<QClass>,
+    ttl: Option<String>,
+    section: Section,
+    summary: String,
+    covers: Option<String>,
+    nameserver: Option<String>,
+}
+
+/// A line of the printed table.
+#[derive(Debug)]
+enum Line<'table> {
+
+    /// A row for a record. If it is not the first in its record set, it is
+    /// paired with the first row, which acts as the set’s header.
+    Record(&'table Row, Option<&'table Row>),
+
+    /// A row for an address record in the additional section that is the
+    /// glue for the nameserver in the NS record above it.
+    Glue(&'table Row),
+
+    /// A note saying how many records from the last record set were not
+    /// displayed, because it had more than the maximum.
+    More(Section, usize),
+}
+
+/// The section of the DNS response that a record was read from.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum Section {
+
+    /// This record was found in the **Answer** section.
+    Answer,
+
+    /// This record was found in the **Authority** section.
+    Authority,
+
+    /// This record was found in the **Additional** section.
+    Additional,
+
+    /// This record was found in the answer section of an UPDATE message,
+    /// which holds the **Prerequisites** for the update.
+    Prerequisite,
+
+    /// This record was found in the authority section of an UPDATE
+    /// message, which holds the **Updates** to make.
+    Update,
+}
+
+
+impl Section {
+
+    /// Returns the sections that the records in the answer and authority
+    /// sections of a message with the given opcode belong in.
+    pub fn for_opcode(opcode: Opcode) -> (Self, Self) {
+        match opcode {
+            Opcode::Update  => (Self::Prerequisite, Self::Update),
+            _               => (Self::Answer, Self::Authority),
+        }
+    }
+}
+
+
+impl Row {
+
+    /// Returns the name of the row’s class for displaying, if it is a class
+    /// other than IN. Unknown classes use the generic ‘CLASS’ form.
+    fn class_name(&self) -> Option<String> {
+        match self.qclass? {
+            QClass::IN        => None,
+            QClass::CH        => Some("CH".into()),
+            QClass::HS        => Some("HS".into()),
+            QClass::Other(n)  => Some(format!("CLASS{}", n)),
+        }
+    }
+
+    /// Whether this row is for a record in the same record set as another row.
+    fn is_same_rrset(&self, other: &Self) -> bool {
+        self.section == other.section
+            && self.qclass.is_some() && self.qclass == other.qclass
+            && *self.qtype == *other.qtype
+            && self.qname.eq_ignore_ascii_case(&other.qname)
+    }
+
+    /// Whether this row is for a record in the set covered by the given
+    /// RRSIG row.
+    fn is_covered_by(&self, signature: &Self) -> bool {
+        signature.covers.as_deref() == Some(&*self.qtype)
+            && self.section == signature.section
+            && self.qclass.is_some() && self.qclass == signature.qclass
+            && self.qname.eq_ignore_ascii_case(&signature.qname)
+    }
+
+    /// Whether this row is for an address record in the additional section
+    /// for the nameserver in the given NS row, making it glue for it.
+    fn is_glue_for(&self, ns: &Self) -> bool {
+        self.section == Section::Additional
+            && (*self.qtype == *"A" || *self.qtype == *"AAAA")
+            && self.qclass.is_some() && self.qclass == ns.qclass
+            && ns.nameserver.as_ref().map_or(false, |name| self.qname.eq_ignore_ascii_case(name))
+    }
+}
+
+
+impl Table {
+
+    /// Create a new table with no rows.
+    pub fn new(colours: Colours, text_format: TextFormat) -> Self {
+        Self { colours, text_format, rows: Vec::new() }
+    }
+
+    /// Adds a row to the table, containing the data in the given answer in
+    /// the right section.
+    pub fn add_row(&mut self, answer: Answer, section: Section) {
+        match answer {
+            Answer::Standard { record, qname, qclass, ttl } => {
+                let qtype = self.coloured_record_type(&record);
+                let enum_note = output::enum_summary(&qname, &record);
+                let covers = match &record {
+                    Record::RRSIG(rrsig)  => Some(output::record_type_name(rrsig.type_covered)),
+                    _                     => None,
+                };
+                let nameserver = match &record {
+                    Record::NS(ns)  => Some(ns.nameserver.to_string()),
+                    _               => None,
+                };
+                let qname = qname.to_string();
+                let summary = self.text_format.record_payload_summary(record) + &enum_note.unwrap_or_default();
+                let ttl = Some(self.text_format.format_duration(ttl));
+                self.rows.push(Row { qtype, qname, qclass: Some(qclass), ttl, summary, section, covers, nameserver });
+            }
+            Answer::Pseudo { qname, opt } => {
+                let qtype = self.colours.opt.paint("OPT");
+                let qname = qname.to_string();
+                let summary = self.text_format.pseudo_record_payload_summary(opt);
+                self.rows.push(Row { qtype, qname, qclass: None, ttl: None, summary, section, covers: None, nameserver: None });
+            }
+        }
+    }
+
+    /// Prints the formatted table to stdout.
+    pub fn print(self, duration: Option<Duration>) {
+        print!("{}", self.render(duration));
+    }
+
+    /// Returns the formatted table as it gets printed, with a newline at
+    /// the end of each line.
+    pub fn render(self, duration: Option<Duration>) -> String {
+        let mut out = String::new();
+
+        if ! self.rows.is_empty() {
+            let qtype_len = self.max_qtype_len();
+            let qname_len = self.max_qname_len();
+            let ttl_len   = self.max_ttl_len();
+            let class_len = self.max_class_len();
+
+            for line in self.lines() {
+                let (r, header) = match line {
+                    Line::Record(r, header) => (r, header),
+                    Line::Glue(r) => {
+                        let indent = qtype_len + qname_len + class_len + 2;
+                        pad(&mut out, indent);
+
+                        self.write_ttl(&mut out, r.ttl.as_ref(), ttl_len);
+                        let _ = writeln!(out, " {} └ {} {}", self.format_section(r.section), r.qtype, r.summary);
+                        continue;
+                    }
+                    Line::More(section, count) => {
+                        let indent = qtype_len + qname_len + class_len + ttl_len + 2;
+                        pad(&mut out, indent);
+
+                        let _ = writeln!(out, " {} {}", self.format_section(section), self.text_format.language().fill(Message::AndMore, count));
+                        continue;
+                    }
+                };
+
+                // Records after the first in a record set leave the type and
+                // name columns blank, and only show their TTL if it differs
+                // from the first record’s.
+                if let Some(header) = header {
+                    let indent = qtype_len + qname_len + class_len + 2;
+                    pad(&mut out, indent);
+
+                    let ttl = r.ttl.as_ref().filter(|ttl| Some(*ttl) != header.ttl.as_ref());
+                    self.write_ttl(&mut out, ttl, ttl_len);
+                }
+                else {
+                    pad(&mut out, qtype_len - r.qtype.len());
+                    let _ = write!(out, "{} {} ", r.qtype, self.colours.qname.paint(&r.qname));
+                    pad(&mut out, qname_len - r.qname.len());
+
+                    if class_len > 0 {
+                        let class = r.class_name().unwrap_or_default();
+                        out.push_str(&class);
+                        pad(&mut out, class_len - class.len());
+                    }
+
+                    self.write_ttl(&mut out, r.ttl.as_ref(), ttl_len);
+                }
+
+                let _ = writeln!(out, " {} {}", self.format_section(r.section), r.summary);
+            }
+        }
+
+        if let Some(dur) = duration {
+            let _ = writeln!(out, "{}", self.text_format.language().fill(Message::RanIn, dur.as_millis()));
+        }
+
+        out
+    }
+
+    fn coloured_record_type(&self, record: &Record) -> ANSIString<'static> {
+        match *record {
+            Record::A(_)           => self.colours.a.paint("A"),
+            Record::AAAA(_)        => self.colours.aaaa.paint("AAAA"),
+            Record::AMTRELAY(_)    => self.colours.amtrelay.paint("AMTRELAY"),
+            Record::CAA(_)         => self.colours.caa.paint("CAA"),
+            Record::CNAME(_)       => self.colours.cname.paint("CNAME"),
+            Record::DLV(_)         => self.colours.dlv.paint("DLV"),
+            Record::DNSKEY(_)      => self.colours.dnskey.paint("DNSKEY"),
+            Record::DOA(_)         => self.colours.doa.paint("DOA"),
+            Record::DS(_)          => self.colours.ds.paint("DS"),
+            Record::EUI48(_)       => self.colours.eui48.paint("EUI48"),
+            Record::EUI64(_)       => self.colours.eui64.paint("EUI64"),
+            Record::HINFO(_)       => self.colours.hinfo.paint("HINFO"),
+            Record::HIP(_)         => self.colours.hip.paint("HIP"),
+            Record::HTTPS(_)       => self.colours.https.paint("HTTPS"),
+            Record::LOC(_)         => self.colours.loc.paint("LOC"),
+            Record::MX(_)          => self.colours.mx.paint("MX"),
+            Record::NAPTR(_)       => self.colours.ns.paint("NAPTR"),
+            Record::NS(_)          => self.colours.ns.paint("NS"),
+            Record::NSEC(_)        => self.colours.nsec3.paint("NSEC"),
+            Record::NSEC3(_)       => self.colours.nsec3.paint("NSEC3"),
+            Record::NSEC3PARAM(_)  => self.colours.nsec3.paint("NSEC3PARAM"),
+            Record::OPENPGPKEY(_)  => self.colours.openpgpkey.paint("OPENPGPKEY"),
+            Record::PTR(_)         => self.colours.ptr.paint("PTR"),
+            Record::RRSIG(_)       => self.colours.rrsig.paint("RRSIG"),
+            Record::SSHFP(_)       => self.colours.sshfp.paint("SSHFP"),
+            Record::SOA(_)         => self.colours.soa.paint("SOA"),
+            Record::SRV(_)         => self.colours.srv.paint("SRV"),
+            Record::SVCB(_)        => self.colours.svcb.paint("SVCB"),
+            Record::TA(_)          => self.colours.ta.paint("TA"),
+            Record::TLSA(_)        => self.colours.tlsa.paint("TLSA"),
+            Record::TXT(_)         => self.colours.txt.paint("TXT"),
+            Record::URI(_)         => self.colours.uri.paint("URI"),
+
+            Record::Other { ref type_number, .. } => self.colours.unknown.paint(type_number.to_string()),
+        }
+    }
+
+    fn write_ttl(&self, out: &mut String, ttl: Option<&String>, ttl_len: usize) {
+        if let Some(ttl) = ttl {
+            pad(out, ttl_len - ttl.len());
+            out.push_str(ttl);
+        }
+        else {
+            pad(out, ttl_len);
+        }
+    }
+
+    /// Returns the rows grouped into record sets, which are the records in
+    /// the same section with the same name, type, and class. Each set appears
+    /// where its first record did. Every row after the first in a set is
+    /// paired with the first row, which acts as the set’s header.
+    ///
+    /// RRSIG records are moved to directly beneath the set they cover, where
+    /// it is present, and grouped with the other signatures over that set.
+    fn grouped_rows(&self) -> Vec<(&Row, Option<&Row>)> {
+        let mut grouped = Vec::with_capacity(self.rows.len());
+        let mut used = vec![false; self.rows.len()];
+
+        for (i, row) in self.rows.iter().enumerate() {
+            if row.covers.is_some() && self.rows.iter().any(|r| r.is_covered_by(row)) {
+                used[i] = true;
+            }
+        }
+
+        for (i, header) in self.rows.iter().enumerate() {
+            if used[i] {
+                continue;
+            }
+
+            used[i] = true;
+            grouped.push((header, None));
+
+            if header.qclass.is_none() {
+                continue;
+            }
+
+            for (j, row) in self.rows.iter().enumerate().skip(i + 1) {
+                if ! used[j] && row.is_same_rrset(header) {
+                    used[j] = true;
+                    grouped.push((row, Some(header)));
+                }
+            }
+
+            if header.covers.is_some() {
+                continue;
+            }
+
+            let mut signatures = self.rows.iter().filter(|r| header.is_covered_by(r));
+            if let Some(first) = signatures.next() {
+                grouped.push((first, None));
+                grouped.extend(signatures.map(|r| (r, Some(first))));
+            }
+        }
+
+        grouped
+    }
+
+    /// Returns the lines to print, which are the grouped rows, with any
+    /// record set longer than the maximum cut short and followed by a note
+    /// saying how many of its records were left out.
+    ///
+    /// The glue for each NS record that gets shown is nested beneath it,
+    /// rather than being left in the additional section, where it would
+    /// take some matching up by eye to see which nameserver it belongs to.
+    fn lines(&self) -> Vec<Line<'_>> {
+        let mut lines = Vec::new();
+        let mut shown = 0;
+        let mut hidden = 0;
+        let mut section = Section::Answer;
+
+        for (r, header) in self.grouped_rows() {
+            let is_nested = lines.iter().any(|line| matches!(line, Line::Glue(glue) if std::ptr::eq(*glue, r)));
+            if is_nested {
+                continue;
+            }
+
+            if header.is_none() {
+                if hidden > 0 {
+                    lines.push(Line::More(section, hidden));
+                }
+
+                shown = 0;
+                hidden = 0;
+                section = r.section;
+            }
+
+            if self.text_format.max_answers.map_or(false, |max| shown >= max) {
+                hidden += 1;
+            }
+            else {
+                shown += 1;
+                lines.push(Line::Record(r, header));
+                lines.extend(self.rows.iter().filter(|glue| glue.is_glue_for(r)).map(Line::Glue));
+            }
+        }
+
+        if hidden > 0 {
+            lines.push(Line::More(section, hidden));
+        }
+
+        lines
+    }
+
+    fn max_qtype_len(&self) -> usize {
+        self.rows.iter().map(|r| r.qtype.len()).max().unwrap()
+    }
+
+    fn max_qname_len(&self) -> usize {
+        self.rows.iter().map(|r| r.qname.len()).max().unwrap()
+    }
+
+    /// Returns the width of the class column, which is only shown, with a
+    /// space after it, when there are records in a class other than IN.
+    fn max_class_len(&self) -> usize {
+        self.rows.iter().filter_map(Row::class_name).map(|class| class.len() + 1).max().unwrap_or(0)
+    }
+
+    fn max_ttl_len(&self) -> usize {
+        self.rows.iter().map(|r| r.ttl.as_ref().map_or(0, String::len)).max().unwrap()
+    }
+
+    fn format_section(&self, section: Section) -> ANSIString<'static> {
+        match section {
+            Section::Answer        => self.colours.answer.paint(" "),
+            Section::Authority     => self.colours.authority.paint("A"),
+            Section::Additional    => self.colours.additional.paint("+"),
+            Section::Prerequisite  => self.colours.answer.paint("P"),
+            Section::Update        => self.colours.authority.paint("U"),
+        }
+    }
+}
+
+/// Adds the given number of spaces to the end of the output.
+fn pad(out: &mut String, count: usize) {
+    for _ in 0 .. count {
+        out.push(' ');
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ansi_term::Style;
+    use crate::messages::Language;
+    use crate::output::CoordinateFormat;
+    use pretty_assertions::assert_eq;
+
+    fn row(qtype: &'static str, qname: &str, section: Section, summary: &str) -> Row {
+        Row {
+            qtype: Style::default().paint(qtype),
+            qname: qname.into(),
+            qclass: Some(QClass::IN),
+            ttl: Some("5m00s".into()),
+            section,
+            summary: summary.into(),
+            covers: None,
+            nameserver: None,
+        }
+    }
+
+    fn ns(qname: &str, nameserver: &str, section: Section) -> Row {
+        Row {
+            nameserver: Some(nameserver.into()),
+            .. row("NS", qname, section, nameserver)
+        }
+    }
+
+    fn signature(qname: &str, covers: &str, summary: &str) -> Row {
+        Row {
+            covers: Some(covers.into()),
+            .. row("RRSIG", qname, Section::Answer, summary)
+        }
+    }
+
+    fn table(max_answers: Option<usize>) -> Table {
+        let text_format = TextFormat {
+            format_durations: true,
+            coordinates: CoordinateFormat::DegreesMinutesSeconds,
+            max_answers,
+            language: Some(Language::English),
+            txt_decoding: None,
+        };
+
+        Table::new(Colours::plain(), text_format)
+    }
+
+    fn line_summaries(lines: Vec<Line<'_>>) -> Vec<String> {
+        lines.into_iter().map(|line| match line {
+            Line::Record(r, _)          => r.summary.clone(),
+            Line::Glue(r)               => format!("└ {}", r.summary),
+            Line::More(section, count)  => format!("{:?} {}", section, count),
+        }).collect()
+    }
+
+    fn summaries<'a>(grouped: Vec<(&'a Row, Option<&'a Row>)>) -> Vec<(&'a str, Option<&'a str>)> {
+        grouped.into_iter()
+               .map(|(r, header)| (r.summary.as_str(), header.map(|h| h.summary.as_str())))
+               .collect()
+    }
+
+    #[test]
+    fn groups_rrsets() {
+        let mut table = table(None);
+        table.rows.push(row("A",    "lookup.dog.", Section::Answer, "1"));
+        table.rows.push(row("AAAA", "lookup.dog.", Section::Answer, "2"));
+        table.rows.push(row("A",    "LOOKUP.dog.", Section::Answer, "3"));
+        table.rows.push(row("A",    "lookup.dog.", Section::Additional, "4"));
+
+        assert_eq!(summaries(table.grouped_rows()), vec![
+            ("1", None),
+            ("3", Some("1")),
+            ("2", None),
+            ("4", None),
+        ]);
+    }
+
+    #[test]
+    fn signatures_beneath_rrsets() {
+        let mut table = table(None);
+        table.rows.push(signature("lookup.dog.", "AAAA", "sig aaaa"));
+        table.rows.push(signature("lookup.dog.", "A", "sig a 1"));
+        table.rows.push(row("A",    "lookup.dog.", Section::Answer, "a 1"));
+        table.rows.push(row("AAAA", "lookup.dog.", Section::Answer, "aaaa"));
+        table.rows.push(signature("LOOKUP.dog.", "A", "sig a 2"));
+        table.rows.push(row("A",    "lookup.dog.", Section::Answer, "a 2"));
+
+        assert_eq!(summaries(table.grouped_rows()), vec![
+            ("a 1", None),
+            ("a 2", Some("a 1")),
+            ("sig a 1", None),
+            ("sig a 2", Some("sig a 1")),
+            ("aaaa", None),
+            ("sig aaaa", None),
+        ]);
+    }
+
+    #[test]
+    fn signatures_without_rrsets() {
+        let mut table = table(None);
+        table.rows.push(signature("lookup.dog.", "MX", "sig mx"));
+        table.rows.push(row("A", "lookup.dog.", Section::Answer, "a"));
+        table.rows.push(signature("lookup.dog.", "A", "sig a"));
+        table.rows.push(signature("lookup.dog.", "NS", "sig ns"));
+
+        assert_eq!(summaries(table.grouped_rows()), vec![
+            ("sig mx", None),
+            ("sig ns", Some("sig mx")),
+            ("a", None),
+            ("sig a", None),
+        ]);
+    }
+
+    #[test]
+    fn limits_rrsets() {
+        let mut table = table(Some(2));
+        table.rows.push(row("A",    "lookup.dog.", Section::Answer, "a 1"));
+        table.rows.push(row("A",    "lookup.dog.", Section::Answer, "a 2"));
+        table.rows.push(row("A",    "lookup.dog.", Section::Answer, "a 3"));
+        table.rows.push(row("AAAA", "lookup.dog.", Section::Answer, "aaaa"));
+        table.rows.push(row("A",    "lookup.dog.", Section::Answer, "a 4"));
+        table.rows.push(row("NS",   "lookup.dog.", Section::Authority, "ns 1"));
+        table.rows.push(row("NS",   "lookup.dog.", Section::Authority, "ns 2"));
+        table.rows.push(row("NS",   "lookup.dog.", Section::Authority, "ns 3"));
+
+        assert_eq!(line_summaries(table.lines()), vec![
+            "a 1", "a 2", "Answer 2",
+            "aaaa",
+            "ns 1", "ns 2", "Authority 1",
+        ]);
+    }
+
+    #[test]
+    fn unlimited_rrsets() {
+        let mut table = table(None);
+        for _ in 0 .. 50 {
+            table.rows.push(row("TXT", "lookup.dog.", Section::Answer, "txt"));
+        }
+
+        assert_eq!(table.lines().len(), 50);
+    }
+
+    #[test]
+    fn no_class_column_for_internet() {
+        let mut table = table(None);
+        table.rows.push(row("A", "lookup.dog.", Section::Answer, "a"));
+        table.rows.push(Row { qclass: None, .. row("OPT", ".", Section::Additional, "opt") });
+
+        assert_eq!(table.max_class_len(), 0);
+    }
+
+    #[test]
+    fn class_column() {
+        let mut table = table(None);
+        table.rows.push(row("A", "lookup.dog.", Section::Answer, "a"));
+        table.rows.push(Row { qclass: Some(QClass::CH), .. row("TXT", "version.bind.", Section::Answer, "txt") });
+        table.rows.push(Row { qclass: Some(QClass::Other(254)), .. row("TXT", "lookup.dog.", Section::Answer, "txt") });
+
+        assert_eq!(table.rows[1].class_name(), Some("CH".into()));
+        assert_eq!(table.rows[2].class_name(), Some("CLASS254".into()));
+        assert_eq!(table.max_class_len(), 9);
+    }
+
+    #[test]
+    fn glue_beneath_nameservers() {
+        let mut table = table(None);
+        table.rows.push(ns("dog.", "b.ns.dog.", Section::Authority));
+        table.rows.push(ns("dog.", "a.ns.dog.", Section::Authority));
+        table.rows.push(ns("dog.", "ns.elsewhere.", Section::Authority));
+        table.rows.push(row("A",    "a.ns.dog.", Section::Authority, "not glue"));
+        table.rows.push(row("A",    "a.ns.dog.", Section::Additional, "a4"));
+        table.rows.push(row("AAAA", "A.NS.dog.", Section::Additional, "a6"));
+        table.rows.push(row("A",    "b.ns.dog.", Section::Additional, "b4"));
+        table.rows.push(row("A",    "c.ns.dog.", Section::Additional, "c4"));
+
+        assert_eq!(line_summaries(table.lines()), vec![
+            "b.ns.dog.", "└ b4",
+            "a.ns.dog.", "└ a4", "└ a6",
+            "ns.elsewhere.",
+            "not glue",
+            "c4",
+        ]);
+    }
+
+    #[test]
+    fn glue_for_hidden_nameservers() {
+        let mut table = table(Some(1));
+        table.rows.push(ns("dog.", "a.ns.dog.", Section::Authority));
+        table.rows.push(ns("dog.", "b.ns.dog.", Section::Authority));
+        table.rows.push(row("A", "a.ns.dog.", Section::Additional, "a4"));
+        table.rows.push(row("A", "b.ns.dog.", Section::Additional, "b4"));
+
+        assert_eq!(line_summaries(table.lines()), vec![
+            "a.ns.dog.", "└ a4", "Authority 1",
+            "b4",
+        ]);
+    }
+}
@@ -1,7 +1,8 @@
+use std::cell::{Cell, RefCell};
+
 use log::*;
 
-use dns::{Request, Response};
-use super::{Transport, Error, UdpTransport, TcpTransport};
+use super::{Transport, Error, UdpTransport, TcpTransport, ResponseChecks, Mismatch, Timeouts, Source};
 
 
 /// The **automatic transport**, which sends DNS wire data using the UDP
@@ -11,30 +12,98 @@ use super::{Transport, Error, UdpTransport, TcpTransport};
 /// This is the default behaviour for many DNS clients.
 pub struct AutoTransport {
     addr: String,
+    checks: ResponseChecks,
+    ignore_truncation: bool,
+    timeouts: Timeouts,
+    source: Source,
+    ignored: Cell<usize>,
+    fallbacks: Cell<usize>,
+    mismatches: RefCell<Vec<Mismatch>>,
 }
 
 impl AutoTransport {
 
     /// Creates a new automatic transport that connects to the given host.
     pub fn new(addr: String) -> Self {
-        Self { addr }
+        Self {
+            addr,
+            checks: ResponseChecks::Strict,
+            ignore_truncation: false,
+            timeouts: Timeouts::default(),
+            source: Source::default(),
+            ignored: Cell::new(0),
+            fallbacks: Cell::new(0),
+            mismatches: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Sets whether truncated responses get returned as they are, rather
+    /// than the query being sent again over TCP.
+    pub fn ignore_truncation(mut self, ignore_truncation: bool) -> Self {
+        self.ignore_truncation = ignore_truncation;
+        self
+    }
+
+    /// Sets how long to wait for each response, and how long to wait to
+    /// connect when the query gets sent again over TCP.
+    pub fn with_timeouts(mut self, timeouts: Timeouts) -> Self {
+        self.timeouts = timeouts;
+        self
+    }
+
+    /// Sets the local address and interface to send queries from, over
+    /// both UDP and TCP.
+    pub fn with_source(mut self, source: Source) -> Self {
+        self.source = source;
+        self
+    }
+
+    /// Sets how closely a UDP packet has to match the query to be taken as
+    /// its response.
+    pub fn with_checks(mut self, checks: ResponseChecks) -> Self {
+        self.checks = checks;
+        self
     }
 }
 
 
 impl Transport for AutoTransport {
-    fn send(&self, request: &Request) -> Result<Response, Error> {
-        let udp_transport = UdpTransport::new(self.addr.clone());
-        let udp_response = udp_transport.send(&request)?;
+    fn send_raw(&self, bytes_to_send: &[u8]) -> Result<Vec<u8>, Error> {
+        let udp_transport = match self.timeouts.read {
+            Some(timeout)  => UdpTransport::with_timeout(self.addr.clone(), timeout),
+            None           => UdpTransport::new(self.addr.clone()),
+        };
+
+        let udp_transport = udp_transport.with_checks(self.checks).with_source(self.source.clone());
+        let udp_result = udp_transport.send_raw(bytes_to_send);
+        self.ignored.set(self.ignored.get() + udp_transport.ignored_packets());
+        self.mismatches.borrow_mut().extend(udp_transport.take_mismatches());
+        let udp_response = udp_result?;
 
-        if ! udp_response.flags.truncated {
+        // The truncated flag is in the third byte of the header, so look
+        // for it without parsing the rest of the response
+        let truncated = udp_response.get(2).map_or(false, |byte| byte & 0b_0000_0010 != 0);
+        if ! truncated || self.ignore_truncation {
             return Ok(udp_response);
         }
 
         debug!("Truncated flag set, so switching to TCP");
+        self.fallbacks.set(self.fallbacks.get() + 1);
 
-        let tcp_transport = TcpTransport::new(self.addr.clone());
-        let tcp_response = tcp_transport.send(&request)?;
+        let tcp_transport = TcpTransport::new(self.addr.clone()).with_timeouts(self.timeouts).with_source(self.source.clone());
+        let tcp_response = tcp_transport.send_raw(bytes_to_send)?;
         Ok(tcp_response)
     }
+
+    fn ignored_packets(&self) -> usize {
+        self.ignored.get()
+    }
+
+    fn tcp_fallbacks(&self) -> usize {
+        self.fallbacks.get()
+    }
+
+    fn take_mismatches(&self) -> Vec<Mismatch> {
+        self.mismatches.take()
+    }
 }
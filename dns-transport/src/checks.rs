@@ -0,0 +1,157 @@
+use std::fmt;
+use std::net::SocketAddr;
+
+
+/// How closely a response has to match the request it is meant to be the
+/// response to.
+///
+/// A response should have the request’s transaction ID and echo its
+/// question, and over UDP, it should come from the address and port the
+/// request went to. Anything else is either a forgery or a sign of
+/// something broken between dog and the server.
+///
+/// # References
+///
+/// - [RFC 5452 §9.1](https://tools.ietf.org/html/rfc5452#section-9.1) —
+///   Measures for Making DNS More Resilient against Forged Answers
+///   (January 2009)
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum ResponseChecks {
+
+    /// Responses that do not match are not accepted. The UDP transport
+    /// ignores them and keeps waiting; the others fail with an error.
+    Strict,
+
+    /// The first response is accepted whatever is in it, and any ways it
+    /// does not match are reported instead.
+    TrustAnything,
+}
+
+impl Default for ResponseChecks {
+    fn default() -> Self {
+        Self::Strict
+    }
+}
+
+
+/// A way a response did not match the request it was meant to be the
+/// response to.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum Mismatch {
+
+    /// It came from a different address or port than the request went to.
+    Source,
+
+    /// Its transaction ID is not the request’s.
+    TransactionId,
+
+    /// Its question section does not echo the request’s.
+    Question,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Source         => write!(f, "not from the nameserver"),
+            Self::TransactionId  => write!(f, "wrong transaction ID"),
+            Self::Question       => write!(f, "question does not match"),
+        }
+    }
+}
+
+
+/// Checks that a parsed response matches the request: that it has the
+/// request’s transaction ID and question. A response with an error code is
+/// allowed to leave the question out, as servers do that when they cannot
+/// make sense of it. The letters in the name can be in any case, as some
+/// servers change it.
+pub fn check_response(request: &dns::Request, response: &dns::Response) -> Result<(), Mismatch> {
+    if response.transaction_id != request.transaction_id {
+        return Err(Mismatch::TransactionId);
+    }
+
+    match response.queries.first() {
+        Some(echoed) if same_query(&request.query, echoed) => Ok(()),
+        None if response.flags.error_code.is_some() => Ok(()),
+        _ => Err(Mismatch::Question),
+    }
+}
+
+fn same_query(one: &dns::Query, other: &dns::Query) -> bool {
+    one.qtype == other.qtype
+        && one.qclass == other.qclass
+        && one.qname.to_canonical() == other.qname.to_canonical()
+}
+
+
+/// Checks that a packet that arrived over UDP is the response to a query,
+/// without parsing it: that it came from the server the query went to, and
+/// has the query’s transaction ID and question, as with `check_response`.
+pub fn check_packet(query: &[u8], received: &[u8], source: SocketAddr, server: SocketAddr) -> Result<(), Mismatch> {
+    if source != server {
+        return Err(Mismatch::Source);
+    }
+
+    // The transaction ID is in the first two bytes of the header
+    if received.len() < 2 || received.get(.. 2) != query.get(.. 2) {
+        return Err(Mismatch::TransactionId);
+    }
+
+    let asked = match question_section(query) {
+        Some(question)  => question,
+        None            => return Ok(()),
+    };
+
+    match question_section(received) {
+        Some(echoed) if same_question(asked, echoed) => Ok(()),
+        None if question_count(received) == Some(0) && has_error_code(received) => Ok(()),
+        _ => Err(Mismatch::Question),
+    }
+}
+
+/// Returns the bytes of the first question in a message, which starts
+/// straight after the header, or `None` if the message has no questions or
+/// the question is cut short. The name in the question never needs to be
+/// compressed, as there’s no name before it to point to.
+fn question_section(message: &[u8]) -> Option<&[u8]> {
+    if question_count(message)? == 0 {
+        return None;
+    }
+
+    let mut position = 12;
+    loop {
+        let length = usize::from(*message.get(position)?);
+        if length == 0 {
+            break;
+        }
+        else if length & 0b_1100_0000 != 0 {
+            return None;
+        }
+
+        position += 1 + length;
+    }
+
+    // the terminating zero-length label, then the type and class
+    message.get(12 .. position + 5)
+}
+
+/// Returns the question count from a message’s header.
+fn question_count(message: &[u8]) -> Option<u16> {
+    Some(u16::from_be_bytes([ *message.get(4)?, *message.get(5)? ]))
+}
+
+/// Whether a message’s header has a non-zero response code.
+fn has_error_code(message: &[u8]) -> bool {
+    message.get(3).map_or(false, |byte| byte & 0b_0000_1111 != 0)
+}
+
+/// Whether two question sections are the same, ignoring the case of the
+/// letters in the name.
+fn same_question(one: &[u8], other: &[u8]) -> bool {
+    let name_length = one.len() - 4;
+
+    one.len() == other.len()
+        && one[.. name_length].eq_ignore_ascii_case(&other[.. name_length])
+        && one[name_length ..] == other[name_length ..]
+}
+
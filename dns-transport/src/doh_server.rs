@@ -0,0 +1,216 @@
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+
+use log::*;
+
+use dns::{Request, Response};
+use super::Error;
+use super::https::find_subsequence;
+
+
+/// The largest HTTP request the server reads. DNS messages sent over HTTP
+/// are limited to 65535 bytes, and the headers of a request rarely need
+/// more than a few hundred.
+const MAX_REQUEST_LENGTH: usize = 65535 + 4096;
+
+/// The **DNS-over-HTTP server**, which answers DNS messages sent to it in
+/// HTTP/1.1 `POST` requests by passing them to a handler, for testing the
+/// HTTPS transport without the network.
+///
+/// It speaks plain HTTP without TLS, so it should only listen on loopback
+/// addresses, and be queried with an `http://` URL. Only POST requests are
+/// supported, as GET requests need the message in base64 in the URL.
+///
+/// # References
+///
+/// - [RFC 8484 §4](https://tools.ietf.org/html/rfc8484#section-4) — DNS
+///   Queries over HTTPS (October 2018)
+pub struct DohServer {
+    listener: TcpListener,
+}
+
+/// The HTTP response to send back to a client.
+struct HttpReply {
+    status: &'static str,
+    body: Vec<u8>,
+}
+
+impl DohServer {
+
+    /// Creates a new server listening on the given address. Binding to
+    /// port 0 picks a free port, which `local_addr` then returns.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the address cannot be listened on.
+    pub fn bind<A: ToSocketAddrs>(address: A) -> Result<Self, Error> {
+        let listener = TcpListener::bind(address)?;
+        Ok(Self { listener })
+    }
+
+    /// Returns the address the server is listening on.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the socket has stopped being able to tell.
+    pub fn local_addr(&self) -> Result<SocketAddr, Error> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    /// Returns the URL to send DNS-over-HTTP requests to the server with.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the socket has stopped being able to tell.
+    pub fn url(&self) -> Result<String, Error> {
+        Ok(format!("http://{}/dns-query", self.local_addr()?))
+    }
+
+    /// Answers requests forever, passing each DNS message that arrives to
+    /// the handler and sending back the response it returns. The message
+    /// is parsed the same way as a response, as that can hold any message.
+    /// Connections that fail are logged and then ignored.
+    ///
+    /// # Errors
+    ///
+    /// This never returns, so it never returns an error either.
+    pub fn serve<F>(&self, handler: F) -> Result<(), Error>
+    where F: Fn(&Response) -> Response
+    {
+        loop {
+            if let Err(e) = self.serve_one(&handler) {
+                warn!("DoH server connection failed: {:?}", e);
+            }
+        }
+    }
+
+    /// Accepts one connection, and answers the one request sent over it
+    /// using the handler, before closing it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there’s a network error, or the connection gets
+    /// closed before the whole request arrives.
+    pub fn serve_one<F>(&self, handler: &F) -> Result<(), Error>
+    where F: Fn(&Response) -> Response
+    {
+        let (mut stream, peer) = self.listener.accept()?;
+        info!("Accepted DoH connection from {}", peer);
+
+        let reply = match read_http_body(&mut stream)? {
+            Ok(body) => {
+                match Response::from_bytes(&body) {
+                    Ok(message) => {
+                        let response = handler(&message);
+                        HttpReply { status: "200 OK", body: response_bytes(&response)? }
+                    }
+                    Err(e) => {
+                        warn!("Received undecodable DNS message: {:?}", e);
+                        HttpReply { status: "400 Bad Request", body: Vec::new() }
+                    }
+                }
+            }
+            Err(status) => {
+                HttpReply { status, body: Vec::new() }
+            }
+        };
+
+        write_reply(&mut stream, &reply)?;
+        Ok(())
+    }
+}
+
+/// Reads an HTTP request from the stream, returning the body if it was a
+/// POST with a length, or the HTTP status to reply with if it was not.
+fn read_http_body(stream: &mut TcpStream) -> Result<Result<Vec<u8>, &'static str>, Error> {
+    let mut buf = Vec::new();
+    let mut chunk = [0; 4096];
+
+    let header_end = loop {
+        if let Some(index) = find_subsequence(&buf, b"\r\n\r\n") {
+            break index + 4;
+        }
+
+        if buf.len() >= MAX_REQUEST_LENGTH {
+            return Ok(Err("431 Request Header Fields Too Large"));
+        }
+
+        let read_len = stream.read(&mut chunk)?;
+        if read_len == 0 {
+            return Err(Error::TruncatedResponse);
+        }
+        buf.extend(&chunk[.. read_len]);
+    };
+
+    let head = String::from_utf8_lossy(&buf[.. header_end]).into_owned();
+    let mut lines = head.split("\r\n");
+    let request_line = lines.next().unwrap_or_default();
+    debug!("HTTP request line -> {:?}", request_line);
+
+    if ! request_line.starts_with("POST ") {
+        return Ok(Err("405 Method Not Allowed"));
+    }
+
+    let content_length = lines
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("Content-Length"))
+        .and_then(|(_, value)| value.trim().parse::<usize>().ok());
+
+    let content_length = match content_length {
+        Some(length) if length <= MAX_REQUEST_LENGTH => length,
+        Some(_)  => return Ok(Err("413 Payload Too Large")),
+        None     => return Ok(Err("411 Length Required")),
+    };
+
+    let expected_len = header_end + content_length;
+    while buf.len() < expected_len {
+        let read_len = stream.read(&mut chunk)?;
+        if read_len == 0 {
+            return Err(Error::TruncatedResponse);
+        }
+        buf.extend(&chunk[.. read_len]);
+    }
+
+    Ok(Ok(buf[header_end .. expected_len].to_vec()))
+}
+
+/// Writes the reply to the stream, with the headers a DNS-over-HTTPS
+/// client expects.
+fn write_reply(stream: &mut TcpStream, reply: &HttpReply) -> io::Result<()> {
+    let mut bytes = format!("HTTP/1.1 {}\r\n", reply.status).into_bytes();
+    if ! reply.body.is_empty() {
+        bytes.extend(b"Content-Type: application/dns-message\r\n");
+    }
+    write!(bytes, "Content-Length: {}\r\n", reply.body.len())?;
+    bytes.extend(b"Connection: close\r\n\r\n");
+    bytes.extend(&reply.body);
+
+    info!("Sending {} bytes of DoH reply ({})", bytes.len(), reply.status);
+    stream.write_all(&bytes)?;
+    stream.flush()
+}
+
+/// Converts a response into the bytes of a DNS message.
+fn response_bytes(response: &Response) -> io::Result<Vec<u8>> {
+    let mut builder = Request::builder()
+        .transaction_id(response.transaction_id)
+        .flags(response.flags);
+
+    for query in &response.queries {
+        builder = builder.query(query.clone());
+    }
+
+    for answer in &response.answers {
+        builder = builder.answer(answer.clone());
+    }
+
+    for authority in &response.authorities {
+        builder = builder.authority(authority.clone());
+    }
+
+    for additional in &response.additionals {
+        builder = builder.additional(additional.clone());
+    }
+
+    builder.to_bytes()
+}
@@ -1,3 +1,6 @@
+use crate::Mismatch;
+
+
 /// Something that can go wrong making a DNS request.
 #[derive(Debug)]
 pub enum Error {
@@ -33,6 +36,46 @@ pub enum Error {
     /// response code text, if present.
     #[cfg(feature = "with_https")]
     WrongHttpStatus(u16, Option<String>),
+
+    /// A QUIC connection could not be started.
+    #[cfg(feature = "with_quic")]
+    QuicConnectError(quinn::ConnectError),
+
+    /// A QUIC connection failed or was closed.
+    #[cfg(feature = "with_quic")]
+    QuicConnectionError(quinn::ConnectionError),
+
+    /// A query could not be written to its QUIC stream.
+    #[cfg(feature = "with_quic")]
+    QuicWriteError(quinn::WriteError),
+
+    /// A response could not be read from its QUIC stream.
+    #[cfg(feature = "with_quic")]
+    QuicReadError(quinn::ReadToEndError),
+
+    /// There was a problem with an HTTP/2 connection or stream.
+    #[cfg(feature = "with_https")]
+    Http2Error(h2::Error),
+
+    /// The JSON in a response from a DNS JSON API was invalid, or did not
+    /// describe a response.
+    #[cfg(feature = "with_https")]
+    JsonApiError(String),
+
+    /// There was a problem with an HTTP/3 connection or stream.
+    #[cfg(feature = "with_http3")]
+    Http3Error(h3::Error),
+
+    /// The request was signed, but the signature on the response was
+    /// missing or wrong.
+    TsigError(dns::TsigError),
+
+    /// A DSO request was sent, but what came back was not a DSO message.
+    DsoError(dns::DsoError),
+
+    /// The response did not match the request it was meant to be the
+    /// response to.
+    Mismatch(Mismatch),
 }
 
 
@@ -44,6 +87,24 @@ impl From<dns::WireError> for Error {
     }
 }
 
+impl From<dns::TsigError> for Error {
+    fn from(inner: dns::TsigError) -> Self {
+        Self::TsigError(inner)
+    }
+}
+
+impl From<dns::DsoError> for Error {
+    fn from(inner: dns::DsoError) -> Self {
+        Self::DsoError(inner)
+    }
+}
+
+impl From<Mismatch> for Error {
+    fn from(inner: Mismatch) -> Self {
+        Self::Mismatch(inner)
+    }
+}
+
 impl From<std::io::Error> for Error {
     fn from(inner: std::io::Error) -> Self {
         Self::NetworkError(inner)
@@ -77,3 +138,45 @@ impl From<httparse::Error> for Error {
         Self::HttpError(inner)
     }
 }
+
+#[cfg(feature = "with_https")]
+impl From<h2::Error> for Error {
+    fn from(inner: h2::Error) -> Self {
+        Self::Http2Error(inner)
+    }
+}
+
+#[cfg(feature = "with_http3")]
+impl From<h3::Error> for Error {
+    fn from(inner: h3::Error) -> Self {
+        Self::Http3Error(inner)
+    }
+}
+
+#[cfg(feature = "with_quic")]
+impl From<quinn::ConnectError> for Error {
+    fn from(inner: quinn::ConnectError) -> Self {
+        Self::QuicConnectError(inner)
+    }
+}
+
+#[cfg(feature = "with_quic")]
+impl From<quinn::ConnectionError> for Error {
+    fn from(inner: quinn::ConnectionError) -> Self {
+        Self::QuicConnectionError(inner)
+    }
+}
+
+#[cfg(feature = "with_quic")]
+impl From<quinn::WriteError> for Error {
+    fn from(inner: quinn::WriteError) -> Self {
+        Self::QuicWriteError(inner)
+    }
+}
+
+#[cfg(feature = "with_quic")]
+impl From<quinn::ReadToEndError> for Error {
+    fn from(inner: quinn::ReadToEndError) -> Self {
+        Self::QuicReadError(inner)
+    }
+}
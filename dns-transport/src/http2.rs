@@ -0,0 +1,148 @@
+//! Sending DNS-over-HTTPS requests over HTTP/2. The connection is kept open
+//! after the first request, and every later request to the same server gets
+//! sent down it as a new stream, the way browsers and other DoH clients do.
+//!
+//! # References
+//!
+//! - [RFC 8484 §5.2](https://tools.ietf.org/html/rfc8484#section-5.2) — DNS
+//!   Queries over HTTPS (October 2018)
+//! - [RFC 7540](https://tools.ietf.org/html/rfc7540) — Hypertext Transfer
+//!   Protocol Version 2 (HTTP/2) (May 2015)
+
+use bytes::Bytes;
+use log::*;
+use tokio::net::TcpStream;
+
+use super::{Error, Timeouts, Source};
+use super::timeouts::within;
+
+
+/// An open HTTP/2 connection, along with the runtime that drives it.
+pub(crate) struct Http2Connection {
+    runtime: tokio::runtime::Runtime,
+    sender: h2::client::SendRequest<Bytes>,
+    timeouts: Timeouts,
+}
+
+impl Http2Connection {
+
+    /// Opens a TLS connection to the given domain from the source, asking
+    /// for HTTP/2 using ALPN. Returns `None` if the server picks another
+    /// protocol, in which case HTTP/1.1 has to be used instead. Connecting
+    /// and both handshakes have to finish within the connect timeout.
+    pub(crate) fn connect(domain: &str, port: u16, timeouts: Timeouts, source: &Source) -> Result<Option<Self>, Error> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+
+        let sender = runtime.block_on(within(timeouts.connect, async {
+            let tcp = tcp_connect(domain, port, source).await?;
+            let tls = match tls_connect(domain, tcp).await? {
+                Some(tls)  => tls,
+                None       => return Ok(None),
+            };
+
+            let (sender, connection) = h2::client::handshake(tls).await?;
+            debug!("Connected using HTTP/2");
+
+            tokio::spawn(async move {
+                if let Err(e) = connection.await {
+                    warn!("HTTP/2 connection failed -> {:?}", e);
+                }
+            });
+
+            Ok::<_, Error>(Some(sender))
+        })).map_err(|e| timeouts.connect_error(e))?;
+
+        Ok(sender.map(|sender| Self { runtime, sender, timeouts }))
+    }
+
+    /// Sends a request with the given body on a new stream, and waits for
+    /// the response, returning its status and headers along with its body.
+    /// An empty body means no body at all.
+    pub(crate) fn send(&self, request: http::Request<()>, body: &[u8]) -> Result<(http::response::Parts, Vec<u8>), Error> {
+        let sender = self.sender.clone();
+
+        self.runtime.block_on(within(self.timeouts.read, async move {
+            let mut sender = sender.ready().await?;
+            let (response, mut stream) = sender.send_request(request, body.is_empty())?;
+            if ! body.is_empty() {
+                stream.send_data(Bytes::copy_from_slice(body), true)?;
+            }
+
+            let (parts, mut received) = response.await?.into_parts();
+            let mut body = Vec::new();
+            while let Some(chunk) = received.data().await {
+                let chunk = chunk?;
+                received.flow_control().release_capacity(chunk.len())?;
+                body.extend_from_slice(&chunk);
+            }
+
+            debug!("HTTP/2 body has {} bytes", body.len());
+            Ok::<_, Error>((parts, body))
+        })).map_err(|e| self.timeouts.read_error(e))
+    }
+}
+
+
+/// Opens a TCP connection to the given domain. When there is a source
+/// address or interface, the socket has to be bound before it connects,
+/// which means opening it through `socket2` and handing it to Tokio.
+async fn tcp_connect(domain: &str, port: u16, source: &Source) -> Result<TcpStream, Error> {
+    if source.is_default() {
+        return Ok(TcpStream::connect((domain, port)).await?);
+    }
+
+    let server = tokio::net::lookup_host((domain, port)).await?
+        .find(|address| source.reaches(address))
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "no address for HTTP/2 server"))?;
+
+    let socket = source.tcp_socket(server)?;
+    socket.set_nonblocking(true)?;
+    let socket = tokio::net::TcpSocket::from_std_stream(socket.into());
+    Ok(socket.connect(server).await?)
+}
+
+
+/// The ALPN token that identifies HTTP/2 over TLS.
+const ALPN_H2: &str = "h2";
+
+#[cfg(any(feature = "with_nativetls", feature = "with_nativetls_vendored"))]
+async fn tls_connect(domain: &str, tcp: TcpStream) -> Result<Option<tokio_native_tls::TlsStream<TcpStream>>, Error> {
+    let connector = native_tls::TlsConnector::builder()
+        .request_alpns(&[ ALPN_H2 ])
+        .build()?;
+
+    let stream = tokio_native_tls::TlsConnector::from(connector).connect(domain, tcp).await?;
+    let protocol = stream.get_ref().negotiated_alpn()?;
+
+    if protocol.as_deref() == Some(ALPN_H2.as_bytes()) {
+        Ok(Some(stream))
+    }
+    else {
+        info!("Server did not negotiate HTTP/2 (got {:?})", protocol);
+        Ok(None)
+    }
+}
+
+#[cfg(all(feature = "with_rustls", not(any(feature = "with_nativetls", feature = "with_nativetls_vendored"))))]
+async fn tls_connect(domain: &str, tcp: TcpStream) -> Result<Option<tokio_rustls::client::TlsStream<TcpStream>>, Error> {
+    use std::sync::Arc;
+    use rustls::Session;
+
+    let mut config = rustls::ClientConfig::new();
+    config.root_store.add_server_trust_anchors(&webpki_roots::TLS_SERVER_ROOTS);
+    config.alpn_protocols = vec![ ALPN_H2.as_bytes().to_vec() ];
+
+    let dns_name = webpki::DNSNameRef::try_from_ascii_str(domain)?;
+    let stream = tokio_rustls::TlsConnector::from(Arc::new(config)).connect(dns_name, tcp).await?;
+    let protocol = stream.get_ref().1.get_alpn_protocol().map(<[u8]>::to_vec);
+
+    if protocol.as_deref() == Some(ALPN_H2.as_bytes()) {
+        Ok(Some(stream))
+    }
+    else {
+        info!("Server did not negotiate HTTP/2 (got {:?})", protocol);
+        Ok(None)
+    }
+}
@@ -0,0 +1,93 @@
+//! Sending DNS-over-HTTPS requests over HTTP/3, which runs on QUIC instead
+//! of TCP and TLS. As with HTTP/2, the connection is kept open after the
+//! first request, and every later request gets sent down it as a new stream.
+//! There is no fallback: a server that does not speak HTTP/3 fails to
+//! connect.
+//!
+//! # References
+//!
+//! - [RFC 8484 §5.2](https://tools.ietf.org/html/rfc8484#section-5.2) — DNS
+//!   Queries over HTTPS (October 2018)
+//! - [RFC 9114](https://tools.ietf.org/html/rfc9114) — HTTP/3 (June 2022)
+
+use bytes::{BufMut, Bytes};
+use log::*;
+
+use super::{Error, Timeouts, Source};
+use super::quic;
+use super::timeouts::within;
+
+
+/// An open HTTP/3 connection, along with the runtime that drives it.
+pub(crate) struct Http3Connection {
+    runtime: tokio::runtime::Runtime,
+    endpoint: quinn::Endpoint,
+    sender: h3::client::SendRequest<h3_quinn::OpenStreams, Bytes>,
+    timeouts: Timeouts,
+}
+
+impl Http3Connection {
+
+    /// Opens a QUIC connection to the given domain from the source, asking
+    /// for HTTP/3 using ALPN, and performs the HTTP/3 handshake on top of
+    /// it, all within the connect timeout.
+    pub(crate) fn connect(domain: &str, port: u16, timeouts: Timeouts, source: &Source) -> Result<Self, Error> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+
+        let config = quic::client_config(ALPN_H3);
+        let (endpoint, sender) = runtime.block_on(within(timeouts.connect, async {
+            let (endpoint, connection) = quic::connect(&config, domain, port, source).await?;
+            let (mut driver, sender) = h3::client::new(h3_quinn::Connection::new(connection)).await?;
+            debug!("Connected using HTTP/3");
+
+            tokio::spawn(async move {
+                if let Err(e) = std::future::poll_fn(|cx| driver.poll_close(cx)).await {
+                    warn!("HTTP/3 connection failed -> {:?}", e);
+                }
+            });
+
+            Ok::<_, Error>((endpoint, sender))
+        })).map_err(|e| timeouts.connect_error(e))?;
+
+        Ok(Self { runtime, endpoint, sender, timeouts })
+    }
+
+    /// Sends a request with the given body on a new stream, and waits for
+    /// the response, returning its status and headers along with its body.
+    /// An empty body means no body at all.
+    pub(crate) fn send(&self, request: http::Request<()>, body: &[u8]) -> Result<(http::response::Parts, Vec<u8>), Error> {
+        let mut sender = self.sender.clone();
+
+        self.runtime.block_on(within(self.timeouts.read, async move {
+            let mut stream = sender.send_request(request).await?;
+            if ! body.is_empty() {
+                stream.send_data(Bytes::copy_from_slice(body)).await?;
+            }
+            stream.finish().await?;
+
+            let (parts, ()) = stream.recv_response().await?.into_parts();
+            let mut body = Vec::new();
+            while let Some(chunk) = stream.recv_data().await? {
+                body.put(chunk);
+            }
+
+            debug!("HTTP/3 body has {} bytes", body.len());
+            Ok::<_, Error>((parts, body))
+        })).map_err(|e| self.timeouts.read_error(e))
+    }
+}
+
+impl Drop for Http3Connection {
+    fn drop(&mut self) {
+        // Error code 0x100 is H3_NO_ERROR (RFC 9114 §8.1)
+        self.endpoint.close(0x100_u32.into(), b"");
+        let endpoint = &self.endpoint;
+        self.runtime.block_on(endpoint.wait_idle());
+    }
+}
+
+
+/// The ALPN token that identifies HTTP/3.
+const ALPN_H3: &[u8] = b"h3";
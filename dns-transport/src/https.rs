@@ -1,30 +1,259 @@
 #![cfg_attr(not(feature = "https"), allow(unused))]
 
+use std::borrow::Cow;
+use std::cell::{Cell, RefCell};
 use std::io::{Read, Write};
-use std::net::TcpStream;
 
 use log::*;
 
 use dns::{Request, Response, WireError};
-use super::{Transport, Error};
+use super::{Transport, Error, Timeouts, Source};
+use super::quic::domain_and_port;
 
 use super::tls_stream;
 
+#[cfg(feature = "with_https")]
+use super::http2::Http2Connection;
+
+#[cfg(feature = "with_http3")]
+use super::http3::Http3Connection;
+
+#[cfg(feature = "with_https")]
+use super::json_api;
+
 /// The **HTTPS transport**, which sends DNS wire data inside HTTP packets
-/// encrypted with TLS, using TCP.
+/// encrypted with TLS, using TCP. HTTP/2 is used when the server agrees to
+/// it, with the connection kept open so that every request shares it;
+/// otherwise, each request is sent over its own HTTP/1.1 connection. URLs
+/// that start with `http://` instead get sent without TLS over HTTP/1.1,
+/// which is only useful for testing. HTTP/3 is only used when asked for.
 pub struct HttpsTransport {
     url: String,
+    headers: HttpHeaders,
+    timeouts: Timeouts,
+    source: Source,
+
+    /// The HTTP/2 connection that gets reused between requests.
+    #[cfg(feature = "with_https")]
+    http2: RefCell<Option<Http2Connection>>,
+
+    /// Whether the server has refused HTTP/2, so it should not be asked
+    /// for again.
+    http1_only: Cell<bool>,
+
+    /// Whether to send requests over HTTP/3 instead.
+    use_http3: bool,
+
+    /// The HTTP/3 connection that gets reused between requests.
+    #[cfg(feature = "with_http3")]
+    http3: RefCell<Option<Http3Connection>>,
 }
 
 impl HttpsTransport {
 
-    /// Creates a new HTTPS transport that connects to the given URL.
-    pub fn new(url: String) -> Self {
-        Self { url }
+    /// Creates a new HTTPS transport that connects to the given URL, and
+    /// sends the given headers with each request.
+    pub fn new(url: String, headers: HttpHeaders) -> Self {
+        Self {
+            url,
+            headers,
+            timeouts: Timeouts::default(),
+            source: Source::default(),
+            #[cfg(feature = "with_https")]
+            http2: RefCell::new(None),
+            http1_only: Cell::new(false),
+            use_http3: false,
+            #[cfg(feature = "with_http3")]
+            http3: RefCell::new(None),
+        }
+    }
+
+    /// Makes this transport send its requests over HTTP/3, using QUIC,
+    /// rather than over TCP. There is no falling back to an older version
+    /// if the server does not support it.
+    pub fn with_http3(mut self) -> Self {
+        self.use_http3 = true;
+        self
+    }
+
+    /// Sets how long to wait to connect, including the TLS or QUIC
+    /// handshake, and how long to wait for each response.
+    pub fn with_timeouts(mut self, timeouts: Timeouts) -> Self {
+        self.timeouts = timeouts;
+        self
+    }
+
+    /// Sets the local address and interface to connect from.
+    pub fn with_source(mut self, source: Source) -> Self {
+        self.source = source;
+        self
     }
 }
 
-fn find_subsequence(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+/// Which HTTP headers get sent with each DNS-over-HTTPS request, which
+/// decides how easy it is to tell that the request came from dog.
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct HttpHeaders {
+
+    /// The value of the `User-Agent` header. If this is `None`, dog sends
+    /// its own name and version, unless it’s in vanilla mode, in which case
+    /// it sends no `User-Agent` at all. An empty string also sends none.
+    pub user_agent: Option<String>,
+
+    /// Whether to send only the headers a DNS-over-HTTPS request needs, in the order a
+    /// web browser sends them, instead of dog’s usual set.
+    pub vanilla: bool,
+
+    /// Which HTTP method to send the request with.
+    pub method: DohMethod,
+
+    /// Whether to ask a JSON API, such as Google’s or Cloudflare’s
+    /// `resolve?name=…&type=…` endpoints, instead of sending DNS messages.
+    /// Requests to these are always sent with GET.
+    pub json: bool,
+
+    /// Any other headers to send, as pairs of names and values, such as an
+    /// `Authorization` header for a resolver that needs an API token. One
+    /// with the same name as a header dog already sends replaces it.
+    pub extra: Vec<(String, String)>,
+}
+
+/// Which HTTP method DNS-over-HTTPS requests get sent with.
+///
+/// # References
+///
+/// - [RFC 8484 §4.1](https://tools.ietf.org/html/rfc8484#section-4.1) — DNS
+///   Queries over HTTPS (October 2018)
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum DohMethod {
+
+    /// Send the message as the body of a POST request.
+    Post,
+
+    /// Send the message in the `dns` parameter of the URL of a GET request,
+    /// encoded with base64url. Some HTTP caches treat these differently, and
+    /// some proxies only let GET requests through.
+    Get,
+}
+
+impl Default for DohMethod {
+    fn default() -> Self {
+        Self::Post
+    }
+}
+
+impl HttpHeaders {
+
+    /// Returns the name and value of each header to send with a request
+    /// whose body is the given length, in the order they should be sent.
+    /// GET requests have no body, so they get no headers describing one.
+    /// Both the body and the response are of the given media type.
+    fn list<'a>(&'a self, domain: &'a str, media_type: &'static str, content_length: Option<usize>) -> Vec<(&'a str, Cow<'a, str>)> {
+        let user_agent = match (&self.user_agent, self.vanilla) {
+            (Some(ua), _) if ua.is_empty()  => None,
+            (Some(ua), _)                   => Some(Cow::Borrowed(ua.as_str())),
+            (None, false)                   => Some(Cow::Borrowed(USER_AGENT)),
+            (None, true)                    => None,
+        };
+
+        let mut headers = vec![ ("Host", Cow::Borrowed(domain)) ];
+
+        match content_length {
+            None if self.vanilla => {
+                headers.extend(user_agent.map(|ua| ("User-Agent", ua)));
+                headers.push(("Accept", Cow::Borrowed(media_type)));
+            }
+            None => {
+                headers.push(("Accept", Cow::Borrowed(media_type)));
+                headers.extend(user_agent.map(|ua| ("User-Agent", ua)));
+            }
+            Some(length) if self.vanilla => {
+                // Chromium puts the length and the agent before the content
+                // headers for a fetch() POST
+                headers.push(("Content-Length", Cow::Owned(length.to_string())));
+                headers.extend(user_agent.map(|ua| ("User-Agent", ua)));
+                headers.push(("Content-Type", Cow::Borrowed(media_type)));
+                headers.push(("Accept", Cow::Borrowed(media_type)));
+            }
+            Some(length) => {
+                headers.push(("Content-Type", Cow::Borrowed(media_type)));
+                headers.push(("Accept", Cow::Borrowed(media_type)));
+                headers.extend(user_agent.map(|ua| ("User-Agent", ua)));
+                headers.push(("Content-Length", Cow::Owned(length.to_string())));
+            }
+        }
+
+        for (name, value) in &self.extra {
+            let header = (name.as_str(), Cow::Borrowed(value.as_str()));
+            match headers.iter_mut().find(|(n, _)| n.eq_ignore_ascii_case(name)) {
+                Some(existing)  => *existing = header,
+                None            => headers.push(header),
+            }
+        }
+
+        headers
+    }
+}
+
+/// How a request gets put into an HTTP request.
+enum Payload<'a> {
+
+    /// The message is the body of a POST request.
+    Body(&'a [u8]),
+
+    /// The request is in the query string of a GET request, and the
+    /// response is of the given media type.
+    Query(String, &'static str),
+}
+
+impl Payload<'_> {
+
+    /// The name of the method to send the request with.
+    fn method(&self) -> &'static str {
+        match self {
+            Self::Body(_)      => "POST",
+            Self::Query(_, _)  => "GET",
+        }
+    }
+
+    /// The media type of the body and of the response.
+    fn media_type(&self) -> &'static str {
+        match self {
+            Self::Body(_)               => DNS_MESSAGE,
+            Self::Query(_, media_type)  => media_type,
+        }
+    }
+
+    /// The body to send with the request, which is empty for GET requests.
+    fn body(&self) -> &[u8] {
+        match self {
+            Self::Body(bytes)  => bytes,
+            Self::Query(_, _)  => &[],
+        }
+    }
+
+    /// The length of the body, if there is one.
+    fn content_length(&self) -> Option<usize> {
+        match self {
+            Self::Body(bytes)  => Some(bytes.len()),
+            Self::Query(_, _)  => None,
+        }
+    }
+
+    /// Returns the path or URL to send the request to, with the query
+    /// string added to it for GET requests.
+    fn target<'t>(&self, target: &'t str) -> Cow<'t, str> {
+        match self {
+            Self::Body(_) => Cow::Borrowed(target),
+            Self::Query(query, _) => {
+                let separator = if target.contains('?') { '&' } else { '?' };
+                Cow::Owned(format!("{}{}{}", target, separator, query))
+            }
+        }
+    }
+}
+
+pub(crate) fn find_subsequence(haystack: &[u8], needle: &[u8]) -> Option<usize> {
     haystack.windows(needle.len()).position(|window| window == needle)
 }
 
@@ -36,26 +265,186 @@ fn contains_header(buf: &[u8]) -> bool {
 use tls_stream::TlsStream;
 
 impl Transport for HttpsTransport {
+    fn send_via_cache(&self, request: &Request) -> Result<(Response, Option<HttpCaching>), Error> {
+        let request_bytes = request.to_bytes().expect("failed to serialise request");
+        let (body, caching) = self.exchange(&request_bytes)?;
+        let response = Response::from_bytes(&body)?;
+        Ok((response, caching))
+    }
+
+    fn send_raw(&self, request_bytes: &[u8]) -> Result<Vec<u8>, Error> {
+        let (body, _) = self.exchange(request_bytes)?;
+        Ok(body)
+    }
+}
+
+impl HttpsTransport {
 
+    /// Sends the bytes of a request in an HTTP request, returning the body
+    /// of the HTTP response and what its caching headers said.
     #[cfg(any(feature = "with_https"))]
-    fn send(&self, request: &Request) -> Result<Response, Error> {
+    fn exchange(&self, request_bytes: &[u8]) -> Result<(Vec<u8>, Option<HttpCaching>), Error> {
+        if self.headers.json {
+            let payload = Payload::Query(json_api::query_string(request_bytes)?, DNS_JSON);
+            let (body, caching) = self.exchange_payload(&payload)?;
+            return Ok((json_api::response_bytes(request_bytes, &body)?, caching));
+        }
+
+        if self.headers.method == DohMethod::Post {
+            return self.exchange_payload(&Payload::Body(request_bytes));
+        }
+
+        // GET requests are sent with a message ID of zero, so that the same
+        // question always has the same URL and can be cached (RFC 8484
+        // §4.1). The real ID is put back into the response so it still
+        // matches.
+        let mut message = request_bytes.to_vec();
+        let transaction_id = [ message[0], message[1] ];
+        message[0] = 0;
+        message[1] = 0;
+
+        let encoded = base64::encode_config(&message, base64::URL_SAFE_NO_PAD);
+        let payload = Payload::Query(format!("dns={}", encoded), DNS_MESSAGE);
+        let (mut body, caching) = self.exchange_payload(&payload)?;
+        if let Some(id) = body.get_mut(.. 2) {
+            id.copy_from_slice(&transaction_id);
+        }
+        Ok((body, caching))
+    }
+
+    /// Sends a request over whichever version of HTTP the server supports.
+    #[cfg(any(feature = "with_https"))]
+    fn exchange_payload(&self, payload: &Payload<'_>) -> Result<(Vec<u8>, Option<HttpCaching>), Error> {
+        if let Some((authority, path)) = self.split_plain_authority() {
+            info!("Opening plain TCP socket to {:?}", authority);
+            let (host, port) = domain_and_port(authority, 80)?;
+            let stream = self.timeouts.connect_tcp(host, port, &self.source)?;
+            self.timeouts.connected(&stream)?;
+            return self.exchange_over(stream, authority, path, payload).map_err(|e| self.timeouts.read_error(e));
+        }
+
         let (domain, path) = self.split_domain().expect("Invalid HTTPS nameserver");
 
+        if self.use_http3 {
+            return self.exchange_http3(domain, payload);
+        }
+
+        if ! self.http1_only.get() {
+            if let Some(response) = self.exchange_http2(domain, payload)? {
+                return Ok(response);
+            }
+        }
+
         info!("Opening TLS socket to {:?}", domain);
-        let mut stream = Self::stream(&domain, 443)?;
+        let stream = Self::stream(&domain, 443, self.timeouts, &self.source)?;
+        self.exchange_over(stream, domain, path, payload).map_err(|e| self.timeouts.read_error(e))
+    }
+
+    /// Sends the HTTP request over HTTP/2, connecting first if there is no
+    /// connection to reuse. Returns `None` if the server does not speak
+    /// HTTP/2, and remembers not to ask again.
+    #[cfg(any(feature = "with_https"))]
+    fn exchange_http2(&self, domain: &str, payload: &Payload<'_>) -> Result<Option<(Vec<u8>, Option<HttpCaching>)>, Error> {
+        let mut slot = self.http2.borrow_mut();
+
+        if slot.is_none() {
+            info!("Opening HTTP/2 connection to {:?}", domain);
+            match Http2Connection::connect(domain, 443, self.timeouts, &self.source)? {
+                Some(connection) => {
+                    *slot = Some(connection);
+                }
+                None => {
+                    self.http1_only.set(true);
+                    return Ok(None);
+                }
+            }
+        }
+
+        let request = self.http_request(domain, payload);
+
+        info!("Sending {} bytes of data to {:?} over HTTP/2", payload.body().len(), self.url);
+        let connection = slot.as_ref().unwrap();
+        let (parts, body) = match connection.send(request, payload.body()) {
+            Ok(response) => response,
+            Err(e) => {
+                // The connection cannot be trusted after a failure
+                *slot = None;
+                return Err(e);
+            }
+        };
+
+        Self::check_http_response(&parts).map(|caching| Some((body, caching)))
+    }
+
+    /// Sends the HTTP request over HTTP/3, connecting first if there is no
+    /// connection to reuse.
+    #[cfg(feature = "with_http3")]
+    fn exchange_http3(&self, domain: &str, payload: &Payload<'_>) -> Result<(Vec<u8>, Option<HttpCaching>), Error> {
+        let mut slot = self.http3.borrow_mut();
+
+        if slot.is_none() {
+            info!("Opening HTTP/3 connection to {:?}", domain);
+            *slot = Some(Http3Connection::connect(domain, 443, self.timeouts, &self.source)?);
+        }
+
+        let request = self.http_request(domain, payload);
+
+        info!("Sending {} bytes of data to {:?} over HTTP/3", payload.body().len(), self.url);
+        let connection = slot.as_ref().unwrap();
+        let (parts, body) = match connection.send(request, payload.body()) {
+            Ok(response) => response,
+            Err(e) => {
+                *slot = None;
+                return Err(e);
+            }
+        };
+
+        Self::check_http_response(&parts).map(|caching| (body, caching))
+    }
+
+    #[cfg(all(feature = "with_https", not(feature = "with_http3")))]
+    fn exchange_http3(&self, domain: &str, payload: &Payload<'_>) -> Result<(Vec<u8>, Option<HttpCaching>), Error> {
+        unreachable!("HTTP/3 feature disabled")
+    }
 
+    /// Builds the request to send over HTTP/2 or HTTP/3, which have the
+    /// `:authority` pseudo-header instead of `Host`.
+    #[cfg(any(feature = "with_https"))]
+    fn http_request(&self, domain: &str, payload: &Payload<'_>) -> http::Request<()> {
+        let uri = payload.target(&self.url);
+        let mut builder = http::Request::builder().method(payload.method()).uri(&*uri);
+        for (name, value) in self.headers.list(domain, payload.media_type(), payload.content_length()) {
+            if name != "Host" {
+                builder = builder.header(name, &*value);
+            }
+        }
+        builder.body(()).expect("Invalid HTTPS nameserver")
+    }
+
+    /// Checks the status of an HTTP/2 or HTTP/3 response, returning what
+    /// its caching headers said.
+    #[cfg(any(feature = "with_https"))]
+    fn check_http_response(parts: &http::response::Parts) -> Result<Option<HttpCaching>, Error> {
+        if parts.status != http::StatusCode::OK {
+            let reason = parts.status.canonical_reason().map(str::to_owned);
+            return Err(Error::WrongHttpStatus(parts.status.as_u16(), reason));
+        }
+
+        let header = |name: &str| parts.headers.get(name).and_then(|value| value.to_str().ok());
+        Ok(HttpCaching::from_headers(header("age"), header("cache-control")))
+    }
+
+    /// Sends the HTTP request over an open stream to the given host.
+    #[cfg(any(feature = "with_https"))]
+    fn exchange_over<S: Read + Write>(&self, mut stream: S, domain: &str, path: &str, payload: &Payload<'_>) -> Result<(Vec<u8>, Option<HttpCaching>), Error> {
         debug!("Connected");
 
-        let request_bytes = request.to_bytes().expect("failed to serialise request");
-        let mut bytes_to_send = format!("\
-            POST {} HTTP/1.1\r\n\
-            Host: {}\r\n\
-            Content-Type: application/dns-message\r\n\
-            Accept: application/dns-message\r\n\
-            User-Agent: {}\r\n\
-            Content-Length: {}\r\n\r\n",
-            path, domain, USER_AGENT, request_bytes.len()).into_bytes();
-        bytes_to_send.extend(request_bytes);
+        let mut bytes_to_send = format!("{} {} HTTP/1.1\r\n", payload.method(), payload.target(path)).into_bytes();
+        for (name, value) in self.headers.list(domain, payload.media_type(), payload.content_length()) {
+            write!(bytes_to_send, "{}: {}\r\n", name, value)?;
+        }
+        bytes_to_send.extend(b"\r\n");
+        bytes_to_send.extend(payload.body());
 
         info!("Sending {} bytes of data to {:?} over HTTPS", bytes_to_send.len(), self.url);
         stream.write_all(&bytes_to_send)?;
@@ -71,6 +460,8 @@ impl Transport for HttpsTransport {
             read_len += stream.read(&mut buf[read_len..])?;
         }
         let mut expected_len = read_len;
+        let mut age = None;
+        let mut cache_control = None;
         info!("Received {} bytes of data", read_len);
 
         let mut headers = [httparse::EMPTY_HEADER; 16];
@@ -89,6 +480,12 @@ impl Transport for HttpsTransport {
                 let content_length: usize = str_value.parse().unwrap();
                 expected_len = index + content_length;
             }
+            else if header.name.eq_ignore_ascii_case("Age") {
+                age = Some(str_value.to_string());
+            }
+            else if header.name.eq_ignore_ascii_case("Cache-Control") {
+                cache_control = Some(str_value.to_string());
+            }
         }
 
         while read_len < expected_len {
@@ -100,17 +497,27 @@ impl Transport for HttpsTransport {
 
         let body = &buf[index .. read_len];
         debug!("HTTP body has {} bytes", body.len());
-        let response = Response::from_bytes(&body)?;
-        Ok(response)
+        let caching = HttpCaching::from_headers(age.as_deref(), cache_control.as_deref());
+        Ok((body.to_vec(), caching))
     }
 
     #[cfg(not(feature = "with_https"))]
-    fn send(&self, request: &Request) -> Result<Response, Error> {
+    fn exchange(&self, request_bytes: &[u8]) -> Result<(Vec<u8>, Option<HttpCaching>), Error> {
         unreachable!("HTTPS feature disabled")
     }
 }
 
 impl HttpsTransport {
+
+    /// Splits an `http://` URL, which gets sent without TLS to the port in
+    /// it, such as one for a local `DohServer`, into its host and port and
+    /// its path.
+    fn split_plain_authority(&self) -> Option<(&str, &str)> {
+        let sp = self.url.strip_prefix("http://")?;
+        let slash_index = sp.find('/')?;
+        Some((&sp[.. slash_index], &sp[slash_index ..]))
+    }
+
     fn split_domain(&self) -> Option<(&str, &str)> {
         if let Some(sp) = self.url.strip_prefix("https://") {
             if let Some(colon_index) = sp.find('/') {
@@ -122,6 +529,66 @@ impl HttpsTransport {
     }
 }
 
+/// What the caching headers of a DNS-over-HTTPS response said about it. A
+/// response that has an `Age` header was served from an HTTP cache, and
+/// the records in it have been counting down while they sat there.
+///
+/// # References
+///
+/// - [RFC 8484 §5.1](https://tools.ietf.org/html/rfc8484#section-5.1) — DNS
+///   Queries over HTTPS (October 2018)
+/// - [RFC 7234 §5](https://tools.ietf.org/html/rfc7234#section-5) — HTTP/1.1
+///   Caching (June 2014)
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub struct HttpCaching {
+
+    /// The number of seconds the response spent in a cache, from the `Age`
+    /// header.
+    pub age: Option<u32>,
+
+    /// The number of seconds the response could be cached for, from the
+    /// `max-age` directive of the `Cache-Control` header.
+    pub max_age: Option<u32>,
+}
+
+impl HttpCaching {
+
+    /// Interprets the values of the `Age` and `Cache-Control` headers,
+    /// returning `None` if neither had anything to say about caching.
+    pub fn from_headers(age: Option<&str>, cache_control: Option<&str>) -> Option<Self> {
+        let age = age.and_then(|a| a.trim().parse().ok());
+
+        let max_age = cache_control.and_then(|cc| {
+            cc.split(',')
+              .map(str::trim)
+              .find_map(|directive| {
+                  let (name, value) = directive.split_once('=')?;
+                  if name.trim().eq_ignore_ascii_case("max-age") { value.trim().trim_matches('"').parse().ok() } else { None }
+              })
+        });
+
+        if age.is_none() && max_age.is_none() {
+            None
+        }
+        else {
+            Some(Self { age, max_age })
+        }
+    }
+
+    /// Whether the response sat in a cache for longer than it was allowed
+    /// to be cached for.
+    pub fn is_stale(self) -> bool {
+        matches!((self.age, self.max_age), (Some(age), Some(max_age)) if age > max_age)
+    }
+}
+
+
 /// The User-Agent header sent with HTTPS requests.
 static USER_AGENT: &str = concat!("dog/", env!("CARGO_PKG_VERSION"));
 
+/// The media type of DNS messages (RFC 8484 §6).
+static DNS_MESSAGE: &str = "application/dns-message";
+
+/// The media type of responses from JSON APIs.
+static DNS_JSON: &str = "application/dns-json";
+
@@ -0,0 +1,143 @@
+//! Talking to the JSON APIs that some DNS-over-HTTPS resolvers offer
+//! alongside RFC 8484, such as Google’s and Cloudflare’s, which take the
+//! question in the query string of a GET request and return the answer as
+//! a JSON object. The answer gets turned back into a DNS message, so the
+//! rest of dog can treat it like any other response.
+//!
+//! # References
+//!
+//! - [Google Public DNS JSON API](https://developers.google.com/speed/public-dns/docs/doh/json)
+//! - [Cloudflare DNS over HTTPS JSON format](https://developers.cloudflare.com/1.1.1.1/encryption/dns-over-https/make-api-requests/dns-json/)
+
+use json::JsonValue;
+use log::*;
+
+use dns::{Request, Response, Answer, Flags, QClass, Labels, Wire};
+use dns::record::{RecordType, TXT};
+
+use super::Error;
+
+
+/// Returns the query string that asks the JSON API the question in the
+/// given request, passing on whether it disables DNSSEC checking and
+/// whether it wants DNSSEC records.
+pub(crate) fn query_string(request_bytes: &[u8]) -> Result<String, Error> {
+    let request = Response::from_bytes(request_bytes)?;
+    let query = request.queries.first().ok_or_else(|| Error::JsonApiError("Request has no question".into()))?;
+
+    let mut query_string = format!("name={}&type={}", percent_encode(&query.qname.to_string()), query.qtype.type_number());
+
+    if request.flags.checking_disabled {
+        query_string.push_str("&cd=1");
+    }
+
+    let dnssec_ok = request.additionals.iter().any(|additional| {
+        matches!(additional, Answer::Pseudo { opt, .. } if opt.flags & 0b_1000_0000_0000_0000 != 0)
+    });
+
+    if dnssec_ok {
+        query_string.push_str("&do=1");
+    }
+
+    Ok(query_string)
+}
+
+/// Turns the JSON body of a response from a JSON API into the bytes of the
+/// DNS message it describes, giving it the transaction ID and question of
+/// the request it answers. Records whose data cannot be read are left out,
+/// with a warning.
+pub(crate) fn response_bytes(request_bytes: &[u8], body: &[u8]) -> Result<Vec<u8>, Error> {
+    let request = Response::from_bytes(request_bytes)?;
+
+    let text = std::str::from_utf8(body).map_err(|_| Error::JsonApiError("Response is not valid UTF-8".into()))?;
+    let json = json::parse(text).map_err(|e| Error::JsonApiError(e.to_string()))?;
+
+    let status = json["Status"].as_u16().ok_or_else(|| Error::JsonApiError("Response has no status".into()))?;
+
+    // Only the lower four bits of the status fit in the header
+    let mut flags = Flags::from_u16(0b_1000_0000_0000_0000 | (status & 0b_1111));
+    flags.truncated           = json["TC"].as_bool().unwrap_or(false);
+    flags.recursion_desired   = json["RD"].as_bool().unwrap_or(false);
+    flags.recursion_available = json["RA"].as_bool().unwrap_or(false);
+    flags.authentic_data      = json["AD"].as_bool().unwrap_or(false);
+    flags.checking_disabled   = json["CD"].as_bool().unwrap_or(false);
+
+    let mut builder = Request::builder()
+        .transaction_id(request.transaction_id)
+        .flags(flags);
+
+    for query in request.queries {
+        builder = builder.query(query);
+    }
+
+    for answer in read_section(&json["Answer"])? {
+        builder = builder.answer(answer);
+    }
+
+    for authority in read_section(&json["Authority"])? {
+        builder = builder.authority(authority);
+    }
+
+    for additional in read_section(&json["Additional"])? {
+        builder = builder.additional(additional);
+    }
+
+    Ok(builder.to_bytes()?)
+}
+
+/// Reads the records in one section of a JSON response, which is missing
+/// entirely when the section is empty.
+fn read_section(section: &JsonValue) -> Result<Vec<Answer>, Error> {
+    let mut answers = Vec::new();
+
+    for member in section.members() {
+        let invalid = |field: &str| Error::JsonApiError(format!("Record has no {}", field));
+        let name = member["name"].as_str().ok_or_else(|| invalid("name"))?;
+        let type_number = member["type"].as_u16().ok_or_else(|| invalid("type"))?;
+        let ttl = member["TTL"].as_u32().ok_or_else(|| invalid("TTL"))?;
+        let data = member["data"].as_str().ok_or_else(|| invalid("data"))?;
+
+        let qname = Labels::encode(name).map_err(|label| Error::JsonApiError(format!("Invalid label {:?}", label)))?;
+        let type_name = RecordType::from(type_number).mnemonic();
+
+        // Some APIs give the strings of TXT records without quotes, in
+        // which case the data is one string
+        let data = if type_number == TXT::RR_TYPE && ! data.starts_with('"') {
+            format!("\"{}\"", data.replace('\\', "\\\\").replace('"', "\\\""))
+        }
+        else {
+            data.to_owned()
+        };
+
+        match dns::zonefile::parse_record_data(&type_name, &data) {
+            Ok(Some(record)) => {
+                answers.push(Answer::Standard { qname, qclass: QClass::IN, ttl, record });
+            }
+            Ok(None) => {
+                warn!("Skipping {} record for {}, as its data cannot be read", type_name, name);
+            }
+            Err(e) => {
+                warn!("Skipping {} record for {} with invalid data {:?}: {}", type_name, name, data, e);
+            }
+        }
+    }
+
+    Ok(answers)
+}
+
+/// Escapes the characters in a domain name that cannot appear in a query
+/// string as they are.
+fn percent_encode(input: &str) -> String {
+    let mut output = String::with_capacity(input.len());
+
+    for byte in input.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+            output.push(char::from(byte));
+        }
+        else {
+            output.push_str(&format!("%{:02X}", byte));
+        }
+    }
+
+    output
+}
@@ -0,0 +1,272 @@
+//! Holding on to TCP connections between queries, and pipelining queries
+//! down them.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::time::{Duration, Instant};
+
+use log::*;
+
+use dns::{DsoMessage, Opcode};
+use dns::record::EdnsOption;
+
+use super::{Error, TcpTransport};
+
+
+/// A stream that DNS messages can be written to and read from, whichever
+/// type of connection it happens to be.
+pub(crate) trait Stream: Read + Write {}
+
+impl<S: Read + Write> Stream for S {}
+
+
+/// A **kept-alive connection** is a stream that stays open after the
+/// response to a query has been read from it, so the next query can be
+/// sent over it rather than over a new connection. The server can say how
+/// long it will keep the connection open for using the edns-tcp-keepalive
+/// option or a DSO Keepalive TLV; otherwise, it gets kept for a few
+/// seconds, and a new connection gets opened if the server has closed it.
+///
+/// Queries can also be written to the connection before the responses to
+/// the earlier ones have been read. The responses may come back in any
+/// order, so they are matched to their queries by transaction ID, and any
+/// that arrive early are held on to until their queries ask for them.
+///
+/// # References
+///
+/// - [RFC 7766 §6.2.1](https://tools.ietf.org/html/rfc7766#section-6.2.1) —
+///   DNS Transport over TCP, Implementation Requirements (March 2016)
+/// - [RFC 7828 §3.3](https://tools.ietf.org/html/rfc7828#section-3.3) — The
+///   edns-tcp-keepalive EDNS0 Option (April 2016)
+/// - [RFC 8490 §7.1](https://tools.ietf.org/html/rfc8490#section-7.1) — DNS
+///   Stateful Operations (March 2019)
+pub(crate) struct KeptAlive {
+    stream: Box<dyn Stream>,
+    idle_until: Instant,
+
+    /// The length-prefixed messages that have been written ahead of time,
+    /// and not asked for the responses to yet.
+    in_flight: Vec<Vec<u8>>,
+
+    /// The responses that have been read while waiting for a different
+    /// one, by transaction ID.
+    received: HashMap<u16, Vec<u8>>,
+}
+
+impl KeptAlive {
+
+    /// Wraps a newly-opened stream.
+    pub(crate) fn new(stream: Box<dyn Stream>) -> Self {
+        Self {
+            stream,
+            idle_until: Instant::now() + DEFAULT_IDLE_TIMEOUT,
+            in_flight: Vec::new(),
+            received: HashMap::new(),
+        }
+    }
+
+    /// Takes the connection out of the slot, if there is one and its idle
+    /// timeout has not passed yet.
+    pub(crate) fn take_fresh(slot: &RefCell<Option<Self>>) -> Option<Self> {
+        let kept = slot.borrow_mut().take()?;
+
+        if Instant::now() < kept.idle_until {
+            debug!("Reusing kept-alive connection");
+            Some(kept)
+        }
+        else {
+            debug!("Kept-alive connection has timed out");
+            None
+        }
+    }
+
+    /// Writes an already length-prefixed message to the stream without
+    /// reading its response, so that `exchange` can read it later. A
+    /// message whose transaction ID is already in flight is not written,
+    /// as its response could not be told apart from the other one’s.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there’s a network error.
+    pub(crate) fn send_ahead(&mut self, message: &[u8]) -> Result<(), Error> {
+        let transaction_id = prefixed_id(message);
+        if self.in_flight.iter().any(|m| prefixed_id(m) == transaction_id) {
+            debug!("Not pipelining query with ID {:?}, as one is already in flight", transaction_id);
+            return Ok(());
+        }
+
+        self.stream.write_all(message)?;
+        debug!("Pipelined query with ID {:?}", transaction_id);
+        self.in_flight.push(message.to_vec());
+        Ok(())
+    }
+
+    /// Writes an already length-prefixed message to the stream, unless it
+    /// has been written ahead of time, and reads responses until the one
+    /// with its transaction ID comes back.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there’s a network error, or the stream ends
+    /// before the response has been read.
+    pub(crate) fn exchange(&mut self, message: &[u8]) -> Result<Vec<u8>, Error> {
+        let transaction_id = prefixed_id(message);
+
+        if let Some(index) = self.in_flight.iter().position(|m| m == message) {
+            debug!("Query was already sent ahead");
+            self.in_flight.remove(index);
+        }
+        else {
+            self.stream.write_all(message)?;
+            debug!("Wrote all bytes");
+        }
+
+        loop {
+            if let Some(response) = transaction_id.and_then(|id| self.received.remove(&id)) {
+                debug!("Response had already arrived");
+                return Ok(response);
+            }
+
+            let response = TcpTransport::length_prefixed_read(&mut self.stream)?;
+
+            // A response too short to have an ID gets returned as it is,
+            // so that it fails to parse
+            let response_id = match response.get(.. 2) {
+                Some(id) => u16::from_be_bytes([ id[0], id[1] ]),
+                None     => return Ok(response),
+            };
+
+            if transaction_id == Some(response_id) {
+                return Ok(response);
+            }
+            else if self.in_flight.iter().any(|m| prefixed_id(m) == Some(response_id)) {
+                debug!("Holding on to response with ID {} for later", response_id);
+                self.received.insert(response_id, response);
+            }
+            else {
+                warn!("Ignoring response with unexpected ID {}", response_id);
+            }
+        }
+    }
+
+    /// Puts the connection back in the slot, unless the response read from
+    /// it says the server is going to close it. If the server said how long
+    /// it will keep the connection open for, the connection is used for
+    /// that long.
+    pub(crate) fn keep(mut self, slot: &RefCell<Option<Self>>, response_bytes: &[u8]) {
+        let response = match dns::Response::from_bytes(response_bytes) {
+            Ok(r)   => r,
+            Err(_)  => return,
+        };
+
+        let idle_timeout = if response.flags.opcode == Opcode::Dso {
+            // A session that never times out still gets closed eventually,
+            // as the longest timeout is about seven weeks.
+            DsoMessage::from_bytes(response_bytes).ok()
+                .and_then(|message| message.keepalive())
+                .map(|keepalive| Duration::from_millis(u64::from(keepalive.inactivity_timeout)))
+        }
+        else {
+            response.edns_options().into_iter().find_map(|option| match option {
+                EdnsOption::TcpKeepalive(tk) => tk.duration(),
+                _                            => None,
+            })
+        };
+
+        match idle_timeout {
+            Some(timeout) if timeout.is_zero() => {
+                debug!("Server is going to close the connection");
+            }
+            Some(timeout) => {
+                info!("Server will keep the connection open for {:?}", timeout);
+                self.idle_until = Instant::now() + timeout;
+                *slot.borrow_mut() = Some(self);
+            }
+            None => {
+                debug!("Server did not say how long to keep the connection open");
+                self.idle_until = Instant::now() + DEFAULT_IDLE_TIMEOUT;
+                *slot.borrow_mut() = Some(self);
+            }
+        }
+    }
+}
+
+/// Reads the transaction ID of a message that has been prefixed with its
+/// length, which is the two bytes after the length.
+fn prefixed_id(message: &[u8]) -> Option<u16> {
+    message.get(2 .. 4).map(|id| u16::from_be_bytes([ id[0], id[1] ]))
+}
+
+/// How long to keep a connection open for when the server has not said,
+/// which is short, as servers close idle connections quickly
+/// (RFC 7766 §6.2.3).
+const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(2);
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::io::Cursor;
+
+    /// A stream that reads back the bytes it was created with, and throws
+    /// away whatever gets written to it.
+    struct Canned(Cursor<Vec<u8>>);
+
+    impl Read for Canned {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.0.read(buf)
+        }
+    }
+
+    impl Write for Canned {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn two_responses_in_one_write() {
+        let first_query  = [ 0x00, 0x02, 0xaa, 0xaa ];
+        let second_query = [ 0x00, 0x02, 0xbb, 0xbb ];
+
+        let both_responses = vec![ 0x00, 0x04, 0xaa, 0xaa, 0x01, 0x02,
+                                   0x00, 0x04, 0xbb, 0xbb, 0x03, 0x04 ];
+        let mut connection = KeptAlive::new(Box::new(Canned(Cursor::new(both_responses))));
+
+        connection.send_ahead(&first_query).unwrap();
+        connection.send_ahead(&second_query).unwrap();
+
+        assert_eq!(connection.exchange(&first_query).unwrap(),  vec![ 0xaa, 0xaa, 0x01, 0x02 ]);
+        assert_eq!(connection.exchange(&second_query).unwrap(), vec![ 0xbb, 0xbb, 0x03, 0x04 ]);
+    }
+
+    #[test]
+    fn responses_out_of_order() {
+        let first_query  = [ 0x00, 0x02, 0xaa, 0xaa ];
+        let second_query = [ 0x00, 0x02, 0xbb, 0xbb ];
+
+        let both_responses = vec![ 0x00, 0x04, 0xbb, 0xbb, 0x03, 0x04,
+                                   0x00, 0x04, 0xaa, 0xaa, 0x01, 0x02 ];
+        let mut connection = KeptAlive::new(Box::new(Canned(Cursor::new(both_responses))));
+
+        connection.send_ahead(&first_query).unwrap();
+        connection.send_ahead(&second_query).unwrap();
+
+        assert_eq!(connection.exchange(&first_query).unwrap(),  vec![ 0xaa, 0xaa, 0x01, 0x02 ]);
+        assert_eq!(connection.exchange(&second_query).unwrap(), vec![ 0xbb, 0xbb, 0x03, 0x04 ]);
+    }
+
+    #[test]
+    fn stream_ends_early() {
+        let query = [ 0x00, 0x02, 0xaa, 0xaa ];
+        let mut connection = KeptAlive::new(Box::new(Canned(Cursor::new(vec![ 0x00, 0x04, 0xaa ]))));
+
+        assert!(matches!(connection.exchange(&query), Err(Error::TruncatedResponse)));
+    }
+}
@@ -31,17 +31,48 @@ pub use self::auto::AutoTransport;
 mod udp;
 pub use self::udp::UdpTransport;
 
+mod checks;
+pub use self::checks::{ResponseChecks, Mismatch, check_response};
+
 mod tcp;
 pub use self::tcp::TcpTransport;
 
+mod mdns;
+pub use self::mdns::MdnsTransport;
+
 mod tls;
 pub use self::tls::TlsTransport;
 
+mod quic;
+pub use self::quic::QuicTransport;
+
 mod https;
-pub use self::https::HttpsTransport;
+pub use self::https::{HttpsTransport, HttpCaching, HttpHeaders, DohMethod};
+
+#[cfg(feature = "with_https")]
+mod http2;
+
+#[cfg(feature = "with_http3")]
+mod http3;
+
+#[cfg(feature = "with_https")]
+mod json_api;
+
+#[cfg(feature = "with_doh_server")]
+mod doh_server;
+#[cfg(feature = "with_doh_server")]
+pub use self::doh_server::DohServer;
 
 mod error;
 
+mod timeouts;
+pub use self::timeouts::Timeouts;
+
+mod source;
+pub use self::source::Source;
+
+mod keepalive;
+
 mod tls_stream;
 
 pub use self::error::Error;
@@ -58,5 +89,117 @@ pub trait Transport {
     /// receiving data, or the DNS packet in the response contained invalid
     /// bytes and failed to parse, or if there was a protocol-level error for
     /// the TLS and HTTPS transports.
-    fn send(&self, request: &dns::Request) -> Result<dns::Response, Error>;
+    fn send(&self, request: &dns::Request) -> Result<dns::Response, Error> {
+        let bytes_to_send = request.to_bytes().expect("failed to serialise request");
+        let received_bytes = self.send_raw(&bytes_to_send)?;
+        let response = dns::Response::from_bytes(&received_bytes)?;
+        Ok(response)
+    }
+
+    /// Like `send`, but also returns what the caching headers said about the
+    /// response, for the transports that can be served by an HTTP cache.
+    /// Other transports never have anything to say.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as `send`.
+    fn send_via_cache(&self, request: &dns::Request) -> Result<(dns::Response, Option<HttpCaching>), Error> {
+        Ok((self.send(request)?, None))
+    }
+
+    /// Like `send`, but parses the response leniently, so a record that
+    /// fails to parse gets skipped over rather than failing the whole
+    /// response. The errors for the records that were skipped are returned
+    /// alongside the response.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as `send`, except that a parse error is only
+    /// returned if the response’s header could not be read.
+    fn send_lenient(&self, request: &dns::Request) -> Result<dns::PartialResponse, Error> {
+        let bytes_to_send = request.to_bytes().expect("failed to serialise request");
+        let received_bytes = self.send_raw(&bytes_to_send)?;
+        let partial = dns::Response::from_bytes_lenient(&received_bytes)?;
+        Ok(partial)
+    }
+
+    /// Send a DNS message that has already been serialised over the
+    /// network, wait for a response, and return its bytes without
+    /// deserialising them. The message is not checked at all before it gets
+    /// sent.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error` error if there’s an I/O error sending or
+    /// receiving data, or if there was a protocol-level error for the TLS
+    /// and HTTPS transports.
+    fn send_raw(&self, bytes_to_send: &[u8]) -> Result<Vec<u8>, Error>;
+
+    /// Writes a DNS message that has already been serialised to the
+    /// connection without waiting for its response, so that several queries
+    /// can be in flight down one connection at once. The response gets read
+    /// when the same message is later passed to `send_raw`, which does not
+    /// write it again. Only the TCP and TLS transports can pipeline queries;
+    /// the rest do nothing, and any errors are left for `send_raw` to find.
+    fn send_ahead(&self, _bytes_to_send: &[u8]) {}
+
+    /// The number of packets that have arrived while waiting for responses
+    /// that turned out not to be responses to the queries that were sent,
+    /// and were ignored. Only the UDP transport can receive these.
+    fn ignored_packets(&self) -> usize {
+        0
+    }
+
+    /// The number of queries that have been sent again over TCP because
+    /// the response that came back over UDP was truncated. Only the
+    /// automatic transport does this.
+    fn tcp_fallbacks(&self) -> usize {
+        0
+    }
+
+    /// Returns the ways the responses received since this was last called
+    /// did not match their queries, when the transport has been told to
+    /// trust anything and accepted them anyway. Only the UDP transport can
+    /// tell where a response came from, so only it has anything to say; the
+    /// rest of the checks are made with `check_response`.
+    fn take_mismatches(&self) -> Vec<Mismatch> {
+        Vec::new()
+    }
+}
+
+
+/// A transport that has been shared, so that the queries sent through
+/// every copy of it go down the same connection.
+impl<T: Transport + ?Sized> Transport for std::rc::Rc<T> {
+    fn send(&self, request: &dns::Request) -> Result<dns::Response, Error> {
+        (**self).send(request)
+    }
+
+    fn send_via_cache(&self, request: &dns::Request) -> Result<(dns::Response, Option<HttpCaching>), Error> {
+        (**self).send_via_cache(request)
+    }
+
+    fn send_lenient(&self, request: &dns::Request) -> Result<dns::PartialResponse, Error> {
+        (**self).send_lenient(request)
+    }
+
+    fn send_raw(&self, bytes_to_send: &[u8]) -> Result<Vec<u8>, Error> {
+        (**self).send_raw(bytes_to_send)
+    }
+
+    fn send_ahead(&self, bytes_to_send: &[u8]) {
+        (**self).send_ahead(bytes_to_send)
+    }
+
+    fn ignored_packets(&self) -> usize {
+        (**self).ignored_packets()
+    }
+
+    fn tcp_fallbacks(&self) -> usize {
+        (**self).tcp_fallbacks()
+    }
+
+    fn take_mismatches(&self) -> Vec<Mismatch> {
+        (**self).take_mismatches()
+    }
 }
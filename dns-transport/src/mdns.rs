@@ -0,0 +1,103 @@
+use std::cell::Cell;
+use std::io;
+use std::net::{Ipv4Addr, UdpSocket};
+use std::time::{Duration, Instant};
+
+use log::*;
+
+use super::{Transport, Error};
+use super::udp::time_left;
+
+
+/// The **mDNS transport**, which sends DNS wire data to the multicast DNS
+/// group address, and waits for the first unicast reply to come back.
+///
+/// Queries are sent from an ephemeral port, which makes them “legacy unicast”
+/// queries that responders answer directly with a conventional DNS response.
+/// Any host on the network can send to the port, so packets that do not
+/// parse, or are not the response to the query, are ignored and counted.
+///
+/// # References
+///
+/// - [RFC 6762 §6.7](https://tools.ietf.org/html/rfc6762#section-6.7) —
+///   Multicast DNS (February 2013)
+#[derive(Debug, Clone)]
+pub struct MdnsTransport {
+    timeout: Duration,
+    ignored: Cell<usize>,
+}
+
+impl MdnsTransport {
+
+    /// Creates a new mDNS transport that gives up if no responder has
+    /// answered within the given timeout.
+    pub fn new(timeout: Duration) -> Self {
+        Self { timeout, ignored: Cell::new(0) }
+    }
+
+    /// Replaces an error from running out of time while waiting for the
+    /// response with one that says how long was waited, leaving any other
+    /// error as it is.
+    fn timed_out(&self, error: io::Error) -> io::Error {
+        match error.kind() {
+            io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock => {
+                io::Error::new(io::ErrorKind::TimedOut, format!("timed out waiting for an mDNS response after {:?}", self.timeout))
+            }
+            _ => error,
+        }
+    }
+}
+
+/// The IPv4 multicast group that mDNS queries are sent to.
+const MDNS_GROUP: Ipv4Addr = Ipv4Addr::new(224, 0, 0, 251);
+
+/// The port that mDNS responders listen on.
+const MDNS_PORT: u16 = 5353;
+
+
+impl Transport for MdnsTransport {
+    fn send_raw(&self, bytes_to_send: &[u8]) -> Result<Vec<u8>, Error> {
+        info!("Opening UDP socket for mDNS");
+        let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))?;
+        debug!("Opened");
+
+        info!("Sending {} bytes of data to {}:{} over UDP", bytes_to_send.len(), MDNS_GROUP, MDNS_PORT);
+        let written_len = socket.send_to(bytes_to_send, (MDNS_GROUP, MDNS_PORT))?;
+        debug!("Wrote {} bytes", written_len);
+
+        // As the socket is not connected, anything could arrive on it, so
+        // keep reading until a response to our own query turns up. The
+        // timeout is for the whole wait, so that a stream of other packets
+        // cannot keep it going forever.
+        let deadline = Instant::now() + self.timeout;
+        let mut buf = vec![0; 4096];
+        loop {
+            let left = time_left(Some(deadline)).map_err(|e| self.timed_out(e))?;
+            socket.set_read_timeout(left)?;
+
+            info!("Waiting to receive...");
+            let (received_len, responder) = socket.recv_from(&mut buf).map_err(|e| self.timed_out(e))?;
+            info!("Received {} bytes of data from {}", received_len, responder);
+
+            // The transaction ID is in the first two bytes of the header
+            let received = &buf[.. received_len];
+            if received.len() < 2 || received.get(.. 2) != bytes_to_send.get(.. 2) {
+                debug!("Ignoring response with mismatched transaction ID {:?}", received.get(.. 2));
+                self.ignored.set(self.ignored.get() + 1);
+                continue;
+            }
+
+            if let Err(e) = dns::Response::from_bytes(received) {
+                warn!("Ignoring response from {} that failed to parse -> {:?}", responder, e);
+                self.ignored.set(self.ignored.get() + 1);
+                continue;
+            }
+
+            return Ok(received.to_vec());
+        }
+    }
+
+    fn ignored_packets(&self) -> usize {
+        self.ignored.get()
+    }
+}
@@ -0,0 +1,280 @@
+#![cfg_attr(not(feature = "with_quic"), allow(unused))]
+
+use std::cell::RefCell;
+use std::net::{IpAddr, SocketAddr};
+
+use log::*;
+
+use super::{Transport, Error, Timeouts, Source};
+#[cfg(feature = "with_quic")]
+use super::timeouts::within;
+
+
+/// The **QUIC transport**, which sends DNS wire data over an encrypted QUIC
+/// connection, as described in RFC 9250. Each query gets its own stream on
+/// the connection, and the connection is kept open so later queries can be
+/// sent without another handshake.
+///
+/// # References
+///
+/// - [RFC 9250](https://tools.ietf.org/html/rfc9250) — DNS over Dedicated
+///   QUIC Connections (May 2022)
+pub struct QuicTransport {
+    addr: String,
+    timeouts: Timeouts,
+    source: Source,
+
+    /// The client configuration, which holds on to the session tickets
+    /// from earlier connections so that new ones can use 0-RTT.
+    #[cfg(feature = "with_quic")]
+    client_config: quinn::ClientConfig,
+
+    #[cfg(feature = "with_quic")]
+    connection: RefCell<Option<Connected>>,
+}
+
+/// An open connection, along with the runtime that drives it. The runtime
+/// has to outlive the connection, so they are kept together.
+#[cfg(feature = "with_quic")]
+struct Connected {
+    runtime: tokio::runtime::Runtime,
+    endpoint: quinn::Endpoint,
+    connection: quinn::Connection,
+}
+
+impl QuicTransport {
+
+    /// Creates a new QUIC transport that connects to the given host.
+    pub fn new(addr: String) -> Self {
+        Self {
+            addr,
+            timeouts: Timeouts::default(),
+            source: Source::default(),
+            #[cfg(feature = "with_quic")]
+            client_config: client_config(ALPN_DOQ),
+            #[cfg(feature = "with_quic")]
+            connection: RefCell::new(None),
+        }
+    }
+
+    /// Sets how long to wait to connect, including the QUIC handshake, and
+    /// how long to wait for each response.
+    pub fn with_timeouts(mut self, timeouts: Timeouts) -> Self {
+        self.timeouts = timeouts;
+        self
+    }
+
+    /// Sets the local address and interface to send from.
+    pub fn with_source(mut self, source: Source) -> Self {
+        self.source = source;
+        self
+    }
+}
+
+
+impl Transport for QuicTransport {
+
+    #[cfg(feature = "with_quic")]
+    fn send_raw(&self, bytes_to_send: &[u8]) -> Result<Vec<u8>, Error> {
+        // The message ID has to be zero over QUIC, as the stream already
+        // says which query a response is for (RFC 9250 §4.2.1). The real
+        // ID is put back into the response so it still matches.
+        let mut message = bytes_to_send.to_vec();
+        let transaction_id = [ message[0], message[1] ];
+        message[0] = 0;
+        message[1] = 0;
+
+        // Messages are prefixed with their length, as over TCP
+        // (RFC 9250 §4.2)
+        super::TcpTransport::prefix_with_length(&mut message);
+
+        let mut slot = self.connection.borrow_mut();
+
+        if let Some(connected) = slot.as_ref() {
+            info!("Sending {} bytes of data to {} over reused QUIC connection", message.len(), self.addr);
+            match connected.exchange(&message, self.timeouts) {
+                Ok(mut read_bytes) => {
+                    read_bytes[.. 2].copy_from_slice(&transaction_id);
+                    return Ok(read_bytes);
+                }
+                Err(e) => {
+                    warn!("Reused connection failed ({:?}), reconnecting", e);
+                    *slot = None;
+                }
+            }
+        }
+
+        info!("Opening QUIC connection");
+        let (domain, port) = domain_and_port(&self.addr, 853)?;
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        let (endpoint, connection) = runtime.block_on(within(self.timeouts.connect, connect(&self.client_config, domain, port, &self.source)))
+            .map_err(|e| self.timeouts.connect_error(e))?;
+        let connected = Connected { runtime, endpoint, connection };
+
+        info!("Sending {} bytes of data to {} over QUIC", message.len(), self.addr);
+        let mut read_bytes = connected.exchange(&message, self.timeouts)?;
+        read_bytes[.. 2].copy_from_slice(&transaction_id);
+
+        *slot = Some(connected);
+        Ok(read_bytes)
+    }
+
+    #[cfg(not(feature = "with_quic"))]
+    fn send_raw(&self, bytes_to_send: &[u8]) -> Result<Vec<u8>, Error> {
+        unreachable!("QUIC feature disabled")
+    }
+}
+
+
+#[cfg(feature = "with_quic")]
+impl Connected {
+
+    /// Sends a message on a new stream, closing the sending side to show
+    /// that there are no more messages (RFC 9250 §4.2), and reads the
+    /// length-prefixed response that comes back on the same stream, within
+    /// the read timeout.
+    fn exchange(&self, message: &[u8], timeouts: Timeouts) -> Result<Vec<u8>, Error> {
+        self.runtime.block_on(within(timeouts.read, async {
+            let (mut send, mut recv) = self.connection.open_bi().await?;
+            send.write_all(message).await?;
+            send.finish().await?;
+
+            let received = recv.read_to_end(usize::from(u16::MAX) + 2).await?;
+            debug!("Received {} bytes of data", received.len());
+
+            match received.get(2 ..) {
+                Some(response) if received.len() >= 4 => {
+                    let length = usize::from(u16::from_be_bytes([ received[0], received[1] ]));
+                    response.get(.. length).map(<[u8]>::to_vec).ok_or(Error::TruncatedResponse)
+                }
+                _ => Err(Error::TruncatedResponse),
+            }
+        })).map_err(|e| timeouts.read_error(e))
+    }
+}
+
+#[cfg(feature = "with_quic")]
+impl Drop for Connected {
+    fn drop(&mut self) {
+        // Error code 0 is DOQ_NO_ERROR (RFC 9250 §4.3)
+        self.connection.close(0_u32.into(), b"");
+        let endpoint = &self.endpoint;
+        self.runtime.block_on(endpoint.wait_idle());
+    }
+}
+
+
+/// Creates the configuration for QUIC connections that negotiate the given
+/// ALPN token, allowing 0-RTT. Connections made with the same configuration
+/// share its store of session tickets.
+#[cfg(feature = "with_quic")]
+pub(crate) fn client_config(alpn: &[u8]) -> quinn::ClientConfig {
+    use std::sync::Arc;
+
+    let mut roots = rustls_quic::RootCertStore::empty();
+    roots.add_trust_anchors(webpki_roots_quic::TLS_SERVER_ROOTS.iter().map(|anchor| {
+        rustls_quic::OwnedTrustAnchor::from_subject_spki_name_constraints(anchor.subject, anchor.spki, anchor.name_constraints)
+    }));
+
+    let mut crypto = rustls_quic::ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    crypto.alpn_protocols = vec![ alpn.to_vec() ];
+    crypto.enable_early_data = true;
+
+    quinn::ClientConfig::new(Arc::new(crypto))
+}
+
+/// Opens a QUIC connection to the given host from the source. When the host
+/// has been connected to before with the same configuration, the connection
+/// is attempted with 0-RTT, so the first request can be sent alongside the
+/// handshake. This has to be run inside a Tokio runtime.
+#[cfg(feature = "with_quic")]
+pub(crate) async fn connect(config: &quinn::ClientConfig, domain: &str, port: u16, source: &Source) -> Result<(quinn::Endpoint, quinn::Connection), Error> {
+    let server_addr = tokio::net::lookup_host((domain, port)).await?
+        .find(|address| source.reaches(address))
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "no address for QUIC server"))?;
+    debug!("Connecting to {} using domain {:?}", server_addr, domain);
+
+    let socket = source.udp_socket(server_addr)?;
+    let mut endpoint = quinn::Endpoint::new(quinn::EndpointConfig::default(), None, socket, std::sync::Arc::new(quinn::TokioRuntime))?;
+    endpoint.set_default_client_config(config.clone());
+
+    let connecting = endpoint.connect(server_addr, domain)?;
+    let connection = match connecting.into_0rtt() {
+        Ok((connection, _accepted)) => {
+            debug!("Connected using 0-RTT");
+            connection
+        }
+        Err(connecting) => {
+            connecting.await?
+        }
+    };
+
+    debug!("Connected");
+    Ok((endpoint, connection))
+}
+
+/// Splits an address into its host and its port, using the given port if
+/// there isn’t one. IPv6 addresses with ports are written in brackets.
+///
+/// # Errors
+///
+/// Returns an error if the port is not a number from 0 to 65535.
+pub(crate) fn domain_and_port(addr: &str, default_port: u16) -> Result<(&str, u16), Error> {
+    if addr.parse::<IpAddr>().is_ok() {
+        return Ok((addr, default_port));
+    }
+
+    if let Ok(socket_addr) = addr.parse::<SocketAddr>() {
+        let host = addr.rsplit_once(':').map_or(addr, |(host, _)| host);
+        return Ok((host.trim_start_matches('[').trim_end_matches(']'), socket_addr.port()));
+    }
+
+    match addr.split_once(':') {
+        Some((domain, port)) => {
+            let port = port.parse().map_err(|_| {
+                std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("invalid port number {:?}", port))
+            })?;
+
+            Ok((domain, port))
+        }
+        None => {
+            Ok((addr, default_port))
+        }
+    }
+}
+
+/// The ALPN token that identifies DNS over QUIC (RFC 9250 §4.1.1).
+#[cfg(feature = "with_quic")]
+const ALPN_DOQ: &[u8] = b"doq";
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn default_port() {
+        assert_eq!(domain_and_port("dns.lookup.dog", 853).unwrap(), ("dns.lookup.dog", 853));
+    }
+
+    #[test]
+    fn given_port() {
+        assert_eq!(domain_and_port("dns.lookup.dog:8853", 853).unwrap(), ("dns.lookup.dog", 8853));
+    }
+
+    #[test]
+    fn ipv6_with_port() {
+        assert_eq!(domain_and_port("[2001:db8::1]:8853", 853).unwrap(), ("2001:db8::1", 8853));
+    }
+
+    #[test]
+    fn port_out_of_range() {
+        assert!(matches!(domain_and_port("dns.lookup.dog:99999", 853), Err(Error::NetworkError(_))));
+    }
+}
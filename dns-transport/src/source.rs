@@ -0,0 +1,128 @@
+//! Choosing the local address and network interface that queries get sent
+//! from, for hosts with more than one way out to the network.
+
+use std::io;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, TcpStream, UdpSocket};
+use std::time::Duration;
+
+use socket2::{Domain, Protocol, Socket, Type};
+
+
+/// Where a transport sends its queries from. By default, the system picks
+/// the address, port, and interface based on its routing table.
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct Source {
+
+    /// The local address to bind sockets to. A port of zero lets the
+    /// system pick one.
+    pub address: Option<SocketAddr>,
+
+    /// The name of the network interface to send queries out of, whatever
+    /// the routing table says. This is only supported on Linux, where it
+    /// uses `SO_BINDTODEVICE`.
+    pub interface: Option<String>,
+}
+
+impl Source {
+
+    /// Whether the system gets to pick everything, in which case sockets
+    /// can be opened the usual way.
+    pub fn is_default(&self) -> bool {
+        self.address.is_none() && self.interface.is_none()
+    }
+
+    /// Whether a socket bound to the source address could talk to the given
+    /// server, which it cannot if they are in different address families.
+    pub fn reaches(&self, server: &SocketAddr) -> bool {
+        self.address.map_or(true, |address| address.is_ipv4() == server.is_ipv4())
+    }
+
+    /// Opens a UDP socket for sending queries to the given server, bound to
+    /// the source address and interface.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the socket cannot be bound.
+    pub fn udp_socket(&self, server: SocketAddr) -> io::Result<UdpSocket> {
+        if self.is_default() {
+            let socket = if server.is_ipv4() { UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))? }
+                                        else { UdpSocket::bind((Ipv6Addr::UNSPECIFIED, 0))? };
+            return Ok(socket);
+        }
+
+        let socket = self.socket(server, Type::DGRAM, Protocol::UDP)?;
+        Ok(socket.into())
+    }
+
+    /// Opens a TCP connection to the given server from the source address
+    /// and interface, giving up after the timeout if there is one.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the socket cannot be bound, or the connection
+    /// fails or takes too long.
+    pub(crate) fn tcp_connect(&self, server: SocketAddr, timeout: Option<Duration>) -> io::Result<TcpStream> {
+        if self.is_default() {
+            return match timeout {
+                Some(timeout)  => TcpStream::connect_timeout(&server, timeout),
+                None           => TcpStream::connect(server),
+            };
+        }
+
+        let socket = self.tcp_socket(server)?;
+        match timeout {
+            Some(timeout)  => socket.connect_timeout(&server.into(), timeout)?,
+            None           => socket.connect(&server.into())?,
+        }
+
+        Ok(socket.into())
+    }
+
+    /// Creates a TCP socket for talking to the given server, bound to the
+    /// source address and interface but not yet connected, for when
+    /// something else does the connecting.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the socket cannot be bound.
+    pub(crate) fn tcp_socket(&self, server: SocketAddr) -> io::Result<Socket> {
+        self.socket(server, Type::STREAM, Protocol::TCP)
+    }
+
+    /// Creates a socket of the given type in the server’s address family,
+    /// and binds it to the interface and then the address, if either has
+    /// been given.
+    fn socket(&self, server: SocketAddr, kind: Type, protocol: Protocol) -> io::Result<Socket> {
+        if ! self.reaches(&server) {
+            let message = format!("cannot reach {} from source address {}", server, self.address.unwrap());
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, message));
+        }
+
+        let socket = Socket::new(Domain::for_address(server), kind, Some(protocol))?;
+
+        if let Some(interface) = &self.interface {
+            bind_device(&socket, interface)?;
+        }
+
+        if let Some(address) = self.address {
+            socket.bind(&address.into())?;
+        }
+
+        Ok(socket)
+    }
+}
+
+/// Binds the socket to the network interface with the given name, so that
+/// its packets go out of that interface whatever the routing table says.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn bind_device(socket: &Socket, interface: &str) -> io::Result<()> {
+    socket.bind_device(Some(interface.as_bytes()))
+        .map_err(|e| io::Error::new(e.kind(), format!("cannot bind to interface {:?}: {}", interface, e)))
+}
+
+/// Binding to an interface is not available on this platform.
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn bind_device(_socket: &Socket, interface: &str) -> io::Result<()> {
+    let message = format!("cannot bind to interface {:?}, as this is only supported on Linux", interface);
+    Err(io::Error::new(io::ErrorKind::Other, message))
+}
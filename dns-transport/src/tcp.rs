@@ -1,11 +1,14 @@
+use std::cell::RefCell;
 use std::convert::TryFrom;
-use std::net::TcpStream;
 use std::io::{Read, Write};
 
 use log::*;
 
-use dns::{Request, Response};
-use super::{Transport, Error};
+use dns::{AxfrParser, DsoMessage, Request, Response};
+
+use super::{Transport, Error, Timeouts, Source};
+use super::keepalive::{KeptAlive, Stream};
+use super::quic::domain_and_port;
 
 
 /// The **TCP transport**, which sends DNS wire data over a TCP stream.
@@ -18,46 +21,176 @@ use super::{Transport, Error};
 ///   TCP, Implementation Requirements (March 2016)
 pub struct TcpTransport {
     addr: String,
+    timeouts: Timeouts,
+    source: Source,
+    kept_alive: RefCell<Option<KeptAlive>>,
 }
 
 impl TcpTransport {
 
     /// Creates a new TCP transport that connects to the given host.
     pub fn new(addr: String) -> Self {
-        Self { addr }
+        Self { addr, timeouts: Timeouts::default().for_stream(), source: Source::default(), kept_alive: RefCell::new(None) }
+    }
+
+    /// Sets how long to wait to connect, and how long to wait for each
+    /// response, which is limited to ten seconds if no timeout is given.
+    pub fn with_timeouts(mut self, timeouts: Timeouts) -> Self {
+        self.timeouts = timeouts.for_stream();
+        self
+    }
+
+    /// Sets the local address and interface to connect from.
+    pub fn with_source(mut self, source: Source) -> Self {
+        self.source = source;
+        self
     }
 }
 
 
 impl Transport for TcpTransport {
-    fn send(&self, request: &Request) -> Result<Response, Error> {
-        info!("Opening TCP stream");
-        let mut stream =
-            if self.addr.contains(':') {
-                TcpStream::connect(&*self.addr)?
-            }
-            else {
-                TcpStream::connect((&*self.addr, 53))?
-            };
-        debug!("Opened");
-
+    fn send_raw(&self, bytes_to_send: &[u8]) -> Result<Vec<u8>, Error> {
         // The message is prepended with the length when sent over TCP,
         // so the server knows how long it is (RFC 1035 §4.2.2)
-        let mut bytes_to_send = request.to_bytes().expect("failed to serialise request");
+        let mut bytes_to_send = bytes_to_send.to_vec();
         Self::prefix_with_length(&mut bytes_to_send);
 
+        if let Some(mut connection) = KeptAlive::take_fresh(&self.kept_alive) {
+            info!("Sending {} bytes of data to {:?} over kept-alive TCP", bytes_to_send.len(), self.addr);
+            match connection.exchange(&bytes_to_send).map_err(|e| self.timeouts.read_error(e)) {
+                Ok(read_bytes) => {
+                    connection.keep(&self.kept_alive, &read_bytes);
+                    return Ok(read_bytes);
+                }
+                Err(e) => {
+                    warn!("Kept-alive connection failed ({:?}), reconnecting", e);
+                }
+            }
+        }
+
+        let mut connection = KeptAlive::new(self.connect()?);
+
         info!("Sending {} bytes of data to {:?} over TCP", bytes_to_send.len(), self.addr);
-        let written_len = stream.write(&bytes_to_send)?;
-        debug!("Wrote {} bytes", written_len);
+        let read_bytes = connection.exchange(&bytes_to_send).map_err(|e| self.timeouts.read_error(e))?;
+        connection.keep(&self.kept_alive, &read_bytes);
+        Ok(read_bytes)
+    }
+
+    fn send_ahead(&self, bytes_to_send: &[u8]) {
+        let mut bytes_to_send = bytes_to_send.to_vec();
+        Self::prefix_with_length(&mut bytes_to_send);
 
-        let read_bytes = Self::length_prefixed_read(&mut stream)?;
-        let response = Response::from_bytes(&read_bytes)?;
-        Ok(response)
+        let mut connection = match KeptAlive::take_fresh(&self.kept_alive) {
+            Some(connection) => connection,
+            None => match self.connect() {
+                Ok(stream) => KeptAlive::new(stream),
+                Err(e) => {
+                    warn!("Failed to connect to send query ahead ({:?})", e);
+                    return;
+                }
+            },
+        };
+
+        info!("Sending {} bytes of data to {:?} ahead over TCP", bytes_to_send.len(), self.addr);
+        match connection.send_ahead(&bytes_to_send) {
+            Ok(()) => {
+                *self.kept_alive.borrow_mut() = Some(connection);
+            }
+            Err(e) => {
+                warn!("Failed to send query ahead ({:?})", e);
+            }
+        }
     }
 }
 
 impl TcpTransport {
 
+    /// Sends a DSO request, and reads the DSO message that comes back. If
+    /// the response has a Keepalive TLV, the connection is kept open for as
+    /// long as its inactivity timeout says, so that the session can be used
+    /// by the next request.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there’s a network error, or the response is not
+    /// a DSO message, which usually means the server does not support DSO.
+    pub fn send_dso(&self, request: &DsoMessage) -> Result<DsoMessage, Error> {
+        let bytes_to_send = request.to_bytes().expect("failed to serialise request");
+        let received_bytes = self.send_raw(&bytes_to_send)?;
+        Ok(DsoMessage::from_bytes(&received_bytes)?)
+    }
+
+    /// Sends a zone transfer request over a new connection, and reads
+    /// every message of the transfer that comes back. The transfer ends
+    /// with the message containing the closing SOA record, or with the
+    /// first message that the `AxfrParser` rejects (RFC 5936 §2.2).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there’s a network error, or a message fails to
+    /// parse.
+    pub fn zone_transfer(&self, request: &Request) -> Result<Vec<Response>, Error> {
+        let bytes_to_send = request.to_bytes().expect("failed to serialise request");
+        self.zone_transfer_checked(bytes_to_send, |message| Ok(message.to_vec()))
+    }
+
+    /// Like `zone_transfer`, but sends a request that has already been
+    /// serialised, and passes every message that comes back through the
+    /// given function before parsing it, which can reject the message or
+    /// return a changed copy of it. This is how signed transfers have the
+    /// signature on each message checked and removed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there’s a network error, a message fails to
+    /// parse, or the function returns an error.
+    pub fn zone_transfer_checked(&self, mut bytes_to_send: Vec<u8>, mut check: impl FnMut(&[u8]) -> Result<Vec<u8>, Error>) -> Result<Vec<Response>, Error> {
+        Self::prefix_with_length(&mut bytes_to_send);
+
+        info!("Opening TCP stream for zone transfer");
+        let (host, port) = domain_and_port(&self.addr, 53)?;
+        let mut stream = self.timeouts.connect_tcp(host, port, &self.source)?;
+        self.timeouts.connected(&stream)?;
+
+        stream.write_all(&bytes_to_send)?;
+
+        let mut responses = Vec::new();
+        let mut parser = AxfrParser::new();
+
+        loop {
+            let mut length = [0; 2];
+            stream.read_exact(&mut length)?;
+
+            let mut message = vec![0; usize::from(u16::from_be_bytes(length))];
+            stream.read_exact(&mut message)?;
+
+            let response = Response::from_bytes(&check(&message)?)?;
+
+            // A message that the parser rejects ends the transfer, but it
+            // still gets returned, so the caller can find out what was
+            // wrong with it by parsing the messages again.
+            let result = parser.push(&response);
+            debug!("Read zone transfer message {} ({:?})", parser.message_count(), result.as_ref().map(Vec::len));
+            responses.push(response);
+
+            if result.is_err() || parser.is_finished() {
+                return Ok(responses);
+            }
+        }
+    }
+
+    /// Opens a new TCP stream to the host, using port 53 if the host did
+    /// not say which one.
+    fn connect(&self) -> Result<Box<dyn Stream>, Error> {
+        info!("Opening TCP stream");
+        let (host, port) = domain_and_port(&self.addr, 53)?;
+        let stream = self.timeouts.connect_tcp(host, port, &self.source)?;
+        self.timeouts.connected(&stream)?;
+
+        debug!("Opened");
+        Ok(Box::new(stream))
+    }
+
     /// Mutate the given byte buffer, prefixing it with its own length as a
     /// big-endian `u16`.
     pub(crate) fn prefix_with_length(bytes: &mut Vec<u8>) {
@@ -71,8 +204,9 @@ impl TcpTransport {
 
     /// Reads from the given I/O source as many times as necessary to read a
     /// length-prefixed stream of bytes. The first two bytes are taken as a
-    /// big-endian `u16` to determine the length. Then, that many bytes are
-    /// read from the source.
+    /// big-endian `u16` to determine the length. Then, exactly that many
+    /// bytes are read from the source, so that anything after them, such as
+    /// the next pipelined response, is left to be read next time.
     ///
     /// # Errors
     ///
@@ -81,48 +215,30 @@ impl TcpTransport {
     pub(crate) fn length_prefixed_read(stream: &mut impl Read) -> Result<Vec<u8>, Error> {
         info!("Waiting to receive...");
 
-        let mut buf = [0; 4096];
-        let mut read_len = stream.read(&mut buf[..])?;
+        let mut length = [0; 2];
+        read_all(stream, &mut length)?;
 
-        if read_len == 0 {
-            warn!("Received no bytes!");
-            return Err(Error::TruncatedResponse);
-        }
-        else if read_len == 1 {
-            info!("Received one byte of data");
-            let second_read_len = stream.read(&mut buf[1..])?;
-            if second_read_len == 0 {
-                warn!("Received no bytes the second time!");
-                return Err(Error::TruncatedResponse);
-            }
+        let total_len = u16::from_be_bytes(length);
+        debug!("We need to read {} bytes total", total_len);
 
-            read_len += second_read_len;
-        }
-        else {
-            info!("Received {} bytes of data", read_len);
-        }
+        let mut buf = vec![0; usize::from(total_len)];
+        read_all(stream, &mut buf)?;
+        info!("Received {} bytes of data", total_len);
 
-        let total_len = u16::from_be_bytes([buf[0], buf[1]]);
-        if read_len - 2 == usize::from(total_len) {
-            debug!("We have enough bytes");
-            return Ok(buf[2..read_len].to_vec());
-        }
+        Ok(buf)
+    }
+}
 
-        debug!("We need to read {} bytes total", total_len);
-        let mut combined_buffer = buf[2..read_len].to_vec();
-        while combined_buffer.len() < usize::from(total_len) {
-            let mut extend_buf = [0; 4096];
-            let extend_len = stream.read(&mut extend_buf[..])?;
-            info!("Received further {} bytes of data (of {})", extend_len, total_len);
-
-            if read_len == 0 {
-                warn!("Read zero bytes!");
-                return Err(Error::TruncatedResponse);
-            }
 
-            combined_buffer.extend(&extend_buf[0 .. extend_len]);
+/// Fills the buffer from the stream, treating the stream ending early as
+/// the response having been truncated.
+fn read_all(stream: &mut impl Read, buf: &mut [u8]) -> Result<(), Error> {
+    match stream.read_exact(buf) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+            warn!("Stream ended after fewer than {} bytes", buf.len());
+            Err(Error::TruncatedResponse)
         }
-
-        Ok(combined_buffer)
+        Err(e) => Err(e.into()),
     }
 }
@@ -0,0 +1,130 @@
+//! Limiting how long transports wait to connect, and how long they wait for
+//! a response once they have.
+
+use std::io;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use log::*;
+
+use super::{Error, Source};
+
+
+/// How long to wait for each response down a TCP or TLS stream, when no
+/// timeout has been given. Queries get pipelined down these streams, so a
+/// response that gets lost or cannot be matched up would otherwise leave
+/// dog waiting forever.
+const DEFAULT_STREAM_READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long a transport waits for each stage of a request before giving up.
+/// The stages are limited separately, so that a slow handshake can be told
+/// apart from a slow nameserver. A stage without a limit takes as long as
+/// it takes.
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Default)]
+pub struct Timeouts {
+
+    /// How long to wait for a connection to open, including any TLS or QUIC
+    /// handshake. Queries over UDP have no connection to wait for.
+    pub connect: Option<Duration>,
+
+    /// How long to wait for the response once the request has been sent.
+    pub read: Option<Duration>,
+}
+
+impl Timeouts {
+
+    /// Returns these timeouts with the read timeout filled in, if there is
+    /// not one already, for transports that send queries down a stream.
+    pub(crate) fn for_stream(self) -> Self {
+        Self { read: self.read.or(Some(DEFAULT_STREAM_READ_TIMEOUT)), ..self }
+    }
+
+    /// Opens a TCP connection to the given host from the given source,
+    /// trying each of its addresses in turn until one of them connects
+    /// within the connect timeout. Reads and writes on the stream are
+    /// limited by the connect timeout too, so that it covers any handshake,
+    /// until `connected` is called.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the host’s addresses cannot be looked up, or none
+    /// of them could be connected to.
+    pub(crate) fn connect_tcp(self, host: &str, port: u16, source: &Source) -> Result<TcpStream, Error> {
+        if self.connect.is_none() && source.is_default() {
+            return Ok(TcpStream::connect((host, port))?);
+        }
+
+        let mut last_error = None;
+        for address in (host, port).to_socket_addrs()?.filter(|address| source.reaches(address)) {
+            match source.tcp_connect(address, self.connect) {
+                Ok(stream) => {
+                    stream.set_read_timeout(self.connect)?;
+                    stream.set_write_timeout(self.connect)?;
+                    return Ok(stream);
+                }
+                Err(e) => {
+                    debug!("Failed to connect to {} -> {:?}", address, e);
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        let error = last_error.unwrap_or_else(|| io::Error::new(io::ErrorKind::NotFound, "host has no usable addresses"));
+        Err(self.connect_error(error.into()))
+    }
+
+    /// Switches a stream over from the connect timeout to the read timeout,
+    /// once it has connected and any handshake is over.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the timeouts cannot be set.
+    pub(crate) fn connected(self, stream: &TcpStream) -> Result<(), Error> {
+        stream.set_read_timeout(self.read)?;
+        stream.set_write_timeout(self.read)?;
+        Ok(())
+    }
+
+    /// Replaces an error from running out of time while connecting with
+    /// one that says so, leaving any other error as it is.
+    pub(crate) fn connect_error(self, error: Error) -> Error {
+        describe(error, "connecting", self.connect)
+    }
+
+    /// Replaces an error from running out of time while waiting for the
+    /// response with one that says so, leaving any other error as it is.
+    pub(crate) fn read_error(self, error: Error) -> Error {
+        describe(error, "waiting for a response", self.read)
+    }
+}
+
+/// Runs a future to completion, giving up with a timeout error if the limit
+/// passes first. This has to be run inside a Tokio runtime.
+#[cfg(any(feature = "with_https", feature = "with_quic"))]
+pub(crate) async fn within<T>(limit: Option<Duration>, future: impl std::future::Future<Output=Result<T, Error>>) -> Result<T, Error> {
+    match limit {
+        Some(limit) => {
+            tokio::time::timeout(limit, future).await
+                .unwrap_or_else(|_| Err(io::Error::from(io::ErrorKind::TimedOut).into()))
+        }
+        None => {
+            future.await
+        }
+    }
+}
+
+/// Replaces an error that came from running out of time with one that says
+/// what was being waited for, and for how long. A read that runs out of
+/// time can fail with either of two kinds of error, depending on the
+/// platform.
+fn describe(error: Error, stage: &str, limit: Option<Duration>) -> Error {
+    match (error, limit) {
+        (Error::NetworkError(e), Some(limit)) if matches!(e.kind(), io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock) => {
+            let message = format!("timed out {} after {:?}", stage, limit);
+            Error::NetworkError(io::Error::new(io::ErrorKind::TimedOut, message))
+        }
+        (error, _) => {
+            error
+        }
+    }
+}
@@ -1,26 +1,46 @@
 #![cfg_attr(not(feature = "tls"), allow(unused))]
 
+use std::cell::RefCell;
 use std::net::TcpStream;
-use std::io::Write;
 
 use log::*;
 
-use dns::{Request, Response};
-use super::{Transport, Error, TcpTransport};
+use dns::DsoMessage;
+
+use super::{Transport, Error, TcpTransport, Timeouts, Source};
+use super::keepalive::{KeptAlive, Stream};
 use super::tls_stream::TlsStream;
+use super::quic::domain_and_port;
 
 
 /// The **TLS transport**, which sends DNS wire data using TCP through an
 /// encrypted TLS connection.
 pub struct TlsTransport {
     addr: String,
+    timeouts: Timeouts,
+    source: Source,
+    kept_alive: RefCell<Option<KeptAlive>>,
 }
 
 impl TlsTransport {
 
     /// Creates a new TLS transport that connects to the given host.
     pub fn new(addr: String) -> Self {
-        Self { addr }
+        Self { addr, timeouts: Timeouts::default().for_stream(), source: Source::default(), kept_alive: RefCell::new(None) }
+    }
+
+    /// Sets how long to wait to connect, including the TLS handshake, and
+    /// how long to wait for each response, which is limited to ten
+    /// seconds if no timeout is given.
+    pub fn with_timeouts(mut self, timeouts: Timeouts) -> Self {
+        self.timeouts = timeouts.for_stream();
+        self
+    }
+
+    /// Sets the local address and interface to connect from.
+    pub fn with_source(mut self, source: Source) -> Self {
+        self.source = source;
+        self
     }
 }
 
@@ -29,47 +49,95 @@ impl TlsTransport {
 impl Transport for TlsTransport {
 
     #[cfg(feature = "with_tls")]
-    fn send(&self, request: &Request) -> Result<Response, Error> {
-        info!("Opening TLS socket");
-
-        let domain = self.sni_domain();
-        info!("Connecting using domain {:?}", domain);
-        let mut stream =
-            if self.addr.contains(':') {
-                let mut parts = self.addr.split(":");
-                let domain = parts.nth(0).unwrap();
-                let port = parts.last().unwrap().parse::<u16>().expect("Invalid port number");
+    fn send_raw(&self, bytes_to_send: &[u8]) -> Result<Vec<u8>, Error> {
+        // The message is prepended with the length when sent over TCP,
+        // so the server knows how long it is (RFC 1035 §4.2.2)
+        let mut bytes_to_send = bytes_to_send.to_vec();
+        TcpTransport::prefix_with_length(&mut bytes_to_send);
 
-                Self::stream(domain, port)?
+        if let Some(mut connection) = KeptAlive::take_fresh(&self.kept_alive) {
+            info!("Sending {} bytes of data to {} over kept-alive TLS", bytes_to_send.len(), self.addr);
+            match connection.exchange(&bytes_to_send).map_err(|e| self.timeouts.read_error(e)) {
+                Ok(read_bytes) => {
+                    connection.keep(&self.kept_alive, &read_bytes);
+                    return Ok(read_bytes);
+                }
+                Err(e) => {
+                    warn!("Kept-alive connection failed ({:?}), reconnecting", e);
+                }
             }
-            else {
-                Self::stream(&*self.addr, 853)?
-            };
+        }
 
+        let mut connection = KeptAlive::new(self.connect()?);
 
-        debug!("Connected");
+        info!("Sending {} bytes of data to {} over TLS", bytes_to_send.len(), self.addr);
+        let read_bytes = connection.exchange(&bytes_to_send).map_err(|e| self.timeouts.read_error(e))?;
+        connection.keep(&self.kept_alive, &read_bytes);
+        Ok(read_bytes)
+    }
 
-        // The message is prepended with the length when sent over TCP,
-        // so the server knows how long it is (RFC 1035 §4.2.2)
-        let mut bytes_to_send = request.to_bytes().expect("failed to serialise request");
+    #[cfg(feature = "with_tls")]
+    fn send_ahead(&self, bytes_to_send: &[u8]) {
+        let mut bytes_to_send = bytes_to_send.to_vec();
         TcpTransport::prefix_with_length(&mut bytes_to_send);
 
-        info!("Sending {} bytes of data to {} over TLS", bytes_to_send.len(), self.addr);
-        stream.write_all(&bytes_to_send)?;
-        debug!("Wrote all bytes");
-
-        let read_bytes = TcpTransport::length_prefixed_read(&mut stream)?;
-        let response = Response::from_bytes(&read_bytes)?;
-        Ok(response)
+        let mut connection = match KeptAlive::take_fresh(&self.kept_alive) {
+            Some(connection) => connection,
+            None => match self.connect() {
+                Ok(stream) => KeptAlive::new(stream),
+                Err(e) => {
+                    warn!("Failed to connect to send query ahead ({:?})", e);
+                    return;
+                }
+            },
+        };
+
+        info!("Sending {} bytes of data to {} ahead over TLS", bytes_to_send.len(), self.addr);
+        match connection.send_ahead(&bytes_to_send) {
+            Ok(()) => {
+                *self.kept_alive.borrow_mut() = Some(connection);
+            }
+            Err(e) => {
+                warn!("Failed to send query ahead ({:?})", e);
+            }
+        }
     }
 
     #[cfg(not(feature = "with_tls"))]
-    fn send(&self, request: &Request) -> Result<Response, Error> {
+    fn send_raw(&self, bytes_to_send: &[u8]) -> Result<Vec<u8>, Error> {
         unreachable!("TLS feature disabled")
     }
 }
 
 impl TlsTransport {
+
+    /// Sends a DSO request over TLS, and reads the DSO message that comes
+    /// back, as `TcpTransport::send_dso` does.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there’s a network or TLS error, or the response
+    /// is not a DSO message.
+    pub fn send_dso(&self, request: &DsoMessage) -> Result<DsoMessage, Error> {
+        let bytes_to_send = request.to_bytes().expect("failed to serialise request");
+        let received_bytes = self.send_raw(&bytes_to_send)?;
+        Ok(DsoMessage::from_bytes(&received_bytes)?)
+    }
+
+    /// Opens a new TLS connection to the host.
+    #[cfg(feature = "with_tls")]
+    fn connect(&self) -> Result<Box<dyn Stream>, Error> {
+        info!("Opening TLS socket");
+
+        let domain = self.sni_domain();
+        info!("Connecting using domain {:?}", domain);
+        let (host, port) = domain_and_port(&self.addr, 853)?;
+        let stream: Box<dyn Stream> = Box::new(Self::stream(host, port, self.timeouts, &self.source)?);
+
+        debug!("Connected");
+        Ok(stream)
+    }
+
     fn sni_domain(&self) -> &str {
         if let Some(colon_index) = self.addr.find(':') {
             &self.addr[.. colon_index]
@@ -2,17 +2,37 @@ use std::net::TcpStream;
 use super::Error;
 use super::HttpsTransport;
 use super::TlsTransport;
+use super::{Timeouts, Source};
 
+/// Connects from the source and performs the TLS handshake, both within
+/// the connect timeout, then switches the stream over to the read timeout.
 #[cfg(any(feature = "with_nativetls", feature = "with_nativetls_vendored"))]
-fn stream_nativetls(domain: &str, port: u16) -> Result<native_tls::TlsStream<TcpStream>, Error> {
+fn stream_nativetls(domain: &str, port: u16, timeouts: Timeouts, source: &Source) -> Result<native_tls::TlsStream<TcpStream>, Error> {
     let connector = native_tls::TlsConnector::new()?;
-    let stream = TcpStream::connect((domain, port))?;
-    Ok(connector.connect(domain, stream)?)
+    let stream = timeouts.connect_tcp(domain, port, source)?;
+
+    // A handshake that runs out of time gets interrupted rather than
+    // failing with an I/O error
+    let tls = match connector.connect(domain, stream) {
+        Ok(tls) => tls,
+        Err(native_tls::HandshakeError::WouldBlock(_)) => {
+            return Err(timeouts.connect_error(std::io::Error::from(std::io::ErrorKind::TimedOut).into()));
+        }
+        Err(e) => {
+            return Err(e.into());
+        }
+    };
+
+    timeouts.connected(tls.get_ref())?;
+    Ok(tls)
 }
 
+/// Connects from the source and performs the TLS handshake, both within
+/// the connect timeout, then switches the stream over to the read timeout.
 #[cfg(feature = "with_rustls")]
-fn stream_rustls(domain: &str, port: u16) -> Result<rustls::StreamOwned<rustls::ClientSession,TcpStream>, Error> {
+fn stream_rustls(domain: &str, port: u16, timeouts: Timeouts, source: &Source) -> Result<rustls::StreamOwned<rustls::ClientSession,TcpStream>, Error> {
     use std::sync::Arc;
+    use rustls::Session;
 
     let mut config = rustls::ClientConfig::new();
 
@@ -20,16 +40,21 @@ fn stream_rustls(domain: &str, port: u16) -> Result<rustls::StreamOwned<rustls::
 
     let dns_name = webpki::DNSNameRef::try_from_ascii_str(domain)?;
 
-    let conn = rustls::ClientSession::new(&Arc::new(config), dns_name);
+    let mut conn = rustls::ClientSession::new(&Arc::new(config), dns_name);
+
+    // The handshake would otherwise happen during the first write, after
+    // the stream has switched over to the read timeout
+    let mut sock = timeouts.connect_tcp(domain, port, source)?;
+    conn.complete_io(&mut sock).map_err(|e| timeouts.connect_error(e.into()))?;
+    timeouts.connected(&sock)?;
 
-    let sock = TcpStream::connect((domain, port))?;
     let tls = rustls::StreamOwned::new(conn, sock);
 
     Ok(tls)
 }
 
 pub trait TlsStream<S: std::io::Read + std::io::Write> {
-    fn stream(domain: &str, port: u16) -> Result<S, Error>;
+    fn stream(domain: &str, port: u16, timeouts: Timeouts, source: &Source) -> Result<S, Error>;
 }
 
 #[cfg(any(feature = "with_tls", feature = "with_https"))]
@@ -37,28 +62,28 @@ cfg_if::cfg_if! {
     if #[cfg(any(feature = "with_nativetls", feature = "with_nativetls_vendored"))] {
 
         impl TlsStream<native_tls::TlsStream<TcpStream>> for HttpsTransport {
-            fn stream(domain: &str, port: u16) -> Result<native_tls::TlsStream<TcpStream>, Error> {
-                stream_nativetls(domain, port)
+            fn stream(domain: &str, port: u16, timeouts: Timeouts, source: &Source) -> Result<native_tls::TlsStream<TcpStream>, Error> {
+                stream_nativetls(domain, port, timeouts, source)
             }
         }
 
         impl TlsStream<native_tls::TlsStream<TcpStream>> for TlsTransport {
-            fn stream(domain: &str, port: u16) -> Result<native_tls::TlsStream<TcpStream>, Error> {
-                stream_nativetls(domain, port)
+            fn stream(domain: &str, port: u16, timeouts: Timeouts, source: &Source) -> Result<native_tls::TlsStream<TcpStream>, Error> {
+                stream_nativetls(domain, port, timeouts, source)
             }
         }
 
     } else if #[cfg(feature = "with_rustls")] {
 
         impl TlsStream<rustls::StreamOwned<rustls::ClientSession,TcpStream>> for HttpsTransport {
-            fn stream(domain: &str, port: u16) -> Result<rustls::StreamOwned<rustls::ClientSession,TcpStream>, Error> {
-                stream_rustls(domain, port)
+            fn stream(domain: &str, port: u16, timeouts: Timeouts, source: &Source) -> Result<rustls::StreamOwned<rustls::ClientSession,TcpStream>, Error> {
+                stream_rustls(domain, port, timeouts, source)
             }
         }
 
         impl TlsStream<rustls::StreamOwned<rustls::ClientSession,TcpStream>> for TlsTransport {
-            fn stream(domain: &str, port: u16) -> Result<rustls::StreamOwned<rustls::ClientSession,TcpStream>, Error> {
-                stream_rustls(domain, port)
+            fn stream(domain: &str, port: u16, timeouts: Timeouts, source: &Source) -> Result<rustls::StreamOwned<rustls::ClientSession,TcpStream>, Error> {
+                stream_rustls(domain, port, timeouts, source)
             }
         }
 
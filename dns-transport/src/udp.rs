@@ -1,56 +1,168 @@
-use std::net::{Ipv4Addr, UdpSocket};
+use std::cell::{Cell, RefCell};
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::time::{Duration, Instant};
 
 use log::*;
 
-use dns::{Request, Response};
-use super::{Transport, Error};
+use super::{Transport, Error, ResponseChecks, Mismatch, Source};
+use super::checks::check_packet;
 
 
 /// The **UDP transport**, which sends DNS wire data inside a UDP datagram.
 ///
+/// As anyone can send a UDP packet to the port a query went out from, a
+/// packet only counts as the response if it came from the address and port
+/// the query was sent to, and has the same transaction ID and question as
+/// the query. Any other packet that arrives while waiting is ignored, and
+/// counted, as a lot of them can be a sign of someone trying to spoof one.
+/// When told to trust anything, the first packet to arrive is taken as the
+/// response instead, and if it came from somewhere else, that gets noted.
+///
 /// # References
 ///
 /// - [RFC 1035 §4.2.1](https://tools.ietf.org/html/rfc1035) — Domain Names,
 ///   Implementation and Specification (November 1987)
+/// - [RFC 5452 §9.1](https://tools.ietf.org/html/rfc5452#section-9.1) —
+///   Measures for Making DNS More Resilient against Forged Answers
+///   (January 2009)
 pub struct UdpTransport {
     addr: String,
+    timeout: Option<Duration>,
+    source: Source,
+    checks: ResponseChecks,
+    ignored: Cell<usize>,
+    mismatches: RefCell<Vec<Mismatch>>,
 }
 
 impl UdpTransport {
 
     /// Creates a new UDP transport that connects to the given host.
     pub fn new(addr: String) -> Self {
-        Self { addr }
+        Self { addr, timeout: None, source: Source::default(), checks: ResponseChecks::Strict, ignored: Cell::new(0), mismatches: RefCell::new(Vec::new()) }
     }
-}
 
+    /// Creates a new UDP transport that connects to the given host, and
+    /// gives up waiting for a response after the given timeout.
+    pub fn with_timeout(addr: String, timeout: Duration) -> Self {
+        Self { addr, timeout: Some(timeout), source: Source::default(), checks: ResponseChecks::Strict, ignored: Cell::new(0), mismatches: RefCell::new(Vec::new()) }
+    }
 
-impl Transport for UdpTransport {
-    fn send(&self, request: &Request) -> Result<Response, Error> {
-        info!("Opening UDP socket");
-        // TODO: This will need to be changed for IPv6 support.
-        let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))?;
+    /// Sets how closely a packet has to match the query to be taken as its
+    /// response.
+    pub fn with_checks(mut self, checks: ResponseChecks) -> Self {
+        self.checks = checks;
+        self
+    }
+
+    /// Sets the local address and interface to send queries from.
+    pub fn with_source(mut self, source: Source) -> Self {
+        self.source = source;
+        self
+    }
 
-        if self.addr.contains(':') {
-            socket.connect(&*self.addr)?;
+    /// Looks up the address and port to send queries to, using port 53 if
+    /// the host did not say which one. Only addresses that can be reached
+    /// from the source address are considered.
+    fn server_address(&self) -> io::Result<SocketAddr> {
+        let mut addresses = if self.addr.contains(':') {
+            self.addr.to_socket_addrs()?
         }
         else {
-            socket.connect((&*self.addr, 53))?;
+            (&*self.addr, 53).to_socket_addrs()?
+        };
+
+        addresses.find(|address| self.source.reaches(address))
+                 .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "nameserver has no usable addresses"))
+    }
+
+    /// Replaces an error from running out of time while waiting for the
+    /// response with one that says how long was waited, leaving any other
+    /// error as it is. A read that runs out of time can fail with either of
+    /// two kinds of error, depending on the platform.
+    fn timed_out(&self, error: io::Error) -> io::Error {
+        match (error.kind(), self.timeout) {
+            (io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock, Some(timeout)) => {
+                io::Error::new(io::ErrorKind::TimedOut, format!("timed out waiting for a response after {:?}", timeout))
+            }
+            _ => error,
         }
-        debug!("Opened");
+    }
+}
+
 
-        let bytes_to_send = request.to_bytes().expect("failed to serialise request");
+impl Transport for UdpTransport {
+    fn send_raw(&self, bytes_to_send: &[u8]) -> Result<Vec<u8>, Error> {
+        let server = self.server_address()?;
 
-        info!("Sending {} bytes of data to {} over UDP", bytes_to_send.len(), self.addr);
-        let written_len = socket.send(&bytes_to_send)?;
+        info!("Opening UDP socket");
+        let socket = self.source.udp_socket(server)?;
+        debug!("Opened");
+
+        info!("Sending {} bytes of data to {} over UDP", bytes_to_send.len(), server);
+        let written_len = socket.send_to(bytes_to_send, server)?;
         debug!("Wrote {} bytes", written_len);
 
-        info!("Waiting to receive...");
+        // The socket is not connected, so packets from anywhere else reach
+        // it too, instead of being thrown away by the kernel without a word.
+        // Keep reading until the real response turns up, or time runs out.
+        let deadline = self.timeout.map(|timeout| Instant::now() + timeout);
         let mut buf = vec![0; 4096];
-        let received_len = socket.recv(&mut buf)?;
+        loop {
+            let left = time_left(deadline).map_err(|e| self.timed_out(e))?;
+            socket.set_read_timeout(left)?;
+
+            info!("Waiting to receive...");
+            let (received_len, source) = socket.recv_from(&mut buf).map_err(|e| self.timed_out(e))?;
+            info!("Received {} bytes of data from {}", received_len, source);
+
+            let received = &buf[.. received_len];
+            if self.checks == ResponseChecks::TrustAnything {
+                // The transaction ID and question get checked once the
+                // response has been parsed, so only the source is left.
+                if source != server {
+                    warn!("Accepting UDP packet from {} anyway", source);
+                    self.mismatches.borrow_mut().push(Mismatch::Source);
+                }
+
+                return Ok(received.to_vec());
+            }
+
+            match check_packet(bytes_to_send, received, source, server) {
+                Ok(()) => {
+                    return Ok(received.to_vec());
+                }
+                Err(mismatch) => {
+                    warn!("Ignoring UDP packet from {}: {}", source, mismatch);
+                    self.ignored.set(self.ignored.get() + 1);
+                }
+            }
+        }
+    }
 
-        info!("Received {} bytes of data", received_len);
-        let response = Response::from_bytes(&buf[.. received_len])?;
-        Ok(response)
+    fn ignored_packets(&self) -> usize {
+        self.ignored.get()
+    }
+
+    fn take_mismatches(&self) -> Vec<Mismatch> {
+        self.mismatches.take()
+    }
+}
+
+
+/// Returns how long is left to wait for the response, or an error if the
+/// time has already run out. Without a deadline there is no limit.
+pub(crate) fn time_left(deadline: Option<Instant>) -> io::Result<Option<Duration>> {
+    let deadline = match deadline {
+        Some(deadline)  => deadline,
+        None            => return Ok(None),
+    };
+
+    let left = deadline.saturating_duration_since(Instant::now());
+    if left == Duration::from_secs(0) {
+        Err(io::Error::from(io::ErrorKind::TimedOut))
+    }
+    else {
+        Ok(Some(left))
     }
 }
@@ -0,0 +1,69 @@
+#![warn(deprecated_in_future)]
+#![warn(future_incompatible)]
+#![warn(missing_copy_implementations)]
+#![warn(missing_docs)]
+#![warn(nonstandard_style)]
+#![warn(rust_2018_compatibility)]
+#![warn(rust_2018_idioms)]
+#![warn(single_use_lifetimes)]
+#![warn(trivial_casts, trivial_numeric_casts)]
+#![warn(unused)]
+
+#![warn(clippy::all, clippy::pedantic)]
+#![allow(clippy::doc_markdown)]
+#![allow(clippy::missing_errors_doc)]
+#![allow(clippy::must_use_candidate)]
+
+// Unsafe code is not denied here, as the glue code that wasm-bindgen
+// generates for each exported function uses it.
+
+
+//! The WASM crate wraps the `dns` crate’s parser for use from JavaScript,
+//! so web-based DNS tools can read messages the same way dog does. It does
+//! no networking of its own: a browser can send the bytes from `query` to
+//! a DNS-over-HTTPS server with `fetch`, and pass the bytes that come back
+//! to `parse_response`.
+//!
+//! Build it with `wasm-pack build dns-wasm`, or with Cargo for the
+//! `wasm32-unknown-unknown` target.
+
+use wasm_bindgen::prelude::*;
+
+use dns::{Flags, QClass, Query, Request, Response};
+use dns::record::RecordType;
+
+
+/// Parses the bytes of a DNS response, returning it as a JavaScript object
+/// with the same shape as the `Response` type’s serde serialisation.
+/// Throws an error if the bytes are not a valid message.
+#[wasm_bindgen(js_name = parseResponse)]
+pub fn parse_response(bytes: &[u8]) -> Result<JsValue, JsValue> {
+    let response = Response::from_bytes(bytes)
+        .map_err(|e| JsValue::from_str(&format!("Malformed response: {:?}", e)))?;
+
+    serde_wasm_bindgen::to_value(&response)
+        .map_err(JsValue::from)
+}
+
+/// Returns the bytes of a query for the given domain and record type, such
+/// as `"A"` or `"MX"`, with an OPT record and a transaction ID of zero, as
+/// DNS-over-HTTPS requests should have (RFC 8484 §4.1). Throws an error if
+/// the domain or type are invalid.
+#[wasm_bindgen]
+pub fn query(domain: &str, record_type: &str) -> Result<Vec<u8>, JsValue> {
+    let qname = dns::Labels::encode(domain)
+        .map_err(|label| JsValue::from_str(&format!("Invalid domain label: {:?}", label)))?;
+
+    let qtype = RecordType::from_type_name(record_type)
+        .ok_or_else(|| JsValue::from_str(&format!("Unknown record type: {:?}", record_type)))?;
+
+    let request = Request {
+        transaction_id: 0,
+        flags: Flags::query(),
+        query: Query { qname, qclass: QClass::IN, qtype },
+        additional: Some(Request::additional_record()),
+    };
+
+    request.to_bytes()
+        .map_err(|e| JsValue::from_str(&format!("Unable to write query: {}", e)))
+}
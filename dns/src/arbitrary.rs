@@ -0,0 +1,483 @@
+//! Generating random records for property-based tests, using `quickcheck`.
+//!
+//! Every record type implements `Arbitrary`, as do `Record` and `Labels`.
+//! The records that get generated are always ones that can be written and
+//! then read back as the same record, so `round_trip` can check that
+//! encoding and decoding agree with each other:
+//!
+//! ```ignore
+//! quickcheck::quickcheck(|record: Record| dns::arbitrary::round_trip(&record) == Ok(record));
+//! ```
+//!
+//! Fields that the wire format limits, such as character-strings that can
+//! be at most 255 bytes long, or fields that must not be empty, are kept
+//! within those limits.
+
+use std::convert::TryFrom;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use quickcheck::{Arbitrary, Gen};
+
+use crate::record::*;
+use crate::strings::Labels;
+use crate::wire::*;
+
+
+/// Writes a record’s data, then reads it back as the same type of record,
+/// returning what was read. For any record that this module generates, the
+/// result should be the record that went in.
+pub fn round_trip(record: &Record) -> Result<Record, WireError> {
+    let mut bytes = Vec::new();
+    record.write(&mut bytes)?;
+
+    let length = u16::try_from(bytes.len())
+        .map_err(|_| WireError::IO)?;
+
+    let mut c = Cursor::new(&bytes[..]);
+    let read = Record::from_bytes(record.record_type(), length, &mut c)?;
+
+    if usize::try_from(c.position()).ok() == Some(bytes.len()) {
+        Ok(read)
+    }
+    else {
+        let length_after_labels = u16::try_from(c.position()).unwrap_or(u16::MAX);
+        Err(WireError::WrongLabelLength { stated_length: length, length_after_labels })
+    }
+}
+
+
+impl Arbitrary for Record {
+    fn arbitrary(g: &mut Gen) -> Self {
+        match number_below(g, 33) {
+            0  => Self::A(A::arbitrary(g)),
+            1  => Self::AAAA(AAAA::arbitrary(g)),
+            2  => Self::AMTRELAY(AMTRELAY::arbitrary(g)),
+            3  => Self::CAA(CAA::arbitrary(g)),
+            4  => Self::CNAME(CNAME::arbitrary(g)),
+            5  => Self::DLV(DLV(DS::arbitrary(g))),
+            6  => Self::DNSKEY(DNSKEY::arbitrary(g)),
+            7  => Self::DOA(DOA::arbitrary(g)),
+            8  => Self::DS(DS::arbitrary(g)),
+            9  => Self::EUI48(EUI48::arbitrary(g)),
+            10 => Self::EUI64(EUI64::arbitrary(g)),
+            11 => Self::HINFO(HINFO::arbitrary(g)),
+            12 => Self::HIP(HIP::arbitrary(g)),
+            13 => Self::HTTPS(HTTPS(SVCB::arbitrary(g))),
+            14 => Self::LOC(LOC::arbitrary(g)),
+            15 => Self::MX(MX::arbitrary(g)),
+            16 => Self::NAPTR(NAPTR::arbitrary(g)),
+            17 => Self::NS(NS::arbitrary(g)),
+            18 => Self::NSEC(NSEC::arbitrary(g)),
+            19 => Self::NSEC3(NSEC3::arbitrary(g)),
+            20 => Self::NSEC3PARAM(NSEC3PARAM::arbitrary(g)),
+            21 => Self::OPENPGPKEY(OPENPGPKEY::arbitrary(g)),
+            22 => Self::PTR(PTR::arbitrary(g)),
+            23 => Self::RRSIG(RRSIG::arbitrary(g)),
+            24 => Self::SSHFP(SSHFP::arbitrary(g)),
+            25 => Self::SOA(SOA::arbitrary(g)),
+            26 => Self::SRV(SRV::arbitrary(g)),
+            27 => Self::SVCB(SVCB::arbitrary(g)),
+            28 => Self::TA(TA(DS::arbitrary(g))),
+            29 => Self::TLSA(TLSA::arbitrary(g)),
+            30 => Self::TXT(TXT::arbitrary(g)),
+            31 => Self::URI(URI::arbitrary(g)),
+            _  => {
+                // Only type numbers that no record type is read as can be
+                // used, or the data would be read back as that type.
+                let type_number = loop {
+                    let number = u16::arbitrary(g);
+                    if number != OPT::RR_TYPE && matches!(RecordType::from(number), RecordType::Other(_)) {
+                        break number;
+                    }
+                };
+
+                Self::Other { type_number: UnknownQtype::from(type_number), bytes: Vec::arbitrary(g) }
+            }
+        }
+    }
+}
+
+impl Arbitrary for Labels {
+    fn arbitrary(g: &mut Gen) -> Self {
+        let count = number_below(g, 5);
+        let labels = (0 .. count).map(|_| label(g)).collect::<Vec<_>>();
+        Self::encode(&labels.join(".")).expect("generated an invalid domain name")
+    }
+}
+
+
+impl Arbitrary for A {
+    fn arbitrary(g: &mut Gen) -> Self {
+        Self { address: Ipv4Addr::arbitrary(g) }
+    }
+}
+
+impl Arbitrary for AAAA {
+    fn arbitrary(g: &mut Gen) -> Self {
+        Self { address: Ipv6Addr::arbitrary(g) }
+    }
+}
+
+impl Arbitrary for AMTRELAY {
+    fn arbitrary(g: &mut Gen) -> Self {
+        let relay = match number_below(g, 5) {
+            0 => Relay::Empty,
+            1 => Relay::Ipv4(Ipv4Addr::arbitrary(g)),
+            2 => Relay::Ipv6(Ipv6Addr::arbitrary(g)),
+            3 => Relay::Domain(Labels::arbitrary(g)),
+            _ => Relay::Other { relay_type: 4 + u8::arbitrary(g) % 124, bytes: Vec::arbitrary(g) },
+        };
+
+        Self { precedence: u8::arbitrary(g), discovery_optional: bool::arbitrary(g), relay }
+    }
+}
+
+impl Arbitrary for CAA {
+    fn arbitrary(g: &mut Gen) -> Self {
+        Self {
+            critical: bool::arbitrary(g),
+            tag: character_string(g).into_boxed_slice(),
+            value: Vec::arbitrary(g).into_boxed_slice(),
+        }
+    }
+}
+
+impl Arbitrary for CNAME {
+    fn arbitrary(g: &mut Gen) -> Self {
+        Self { domain: Labels::arbitrary(g) }
+    }
+}
+
+impl Arbitrary for DNSKEY {
+    fn arbitrary(g: &mut Gen) -> Self {
+        Self {
+            flags: u16::arbitrary(g),
+            protocol: u8::arbitrary(g),
+            algorithm: u8::arbitrary(g),
+            public_key: non_empty_bytes(g),
+        }
+    }
+}
+
+impl Arbitrary for DOA {
+    fn arbitrary(g: &mut Gen) -> Self {
+        Self {
+            enterprise: u32::arbitrary(g),
+            doa_type: u32::arbitrary(g),
+            location: u8::arbitrary(g),
+            media_type: character_string(g).into_boxed_slice(),
+            data: Vec::arbitrary(g),
+        }
+    }
+}
+
+impl Arbitrary for DS {
+    fn arbitrary(g: &mut Gen) -> Self {
+        Self {
+            key_tag: u16::arbitrary(g),
+            algorithm: u8::arbitrary(g),
+            digest_type: u8::arbitrary(g),
+            digest: non_empty_bytes(g),
+        }
+    }
+}
+
+impl Arbitrary for EUI48 {
+    fn arbitrary(g: &mut Gen) -> Self {
+        let mut octets = [0; 6];
+        octets.iter_mut().for_each(|octet| *octet = u8::arbitrary(g));
+        Self { octets }
+    }
+}
+
+impl Arbitrary for EUI64 {
+    fn arbitrary(g: &mut Gen) -> Self {
+        let mut octets = [0; 8];
+        octets.iter_mut().for_each(|octet| *octet = u8::arbitrary(g));
+        Self { octets }
+    }
+}
+
+impl Arbitrary for HINFO {
+    fn arbitrary(g: &mut Gen) -> Self {
+        Self {
+            cpu: character_string(g).into_boxed_slice(),
+            os: character_string(g).into_boxed_slice(),
+        }
+    }
+}
+
+impl Arbitrary for HIP {
+    fn arbitrary(g: &mut Gen) -> Self {
+        let count = number_below(g, 3);
+
+        Self {
+            algorithm: u8::arbitrary(g),
+            hit: character_string(g),
+            public_key: Vec::arbitrary(g),
+            rendezvous_servers: (0 .. count).map(|_| Labels::arbitrary(g)).collect(),
+        }
+    }
+}
+
+impl Arbitrary for LOC {
+    fn arbitrary(g: &mut Gen) -> Self {
+        // The positions have private fields and a range they have to be in,
+        // so it is easiest to build the record’s bytes and read them.
+        let mut bytes = vec![ 0 ];  // version
+        bytes.extend_from_slice(&[ u8::arbitrary(g), u8::arbitrary(g), u8::arbitrary(g) ]);
+        bytes.extend_from_slice(&position(g, 90).to_be_bytes());
+        bytes.extend_from_slice(&position(g, 180).to_be_bytes());
+        bytes.extend_from_slice(&u32::arbitrary(g).to_be_bytes());
+
+        Self::read(16, &mut Cursor::new(&bytes[..])).expect("generated an invalid LOC record")
+    }
+}
+
+impl Arbitrary for MX {
+    fn arbitrary(g: &mut Gen) -> Self {
+        Self { preference: u16::arbitrary(g), exchange: Labels::arbitrary(g) }
+    }
+}
+
+impl Arbitrary for NAPTR {
+    fn arbitrary(g: &mut Gen) -> Self {
+        Self {
+            order: u16::arbitrary(g),
+            preference: u16::arbitrary(g),
+            flags: character_string(g).into_boxed_slice(),
+            service: character_string(g).into_boxed_slice(),
+            regex: character_string(g).into_boxed_slice(),
+            replacement: Labels::arbitrary(g),
+        }
+    }
+}
+
+impl Arbitrary for NS {
+    fn arbitrary(g: &mut Gen) -> Self {
+        Self { nameserver: Labels::arbitrary(g) }
+    }
+}
+
+impl Arbitrary for NSEC {
+    fn arbitrary(g: &mut Gen) -> Self {
+        Self { next_domain: Labels::arbitrary(g), types: record_types(g) }
+    }
+}
+
+impl Arbitrary for NSEC3 {
+    fn arbitrary(g: &mut Gen) -> Self {
+        Self {
+            hash_algorithm: u8::arbitrary(g),
+            flags: u8::arbitrary(g),
+            iterations: u16::arbitrary(g),
+            salt: character_string(g),
+            next_hashed_owner: character_string(g),
+            types: record_types(g),
+        }
+    }
+}
+
+impl Arbitrary for NSEC3PARAM {
+    fn arbitrary(g: &mut Gen) -> Self {
+        Self {
+            hash_algorithm: u8::arbitrary(g),
+            flags: u8::arbitrary(g),
+            iterations: u16::arbitrary(g),
+            salt: character_string(g),
+        }
+    }
+}
+
+impl Arbitrary for OPENPGPKEY {
+    fn arbitrary(g: &mut Gen) -> Self {
+        Self { key: non_empty_bytes(g) }
+    }
+}
+
+impl Arbitrary for PTR {
+    fn arbitrary(g: &mut Gen) -> Self {
+        Self { cname: Labels::arbitrary(g) }
+    }
+}
+
+impl Arbitrary for RRSIG {
+    fn arbitrary(g: &mut Gen) -> Self {
+        Self {
+            type_covered: u16::arbitrary(g),
+            algorithm: u8::arbitrary(g),
+            labels: u8::arbitrary(g),
+            original_ttl: u32::arbitrary(g),
+            signature_expiration: u32::arbitrary(g),
+            signature_inception: u32::arbitrary(g),
+            key_tag: u16::arbitrary(g),
+            signer_name: Labels::arbitrary(g),
+            signature: Vec::arbitrary(g),
+        }
+    }
+}
+
+impl Arbitrary for SSHFP {
+    fn arbitrary(g: &mut Gen) -> Self {
+        Self {
+            algorithm: u8::arbitrary(g),
+            fingerprint_type: u8::arbitrary(g),
+            fingerprint: non_empty_bytes(g),
+        }
+    }
+}
+
+impl Arbitrary for SOA {
+    fn arbitrary(g: &mut Gen) -> Self {
+        Self {
+            mname: Labels::arbitrary(g),
+            rname: Labels::arbitrary(g),
+            serial: u32::arbitrary(g),
+            refresh_interval: u32::arbitrary(g),
+            retry_interval: u32::arbitrary(g),
+            expire_limit: u32::arbitrary(g),
+            minimum_ttl: u32::arbitrary(g),
+        }
+    }
+}
+
+impl Arbitrary for SRV {
+    fn arbitrary(g: &mut Gen) -> Self {
+        Self {
+            priority: u16::arbitrary(g),
+            weight: u16::arbitrary(g),
+            port: u16::arbitrary(g),
+            target: Labels::arbitrary(g),
+        }
+    }
+}
+
+impl Arbitrary for SVCB {
+    fn arbitrary(g: &mut Gen) -> Self {
+        let count = number_below(g, 4);
+
+        Self {
+            priority: u16::arbitrary(g),
+            target: Labels::arbitrary(g),
+            parameters: (0 .. count).map(|_| SvcParam::arbitrary(g)).collect(),
+        }
+    }
+}
+
+impl Arbitrary for SvcParam {
+    fn arbitrary(g: &mut Gen) -> Self {
+        match number_below(g, 9) {
+            0 => Self::Mandatory(non_empty(g, u16::arbitrary)),
+            1 => Self::Alpn(non_empty(g, |g| character_string(g).into_boxed_slice())),
+            2 => Self::NoDefaultAlpn,
+            3 => Self::Port(u16::arbitrary(g)),
+            4 => Self::Ipv4Hint(non_empty(g, Ipv4Addr::arbitrary)),
+            5 => Self::Ech(EchConfigList(Vec::arbitrary(g))),
+            6 => Self::Ipv6Hint(non_empty(g, Ipv6Addr::arbitrary)),
+            7 => Self::DohPath(Vec::arbitrary(g).into_boxed_slice()),
+            _ => Self::Other { key: 8.max(u16::arbitrary(g)), value: Vec::arbitrary(g) },
+        }
+    }
+}
+
+impl Arbitrary for TLSA {
+    fn arbitrary(g: &mut Gen) -> Self {
+        Self {
+            certificate_usage: u8::arbitrary(g),
+            selector: u8::arbitrary(g),
+            matching_type: u8::arbitrary(g),
+            certificate_data: non_empty_bytes(g),
+        }
+    }
+}
+
+impl Arbitrary for TXT {
+    fn arbitrary(g: &mut Gen) -> Self {
+        // A record with no messages would have no data at all, which does
+        // not read as a TXT record. Messages can be longer than a
+        // character-string, as they get split up across several.
+        let messages = non_empty(g, |g| {
+            let length = number_below(g, 600);
+            (0 .. length).map(|_| u8::arbitrary(g)).collect::<Vec<_>>().into_boxed_slice()
+        });
+
+        Self { messages }
+    }
+}
+
+impl Arbitrary for URI {
+    fn arbitrary(g: &mut Gen) -> Self {
+        Self {
+            priority: u16::arbitrary(g),
+            weight: u16::arbitrary(g),
+            target: non_empty_bytes(g).into_boxed_slice(),
+        }
+    }
+}
+
+
+/// Returns a number from zero up to, but not including, the given limit.
+fn number_below(g: &mut Gen, limit: u32) -> u32 {
+    u32::arbitrary(g) % limit
+}
+
+/// Returns one label of a domain name, made of the letters, digits, and
+/// hyphens that are allowed in one.
+fn label(g: &mut Gen) -> String {
+    const ALPHABET: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789";
+    let length = 1 + number_below(g, 12);
+
+    (0 .. length)
+        .map(|_| char::from(*g.choose(ALPHABET).unwrap()))
+        .collect()
+}
+
+/// Returns bytes that fit in a character-string, which can be at most 255
+/// bytes long.
+fn character_string(g: &mut Gen) -> Vec<u8> {
+    let mut bytes = Vec::<u8>::arbitrary(g);
+    bytes.truncate(255);
+    bytes
+}
+
+/// Returns at least one byte, for the fields that take up the rest of a
+/// record and must not be empty.
+fn non_empty_bytes(g: &mut Gen) -> Vec<u8> {
+    non_empty(g, u8::arbitrary)
+}
+
+/// Returns a list of at least one item, made with the given function.
+fn non_empty<T>(g: &mut Gen, item: impl Fn(&mut Gen) -> T) -> Vec<T> {
+    let count = 1 + number_below(g, 8);
+    (0 .. count).map(|_| item(g)).collect()
+}
+
+/// Returns a sorted list of type numbers without duplicates, as type bitmaps
+/// are read back.
+fn record_types(g: &mut Gen) -> Vec<u16> {
+    let mut types = Vec::<u16>::arbitrary(g);
+    types.sort_unstable();
+    types.dedup();
+    types
+}
+
+/// Returns the number a LOC record uses for a latitude or longitude, no
+/// further than the given number of degrees from the equator or prime
+/// meridian.
+fn position(g: &mut Gen, max_degrees: u32) -> u32 {
+    let limit = 1000 * 60 * 60 * max_degrees;
+    0x_8000_0000 - limit + number_below(g, limit * 2 + 1)
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use quickcheck::quickcheck;
+
+    quickcheck! {
+        fn records_round_trip(record: Record) -> bool {
+            round_trip(&record).as_ref() == Ok(&record)
+        }
+    }
+}
@@ -0,0 +1,304 @@
+//! Reading the stream of messages that make up a zone transfer. The records
+//! of a zone are sent over TCP in as many messages as they need, starting
+//! and ending with the zone’s SOA record, so the only way to know that a
+//! transfer has finished is to keep track of the SOA records that have been
+//! seen so far.
+//!
+//! # References
+//!
+//! - [RFC 5936 §2.2](https://tools.ietf.org/html/rfc5936#section-2.2) —
+//!   DNS Zone Transfer Protocol (AXFR) (June 2010)
+
+use std::fmt;
+
+use crate::record::Record;
+use crate::types::{Answer, ErrorCode, Response};
+use crate::wire::WireError;
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+
+/// A parser for the messages of a zone transfer, which gets given each
+/// message as it arrives and hands back the records in it. It checks that
+/// the transfer starts with an SOA record, and finishes once the closing
+/// SOA record arrives.
+#[derive(PartialEq, Debug, Copy, Clone, Default)]
+pub struct AxfrParser {
+
+    /// The serial number of the opening SOA record, once it has been read.
+    serial: Option<u32>,
+
+    /// Whether the closing SOA record has been read.
+    finished: bool,
+
+    /// The number of messages that have been read.
+    messages: usize,
+}
+
+/// Something wrong with a message in a zone transfer.
+#[derive(PartialEq, Debug)]
+pub enum TransferError {
+
+    /// The server sent back an error code instead of the zone.
+    ErrorCode(ErrorCode),
+
+    /// The first record was not an SOA record.
+    NoOpeningSoa,
+
+    /// The closing SOA record has a different serial number to the opening
+    /// one, which means the zone changed during the transfer.
+    SerialChanged {
+
+        /// The serial number in the opening SOA record.
+        opening: u32,
+
+        /// The serial number in the closing SOA record.
+        closing: u32,
+    },
+
+    /// A message arrived after the closing SOA record.
+    RecordsAfterEnd,
+
+    /// A message could not be read.
+    Malformed(WireError),
+}
+
+
+impl AxfrParser {
+
+    /// Creates a parser for a transfer that has not started yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads a message from its bytes, returning the records in it, as
+    /// `push` does.
+    pub fn read_message(&mut self, bytes: &[u8]) -> Result<Vec<Answer>, TransferError> {
+        let response = Response::from_bytes(bytes)?;
+        self.push(&response)
+    }
+
+    /// Reads the answer section of the next message in the transfer,
+    /// returning the records in it. The opening SOA record is returned
+    /// along with the rest of the zone, but the closing one is not. OPT
+    /// pseudo-records are skipped.
+    pub fn push(&mut self, response: &Response) -> Result<Vec<Answer>, TransferError> {
+        if self.finished {
+            return Err(TransferError::RecordsAfterEnd);
+        }
+
+        // Only the first message can carry an error code, as the server has
+        // committed to sending the zone once any of it has been sent.
+        if let Some(rcode) = response.flags.error_code {
+            return Err(TransferError::ErrorCode(rcode));
+        }
+
+        self.messages += 1;
+        let mut records = Vec::new();
+
+        for answer in &response.answers {
+            let record = match answer {
+                Answer::Standard { record, .. }  => record,
+                Answer::Pseudo { .. }            => continue,
+            };
+
+            if self.finished {
+                return Err(TransferError::RecordsAfterEnd);
+            }
+
+            match (self.serial, record) {
+                (None, Record::SOA(soa)) => {
+                    self.serial = Some(soa.serial);
+                }
+                (None, _) => {
+                    return Err(TransferError::NoOpeningSoa);
+                }
+                (Some(opening), Record::SOA(soa)) => {
+                    if soa.serial != opening {
+                        return Err(TransferError::SerialChanged { opening, closing: soa.serial });
+                    }
+
+                    self.finished = true;
+                    continue;
+                }
+                (Some(_), _) => {}
+            }
+
+            records.push(answer.clone());
+        }
+
+        if self.serial.is_none() {
+            return Err(TransferError::NoOpeningSoa);
+        }
+
+        Ok(records)
+    }
+
+    /// Whether the closing SOA record has been read, which means no more
+    /// messages should be read.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Returns the serial number of the zone, once the opening SOA record
+    /// has been read.
+    pub fn serial(&self) -> Option<u32> {
+        self.serial
+    }
+
+    /// Returns the number of messages that have been read so far.
+    pub fn message_count(&self) -> usize {
+        self.messages
+    }
+}
+
+
+impl From<WireError> for TransferError {
+    fn from(inner: WireError) -> Self {
+        Self::Malformed(inner)
+    }
+}
+
+impl fmt::Display for TransferError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ErrorCode(rcode)   => write!(f, "server returned error code {:?}", rcode),
+            Self::NoOpeningSoa       => write!(f, "transfer did not start with an SOA record"),
+            Self::SerialChanged { opening, closing }  => write!(f, "serial changed from {} to {} during the transfer", opening, closing),
+            Self::RecordsAfterEnd    => write!(f, "records arrived after the closing SOA record"),
+            Self::Malformed(_)       => write!(f, "malformed message"),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    use crate::record::{A, OPT, SOA};
+    use crate::strings::Labels;
+    use crate::types::{Flags, QClass};
+
+    fn soa(serial: u32) -> Answer {
+        Answer::Standard {
+            qname: Labels::encode("lookup.dog").unwrap(),
+            qclass: QClass::IN,
+            ttl: 3600,
+            record: Record::SOA(SOA {
+                mname: Labels::encode("ns1.lookup.dog").unwrap(),
+                rname: Labels::encode("hostmaster.lookup.dog").unwrap(),
+                serial,
+                refresh_interval: 7200,
+                retry_interval: 3600,
+                expire_limit: 1_209_600,
+                minimum_ttl: 300,
+            }),
+        }
+    }
+
+    fn a(last: u8) -> Answer {
+        Answer::Standard {
+            qname: Labels::encode("lookup.dog").unwrap(),
+            qclass: QClass::IN,
+            ttl: 60,
+            record: Record::A(A { address: std::net::Ipv4Addr::new(192, 0, 2, last) }),
+        }
+    }
+
+    fn response(answers: Vec<Answer>) -> Response {
+        Response { transaction_id: 1, flags: Flags::standard_response(), queries: vec![], answers, authorities: vec![], additionals: vec![] }
+    }
+
+    #[test]
+    fn one_message() {
+        let mut parser = AxfrParser::new();
+        assert_eq!(parser.push(&response(vec![ soa(5), a(1), a(2), soa(5) ])),
+                   Ok(vec![ soa(5), a(1), a(2) ]));
+        assert!(parser.is_finished());
+        assert_eq!(parser.serial(), Some(5));
+    }
+
+    #[test]
+    fn several_messages() {
+        let mut parser = AxfrParser::new();
+
+        assert_eq!(parser.push(&response(vec![ soa(5), a(1) ])), Ok(vec![ soa(5), a(1) ]));
+        assert!(! parser.is_finished());
+
+        assert_eq!(parser.push(&response(vec![ a(2) ])), Ok(vec![ a(2) ]));
+        assert!(! parser.is_finished());
+
+        assert_eq!(parser.push(&response(vec![ a(3), soa(5) ])), Ok(vec![ a(3) ]));
+        assert!(parser.is_finished());
+        assert_eq!(parser.message_count(), 3);
+    }
+
+    #[test]
+    fn only_soa() {
+        let mut parser = AxfrParser::new();
+        assert_eq!(parser.push(&response(vec![ soa(5) ])), Ok(vec![ soa(5) ]));
+        assert_eq!(parser.push(&response(vec![ soa(5) ])), Ok(vec![]));
+        assert!(parser.is_finished());
+    }
+
+    #[test]
+    fn pseudo_records_are_skipped() {
+        let opt = Answer::Pseudo {
+            qname: Labels::root(),
+            opt: OPT { udp_payload_size: 1232, higher_bits: 0, edns0_version: 0, flags: 0, data: vec![] },
+        };
+
+        let mut parser = AxfrParser::new();
+        assert_eq!(parser.push(&response(vec![ soa(5), opt, a(1), soa(5) ])),
+                   Ok(vec![ soa(5), a(1) ]));
+    }
+
+    #[test]
+    fn refused() {
+        let mut refused = response(vec![]);
+        refused.flags.error_code = Some(ErrorCode::QueryRefused);
+
+        assert_eq!(AxfrParser::new().push(&refused),
+                   Err(TransferError::ErrorCode(ErrorCode::QueryRefused)));
+    }
+
+    #[test]
+    fn no_opening_soa() {
+        assert_eq!(AxfrParser::new().push(&response(vec![ a(1), soa(5) ])),
+                   Err(TransferError::NoOpeningSoa));
+    }
+
+    #[test]
+    fn empty_first_message() {
+        assert_eq!(AxfrParser::new().push(&response(vec![])),
+                   Err(TransferError::NoOpeningSoa));
+    }
+
+    #[test]
+    fn serial_changed() {
+        let mut parser = AxfrParser::new();
+        assert_eq!(parser.push(&response(vec![ soa(5), a(1), soa(6) ])),
+                   Err(TransferError::SerialChanged { opening: 5, closing: 6 }));
+    }
+
+    #[test]
+    fn records_after_end() {
+        let mut parser = AxfrParser::new();
+        assert_eq!(parser.push(&response(vec![ soa(5), soa(5), a(1) ])),
+                   Err(TransferError::RecordsAfterEnd));
+
+        let mut parser = AxfrParser::new();
+        parser.push(&response(vec![ soa(5), soa(5) ])).unwrap();
+        assert_eq!(parser.push(&response(vec![ a(1) ])),
+                   Err(TransferError::RecordsAfterEnd));
+    }
+
+    #[test]
+    fn malformed_message() {
+        assert_eq!(AxfrParser::new().read_message(&[ 0x12, 0x34 ]),
+                   Err(TransferError::Malformed(WireError::IO)));
+    }
+}
@@ -0,0 +1,149 @@
+//! Composing DNS messages of any shape, rather than the single-query
+//! requests that `Request` can represent.
+
+use std::convert::TryFrom;
+
+use crate::record::OPT;
+use crate::strings::{Labels, NameCompressor};
+use crate::types::*;
+use crate::wire::*;
+
+
+/// A builder for a DNS message, which can have any number of queries and
+/// records in each section, and any flags and opcode. Start one with
+/// `Request::builder()`, then turn it into bytes with `to_bytes`.
+///
+/// Nameservers usually only answer the first query in a message, so
+/// messages with more than one are mostly useful for testing them.
+#[derive(PartialEq, Debug, Clone)]
+pub struct RequestBuilder {
+    transaction_id: u16,
+    flags: Flags,
+    queries: Vec<Query>,
+    answers: Vec<Answer>,
+    authorities: Vec<Answer>,
+    additionals: Vec<Answer>,
+    compress_names: bool,
+}
+
+impl Request {
+
+    /// Starts building a message. It has a transaction ID of zero, the flags
+    /// of a standard query, and no queries or records until they are added.
+    pub fn builder() -> RequestBuilder {
+        RequestBuilder {
+            transaction_id: 0,
+            flags: Flags::query(),
+            queries: Vec::new(),
+            answers: Vec::new(),
+            authorities: Vec::new(),
+            additionals: Vec::new(),
+            compress_names: true,
+        }
+    }
+}
+
+impl RequestBuilder {
+
+    /// Sets the transaction ID of the message.
+    pub fn transaction_id(mut self, transaction_id: u16) -> Self {
+        self.transaction_id = transaction_id;
+        self
+    }
+
+    /// Sets all the flags of the message at once, including its opcode and
+    /// error code.
+    pub fn flags(mut self, flags: Flags) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    /// Sets the opcode of the message, leaving the other flags alone.
+    pub fn opcode(mut self, opcode: Opcode) -> Self {
+        self.flags.opcode = opcode;
+        self
+    }
+
+    /// Adds a query to the end of the question section.
+    pub fn query(mut self, query: Query) -> Self {
+        self.queries.push(query);
+        self
+    }
+
+    /// Adds a record to the end of the answer section.
+    pub fn answer(mut self, answer: Answer) -> Self {
+        self.answers.push(answer);
+        self
+    }
+
+    /// Adds a record to the end of the authority section.
+    pub fn authority(mut self, authority: Answer) -> Self {
+        self.authorities.push(authority);
+        self
+    }
+
+    /// Adds a record to the end of the additional section.
+    pub fn additional(mut self, additional: Answer) -> Self {
+        self.additionals.push(additional);
+        self
+    }
+
+    /// Adds an OPT pseudo-record, owned by the root, to the end of the
+    /// additional section.
+    pub fn opt(self, opt: OPT) -> Self {
+        self.additional(Answer::Pseudo { qname: Labels::root(), opt })
+    }
+
+    /// Sets whether domain names should be compressed by pointing back to
+    /// the names written earlier in the message, which they are unless this
+    /// gets turned off. Only the names of queries and records get
+    /// compressed, and never the names inside record data.
+    pub fn compress_names(mut self, compress_names: bool) -> Self {
+        self.compress_names = compress_names;
+        self
+    }
+
+    /// Converts the message to a vector of bytes.
+    ///
+    /// Returns an error if a section has more entries than its count can
+    /// hold, or if a record cannot be written.
+    pub fn to_bytes(&self) -> io::Result<Vec<u8>> {
+        let mut bytes = Vec::with_capacity(32);
+
+        bytes.write_u16::<BigEndian>(self.transaction_id)?;
+        bytes.write_u16::<BigEndian>(self.flags.to_u16())?;
+
+        bytes.write_u16::<BigEndian>(section_count(self.queries.len())?)?;
+        bytes.write_u16::<BigEndian>(section_count(self.answers.len())?)?;
+        bytes.write_u16::<BigEndian>(section_count(self.authorities.len())?)?;
+        bytes.write_u16::<BigEndian>(section_count(self.additionals.len())?)?;
+
+        let mut names = NameCompressor::default();
+
+        for query in &self.queries {
+            if self.compress_names {
+                query.write_compressed(&mut bytes, &mut names)?;
+            }
+            else {
+                query.write(&mut bytes)?;
+            }
+        }
+
+        for answer in self.answers.iter().chain(&self.authorities).chain(&self.additionals) {
+            if self.compress_names {
+                answer.write_compressed(&mut bytes, &mut names)?;
+            }
+            else {
+                answer.write(&mut bytes)?;
+            }
+        }
+
+        Ok(bytes)
+    }
+}
+
+fn section_count(len: usize) -> io::Result<u16> {
+    u16::try_from(len)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "too many entries in one section"))
+}
+
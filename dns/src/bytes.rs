@@ -0,0 +1,204 @@
+//! A stand-in for the parts of `std::io`, and of byteorder’s extension
+//! traits, that the wire layer uses, for when the crate is built without the
+//! standard library. Reading only ever happens from a buffer that is already
+//! in memory, and writing only ever appends to a vector, so these are a lot
+//! simpler than the traits they replace — but they have the same names and
+//! signatures, so the record decoders do not need to know which ones they
+//! are using.
+
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::fmt;
+
+use byteorder::ByteOrder;
+
+
+/// The result of reading or writing.
+pub type Result<T> = core::result::Result<T, Error>;
+
+/// Something that went wrong reading or writing.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub struct Error {
+    kind: ErrorKind,
+    message: &'static str,
+}
+
+/// The kinds of error that reading and writing can return.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum ErrorKind {
+
+    /// The buffer ran out before as many bytes as were needed were read.
+    UnexpectedEof,
+
+    /// The value being written cannot be encoded.
+    InvalidInput,
+}
+
+impl Error {
+
+    /// Creates a new error of the given kind.
+    pub fn new(kind: ErrorKind, message: &'static str) -> Self {
+        Self { kind, message }
+    }
+
+    /// Returns the kind of this error.
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+}
+
+impl From<ErrorKind> for Error {
+    fn from(kind: ErrorKind) -> Self {
+        Self::new(kind, "")
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}: {}", self.kind, self.message)
+    }
+}
+
+
+/// A position in a buffer of bytes, which reads from the buffer and moves
+/// forward as it goes.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Cursor<T> {
+    inner: T,
+    position: u64,
+}
+
+impl<T> Cursor<T> {
+
+    /// Creates a cursor at the start of the given buffer.
+    pub fn new(inner: T) -> Self {
+        Self { inner, position: 0 }
+    }
+
+    /// Returns the buffer this cursor reads from.
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Returns the buffer this cursor reads from, consuming the cursor.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    /// Returns how far into the buffer the cursor is.
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Moves the cursor to the given position in the buffer.
+    pub fn set_position(&mut self, position: u64) {
+        self.position = position;
+    }
+}
+
+
+/// Something that bytes can be read from.
+pub trait Read {
+
+    /// Fills the given buffer, returning an error and leaving the reader
+    /// at its end if there are not enough bytes left.
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<()>;
+}
+
+impl<T: AsRef<[u8]>> Read for Cursor<T> {
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<()> {
+        let bytes = self.inner.as_ref();
+        let start = usize::try_from(self.position).unwrap_or(usize::MAX).min(bytes.len());
+        let remaining = &bytes[start ..];
+
+        if remaining.len() < buf.len() {
+            self.position = bytes.len() as u64;
+            return Err(Error::new(ErrorKind::UnexpectedEof, "failed to fill whole buffer"));
+        }
+
+        buf.copy_from_slice(&remaining[.. buf.len()]);
+        self.position += buf.len() as u64;
+        Ok(())
+    }
+}
+
+/// Something that bytes can be written to.
+pub trait Write {
+
+    /// Writes all of the given bytes.
+    fn write_all(&mut self, buf: &[u8]) -> Result<()>;
+}
+
+impl Write for Vec<u8> {
+    fn write_all(&mut self, buf: &[u8]) -> Result<()> {
+        self.extend_from_slice(buf);
+        Ok(())
+    }
+}
+
+
+/// Reads numbers in the given byte order.
+pub trait ReadBytesExt: Read {
+
+    /// Reads one byte.
+    fn read_u8(&mut self) -> Result<u8> {
+        let mut buf = [0; 1];
+        self.read_exact(&mut buf)?;
+        Ok(buf[0])
+    }
+
+    /// Reads a 16-bit number.
+    fn read_u16<B: ByteOrder>(&mut self) -> Result<u16> {
+        let mut buf = [0; 2];
+        self.read_exact(&mut buf)?;
+        Ok(B::read_u16(&buf))
+    }
+
+    /// Reads a 32-bit number.
+    fn read_u32<B: ByteOrder>(&mut self) -> Result<u32> {
+        let mut buf = [0; 4];
+        self.read_exact(&mut buf)?;
+        Ok(B::read_u32(&buf))
+    }
+
+    /// Reads a 48-bit number.
+    fn read_u48<B: ByteOrder>(&mut self) -> Result<u64> {
+        let mut buf = [0; 6];
+        self.read_exact(&mut buf)?;
+        Ok(B::read_u48(&buf))
+    }
+}
+
+impl<R: Read + ?Sized> ReadBytesExt for R {}
+
+/// Writes numbers in the given byte order.
+pub trait WriteBytesExt: Write {
+
+    /// Writes one byte.
+    fn write_u8(&mut self, number: u8) -> Result<()> {
+        self.write_all(&[ number ])
+    }
+
+    /// Writes a 16-bit number.
+    fn write_u16<B: ByteOrder>(&mut self, number: u16) -> Result<()> {
+        let mut buf = [0; 2];
+        B::write_u16(&mut buf, number);
+        self.write_all(&buf)
+    }
+
+    /// Writes a 32-bit number.
+    fn write_u32<B: ByteOrder>(&mut self, number: u32) -> Result<()> {
+        let mut buf = [0; 4];
+        B::write_u32(&mut buf, number);
+        self.write_all(&buf)
+    }
+
+    /// Writes a 48-bit number.
+    fn write_u48<B: ByteOrder>(&mut self, number: u64) -> Result<()> {
+        let mut buf = [0; 6];
+        B::write_u48(&mut buf, number);
+        self.write_all(&buf)
+    }
+}
+
+impl<W: Write + ?Sized> WriteBytesExt for W {}
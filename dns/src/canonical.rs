@@ -0,0 +1,167 @@
+//! The canonical form and order of records, which is how the records in a
+//! set have to be arranged before a DNSSEC signature over them can be
+//! checked.
+//!
+//! # References
+//!
+//! - [RFC 4034 §6](https://tools.ietf.org/html/rfc4034#section-6) —
+//!   Resource Records for the DNS Security Extensions (March 2005)
+//! - [RFC 6840 §5.1](https://tools.ietf.org/html/rfc6840#section-5.1) —
+//!   Clarifications and Implementation Notes for DNS Security (February 2013)
+
+use std::convert::TryFrom;
+
+use crate::record::*;
+use crate::strings::{Labels, WriteLabels};
+use crate::types::*;
+use crate::wire::*;
+
+
+impl Record {
+
+    /// Returns the record in its canonical form, with the domain names in
+    /// its data made lowercase for the record types that DNSSEC requires
+    /// it for. The names in records of other types, such as SVCB, are left
+    /// as they are.
+    pub fn to_canonical(&self) -> Self {
+        match self {
+            Self::CNAME(r)  => Self::CNAME(CNAME { domain: r.domain.to_canonical() }),
+            Self::MX(r)     => Self::MX(MX { exchange: r.exchange.to_canonical(), .. r.clone() }),
+            Self::NAPTR(r)  => Self::NAPTR(NAPTR { replacement: r.replacement.to_canonical(), .. r.clone() }),
+            Self::NS(r)     => Self::NS(NS { nameserver: r.nameserver.to_canonical() }),
+            Self::PTR(r)    => Self::PTR(PTR { cname: r.cname.to_canonical() }),
+            Self::RRSIG(r)  => Self::RRSIG(RRSIG { signer_name: r.signer_name.to_canonical(), .. r.clone() }),
+            Self::SOA(r)    => Self::SOA(SOA { mname: r.mname.to_canonical(), rname: r.rname.to_canonical(), .. r.clone() }),
+            Self::SRV(r)    => Self::SRV(SRV { target: r.target.to_canonical(), .. r.clone() }),
+            other           => other.clone(),
+        }
+    }
+}
+
+
+/// Returns the data of each record in canonical form, sorted into
+/// canonical order, which compares the data as strings of bytes. Records
+/// with the same data only appear once.
+///
+/// Returns an error if a record cannot be written.
+pub fn canonical_rdatas(records: &[Record]) -> io::Result<Vec<Vec<u8>>> {
+    let mut rdatas = records.iter()
+        .map(|record| {
+            let mut data = Vec::new();
+            record.to_canonical().write(&mut data)?;
+            Ok(data)
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+
+    rdatas.sort();
+    rdatas.dedup();
+    Ok(rdatas)
+}
+
+/// Returns the bytes of a record set in the form its signature is made
+/// over: each record in canonical order, with the owner name in canonical
+/// form and without compression, and the TTL from the signature rather
+/// than the TTL the records were served with. Every record must have the
+/// same type.
+///
+/// Returns an error if the records are not all the same type, or a
+/// record cannot be written.
+pub fn canonical_rrset(owner: &Labels, qclass: QClass, original_ttl: u32, records: &[Record]) -> io::Result<Vec<u8>> {
+    let record_type = match records.first() {
+        Some(record)  => record.record_type(),
+        None          => return Ok(Vec::new()),
+    };
+
+    if records.iter().any(|record| record.record_type() != record_type) {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "records in a set must all have the same type"));
+    }
+
+    let owner = owner.to_canonical();
+    let mut bytes = Vec::new();
+
+    for rdata in canonical_rdatas(records)? {
+        let data_length = u16::try_from(rdata.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "record data is longer than 65535 bytes"))?;
+
+        bytes.write_labels(&owner)?;
+        bytes.write_u16::<BigEndian>(record_type.type_number())?;
+        bytes.write_u16::<BigEndian>(qclass.to_u16())?;
+        bytes.write_u32::<BigEndian>(original_ttl)?;
+        bytes.write_u16::<BigEndian>(data_length)?;
+        bytes.extend(rdata);
+    }
+
+    Ok(bytes)
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::net::Ipv4Addr;
+    use pretty_assertions::assert_eq;
+
+    fn a(last: u8) -> Record {
+        Record::A(A { address: Ipv4Addr::new(192, 0, 2, last) })
+    }
+
+    #[test]
+    fn lowercases_names_in_data() {
+        let record = Record::MX(MX { preference: 10, exchange: Labels::encode("Mail.Lookup.DOG").unwrap() });
+        assert_eq!(record.to_canonical(),
+                   Record::MX(MX { preference: 10, exchange: Labels::encode("mail.lookup.dog").unwrap() }));
+    }
+
+    #[test]
+    fn sorts_and_dedups() {
+        let rdatas = canonical_rdatas(&[ a(3), a(1), a(2), a(1) ]).unwrap();
+        assert_eq!(rdatas, vec![
+            vec![ 192, 0, 2, 1 ],
+            vec![ 192, 0, 2, 2 ],
+            vec![ 192, 0, 2, 3 ],
+        ]);
+    }
+
+    #[test]
+    fn sorts_by_canonical_data() {
+        let upper = Record::NS(NS { nameserver: Labels::encode("B.lookup.dog").unwrap() });
+        let lower = Record::NS(NS { nameserver: Labels::encode("a.lookup.dog").unwrap() });
+
+        let rdatas = canonical_rdatas(&[ upper, lower ]).unwrap();
+        assert_eq!(rdatas[0][1], b'a');
+        assert_eq!(rdatas[1][1], b'b');
+    }
+
+    #[test]
+    fn rrset_bytes() {
+        let owner = Labels::encode("DOG").unwrap();
+        let bytes = canonical_rrset(&owner, QClass::IN, 3600, &[ a(2), a(1) ]).unwrap();
+
+        assert_eq!(bytes, vec![
+            0x03, b'd', b'o', b'g', 0x00,  // owner name
+            0x00, 0x01,  // type A
+            0x00, 0x01,  // class IN
+            0x00, 0x00, 0x0e, 0x10,  // original TTL
+            0x00, 0x04,  // data length
+            192, 0, 2, 1,  // data
+
+            0x03, b'd', b'o', b'g', 0x00,  // owner name
+            0x00, 0x01,  // type A
+            0x00, 0x01,  // class IN
+            0x00, 0x00, 0x0e, 0x10,  // original TTL
+            0x00, 0x04,  // data length
+            192, 0, 2, 2,  // data
+        ]);
+    }
+
+    #[test]
+    fn mixed_types() {
+        let ns = Record::NS(NS { nameserver: Labels::encode("ns.lookup.dog").unwrap() });
+        assert!(canonical_rrset(&Labels::root(), QClass::IN, 60, &[ a(1), ns ]).is_err());
+    }
+
+    #[test]
+    fn empty_rrset() {
+        assert_eq!(canonical_rrset(&Labels::root(), QClass::IN, 60, &[]).unwrap(), Vec::<u8>::new());
+    }
+}
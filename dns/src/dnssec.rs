@@ -0,0 +1,770 @@
+//! Verifying the DNSSEC signatures over sets of records, so that answers
+//! can be checked cryptographically, instead of relying on the AD bit set
+//! by a resolver that the network between here and there could have
+//! tampered with.
+//!
+//! # References
+//!
+//! - [RFC 4034 §3.1.8.1](https://tools.ietf.org/html/rfc4034#section-3.1.8.1) —
+//!   Resource Records for the DNS Security Extensions (March 2005)
+//! - [RFC 4034 §5.1.4](https://tools.ietf.org/html/rfc4034#section-5.1.4) —
+//!   Resource Records for the DNS Security Extensions (March 2005)
+//! - [RFC 4035 §5.3](https://tools.ietf.org/html/rfc4035#section-5.3) —
+//!   Protocol Modifications for the DNS Security Extensions (March 2005)
+//! - [RFC 5155 §5](https://tools.ietf.org/html/rfc5155#section-5) — DNS
+//!   Security (DNSSEC) Hashed Authenticated Denial of Existence (March 2008)
+
+use std::cmp::Ordering;
+use std::fmt;
+
+use ring::digest;
+use ring::signature::{self, RsaPublicKeyComponents, UnparsedPublicKey};
+
+use crate::canonical::canonical_rrset;
+use crate::rrset::RRset;
+use crate::record::{base32hex, RecordType, DNSKEY, DS, NSEC, NSEC3, RRSIG};
+use crate::strings::{Labels, WriteLabels};
+use crate::wire::*;
+
+
+/// The public key to verify a signature with, along with the name of the
+/// zone it belongs to.
+#[derive(Debug, Copy, Clone)]
+pub struct SigningKey<'a> {
+
+    /// The owner name of the DNSKEY record, which is the zone’s apex.
+    pub owner: &'a Labels,
+
+    /// The DNSKEY record itself.
+    pub dnskey: &'a DNSKEY,
+}
+
+/// A signature algorithm that dog can verify signatures made with.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum Algorithm {
+
+    /// RSA with SHA-256 (algorithm 8).
+    RsaSha256,
+
+    /// RSA with SHA-512 (algorithm 10).
+    RsaSha512,
+
+    /// ECDSA over the P-256 curve with SHA-256 (algorithm 13).
+    EcdsaP256Sha256,
+
+    /// ECDSA over the P-384 curve with SHA-384 (algorithm 14).
+    EcdsaP384Sha384,
+
+    /// Ed25519 (algorithm 15).
+    Ed25519,
+}
+
+impl Algorithm {
+
+    /// Returns the algorithm with the given number, or `None` if it is one
+    /// that dog cannot verify.
+    pub fn from_number(number: u8) -> Option<Self> {
+        match number {
+             8 => Some(Self::RsaSha256),
+            10 => Some(Self::RsaSha512),
+            13 => Some(Self::EcdsaP256Sha256),
+            14 => Some(Self::EcdsaP384Sha384),
+            15 => Some(Self::Ed25519),
+             _ => None,
+        }
+    }
+}
+
+
+/// Something that stops a signature from verifying.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum VerifyError {
+
+    /// There are no records in the set.
+    EmptyRRset,
+
+    /// The signature covers a different type from the records in the set.
+    WrongType,
+
+    /// The signature says it was made by a key for a zone that the records
+    /// are not in.
+    WrongSigner,
+
+    /// The signature’s key tag or algorithm are not the key’s, the key does
+    /// not belong to the zone that made the signature, or the key is not
+    /// marked as a zone key.
+    KeyMismatch,
+
+    /// The signature says its owner name has more labels than it does.
+    TooManyLabels,
+
+    /// The signature’s validity period has not begun yet.
+    NotYetValid,
+
+    /// The signature’s validity period has ended.
+    Expired,
+
+    /// The signature was made with an algorithm dog cannot verify.
+    UnsupportedAlgorithm(u8),
+
+    /// The DS record’s digest was made with a digest type dog cannot
+    /// compute.
+    UnsupportedDigest(u8),
+
+    /// The NSEC3 record’s names were hashed with an algorithm dog cannot
+    /// compute.
+    UnsupportedHash(u8),
+
+    /// The public key is not in the format its algorithm uses.
+    MalformedKey,
+
+    /// A record in the set could not be written out in canonical form.
+    Unwritable,
+
+    /// The signature does not match the records and the key.
+    BadSignature,
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::EmptyRRset               => write!(f, "no records to verify"),
+            Self::WrongType                => write!(f, "signature covers a different type"),
+            Self::WrongSigner              => write!(f, "signer is not the records’ zone"),
+            Self::KeyMismatch              => write!(f, "key does not match signature"),
+            Self::TooManyLabels            => write!(f, "signature has too many labels"),
+            Self::NotYetValid              => write!(f, "signature not yet valid"),
+            Self::Expired                  => write!(f, "signature expired"),
+            Self::UnsupportedAlgorithm(n)  => write!(f, "unsupported algorithm {}", n),
+            Self::UnsupportedDigest(n)     => write!(f, "unsupported digest type {}", n),
+            Self::UnsupportedHash(n)       => write!(f, "unsupported NSEC3 hash algorithm {}", n),
+            Self::MalformedKey             => write!(f, "malformed public key"),
+            Self::Unwritable               => write!(f, "records could not be written"),
+            Self::BadSignature             => write!(f, "bad signature"),
+        }
+    }
+}
+
+
+/// Verifies that the RRSIG record is a valid signature over the set of
+/// records, made by the given key, at the given time, which is the number
+/// of seconds since the Unix epoch modulo 2³², as signature timestamps are.
+///
+/// The owner name the signature is checked against has the number of
+/// labels the signature says it has, so a record that was synthesised from
+/// a wildcard gets checked against the wildcard name.
+///
+/// # Errors
+///
+/// Returns an error saying what was wrong if the signature does not verify.
+pub fn verify_rrsig(rrset: &RRset, rrsig: &RRSIG, key: SigningKey<'_>, now: u32) -> Result<(), VerifyError> {
+    if rrset.records.is_empty() {
+        return Err(VerifyError::EmptyRRset);
+    }
+
+    if rrset.record_type != RecordType::from(rrsig.type_covered) {
+        return Err(VerifyError::WrongType);
+    }
+
+    if ! rrset.owner.is_within(&rrsig.signer_name) {
+        return Err(VerifyError::WrongSigner);
+    }
+
+    if key.owner.canonical_cmp(&rrsig.signer_name) != Ordering::Equal
+        || rrsig.algorithm != key.dnskey.algorithm
+        || rrsig.key_tag != key.dnskey.key_tag()
+        || key.dnskey.protocol != 3
+        || ! key.dnskey.is_zone_key()
+    {
+        return Err(VerifyError::KeyMismatch);
+    }
+
+    if serial_lt(now, rrsig.signature_inception) {
+        return Err(VerifyError::NotYetValid);
+    }
+
+    if serial_lt(rrsig.signature_expiration, now) {
+        return Err(VerifyError::Expired);
+    }
+
+    let algorithm = Algorithm::from_number(rrsig.algorithm)
+        .ok_or(VerifyError::UnsupportedAlgorithm(rrsig.algorithm))?;
+
+    let message = signed_data(rrset, rrsig)?;
+    verify_signature(algorithm, &key.dnskey.public_key, &message, &rrsig.signature)
+}
+
+/// Whether the DS record refers to the DNSKEY record with the given owner
+/// name: the key tag and algorithm have to be the same, and the digest has
+/// to be the digest of the owner name in canonical form followed by the
+/// DNSKEY record’s data.
+///
+/// # Errors
+///
+/// Returns an error if the DS record uses a digest type that dog cannot
+/// compute, as then there is no way to tell whether it matches.
+pub fn ds_matches(ds: &DS, owner: &Labels, dnskey: &DNSKEY) -> Result<bool, VerifyError> {
+    let algorithm = digest_algorithm(ds.digest_type)
+        .ok_or(VerifyError::UnsupportedDigest(ds.digest_type))?;
+
+    if ds.key_tag != dnskey.key_tag() || ds.algorithm != dnskey.algorithm {
+        return Ok(false);
+    }
+
+    let mut bytes = Vec::new();
+    bytes.write_labels(&owner.to_canonical()).map_err(|_| VerifyError::Unwritable)?;
+    dnskey.write(&mut bytes).map_err(|_| VerifyError::Unwritable)?;
+
+    Ok(digest::digest(algorithm, &bytes).as_ref() == &ds.digest[..])
+}
+
+/// Whether dog can compute digests of the given DS digest type, so that
+/// DS records that use it can be checked.
+pub fn supports_digest(digest_type: u8) -> bool {
+    digest_algorithm(digest_type).is_some()
+}
+
+/// Whether the NSEC record with the given owner name covers a name, which
+/// means that the name comes after the owner name and before the next name
+/// in canonical order, so it does not exist in the zone. The last NSEC
+/// record in a zone has the apex as its next name, so it covers every name
+/// after its owner name.
+pub fn nsec_covers(owner: &Labels, nsec: &NSEC, name: &Labels) -> bool {
+    let after_owner = owner.canonical_cmp(name) == Ordering::Less;
+    let before_next = name.canonical_cmp(&nsec.next_domain) == Ordering::Less;
+
+    if owner.canonical_cmp(&nsec.next_domain) == Ordering::Less {
+        after_owner && before_next
+    }
+    else {
+        after_owner || before_next
+    }
+}
+
+/// Returns the hash of a name, using the algorithm, salt, and number of
+/// iterations from the NSEC3 record, encoded the same way as the first
+/// label of an NSEC3 record’s owner name.
+///
+/// # Errors
+///
+/// Returns an error if the NSEC3 record uses a hash algorithm that dog
+/// cannot compute.
+pub fn nsec3_hash(name: &Labels, nsec3: &NSEC3) -> Result<String, VerifyError> {
+    if nsec3.hash_algorithm != 1 {
+        return Err(VerifyError::UnsupportedHash(nsec3.hash_algorithm));
+    }
+
+    let mut bytes = Vec::new();
+    bytes.write_labels(&name.to_canonical()).map_err(|_| VerifyError::Unwritable)?;
+
+    let mut hash = hash_with_salt(&bytes, &nsec3.salt);
+    for _ in 0 .. nsec3.iterations {
+        hash = hash_with_salt(&hash, &nsec3.salt);
+    }
+
+    Ok(base32hex(&hash))
+}
+
+/// Whether the NSEC3 record, with the given hashed label at the start of
+/// its owner name, covers a name with the given hash, which means that the
+/// hash comes after the owner’s hash and before the next hashed owner name,
+/// wrapping around at the end of the zone in the same way as NSEC records.
+pub fn nsec3_covers(owner_hash: &str, nsec3: &NSEC3, hash: &str) -> bool {
+    // The extended hex alphabet keeps the order of the hashes, so the
+    // labels can be compared without decoding them.
+    let owner_hash = owner_hash.to_ascii_lowercase();
+    let next_hash = nsec3.base32_next_hashed_owner();
+    let hash = hash.to_ascii_lowercase();
+
+    if owner_hash < next_hash {
+        owner_hash < hash && hash < next_hash
+    }
+    else {
+        owner_hash < hash || hash < next_hash
+    }
+}
+
+/// Returns the SHA-1 digest of the data followed by the salt, which is one
+/// iteration of the NSEC3 hash.
+fn hash_with_salt(data: &[u8], salt: &[u8]) -> Vec<u8> {
+    let mut bytes = data.to_vec();
+    bytes.extend_from_slice(salt);
+    digest::digest(&digest::SHA1_FOR_LEGACY_USE_ONLY, &bytes).as_ref().to_vec()
+}
+
+/// Returns the digest algorithm for a DS digest type number.
+fn digest_algorithm(digest_type: u8) -> Option<&'static digest::Algorithm> {
+    match digest_type {
+        1 => Some(&digest::SHA1_FOR_LEGACY_USE_ONLY),
+        2 => Some(&digest::SHA256),
+        4 => Some(&digest::SHA384),
+        _ => None,
+    }
+}
+
+/// Returns the data that the signature was made over: the RRSIG record’s
+/// data without the signature, followed by the records in canonical form
+/// and order, with the signature’s original TTL.
+fn signed_data(rrset: &RRset, rrsig: &RRSIG) -> Result<Vec<u8>, VerifyError> {
+    let owner_labels = rrset.owner.len() - usize::from(rrset.owner.is_wildcard());
+    let signed_labels = usize::from(rrsig.labels);
+
+    let owner = match signed_labels.cmp(&owner_labels) {
+        Ordering::Equal    => rrset.owner.clone(),
+        Ordering::Less     => rrset.owner.ancestor(signed_labels).wildcard(),
+        Ordering::Greater  => return Err(VerifyError::TooManyLabels),
+    };
+
+    let mut bytes = Vec::new();
+    write_rrsig_fields(&mut bytes, rrsig).map_err(|_| VerifyError::Unwritable)?;
+
+    let records = canonical_rrset(&owner, rrset.qclass, rrsig.original_ttl, &rrset.records)
+        .map_err(|_| VerifyError::Unwritable)?;
+    bytes.extend(records);
+
+    Ok(bytes)
+}
+
+/// Writes the fields of an RRSIG record that come before the signature,
+/// with the signer’s name in canonical form.
+fn write_rrsig_fields(bytes: &mut Vec<u8>, rrsig: &RRSIG) -> io::Result<()> {
+    bytes.write_u16::<BigEndian>(rrsig.type_covered)?;
+    bytes.write_u8(rrsig.algorithm)?;
+    bytes.write_u8(rrsig.labels)?;
+    bytes.write_u32::<BigEndian>(rrsig.original_ttl)?;
+    bytes.write_u32::<BigEndian>(rrsig.signature_expiration)?;
+    bytes.write_u32::<BigEndian>(rrsig.signature_inception)?;
+    bytes.write_u16::<BigEndian>(rrsig.key_tag)?;
+    bytes.write_labels(&rrsig.signer_name.to_canonical())?;
+    Ok(())
+}
+
+/// Checks the signature over the message with the public key, which is in
+/// the format the DNSKEY record uses for the algorithm.
+fn verify_signature(algorithm: Algorithm, public_key: &[u8], message: &[u8], signature: &[u8]) -> Result<(), VerifyError> {
+    let result = match algorithm {
+        Algorithm::RsaSha256 => {
+            let key = rsa_public_key(public_key)?;
+            key.verify(&signature::RSA_PKCS1_1024_8192_SHA256_FOR_LEGACY_USE_ONLY, message, signature)
+        }
+        Algorithm::RsaSha512 => {
+            let key = rsa_public_key(public_key)?;
+            key.verify(&signature::RSA_PKCS1_1024_8192_SHA512_FOR_LEGACY_USE_ONLY, message, signature)
+        }
+        Algorithm::EcdsaP256Sha256 => {
+            let key = ecdsa_public_key(public_key, 64)?;
+            UnparsedPublicKey::new(&signature::ECDSA_P256_SHA256_FIXED, key).verify(message, signature)
+        }
+        Algorithm::EcdsaP384Sha384 => {
+            let key = ecdsa_public_key(public_key, 96)?;
+            UnparsedPublicKey::new(&signature::ECDSA_P384_SHA384_FIXED, key).verify(message, signature)
+        }
+        Algorithm::Ed25519 => {
+            if public_key.len() != 32 {
+                return Err(VerifyError::MalformedKey);
+            }
+
+            UnparsedPublicKey::new(&signature::ED25519, public_key).verify(message, signature)
+        }
+    };
+
+    result.map_err(|_| VerifyError::BadSignature)
+}
+
+/// Splits an RSA public key into its exponent and modulus. The exponent’s
+/// length comes first, in one byte, or in the two bytes after a zero byte
+/// if it is longer than 255 bytes.
+///
+/// # References
+///
+/// - [RFC 3110 §2](https://tools.ietf.org/html/rfc3110#section-2) — RSA/SHA-1
+///   SIGs and RSA KEYs in the Domain Name System (May 2001)
+fn rsa_public_key(public_key: &[u8]) -> Result<RsaPublicKeyComponents<&[u8]>, VerifyError> {
+    let (exponent_length, rest) = match public_key {
+        [ 0, high, low, rest @ .. ]  => (usize::from(u16::from_be_bytes([ *high, *low ])), rest),
+        [ length, rest @ .. ]        => (usize::from(*length), rest),
+        []                           => return Err(VerifyError::MalformedKey),
+    };
+
+    if exponent_length == 0 || rest.len() <= exponent_length {
+        return Err(VerifyError::MalformedKey);
+    }
+
+    let (e, n) = rest.split_at(exponent_length);
+    let n = &n[n.iter().take_while(|byte| **byte == 0).count() ..];
+    if n.is_empty() {
+        return Err(VerifyError::MalformedKey);
+    }
+
+    Ok(RsaPublicKeyComponents { n, e })
+}
+
+/// Turns an ECDSA public key, which DNSKEY records hold as the two
+/// coordinates of the point next to each other, into the uncompressed
+/// point format, which has an extra byte at the start.
+///
+/// # References
+///
+/// - [RFC 6605 §4](https://tools.ietf.org/html/rfc6605#section-4) — Elliptic
+///   Curve Digital Signature Algorithm (DSA) for DNSSEC (April 2012)
+fn ecdsa_public_key(public_key: &[u8], length: usize) -> Result<Vec<u8>, VerifyError> {
+    if public_key.len() != length {
+        return Err(VerifyError::MalformedKey);
+    }
+
+    let mut point = Vec::with_capacity(length + 1);
+    point.push(0x04);
+    point.extend_from_slice(public_key);
+    Ok(point)
+}
+
+/// Whether one timestamp comes before another, using serial number
+/// arithmetic, so that timestamps still compare correctly after they
+/// wrap around in 2106.
+///
+/// # References
+///
+/// - [RFC 1982 §3.2](https://tools.ietf.org/html/rfc1982#section-3.2) —
+///   Serial Number Arithmetic (August 1996)
+fn serial_lt(one: u32, other: u32) -> bool {
+    one != other && other.wrapping_sub(one) < 0x8000_0000
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::net::Ipv4Addr;
+    use crate::record::{Record, A};
+    use crate::types::QClass;
+    use pretty_assertions::assert_eq;
+
+    const INCEPTION: u32 = 1_600_000_000;
+    const EXPIRATION: u32 = 1_700_000_000;
+    const NOW: u32 = 1_650_000_000;
+
+    fn name(input: &str) -> Labels {
+        Labels::encode(input).unwrap()
+    }
+
+    fn a(last: u8) -> Record {
+        Record::A(A { address: Ipv4Addr::new(192, 0, 2, last) })
+    }
+
+    fn dnskey() -> DNSKEY {
+        DNSKEY { flags: 256, protocol: 3, algorithm: 15, public_key: vec![ 0x42; 32 ] }
+    }
+
+    fn rrsig(key: &DNSKEY) -> RRSIG {
+        RRSIG {
+            type_covered: 1,
+            algorithm: 15,
+            labels: 2,
+            original_ttl: 3600,
+            signature_expiration: EXPIRATION,
+            signature_inception: INCEPTION,
+            key_tag: key.key_tag(),
+            signer_name: name("lookup.dog"),
+            signature: vec![ 0x00; 64 ],
+        }
+    }
+
+    fn verify(owner: &str, records: &[Record], rrsig: &RRSIG, key: &DNSKEY, now: u32) -> Result<(), VerifyError> {
+        let owner = name(owner);
+        let key_owner = name("lookup.dog");
+        let rrset = RRset { owner, qclass: QClass::IN, record_type: RecordType::A, ttl: 3600, records: records.to_vec() };
+        verify_rrsig(&rrset, rrsig, SigningKey { owner: &key_owner, dnskey: key }, now)
+    }
+
+    #[test]
+    fn signed_data_layout() {
+        let key = dnskey();
+        let rrsig = RRSIG { signer_name: name("LOOKUP.dog"), .. rrsig(&key) };
+        let owner = name("Lookup.Dog");
+        let rrset = RRset { owner, qclass: QClass::IN, record_type: RecordType::A, ttl: 3600, records: vec![ a(2), a(1) ] };
+
+        let mut expected = vec![
+            0x00, 0x01,  // type covered
+            0x0f,  // algorithm
+            0x02,  // labels
+            0x00, 0x00, 0x0e, 0x10,  // original TTL
+        ];
+        expected.extend_from_slice(&EXPIRATION.to_be_bytes());
+        expected.extend_from_slice(&INCEPTION.to_be_bytes());
+        expected.extend_from_slice(&key.key_tag().to_be_bytes());
+        expected.extend_from_slice(b"\x06lookup\x03dog\x00");  // signer name
+        expected.extend(canonical_rrset(&name("lookup.dog"), QClass::IN, 3600, &[ a(1), a(2) ]).unwrap());
+
+        assert_eq!(signed_data(&rrset, &rrsig).unwrap(), expected);
+    }
+
+    #[test]
+    fn signed_data_for_wildcards() {
+        let key = dnskey();
+        let owner = name("anything.lookup.dog");
+        let rrset = RRset { owner, qclass: QClass::IN, record_type: RecordType::A, ttl: 3600, records: vec![ a(1) ] };
+
+        let data = signed_data(&rrset, &rrsig(&key)).unwrap();
+        let records = canonical_rrset(&name("*.lookup.dog"), QClass::IN, 3600, &[ a(1) ]).unwrap();
+        assert!(data.ends_with(&records));
+    }
+
+    #[test]
+    fn signed_data_for_wildcard_owners() {
+        let key = dnskey();
+        let owner = name("*.lookup.dog");
+        let rrset = RRset { owner, qclass: QClass::IN, record_type: RecordType::A, ttl: 3600, records: vec![ a(1) ] };
+
+        assert!(signed_data(&rrset, &rrsig(&key)).is_ok());
+    }
+
+    #[test]
+    fn bad_signature() {
+        let key = dnskey();
+        assert_eq!(verify("lookup.dog", &[ a(1) ], &rrsig(&key), &key, NOW),
+                   Err(VerifyError::BadSignature));
+    }
+
+    #[test]
+    fn empty_rrset() {
+        let key = dnskey();
+        assert_eq!(verify("lookup.dog", &[], &rrsig(&key), &key, NOW),
+                   Err(VerifyError::EmptyRRset));
+    }
+
+    #[test]
+    fn wrong_type() {
+        let key = dnskey();
+        let rrsig = RRSIG { type_covered: 28, .. rrsig(&key) };
+        assert_eq!(verify("lookup.dog", &[ a(1) ], &rrsig, &key, NOW),
+                   Err(VerifyError::WrongType));
+    }
+
+    #[test]
+    fn wrong_signer() {
+        let key = dnskey();
+        assert_eq!(verify("lookup.cat", &[ a(1) ], &rrsig(&key), &key, NOW),
+                   Err(VerifyError::WrongSigner));
+    }
+
+    #[test]
+    fn wrong_key_tag() {
+        let key = dnskey();
+        let rrsig = RRSIG { key_tag: key.key_tag().wrapping_add(1), .. rrsig(&key) };
+        assert_eq!(verify("lookup.dog", &[ a(1) ], &rrsig, &key, NOW),
+                   Err(VerifyError::KeyMismatch));
+    }
+
+    #[test]
+    fn not_a_zone_key() {
+        let key = DNSKEY { flags: 0, .. dnskey() };
+        assert_eq!(verify("lookup.dog", &[ a(1) ], &rrsig(&key), &key, NOW),
+                   Err(VerifyError::KeyMismatch));
+    }
+
+    #[test]
+    fn key_from_another_zone() {
+        let key = dnskey();
+        let rrsig = RRSIG { signer_name: name("dog"), .. rrsig(&key) };
+        assert_eq!(verify("lookup.dog", &[ a(1) ], &rrsig, &key, NOW),
+                   Err(VerifyError::KeyMismatch));
+    }
+
+    #[test]
+    fn too_many_labels() {
+        let key = dnskey();
+        let rrsig = RRSIG { labels: 3, .. rrsig(&key) };
+        assert_eq!(verify("lookup.dog", &[ a(1) ], &rrsig, &key, NOW),
+                   Err(VerifyError::TooManyLabels));
+    }
+
+    #[test]
+    fn not_yet_valid() {
+        let key = dnskey();
+        assert_eq!(verify("lookup.dog", &[ a(1) ], &rrsig(&key), &key, INCEPTION - 1),
+                   Err(VerifyError::NotYetValid));
+    }
+
+    #[test]
+    fn expired() {
+        let key = dnskey();
+        assert_eq!(verify("lookup.dog", &[ a(1) ], &rrsig(&key), &key, EXPIRATION + 1),
+                   Err(VerifyError::Expired));
+    }
+
+    #[test]
+    fn validity_across_wraparound() {
+        assert!(serial_lt(0xFFFF_FFF0, 0x0000_0010));
+        assert!(! serial_lt(0x0000_0010, 0xFFFF_FFF0));
+        assert!(! serial_lt(5, 5));
+    }
+
+    #[test]
+    fn unsupported_algorithm() {
+        let key = DNSKEY { algorithm: 5, .. dnskey() };
+        let rrsig = RRSIG { algorithm: 5, .. rrsig(&key) };
+        assert_eq!(verify("lookup.dog", &[ a(1) ], &rrsig, &key, NOW),
+                   Err(VerifyError::UnsupportedAlgorithm(5)));
+    }
+
+    #[test]
+    fn short_ed25519_key() {
+        let key = DNSKEY { public_key: vec![ 0x42; 31 ], .. dnskey() };
+        assert_eq!(verify("lookup.dog", &[ a(1) ], &rrsig(&key), &key, NOW),
+                   Err(VerifyError::MalformedKey));
+    }
+
+    #[test]
+    fn rsa_key_parts() {
+        let key = rsa_public_key(&[ 0x03, 0x01, 0x00, 0x01, 0x00, 0xab, 0xcd ]).unwrap();
+        assert_eq!(key.e, &[ 0x01, 0x00, 0x01 ]);
+        assert_eq!(key.n, &[ 0xab, 0xcd ]);
+    }
+
+    #[test]
+    fn rsa_key_long_exponent() {
+        let mut public_key = vec![ 0x00, 0x01, 0x00 ];
+        public_key.extend(vec![ 0x01; 256 ]);
+        public_key.extend(vec![ 0xee; 4 ]);
+
+        let key = rsa_public_key(&public_key).unwrap();
+        assert_eq!(key.e.len(), 256);
+        assert_eq!(key.n, &[ 0xee; 4 ]);
+    }
+
+    #[test]
+    fn rsa_key_without_modulus() {
+        assert_eq!(rsa_public_key(&[ 0x03, 0x01, 0x00, 0x01 ]).err(), Some(VerifyError::MalformedKey));
+        assert_eq!(rsa_public_key(&[]).err(), Some(VerifyError::MalformedKey));
+    }
+
+    #[test]
+    fn ecdsa_point() {
+        let point = ecdsa_public_key(&[ 0x11; 64 ], 64).unwrap();
+        assert_eq!(point.len(), 65);
+        assert_eq!(point[0], 0x04);
+        assert_eq!(ecdsa_public_key(&[ 0x11; 64 ], 96), Err(VerifyError::MalformedKey));
+    }
+
+    fn hex(input: &str) -> Vec<u8> {
+        (0 .. input.len()).step_by(2)
+            .map(|i| u8::from_str_radix(&input[i .. i + 2], 16).unwrap())
+            .collect()
+    }
+
+    fn ksk() -> DNSKEY {
+        DNSKEY { flags: 257, protocol: 3, algorithm: 13, public_key: vec![ 0x42; 64 ] }
+    }
+
+    fn ds(digest_type: u8, digest: &str) -> DS {
+        DS { key_tag: 19542, algorithm: 13, digest_type, digest: hex(digest) }
+    }
+
+    #[test]
+    fn ds_sha256() {
+        let ds = ds(2, "266eb8e2ca9eefc285ac94c3a16eb695b7ca544195ea44c57cf4340b7717980d");
+        assert_eq!(ds_matches(&ds, &name("lookup.dog"), &ksk()), Ok(true));
+        assert_eq!(ds_matches(&ds, &name("LOOKUP.DOG"), &ksk()), Ok(true));
+        assert_eq!(ds_matches(&ds, &name("lookup.cat"), &ksk()), Ok(false));
+    }
+
+    #[test]
+    fn ds_sha1() {
+        let ds = ds(1, "c644313e24fc35124cde64d30cfd77be237f8e5c");
+        assert_eq!(ds_matches(&ds, &name("lookup.dog"), &ksk()), Ok(true));
+    }
+
+    #[test]
+    fn ds_sha384() {
+        let ds = ds(4, "6cf1559354f418ab5817362eaf94ea65908b01399721ad15c240ac9464a1fc12e315fc443f7c6e40532475fac2c95de2");
+        assert_eq!(ds_matches(&ds, &name("lookup.dog"), &ksk()), Ok(true));
+    }
+
+    #[test]
+    fn ds_for_another_key() {
+        let ds = ds(2, "266eb8e2ca9eefc285ac94c3a16eb695b7ca544195ea44c57cf4340b7717980d");
+        let key = DNSKEY { public_key: vec![ 0x43; 64 ], .. ksk() };
+        assert_eq!(ds_matches(&ds, &name("lookup.dog"), &key), Ok(false));
+    }
+
+    #[test]
+    fn ds_unsupported_digest() {
+        let ds = ds(3, "00");
+        assert_eq!(ds_matches(&ds, &name("lookup.dog"), &ksk()), Err(VerifyError::UnsupportedDigest(3)));
+    }
+
+    fn nsec(next_domain: &str) -> NSEC {
+        NSEC { next_domain: name(next_domain), types: vec![ 1, 46, 47 ] }
+    }
+
+    #[test]
+    fn nsec_covers_names_between() {
+        let nsec = nsec("d.lookup.dog");
+        assert!(nsec_covers(&name("b.lookup.dog"), &nsec, &name("c.lookup.dog")));
+        assert!(nsec_covers(&name("b.lookup.dog"), &nsec, &name("x.b.lookup.dog")));
+        assert!(! nsec_covers(&name("b.lookup.dog"), &nsec, &name("b.lookup.dog")));
+        assert!(! nsec_covers(&name("b.lookup.dog"), &nsec, &name("d.lookup.dog")));
+        assert!(! nsec_covers(&name("b.lookup.dog"), &nsec, &name("e.lookup.dog")));
+    }
+
+    #[test]
+    fn nsec_covers_names_after_the_last() {
+        let nsec = nsec("lookup.dog");
+        assert!(nsec_covers(&name("x.lookup.dog"), &nsec, &name("y.lookup.dog")));
+        assert!(! nsec_covers(&name("x.lookup.dog"), &nsec, &name("a.lookup.dog")));
+        assert!(! nsec_covers(&name("x.lookup.dog"), &nsec, &name("lookup.dog")));
+    }
+
+    #[test]
+    fn nsec_covers_ignores_case() {
+        assert!(nsec_covers(&name("B.Lookup.Dog"), &nsec("D.lookup.dog"), &name("c.LOOKUP.dog")));
+    }
+
+    fn nsec3(next_hashed_owner: &str) -> NSEC3 {
+        let next_hashed_owner = (0 .. next_hashed_owner.len()).step_by(2)
+            .map(|i| u8::from_str_radix(&next_hashed_owner[i .. i + 2], 16).unwrap())
+            .collect();
+
+        NSEC3 { hash_algorithm: 1, flags: 0, iterations: 12, salt: vec![ 0xaa, 0xbb, 0xcc, 0xdd ], next_hashed_owner, types: vec![] }
+    }
+
+    #[test]
+    fn nsec3_hashes() {
+        // test vectors from RFC 5155 appendix A
+        let params = nsec3("");
+        assert_eq!(nsec3_hash(&name("example"), &params), Ok(String::from("0p9mhaveqvm6t7vbl5lop2u3t2rp3tom")));
+        assert_eq!(nsec3_hash(&name("a.example"), &params), Ok(String::from("35mthgpgcu1qg68fab165klnsnk3dpvl")));
+        assert_eq!(nsec3_hash(&name("*.w.example"), &params), Ok(String::from("r53bq7cc2uvmubfu5ocmm6pers9tk9en")));
+        assert_eq!(nsec3_hash(&name("X.W.Example"), &params), Ok(String::from("b4um86eghhds6nea196smvmlo4ors995")));
+    }
+
+    #[test]
+    fn nsec3_hash_unsupported() {
+        let params = NSEC3 { hash_algorithm: 2, .. nsec3("") };
+        assert_eq!(nsec3_hash(&name("example"), &params), Err(VerifyError::UnsupportedHash(2)));
+    }
+
+    #[test]
+    fn nsec3_covers_hashes_between() {
+        // 35mthgpgcu1qg68fab165klnsnk3dpvl in hex
+        let nsec3 = nsec3("196dd8c3306783a8190f52c262d2b7e5e836e7f5");
+        assert!(nsec3_covers("2T7B4G4VSA5SMI47K61MV5BV1A22BOJR", &nsec3, "2vptu5timamqttgl4luu9kg21e0aor3s"));
+        assert!(! nsec3_covers("2t7b4g4vsa5smi47k61mv5bv1a22bojr", &nsec3, "2t7b4g4vsa5smi47k61mv5bv1a22bojr"));
+        assert!(! nsec3_covers("2t7b4g4vsa5smi47k61mv5bv1a22bojr", &nsec3, "b4um86eghhds6nea196smvmlo4ors995"));
+    }
+
+    #[test]
+    fn nsec3_covers_hashes_after_the_last() {
+        // 0p9mhaveqvm6t7vbl5lop2u3t2rp3tom in hex
+        let nsec3 = nsec3("065368abeed7ec6e9feba96b8c8bc3e8b791f716");
+        assert!(nsec3_covers("t644ebqk9bibcna874givr6joj62mlhv", &nsec3, "v0000000000000000000000000000000"));
+        assert!(nsec3_covers("t644ebqk9bibcna874givr6joj62mlhv", &nsec3, "00000000000000000000000000000000"));
+        assert!(! nsec3_covers("t644ebqk9bibcna874givr6joj62mlhv", &nsec3, "35mthgpgcu1qg68fab165klnsnk3dpvl"));
+    }
+
+    #[test]
+    fn algorithm_numbers() {
+        assert_eq!(Algorithm::from_number(13), Some(Algorithm::EcdsaP256Sha256));
+        assert_eq!(Algorithm::from_number(7), None);
+    }
+}
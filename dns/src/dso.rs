@@ -0,0 +1,497 @@
+//! DNS Stateful Operations, which let a client and server set up a session
+//! over a TCP or TLS connection and agree on how long to keep it open.
+//!
+//! A DSO message has the usual twelve-byte header with the DSO opcode, but
+//! all four of its section counts are zero. In place of the sections, it
+//! holds a list of TLVs _(type-length-values)_, each of which has a type,
+//! the length of its data, and the data itself. The first TLV in a request
+//! says what the request is for, and is echoed back in the response.
+//!
+//! # References
+//!
+//! - [RFC 8490](https://tools.ietf.org/html/rfc8490) — DNS Stateful
+//!   Operations (March 2019)
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::time::Duration;
+
+use log::*;
+
+use crate::types::{ErrorCode, Flags, Opcode};
+use crate::wire::*;
+
+
+/// A **DSO message**, which is either a request, a response to one, or a
+/// unidirectional message that needs no response.
+#[derive(PartialEq, Debug, Clone)]
+pub struct DsoMessage {
+
+    /// The transaction ID of the message. Requests have a non-zero ID,
+    /// which their response shares, and unidirectional messages have an ID
+    /// of zero.
+    pub transaction_id: u16,
+
+    /// The flags of the message. The opcode is always DSO.
+    pub flags: Flags,
+
+    /// The TLVs in the message, in the order they were sent.
+    pub tlvs: Vec<DsoTlv>,
+}
+
+/// One of the TLVs in a DSO message.
+#[derive(PartialEq, Debug, Clone)]
+pub enum DsoTlv {
+
+    /// How long the session may sit idle, and how often the client should
+    /// send traffic to keep it alive.
+    Keepalive(Keepalive),
+
+    /// How long the client should wait before reconnecting, sent by a
+    /// server that is about to close the session.
+    RetryDelay(RetryDelay),
+
+    /// Some padding, to hide the length of the message over TLS.
+    EncryptionPadding(EncryptionPadding),
+
+    /// A TLV with a type that dog does not know how to interpret.
+    Other {
+
+        /// The DSO type number.
+        dso_type: u16,
+
+        /// The TLV’s data, uninterpreted.
+        data: Vec<u8>,
+    },
+}
+
+/// A **Keepalive** TLV, which a client sends to start a session, and which
+/// the server sends back with the timeouts it wants the client to use. Both
+/// times are in milliseconds.
+///
+/// # References
+///
+/// - [RFC 8490 §7.1](https://tools.ietf.org/html/rfc8490#section-7.1) — DNS
+///   Stateful Operations (March 2019)
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub struct Keepalive {
+
+    /// How long the session may go without any requests in progress before
+    /// the client should close it.
+    pub inactivity_timeout: u32,
+
+    /// How long the session may go without any traffic at all before the
+    /// client should send something to keep it alive.
+    pub keepalive_interval: u32,
+}
+
+/// A **Retry Delay** TLV, which a server sends when it is about to close a
+/// session, to say how many milliseconds the client should wait before
+/// connecting again.
+///
+/// # References
+///
+/// - [RFC 8490 §7.2](https://tools.ietf.org/html/rfc8490#section-7.2) — DNS
+///   Stateful Operations (March 2019)
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub struct RetryDelay(pub u32);
+
+/// An **Encryption Padding** TLV, which holds the number of bytes of
+/// padding, which are sent as zeroes.
+///
+/// # References
+///
+/// - [RFC 8490 §7.3](https://tools.ietf.org/html/rfc8490#section-7.3) — DNS
+///   Stateful Operations (March 2019)
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub struct EncryptionPadding(pub u16);
+
+/// Something wrong with a message that was meant to be a DSO message.
+#[derive(PartialEq, Debug)]
+pub enum DsoError {
+
+    /// The message had a different opcode, which usually means the server
+    /// does not support DSO and sent back a normal response.
+    WrongOpcode(Opcode),
+
+    /// The message had records in its sections, which DSO messages never
+    /// have.
+    NonEmptySections,
+
+    /// The message could not be read.
+    Malformed(WireError),
+}
+
+
+impl DsoMessage {
+
+    /// Creates a request that starts a session, with a Keepalive TLV
+    /// holding the timeouts the client would like to use, in milliseconds.
+    pub fn keepalive_request(transaction_id: u16, inactivity_timeout: u32, keepalive_interval: u32) -> Self {
+        let keepalive = Keepalive { inactivity_timeout, keepalive_interval };
+        Self { transaction_id, flags: Self::request_flags(), tlvs: vec![ DsoTlv::Keepalive(keepalive) ] }
+    }
+
+    /// The flags of a DSO request: the DSO opcode, and nothing else set.
+    pub fn request_flags() -> Flags {
+        Flags::from_u16(0b_0011_0000_0000_0000)
+    }
+
+    /// Whether this message is a response to a request.
+    pub fn is_response(&self) -> bool {
+        self.flags.response
+    }
+
+    /// Returns the error code of this message, if it has one. A server
+    /// that does not know the type of the first TLV in a request responds
+    /// with `DSOTYPENI` (11).
+    pub fn error_code(&self) -> Option<ErrorCode> {
+        self.flags.error_code
+    }
+
+    /// Returns the Keepalive TLV in this message, if there is one.
+    pub fn keepalive(&self) -> Option<Keepalive> {
+        self.tlvs.iter().find_map(|tlv| match tlv {
+            DsoTlv::Keepalive(keepalive)  => Some(*keepalive),
+            _                             => None,
+        })
+    }
+
+    /// Returns the Retry Delay TLV in this message, if there is one.
+    pub fn retry_delay(&self) -> Option<RetryDelay> {
+        self.tlvs.iter().find_map(|tlv| match tlv {
+            DsoTlv::RetryDelay(delay)  => Some(*delay),
+            _                          => None,
+        })
+    }
+
+    /// Converts this message to a vector of bytes.
+    pub fn to_bytes(&self) -> io::Result<Vec<u8>> {
+        let mut bytes = Vec::with_capacity(32);
+
+        bytes.write_u16::<BigEndian>(self.transaction_id)?;
+        bytes.write_u16::<BigEndian>(self.flags.to_u16())?;
+
+        for _ in 0 .. 4 {
+            bytes.write_u16::<BigEndian>(0)?;  // section counts
+        }
+
+        for tlv in &self.tlvs {
+            bytes.extend(tlv.to_bytes()?);
+        }
+
+        Ok(bytes)
+    }
+
+    /// Reads bytes off of the given slice, parsing them into a DSO message.
+    #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DsoError> {
+        let mut c = Cursor::new(bytes);
+
+        let transaction_id = c.read_u16::<BigEndian>()?;
+        let flags = Flags::from_u16(c.read_u16::<BigEndian>()?);
+        trace!("Read DSO flags -> {:#?}", flags);
+
+        if flags.opcode != Opcode::Dso {
+            return Err(DsoError::WrongOpcode(flags.opcode));
+        }
+
+        for _ in 0 .. 4 {
+            if c.read_u16::<BigEndian>()? != 0 {
+                return Err(DsoError::NonEmptySections);
+            }
+        }
+
+        let mut tlvs = Vec::new();
+        while c.position() < bytes.len() as u64 {
+            tlvs.push(DsoTlv::read(&mut c)?);
+        }
+
+        debug!("Read {}x DSO TLV", tlvs.len());
+        Ok(Self { transaction_id, flags, tlvs })
+    }
+}
+
+impl DsoTlv {
+
+    /// Reads a single TLV, including its type and length.
+    fn read(c: &mut Cursor<&[u8]>) -> Result<Self, WireError> {
+        let dso_type = c.read_u16::<BigEndian>()?;
+        trace!("Parsed DSO type -> {:?}", dso_type);
+
+        let length = c.read_u16::<BigEndian>()?;
+        trace!("Parsed DSO length -> {:?}", length);
+
+        let mut data = vec![0_u8; usize::from(length)];
+        c.read_exact(&mut data)?;
+
+        match dso_type {
+            Keepalive::DSO_TYPE => {
+                Keepalive::read(length, &mut Cursor::new(&data)).map(Self::Keepalive)
+            }
+            RetryDelay::DSO_TYPE => {
+                RetryDelay::read(length, &mut Cursor::new(&data)).map(Self::RetryDelay)
+            }
+            EncryptionPadding::DSO_TYPE => {
+                Ok(Self::EncryptionPadding(EncryptionPadding(length)))
+            }
+            _ => {
+                Ok(Self::Other { dso_type, data })
+            }
+        }
+    }
+
+    /// Returns this TLV’s DSO type number.
+    pub fn dso_type(&self) -> u16 {
+        match self {
+            Self::Keepalive(_)            => Keepalive::DSO_TYPE,
+            Self::RetryDelay(_)           => RetryDelay::DSO_TYPE,
+            Self::EncryptionPadding(_)    => EncryptionPadding::DSO_TYPE,
+            Self::Other { dso_type, .. }  => *dso_type,
+        }
+    }
+
+    /// Returns this TLV’s data, without its type or length.
+    fn data(&self) -> Vec<u8> {
+        match self {
+            Self::Keepalive(keepalive)  => {
+                let mut data = keepalive.inactivity_timeout.to_be_bytes().to_vec();
+                data.extend(&keepalive.keepalive_interval.to_be_bytes());
+                data
+            }
+            Self::RetryDelay(delay)          => delay.0.to_be_bytes().to_vec(),
+            Self::EncryptionPadding(padding) => vec![0; usize::from(padding.0)],
+            Self::Other { data, .. }         => data.clone(),
+        }
+    }
+
+    /// Serialises this TLV into a vector of bytes.
+    pub fn to_bytes(&self) -> io::Result<Vec<u8>> {
+        let data = self.data();
+        let mut bytes = Vec::with_capacity(4 + data.len());
+
+        bytes.write_u16::<BigEndian>(self.dso_type())?;
+
+        let data_len = u16::try_from(data.len()).expect("Sending too much data");
+        bytes.write_u16::<BigEndian>(data_len)?;
+        bytes.extend(data);
+
+        Ok(bytes)
+    }
+}
+
+impl Keepalive {
+
+    /// The DSO type number associated with keepalives.
+    pub const DSO_TYPE: u16 = 1;
+
+    #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+    fn read(stated_length: u16, c: &mut Cursor<&[u8]>) -> Result<Self, WireError> {
+        if stated_length != 8 {
+            let mandated_length = MandatedLength::Exactly(8);
+            return Err(WireError::WrongRecordLength { stated_length, mandated_length });
+        }
+
+        let inactivity_timeout = c.read_u32::<BigEndian>()?;
+        trace!("Parsed inactivity timeout -> {:?}", inactivity_timeout);
+
+        let keepalive_interval = c.read_u32::<BigEndian>()?;
+        trace!("Parsed keepalive interval -> {:?}", keepalive_interval);
+
+        Ok(Self { inactivity_timeout, keepalive_interval })
+    }
+
+    /// Returns the inactivity timeout as a duration. The largest value
+    /// means the session never times out, so it has no duration.
+    pub fn inactivity_duration(self) -> Option<Duration> {
+        if self.inactivity_timeout == u32::MAX {
+            None
+        }
+        else {
+            Some(Duration::from_millis(u64::from(self.inactivity_timeout)))
+        }
+    }
+}
+
+impl RetryDelay {
+
+    /// The DSO type number associated with retry delays.
+    pub const DSO_TYPE: u16 = 2;
+
+    #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+    fn read(stated_length: u16, c: &mut Cursor<&[u8]>) -> Result<Self, WireError> {
+        if stated_length != 4 {
+            let mandated_length = MandatedLength::Exactly(4);
+            return Err(WireError::WrongRecordLength { stated_length, mandated_length });
+        }
+
+        let delay = c.read_u32::<BigEndian>()?;
+        trace!("Parsed retry delay -> {:?}", delay);
+        Ok(Self(delay))
+    }
+
+    /// Returns the delay as a duration.
+    pub fn duration(self) -> Duration {
+        Duration::from_millis(u64::from(self.0))
+    }
+}
+
+impl EncryptionPadding {
+
+    /// The DSO type number associated with padding.
+    pub const DSO_TYPE: u16 = 3;
+}
+
+
+impl From<WireError> for DsoError {
+    fn from(inner: WireError) -> Self {
+        Self::Malformed(inner)
+    }
+}
+
+impl From<io::Error> for DsoError {
+    fn from(inner: io::Error) -> Self {
+        Self::Malformed(WireError::from(inner))
+    }
+}
+
+impl fmt::Display for DsoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::WrongOpcode(opcode)  => write!(f, "expected a DSO message, got opcode {:?}", opcode),
+            Self::NonEmptySections     => write!(f, "DSO message had records in its sections"),
+            Self::Malformed(_)         => write!(f, "malformed message"),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn build_keepalive_request() {
+        let request = DsoMessage::keepalive_request(0x1234, 15_000, 3_600_000);
+
+        assert_eq!(request.to_bytes().unwrap(), vec![
+            0x12, 0x34,  // transaction ID
+            0x30, 0x00,  // flags (DSO)
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,  // counts (0, 0, 0, 0)
+
+            // keepalive TLV:
+            0x00, 0x01,  // type
+            0x00, 0x08,  // length
+            0x00, 0x00, 0x3a, 0x98,  // inactivity timeout
+            0x00, 0x36, 0xee, 0x80,  // keepalive interval
+        ]);
+    }
+
+    #[test]
+    fn parse_keepalive_response() {
+        let buf = &[
+            0x12, 0x34,  // transaction ID
+            0xb0, 0x00,  // flags (DSO, response)
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,  // counts (0, 0, 0, 0)
+
+            // keepalive TLV:
+            0x00, 0x01, 0x00, 0x08,
+            0x00, 0x00, 0x75, 0x30,
+            0x00, 0x00, 0xea, 0x60,
+
+            // padding TLV:
+            0x00, 0x03, 0x00, 0x02,
+            0x00, 0x00,
+        ];
+
+        let response = DsoMessage::from_bytes(buf).unwrap();
+        assert!(response.is_response());
+        assert_eq!(response.error_code(), None);
+        assert_eq!(response.keepalive(), Some(Keepalive { inactivity_timeout: 30_000, keepalive_interval: 60_000 }));
+        assert_eq!(response.tlvs[1], DsoTlv::EncryptionPadding(EncryptionPadding(2)));
+        assert_eq!(response.keepalive().unwrap().inactivity_duration(), Some(Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn parse_retry_delay() {
+        let buf = &[
+            0x00, 0x00,  // transaction ID (unidirectional)
+            0x30, 0x00,  // flags (DSO)
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,  // counts (0, 0, 0, 0)
+            0x00, 0x02, 0x00, 0x04,
+            0x00, 0x00, 0x13, 0x88,
+        ];
+
+        let message = DsoMessage::from_bytes(buf).unwrap();
+        assert_eq!(message.retry_delay().map(RetryDelay::duration), Some(Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn unknown_tlvs_round_trip() {
+        let message = DsoMessage {
+            transaction_id: 7,
+            flags: DsoMessage::request_flags(),
+            tlvs: vec![ DsoTlv::Other { dso_type: 0xf901, data: vec![ 0xab, 0xcd ] } ],
+        };
+
+        assert_eq!(DsoMessage::from_bytes(&message.to_bytes().unwrap()), Ok(message));
+    }
+
+    #[test]
+    fn not_implemented() {
+        let buf = &[
+            0x12, 0x34,  // transaction ID
+            0xb0, 0x0b,  // flags (DSO, response, DSOTYPENI)
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,  // counts (0, 0, 0, 0)
+        ];
+
+        let response = DsoMessage::from_bytes(buf).unwrap();
+        assert_eq!(response.error_code(), Some(ErrorCode::Other(11)));
+        assert_eq!(response.tlvs, vec![]);
+    }
+
+    #[test]
+    fn normal_response() {
+        let buf = &[
+            0x12, 0x34,  // transaction ID
+            0x80, 0x04,  // flags (query, response, NOTIMP)
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,  // counts (0, 0, 0, 0)
+        ];
+
+        assert_eq!(DsoMessage::from_bytes(buf),
+                   Err(DsoError::WrongOpcode(Opcode::Query)));
+    }
+
+    #[test]
+    fn records_in_sections() {
+        let buf = &[
+            0x12, 0x34,  // transaction ID
+            0x30, 0x00,  // flags (DSO)
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,  // counts (1, 0, 0, 0)
+        ];
+
+        assert_eq!(DsoMessage::from_bytes(buf),
+                   Err(DsoError::NonEmptySections));
+    }
+
+    #[test]
+    fn wrong_keepalive_length() {
+        let buf = &[
+            0x12, 0x34,  // transaction ID
+            0x30, 0x00,  // flags (DSO)
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,  // counts (0, 0, 0, 0)
+            0x00, 0x01, 0x00, 0x04,
+            0x00, 0x00, 0x75, 0x30,
+        ];
+
+        assert_eq!(DsoMessage::from_bytes(buf),
+                   Err(DsoError::Malformed(WireError::WrongRecordLength { stated_length: 4, mandated_length: MandatedLength::Exactly(8) })));
+    }
+
+    #[test]
+    fn truncated() {
+        assert_eq!(DsoMessage::from_bytes(&[ 0x12, 0x34, 0x30 ]),
+                   Err(DsoError::Malformed(WireError::IO)));
+    }
+}
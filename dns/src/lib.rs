@@ -27,9 +27,30 @@
 #![deny(clippy::cast_sign_loss)]
 #![deny(unsafe_code)]
 
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
 
 //! The DNS crate is the ‘library’ part of dog. It implements the DNS
 //! protocol: creating and decoding packets from their byte structure.
+//!
+//! The wire layer — the types, the records, and reading and writing them —
+//! only needs `core` and `alloc`, so turning off the default `std` feature
+//! builds the crate without the standard library. The zone file parser and
+//! the features that need other libraries, such as IDNA and DNSSEC, turn
+//! `std` back on.
+
+
+#[cfg_attr(not(any(feature = "std", test)), macro_use)]
+extern crate alloc;
+
+// Without the standard library, the `std` paths used throughout the crate
+// refer to `core` instead. Everything in `alloc` is imported explicitly.
+// The tests always have the standard library to run in.
+#[cfg(not(any(feature = "std", test)))]
+extern crate core as std;
+
+#[cfg(not(feature = "std"))]
+mod bytes;
 
 
 mod types;
@@ -38,7 +59,54 @@ pub use self::types::*;
 mod strings;
 pub use self::strings::Labels;
 
+mod name;
+pub use self::name::Name;
+
+mod rrset;
+pub use self::rrset::RRset;
+
 mod wire;
-pub use self::wire::{Wire, WireError, MandatedLength};
+pub use self::wire::{Wire, WireError, MandatedLength, NameLimit};
+
+mod builder;
+pub use self::builder::RequestBuilder;
+
+mod canonical;
+pub use self::canonical::{canonical_rdatas, canonical_rrset};
+
+pub mod presentation;
+pub use self::presentation::signature_timestamp;
+
+#[cfg(feature = "with_serde")]
+mod serialise;
+
+#[cfg(feature = "with_dnssec")]
+pub mod dnssec;
+
+pub mod tsig;
+pub use self::tsig::{TsigKey, TsigAlgorithm, TsigError};
+
+pub mod sig0;
+pub use self::sig0::{Sig0Key, Sig0Error};
+
+#[cfg(feature = "with_zero_copy")]
+mod view;
+#[cfg(feature = "with_zero_copy")]
+pub use self::view::{ResponseView, QueryView, RecordView, NameView, LabelsIter};
 
 pub mod record;
+
+#[cfg(any(feature = "with_arbitrary", test))]
+pub mod arbitrary;
+
+#[cfg(any(feature = "std", test))]
+pub mod zonefile;
+
+pub mod axfr;
+pub use self::axfr::{AxfrParser, TransferError};
+
+pub mod update;
+pub use self::update::{Update, Prerequisite, Operation};
+
+pub mod dso;
+pub use self::dso::{DsoMessage, DsoTlv, DsoError};
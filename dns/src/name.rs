@@ -0,0 +1,232 @@
+//! Comparing and normalising domain names.
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+
+use crate::strings::Labels;
+
+#[cfg(not(feature = "std"))]
+use alloc::{borrow::ToOwned, string::String};
+
+
+/// A **name** is a domain name that compares the way the DNS does: without
+/// caring about the case of ASCII letters, or about whether it was written
+/// with a trailing dot. ‘`Lookup.DOG`’ and ‘`lookup.dog.`’ are the same
+/// name, and they hash the same way, so names can be used as map keys.
+///
+/// Names are ordered in canonical DNS order, from the rightmost label
+/// leftwards.
+///
+/// # References
+///
+/// - [RFC 4343](https://tools.ietf.org/html/rfc4343) — Domain Name System
+///   (DNS) Case Insensitivity Clarification (January 2006)
+#[derive(Debug, Clone)]
+pub struct Name {
+    labels: Labels,
+}
+
+impl Name {
+
+    /// Returns the name of the root of the DNS, which has no labels.
+    pub fn root() -> Self {
+        Self { labels: Labels::root() }
+    }
+
+    /// Parses a name from the text form used on the command-line and in
+    /// zone files, with or without the trailing dot. If any label is too
+    /// long or has an invalid escape, returns that label as an error.
+    pub fn new(input: &str) -> Result<Self, &str> {
+        Labels::encode(input).map(Self::from)
+    }
+
+    /// Returns an iterator over the bytes of each label, from the leftmost
+    /// one to the rightmost, in the form they take on the wire.
+    pub fn labels(&self) -> impl DoubleEndedIterator<Item = &[u8]> + ExactSizeIterator {
+        self.labels.segments()
+    }
+
+    /// Returns the number of labels, which is zero for the root.
+    pub fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    /// Whether this is the root name.
+    pub fn is_root(&self) -> bool {
+        self.labels.len() == 0
+    }
+
+    /// Whether this name is the same as the given one, or is anywhere
+    /// beneath it. Every name is a subdomain of the root.
+    pub fn is_subdomain_of(&self, ancestor: &Self) -> bool {
+        self.labels.is_within(&ancestor.labels)
+    }
+
+    /// Returns the name with one label taken off the left, or `None` if
+    /// this is the root.
+    pub fn parent(&self) -> Option<Self> {
+        if self.is_root() {
+            None
+        }
+        else {
+            Some(Self::from(self.labels.ancestor(self.len() - 1)))
+        }
+    }
+
+    /// Returns the name in its canonical form, with every uppercase ASCII
+    /// letter made lowercase.
+    pub fn to_canonical(&self) -> Self {
+        Self::from(self.labels.to_canonical())
+    }
+
+    /// Returns the labels the name is made of, as they were written.
+    pub fn as_labels(&self) -> &Labels {
+        &self.labels
+    }
+
+    /// Turns the name back into the labels it is made of.
+    pub fn into_labels(self) -> Labels {
+        self.labels
+    }
+}
+
+impl From<Labels> for Name {
+    fn from(labels: Labels) -> Self {
+        Self { labels }
+    }
+}
+
+impl FromStr for Name {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        Self::new(input).map_err(str::to_owned)
+    }
+}
+
+impl PartialEq for Name {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Name {}
+
+impl PartialOrd for Name {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Name {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.labels.canonical_cmp(&other.labels)
+    }
+}
+
+impl Hash for Name {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for label in self.labels() {
+            label.len().hash(state);
+            for byte in label {
+                byte.to_ascii_lowercase().hash(state);
+            }
+        }
+    }
+}
+
+/// Names are always written with the trailing dot, so the root is written
+/// as a lone ‘`.`’.
+impl fmt::Display for Name {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_root() {
+            write!(f, ".")
+        }
+        else {
+            write!(f, "{}", self.labels)
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashSet;
+    use pretty_assertions::assert_eq;
+
+    fn name(input: &str) -> Name {
+        Name::new(input).unwrap()
+    }
+
+    #[test]
+    fn case_insensitive() {
+        assert_eq!(name("Lookup.DOG"), name("lookup.dog"));
+        assert_ne!(name("lookup.dog"), name("lookup.cat"));
+    }
+
+    #[test]
+    fn trailing_dot() {
+        assert_eq!(name("lookup.dog."), name("lookup.dog"));
+        assert_eq!(name("lookup.dog").to_string(), "lookup.dog.");
+    }
+
+    #[test]
+    fn root() {
+        assert_eq!(name("."), Name::root());
+        assert_eq!(name(""), Name::root());
+        assert_eq!(Name::root().to_string(), ".");
+        assert_eq!(Name::root().parent(), None);
+    }
+
+    #[test]
+    fn hashes_ignore_case() {
+        let mut set = HashSet::new();
+        set.insert(name("WWW.lookup.dog"));
+        assert!(set.contains(&name("www.LOOKUP.dog.")));
+    }
+
+    #[test]
+    fn labels() {
+        let www = name("www.lookup.dog");
+        assert_eq!(www.labels().collect::<Vec<_>>(), vec![ &b"www"[..], b"lookup", b"dog" ]);
+        assert_eq!(www.labels().len(), 3);
+    }
+
+    #[test]
+    fn subdomains() {
+        assert!(name("www.Lookup.dog").is_subdomain_of(&name("lookup.DOG")));
+        assert!(name("lookup.dog").is_subdomain_of(&name("lookup.dog")));
+        assert!(name("lookup.dog").is_subdomain_of(&Name::root()));
+        assert!(! name("lookup.dog").is_subdomain_of(&name("www.lookup.dog")));
+        assert!(! name("xlookup.dog").is_subdomain_of(&name("lookup.dog")));
+    }
+
+    #[test]
+    fn parent() {
+        assert_eq!(name("www.lookup.dog").parent(), Some(name("lookup.dog")));
+        assert_eq!(name("dog").parent(), Some(Name::root()));
+    }
+
+    #[test]
+    fn canonical() {
+        let canonical = name("WWW.Lookup.dog").to_canonical();
+        assert_eq!(canonical.to_string(), "www.lookup.dog.");
+        assert_eq!(canonical.as_labels(), &Labels::encode("www.lookup.dog").unwrap());
+    }
+
+    #[test]
+    fn canonical_order() {
+        let mut names = vec![ name("z.lookup.dog"), name("lookup.dog"), name("A.lookup.dog"), name("a.dog") ];
+        names.sort();
+        assert_eq!(names, vec![ name("a.dog"), name("lookup.dog"), name("A.lookup.dog"), name("z.lookup.dog") ]);
+    }
+
+    #[test]
+    fn parse_errors() {
+        let long = "a".repeat(300);
+        assert_eq!(long.parse::<Name>().unwrap_err(), long);
+    }
+}
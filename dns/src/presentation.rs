@@ -0,0 +1,184 @@
+//! The presentation format of records, which is how their data is written
+//! in zone files and in the output of tools such as dig. Each record type
+//! has its own `to_presentation` method; this module holds the pieces of
+//! the format that several types share.
+//!
+//! # References
+//!
+//! - [RFC 1035 §5.1](https://tools.ietf.org/html/rfc1035#section-5.1) —
+//!   Domain Names - Implementation and Specification (November 1987)
+//! - [RFC 3597 §5](https://tools.ietf.org/html/rfc3597#section-5) —
+//!   Handling of Unknown DNS Resource Record (RR) Types (September 2003)
+//! - [RFC 4034 §3.2](https://tools.ietf.org/html/rfc4034#section-3.2) —
+//!   Resource Records for the DNS Security Extensions (March 2005)
+
+use crate::strings::Labels;
+
+#[cfg(not(feature = "std"))]
+use alloc::string::{String, ToString};
+
+
+/// Writes a domain name with a dot at the end, which is just a dot for the
+/// root domain.
+pub(crate) fn name(labels: &Labels) -> String {
+    if labels.len() == 0 {
+        String::from(".")
+    }
+    else {
+        labels.to_string()
+    }
+}
+
+/// Writes a character-string in double quotes, escaping quotes and
+/// backslashes with a backslash, and bytes that cannot be printed as a
+/// backslash and their three-digit decimal value.
+pub(crate) fn quoted(bytes: &[u8]) -> String {
+    let mut output = String::from("\"");
+
+    for byte in bytes.iter().copied() {
+        match byte {
+            b'"' | b'\\'      => { output.push('\\'); output.push(char::from(byte)); }
+            0x20 ..= 0x7e     => output.push(char::from(byte)),
+            _                 => output.push_str(&format!("\\{:03}", byte)),
+        }
+    }
+
+    output.push('"');
+    output
+}
+
+/// Writes bytes in uppercase hexadecimal, as dig does.
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02X}", byte)).collect()
+}
+
+/// Writes record data in the generic format from RFC 3597: a `\#` token,
+/// the length of the data, and the data itself in hexadecimal.
+pub fn generic(bytes: &[u8]) -> String {
+    if bytes.is_empty() {
+        String::from("\\# 0")
+    }
+    else {
+        format!("\\# {} {}", bytes.len(), hex(bytes))
+    }
+}
+
+/// Formats a DNSSEC signature time, which is a number of seconds since the
+/// Unix epoch, in the `YYYYMMDDHHmmSS` format that zone files use.
+pub fn signature_timestamp(time: u32) -> String {
+    let days = i64::from(time / 86400);
+    let seconds = time % 86400;
+
+    // Converts days since the epoch to a civil date, with years starting in
+    // March so that leap days fall at the end.
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let day_of_era = z - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!("{:04}{:02}{:02}{:02}{:02}{:02}",
+        year, month, day,
+        seconds / 3600, (seconds % 3600) / 60, seconds % 60)
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    use crate::record::*;
+    use crate::zonefile;
+
+    /// Writes a record in presentation format, reads it back with the zone
+    /// file parser, and checks that the same record comes out.
+    fn round_trip(record: Record) {
+        let text = format!("example.com. 300 IN {} {}\n", record.record_type().mnemonic(), record.to_presentation());
+        let zone = zonefile::parse(&text).unwrap_or_else(|e| panic!("{}: {}", text, e));
+
+        assert_eq!(zone.skipped, vec![], "{}", text);
+        assert_eq!(zone.records.into_iter().map(|r| r.record).collect::<Vec<_>>(), vec![ record ], "{}", text);
+    }
+
+    #[test]
+    fn quoted_strings() {
+        assert_eq!(quoted(b"v=spf1 -all"), "\"v=spf1 -all\"");
+        assert_eq!(quoted(b"say \"hi\" \\o/"), "\"say \\\"hi\\\" \\\\o/\"");
+        assert_eq!(quoted(b"tab\there\xff"), "\"tab\\009here\\255\"");
+        assert_eq!(quoted(b""), "\"\"");
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(name(&Labels::encode("dns.lookup.dog").unwrap()), "dns.lookup.dog.");
+        assert_eq!(name(&Labels::root()), ".");
+    }
+
+    #[test]
+    fn generic_data() {
+        assert_eq!(generic(&[ 0x0a, 0x00, 0x00, 0xff ]), "\\# 4 0A0000FF");
+        assert_eq!(generic(&[]), "\\# 0");
+    }
+
+    #[test]
+    fn signature_timestamps() {
+        assert_eq!(signature_timestamp(0), "19700101000000");
+        assert_eq!(signature_timestamp(951_782_400), "20000229000000");
+        assert_eq!(signature_timestamp(0x6a00_0000), "20260510034816");
+    }
+
+    #[test]
+    fn round_trip_addresses() {
+        round_trip(Record::A(A { address: "192.0.2.1".parse().unwrap() }));
+        round_trip(Record::AAAA(AAAA { address: "2001:db8::1".parse().unwrap() }));
+    }
+
+    #[test]
+    fn round_trip_names() {
+        let name = Labels::encode("ns1.example.net").unwrap();
+        round_trip(Record::CNAME(CNAME { domain: name.clone() }));
+        round_trip(Record::NS(NS { nameserver: name.clone() }));
+        round_trip(Record::PTR(PTR { cname: name.clone() }));
+        round_trip(Record::MX(MX { preference: 10, exchange: name.clone() }));
+        round_trip(Record::SRV(SRV { priority: 1, weight: 2, port: 5060, target: name }));
+    }
+
+    #[test]
+    fn round_trip_soa() {
+        round_trip(Record::SOA(SOA {
+            mname: Labels::encode("ns1.example.com").unwrap(),
+            rname: Labels::encode("hostmaster.example.com").unwrap(),
+            serial: 2021_01_01_00,
+            refresh_interval: 7200,
+            retry_interval: 3600,
+            expire_limit: 1_209_600,
+            minimum_ttl: 300,
+        }));
+    }
+
+    #[test]
+    fn round_trip_strings() {
+        round_trip(Record::TXT(TXT { messages: vec![ Box::new(*b"v=spf1 -all"), Box::new(*b"say \"hi\"; \\o/") ] }));
+        round_trip(Record::HINFO(HINFO { cpu: Box::new(*b"PDP-11"), os: Box::new(*b"UNIX") }));
+        round_trip(Record::CAA(CAA { critical: true, tag: Box::new(*b"issue"), value: Box::new(*b"ca.example.net; account=230123") }));
+        round_trip(Record::CAA(CAA { critical: false, tag: Box::new(*b"iodef"), value: Box::new(*b"mailto:security@example.com") }));
+    }
+
+    #[test]
+    fn round_trip_binary_data() {
+        round_trip(Record::DS(DS { key_tag: 20326, algorithm: 8, digest_type: 2, digest: vec![ 0xe0, 0x6d, 0x44, 0xb8 ] }));
+        round_trip(Record::DNSKEY(DNSKEY { flags: 257, protocol: 3, algorithm: 8, public_key: vec![ 0x03, 0x01, 0x00, 0x01, 0xac ] }));
+        round_trip(Record::SSHFP(SSHFP { algorithm: 4, fingerprint_type: 2, fingerprint: vec![ 0x12, 0x34, 0xab, 0xcd ] }));
+        round_trip(Record::TLSA(TLSA { certificate_usage: 3, selector: 1, matching_type: 1, certificate_data: vec![ 0xde, 0xad, 0xbe, 0xef ] }));
+    }
+
+    #[test]
+    fn round_trip_unknown_type() {
+        round_trip(Record::Other { type_number: UnknownQtype::UnheardOf(65280), bytes: vec![ 0x0a, 0x00, 0x00, 0x01 ] });
+    }
+}
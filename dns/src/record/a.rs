@@ -12,6 +12,7 @@ use crate::wire::*;
 /// - [RFC 1035 §3.4.1](https://tools.ietf.org/html/rfc1035) — Domain Names,
 ///   Implementation and Specification (November 1987)
 #[derive(PartialEq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct A {
 
     /// The IPv4 address contained in the packet.
@@ -38,6 +39,20 @@ impl Wire for A {
 
         Ok(Self { address })
     }
+
+    fn write(&self, bytes: &mut Vec<u8>) -> io::Result<()> {
+        bytes.extend_from_slice(&self.address.octets());
+        Ok(())
+    }
+}
+
+impl A {
+
+    /// Returns the record’s data in presentation format, which is the address
+    /// itself.
+    pub fn to_presentation(&self) -> String {
+        self.address.to_string()
+    }
 }
 
 
@@ -92,4 +107,16 @@ mod test {
         assert_eq!(A::read(4, &mut Cursor::new(buf)),
                    Err(WireError::IO));
     }
+
+    #[test]
+    fn round_trip() {
+        let buf = &[
+            0x7F, 0x00, 0x00, 0x01,  // IPv4 address
+        ];
+
+        let record = A::read(buf.len() as _, &mut Cursor::new(buf)).unwrap();
+        let mut bytes = Vec::new();
+        record.write(&mut bytes).unwrap();
+        assert_eq!(bytes, buf);
+    }
 }
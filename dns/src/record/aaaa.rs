@@ -12,6 +12,7 @@ use crate::wire::*;
 /// - [RFC 3596](https://tools.ietf.org/html/rfc3596) — DNS Extensions to
 ///   Support IP Version 6 (October 2003)
 #[derive(PartialEq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AAAA {
 
     /// The IPv6 address contained in the packet.
@@ -38,6 +39,20 @@ impl Wire for AAAA {
 
         Ok(Self { address })
     }
+
+    fn write(&self, bytes: &mut Vec<u8>) -> io::Result<()> {
+        bytes.extend_from_slice(&self.address.octets());
+        Ok(())
+    }
+}
+
+impl AAAA {
+
+    /// Returns the record’s data in presentation format, which is the address
+    /// itself.
+    pub fn to_presentation(&self) -> String {
+        self.address.to_string()
+    }
 }
 
 
@@ -94,4 +109,17 @@ mod test {
         assert_eq!(AAAA::read(16, &mut Cursor::new(buf)),
                    Err(WireError::IO));
     }
+
+    #[test]
+    fn round_trip() {
+        let buf = &[
+            0x20, 0x01, 0x0d, 0xb8, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01,  // IPv6 address
+        ];
+
+        let record = AAAA::read(buf.len() as _, &mut Cursor::new(buf)).unwrap();
+        let mut bytes = Vec::new();
+        record.write(&mut bytes).unwrap();
+        assert_eq!(bytes, buf);
+    }
 }
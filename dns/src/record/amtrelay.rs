@@ -0,0 +1,354 @@
+use std::fmt;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use log::*;
+
+use crate::presentation::{hex, name};
+use crate::strings::{Labels, ReadLabels, WriteLabels};
+use crate::wire::*;
+
+
+/// An **AMTRELAY** record, which advertises the address of an Automatic
+/// Multicast Tunneling relay that can forward multicast traffic for a
+/// source network.
+///
+/// # References
+///
+/// - [RFC 8777](https://tools.ietf.org/html/rfc8777) — DNS Reverse IP
+///   Automatic Multicast Tunneling (AMT) Discovery (April 2020)
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AMTRELAY {
+
+    /// The preference for this relay, with lower values being tried first.
+    pub precedence: u8,
+
+    /// Whether the D-bit is set, which signals that the receiver should
+    /// attempt to use AMT discovery before connecting to the relay.
+    pub discovery_optional: bool,
+
+    /// The address of the relay itself.
+    pub relay: Relay,
+}
+
+/// The address of an AMT relay, the encoding of which depends on the relay
+/// type field.
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Relay {
+
+    /// Relay type 0: there is no relay.
+    Empty,
+
+    /// Relay type 1: the relay is at an IPv4 address.
+    Ipv4(Ipv4Addr),
+
+    /// Relay type 2: the relay is at an IPv6 address.
+    Ipv6(Ipv6Addr),
+
+    /// Relay type 3: the relay is at a domain name.
+    Domain(Labels),
+
+    /// A relay type that we don’t know how to interpret.
+    Other {
+
+        /// The number representing the relay type.
+        relay_type: u8,
+
+        /// The undecodable bytes of the relay field.
+        bytes: Vec<u8>,
+    },
+}
+
+impl Wire for AMTRELAY {
+    const NAME: &'static str = "AMTRELAY";
+    const RR_TYPE: u16 = 260;
+
+    #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+    fn read(stated_length: u16, c: &mut Cursor<&[u8]>) -> Result<Self, WireError> {
+        let precedence = c.read_u8()?;
+        trace!("Parsed precedence -> {:?}", precedence);
+
+        let type_byte = c.read_u8()?;
+        let discovery_optional = type_byte & 0b_1000_0000 != 0;
+        let relay_type = type_byte & 0b_0111_1111;
+        trace!("Parsed D-bit -> {:?}", discovery_optional);
+        trace!("Parsed relay type -> {:?}", relay_type);
+
+        if stated_length < 2 {
+            let mandated_length = MandatedLength::AtLeast(2);
+            return Err(WireError::WrongRecordLength { stated_length, mandated_length });
+        }
+
+        let relay = match relay_type {
+            0 => {
+                if stated_length != 2 {
+                    let mandated_length = MandatedLength::Exactly(2);
+                    return Err(WireError::WrongRecordLength { stated_length, mandated_length });
+                }
+
+                Relay::Empty
+            }
+            1 => {
+                if stated_length != 2 + 4 {
+                    let mandated_length = MandatedLength::Exactly(2 + 4);
+                    return Err(WireError::WrongRecordLength { stated_length, mandated_length });
+                }
+
+                let mut buf = [0_u8; 4];
+                c.read_exact(&mut buf)?;
+                Relay::Ipv4(Ipv4Addr::from(buf))
+            }
+            2 => {
+                if stated_length != 2 + 16 {
+                    let mandated_length = MandatedLength::Exactly(2 + 16);
+                    return Err(WireError::WrongRecordLength { stated_length, mandated_length });
+                }
+
+                let mut buf = [0_u8; 16];
+                c.read_exact(&mut buf)?;
+                Relay::Ipv6(Ipv6Addr::from(buf))
+            }
+            3 => {
+                let (domain, domain_length) = c.read_labels()?;
+
+                let length_after_labels = 2 + domain_length;
+                if stated_length != length_after_labels {
+                    return Err(WireError::WrongLabelLength { stated_length, length_after_labels });
+                }
+
+                Relay::Domain(domain)
+            }
+            _ => {
+                let mut bytes = vec![0_u8; usize::from(stated_length - 2)];
+                c.read_exact(&mut bytes)?;
+                Relay::Other { relay_type, bytes }
+            }
+        };
+
+        trace!("Parsed relay -> {:?}", relay);
+        Ok(Self { precedence, discovery_optional, relay })
+    }
+
+    fn write(&self, bytes: &mut Vec<u8>) -> io::Result<()> {
+        bytes.write_u8(self.precedence)?;
+
+        let discovery_bit = if self.discovery_optional { 0b_1000_0000 } else { 0 };
+        bytes.write_u8(discovery_bit | (self.relay.relay_type() & 0b_0111_1111))?;
+
+        match &self.relay {
+            Relay::Empty                      => {}
+            Relay::Ipv4(address)              => bytes.extend_from_slice(&address.octets()),
+            Relay::Ipv6(address)              => bytes.extend_from_slice(&address.octets()),
+            Relay::Domain(domain)             => bytes.write_labels(domain)?,
+            Relay::Other { bytes: data, .. }  => bytes.extend_from_slice(data),
+        }
+
+        Ok(())
+    }
+}
+
+impl AMTRELAY {
+
+    /// Returns the record’s data in presentation format, with the relay
+    /// written as `.` if there is none, and in hexadecimal if its type is
+    /// unknown.
+    pub fn to_presentation(&self) -> String {
+        let relay = match &self.relay {
+            Relay::Domain(domain)       => name(domain),
+            Relay::Other { bytes, .. }  => hex(bytes),
+            relay                       => relay.to_string(),
+        };
+
+        format!("{} {} {} {}",
+            self.precedence,
+            u8::from(self.discovery_optional),
+            self.relay.relay_type(),
+            relay,
+        )
+    }
+}
+
+impl Relay {
+
+    /// Returns the number of the relay type, as it would appear in the
+    /// record’s type field.
+    pub fn relay_type(&self) -> u8 {
+        match self {
+            Self::Empty                      => 0,
+            Self::Ipv4(_)                    => 1,
+            Self::Ipv6(_)                    => 2,
+            Self::Domain(_)                  => 3,
+            Self::Other { relay_type, .. }   => *relay_type,
+        }
+    }
+}
+
+impl fmt::Display for Relay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty               => write!(f, "."),
+            Self::Ipv4(address)       => write!(f, "{}", address),
+            Self::Ipv6(address)       => write!(f, "{}", address),
+            Self::Domain(domain)      => write!(f, "{}", domain),
+            Self::Other { bytes, .. } => write!(f, "{:?}", bytes),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parses_empty() {
+        let buf = &[
+            0x0A,  // precedence
+            0x80,  // D-bit and relay type
+        ];
+
+        assert_eq!(AMTRELAY::read(buf.len() as _, &mut Cursor::new(buf)).unwrap(),
+                   AMTRELAY {
+                       precedence: 10,
+                       discovery_optional: true,
+                       relay: Relay::Empty,
+                   });
+    }
+
+    #[test]
+    fn parses_ipv4() {
+        let buf = &[
+            0x0A,  // precedence
+            0x01,  // D-bit and relay type
+            0xCB, 0x00, 0x71, 0x0F,  // IPv4 address
+        ];
+
+        assert_eq!(AMTRELAY::read(buf.len() as _, &mut Cursor::new(buf)).unwrap(),
+                   AMTRELAY {
+                       precedence: 10,
+                       discovery_optional: false,
+                       relay: Relay::Ipv4(Ipv4Addr::new(203, 0, 113, 15)),
+                   });
+    }
+
+    #[test]
+    fn parses_ipv6() {
+        let buf = &[
+            0x0A,  // precedence
+            0x82,  // D-bit and relay type
+            0x20, 0x01, 0x0d, 0xb8, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x0F,  // IPv6 address
+        ];
+
+        assert_eq!(AMTRELAY::read(buf.len() as _, &mut Cursor::new(buf)).unwrap(),
+                   AMTRELAY {
+                       precedence: 10,
+                       discovery_optional: true,
+                       relay: Relay::Ipv6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 15)),
+                   });
+    }
+
+    #[test]
+    fn parses_domain() {
+        let buf = &[
+            0x80,  // precedence
+            0x03,  // D-bit and relay type
+            0x03, 0x64, 0x6f, 0x67, 0x00,  // domain
+        ];
+
+        assert_eq!(AMTRELAY::read(buf.len() as _, &mut Cursor::new(buf)).unwrap(),
+                   AMTRELAY {
+                       precedence: 128,
+                       discovery_optional: false,
+                       relay: Relay::Domain(Labels::encode("dog").unwrap()),
+                   });
+    }
+
+    #[test]
+    fn parses_other() {
+        let buf = &[
+            0x0A,  // precedence
+            0x7F,  // D-bit and relay type
+            0x12, 0x34,  // relay
+        ];
+
+        assert_eq!(AMTRELAY::read(buf.len() as _, &mut Cursor::new(buf)).unwrap(),
+                   AMTRELAY {
+                       precedence: 10,
+                       discovery_optional: false,
+                       relay: Relay::Other { relay_type: 127, bytes: vec![ 0x12, 0x34 ] },
+                   });
+    }
+
+    #[test]
+    fn ipv4_too_short() {
+        let buf = &[
+            0x0A,  // precedence
+            0x01,  // D-bit and relay type
+            0xCB, 0x00,  // half an IPv4 address
+        ];
+
+        assert_eq!(AMTRELAY::read(buf.len() as _, &mut Cursor::new(buf)),
+                   Err(WireError::WrongRecordLength { stated_length: 4, mandated_length: MandatedLength::Exactly(6) }));
+    }
+
+    #[test]
+    fn incorrect_domain_length() {
+        let buf = &[
+            0x0A,  // precedence
+            0x03,  // D-bit and relay type
+            0x03, 0x64, 0x6f, 0x67, 0x00,  // domain
+        ];
+
+        assert_eq!(AMTRELAY::read(6, &mut Cursor::new(buf)),
+                   Err(WireError::WrongLabelLength { stated_length: 6, length_after_labels: 7 }));
+    }
+
+    #[test]
+    fn record_empty() {
+        assert_eq!(AMTRELAY::read(0, &mut Cursor::new(&[])),
+                   Err(WireError::IO));
+    }
+
+    #[test]
+    fn buffer_ends_abruptly() {
+        let buf = &[
+            0x0A,  // precedence
+            0x01,  // D-bit and relay type
+            0xCB,  // a quarter of an IPv4 address
+        ];
+
+        assert_eq!(AMTRELAY::read(6, &mut Cursor::new(buf)),
+                   Err(WireError::IO));
+    }
+
+    #[test]
+    fn round_trip_ipv4() {
+        let buf = &[
+            0x0A,  // precedence
+            0x01,  // D-bit and relay type
+            0xCB, 0x00, 0x71, 0x0F,  // IPv4 address
+        ];
+
+        let record = AMTRELAY::read(buf.len() as _, &mut Cursor::new(buf)).unwrap();
+        let mut bytes = Vec::new();
+        record.write(&mut bytes).unwrap();
+        assert_eq!(bytes, buf);
+    }
+
+    #[test]
+    fn round_trip_domain() {
+        let buf = &[
+            0x80,  // precedence
+            0x83,  // D-bit and relay type
+            0x03, 0x64, 0x6f, 0x67, 0x00,  // domain
+        ];
+
+        let record = AMTRELAY::read(buf.len() as _, &mut Cursor::new(buf)).unwrap();
+        let mut bytes = Vec::new();
+        record.write(&mut bytes).unwrap();
+        assert_eq!(bytes, buf);
+    }
+}
@@ -1,5 +1,6 @@
 use log::*;
 
+use crate::presentation::quoted;
 use crate::wire::*;
 
 
@@ -11,7 +12,10 @@ use crate::wire::*;
 ///
 /// - [RFC 6844](https://tools.ietf.org/html/rfc6844) — DNS Certification
 ///   Authority Authorization Resource Record (January 2013)
-#[derive(PartialEq, Debug)]
+/// - [RFC 8659](https://tools.ietf.org/html/rfc8659) — DNS Certification
+///   Authority Authorization (CAA) Resource Record (November 2019)
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CAA {
 
     /// Whether this record is marked as “critical” or not.
@@ -55,7 +59,140 @@ impl Wire for CAA {
         c.read_exact(&mut value)?;
         trace!("Parsed value -> {:?}", String::from_utf8_lossy(&value));
 
-        Ok(Self { critical, tag, value })
+        let caa = Self { critical, tag, value };
+        if caa.critical && ! caa.has_known_tag() {
+            warn!("CAA record has unknown critical tag {:?}", String::from_utf8_lossy(&caa.tag));
+        }
+
+        Ok(caa)
+    }
+
+    fn write(&self, bytes: &mut Vec<u8>) -> io::Result<()> {
+        bytes.write_u8(if self.critical { 0b_1000_0000 } else { 0 })?;
+        write_character_string(bytes, &self.tag)?;
+        bytes.extend_from_slice(&self.value);
+        Ok(())
+    }
+}
+
+/// The tags that have a defined meaning. Tags are compared without regard
+/// to case.
+const KNOWN_TAGS: &[&str] = &[
+    "issue", "issuewild", "issuemail", "iodef", "contactemail", "contactphone",
+];
+
+impl CAA {
+
+    /// Whether this record’s tag is one that we know the meaning of. A
+    /// record with an unknown tag that is marked as critical means that no
+    /// certificate authority should issue certificates for the domain.
+    pub fn has_known_tag(&self) -> bool {
+        KNOWN_TAGS.iter().any(|known| known.as_bytes().eq_ignore_ascii_case(&self.tag))
+    }
+
+    /// Interprets the value of this record according to its tag. Returns
+    /// `None` if the tag is unknown, or if the value is not valid for the
+    /// tag.
+    pub fn property(&self) -> Option<CaaProperty> {
+        let value = std::str::from_utf8(&self.value).ok()?;
+        let tag = std::str::from_utf8(&self.tag).ok()?.to_ascii_lowercase();
+
+        match &tag[..] {
+            "issue" | "issuewild" | "issuemail" => {
+                IssueValue::parse(value).map(CaaProperty::Issue)
+            }
+            "iodef" => {
+                Some(CaaProperty::Iodef(value.into()))
+            }
+            "contactemail" => {
+                Some(CaaProperty::ContactEmail(value.into()))
+            }
+            "contactphone" => {
+                Some(CaaProperty::ContactPhone(value.into()))
+            }
+            _ => {
+                None
+            }
+        }
+    }
+
+    /// Returns the record’s data in presentation format, with the flags as a
+    /// number and the value as a quoted string.
+    pub fn to_presentation(&self) -> String {
+        let flags = if self.critical { 0b_1000_0000 } else { 0 };
+        format!("{} {} {}", flags, String::from_utf8_lossy(&self.tag), quoted(&self.value))
+    }
+}
+
+/// The meaning of a CAA record, depending on its tag.
+#[derive(PartialEq, Debug, Clone)]
+pub enum CaaProperty {
+
+    /// An `issue`, `issuewild`, or `issuemail` property, authorising a
+    /// certificate authority to issue certificates.
+    Issue(IssueValue),
+
+    /// An `iodef` property, containing a URL to report invalid certificate
+    /// requests to.
+    Iodef(String),
+
+    /// A `contactemail` property, containing an e-mail address for the
+    /// domain’s owner.
+    ContactEmail(String),
+
+    /// A `contactphone` property, containing a phone number for the
+    /// domain’s owner.
+    ContactPhone(String),
+}
+
+/// The value of an issuing property, which is an optional issuer domain
+/// name followed by a list of parameters.
+#[derive(PartialEq, Debug, Clone)]
+pub struct IssueValue {
+
+    /// The domain name of the certificate authority that may issue
+    /// certificates, or `None` if no authority may.
+    pub issuer: Option<String>,
+
+    /// The parameters for the certificate authority, such as `accounturi`
+    /// or `validationmethods`, as pairs of tags and values.
+    pub parameters: Vec<(String, String)>,
+}
+
+impl IssueValue {
+
+    /// Parses the value of an issuing property, which takes the form
+    /// `issuer; tag=value; tag=value`. Returns `None` if a parameter is
+    /// missing its `=` sign or its tag.
+    fn parse(input: &str) -> Option<Self> {
+        let mut parts = input.split(';');
+
+        let issuer = parts.next().map(str::trim).filter(|i| ! i.is_empty()).map(String::from);
+
+        let mut parameters = Vec::new();
+        for part in parts {
+            let part = part.trim();
+            if part.is_empty() {
+                continue;
+            }
+
+            let (tag, value) = part.split_once('=')?;
+            let tag = tag.trim();
+            if tag.is_empty() {
+                return None;
+            }
+
+            parameters.push((tag.into(), value.trim().into()));
+        }
+
+        Some(Self { issuer, parameters })
+    }
+
+    /// Returns the value of the parameter with the given tag, if present.
+    pub fn parameter(&self, tag: &str) -> Option<&str> {
+        self.parameters.iter()
+            .find(|(t, _)| t.eq_ignore_ascii_case(tag))
+            .map(|(_, v)| &v[..])
     }
 }
 
@@ -116,6 +253,76 @@ mod test {
                    });
     }
 
+    #[test]
+    fn issue_with_parameters() {
+        let caa = CAA {
+            critical: false,
+            tag: Box::new(*b"issue"),
+            value: Box::new(*b"ca.example; accounturi=https://ca.example/acct/1; validationmethods=dns-01"),
+        };
+
+        let property = caa.property().unwrap();
+        assert_eq!(property,
+                   CaaProperty::Issue(IssueValue {
+                       issuer: Some("ca.example".into()),
+                       parameters: vec![
+                           ("accounturi".into(), "https://ca.example/acct/1".into()),
+                           ("validationmethods".into(), "dns-01".into()),
+                       ],
+                   }));
+
+        if let CaaProperty::Issue(issue) = property {
+            assert_eq!(issue.parameter("ValidationMethods"), Some("dns-01"));
+        }
+    }
+
+    #[test]
+    fn issue_nobody() {
+        let caa = CAA {
+            critical: false,
+            tag: Box::new(*b"issuewild"),
+            value: Box::new(*b";"),
+        };
+
+        assert_eq!(caa.property(),
+                   Some(CaaProperty::Issue(IssueValue { issuer: None, parameters: vec![] })));
+    }
+
+    #[test]
+    fn issue_malformed_parameter() {
+        let caa = CAA {
+            critical: false,
+            tag: Box::new(*b"issue"),
+            value: Box::new(*b"ca.example; accounturi"),
+        };
+
+        assert_eq!(caa.property(), None);
+    }
+
+    #[test]
+    fn contact_email() {
+        let caa = CAA {
+            critical: false,
+            tag: Box::new(*b"ContactEmail"),
+            value: Box::new(*b"hostmaster@example.com"),
+        };
+
+        assert_eq!(caa.property(),
+                   Some(CaaProperty::ContactEmail("hostmaster@example.com".into())));
+    }
+
+    #[test]
+    fn unknown_tag() {
+        let caa = CAA {
+            critical: true,
+            tag: Box::new(*b"tbs"),
+            value: Box::new(*b"unknown"),
+        };
+
+        assert!(! caa.has_known_tag());
+        assert_eq!(caa.property(), None);
+    }
+
     #[test]
     fn record_empty() {
         assert_eq!(CAA::read(0, &mut Cursor::new(&[])),
@@ -131,4 +338,19 @@ mod test {
         assert_eq!(CAA::read(23, &mut Cursor::new(buf)),
                    Err(WireError::IO));
     }
+
+    #[test]
+    fn round_trip() {
+        let buf = &[
+            0x80,  // flags (critical bit set)
+            0x09,  // tag length
+            0x69, 0x73, 0x73, 0x75, 0x65, 0x77, 0x69, 0x6c, 0x64,  // tag
+            0x65, 0x6e, 0x74, 0x72, 0x75, 0x73, 0x74, 0x2e, 0x6e, 0x65, 0x74,  // value
+        ];
+
+        let record = CAA::read(buf.len() as _, &mut Cursor::new(buf)).unwrap();
+        let mut bytes = Vec::new();
+        record.write(&mut bytes).unwrap();
+        assert_eq!(bytes, buf);
+    }
 }
@@ -1,6 +1,7 @@
 use log::*;
 
-use crate::strings::{Labels, ReadLabels};
+use crate::presentation::name;
+use crate::strings::{Labels, ReadLabels, WriteLabels};
 use crate::wire::*;
 
 
@@ -10,7 +11,8 @@ use crate::wire::*;
 ///
 /// - [RFC 1035 §3.3.1](https://tools.ietf.org/html/rfc1035) — Domain Names,
 ///   Implementation and Specification (November 1987)
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CNAME {
 
     /// The domain name that this CNAME record is responding with.
@@ -35,6 +37,19 @@ impl Wire for CNAME {
             Err(WireError::WrongLabelLength { stated_length, length_after_labels: domain_length })
         }
     }
+
+    fn write(&self, bytes: &mut Vec<u8>) -> io::Result<()> {
+        bytes.write_labels(&self.domain)
+    }
+}
+
+impl CNAME {
+
+    /// Returns the record’s data in presentation format, which is the domain
+    /// name.
+    pub fn to_presentation(&self) -> String {
+        name(&self.domain)
+    }
 }
 
 
@@ -82,5 +97,18 @@ mod test {
         assert_eq!(CNAME::read(23, &mut Cursor::new(buf)),
                    Err(WireError::IO));
     }
+
+    #[test]
+    fn round_trip() {
+        let buf = &[
+            0x05, 0x62, 0x73, 0x61, 0x67, 0x6f, 0x02, 0x6d, 0x65,  // domain
+            0x00,  // domain terminator
+        ];
+
+        let record = CNAME::read(buf.len() as _, &mut Cursor::new(buf)).unwrap();
+        let mut bytes = Vec::new();
+        record.write(&mut bytes).unwrap();
+        assert_eq!(bytes, buf);
+    }
 }
 
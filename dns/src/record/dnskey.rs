@@ -0,0 +1,218 @@
+use log::*;
+
+use crate::wire::*;
+
+
+/// A **DNSKEY** record, which holds one of the public keys that a zone’s
+/// records are signed with, so their RRSIG records can be verified.
+///
+/// # References
+///
+/// - [RFC 4034 §2](https://tools.ietf.org/html/rfc4034#section-2) — Resource
+///   Records for the DNS Security Extensions (March 2005)
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DNSKEY {
+
+    /// The key’s flags, which say whether it is a zone key, and whether it
+    /// is a key-signing key.
+    pub flags: u16,
+
+    /// The protocol number, which must always be 3.
+    pub protocol: u8,
+
+    /// The algorithm the key is used with. This is a number with several
+    /// defined mappings.
+    pub algorithm: u8,
+
+    /// The public key, in the format used by its algorithm.
+    pub public_key: Vec<u8>,
+}
+
+impl Wire for DNSKEY {
+    const NAME: &'static str = "DNSKEY";
+    const RR_TYPE: u16 = 48;
+
+    #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+    fn read(stated_length: u16, c: &mut Cursor<&[u8]>) -> Result<Self, WireError> {
+        let flags = c.read_u16::<BigEndian>()?;
+        trace!("Parsed flags -> {:#06x}", flags);
+
+        let protocol = c.read_u8()?;
+        trace!("Parsed protocol -> {:?}", protocol);
+
+        let algorithm = c.read_u8()?;
+        trace!("Parsed algorithm -> {:?}", algorithm);
+
+        if stated_length <= 4 {
+            let mandated_length = MandatedLength::AtLeast(5);
+            return Err(WireError::WrongRecordLength { stated_length, mandated_length });
+        }
+
+        let key_length = stated_length - 2 - 1 - 1;
+        let mut public_key = vec![0_u8; usize::from(key_length)];
+        c.read_exact(&mut public_key)?;
+        trace!("Parsed public key -> {:#x?}", public_key);
+
+        Ok(Self { flags, protocol, algorithm, public_key })
+    }
+
+    fn write(&self, bytes: &mut Vec<u8>) -> io::Result<()> {
+        bytes.write_u16::<BigEndian>(self.flags)?;
+        bytes.write_u8(self.protocol)?;
+        bytes.write_u8(self.algorithm)?;
+        bytes.extend_from_slice(&self.public_key);
+        Ok(())
+    }
+}
+
+impl DNSKEY {
+
+    /// Whether the Zone Key flag is set, which it has to be for the key
+    /// to be used to verify signatures over a zone’s records.
+    pub fn is_zone_key(&self) -> bool {
+        self.flags & 0b_0000_0001_0000_0000 != 0
+    }
+
+    /// Whether the Secure Entry Point flag is set, which marks the key as
+    /// a key-signing key that the zone’s DS records should point to.
+    pub fn is_secure_entry_point(&self) -> bool {
+        self.flags & 0b_0000_0000_0000_0001 != 0
+    }
+
+    /// Returns the key tag, a checksum over the record’s data that RRSIG and
+    /// DS records use to say which key they mean. Keys for the obsolete
+    /// RSA/MD5 algorithm, which used a different method, are not handled.
+    ///
+    /// # References
+    ///
+    /// - [RFC 4034 Appendix B](https://tools.ietf.org/html/rfc4034#appendix-B) —
+    ///   Resource Records for the DNS Security Extensions (March 2005)
+    pub fn key_tag(&self) -> u16 {
+        let mut data = Vec::with_capacity(self.public_key.len() + 4);
+        data.extend_from_slice(&self.flags.to_be_bytes());
+        data.push(self.protocol);
+        data.push(self.algorithm);
+        data.extend_from_slice(&self.public_key);
+
+        let mut accumulator: u32 = 0;
+        for (index, byte) in data.iter().enumerate() {
+            if index % 2 == 0 {
+                accumulator += u32::from(*byte) << 8;
+            }
+            else {
+                accumulator += u32::from(*byte);
+            }
+        }
+
+        accumulator += (accumulator >> 16) & 0xFFFF;
+        let [_, _, high, low] = accumulator.to_be_bytes();
+        u16::from_be_bytes([ high, low ])
+    }
+
+    /// The base64-encoded public key.
+    pub fn base64_key(&self) -> String {
+        base64::encode(&self.public_key)
+    }
+
+    /// Returns the record’s data in presentation format, with the key in
+    /// base64.
+    pub fn to_presentation(&self) -> String {
+        format!("{} {} {} {}", self.flags, self.protocol, self.algorithm, self.base64_key())
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parses() {
+        let buf = &[
+            0x01, 0x01,  // flags
+            0x03,  // protocol
+            0x0f,  // algorithm
+            0x12, 0x34, 0x56, 0x78,  // a short public key
+        ];
+
+        assert_eq!(DNSKEY::read(buf.len() as _, &mut Cursor::new(buf)).unwrap(),
+                   DNSKEY {
+                       flags: 257,
+                       protocol: 3,
+                       algorithm: 15,
+                       public_key: vec![ 0x12, 0x34, 0x56, 0x78 ],
+                   });
+    }
+
+    #[test]
+    fn record_too_short() {
+        let buf = &[
+            0x01, 0x00,  // flags
+            0x03,  // protocol
+            0x0d,  // algorithm
+        ];
+
+        assert_eq!(DNSKEY::read(buf.len() as _, &mut Cursor::new(buf)),
+                   Err(WireError::WrongRecordLength { stated_length: 4, mandated_length: MandatedLength::AtLeast(5) }));
+    }
+
+    #[test]
+    fn record_empty() {
+        assert_eq!(DNSKEY::read(0, &mut Cursor::new(&[])),
+                   Err(WireError::IO));
+    }
+
+    #[test]
+    fn buffer_ends_abruptly() {
+        let buf = &[
+            0x01, 0x00,  // flags
+            0x03,  // protocol
+        ];
+
+        assert_eq!(DNSKEY::read(9, &mut Cursor::new(buf)),
+                   Err(WireError::IO));
+    }
+
+    #[test]
+    fn flags() {
+        let ksk = DNSKEY { flags: 257, protocol: 3, algorithm: 13, public_key: vec![ 0x00 ] };
+        assert!(ksk.is_zone_key());
+        assert!(ksk.is_secure_entry_point());
+
+        let zsk = DNSKEY { flags: 256, .. ksk.clone() };
+        assert!(zsk.is_zone_key());
+        assert!(! zsk.is_secure_entry_point());
+
+        let neither = DNSKEY { flags: 0, .. ksk };
+        assert!(! neither.is_zone_key());
+    }
+
+    #[test]
+    fn key_tag() {
+        let key = DNSKEY {
+            flags: 256,
+            protocol: 3,
+            algorithm: 13,
+            public_key: vec![ 0xff, 0xee, 0xdd, 0xcc, 0xbb ],
+        };
+
+        assert_eq!(key.key_tag(), 0x9cc9);
+    }
+
+    #[test]
+    fn round_trip() {
+        let buf = &[
+            0x01, 0x01,  // flags
+            0x03,  // protocol
+            0x08,  // algorithm
+            0x03, 0x01, 0x00, 0x01, 0xab, 0xcd,  // a short public key
+        ];
+
+        let record = DNSKEY::read(buf.len() as _, &mut Cursor::new(buf)).unwrap();
+        let mut bytes = Vec::new();
+        record.write(&mut bytes).unwrap();
+        assert_eq!(bytes, buf);
+    }
+}
@@ -0,0 +1,195 @@
+use log::*;
+
+use crate::presentation::quoted;
+use crate::wire::*;
+
+
+/// A **DOA** _(digital object architecture)_ record, which holds a digital
+/// object along with the information necessary to interpret it. This is an
+/// experimental record type, but some IoT registries have started to
+/// publish them.
+///
+/// # References
+///
+/// - [draft-durand-doa-over-dns](https://tools.ietf.org/html/draft-durand-doa-over-dns-03) —
+///   DOA over DNS (February 2018)
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DOA {
+
+    /// The number of the enterprise that has defined the type of object,
+    /// as assigned by IANA, or zero for the IETF.
+    pub enterprise: u32,
+
+    /// The type of object, as defined by the enterprise.
+    pub doa_type: u32,
+
+    /// Where the data can be found: within the record itself, at a URI in
+    /// the data field, or through some other, enterprise-specific means.
+    pub location: u8,
+
+    /// The media type of the data, such as `image/png`. This may be empty.
+    pub media_type: Box<[u8]>,
+
+    /// The object data itself, as unencoded bytes.
+    pub data: Vec<u8>,
+}
+
+impl Wire for DOA {
+    const NAME: &'static str = "DOA";
+    const RR_TYPE: u16 = 259;
+
+    #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+    fn read(stated_length: u16, c: &mut Cursor<&[u8]>) -> Result<Self, WireError> {
+        let enterprise = c.read_u32::<BigEndian>()?;
+        trace!("Parsed enterprise -> {:?}", enterprise);
+
+        let doa_type = c.read_u32::<BigEndian>()?;
+        trace!("Parsed DOA type -> {:?}", doa_type);
+
+        let location = c.read_u8()?;
+        trace!("Parsed location -> {:?}", location);
+
+        let media_type_length = c.read_u8()?;
+        trace!("Parsed media type length -> {:?}", media_type_length);
+
+        let length_before_data = 4 + 4 + 1 + 1 + u16::from(media_type_length);
+        if stated_length < length_before_data {
+            let mandated_length = MandatedLength::AtLeast(length_before_data);
+            return Err(WireError::WrongRecordLength { stated_length, mandated_length });
+        }
+
+        let mut media_type = vec![0_u8; usize::from(media_type_length)].into_boxed_slice();
+        c.read_exact(&mut media_type)?;
+        trace!("Parsed media type -> {:?}", String::from_utf8_lossy(&media_type));
+
+        let mut data = vec![0_u8; usize::from(stated_length - length_before_data)];
+        c.read_exact(&mut data)?;
+        trace!("Parsed data -> {:#x?}", data);
+
+        Ok(Self { enterprise, doa_type, location, media_type, data })
+    }
+
+    fn write(&self, bytes: &mut Vec<u8>) -> io::Result<()> {
+        bytes.write_u32::<BigEndian>(self.enterprise)?;
+        bytes.write_u32::<BigEndian>(self.doa_type)?;
+        bytes.write_u8(self.location)?;
+        write_character_string(bytes, &self.media_type)?;
+        bytes.extend_from_slice(&self.data);
+        Ok(())
+    }
+}
+
+impl DOA {
+
+    /// The base64-encoded object data.
+    pub fn base64_data(&self) -> String {
+        base64::encode(&self.data)
+    }
+
+    /// Returns the record’s data in presentation format, with the data in
+    /// base64, or `-` if there is none.
+    pub fn to_presentation(&self) -> String {
+        let data = if self.data.is_empty() { "-".into() } else { self.base64_data() };
+
+        format!("{} {} {} {} {}",
+            self.enterprise,
+            self.doa_type,
+            self.location,
+            quoted(&self.media_type),
+            data,
+        )
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parses() {
+        let buf = &[
+            0x00, 0x00, 0x00, 0x00,  // enterprise
+            0x00, 0x00, 0x00, 0x01,  // DOA type
+            0x02,  // location
+            0x09, 0x74, 0x65, 0x78, 0x74, 0x2f, 0x70, 0x6c, 0x61, 0x69,  // media type
+            0x77, 0x6f, 0x6f, 0x66,  // data
+        ];
+
+        assert_eq!(DOA::read(buf.len() as _, &mut Cursor::new(buf)).unwrap(),
+                   DOA {
+                       enterprise: 0,
+                       doa_type: 1,
+                       location: 2,
+                       media_type: Box::new(*b"text/plai"),
+                       data: b"woof".to_vec(),
+                   });
+    }
+
+    #[test]
+    fn parses_empty_fields() {
+        let buf = &[
+            0x00, 0x00, 0x00, 0x00,  // enterprise
+            0x00, 0x00, 0x00, 0x00,  // DOA type
+            0x01,  // location
+            0x00,  // media type
+        ];
+
+        assert_eq!(DOA::read(buf.len() as _, &mut Cursor::new(buf)).unwrap(),
+                   DOA {
+                       enterprise: 0,
+                       doa_type: 0,
+                       location: 1,
+                       media_type: Box::new([]),
+                       data: vec![],
+                   });
+    }
+
+    #[test]
+    fn record_too_short() {
+        let buf = &[
+            0x00, 0x00, 0x00, 0x00,  // enterprise
+            0x00, 0x00, 0x00, 0x01,  // DOA type
+            0x02,  // location
+            0x09, 0x74, 0x65,  // media type (partial)
+        ];
+
+        assert_eq!(DOA::read(buf.len() as _, &mut Cursor::new(buf)),
+                   Err(WireError::WrongRecordLength { stated_length: 12, mandated_length: MandatedLength::AtLeast(19) }));
+    }
+
+    #[test]
+    fn record_empty() {
+        assert_eq!(DOA::read(0, &mut Cursor::new(&[])),
+                   Err(WireError::IO));
+    }
+
+    #[test]
+    fn buffer_ends_abruptly() {
+        let buf = &[
+            0x00, 0x00, 0x00, 0x00,  // enterprise
+            0x00, 0x00,  // half a DOA type
+        ];
+
+        assert_eq!(DOA::read(23, &mut Cursor::new(buf)),
+                   Err(WireError::IO));
+    }
+
+    #[test]
+    fn round_trip() {
+        let buf = &[
+            0x00, 0x00, 0x00, 0x00,  // enterprise
+            0x00, 0x00, 0x00, 0x01,  // DOA type
+            0x02,  // location
+            0x09, 0x74, 0x65, 0x78, 0x74, 0x2f, 0x70, 0x6c, 0x61, 0x69,  // media type
+            0x77, 0x6f, 0x6f, 0x66,  // data
+        ];
+
+        let record = DOA::read(buf.len() as _, &mut Cursor::new(buf)).unwrap();
+        let mut bytes = Vec::new();
+        record.write(&mut bytes).unwrap();
+        assert_eq!(bytes, buf);
+    }
+}
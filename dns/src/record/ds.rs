@@ -0,0 +1,293 @@
+use log::*;
+
+use crate::presentation::hex;
+use crate::wire::*;
+
+
+/// A **DS** _(delegation signer)_ record, which contains the digest of a
+/// DNSKEY record belonging to a child zone, so that the key can be
+/// authenticated from the parent.
+///
+/// # References
+///
+/// - [RFC 4034 §5](https://tools.ietf.org/html/rfc4034#section-5) — Resource
+///   Records for the DNS Security Extensions (March 2005)
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DS {
+
+    /// The key tag of the DNSKEY record that this record refers to.
+    pub key_tag: u16,
+
+    /// The algorithm of the DNSKEY record that this record refers to. This
+    /// is a number with several defined mappings.
+    pub algorithm: u8,
+
+    /// The algorithm used to construct the digest. This is a number with
+    /// several defined mappings.
+    pub digest_type: u8,
+
+    /// The digest of the DNSKEY record that this record refers to.
+    pub digest: Vec<u8>,
+}
+
+impl Wire for DS {
+    const NAME: &'static str = "DS";
+    const RR_TYPE: u16 = 43;
+
+    #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+    fn read(stated_length: u16, c: &mut Cursor<&[u8]>) -> Result<Self, WireError> {
+        let key_tag = c.read_u16::<BigEndian>()?;
+        trace!("Parsed key tag -> {:?}", key_tag);
+
+        let algorithm = c.read_u8()?;
+        trace!("Parsed algorithm -> {:?}", algorithm);
+
+        let digest_type = c.read_u8()?;
+        trace!("Parsed digest type -> {:?}", digest_type);
+
+        if stated_length <= 4 {
+            let mandated_length = MandatedLength::AtLeast(5);
+            return Err(WireError::WrongRecordLength { stated_length, mandated_length });
+        }
+
+        let digest_length = stated_length - 2 - 1 - 1;
+        let mut digest = vec![0_u8; usize::from(digest_length)];
+        c.read_exact(&mut digest)?;
+        trace!("Parsed digest -> {:#x?}", digest);
+
+        Ok(Self { key_tag, algorithm, digest_type, digest })
+    }
+
+    fn write(&self, bytes: &mut Vec<u8>) -> io::Result<()> {
+        bytes.write_u16::<BigEndian>(self.key_tag)?;
+        bytes.write_u8(self.algorithm)?;
+        bytes.write_u8(self.digest_type)?;
+        bytes.extend_from_slice(&self.digest);
+        Ok(())
+    }
+}
+
+impl DS {
+
+    /// Returns the hexadecimal representation of the digest.
+    pub fn hex_digest(&self) -> String {
+        self.digest.iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
+    /// Returns the record’s data in presentation format, with the digest in
+    /// hexadecimal.
+    pub fn to_presentation(&self) -> String {
+        format!("{} {} {} {}", self.key_tag, self.algorithm, self.digest_type, hex(&self.digest))
+    }
+}
+
+
+/// A **TA** _(trust anchor)_ record, which was proposed as a way of
+/// publishing DNSSEC trust anchors for zones without a signed parent. It
+/// uses the same wire format as the DS record.
+///
+/// # References
+///
+/// - [Deploying DNSSEC Without a Signed Root](http://www.watson.org/~weiler/INI1999-19.pdf)
+///   — Samuel Weiler (2004)
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TA(pub DS);
+
+impl Wire for TA {
+    const NAME: &'static str = "TA";
+    const RR_TYPE: u16 = 32768;
+
+    #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+    fn read(stated_length: u16, c: &mut Cursor<&[u8]>) -> Result<Self, WireError> {
+        DS::read(stated_length, c).map(Self)
+    }
+
+    fn write(&self, bytes: &mut Vec<u8>) -> io::Result<()> {
+        self.0.write(bytes)
+    }
+}
+
+impl TA {
+
+    /// Returns the record’s data in presentation format, which is the same as
+    /// a DS record’s.
+    pub fn to_presentation(&self) -> String {
+        self.0.to_presentation()
+    }
+}
+
+
+/// A **DLV** _(DNSSEC lookaside validation)_ record, which was used to
+/// publish trust anchors in a registry outside of the usual DNS hierarchy.
+/// The registries have since been decommissioned, but the records can still
+/// be found in the wild. It uses the same wire format as the DS record.
+///
+/// # References
+///
+/// - [RFC 4431](https://tools.ietf.org/html/rfc4431) — The DNSSEC Lookaside
+///   Validation (DLV) DNS Resource Record (February 2006)
+/// - [RFC 8749](https://tools.ietf.org/html/rfc8749) — Moving DNSSEC
+///   Lookaside Validation (DLV) to Historic Status (March 2020)
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DLV(pub DS);
+
+impl Wire for DLV {
+    const NAME: &'static str = "DLV";
+    const RR_TYPE: u16 = 32769;
+
+    #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+    fn read(stated_length: u16, c: &mut Cursor<&[u8]>) -> Result<Self, WireError> {
+        DS::read(stated_length, c).map(Self)
+    }
+
+    fn write(&self, bytes: &mut Vec<u8>) -> io::Result<()> {
+        self.0.write(bytes)
+    }
+}
+
+impl DLV {
+
+    /// Returns the record’s data in presentation format, which is the same as
+    /// a DS record’s.
+    pub fn to_presentation(&self) -> String {
+        self.0.to_presentation()
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parses() {
+        let buf = &[
+            0x4f, 0x66,  // key tag
+            0x08,  // algorithm
+            0x02,  // digest type
+            0xe2, 0xd3, 0xc9, 0x16, 0xf6, 0xde,  // a short digest
+        ];
+
+        assert_eq!(DS::read(buf.len() as _, &mut Cursor::new(buf)).unwrap(),
+                   DS {
+                       key_tag: 20326,
+                       algorithm: 8,
+                       digest_type: 2,
+                       digest: vec![ 0xe2, 0xd3, 0xc9, 0x16, 0xf6, 0xde ],
+                   });
+    }
+
+    #[test]
+    fn parses_as_ta() {
+        let buf = &[
+            0x4f, 0x66,  // key tag
+            0x08,  // algorithm
+            0x02,  // digest type
+            0xe2,  // an extremely short digest
+        ];
+
+        assert_eq!(TA::read(buf.len() as _, &mut Cursor::new(buf)).unwrap(),
+                   TA(DS {
+                       key_tag: 20326,
+                       algorithm: 8,
+                       digest_type: 2,
+                       digest: vec![ 0xe2 ],
+                   }));
+    }
+
+    #[test]
+    fn parses_as_dlv() {
+        let buf = &[
+            0x4f, 0x66,  // key tag
+            0x08,  // algorithm
+            0x01,  // digest type
+            0xe2, 0xd3,  // a short digest
+        ];
+
+        assert_eq!(DLV::read(buf.len() as _, &mut Cursor::new(buf)).unwrap(),
+                   DLV(DS {
+                       key_tag: 20326,
+                       algorithm: 8,
+                       digest_type: 1,
+                       digest: vec![ 0xe2, 0xd3 ],
+                   }));
+    }
+
+    #[test]
+    fn record_too_short() {
+        let buf = &[
+            0x4f, 0x66,  // key tag
+            0x08,  // algorithm
+            0x02,  // digest type
+        ];
+
+        assert_eq!(DS::read(buf.len() as _, &mut Cursor::new(buf)),
+                   Err(WireError::WrongRecordLength { stated_length: 4, mandated_length: MandatedLength::AtLeast(5) }));
+    }
+
+    #[test]
+    fn record_empty() {
+        assert_eq!(DS::read(0, &mut Cursor::new(&[])),
+                   Err(WireError::IO));
+    }
+
+    #[test]
+    fn buffer_ends_abruptly() {
+        let buf = &[
+            0x4f, 0x66,  // key tag
+            0x08,  // algorithm
+        ];
+
+        assert_eq!(DLV::read(9, &mut Cursor::new(buf)),
+                   Err(WireError::IO));
+    }
+
+    #[test]
+    fn hex_rep() {
+        let ds = DS {
+            key_tag: 20326,
+            algorithm: 8,
+            digest_type: 2,
+            digest: vec![ 0xe0, 0x6d, 0x44, 0xb8 ],
+        };
+
+        assert_eq!(ds.hex_digest(),
+                   String::from("e06d44b8"));
+    }
+
+    #[test]
+    fn round_trip() {
+        let buf = &[
+            0x4f, 0x66,  // key tag
+            0x08,  // algorithm
+            0x02,  // digest type
+            0xe2, 0xd3, 0xc9, 0x16, 0xf6, 0xde,  // a short digest
+        ];
+
+        let record = DS::read(buf.len() as _, &mut Cursor::new(buf)).unwrap();
+        let mut bytes = Vec::new();
+        record.write(&mut bytes).unwrap();
+        assert_eq!(bytes, buf);
+    }
+
+    #[test]
+    fn round_trip_dlv() {
+        let buf = &[
+            0x4f, 0x66,  // key tag
+            0x08,  // algorithm
+            0x01,  // digest type
+            0xe2, 0xd3,  // a short digest
+        ];
+
+        let record = DLV::read(buf.len() as _, &mut Cursor::new(buf)).unwrap();
+        let mut bytes = Vec::new();
+        record.write(&mut bytes).unwrap();
+        assert_eq!(bytes, buf);
+    }
+}
@@ -0,0 +1,1092 @@
+//! The options that can be carried in the data of an OPT pseudo-record.
+
+use std::convert::TryFrom;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::time::Duration;
+
+use log::*;
+
+use crate::strings::{Labels, ReadLabels, WriteLabels};
+use crate::wire::*;
+
+
+/// One of the options in the data of an OPT record. Each one has a code
+/// that says what it is, followed by its data.
+///
+/// # References
+///
+/// - [RFC 6891 §6.1.2](https://tools.ietf.org/html/rfc6891#section-6.1.2) —
+///   Extension Mechanisms for DNS (April 2013)
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EdnsOption {
+
+    /// A name server identifier, saying which server answered.
+    Nsid(Nsid),
+
+    /// How long a secondary server has left before its copy of the zone
+    /// expires.
+    Expire(Expire),
+
+    /// A pair of cookies, used to tell real clients and servers apart from
+    /// ones spoofing their addresses.
+    Cookie(Cookie),
+
+    /// The network a query came from, so the answer can be tailored to it.
+    ClientSubnet(ClientSubnet),
+
+    /// How long a TCP connection may sit idle before the server closes it.
+    TcpKeepalive(TcpKeepalive),
+
+    /// Some meaningless bytes, used to hide the length of the message.
+    Padding(Padding),
+
+    /// The trust point that a chain of DNSSEC records should start from.
+    Chain(Chain),
+
+    /// An extended error, explaining why a query failed.
+    ExtendedError(ExtendedError),
+
+    /// The domain that errors found while resolving a query can be
+    /// reported to.
+    ReportChannel(ReportChannel),
+
+    /// An option with a code that dog does not know how to interpret.
+    Other {
+
+        /// The option code.
+        code: u16,
+
+        /// The option’s data, uninterpreted.
+        data: Vec<u8>,
+    },
+}
+
+/// An **NSID** _(name server identifier)_, which a server sends back when
+/// asked to say which server it is. This is most useful for anycast
+/// addresses, where many servers share the same address. Requests carry an
+/// empty identifier.
+///
+/// # References
+///
+/// - [RFC 5001](https://tools.ietf.org/html/rfc5001) — DNS Name Server
+///   Identifier (NSID) Option (August 2007)
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Nsid(pub Vec<u8>);
+
+/// A **DNS Cookie**, which a client sends with its queries, and which a
+/// server sends back alongside a cookie of its own. The client sends the
+/// server’s cookie back in later queries to prove that it can receive
+/// responses at the address it claims to have.
+///
+/// # References
+///
+/// - [RFC 7873](https://tools.ietf.org/html/rfc7873) — Domain Name System
+///   (DNS) Cookies (May 2016)
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Cookie {
+
+    /// The client cookie, which is always eight bytes long.
+    pub client: [u8; 8],
+
+    /// The server cookie, which is empty until a server has sent one, and
+    /// between eight and thirty-two bytes long afterwards.
+    pub server: Vec<u8>,
+}
+
+/// An **EDNS Client Subnet**, which a resolver sends to an authoritative
+/// server to say which network the client is on, so that the server can
+/// give an answer that suits the client’s location. The server sends it
+/// back with the scope prefix length filled in, saying which networks the
+/// answer is valid for.
+///
+/// # References
+///
+/// - [RFC 7871](https://tools.ietf.org/html/rfc7871) — Client Subnet in DNS
+///   Queries (May 2016)
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ClientSubnet {
+
+    /// The address family number: 1 for IPv4, or 2 for IPv6.
+    pub family: u16,
+
+    /// The number of leading bits of the address that were sent.
+    pub source_prefix: u8,
+
+    /// The number of leading bits of the address that the answer covers.
+    /// This is always zero in queries.
+    pub scope_prefix: u8,
+
+    /// The leading bytes of the address, with as many bytes as are needed
+    /// to hold the source prefix.
+    pub address: Vec<u8>,
+}
+
+/// An **EXPIRE** option, which a client sends to ask an authoritative
+/// server how long its copy of the zone has left before it expires. A
+/// secondary server counts down from the expire field of the zone’s SOA
+/// record each time it fails to refresh it, and a primary server always
+/// sends that field as it is. Requests leave the timer out.
+///
+/// # References
+///
+/// - [RFC 7314](https://tools.ietf.org/html/rfc7314) — Extension Mechanisms
+///   for DNS (EDNS) EXPIRE Option (July 2014)
+#[derive(PartialEq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Expire {
+
+    /// The number of seconds left before the zone expires.
+    pub timer: Option<u32>,
+}
+
+/// A **TCP keepalive**, which a client sends over TCP to ask that the
+/// connection be kept open for more queries, and which the server sends back
+/// with the time it will wait for one before closing the connection. The
+/// timeout is counted in units of 100 milliseconds, and is left out in
+/// queries.
+///
+/// # References
+///
+/// - [RFC 7828](https://tools.ietf.org/html/rfc7828) — The edns-tcp-keepalive
+///   EDNS0 Option (April 2016)
+#[derive(PartialEq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TcpKeepalive {
+
+    /// The idle timeout, in units of 100 milliseconds.
+    pub timeout: Option<u16>,
+}
+
+/// Some **padding**, which makes an encrypted message a different length
+/// so that its real length cannot be guessed. This holds the number of
+/// bytes of padding, which are sent as zeroes.
+///
+/// # References
+///
+/// - [RFC 7830](https://tools.ietf.org/html/rfc7830) — The EDNS(0) Padding
+///   Option (May 2016)
+/// - [RFC 8467](https://tools.ietf.org/html/rfc8467) — Padding Policies for
+///   Extension Mechanisms for DNS (October 2018)
+#[derive(PartialEq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Padding(pub u16);
+
+/// A **CHAIN** option, which a validating stub resolver sends to ask the
+/// recursive server for every record needed to validate the answer, starting
+/// from the closest trust point the stub already has. The server echoes
+/// back the trust point that its chain starts from.
+///
+/// # References
+///
+/// - [RFC 7901](https://tools.ietf.org/html/rfc7901) — CHAIN Query Requests
+///   in DNS (June 2016)
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Chain {
+
+    /// The closest trust point, as an uncompressed domain name.
+    pub trust_point: Labels,
+}
+
+/// A **Report-Channel** option, which an authoritative server sends to say
+/// which agent domain resolvers should report errors in its zones to, by
+/// querying a name under it that describes the error.
+///
+/// # References
+///
+/// - [RFC 9567](https://tools.ietf.org/html/rfc9567) — DNS Error Reporting
+///   (April 2024)
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReportChannel {
+
+    /// The agent domain, as an uncompressed domain name.
+    pub agent_domain: Labels,
+}
+
+/// An **Extended DNS Error**, which a server can use to give the reason it
+/// returned the response it did, most often a failure.
+///
+/// # References
+///
+/// - [RFC 8914](https://tools.ietf.org/html/rfc8914) — Extended DNS Errors
+///   (October 2020)
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExtendedError {
+
+    /// The number that says which error this is.
+    pub info_code: u16,
+
+    /// Some text that the server chose to add, intended for humans to read.
+    /// This is meant to be UTF-8, but may not be.
+    pub extra_text: Vec<u8>,
+}
+
+impl EdnsOption {
+
+    /// Reads every option out of the data of an OPT record.
+    #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+    pub fn read_all(data: &[u8]) -> Result<Vec<Self>, WireError> {
+        let mut c = Cursor::new(data);
+        let mut options = Vec::new();
+
+        while c.position() < data.len() as u64 {
+            options.push(Self::read(&mut c)?);
+        }
+
+        Ok(options)
+    }
+
+    /// Reads a single option, including its code and length.
+    fn read(c: &mut Cursor<&[u8]>) -> Result<Self, WireError> {
+        let code = c.read_u16::<BigEndian>()?;
+        trace!("Parsed option code -> {:?}", code);
+
+        let length = c.read_u16::<BigEndian>()?;
+        trace!("Parsed option length -> {:?}", length);
+
+        let mut data = vec![0_u8; usize::from(length)];
+        c.read_exact(&mut data)?;
+        trace!("Parsed option data -> {:#x?}", data);
+
+        match code {
+            Nsid::OPTION_CODE => {
+                Ok(Self::Nsid(Nsid(data)))
+            }
+            Cookie::OPTION_CODE => {
+                Cookie::read(length, &mut Cursor::new(&data)).map(Self::Cookie)
+            }
+            ClientSubnet::OPTION_CODE => {
+                ClientSubnet::read(length, &mut Cursor::new(&data)).map(Self::ClientSubnet)
+            }
+            Expire::OPTION_CODE => {
+                Expire::read(length, &mut Cursor::new(&data)).map(Self::Expire)
+            }
+            TcpKeepalive::OPTION_CODE => {
+                TcpKeepalive::read(length, &mut Cursor::new(&data)).map(Self::TcpKeepalive)
+            }
+            Padding::OPTION_CODE => {
+                Ok(Self::Padding(Padding(length)))
+            }
+            Chain::OPTION_CODE => {
+                Chain::read(length, &mut Cursor::new(&data)).map(Self::Chain)
+            }
+            ReportChannel::OPTION_CODE => {
+                ReportChannel::read(length, &mut Cursor::new(&data)).map(Self::ReportChannel)
+            }
+            ExtendedError::OPTION_CODE => {
+                ExtendedError::read(length, &mut Cursor::new(&data)).map(Self::ExtendedError)
+            }
+            _ => {
+                Ok(Self::Other { code, data })
+            }
+        }
+    }
+
+    /// Returns this option’s code.
+    pub fn code(&self) -> u16 {
+        match self {
+            Self::Nsid(_)             => Nsid::OPTION_CODE,
+            Self::Cookie(_)           => Cookie::OPTION_CODE,
+            Self::ClientSubnet(_)     => ClientSubnet::OPTION_CODE,
+            Self::Expire(_)           => Expire::OPTION_CODE,
+            Self::TcpKeepalive(_)     => TcpKeepalive::OPTION_CODE,
+            Self::Padding(_)          => Padding::OPTION_CODE,
+            Self::Chain(_)            => Chain::OPTION_CODE,
+            Self::ExtendedError(_)    => ExtendedError::OPTION_CODE,
+            Self::ReportChannel(_)    => ReportChannel::OPTION_CODE,
+            Self::Other { code, .. }  => *code,
+        }
+    }
+
+    /// Returns this option’s data, without its code or length.
+    fn data(&self) -> Vec<u8> {
+        match self {
+            Self::Nsid(nsid)          => nsid.0.clone(),
+            Self::Cookie(cookie)      => {
+                let mut data = cookie.client.to_vec();
+                data.extend(&cookie.server);
+                data
+            }
+            Self::ClientSubnet(ecs)   => {
+                let mut data = ecs.family.to_be_bytes().to_vec();
+                data.push(ecs.source_prefix);
+                data.push(ecs.scope_prefix);
+                data.extend(&ecs.address);
+                data
+            }
+            Self::Expire(expire)      => expire.timer.map(|t| t.to_be_bytes().to_vec()).unwrap_or_default(),
+            Self::TcpKeepalive(tk)    => tk.timeout.map(|t| t.to_be_bytes().to_vec()).unwrap_or_default(),
+            Self::Padding(padding)    => vec![0; usize::from(padding.0)],
+            Self::Chain(chain)        => {
+                let mut data = Vec::new();
+                data.write_labels(&chain.trust_point).expect("Failed to write trust point");
+                data
+            }
+            Self::ExtendedError(ede)  => {
+                let mut data = ede.info_code.to_be_bytes().to_vec();
+                data.extend(&ede.extra_text);
+                data
+            }
+            Self::ReportChannel(rc)   => {
+                let mut data = Vec::new();
+                data.write_labels(&rc.agent_domain).expect("Failed to write agent domain");
+                data
+            }
+            Self::Other { data, .. }  => data.clone(),
+        }
+    }
+
+    /// Serialises this option into a vector of bytes, to be added to the
+    /// data of an OPT record in a request.
+    pub fn to_bytes(&self) -> io::Result<Vec<u8>> {
+        let data = self.data();
+        let mut bytes = Vec::with_capacity(4 + data.len());
+
+        bytes.write_u16::<BigEndian>(self.code())?;
+
+        let data_len = u16::try_from(data.len()).expect("Sending too much data");
+        bytes.write_u16::<BigEndian>(data_len)?;
+        bytes.extend(data);
+
+        Ok(bytes)
+    }
+}
+
+impl Nsid {
+
+    /// The option code associated with NSIDs.
+    pub const OPTION_CODE: u16 = 3;
+
+    /// Returns the identifier as a hexadecimal string.
+    pub fn hex(&self) -> String {
+        hex(&self.0)
+    }
+
+    /// Returns the identifier as text, with any bytes that are not valid
+    /// UTF-8 replaced.
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.0).to_string()
+    }
+}
+
+impl Cookie {
+
+    /// The option code associated with cookies.
+    pub const OPTION_CODE: u16 = 10;
+
+    /// Creates a cookie to send in a first query, with a client cookie but
+    /// no server cookie.
+    pub fn new(client: [u8; 8]) -> Self {
+        Self { client, server: Vec::new() }
+    }
+
+    #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+    fn read(stated_length: u16, c: &mut Cursor<&[u8]>) -> Result<Self, WireError> {
+        if stated_length < 8 {
+            let mandated_length = MandatedLength::AtLeast(8);
+            return Err(WireError::WrongRecordLength { stated_length, mandated_length });
+        }
+
+        let mut client = [0_u8; 8];
+        c.read_exact(&mut client)?;
+        trace!("Parsed client cookie -> {:#x?}", client);
+
+        let mut server = vec![0_u8; usize::from(stated_length - 8)];
+        c.read_exact(&mut server)?;
+        trace!("Parsed server cookie -> {:#x?}", server);
+
+        Ok(Self { client, server })
+    }
+
+    /// Returns the client cookie as a hexadecimal string.
+    pub fn client_hex(&self) -> String {
+        hex(&self.client)
+    }
+
+    /// Returns the server cookie as a hexadecimal string, or `None` if the
+    /// server has not sent one.
+    pub fn server_hex(&self) -> Option<String> {
+        if self.server.is_empty() {
+            None
+        }
+        else {
+            Some(hex(&self.server))
+        }
+    }
+}
+
+impl ClientSubnet {
+
+    /// The option code associated with client subnets.
+    pub const OPTION_CODE: u16 = 8;
+
+    /// Creates a client subnet to send in a query, for the network with the
+    /// given address and prefix length. Any bits of the address past the
+    /// prefix are cleared, and any bytes past it are left out.
+    pub fn new(address: IpAddr, source_prefix: u8) -> Self {
+        let (family, octets) = match address {
+            IpAddr::V4(v4)  => (1, v4.octets().to_vec()),
+            IpAddr::V6(v6)  => (2, v6.octets().to_vec()),
+        };
+
+        let mut address = octets.into_iter()
+            .take(usize::from(source_prefix).div_ceil(8))
+            .collect::<Vec<_>>();
+
+        if source_prefix % 8 != 0 {
+            if let Some(last) = address.last_mut() {
+                *last &= 0xff << (8 - source_prefix % 8);
+            }
+        }
+
+        Self { family, source_prefix, scope_prefix: 0, address }
+    }
+
+    #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+    fn read(stated_length: u16, c: &mut Cursor<&[u8]>) -> Result<Self, WireError> {
+        if stated_length < 4 {
+            let mandated_length = MandatedLength::AtLeast(4);
+            return Err(WireError::WrongRecordLength { stated_length, mandated_length });
+        }
+
+        let family = c.read_u16::<BigEndian>()?;
+        trace!("Parsed family -> {:?}", family);
+
+        let source_prefix = c.read_u8()?;
+        trace!("Parsed source prefix -> {:?}", source_prefix);
+
+        let scope_prefix = c.read_u8()?;
+        trace!("Parsed scope prefix -> {:?}", scope_prefix);
+
+        let mut address = vec![0_u8; usize::from(stated_length - 4)];
+        c.read_exact(&mut address)?;
+        trace!("Parsed address -> {:?}", address);
+
+        Ok(Self { family, source_prefix, scope_prefix, address })
+    }
+
+    /// Returns the address of the network, with the missing bytes filled
+    /// in with zeroes, or `None` if the family is not IPv4 or IPv6 or the
+    /// address is too long for it.
+    pub fn ip_address(&self) -> Option<IpAddr> {
+        match self.family {
+            1 if self.address.len() <= 4 => {
+                let mut octets = [0_u8; 4];
+                octets[.. self.address.len()].copy_from_slice(&self.address);
+                Some(Ipv4Addr::from(octets).into())
+            }
+            2 if self.address.len() <= 16 => {
+                let mut octets = [0_u8; 16];
+                octets[.. self.address.len()].copy_from_slice(&self.address);
+                Some(Ipv6Addr::from(octets).into())
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Expire {
+
+    /// The option code associated with EXPIRE.
+    pub const OPTION_CODE: u16 = 9;
+
+    #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+    fn read(stated_length: u16, c: &mut Cursor<&[u8]>) -> Result<Self, WireError> {
+        match stated_length {
+            0 => {
+                Ok(Self { timer: None })
+            }
+            4 => {
+                let timer = c.read_u32::<BigEndian>()?;
+                trace!("Parsed expire timer -> {:?}", timer);
+                Ok(Self { timer: Some(timer) })
+            }
+            _ => {
+                let mandated_length = MandatedLength::Exactly(4);
+                Err(WireError::WrongRecordLength { stated_length, mandated_length })
+            }
+        }
+    }
+}
+
+impl TcpKeepalive {
+
+    /// The option code associated with TCP keepalives.
+    pub const OPTION_CODE: u16 = 11;
+
+    #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+    fn read(stated_length: u16, c: &mut Cursor<&[u8]>) -> Result<Self, WireError> {
+        match stated_length {
+            0 => {
+                Ok(Self { timeout: None })
+            }
+            2 => {
+                let timeout = c.read_u16::<BigEndian>()?;
+                trace!("Parsed timeout -> {:?}", timeout);
+                Ok(Self { timeout: Some(timeout) })
+            }
+            _ => {
+                let mandated_length = MandatedLength::Exactly(2);
+                Err(WireError::WrongRecordLength { stated_length, mandated_length })
+            }
+        }
+    }
+
+    /// Returns the idle timeout as a duration, if there is one.
+    pub fn duration(self) -> Option<Duration> {
+        self.timeout.map(|t| Duration::from_millis(u64::from(t) * 100))
+    }
+}
+
+impl Padding {
+
+    /// The option code associated with padding.
+    pub const OPTION_CODE: u16 = 12;
+}
+
+impl Chain {
+
+    /// The option code associated with CHAIN.
+    pub const OPTION_CODE: u16 = 13;
+
+    #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+    fn read(stated_length: u16, c: &mut Cursor<&[u8]>) -> Result<Self, WireError> {
+        let (trust_point, trust_point_length) = c.read_labels()?;
+        trace!("Parsed trust point -> {:?}", trust_point);
+
+        if stated_length == trust_point_length {
+            trace!("Length is correct");
+            Ok(Self { trust_point })
+        }
+        else {
+            warn!("Length is incorrect (stated length {:?}, trust point length {:?})", stated_length, trust_point_length);
+            Err(WireError::WrongLabelLength { stated_length, length_after_labels: trust_point_length })
+        }
+    }
+}
+
+impl ReportChannel {
+
+    /// The option code associated with Report-Channel.
+    pub const OPTION_CODE: u16 = 18;
+
+    #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+    fn read(stated_length: u16, c: &mut Cursor<&[u8]>) -> Result<Self, WireError> {
+        let (agent_domain, agent_domain_length) = c.read_labels()?;
+        trace!("Parsed agent domain -> {:?}", agent_domain);
+
+        if stated_length == agent_domain_length {
+            trace!("Length is correct");
+            Ok(Self { agent_domain })
+        }
+        else {
+            warn!("Length is incorrect (stated length {:?}, agent domain length {:?})", stated_length, agent_domain_length);
+            Err(WireError::WrongLabelLength { stated_length, length_after_labels: agent_domain_length })
+        }
+    }
+}
+
+impl ExtendedError {
+
+    /// The option code associated with extended errors.
+    pub const OPTION_CODE: u16 = 15;
+
+    #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+    fn read(stated_length: u16, c: &mut Cursor<&[u8]>) -> Result<Self, WireError> {
+        if stated_length < 2 {
+            let mandated_length = MandatedLength::AtLeast(2);
+            return Err(WireError::WrongRecordLength { stated_length, mandated_length });
+        }
+
+        let info_code = c.read_u16::<BigEndian>()?;
+        trace!("Parsed info code -> {:?}", info_code);
+
+        let mut extra_text = vec![0_u8; usize::from(stated_length - 2)];
+        c.read_exact(&mut extra_text)?;
+        trace!("Parsed extra text -> {:?}", String::from_utf8_lossy(&extra_text));
+
+        Ok(Self { info_code, extra_text })
+    }
+
+    /// Returns the name of this error’s info code, if it has one.
+    pub fn info_code_name(&self) -> Option<&'static str> {
+        extended_error_name(self.info_code)
+    }
+}
+
+/// Formats some bytes as a lowercase hexadecimal string.
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Returns the name that IANA has registered for an extended error info
+/// code, if there is one.
+pub fn extended_error_name(info_code: u16) -> Option<&'static str> {
+    let name = match info_code {
+         0 => "Other Error",
+         1 => "Unsupported DNSKEY Algorithm",
+         2 => "Unsupported DS Digest Type",
+         3 => "Stale Answer",
+         4 => "Forged Answer",
+         5 => "DNSSEC Indeterminate",
+         6 => "DNSSEC Bogus",
+         7 => "Signature Expired",
+         8 => "Signature Not Yet Valid",
+         9 => "DNSKEY Missing",
+        10 => "RRSIGs Missing",
+        11 => "No Zone Key Bit Set",
+        12 => "NSEC Missing",
+        13 => "Cached Error",
+        14 => "Not Ready",
+        15 => "Blocked",
+        16 => "Censored",
+        17 => "Filtered",
+        18 => "Prohibited",
+        19 => "Stale NXDomain Answer",
+        20 => "Not Authoritative",
+        21 => "Not Supported",
+        22 => "No Reachable Authority",
+        23 => "Network Error",
+        24 => "Invalid Data",
+        25 => "Signature Expired before Valid",
+        26 => "Too Early",
+        27 => "Unsupported NSEC3 Iterations Value",
+        28 => "Unable to conform to policy",
+        29 => "Synthesized",
+        30 => "Invalid Query Type",
+        _  => return None,
+    };
+
+    Some(name)
+}
+
+/// Returns the mnemonic that IANA has registered for an EDNS option code,
+/// if there is one.
+pub fn edns_option_name(code: u16) -> Option<&'static str> {
+    let name = match code {
+            1 => "LLQ",
+            2 => "UL",
+            3 => "NSID",
+            5 => "DAU",
+            6 => "DHU",
+            7 => "N3U",
+            8 => "edns-client-subnet",
+            9 => "EDNS EXPIRE",
+           10 => "COOKIE",
+           11 => "edns-tcp-keepalive",
+           12 => "Padding",
+           13 => "CHAIN",
+           14 => "edns-key-tag",
+           15 => "Extended DNS Error",
+           16 => "EDNS-Client-Tag",
+           17 => "EDNS-Server-Tag",
+           18 => "Report-Channel",
+           19 => "ZONEVERSION",
+        20292 => "Umbrella Ident",
+        26946 => "DeviceID",
+        _     => return None,
+    };
+
+    Some(name)
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parses_extended_error() {
+        let buf = &[
+            0x00, 0x0f,  // option code
+            0x00, 0x06,  // option length
+            0x00, 0x06,  // info code
+            0x62, 0x61, 0x64, 0x21,  // extra text
+        ];
+
+        assert_eq!(EdnsOption::read_all(buf),
+                   Ok(vec![ EdnsOption::ExtendedError(ExtendedError {
+                       info_code: 6,
+                       extra_text: b"bad!".to_vec(),
+                   }) ]));
+    }
+
+    #[test]
+    fn parses_extended_error_without_text() {
+        let buf = &[
+            0x00, 0x0f,  // option code
+            0x00, 0x02,  // option length
+            0x00, 0x12,  // info code
+        ];
+
+        assert_eq!(EdnsOption::read_all(buf),
+                   Ok(vec![ EdnsOption::ExtendedError(ExtendedError {
+                       info_code: 18,
+                       extra_text: vec![],
+                   }) ]));
+    }
+
+    #[test]
+    fn parses_several() {
+        let buf = &[
+            0xfd, 0xe9,  // option code
+            0x00, 0x01,  // option length
+            0x99,        // option data
+            0x00, 0x0f,  // option code
+            0x00, 0x02,  // option length
+            0x00, 0x03,  // info code
+        ];
+
+        assert_eq!(EdnsOption::read_all(buf),
+                   Ok(vec![
+                       EdnsOption::Other { code: 65001, data: vec![ 0x99 ] },
+                       EdnsOption::ExtendedError(ExtendedError { info_code: 3, extra_text: vec![] }),
+                   ]));
+    }
+
+    #[test]
+    fn parses_expire() {
+        let buf = &[
+            0x00, 0x09,  // option code
+            0x00, 0x04,  // option length
+            0x00, 0x01, 0x51, 0x80,  // expire timer
+        ];
+
+        assert_eq!(EdnsOption::read_all(buf),
+                   Ok(vec![ EdnsOption::Expire(Expire { timer: Some(86400) }) ]));
+    }
+
+    #[test]
+    fn expire_wrong_length() {
+        let buf = &[
+            0x00, 0x09,  // option code
+            0x00, 0x02,  // option length
+            0x01, 0x51,  // half an expire timer
+        ];
+
+        assert_eq!(EdnsOption::read_all(buf),
+                   Err(WireError::WrongRecordLength { stated_length: 2, mandated_length: MandatedLength::Exactly(4) }));
+    }
+
+    #[test]
+    fn serialises_expire_request() {
+        assert_eq!(EdnsOption::Expire(Expire { timer: None }).to_bytes().unwrap(),
+                   vec![ 0x00, 0x09, 0x00, 0x00 ]);
+    }
+
+    #[test]
+    fn option_names() {
+        assert_eq!(edns_option_name(5), Some("DAU"));
+        assert_eq!(edns_option_name(20292), Some("Umbrella Ident"));
+        assert_eq!(edns_option_name(65001), None);
+    }
+
+    #[test]
+    fn parses_nsid() {
+        let buf = &[
+            0x00, 0x03,  // option code
+            0x00, 0x03,  // option length
+            0x6c, 0x6f, 0x6e,  // identifier
+        ];
+
+        assert_eq!(EdnsOption::read_all(buf),
+                   Ok(vec![ EdnsOption::Nsid(Nsid(b"lon".to_vec())) ]));
+    }
+
+    #[test]
+    fn serialises_nsid_request() {
+        assert_eq!(EdnsOption::Nsid(Nsid(vec![])).to_bytes().unwrap(),
+                   vec![ 0x00, 0x03, 0x00, 0x00 ]);
+    }
+
+    #[test]
+    fn parses_cookies() {
+        let buf = &[
+            0x00, 0x0a,  // option code
+            0x00, 0x10,  // option length
+            0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08,  // client cookie
+            0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18,  // server cookie
+        ];
+
+        assert_eq!(EdnsOption::read_all(buf),
+                   Ok(vec![ EdnsOption::Cookie(Cookie {
+                       client: [ 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08 ],
+                       server: vec![ 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18 ],
+                   }) ]));
+    }
+
+    #[test]
+    fn parses_client_cookie() {
+        let buf = &[
+            0x00, 0x0a,  // option code
+            0x00, 0x08,  // option length
+            0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08,  // client cookie
+        ];
+
+        assert_eq!(EdnsOption::read_all(buf),
+                   Ok(vec![ EdnsOption::Cookie(Cookie::new([ 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08 ])) ]));
+    }
+
+    #[test]
+    fn cookie_too_short() {
+        let buf = &[
+            0x00, 0x0a,  // option code
+            0x00, 0x02,  // option length
+            0x01, 0x02,  // not enough cookie
+        ];
+
+        assert_eq!(EdnsOption::read_all(buf),
+                   Err(WireError::WrongRecordLength { stated_length: 2, mandated_length: MandatedLength::AtLeast(8) }));
+    }
+
+    #[test]
+    fn serialises_cookies() {
+        let cookie = EdnsOption::Cookie(Cookie { client: [ 0xaa; 8 ], server: vec![ 0xbb; 8 ] });
+        assert_eq!(cookie.to_bytes().unwrap(),
+                   vec![ 0x00, 0x0a, 0x00, 0x10,
+                         0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa,
+                         0xbb, 0xbb, 0xbb, 0xbb, 0xbb, 0xbb, 0xbb, 0xbb ]);
+    }
+
+    #[test]
+    fn cookie_formats() {
+        let cookie = Cookie::new([ 0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef ]);
+        assert_eq!(cookie.client_hex(), "0123456789abcdef");
+        assert_eq!(cookie.server_hex(), None);
+    }
+
+    #[test]
+    fn parses_client_subnet() {
+        let buf = &[
+            0x00, 0x08,  // option code
+            0x00, 0x07,  // option length
+            0x00, 0x01,  // family
+            0x18,        // source prefix
+            0x10,        // scope prefix
+            0x01, 0x02, 0x03,  // address
+        ];
+
+        let ecs = ClientSubnet { family: 1, source_prefix: 24, scope_prefix: 16, address: vec![ 1, 2, 3 ] };
+        assert_eq!(EdnsOption::read_all(buf),
+                   Ok(vec![ EdnsOption::ClientSubnet(ecs.clone()) ]));
+        assert_eq!(ecs.ip_address(),
+                   Some(IpAddr::V4(Ipv4Addr::new(1, 2, 3, 0))));
+    }
+
+    #[test]
+    fn client_subnet_too_short() {
+        let buf = &[
+            0x00, 0x08,  // option code
+            0x00, 0x02,  // option length
+            0x00, 0x01,  // family
+        ];
+
+        assert_eq!(EdnsOption::read_all(buf),
+                   Err(WireError::WrongRecordLength { stated_length: 2, mandated_length: MandatedLength::AtLeast(4) }));
+    }
+
+    #[test]
+    fn serialises_client_subnet_v4() {
+        let ecs = EdnsOption::ClientSubnet(ClientSubnet::new(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 255)), 25));
+        assert_eq!(ecs.to_bytes().unwrap(),
+                   vec![ 0x00, 0x08, 0x00, 0x08, 0x00, 0x01, 0x19, 0x00, 0xc0, 0x00, 0x02, 0x80 ]);
+    }
+
+    #[test]
+    fn serialises_client_subnet_v6() {
+        let ecs = EdnsOption::ClientSubnet(ClientSubnet::new("2001:db8::1".parse().unwrap(), 32));
+        assert_eq!(ecs.to_bytes().unwrap(),
+                   vec![ 0x00, 0x08, 0x00, 0x08, 0x00, 0x02, 0x20, 0x00, 0x20, 0x01, 0x0d, 0xb8 ]);
+    }
+
+    #[test]
+    fn serialises_client_subnet_empty() {
+        let ecs = EdnsOption::ClientSubnet(ClientSubnet::new(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)), 0));
+        assert_eq!(ecs.to_bytes().unwrap(),
+                   vec![ 0x00, 0x08, 0x00, 0x04, 0x00, 0x01, 0x00, 0x00 ]);
+    }
+
+    #[test]
+    fn parses_tcp_keepalive() {
+        let buf = &[
+            0x00, 0x0b,  // option code
+            0x00, 0x02,  // option length
+            0x00, 0x7d,  // timeout
+        ];
+
+        assert_eq!(EdnsOption::read_all(buf),
+                   Ok(vec![ EdnsOption::TcpKeepalive(TcpKeepalive { timeout: Some(125) }) ]));
+    }
+
+    #[test]
+    fn parses_tcp_keepalive_without_timeout() {
+        let buf = &[
+            0x00, 0x0b,  // option code
+            0x00, 0x00,  // option length
+        ];
+
+        assert_eq!(EdnsOption::read_all(buf),
+                   Ok(vec![ EdnsOption::TcpKeepalive(TcpKeepalive { timeout: None }) ]));
+    }
+
+    #[test]
+    fn tcp_keepalive_wrong_length() {
+        let buf = &[
+            0x00, 0x0b,  // option code
+            0x00, 0x01,  // option length
+            0x00,
+        ];
+
+        assert_eq!(EdnsOption::read_all(buf),
+                   Err(WireError::WrongRecordLength { stated_length: 1, mandated_length: MandatedLength::Exactly(2) }));
+    }
+
+    #[test]
+    fn serialises_tcp_keepalive() {
+        assert_eq!(EdnsOption::TcpKeepalive(TcpKeepalive { timeout: None }).to_bytes().unwrap(),
+                   vec![ 0x00, 0x0b, 0x00, 0x00 ]);
+    }
+
+    #[test]
+    fn tcp_keepalive_duration() {
+        assert_eq!(TcpKeepalive { timeout: Some(125) }.duration(),
+                   Some(Duration::from_millis(12_500)));
+    }
+
+    #[test]
+    fn parses_padding() {
+        let buf = &[
+            0x00, 0x0c,  // option code
+            0x00, 0x03,  // option length
+            0x00, 0x00, 0x00,  // padding
+        ];
+
+        assert_eq!(EdnsOption::read_all(buf),
+                   Ok(vec![ EdnsOption::Padding(Padding(3)) ]));
+    }
+
+    #[test]
+    fn serialises_padding() {
+        assert_eq!(EdnsOption::Padding(Padding(2)).to_bytes().unwrap(),
+                   vec![ 0x00, 0x0c, 0x00, 0x02, 0x00, 0x00 ]);
+    }
+
+    #[test]
+    fn parses_chain() {
+        let buf = &[
+            0x00, 0x0d,  // option code
+            0x00, 0x05,  // option length
+            0x03, 0x64, 0x6f, 0x67,  // trust point
+            0x00,  // trust point terminator
+        ];
+
+        assert_eq!(EdnsOption::read_all(buf),
+                   Ok(vec![ EdnsOption::Chain(Chain { trust_point: Labels::encode("dog").unwrap() }) ]));
+    }
+
+    #[test]
+    fn chain_wrong_length() {
+        let buf = &[
+            0x00, 0x0d,  // option code
+            0x00, 0x06,  // option length
+            0x03, 0x64, 0x6f, 0x67,  // trust point
+            0x00,  // trust point terminator
+            0x00,  // something extra
+        ];
+
+        assert_eq!(EdnsOption::read_all(buf),
+                   Err(WireError::WrongLabelLength { stated_length: 6, length_after_labels: 5 }));
+    }
+
+    #[test]
+    fn serialises_chain_from_root() {
+        let chain = EdnsOption::Chain(Chain { trust_point: Labels::root() });
+        assert_eq!(chain.to_bytes().unwrap(),
+                   vec![ 0x00, 0x0d, 0x00, 0x01, 0x00 ]);
+    }
+
+    #[test]
+    fn parses_report_channel() {
+        let buf = &[
+            0x00, 0x12,  // option code
+            0x00, 0x0b,  // option length
+            0x05, 0x61, 0x67, 0x65, 0x6e, 0x74,  // agent domain
+            0x03, 0x64, 0x6f, 0x67,
+            0x00,  // agent domain terminator
+        ];
+
+        assert_eq!(EdnsOption::read_all(buf),
+                   Ok(vec![ EdnsOption::ReportChannel(ReportChannel { agent_domain: Labels::encode("agent.dog").unwrap() }) ]));
+    }
+
+    #[test]
+    fn report_channel_wrong_length() {
+        let buf = &[
+            0x00, 0x12,  // option code
+            0x00, 0x06,  // option length
+            0x03, 0x64, 0x6f, 0x67,  // agent domain
+            0x00,  // agent domain terminator
+            0x00,  // something extra
+        ];
+
+        assert_eq!(EdnsOption::read_all(buf),
+                   Err(WireError::WrongLabelLength { stated_length: 6, length_after_labels: 5 }));
+    }
+
+    #[test]
+    fn serialises_extended_error() {
+        let ede = EdnsOption::ExtendedError(ExtendedError { info_code: 6, extra_text: b"bad".to_vec() });
+        assert_eq!(ede.to_bytes().unwrap(),
+                   vec![ 0x00, 0x0f, 0x00, 0x05, 0x00, 0x06, 0x62, 0x61, 0x64 ]);
+    }
+
+    #[test]
+    fn nsid_formats() {
+        let nsid = Nsid(vec![ 0x6c, 0x6f, 0x6e, 0xff ]);
+        assert_eq!(nsid.hex(), "6c6f6eff");
+        assert_eq!(nsid.text(), "lon\u{fffd}");
+    }
+
+    #[test]
+    fn parses_nothing() {
+        assert_eq!(EdnsOption::read_all(&[]),
+                   Ok(vec![]));
+    }
+
+    #[test]
+    fn extended_error_too_short() {
+        let buf = &[
+            0x00, 0x0f,  // option code
+            0x00, 0x01,  // option length
+            0x00,        // half an info code
+        ];
+
+        assert_eq!(EdnsOption::read_all(buf),
+                   Err(WireError::WrongRecordLength { stated_length: 1, mandated_length: MandatedLength::AtLeast(2) }));
+    }
+
+    #[test]
+    fn buffer_ends_abruptly() {
+        let buf = &[
+            0x00, 0x0f,  // option code
+            0x00, 0x06,  // option length
+            0x00,        // half an info code
+        ];
+
+        assert_eq!(EdnsOption::read_all(buf),
+                   Err(WireError::IO));
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(extended_error_name(6), Some("DNSSEC Bogus"));
+        assert_eq!(extended_error_name(999), None);
+    }
+}
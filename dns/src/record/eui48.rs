@@ -11,6 +11,7 @@ use crate::wire::*;
 /// - [RFC 7043](https://tools.ietf.org/html/rfc7043) — Resource Records for
 ///   EUI-48 and EUI-64 Addresses in the DNS (October 2013)
 #[derive(PartialEq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EUI48 {
 
     /// The six octets that make up the identifier.
@@ -35,6 +36,11 @@ impl Wire for EUI48 {
 
         Ok(Self { octets })
     }
+
+    fn write(&self, bytes: &mut Vec<u8>) -> io::Result<()> {
+        bytes.extend_from_slice(&self.octets);
+        Ok(())
+    }
 }
 
 
@@ -46,6 +52,12 @@ impl EUI48 {
                 self.octets[0], self.octets[1], self.octets[2],
                 self.octets[3], self.octets[4], self.octets[5])
     }
+
+    /// Returns the record’s data in presentation format, which is the
+    /// formatted address.
+    pub fn to_presentation(&self) -> String {
+        self.formatted_address()
+    }
 }
 
 
@@ -108,4 +120,16 @@ mod test {
         assert_eq!(record.formatted_address(),
                    "00-7f-23-12-34-56");
     }
+
+    #[test]
+    fn round_trip() {
+        let buf = &[
+            0x00, 0x7F, 0x23, 0x12, 0x34, 0x56,  // identifier
+        ];
+
+        let record = EUI48::read(buf.len() as _, &mut Cursor::new(buf)).unwrap();
+        let mut bytes = Vec::new();
+        record.write(&mut bytes).unwrap();
+        assert_eq!(bytes, buf);
+    }
 }
@@ -1,5 +1,6 @@
 use log::*;
 
+use crate::presentation::quoted;
 use crate::wire::*;
 
 
@@ -14,7 +15,8 @@ use crate::wire::*;
 ///   Implementation and Specification (November 1987)
 /// - [RFC 8482 §6](https://tools.ietf.org/html/rfc8482#section-6) — Providing
 ///   Minimal-Sized Responses to DNS Queries That Have QTYPE=ANY (January 2019)
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct HINFO {
 
     /// The CPU field, specifying the CPU type.
@@ -55,6 +57,20 @@ impl Wire for HINFO {
             Err(WireError::WrongLabelLength { stated_length, length_after_labels })
         }
     }
+
+    fn write(&self, bytes: &mut Vec<u8>) -> io::Result<()> {
+        write_character_string(bytes, &self.cpu)?;
+        write_character_string(bytes, &self.os)
+    }
+}
+
+impl HINFO {
+
+    /// Returns the record’s data in presentation format, as two quoted
+    /// strings.
+    pub fn to_presentation(&self) -> String {
+        format!("{} {}", quoted(&self.cpu), quoted(&self.os))
+    }
 }
 
 
@@ -109,4 +125,21 @@ mod test {
         assert_eq!(HINFO::read(23, &mut Cursor::new(buf)),
                    Err(WireError::IO));
     }
+
+    #[test]
+    fn round_trip() {
+        let buf = &[
+            0x0e,  // cpu length
+            0x73, 0x6f, 0x6d, 0x65, 0x2d, 0x6b, 0x69, 0x6e, 0x64, 0x61, 0x2d,
+            0x63, 0x70, 0x75,  // cpu
+            0x0d,  // os length
+            0x73, 0x6f, 0x6d, 0x65, 0x2d, 0x6b, 0x69, 0x6e, 0x64, 0x61, 0x2d,
+            0x6f, 0x73,  // os
+        ];
+
+        let record = HINFO::read(buf.len() as _, &mut Cursor::new(buf)).unwrap();
+        let mut bytes = Vec::new();
+        record.write(&mut bytes).unwrap();
+        assert_eq!(bytes, buf);
+    }
 }
@@ -0,0 +1,249 @@
+use std::convert::TryFrom;
+
+use log::*;
+
+use crate::presentation::{hex, name};
+use crate::strings::{Labels, ReadLabels, WriteLabels};
+use crate::wire::*;
+
+
+/// A **HIP** _(host identity protocol)_ record, which associates a host
+/// identity tag and public key with a domain, along with the rendezvous
+/// servers through which the host can be reached.
+///
+/// # References
+///
+/// - [RFC 8005](https://tools.ietf.org/html/rfc8005) — Host Identity
+///   Protocol (HIP) Domain Name System (DNS) Extension (October 2016)
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HIP {
+
+    /// The algorithm of the public key. This is a number with several
+    /// defined mappings.
+    pub algorithm: u8,
+
+    /// The host identity tag, as unencoded bytes.
+    pub hit: Vec<u8>,
+
+    /// The host identity public key, as unencoded bytes.
+    pub public_key: Vec<u8>,
+
+    /// The domain names of the rendezvous servers for this host, in order of
+    /// preference. This list may be empty.
+    pub rendezvous_servers: Vec<Labels>,
+}
+
+impl Wire for HIP {
+    const NAME: &'static str = "HIP";
+    const RR_TYPE: u16 = 55;
+
+    #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+    fn read(stated_length: u16, c: &mut Cursor<&[u8]>) -> Result<Self, WireError> {
+        let hit_length = c.read_u8()?;
+        trace!("Parsed HIT length -> {:?}", hit_length);
+
+        let algorithm = c.read_u8()?;
+        trace!("Parsed algorithm -> {:?}", algorithm);
+
+        let public_key_length = c.read_u16::<BigEndian>()?;
+        trace!("Parsed public key length -> {:?}", public_key_length);
+
+        let length_before_servers = 1 + 1 + 2 + u32::from(hit_length) + u32::from(public_key_length);
+        if u32::from(stated_length) < length_before_servers {
+            let mandated_length = MandatedLength::AtLeast(u16::try_from(length_before_servers).unwrap_or(u16::MAX));
+            return Err(WireError::WrongRecordLength { stated_length, mandated_length });
+        }
+
+        let mut hit = vec![0_u8; usize::from(hit_length)];
+        c.read_exact(&mut hit)?;
+        trace!("Parsed HIT -> {:#x?}", hit);
+
+        let mut public_key = vec![0_u8; usize::from(public_key_length)];
+        c.read_exact(&mut public_key)?;
+        trace!("Parsed public key -> {:#x?}", public_key);
+
+        // This cannot overflow, as it has been checked against the stated
+        // length, which is itself a u16.
+        let mut length_after_labels = 1 + 1 + 2 + u16::from(hit_length) + public_key_length;
+        let mut rendezvous_servers = Vec::new();
+
+        while length_after_labels < stated_length {
+            let (server, server_length) = c.read_labels()?;
+            trace!("Parsed rendezvous server -> {:?}", server);
+
+            rendezvous_servers.push(server);
+            length_after_labels = length_after_labels.saturating_add(server_length);
+        }
+
+        if stated_length == length_after_labels {
+            Ok(Self { algorithm, hit, public_key, rendezvous_servers })
+        }
+        else {
+            Err(WireError::WrongLabelLength { stated_length, length_after_labels })
+        }
+    }
+
+    fn write(&self, bytes: &mut Vec<u8>) -> io::Result<()> {
+        let hit_length = u8::try_from(self.hit.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "HIT is longer than 255 bytes"))?;
+        let public_key_length = u16::try_from(self.public_key.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "public key is longer than 65535 bytes"))?;
+
+        bytes.write_u8(hit_length)?;
+        bytes.write_u8(self.algorithm)?;
+        bytes.write_u16::<BigEndian>(public_key_length)?;
+        bytes.extend_from_slice(&self.hit);
+        bytes.extend_from_slice(&self.public_key);
+
+        for server in &self.rendezvous_servers {
+            bytes.write_labels(server)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl HIP {
+
+    /// Returns the hexadecimal representation of the host identity tag.
+    pub fn hex_hit(&self) -> String {
+        self.hit.iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
+    /// Returns the base64-encoded public key.
+    pub fn base64_public_key(&self) -> String {
+        base64::encode(&self.public_key)
+    }
+
+    /// Returns the record’s data in presentation format, with the HIT in
+    /// hexadecimal and the public key in base64, followed by any rendezvous
+    /// servers.
+    pub fn to_presentation(&self) -> String {
+        let mut parts = vec![
+            self.algorithm.to_string(),
+            hex(&self.hit),
+            self.base64_public_key(),
+        ];
+
+        parts.extend(self.rendezvous_servers.iter().map(name));
+        parts.join(" ")
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parses() {
+        let buf = &[
+            0x02,  // HIT length
+            0x02,  // algorithm
+            0x00, 0x03,  // public key length
+            0x20, 0x01,  // HIT
+            0x03, 0x01, 0x00,  // public key
+            0x03, 0x72, 0x76, 0x73, 0x00,  // rendezvous server
+            0x03, 0x64, 0x6f, 0x67, 0x00,  // rendezvous server
+        ];
+
+        assert_eq!(HIP::read(buf.len() as _, &mut Cursor::new(buf)).unwrap(),
+                   HIP {
+                       algorithm: 2,
+                       hit: vec![ 0x20, 0x01 ],
+                       public_key: vec![ 0x03, 0x01, 0x00 ],
+                       rendezvous_servers: vec![
+                           Labels::encode("rvs").unwrap(),
+                           Labels::encode("dog").unwrap(),
+                       ],
+                   });
+    }
+
+    #[test]
+    fn parses_without_servers() {
+        let buf = &[
+            0x01,  // HIT length
+            0x02,  // algorithm
+            0x00, 0x01,  // public key length
+            0x20,  // HIT
+            0x03,  // public key
+        ];
+
+        assert_eq!(HIP::read(buf.len() as _, &mut Cursor::new(buf)).unwrap(),
+                   HIP {
+                       algorithm: 2,
+                       hit: vec![ 0x20 ],
+                       public_key: vec![ 0x03 ],
+                       rendezvous_servers: vec![],
+                   });
+    }
+
+    #[test]
+    fn record_too_short() {
+        let buf = &[
+            0x04,  // HIT length
+            0x02,  // algorithm
+            0x00, 0x04,  // public key length
+            0x20, 0x01,  // HIT (partial)
+        ];
+
+        assert_eq!(HIP::read(buf.len() as _, &mut Cursor::new(buf)),
+                   Err(WireError::WrongRecordLength { stated_length: 6, mandated_length: MandatedLength::AtLeast(12) }));
+    }
+
+    #[test]
+    fn incorrect_server_length() {
+        let buf = &[
+            0x01,  // HIT length
+            0x02,  // algorithm
+            0x00, 0x01,  // public key length
+            0x20,  // HIT
+            0x03,  // public key
+            0x03, 0x72, 0x76, 0x73, 0x00,  // rendezvous server
+        ];
+
+        assert_eq!(HIP::read(8, &mut Cursor::new(buf)),
+                   Err(WireError::WrongLabelLength { stated_length: 8, length_after_labels: 11 }));
+    }
+
+    #[test]
+    fn record_empty() {
+        assert_eq!(HIP::read(0, &mut Cursor::new(&[])),
+                   Err(WireError::IO));
+    }
+
+    #[test]
+    fn buffer_ends_abruptly() {
+        let buf = &[
+            0x02,  // HIT length
+            0x02,  // algorithm
+            0x00, 0x03,  // public key length
+            0x20,  // half a HIT
+        ];
+
+        assert_eq!(HIP::read(23, &mut Cursor::new(buf)),
+                   Err(WireError::IO));
+    }
+
+    #[test]
+    fn round_trip() {
+        let buf = &[
+            0x02,  // HIT length
+            0x02,  // algorithm
+            0x00, 0x03,  // public key length
+            0x20, 0x01,  // HIT
+            0x03, 0x01, 0x00,  // public key
+            0x03, 0x72, 0x76, 0x73, 0x00,  // rendezvous server
+            0x03, 0x64, 0x6f, 0x67, 0x00,  // rendezvous server
+        ];
+
+        let record = HIP::read(buf.len() as _, &mut Cursor::new(buf)).unwrap();
+        let mut bytes = Vec::new();
+        record.write(&mut bytes).unwrap();
+        assert_eq!(bytes, buf);
+    }
+}
@@ -1,3 +1,4 @@
+use std::convert::TryFrom;
 use std::fmt;
 
 use log::*;
@@ -13,6 +14,7 @@ use crate::wire::*;
 /// - [RFC 1876](https://tools.ietf.org/html/rfc1876) — A Means for Expressing
 ///   Location Information in the Domain Name System (January 1996)
 #[derive(PartialEq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LOC {
 
     /// The diameter of a sphere enclosing the entity at the location, as a
@@ -21,11 +23,11 @@ pub struct LOC {
 
     /// The diameter of the “circle of error” that this location could be in,
     /// measured in centimetres.
-    pub horizontal_precision: u8,
+    pub horizontal_precision: Size,
 
     /// The amount of vertical space that this location could be in, measured
     /// in centimetres.
-    pub vertical_precision: u8,
+    pub vertical_precision: Size,
 
     /// The latitude of the centre of the sphere. If `None`, the packet
     /// parses, but the position is out of range.
@@ -42,6 +44,7 @@ pub struct LOC {
 
 /// A measure of size, in centimetres, represented by a base and an exponent.
 #[derive(PartialEq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Size {
     base: u8,
     power_of_ten: u8,
@@ -49,6 +52,7 @@ pub struct Size {
 
 /// A position on one of the world’s axes.
 #[derive(PartialEq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Position {
     degrees: u32,
     arcminutes: u32,
@@ -59,6 +63,7 @@ pub struct Position {
 
 /// A position on the vertical axis.
 #[derive(PartialEq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Altitude {
     metres: i64,
     centimetres: i64,
@@ -67,6 +72,7 @@ pub struct Altitude {
 /// One of the directions a position could be in, relative to the equator or
 /// prime meridian.
 #[derive(PartialEq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Direction {
     North,
     East,
@@ -99,11 +105,13 @@ impl Wire for LOC {
         let size = Size::from_u8(size_bits);
         trace!("Parsed size -> {:#08b} ({})", size_bits, size);
 
-        let horizontal_precision = c.read_u8()?;
-        trace!("Parsed horizontal precision -> {:?}", horizontal_precision);
+        let horizontal_precision_bits = c.read_u8()?;
+        let horizontal_precision = Size::from_u8(horizontal_precision_bits);
+        trace!("Parsed horizontal precision -> {:#08b} ({})", horizontal_precision_bits, horizontal_precision);
 
-        let vertical_precision = c.read_u8()?;
-        trace!("Parsed vertical precision -> {:?}", vertical_precision);
+        let vertical_precision_bits = c.read_u8()?;
+        let vertical_precision = Size::from_u8(vertical_precision_bits);
+        trace!("Parsed vertical precision -> {:#08b} ({})", vertical_precision_bits, vertical_precision);
 
         let latitude_num = c.read_u32::<BigEndian>()?;
         let latitude = Position::from_u32(latitude_num, true);
@@ -121,6 +129,42 @@ impl Wire for LOC {
             size, horizontal_precision, vertical_precision, latitude, longitude, altitude,
         })
     }
+
+    fn write(&self, bytes: &mut Vec<u8>) -> io::Result<()> {
+        let (latitude, longitude) = match (self.latitude, self.longitude) {
+            (Some(lat), Some(long))  => (lat, long),
+            _                        => return Err(io::Error::new(io::ErrorKind::InvalidInput, "LOC record has a position out of range")),
+        };
+
+        bytes.write_u8(0)?;  // version
+        bytes.write_u8(self.size.to_u8())?;
+        bytes.write_u8(self.horizontal_precision.to_u8())?;
+        bytes.write_u8(self.vertical_precision.to_u8())?;
+        bytes.write_u32::<BigEndian>(latitude.to_u32())?;
+        bytes.write_u32::<BigEndian>(longitude.to_u32())?;
+        bytes.write_u32::<BigEndian>(self.altitude.to_u32())?;
+        Ok(())
+    }
+}
+
+impl LOC {
+
+    /// Returns the record’s data in presentation format, which is the
+    /// latitude and longitude as degrees, minutes, and seconds, followed by
+    /// the altitude, size, and precisions in metres. A position that was out
+    /// of range is written as `-`, which cannot be read back in.
+    pub fn to_presentation(&self) -> String {
+        let position = |pos: Option<Position>| pos.map_or_else(|| "-".into(), Position::to_presentation);
+
+        format!("{} {} {} {} {} {}",
+            position(self.latitude),
+            position(self.longitude),
+            self.altitude.to_presentation(),
+            self.size,
+            self.horizontal_precision,
+            self.vertical_precision,
+        )
+    }
 }
 
 impl Size {
@@ -133,6 +177,21 @@ impl Size {
         let power_of_ten = input & 0b_0000_1111;
         Self { base, power_of_ten }
     }
+
+    fn to_u8(self) -> u8 {
+        (self.base << 4) | self.power_of_ten
+    }
+
+    /// Returns the size as a number of centimetres.
+    pub fn centimetres(self) -> u64 {
+        u64::from(self.base) * 10_u64.pow(u32::from(self.power_of_ten))
+    }
+
+    /// Returns the size as a number of metres.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn in_metres(self) -> f64 {
+        self.centimetres() as f64 / 100.0
+    }
 }
 
 impl Position {
@@ -177,6 +236,41 @@ impl Position {
             Some(pos)
         }
     }
+
+    fn to_u32(self) -> u32 {
+        let offset = ((self.degrees * 60 + self.arcminutes) * 60 + self.arcseconds) * 1000 + self.milliarcseconds;
+
+        match self.direction {
+            Direction::North | Direction::East  => 0x_8000_0000 + offset,
+            Direction::South | Direction::West  => 0x_8000_0000 - offset,
+        }
+    }
+
+    /// Returns the position as degrees, minutes, and seconds with three
+    /// decimal places, followed by its direction.
+    fn to_presentation(self) -> String {
+        format!("{} {} {}.{:03} {}",
+            self.degrees,
+            self.arcminutes,
+            self.arcseconds,
+            self.milliarcseconds,
+            self.direction,
+        )
+    }
+
+    /// Returns the position as a number of degrees, which is negative for
+    /// positions south of the equator or west of the prime meridian.
+    pub fn decimal_degrees(self) -> f64 {
+        let degrees = f64::from(self.degrees)
+                    + f64::from(self.arcminutes) / 60.0
+                    + f64::from(self.arcseconds) / 3600.0
+                    + f64::from(self.milliarcseconds) / 3_600_000.0;
+
+        match self.direction {
+            Direction::North | Direction::East  => degrees,
+            Direction::South | Direction::West  => -degrees,
+        }
+    }
 }
 
 impl Altitude {
@@ -187,12 +281,38 @@ impl Altitude {
         let centimetres = input % 100;
         Self { metres, centimetres }
     }
+
+    fn to_u32(self) -> u32 {
+        let input = self.metres * 100 + self.centimetres + 10_000_000;
+        u32::try_from(input).expect("Altitude out of range")
+    }
+
+    /// Returns the altitude in metres with two decimal places.
+    fn to_presentation(self) -> String {
+        let centimetres = self.metres * 100 + self.centimetres;
+        let sign = if centimetres < 0 { "-" } else { "" };
+        format!("{}{}.{:02}m", sign, centimetres.abs() / 100, centimetres.abs() % 100)
+    }
+
+    /// Returns the altitude as a number of metres above or below the GPS
+    /// reference spheroid.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn in_metres(self) -> f64 {
+        (self.metres * 100 + self.centimetres) as f64 / 100.0
+    }
 }
 
 
 impl fmt::Display for Size {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}e{}", self.base, self.power_of_ten)
+        // Sizes are printed in metres, the same as altitudes
+        let centimetres = self.centimetres();
+        if centimetres % 100 == 0 {
+            write!(f, "{}m", centimetres / 100)
+        }
+        else {
+            write!(f, "{}.{:02}m", centimetres / 100, centimetres % 100)
+        }
     }
 }
 
@@ -257,8 +377,8 @@ mod test {
         assert_eq!(LOC::read(buf.len() as _, &mut Cursor::new(buf)).unwrap(),
                    LOC {
                        size: Size { base: 3, power_of_ten: 2 },
-                       horizontal_precision: 0,
-                       vertical_precision: 0,
+                       horizontal_precision: Size { base: 0, power_of_ten: 0 },
+                       vertical_precision: Size { base: 0, power_of_ten: 0 },
                        latitude:  Position::from_u32(0x_8b_0d_2c_8c, true),
                        longitude: Position::from_u32(0x_7f_f8_fc_a5, false),
                        altitude:  Altitude::from_u32(0x_00_98_96_80),
@@ -319,6 +439,41 @@ mod test {
         assert_eq!(LOC::read(16, &mut Cursor::new(buf)),
                    Err(WireError::IO));
     }
+
+    #[test]
+    fn round_trip() {
+        let buf = &[
+            0x00,  // version
+            0x32,  // size,
+            0x00,  // horizontal precision
+            0x00,  // vertical precision
+            0x8b, 0x0d, 0x2c, 0x8c,  // latitude
+            0x7f, 0xf8, 0xfc, 0xa5,  // longitude
+            0x00, 0x98, 0x96, 0x80,  // altitude
+        ];
+
+        let record = LOC::read(buf.len() as _, &mut Cursor::new(buf)).unwrap();
+        let mut bytes = Vec::new();
+        record.write(&mut bytes).unwrap();
+        assert_eq!(bytes, buf);
+    }
+
+    #[test]
+    fn presentation() {
+        let buf = &[
+            0x00,  // version
+            0x32,  // size,
+            0x00,  // horizontal precision
+            0x00,  // vertical precision
+            0x8b, 0x0d, 0x2c, 0x8c,  // latitude
+            0x7f, 0xf8, 0xfc, 0xa5,  // longitude
+            0x00, 0x98, 0x7f, 0x10,  // altitude
+        ];
+
+        let record = LOC::read(buf.len() as _, &mut Cursor::new(buf)).unwrap();
+        assert_eq!(record.to_presentation(),
+                   "51 30 12.748 N 0 7 39.611 W -60.00m 3m 0m 0m");
+    }
 }
 
 
@@ -330,25 +485,37 @@ mod size_test {
     #[test]
     fn zeroes() {
         assert_eq!(Size::from_u8(0b_0000_0000).to_string(),
-                   String::from("0e0"));
+                   String::from("0m"));
     }
 
     #[test]
     fn ones() {
         assert_eq!(Size::from_u8(0b_0001_0001).to_string(),
-                   String::from("1e1"));
+                   String::from("0.10m"));
     }
 
     #[test]
     fn schfourteen_teen() {
         assert_eq!(Size::from_u8(0b_1110_0011).to_string(),
-                   String::from("14e3"));
+                   String::from("140m"));
     }
 
     #[test]
     fn ones_but_bits_this_time() {
         assert_eq!(Size::from_u8(0b_1111_1111).to_string(),
-                   String::from("15e15"));
+                   String::from("150000000000000m"));
+    }
+
+    #[test]
+    fn centimetres() {
+        assert_eq!(Size::from_u8(0b_0011_0010).centimetres(),
+                   300);
+    }
+
+    #[test]
+    fn in_metres() {
+        assert_eq!(Size::from_u8(0b_0001_0110).in_metres().to_string(),
+                   String::from("10000"));
     }
 }
 
@@ -410,6 +577,26 @@ mod position_test {
                    String::from("0°7′39.611″ W"));
     }
 
+    // decimal degree tests
+
+    #[test]
+    fn some_latitude_in_degrees() {
+        assert_eq!(format!("{:.6}", Position::from_u32(2332896396, true).unwrap().decimal_degrees()),
+                   String::from("51.503541"));
+    }
+
+    #[test]
+    fn some_longitude_in_degrees() {
+        assert_eq!(format!("{:.6}", Position::from_u32(2147024037, false).unwrap().decimal_degrees()),
+                   String::from("-0.127670"));
+    }
+
+    #[test]
+    fn the_far_west_in_degrees() {
+        assert_eq!(Position::from_u32(0x8000_0000 - (1000 * 60 * 60 * 180), false).unwrap().decimal_degrees().to_string(),
+                   String::from("-180"));
+    }
+
     // limit tests
 
     #[test]
@@ -490,4 +677,16 @@ mod altitude_test {
         assert_eq!(Altitude::from_u32(50505050).to_string(),
                    String::from("405050.50m"));
     }
+
+    #[test]
+    fn in_metres() {
+        assert_eq!(Altitude::from_u32(50505050).in_metres().to_string(),
+                   String::from("405050.5"));
+    }
+
+    #[test]
+    fn below_in_metres() {
+        assert_eq!(Altitude::from_u32(9_999_950).in_metres().to_string(),
+                   String::from("-0.5"));
+    }
 }
@@ -1,5 +1,8 @@
-//! All the DNS record types, as well as how to parse each type.
+//! All the DNS record types, as well as how to parse and write each type.
 
+use std::net::IpAddr;
+
+use crate::strings::Labels;
 use crate::wire::*;
 
 
@@ -9,12 +12,27 @@ pub use self::a::A;
 mod aaaa;
 pub use self::aaaa::AAAA;
 
+mod amtrelay;
+pub use self::amtrelay::{AMTRELAY, Relay};
+
 mod caa;
-pub use self::caa::CAA;
+pub use self::caa::{CAA, CaaProperty, IssueValue};
 
 mod cname;
 pub use self::cname::CNAME;
 
+mod dnskey;
+pub use self::dnskey::DNSKEY;
+
+mod doa;
+pub use self::doa::DOA;
+
+mod ds;
+pub use self::ds::{DS, TA, DLV};
+
+mod edns;
+pub use self::edns::{EdnsOption, Nsid, Cookie, ClientSubnet, Expire, TcpKeepalive, Padding, Chain, ExtendedError, ReportChannel, extended_error_name, edns_option_name};
+
 mod eui48;
 pub use self::eui48::EUI48;
 
@@ -24,6 +42,9 @@ pub use self::eui64::EUI64;
 mod hinfo;
 pub use self::hinfo::HINFO;
 
+mod hip;
+pub use self::hip::HIP;
+
 mod loc;
 pub use self::loc::LOC;
 
@@ -31,11 +52,19 @@ mod mx;
 pub use self::mx::MX;
 
 mod naptr;
-pub use self::naptr::NAPTR;
+pub use self::naptr::{NAPTR, Substitution, RegexError, enum_number};
 
 mod ns;
 pub use self::ns::NS;
 
+mod nsec;
+pub use self::nsec::NSEC;
+
+mod nsec3;
+pub use self::nsec3::{NSEC3, NSEC3PARAM};
+#[cfg(feature = "with_dnssec")]
+pub(crate) use self::nsec3::base32hex;
+
 mod openpgpkey;
 pub use self::openpgpkey::OPENPGPKEY;
 
@@ -45,6 +74,9 @@ pub use self::opt::OPT;
 mod ptr;
 pub use self::ptr::PTR;
 
+mod rrsig;
+pub use self::rrsig::RRSIG;
+
 mod sshfp;
 pub use self::sshfp::SSHFP;
 
@@ -54,11 +86,14 @@ pub use self::soa::SOA;
 mod srv;
 pub use self::srv::SRV;
 
+mod svcb;
+pub use self::svcb::{SVCB, HTTPS, SvcParam, EchConfigList, EchConfig, EchConfigContents, HpkeCipherSuite, svc_param_key_name};
+
 mod tlsa;
 pub use self::tlsa::TLSA;
 
 mod txt;
-pub use self::txt::TXT;
+pub use self::txt::{TXT, TextDecoding, DecodedMessage};
 
 mod uri;
 pub use self::uri::URI;
@@ -69,26 +104,40 @@ pub use self::others::UnknownQtype;
 
 
 /// A record that’s been parsed from a byte buffer.
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(missing_docs)]
 pub enum Record {
     A(A),
     AAAA(AAAA),
+    AMTRELAY(AMTRELAY),
     CAA(CAA),
     CNAME(CNAME),
+    DLV(DLV),
+    DNSKEY(DNSKEY),
+    DOA(DOA),
+    DS(DS),
     EUI48(EUI48),
     EUI64(EUI64),
     HINFO(HINFO),
+    HIP(HIP),
+    HTTPS(HTTPS),
     LOC(LOC),
     MX(MX),
     NAPTR(NAPTR),
     NS(NS),
+    NSEC(NSEC),
+    NSEC3(NSEC3),
+    NSEC3PARAM(NSEC3PARAM),
     OPENPGPKEY(OPENPGPKEY),
     // OPT is not included here.
     PTR(PTR),
+    RRSIG(RRSIG),
     SSHFP(SSHFP),
     SOA(SOA),
     SRV(SRV),
+    SVCB(SVCB),
+    TA(TA),
     TLSA(TLSA),
     TXT(TXT),
     URI(URI),
@@ -108,24 +157,38 @@ pub enum Record {
 /// The type of a record that may or may not be one of the known ones. Has no
 /// data associated with it other than what type of record it is.
 #[derive(PartialEq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(missing_docs)]
 pub enum RecordType {
     A,
     AAAA,
+    AMTRELAY,
     CAA,
     CNAME,
+    DLV,
+    DNSKEY,
+    DOA,
+    DS,
     EUI48,
     EUI64,
     HINFO,
+    HIP,
+    HTTPS,
     LOC,
     MX,
     NAPTR,
     NS,
+    NSEC,
+    NSEC3,
+    NSEC3PARAM,
     OPENPGPKEY,
     PTR,
+    RRSIG,
     SSHFP,
     SOA,
     SRV,
+    SVCB,
+    TA,
     TLSA,
     TXT,
     URI,
@@ -146,21 +209,34 @@ impl From<u16> for RecordType {
 
         try_record!(A);
         try_record!(AAAA);
+        try_record!(AMTRELAY);
         try_record!(CAA);
         try_record!(CNAME);
+        try_record!(DLV);
+        try_record!(DNSKEY);
+        try_record!(DOA);
+        try_record!(DS);
         try_record!(EUI48);
         try_record!(EUI64);
         try_record!(HINFO);
+        try_record!(HIP);
+        try_record!(HTTPS);
         try_record!(LOC);
         try_record!(MX);
         try_record!(NAPTR);
         try_record!(NS);
+        try_record!(NSEC);
+        try_record!(NSEC3);
+        try_record!(NSEC3PARAM);
         try_record!(OPENPGPKEY);
         // OPT is handled separately
         try_record!(PTR);
+        try_record!(RRSIG);
         try_record!(SSHFP);
         try_record!(SOA);
         try_record!(SRV);
+        try_record!(SVCB);
+        try_record!(TA);
         try_record!(TLSA);
         try_record!(TXT);
         try_record!(URI);
@@ -170,6 +246,207 @@ impl From<u16> for RecordType {
 }
 
 
+impl Record {
+
+    /// Returns the type of this record.
+    pub fn record_type(&self) -> RecordType {
+        match self {
+            Self::A(_)          => RecordType::A,
+            Self::AAAA(_)       => RecordType::AAAA,
+            Self::AMTRELAY(_)   => RecordType::AMTRELAY,
+            Self::CAA(_)        => RecordType::CAA,
+            Self::CNAME(_)      => RecordType::CNAME,
+            Self::DLV(_)        => RecordType::DLV,
+            Self::DNSKEY(_)     => RecordType::DNSKEY,
+            Self::DOA(_)        => RecordType::DOA,
+            Self::DS(_)         => RecordType::DS,
+            Self::EUI48(_)      => RecordType::EUI48,
+            Self::EUI64(_)      => RecordType::EUI64,
+            Self::HINFO(_)      => RecordType::HINFO,
+            Self::HIP(_)        => RecordType::HIP,
+            Self::HTTPS(_)      => RecordType::HTTPS,
+            Self::LOC(_)        => RecordType::LOC,
+            Self::MX(_)         => RecordType::MX,
+            Self::NAPTR(_)      => RecordType::NAPTR,
+            Self::NS(_)         => RecordType::NS,
+            Self::NSEC(_)       => RecordType::NSEC,
+            Self::NSEC3(_)      => RecordType::NSEC3,
+            Self::NSEC3PARAM(_) => RecordType::NSEC3PARAM,
+            Self::OPENPGPKEY(_) => RecordType::OPENPGPKEY,
+            Self::PTR(_)        => RecordType::PTR,
+            Self::RRSIG(_)      => RecordType::RRSIG,
+            Self::SSHFP(_)      => RecordType::SSHFP,
+            Self::SOA(_)        => RecordType::SOA,
+            Self::SRV(_)        => RecordType::SRV,
+            Self::SVCB(_)       => RecordType::SVCB,
+            Self::TA(_)         => RecordType::TA,
+            Self::TLSA(_)       => RecordType::TLSA,
+            Self::TXT(_)        => RecordType::TXT,
+            Self::URI(_)        => RecordType::URI,
+            Self::Other { type_number, .. }  => RecordType::Other(*type_number),
+        }
+    }
+
+    /// Returns the number of this record’s type.
+    pub fn type_number(&self) -> u16 {
+        self.record_type().type_number()
+    }
+
+    /// Returns the name of this record’s type, or `None` if it is of an
+    /// unknown type that does not have one.
+    pub fn type_name(&self) -> Option<&'static str> {
+        match self.record_type() {
+            RecordType::A           => Some(A::NAME),
+            RecordType::AAAA        => Some(AAAA::NAME),
+            RecordType::AMTRELAY    => Some(AMTRELAY::NAME),
+            RecordType::CAA         => Some(CAA::NAME),
+            RecordType::CNAME       => Some(CNAME::NAME),
+            RecordType::DLV         => Some(DLV::NAME),
+            RecordType::DNSKEY      => Some(DNSKEY::NAME),
+            RecordType::DOA         => Some(DOA::NAME),
+            RecordType::DS          => Some(DS::NAME),
+            RecordType::EUI48       => Some(EUI48::NAME),
+            RecordType::EUI64       => Some(EUI64::NAME),
+            RecordType::HINFO       => Some(HINFO::NAME),
+            RecordType::HIP         => Some(HIP::NAME),
+            RecordType::HTTPS       => Some(HTTPS::NAME),
+            RecordType::LOC         => Some(LOC::NAME),
+            RecordType::MX          => Some(MX::NAME),
+            RecordType::NAPTR       => Some(NAPTR::NAME),
+            RecordType::NS          => Some(NS::NAME),
+            RecordType::NSEC        => Some(NSEC::NAME),
+            RecordType::NSEC3       => Some(NSEC3::NAME),
+            RecordType::NSEC3PARAM  => Some(NSEC3PARAM::NAME),
+            RecordType::OPENPGPKEY  => Some(OPENPGPKEY::NAME),
+            RecordType::PTR         => Some(PTR::NAME),
+            RecordType::RRSIG       => Some(RRSIG::NAME),
+            RecordType::SSHFP       => Some(SSHFP::NAME),
+            RecordType::SOA         => Some(SOA::NAME),
+            RecordType::SRV         => Some(SRV::NAME),
+            RecordType::SVCB        => Some(SVCB::NAME),
+            RecordType::TA          => Some(TA::NAME),
+            RecordType::TLSA        => Some(TLSA::NAME),
+            RecordType::TXT         => Some(TXT::NAME),
+            RecordType::URI         => Some(URI::NAME),
+            RecordType::Other(UnknownQtype::HeardOf(name, _))  => Some(name),
+            RecordType::Other(UnknownQtype::UnheardOf(_))      => None,
+        }
+    }
+
+    /// Returns the IP address in this record, if it is an A or AAAA record.
+    pub fn as_address(&self) -> Option<IpAddr> {
+        match self {
+            Self::A(a)        => Some(IpAddr::V4(a.address)),
+            Self::AAAA(aaaa)  => Some(IpAddr::V6(aaaa.address)),
+            _                 => None,
+        }
+    }
+
+    /// Returns the domain name this record points to, if it is of a type
+    /// that points somewhere else: the canonical name of a CNAME, the host
+    /// of an MX, NS, PTR, SRV, SVCB, or HTTPS record, the replacement of a
+    /// NAPTR, or the relay of an AMTRELAY that has a domain name as its relay.
+    pub fn target_name(&self) -> Option<&Labels> {
+        match self {
+            Self::AMTRELAY(AMTRELAY { relay: Relay::Domain(domain), .. })  => Some(domain),
+            Self::CNAME(cname)   => Some(&cname.domain),
+            Self::HTTPS(https)   => Some(&https.0.target),
+            Self::MX(mx)         => Some(&mx.exchange),
+            Self::NAPTR(naptr)   => Some(&naptr.replacement),
+            Self::NS(ns)         => Some(&ns.nameserver),
+            Self::PTR(ptr)       => Some(&ptr.cname),
+            Self::SRV(srv)       => Some(&srv.target),
+            Self::SVCB(svcb)     => Some(&svcb.target),
+            _                    => None,
+        }
+    }
+
+    /// Writes this record’s data to the end of the given buffer, in the form
+    /// it would be read from. Records of unknown types have their bytes
+    /// written back out as they are.
+    pub fn write(&self, bytes: &mut Vec<u8>) -> io::Result<()> {
+        match self {
+            Self::A(r)          => r.write(bytes),
+            Self::AAAA(r)       => r.write(bytes),
+            Self::AMTRELAY(r)   => r.write(bytes),
+            Self::CAA(r)        => r.write(bytes),
+            Self::CNAME(r)      => r.write(bytes),
+            Self::DLV(r)        => r.write(bytes),
+            Self::DNSKEY(r)     => r.write(bytes),
+            Self::DOA(r)        => r.write(bytes),
+            Self::DS(r)         => r.write(bytes),
+            Self::EUI48(r)      => r.write(bytes),
+            Self::EUI64(r)      => r.write(bytes),
+            Self::HINFO(r)      => r.write(bytes),
+            Self::HIP(r)        => r.write(bytes),
+            Self::HTTPS(r)      => r.write(bytes),
+            Self::LOC(r)        => r.write(bytes),
+            Self::MX(r)         => r.write(bytes),
+            Self::NAPTR(r)      => r.write(bytes),
+            Self::NS(r)         => r.write(bytes),
+            Self::NSEC(r)       => r.write(bytes),
+            Self::NSEC3(r)      => r.write(bytes),
+            Self::NSEC3PARAM(r) => r.write(bytes),
+            Self::OPENPGPKEY(r) => r.write(bytes),
+            Self::PTR(r)        => r.write(bytes),
+            Self::RRSIG(r)      => r.write(bytes),
+            Self::SSHFP(r)      => r.write(bytes),
+            Self::SOA(r)        => r.write(bytes),
+            Self::SRV(r)        => r.write(bytes),
+            Self::SVCB(r)       => r.write(bytes),
+            Self::TA(r)         => r.write(bytes),
+            Self::TLSA(r)       => r.write(bytes),
+            Self::TXT(r)        => r.write(bytes),
+            Self::URI(r)        => r.write(bytes),
+            Self::Other { bytes: data, .. }  => {
+                bytes.extend_from_slice(data);
+                Ok(())
+            }
+        }
+    }
+
+    /// Returns this record’s data in presentation format, as it would be
+    /// written in a zone file or printed by dig. Records of unknown types
+    /// are written in the generic format from RFC 3597.
+    pub fn to_presentation(&self) -> String {
+        match self {
+            Self::A(r)          => r.to_presentation(),
+            Self::AAAA(r)       => r.to_presentation(),
+            Self::AMTRELAY(r)   => r.to_presentation(),
+            Self::CAA(r)        => r.to_presentation(),
+            Self::CNAME(r)      => r.to_presentation(),
+            Self::DLV(r)        => r.to_presentation(),
+            Self::DNSKEY(r)     => r.to_presentation(),
+            Self::DOA(r)        => r.to_presentation(),
+            Self::DS(r)         => r.to_presentation(),
+            Self::EUI48(r)      => r.to_presentation(),
+            Self::EUI64(r)      => r.to_presentation(),
+            Self::HINFO(r)      => r.to_presentation(),
+            Self::HIP(r)        => r.to_presentation(),
+            Self::HTTPS(r)      => r.to_presentation(),
+            Self::LOC(r)        => r.to_presentation(),
+            Self::MX(r)         => r.to_presentation(),
+            Self::NAPTR(r)      => r.to_presentation(),
+            Self::NS(r)         => r.to_presentation(),
+            Self::NSEC(r)       => r.to_presentation(),
+            Self::NSEC3(r)      => r.to_presentation(),
+            Self::NSEC3PARAM(r) => r.to_presentation(),
+            Self::OPENPGPKEY(r) => r.to_presentation(),
+            Self::PTR(r)        => r.to_presentation(),
+            Self::RRSIG(r)      => r.to_presentation(),
+            Self::SSHFP(r)      => r.to_presentation(),
+            Self::SOA(r)        => r.to_presentation(),
+            Self::SRV(r)        => r.to_presentation(),
+            Self::SVCB(r)       => r.to_presentation(),
+            Self::TA(r)         => r.to_presentation(),
+            Self::TLSA(r)       => r.to_presentation(),
+            Self::TXT(r)        => r.to_presentation(),
+            Self::URI(r)        => r.to_presentation(),
+            Self::Other { bytes, .. }  => crate::presentation::generic(bytes),
+        }
+    }
+}
+
 impl RecordType {
 
     /// Determines the record type with a given name, or `None` if none is
@@ -185,21 +462,34 @@ impl RecordType {
 
         try_record!(A);
         try_record!(AAAA);
+        try_record!(AMTRELAY);
         try_record!(CAA);
         try_record!(CNAME);
+        try_record!(DLV);
+        try_record!(DNSKEY);
+        try_record!(DOA);
+        try_record!(DS);
         try_record!(EUI48);
         try_record!(EUI64);
         try_record!(HINFO);
+        try_record!(HIP);
+        try_record!(HTTPS);
         try_record!(LOC);
         try_record!(MX);
         try_record!(NAPTR);
         try_record!(NS);
+        try_record!(NSEC);
+        try_record!(NSEC3);
+        try_record!(NSEC3PARAM);
         try_record!(OPENPGPKEY);
         // OPT is elsewhere
         try_record!(PTR);
+        try_record!(RRSIG);
         try_record!(SSHFP);
         try_record!(SOA);
         try_record!(SRV);
+        try_record!(SVCB);
+        try_record!(TA);
         try_record!(TLSA);
         try_record!(TXT);
         try_record!(URI);
@@ -207,32 +497,142 @@ impl RecordType {
         UnknownQtype::from_type_name(type_name).map(Self::Other)
     }
 
+    /// Returns every record type that dog knows how to parse, in
+    /// alphabetical order.
+    pub fn known_types() -> &'static [Self] {
+        &[
+            Self::A, Self::AAAA, Self::AMTRELAY, Self::CAA, Self::CNAME,
+            Self::DLV, Self::DNSKEY, Self::DOA, Self::DS, Self::EUI48, Self::EUI64,
+            Self::HINFO, Self::HIP, Self::HTTPS, Self::LOC, Self::MX,
+            Self::NAPTR, Self::NS, Self::NSEC, Self::NSEC3, Self::NSEC3PARAM,
+            Self::OPENPGPKEY, Self::PTR, Self::RRSIG, Self::SSHFP, Self::SOA,
+            Self::SRV, Self::SVCB, Self::TA, Self::TLSA, Self::TXT, Self::URI,
+        ]
+    }
+
     /// Returns the record type number associated with this record type.
     pub fn type_number(self) -> u16 {
         match self {
             Self::A           => A::RR_TYPE,
             Self::AAAA        => AAAA::RR_TYPE,
+            Self::AMTRELAY    => AMTRELAY::RR_TYPE,
             Self::CAA         => CAA::RR_TYPE,
             Self::CNAME       => CNAME::RR_TYPE,
+            Self::DLV         => DLV::RR_TYPE,
+            Self::DNSKEY      => DNSKEY::RR_TYPE,
+            Self::DOA         => DOA::RR_TYPE,
+            Self::DS          => DS::RR_TYPE,
             Self::EUI48       => EUI48::RR_TYPE,
             Self::EUI64       => EUI64::RR_TYPE,
             Self::HINFO       => HINFO::RR_TYPE,
+            Self::HIP         => HIP::RR_TYPE,
+            Self::HTTPS       => HTTPS::RR_TYPE,
             Self::LOC         => LOC::RR_TYPE,
             Self::MX          => MX::RR_TYPE,
             Self::NAPTR       => NAPTR::RR_TYPE,
             Self::NS          => NS::RR_TYPE,
+            Self::NSEC        => NSEC::RR_TYPE,
+            Self::NSEC3       => NSEC3::RR_TYPE,
+            Self::NSEC3PARAM  => NSEC3PARAM::RR_TYPE,
             Self::OPENPGPKEY  => OPENPGPKEY::RR_TYPE,
             // Wherefore art thou, OPT
             Self::PTR         => PTR::RR_TYPE,
+            Self::RRSIG       => RRSIG::RR_TYPE,
             Self::SSHFP       => SSHFP::RR_TYPE,
             Self::SOA         => SOA::RR_TYPE,
             Self::SRV         => SRV::RR_TYPE,
+            Self::SVCB        => SVCB::RR_TYPE,
+            Self::TA          => TA::RR_TYPE,
             Self::TLSA        => TLSA::RR_TYPE,
             Self::TXT         => TXT::RR_TYPE,
             Self::URI         => URI::RR_TYPE,
             Self::Other(o)    => o.type_number(),
         }
     }
+
+    /// Returns the mnemonic for this record type, as it appears in zone
+    /// files. Types without a name are written as `TYPE` followed by their
+    /// number, as RFC 3597 describes.
+    pub fn mnemonic(self) -> String {
+        match self {
+            Self::A           => A::NAME.into(),
+            Self::AAAA        => AAAA::NAME.into(),
+            Self::AMTRELAY    => AMTRELAY::NAME.into(),
+            Self::CAA         => CAA::NAME.into(),
+            Self::CNAME       => CNAME::NAME.into(),
+            Self::DLV         => DLV::NAME.into(),
+            Self::DNSKEY      => DNSKEY::NAME.into(),
+            Self::DOA         => DOA::NAME.into(),
+            Self::DS          => DS::NAME.into(),
+            Self::EUI48       => EUI48::NAME.into(),
+            Self::EUI64       => EUI64::NAME.into(),
+            Self::HINFO       => HINFO::NAME.into(),
+            Self::HIP         => HIP::NAME.into(),
+            Self::HTTPS       => HTTPS::NAME.into(),
+            Self::LOC         => LOC::NAME.into(),
+            Self::MX          => MX::NAME.into(),
+            Self::NAPTR       => NAPTR::NAME.into(),
+            Self::NS          => NS::NAME.into(),
+            Self::NSEC        => NSEC::NAME.into(),
+            Self::NSEC3       => NSEC3::NAME.into(),
+            Self::NSEC3PARAM  => NSEC3PARAM::NAME.into(),
+            Self::OPENPGPKEY  => OPENPGPKEY::NAME.into(),
+            Self::PTR         => PTR::NAME.into(),
+            Self::RRSIG       => RRSIG::NAME.into(),
+            Self::SSHFP       => SSHFP::NAME.into(),
+            Self::SOA         => SOA::NAME.into(),
+            Self::SRV         => SRV::NAME.into(),
+            Self::SVCB        => SVCB::NAME.into(),
+            Self::TA          => TA::NAME.into(),
+            Self::TLSA        => TLSA::NAME.into(),
+            Self::TXT         => TXT::NAME.into(),
+            Self::URI         => URI::NAME.into(),
+            Self::Other(UnknownQtype::HeardOf(name, _))  => name.into(),
+            Self::Other(UnknownQtype::UnheardOf(num))    => format!("TYPE{}", num),
+        }
+    }
 }
 
 // This code is really repetitive, I know, I know
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn addresses() {
+        let a = Record::A(A { address: "192.0.2.1".parse().unwrap() });
+        assert_eq!(a.as_address(), Some("192.0.2.1".parse().unwrap()));
+
+        let aaaa = Record::AAAA(AAAA { address: "2001:db8::1".parse().unwrap() });
+        assert_eq!(aaaa.as_address(), Some("2001:db8::1".parse().unwrap()));
+
+        let mx = Record::MX(MX { preference: 10, exchange: Labels::encode("mail.lookup.dog").unwrap() });
+        assert_eq!(mx.as_address(), None);
+    }
+
+    #[test]
+    fn target_names() {
+        let cname = Record::CNAME(CNAME { domain: Labels::encode("lookup.dog").unwrap() });
+        assert_eq!(cname.target_name(), Some(&Labels::encode("lookup.dog").unwrap()));
+
+        let a = Record::A(A { address: "192.0.2.1".parse().unwrap() });
+        assert_eq!(a.target_name(), None);
+    }
+
+    #[test]
+    fn type_names() {
+        let a = Record::A(A { address: "192.0.2.1".parse().unwrap() });
+        assert_eq!(a.type_name(), Some("A"));
+        assert_eq!(a.type_number(), 1);
+
+        let heard_of = Record::Other { type_number: UnknownQtype::from(39), bytes: Vec::new() };
+        assert_eq!(heard_of.type_name(), Some("DNAME"));
+
+        let unheard_of = Record::Other { type_number: UnknownQtype::from(65280), bytes: Vec::new() };
+        assert_eq!(unheard_of.type_name(), None);
+        assert_eq!(unheard_of.type_number(), 65280);
+    }
+}
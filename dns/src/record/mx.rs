@@ -1,6 +1,7 @@
 use log::*;
 
-use crate::strings::{Labels, ReadLabels};
+use crate::presentation::name;
+use crate::strings::{Labels, ReadLabels, WriteLabels};
 use crate::wire::*;
 
 
@@ -11,7 +12,8 @@ use crate::wire::*;
 ///
 /// - [RFC 1035 §3.3.9](https://tools.ietf.org/html/rfc1035) — Domain Names,
 ///   Implementation and Specification (November 1987)
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MX {
 
     /// The preference that clients should give to this MX record amongst all
@@ -44,6 +46,20 @@ impl Wire for MX {
             Err(WireError::WrongLabelLength { stated_length, length_after_labels })
         }
     }
+
+    fn write(&self, bytes: &mut Vec<u8>) -> io::Result<()> {
+        bytes.write_u16::<BigEndian>(self.preference)?;
+        bytes.write_labels(&self.exchange)
+    }
+}
+
+impl MX {
+
+    /// Returns the record’s data in presentation format, which is the
+    /// preference followed by the exchange.
+    pub fn to_presentation(&self) -> String {
+        format!("{} {}", self.preference, name(&self.exchange))
+    }
 }
 
 
@@ -94,4 +110,18 @@ mod test {
         assert_eq!(MX::read(23, &mut Cursor::new(buf)),
                    Err(WireError::IO));
     }
+
+    #[test]
+    fn round_trip() {
+        let buf = &[
+            0x00, 0x0A,  // preference
+            0x05, 0x62, 0x73, 0x61, 0x67, 0x6f, 0x02, 0x6d, 0x65,  // exchange
+            0x00,  // exchange terminator
+        ];
+
+        let record = MX::read(buf.len() as _, &mut Cursor::new(buf)).unwrap();
+        let mut bytes = Vec::new();
+        record.write(&mut bytes).unwrap();
+        assert_eq!(bytes, buf);
+    }
 }
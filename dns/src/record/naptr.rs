@@ -1,6 +1,7 @@
 use log::*;
 
-use crate::strings::{Labels, ReadLabels};
+use crate::presentation::{name, quoted};
+use crate::strings::{Labels, ReadLabels, WriteLabels};
 use crate::wire::*;
 
 
@@ -12,7 +13,11 @@ use crate::wire::*;
 /// - [RFC 3403](https://tools.ietf.org/html/rfc3403) — Dynamic Delegation
 ///   Discovery System (DDDS) Part Three: The Domain Name System (DNS) Database
 ///   (October 2002)
-#[derive(PartialEq, Debug)]
+/// - [RFC 6116](https://tools.ietf.org/html/rfc6116) — The E.164 to Uniform
+///   Resource Identifiers (URI) Dynamic Delegation Discovery System (DDDS)
+///   Application (ENUM) (March 2011)
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct NAPTR {
 
     /// The order in which NAPTR records must be processed.
@@ -88,6 +93,158 @@ impl Wire for NAPTR {
             Err(WireError::WrongLabelLength { stated_length, length_after_labels })
         }
     }
+
+    fn write(&self, bytes: &mut Vec<u8>) -> io::Result<()> {
+        bytes.write_u16::<BigEndian>(self.order)?;
+        bytes.write_u16::<BigEndian>(self.preference)?;
+        write_character_string(bytes, &self.flags)?;
+        write_character_string(bytes, &self.service)?;
+        write_character_string(bytes, &self.regex)?;
+        bytes.write_labels(&self.replacement)
+    }
+}
+
+impl NAPTR {
+
+    /// Splits the regex field into its parts, checking that it follows the
+    /// substitution expression syntax. Returns `None` if the field is empty,
+    /// which is allowed when the replacement field is used instead.
+    pub fn substitution(&self) -> Result<Option<Substitution>, RegexError> {
+        if self.regex.is_empty() {
+            return Ok(None);
+        }
+
+        if self.replacement != Labels::root() {
+            return Err(RegexError::BothRegexAndReplacement);
+        }
+
+        let regex = std::str::from_utf8(&self.regex).map_err(|_| RegexError::NotUtf8)?;
+        let mut chars = regex.chars();
+
+        let delimiter = chars.next().expect("regex is not empty");
+        if delimiter.is_ascii_digit() || delimiter == '\\' || delimiter == 'i' {
+            return Err(RegexError::BadDelimiter(delimiter));
+        }
+
+        let mut parts = vec![ String::new() ];
+        let mut escaped = false;
+        for c in chars {
+            if escaped {
+                escaped = false;
+            }
+            else if c == '\\' {
+                escaped = true;
+            }
+            else if c == delimiter {
+                parts.push(String::new());
+                continue;
+            }
+
+            parts.last_mut().expect("parts is not empty").push(c);
+        }
+
+        if parts.len() != 3 {
+            return Err(RegexError::WrongDelimiterCount(parts.len()));
+        }
+
+        let flags = parts.pop().expect("three parts");
+        if let Some(flag) = flags.chars().find(|f| *f != 'i') {
+            return Err(RegexError::UnknownFlag(flag));
+        }
+
+        let replacement = parts.pop().expect("two parts");
+        let pattern = parts.pop().expect("one part");
+        Ok(Some(Substitution { pattern, replacement, case_insensitive: ! flags.is_empty() }))
+    }
+
+    /// Returns the ENUM services listed in the service field, such as `sip`
+    /// or `voice:tel`, if this is an ENUM record. These are only meaningful
+    /// for records under `e164.arpa`.
+    pub fn enum_services(&self) -> Option<Vec<String>> {
+        let service = std::str::from_utf8(&self.service).ok()?;
+        let mut parts = service.split('+');
+
+        if ! parts.next()?.eq_ignore_ascii_case("E2U") {
+            return None;
+        }
+
+        let services = parts.map(str::to_ascii_lowercase).collect::<Vec<_>>();
+        if services.is_empty() || services.iter().any(String::is_empty) {
+            return None;
+        }
+
+        Some(services)
+    }
+
+    /// Returns the record’s data in presentation format, with the flags,
+    /// services, and regular expression as quoted strings.
+    pub fn to_presentation(&self) -> String {
+        format!("{} {} {} {} {} {}",
+            self.order,
+            self.preference,
+            quoted(&self.flags),
+            quoted(&self.service),
+            quoted(&self.regex),
+            name(&self.replacement),
+        )
+    }
+}
+
+/// Returns the telephone number that a domain name under `e164.arpa`
+/// represents, with its digits put back the right way round, or `None` if
+/// the name is not an ENUM name.
+pub fn enum_number(name: &Labels) -> Option<String> {
+    let name = name.to_string().to_ascii_lowercase();
+    let digits = name.strip_suffix("e164.arpa.")?;
+
+    let mut number = String::from("+");
+    for digit in digits.split('.').rev().filter(|d| ! d.is_empty()) {
+        if digit.len() != 1 || ! digit.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+
+        number.push_str(digit);
+    }
+
+    if number.len() > 1 { Some(number) } else { None }
+}
+
+/// The parts of a valid substitution expression in the regex field.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Substitution {
+
+    /// The extended regular expression to match.
+    pub pattern: String,
+
+    /// The string to replace the match with, which can contain
+    /// back-references.
+    pub replacement: String,
+
+    /// Whether the match should ignore case.
+    pub case_insensitive: bool,
+}
+
+/// Something wrong with the regex field of a NAPTR record.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum RegexError {
+
+    /// The field is not valid UTF-8.
+    NotUtf8,
+
+    /// The first character, which is the delimiter, is one that is not
+    /// allowed to be a delimiter.
+    BadDelimiter(char),
+
+    /// The field has this many parts, rather than the three (pattern,
+    /// replacement, and flags) that it should.
+    WrongDelimiterCount(usize),
+
+    /// The flags contain a character other than `i`.
+    UnknownFlag(char),
+
+    /// Both the regex and the replacement fields were set, when only one of
+    /// them is allowed to be.
+    BothRegexAndReplacement,
 }
 
 
@@ -157,4 +314,117 @@ mod test {
         assert_eq!(NAPTR::read(23, &mut Cursor::new(buf)),
                    Err(WireError::IO));
     }
+
+    fn with_regex(regex: &[u8]) -> NAPTR {
+        NAPTR {
+            order: 100,
+            preference: 10,
+            flags: Box::new(*b"u"),
+            service: Box::new(*b"E2U+sip"),
+            regex: regex.into(),
+            replacement: Labels::root(),
+        }
+    }
+
+    #[test]
+    fn valid_substitution() {
+        assert_eq!(with_regex(b"!^.*$!sip:info@example.com!i").substitution(),
+                   Ok(Some(Substitution {
+                       pattern: "^.*$".into(),
+                       replacement: "sip:info@example.com".into(),
+                       case_insensitive: true,
+                   })));
+    }
+
+    #[test]
+    fn escaped_delimiter() {
+        assert_eq!(with_regex(b"/^a\\/b$/c/").substitution(),
+                   Ok(Some(Substitution {
+                       pattern: "^a\\/b$".into(),
+                       replacement: "c".into(),
+                       case_insensitive: false,
+                   })));
+    }
+
+    #[test]
+    fn empty_regex() {
+        assert_eq!(with_regex(b"").substitution(),
+                   Ok(None));
+    }
+
+    #[test]
+    fn too_few_delimiters() {
+        assert_eq!(with_regex(b"!^.*$!sip:info@example.com").substitution(),
+                   Err(RegexError::WrongDelimiterCount(2)));
+    }
+
+    #[test]
+    fn digit_delimiter() {
+        assert_eq!(with_regex(b"1a1b1").substitution(),
+                   Err(RegexError::BadDelimiter('1')));
+    }
+
+    #[test]
+    fn unknown_flag() {
+        assert_eq!(with_regex(b"!a!b!x").substitution(),
+                   Err(RegexError::UnknownFlag('x')));
+    }
+
+    #[test]
+    fn regex_and_replacement() {
+        let mut naptr = with_regex(b"!a!b!");
+        naptr.replacement = Labels::encode("example.com").unwrap();
+        assert_eq!(naptr.substitution(),
+                   Err(RegexError::BothRegexAndReplacement));
+    }
+
+    #[test]
+    fn enum_services() {
+        let mut naptr = with_regex(b"");
+        naptr.service = Box::new(*b"E2U+voice:tel+sip");
+        assert_eq!(naptr.enum_services(),
+                   Some(vec![ "voice:tel".into(), "sip".into() ]));
+    }
+
+    #[test]
+    fn not_enum_services() {
+        let mut naptr = with_regex(b"");
+        naptr.service = Box::new(*b"SIP+D2U");
+        assert_eq!(naptr.enum_services(), None);
+    }
+
+    #[test]
+    fn enum_number_decoded() {
+        let name = Labels::encode("4.3.2.1.5.5.5.0.2.1.4.4.e164.arpa").unwrap();
+        assert_eq!(enum_number(&name), Some("+441205551234".into()));
+    }
+
+    #[test]
+    fn enum_number_not_enum() {
+        let name = Labels::encode("naptr.example").unwrap();
+        assert_eq!(enum_number(&name), None);
+    }
+
+    #[test]
+    fn round_trip() {
+        let buf = &[
+            0x00, 0x05,  // order
+            0x00, 0x0a,  // preference
+            0x01,  // flags length
+            0x73,  // flags
+            0x03,  // service length
+            0x53, 0x52, 0x56,  // service
+            0x0e,  // regex length
+            0x5c, 0x64, 0x5c, 0x64, 0x3a, 0x5c, 0x64, 0x5c, 0x64, 0x3a, 0x5c,
+            0x64, 0x5c, 0x64,  // regex
+            0x0b, 0x73, 0x72, 0x76, 0x2d, 0x65, 0x78, 0x61, 0x6d, 0x70, 0x6c,
+            0x65, 0x06, 0x6c, 0x6f, 0x6f, 0x6b, 0x75, 0x70, 0x03, 0x64, 0x6f,
+            0x67, 0x00,  // replacement
+        ];
+
+        let record = NAPTR::read(buf.len() as _, &mut Cursor::new(buf)).unwrap();
+        let mut bytes = Vec::new();
+        record.write(&mut bytes).unwrap();
+        assert_eq!(bytes, buf);
+    }
 }
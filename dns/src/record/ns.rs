@@ -1,6 +1,7 @@
 use log::*;
 
-use crate::strings::{Labels, ReadLabels};
+use crate::presentation::name;
+use crate::strings::{Labels, ReadLabels, WriteLabels};
 use crate::wire::*;
 
 
@@ -11,7 +12,8 @@ use crate::wire::*;
 ///
 /// - [RFC 1035 §3.3.11](https://tools.ietf.org/html/rfc1035) — Domain Names,
 ///   Implementation and Specification (November 1987)
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct NS {
 
     /// The address of a nameserver that provides this DNS response.
@@ -36,6 +38,19 @@ impl Wire for NS {
             Err(WireError::WrongLabelLength { stated_length, length_after_labels: nameserver_length })
         }
     }
+
+    fn write(&self, bytes: &mut Vec<u8>) -> io::Result<()> {
+        bytes.write_labels(&self.nameserver)
+    }
+}
+
+impl NS {
+
+    /// Returns the record’s data in presentation format, which is the
+    /// nameserver’s domain name.
+    pub fn to_presentation(&self) -> String {
+        name(&self.nameserver)
+    }
 }
 
 
@@ -84,4 +99,18 @@ mod test {
         assert_eq!(NS::read(23, &mut Cursor::new(buf)),
                    Err(WireError::IO));
     }
+
+    #[test]
+    fn round_trip() {
+        let buf = &[
+            0x01, 0x61, 0x0c, 0x67, 0x74, 0x6c, 0x64, 0x2d, 0x73, 0x65, 0x72,
+            0x76, 0x65, 0x72, 0x73, 0x03, 0x6e, 0x65, 0x74,  // nameserver
+            0x00,  // nameserver terminator
+        ];
+
+        let record = NS::read(buf.len() as _, &mut Cursor::new(buf)).unwrap();
+        let mut bytes = Vec::new();
+        record.write(&mut bytes).unwrap();
+        assert_eq!(bytes, buf);
+    }
 }
@@ -0,0 +1,144 @@
+use log::*;
+
+use crate::presentation::name;
+use crate::record::RecordType;
+use crate::strings::{Labels, ReadLabels, WriteLabels};
+use crate::wire::*;
+
+use super::nsec3::{read_type_bitmaps, write_type_bitmaps};
+
+
+/// A **NSEC** _(next secure)_ record, which proves that a name does not
+/// exist in a signed zone by giving the next name in the zone after its
+/// own owner name, in canonical order, along with the record types that
+/// exist at its own owner name.
+///
+/// # References
+///
+/// - [RFC 4034 §4](https://tools.ietf.org/html/rfc4034#section-4) —
+///   Resource Records for the DNS Security Extensions (March 2005)
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NSEC {
+
+    /// The next name in the zone, or the zone’s apex for the last record.
+    pub next_domain: Labels,
+
+    /// The type numbers of the records that exist at the owner name.
+    pub types: Vec<u16>,
+}
+
+impl Wire for NSEC {
+    const NAME: &'static str = "NSEC";
+    const RR_TYPE: u16 = 47;
+
+    #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+    fn read(stated_length: u16, c: &mut Cursor<&[u8]>) -> Result<Self, WireError> {
+        let (next_domain, next_domain_length) = c.read_labels()?;
+        trace!("Parsed next domain -> {:?}", next_domain);
+
+        if stated_length < next_domain_length {
+            warn!("Length is incorrect (stated length {:?}, next domain length {:?})", stated_length, next_domain_length);
+            return Err(WireError::WrongLabelLength { stated_length, length_after_labels: next_domain_length });
+        }
+
+        let types = read_type_bitmaps(stated_length - next_domain_length, c)?;
+        trace!("Parsed types -> {:?}", types);
+
+        Ok(Self { next_domain, types })
+    }
+
+    fn write(&self, bytes: &mut Vec<u8>) -> io::Result<()> {
+        bytes.write_labels(&self.next_domain)?;
+        write_type_bitmaps(bytes, &self.types)
+    }
+}
+
+impl NSEC {
+
+    /// Returns the record’s data in presentation format, which is the next
+    /// domain name followed by the mnemonic of each type in the bitmap.
+    pub fn to_presentation(&self) -> String {
+        let mut parts = vec![ name(&self.next_domain) ];
+        parts.extend(self.types.iter().map(|t| RecordType::from(*t).mnemonic()));
+        parts.join(" ")
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parses() {
+        let buf = &[
+            0x03, 0x77, 0x77, 0x77, 0x03, 0x64, 0x6f, 0x67,  // next domain
+            0x00,  // next domain terminator
+            0x00, 0x06, 0x40, 0x00, 0x00, 0x00, 0x00, 0x03,  // types in window 0 (A, RRSIG, NSEC)
+        ];
+
+        assert_eq!(NSEC::read(buf.len() as _, &mut Cursor::new(buf)).unwrap(),
+                   NSEC {
+                       next_domain: Labels::encode("www.dog").unwrap(),
+                       types: vec![ 1, 46, 47 ],
+                   });
+    }
+
+    #[test]
+    fn parses_without_types() {
+        let buf = &[
+            0x03, 0x64, 0x6f, 0x67,  // next domain
+            0x00,  // next domain terminator
+        ];
+
+        assert_eq!(NSEC::read(buf.len() as _, &mut Cursor::new(buf)).unwrap(),
+                   NSEC {
+                       next_domain: Labels::encode("dog").unwrap(),
+                       types: vec![],
+                   });
+    }
+
+    #[test]
+    fn record_too_short() {
+        let buf = &[
+            0x03, 0x64, 0x6f, 0x67,  // next domain
+            0x00,  // next domain terminator
+        ];
+
+        assert_eq!(NSEC::read(3, &mut Cursor::new(buf)),
+                   Err(WireError::WrongLabelLength { stated_length: 3, length_after_labels: 5 }));
+    }
+
+    #[test]
+    fn record_empty() {
+        assert_eq!(NSEC::read(0, &mut Cursor::new(&[])),
+                   Err(WireError::IO));
+    }
+
+    #[test]
+    fn round_trip() {
+        let buf = &[
+            0x03, 0x77, 0x77, 0x77, 0x03, 0x64, 0x6f, 0x67,  // next domain
+            0x00,  // next domain terminator
+            0x00, 0x06, 0x40, 0x00, 0x00, 0x00, 0x00, 0x03,  // types in window 0 (A, RRSIG, NSEC)
+        ];
+
+        let record = NSEC::read(buf.len() as _, &mut Cursor::new(buf)).unwrap();
+        let mut bytes = Vec::new();
+        record.write(&mut bytes).unwrap();
+        assert_eq!(bytes, buf);
+    }
+
+    #[test]
+    fn presentation() {
+        let record = NSEC {
+            next_domain: Labels::encode("www.dog").unwrap(),
+            types: vec![ 1, 46, 47, 65280 ],
+        };
+
+        assert_eq!(record.to_presentation(),
+                   "www.dog. A RRSIG NSEC TYPE65280");
+    }
+}
@@ -0,0 +1,460 @@
+use log::*;
+
+use crate::record::RecordType;
+use crate::wire::*;
+
+
+/// A **NSEC3** _(next secure record, version 3)_ record, which proves
+/// that a name does not exist in a signed zone without revealing the names
+/// that do. It gives the hash of the next name in the zone after its own
+/// hashed owner name, and the record types that exist at the original
+/// name.
+///
+/// # References
+///
+/// - [RFC 5155 §3](https://tools.ietf.org/html/rfc5155#section-3) — DNS
+///   Security (DNSSEC) Hashed Authenticated Denial of Existence (March 2008)
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NSEC3 {
+
+    /// The hash algorithm used to hash the owner names. 1 is SHA-1, the
+    /// only one that has been defined.
+    pub hash_algorithm: u8,
+
+    /// The flags field, the lowest bit of which is the Opt-Out flag.
+    pub flags: u8,
+
+    /// The number of extra times the hash function was applied.
+    pub iterations: u16,
+
+    /// The salt appended to the name before hashing.
+    pub salt: Vec<u8>,
+
+    /// The hash of the next owner name in the zone, in hash order.
+    pub next_hashed_owner: Vec<u8>,
+
+    /// The type numbers of the records that exist at the original name.
+    pub types: Vec<u16>,
+}
+
+/// A **NSEC3PARAM** record, which sits at the apex of a zone signed with
+/// NSEC3, and gives the parameters that authoritative servers should use
+/// when hashing names for it.
+///
+/// # References
+///
+/// - [RFC 5155 §4](https://tools.ietf.org/html/rfc5155#section-4) — DNS
+///   Security (DNSSEC) Hashed Authenticated Denial of Existence (March 2008)
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NSEC3PARAM {
+
+    /// The hash algorithm used to hash the owner names.
+    pub hash_algorithm: u8,
+
+    /// The flags field, which is always zero in practice.
+    pub flags: u8,
+
+    /// The number of extra times the hash function is applied.
+    pub iterations: u16,
+
+    /// The salt appended to the name before hashing.
+    pub salt: Vec<u8>,
+}
+
+impl Wire for NSEC3 {
+    const NAME: &'static str = "NSEC3";
+    const RR_TYPE: u16 = 50;
+
+    #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+    fn read(stated_length: u16, c: &mut Cursor<&[u8]>) -> Result<Self, WireError> {
+        let hash_algorithm = c.read_u8()?;
+        trace!("Parsed hash algorithm -> {:?}", hash_algorithm);
+
+        let flags = c.read_u8()?;
+        trace!("Parsed flags -> {:?}", flags);
+
+        let iterations = c.read_u16::<BigEndian>()?;
+        trace!("Parsed iterations -> {:?}", iterations);
+
+        let salt_length = c.read_u8()?;
+        let mut salt = vec![0_u8; usize::from(salt_length)];
+        c.read_exact(&mut salt)?;
+        trace!("Parsed salt -> {:#x?}", salt);
+
+        let hash_length = c.read_u8()?;
+        let mut next_hashed_owner = vec![0_u8; usize::from(hash_length)];
+        c.read_exact(&mut next_hashed_owner)?;
+        trace!("Parsed next hashed owner -> {:#x?}", next_hashed_owner);
+
+        let length_before_types = 1 + 1 + 2 + 1 + u16::from(salt_length) + 1 + u16::from(hash_length);
+        if stated_length < length_before_types {
+            let mandated_length = MandatedLength::AtLeast(length_before_types);
+            return Err(WireError::WrongRecordLength { stated_length, mandated_length });
+        }
+
+        let types = read_type_bitmaps(stated_length - length_before_types, c)?;
+        trace!("Parsed types -> {:?}", types);
+
+        Ok(Self { hash_algorithm, flags, iterations, salt, next_hashed_owner, types })
+    }
+
+    fn write(&self, bytes: &mut Vec<u8>) -> io::Result<()> {
+        bytes.write_u8(self.hash_algorithm)?;
+        bytes.write_u8(self.flags)?;
+        bytes.write_u16::<BigEndian>(self.iterations)?;
+        write_character_string(bytes, &self.salt)?;
+        write_character_string(bytes, &self.next_hashed_owner)?;
+        write_type_bitmaps(bytes, &self.types)
+    }
+}
+
+impl Wire for NSEC3PARAM {
+    const NAME: &'static str = "NSEC3PARAM";
+    const RR_TYPE: u16 = 51;
+
+    #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+    fn read(stated_length: u16, c: &mut Cursor<&[u8]>) -> Result<Self, WireError> {
+        let hash_algorithm = c.read_u8()?;
+        trace!("Parsed hash algorithm -> {:?}", hash_algorithm);
+
+        let flags = c.read_u8()?;
+        trace!("Parsed flags -> {:?}", flags);
+
+        let iterations = c.read_u16::<BigEndian>()?;
+        trace!("Parsed iterations -> {:?}", iterations);
+
+        let salt_length = c.read_u8()?;
+        let mut salt = vec![0_u8; usize::from(salt_length)];
+        c.read_exact(&mut salt)?;
+        trace!("Parsed salt -> {:#x?}", salt);
+
+        let length = 1 + 1 + 2 + 1 + u16::from(salt_length);
+        if stated_length != length {
+            warn!("Length is incorrect (stated length {:?}, salt length {:?})", stated_length, salt_length);
+            let mandated_length = MandatedLength::Exactly(length);
+            return Err(WireError::WrongRecordLength { stated_length, mandated_length });
+        }
+
+        Ok(Self { hash_algorithm, flags, iterations, salt })
+    }
+
+    fn write(&self, bytes: &mut Vec<u8>) -> io::Result<()> {
+        bytes.write_u8(self.hash_algorithm)?;
+        bytes.write_u8(self.flags)?;
+        bytes.write_u16::<BigEndian>(self.iterations)?;
+        write_character_string(bytes, &self.salt)
+    }
+}
+
+/// Reads the type bitmaps at the end of an NSEC or NSEC3 record. Each one has a
+/// window number, which gives the upper byte of the type numbers in it, a
+/// length, and then a bit for each type in the window, starting with the
+/// most significant bit of the first byte.
+pub(super) fn read_type_bitmaps(length: u16, c: &mut Cursor<&[u8]>) -> Result<Vec<u16>, WireError> {
+    let mut types = Vec::new();
+    let mut remaining = length;
+
+    while remaining > 0 {
+        let window = c.read_u8()?;
+        let bitmap_length = c.read_u8()?;
+
+        let mut bitmap = vec![0_u8; usize::from(bitmap_length)];
+        c.read_exact(&mut bitmap)?;
+
+        for (byte_index, byte) in (0_u16 ..).zip(&bitmap) {
+            for bit in 0 .. 8 {
+                if byte & (0b_1000_0000 >> bit) != 0 {
+                    types.push((u16::from(window) << 8) | (byte_index * 8 + bit));
+                }
+            }
+        }
+
+        match remaining.checked_sub(2 + u16::from(bitmap_length)) {
+            Some(r)  => remaining = r,
+            None     => {
+                let mandated_length = MandatedLength::AtLeast(length - remaining + 2 + u16::from(bitmap_length));
+                return Err(WireError::WrongRecordLength { stated_length: length, mandated_length });
+            }
+        }
+    }
+
+    Ok(types)
+}
+
+/// Writes the type bitmaps for a list of record types, with one window for
+/// each upper byte that the types use, leaving out the zero bytes at the end
+/// of each bitmap.
+pub(super) fn write_type_bitmaps(bytes: &mut Vec<u8>, types: &[u16]) -> io::Result<()> {
+    let mut types = types.to_vec();
+    types.sort_unstable();
+    types.dedup();
+
+    let mut index = 0;
+    while index < types.len() {
+        let window = types[index] >> 8;
+        let mut bitmap = Vec::new();
+
+        while index < types.len() && types[index] >> 8 == window {
+            let bit = usize::from(types[index] & 0xff);
+            if bitmap.len() <= bit / 8 {
+                bitmap.resize(bit / 8 + 1, 0);
+            }
+
+            bitmap[bit / 8] |= 0b_1000_0000 >> (bit % 8);
+            index += 1;
+        }
+
+        // The bitmap is at most 32 bytes long, so its length fits in a byte.
+        bytes.write_u8(window as u8)?;
+        write_character_string(bytes, &bitmap)?;
+    }
+
+    Ok(())
+}
+
+
+impl NSEC3 {
+
+    /// Whether the Opt-Out flag is set, meaning that this record may cover
+    /// unsigned delegations.
+    pub fn opt_out(&self) -> bool {
+        self.flags & 0b_0000_0001 != 0
+    }
+
+    /// Returns the salt in hexadecimal, or `-` if there is none, as zone
+    /// files write it.
+    pub fn hex_salt(&self) -> String {
+        hex_salt(&self.salt)
+    }
+
+    /// Returns the next hashed owner name in base32 with the extended hex
+    /// alphabet, which is how it appears as a label.
+    pub fn base32_next_hashed_owner(&self) -> String {
+        base32hex(&self.next_hashed_owner)
+    }
+
+    /// Returns the record’s data in presentation format, with the salt in
+    /// hexadecimal and the next hashed owner name in base32, both in
+    /// uppercase as dig writes them.
+    pub fn to_presentation(&self) -> String {
+        let mut parts = vec![
+            self.hash_algorithm.to_string(),
+            self.flags.to_string(),
+            self.iterations.to_string(),
+            self.hex_salt().to_ascii_uppercase(),
+            self.base32_next_hashed_owner().to_ascii_uppercase(),
+        ];
+
+        parts.extend(self.types.iter().map(|t| RecordType::from(*t).mnemonic()));
+        parts.join(" ")
+    }
+}
+
+impl NSEC3PARAM {
+
+    /// Returns the salt in hexadecimal, or `-` if there is none, as zone
+    /// files write it.
+    pub fn hex_salt(&self) -> String {
+        hex_salt(&self.salt)
+    }
+
+    /// Returns the record’s data in presentation format, with the salt in
+    /// hexadecimal.
+    pub fn to_presentation(&self) -> String {
+        format!("{} {} {} {}",
+            self.hash_algorithm,
+            self.flags,
+            self.iterations,
+            self.hex_salt().to_ascii_uppercase(),
+        )
+    }
+}
+
+fn hex_salt(salt: &[u8]) -> String {
+    if salt.is_empty() {
+        "-".into()
+    }
+    else {
+        salt.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+}
+
+/// Encodes bytes in base32 using the extended hex alphabet, without
+/// padding, as described in RFC 4648 §7.
+pub(crate) fn base32hex(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 32] = b"0123456789abcdefghijklmnopqrstuv";
+
+    let mut out = String::new();
+    let mut buffer = 0_u32;
+    let mut bits = 0;
+
+    for byte in bytes {
+        buffer = (buffer << 8) | u32::from(*byte);
+        bits += 8;
+
+        while bits >= 5 {
+            bits -= 5;
+            out.push(char::from(ALPHABET[((buffer >> bits) & 0x1f) as usize]));
+        }
+    }
+
+    if bits > 0 {
+        out.push(char::from(ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize]));
+    }
+
+    out
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parses() {
+        let buf = &[
+            0x01,  // hash algorithm
+            0x01,  // flags
+            0x00, 0x0a,  // iterations
+            0x02, 0xab, 0xcd,  // salt
+            0x05, 0x01, 0x02, 0x03, 0x04, 0x05,  // next hashed owner
+            0x00, 0x06, 0x40, 0x00, 0x00, 0x00, 0x00, 0x02,  // types in window 0 (A, RRSIG)
+            0x01, 0x01, 0x40,  // types in window 1 (CAA)
+        ];
+
+        assert_eq!(NSEC3::read(buf.len() as _, &mut Cursor::new(buf)).unwrap(),
+                   NSEC3 {
+                       hash_algorithm: 1,
+                       flags: 1,
+                       iterations: 10,
+                       salt: vec![ 0xab, 0xcd ],
+                       next_hashed_owner: vec![ 0x01, 0x02, 0x03, 0x04, 0x05 ],
+                       types: vec![ 1, 46, 257 ],
+                   });
+    }
+
+    #[test]
+    fn parses_without_salt_or_types() {
+        let buf = &[
+            0x01,  // hash algorithm
+            0x00,  // flags
+            0x00, 0x00,  // iterations
+            0x00,  // salt
+            0x01, 0xff,  // next hashed owner
+        ];
+
+        assert_eq!(NSEC3::read(buf.len() as _, &mut Cursor::new(buf)).unwrap(),
+                   NSEC3 {
+                       hash_algorithm: 1,
+                       flags: 0,
+                       iterations: 0,
+                       salt: vec![],
+                       next_hashed_owner: vec![ 0xff ],
+                       types: vec![],
+                   });
+    }
+
+    #[test]
+    fn record_too_short() {
+        let buf = &[
+            0x01,  // hash algorithm
+            0x00,  // flags
+            0x00, 0x00,  // iterations
+            0x00,  // salt
+            0x01, 0xff,  // next hashed owner
+        ];
+
+        assert_eq!(NSEC3::read(5, &mut Cursor::new(buf)),
+                   Err(WireError::WrongRecordLength { stated_length: 5, mandated_length: MandatedLength::AtLeast(7) }));
+    }
+
+    #[test]
+    fn record_empty() {
+        assert_eq!(NSEC3::read(0, &mut Cursor::new(&[])),
+                   Err(WireError::IO));
+    }
+
+    #[test]
+    fn parses_param() {
+        let buf = &[
+            0x01,  // hash algorithm
+            0x00,  // flags
+            0x00, 0x96,  // iterations
+            0x04, 0xde, 0xad, 0xbe, 0xef,  // salt
+        ];
+
+        assert_eq!(NSEC3PARAM::read(buf.len() as _, &mut Cursor::new(buf)).unwrap(),
+                   NSEC3PARAM {
+                       hash_algorithm: 1,
+                       flags: 0,
+                       iterations: 150,
+                       salt: vec![ 0xde, 0xad, 0xbe, 0xef ],
+                   });
+    }
+
+    #[test]
+    fn param_too_long() {
+        let buf = &[
+            0x01,  // hash algorithm
+            0x00,  // flags
+            0x00, 0x00,  // iterations
+            0x00,  // salt
+            0x00,  // something extra
+        ];
+
+        assert_eq!(NSEC3PARAM::read(buf.len() as _, &mut Cursor::new(buf)),
+                   Err(WireError::WrongRecordLength { stated_length: 6, mandated_length: MandatedLength::Exactly(5) }));
+    }
+
+    #[test]
+    fn salts() {
+        let param = NSEC3PARAM { hash_algorithm: 1, flags: 0, iterations: 0, salt: vec![] };
+        assert_eq!(param.hex_salt(), "-");
+
+        let param = NSEC3PARAM { salt: vec![ 0xab, 0x01 ], .. param };
+        assert_eq!(param.hex_salt(), "ab01");
+    }
+
+    #[test]
+    fn base32() {
+        assert_eq!(base32hex(b""), "");
+        assert_eq!(base32hex(b"f"), "co");
+        assert_eq!(base32hex(b"foobar"), "cpnmuoj1e8");
+    }
+
+    #[test]
+    fn round_trip() {
+        let buf = &[
+            0x01,  // hash algorithm
+            0x01,  // flags
+            0x00, 0x0a,  // iterations
+            0x02, 0xab, 0xcd,  // salt
+            0x05, 0x01, 0x02, 0x03, 0x04, 0x05,  // next hashed owner
+            0x00, 0x06, 0x40, 0x00, 0x00, 0x00, 0x00, 0x02,  // types in window 0 (A, RRSIG)
+            0x01, 0x01, 0x40,  // types in window 1 (CAA)
+        ];
+
+        let record = NSEC3::read(buf.len() as _, &mut Cursor::new(buf)).unwrap();
+        let mut bytes = Vec::new();
+        record.write(&mut bytes).unwrap();
+        assert_eq!(bytes, buf);
+    }
+
+    #[test]
+    fn round_trip_param() {
+        let buf = &[
+            0x01,  // hash algorithm
+            0x00,  // flags
+            0x00, 0x96,  // iterations
+            0x04, 0xde, 0xad, 0xbe, 0xef,  // salt
+        ];
+
+        let record = NSEC3PARAM::read(buf.len() as _, &mut Cursor::new(buf)).unwrap();
+        let mut bytes = Vec::new();
+        record.write(&mut bytes).unwrap();
+        assert_eq!(bytes, buf);
+    }
+}
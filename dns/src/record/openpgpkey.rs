@@ -9,7 +9,8 @@ use crate::wire::*;
 ///
 /// - [RFC 1035 §3.3.14](https://tools.ietf.org/html/rfc7929) — DNS-Based
 ///   Authentication of Named Entities Bindings for OpenPGP (August 2016)
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct OPENPGPKEY {
 
     /// The PGP key, as unencoded bytes.
@@ -33,6 +34,11 @@ impl Wire for OPENPGPKEY {
 
         Ok(Self { key })
     }
+
+    fn write(&self, bytes: &mut Vec<u8>) -> io::Result<()> {
+        bytes.extend_from_slice(&self.key);
+        Ok(())
+    }
 }
 
 impl OPENPGPKEY {
@@ -41,6 +47,12 @@ impl OPENPGPKEY {
     pub fn base64_key(&self) -> String {
         base64::encode(&self.key)
     }
+
+    /// Returns the record’s data in presentation format, which is the key in
+    /// base64.
+    pub fn to_presentation(&self) -> String {
+        self.base64_key()
+    }
 }
 
 
@@ -88,4 +100,16 @@ mod test {
         assert_eq!(OPENPGPKEY::read(23, &mut Cursor::new(buf)),
                    Err(WireError::IO));
     }
+
+    #[test]
+    fn round_trip() {
+        let buf = &[
+            0x12, 0x34, 0x56, 0x78,  // key
+        ];
+
+        let record = OPENPGPKEY::read(buf.len() as _, &mut Cursor::new(buf)).unwrap();
+        let mut bytes = Vec::new();
+        record.write(&mut bytes).unwrap();
+        assert_eq!(bytes, buf);
+    }
 }
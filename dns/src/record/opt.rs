@@ -1,8 +1,8 @@
 use std::convert::TryFrom;
-use std::io;
 
 use log::*;
 
+use crate::record::EdnsOption;
 use crate::wire::*;
 
 
@@ -31,6 +31,7 @@ use crate::wire::*;
 /// - [RFC 6891](https://tools.ietf.org/html/rfc6891) — Extension Mechanisms
 ///   for DNS (April 2013)
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct OPT {
 
     /// The maximum size of a UDP packet that the client supports.
@@ -86,6 +87,30 @@ impl OPT {
         Ok(Self { udp_payload_size, higher_bits, edns0_version, flags, data })
     }
 
+    /// Reads the options out of this record’s data.
+    pub fn options(&self) -> Result<Vec<EdnsOption>, WireError> {
+        EdnsOption::read_all(&self.data)
+    }
+
+    /// Adds an option to the end of this record’s data.
+    pub fn push_option(&mut self, option: &EdnsOption) -> io::Result<()> {
+        self.data.extend(option.to_bytes()?);
+        Ok(())
+    }
+
+    /// Adds an option to this record’s data, replacing any options that
+    /// are already there with the same code.
+    pub fn replace_option(&mut self, option: &EdnsOption) -> io::Result<()> {
+        if let Ok(options) = self.options() {
+            self.data.clear();
+            for other in options.iter().filter(|o| o.code() != option.code()) {
+                self.push_option(other)?;
+            }
+        }
+
+        self.push_option(option)
+    }
+
     /// Serialises this OPT record into a vector of bytes.
     ///
     /// This is necessary for OPT records to be sent in the Additional section
@@ -173,4 +198,19 @@ mod test {
         assert_eq!(OPT::read(&mut Cursor::new(buf)),
                    Err(WireError::IO));
     }
+
+    #[test]
+    fn replaces_options() {
+        use crate::record::{Cookie, Nsid};
+
+        let mut opt = OPT { udp_payload_size: 512, higher_bits: 0, edns0_version: 0, flags: 0, data: vec![] };
+        opt.push_option(&EdnsOption::Cookie(Cookie::new([ 0xaa; 8 ]))).unwrap();
+        opt.push_option(&EdnsOption::Nsid(Nsid(vec![]))).unwrap();
+
+        let cookie = Cookie { client: [ 0xaa; 8 ], server: vec![ 0xbb; 8 ] };
+        opt.replace_option(&EdnsOption::Cookie(cookie.clone())).unwrap();
+
+        assert_eq!(opt.options(),
+                   Ok(vec![ EdnsOption::Nsid(Nsid(vec![])), EdnsOption::Cookie(cookie) ]));
+    }
 }
@@ -60,11 +60,7 @@ static TYPES: &[(&str, u16)] = &[
     ("CERT",       37),
     ("CSYNC",      62),
     ("DHCID",      49),
-    ("DLV",     32769),
     ("DNAME",      39),
-    ("DNSKEEYE",   48),
-    ("DS",         43),
-    ("HIP",        55),
     ("IPSECKEY",   45),
     ("IXFR",      251),
     ("KEY",        25),
@@ -73,11 +69,9 @@ static TYPES: &[(&str, u16)] = &[
     ("NSEC3",      50),
     ("NSEC3PARAM", 51),
     ("OPENPGPKEY", 61),
-    ("RRSIG",      46),
     ("RP",         17),
     ("SIG",        24),
     ("SMIMEA",     53),
-    ("TA",      32768),
     ("TKEY",      249),
     ("TSIG",      250),
     ("URI",       256),
@@ -90,8 +84,8 @@ mod test {
 
     #[test]
     fn known() {
-        assert_eq!(UnknownQtype::from(46).to_string(),
-                   String::from("RRSIG"));
+        assert_eq!(UnknownQtype::from(50).to_string(),
+                   String::from("NSEC3"));
     }
 
     #[test]
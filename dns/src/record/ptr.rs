@@ -1,6 +1,7 @@
 use log::*;
 
-use crate::strings::{Labels, ReadLabels};
+use crate::presentation::name;
+use crate::strings::{Labels, ReadLabels, WriteLabels};
 use crate::wire::*;
 
 
@@ -16,7 +17,8 @@ use crate::wire::*;
 ///
 /// - [RFC 1035 §3.3.14](https://tools.ietf.org/html/rfc1035) — Domain Names,
 ///   Implementation and Specification (November 1987)
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PTR {
 
     /// The CNAME contained in the record.
@@ -41,6 +43,19 @@ impl Wire for PTR {
             Err(WireError::WrongLabelLength { stated_length, length_after_labels: cname_length })
         }
     }
+
+    fn write(&self, bytes: &mut Vec<u8>) -> io::Result<()> {
+        bytes.write_labels(&self.cname)
+    }
+}
+
+impl PTR {
+
+    /// Returns the record’s data in presentation format, which is the domain
+    /// name.
+    pub fn to_presentation(&self) -> String {
+        name(&self.cname)
+    }
 }
 
 
@@ -88,4 +103,17 @@ mod test {
         assert_eq!(PTR::read(23, &mut Cursor::new(buf)),
                    Err(WireError::IO));
     }
+
+    #[test]
+    fn round_trip() {
+        let buf = &[
+            0x03, 0x64, 0x6e, 0x73, 0x06, 0x67, 0x6f, 0x6f, 0x67, 0x6c, 0x65,  // cname
+            0x00,  // cname terminator
+        ];
+
+        let record = PTR::read(buf.len() as _, &mut Cursor::new(buf)).unwrap();
+        let mut bytes = Vec::new();
+        record.write(&mut bytes).unwrap();
+        assert_eq!(bytes, buf);
+    }
 }
@@ -0,0 +1,243 @@
+use log::*;
+
+use crate::presentation::{name, signature_timestamp};
+use crate::record::RecordType;
+use crate::strings::{Labels, ReadLabels, WriteLabels};
+use crate::wire::*;
+
+
+/// A **RRSIG** _(resource record signature)_ record, which contains the
+/// DNSSEC signature over the set of records with a particular owner name
+/// and type.
+///
+/// # References
+///
+/// - [RFC 4034 §3](https://tools.ietf.org/html/rfc4034#section-3) — Resource
+///   Records for the DNS Security Extensions (March 2005)
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RRSIG {
+
+    /// The type number of the records that this signature covers.
+    pub type_covered: u16,
+
+    /// The algorithm used to create the signature. This is a number with
+    /// several defined mappings.
+    pub algorithm: u8,
+
+    /// The number of labels in the original owner name, not counting the
+    /// root label or a leading wildcard.
+    pub labels: u8,
+
+    /// The TTL of the covered records, as it appears in the zone.
+    pub original_ttl: u32,
+
+    /// The time after which the signature is no longer valid, as a number
+    /// of seconds since the Unix epoch, modulo 2³².
+    pub signature_expiration: u32,
+
+    /// The time before which the signature is not yet valid, in the same
+    /// format as the expiration.
+    pub signature_inception: u32,
+
+    /// The key tag of the DNSKEY record that validates this signature.
+    pub key_tag: u16,
+
+    /// The owner name of the DNSKEY record that validates this signature.
+    pub signer_name: Labels,
+
+    /// The cryptographic signature.
+    pub signature: Vec<u8>,
+}
+
+impl Wire for RRSIG {
+    const NAME: &'static str = "RRSIG";
+    const RR_TYPE: u16 = 46;
+
+    #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+    fn read(stated_length: u16, c: &mut Cursor<&[u8]>) -> Result<Self, WireError> {
+        let type_covered = c.read_u16::<BigEndian>()?;
+        trace!("Parsed type covered -> {:?}", type_covered);
+
+        let algorithm = c.read_u8()?;
+        trace!("Parsed algorithm -> {:?}", algorithm);
+
+        let labels = c.read_u8()?;
+        trace!("Parsed labels -> {:?}", labels);
+
+        let original_ttl = c.read_u32::<BigEndian>()?;
+        trace!("Parsed original TTL -> {:?}", original_ttl);
+
+        let signature_expiration = c.read_u32::<BigEndian>()?;
+        trace!("Parsed signature expiration -> {:?}", signature_expiration);
+
+        let signature_inception = c.read_u32::<BigEndian>()?;
+        trace!("Parsed signature inception -> {:?}", signature_inception);
+
+        let key_tag = c.read_u16::<BigEndian>()?;
+        trace!("Parsed key tag -> {:?}", key_tag);
+
+        let (signer_name, signer_name_length) = c.read_labels()?;
+        trace!("Parsed signer name -> {:?}", signer_name);
+
+        let length_after_labels = 2 + 1 + 1 + 4 + 4 + 4 + 2 + signer_name_length;
+        if stated_length < length_after_labels {
+            return Err(WireError::WrongLabelLength { stated_length, length_after_labels });
+        }
+
+        let signature_length = stated_length - length_after_labels;
+        let mut signature = vec![0_u8; usize::from(signature_length)];
+        c.read_exact(&mut signature)?;
+        trace!("Parsed signature -> {:#x?}", signature);
+
+        Ok(Self {
+            type_covered, algorithm, labels, original_ttl,
+            signature_expiration, signature_inception, key_tag,
+            signer_name, signature,
+        })
+    }
+
+    fn write(&self, bytes: &mut Vec<u8>) -> io::Result<()> {
+        bytes.write_u16::<BigEndian>(self.type_covered)?;
+        bytes.write_u8(self.algorithm)?;
+        bytes.write_u8(self.labels)?;
+        bytes.write_u32::<BigEndian>(self.original_ttl)?;
+        bytes.write_u32::<BigEndian>(self.signature_expiration)?;
+        bytes.write_u32::<BigEndian>(self.signature_inception)?;
+        bytes.write_u16::<BigEndian>(self.key_tag)?;
+        bytes.write_labels(&self.signer_name)?;
+        bytes.extend_from_slice(&self.signature);
+        Ok(())
+    }
+}
+
+impl RRSIG {
+
+    /// Returns the base64-encoded signature.
+    pub fn base64_signature(&self) -> String {
+        base64::encode(&self.signature)
+    }
+
+    /// Returns the record’s data in presentation format, with the times as
+    /// timestamps and the signature in base64.
+    pub fn to_presentation(&self) -> String {
+        format!("{} {} {} {} {} {} {} {} {}",
+            RecordType::from(self.type_covered).mnemonic(),
+            self.algorithm,
+            self.labels,
+            self.original_ttl,
+            signature_timestamp(self.signature_expiration),
+            signature_timestamp(self.signature_inception),
+            self.key_tag,
+            name(&self.signer_name),
+            self.base64_signature(),
+        )
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parses() {
+        let buf = &[
+            0x00, 0x01,  // type covered
+            0x0d,  // algorithm
+            0x02,  // labels
+            0x00, 0x00, 0x0e, 0x10,  // original TTL
+            0x6a, 0x00, 0x00, 0x00,  // signature expiration
+            0x69, 0x00, 0x00, 0x00,  // signature inception
+            0x30, 0x39,  // key tag
+            0x03, 0x64, 0x6f, 0x67, 0x00,  // signer name
+            0xab, 0xcd, 0xef,  // signature
+        ];
+
+        assert_eq!(RRSIG::read(buf.len() as _, &mut Cursor::new(buf)).unwrap(),
+                   RRSIG {
+                       type_covered: 1,
+                       algorithm: 13,
+                       labels: 2,
+                       original_ttl: 3600,
+                       signature_expiration: 0x6a00_0000,
+                       signature_inception: 0x6900_0000,
+                       key_tag: 12345,
+                       signer_name: Labels::encode("dog").unwrap(),
+                       signature: vec![ 0xab, 0xcd, 0xef ],
+                   });
+    }
+
+    #[test]
+    fn record_too_short() {
+        let buf = &[
+            0x00, 0x01,  // type covered
+            0x0d,  // algorithm
+            0x02,  // labels
+            0x00, 0x00, 0x0e, 0x10,  // original TTL
+            0x6a, 0x00, 0x00, 0x00,  // signature expiration
+            0x69, 0x00, 0x00, 0x00,  // signature inception
+            0x30, 0x39,  // key tag
+            0x03, 0x64, 0x6f, 0x67, 0x00,  // signer name
+        ];
+
+        assert_eq!(RRSIG::read(20, &mut Cursor::new(buf)),
+                   Err(WireError::WrongLabelLength { stated_length: 20, length_after_labels: 23 }));
+    }
+
+    #[test]
+    fn record_empty() {
+        assert_eq!(RRSIG::read(0, &mut Cursor::new(&[])),
+                   Err(WireError::IO));
+    }
+
+    #[test]
+    fn buffer_ends_abruptly() {
+        let buf = &[
+            0x00, 0x01,  // type covered
+            0x0d,  // algorithm
+        ];
+
+        assert_eq!(RRSIG::read(23, &mut Cursor::new(buf)),
+                   Err(WireError::IO));
+    }
+
+    #[test]
+    fn round_trip() {
+        let buf = &[
+            0x00, 0x01,  // type covered
+            0x0d,  // algorithm
+            0x02,  // labels
+            0x00, 0x00, 0x0e, 0x10,  // original TTL
+            0x6a, 0x00, 0x00, 0x00,  // signature expiration
+            0x69, 0x00, 0x00, 0x00,  // signature inception
+            0x30, 0x39,  // key tag
+            0x03, 0x64, 0x6f, 0x67, 0x00,  // signer name
+            0xab, 0xcd, 0xef,  // signature
+        ];
+
+        let record = RRSIG::read(buf.len() as _, &mut Cursor::new(buf)).unwrap();
+        let mut bytes = Vec::new();
+        record.write(&mut bytes).unwrap();
+        assert_eq!(bytes, buf);
+    }
+
+    #[test]
+    fn presentation() {
+        let record = RRSIG {
+            type_covered: 1,
+            algorithm: 13,
+            labels: 2,
+            original_ttl: 3600,
+            signature_expiration: 0x6a00_0000,
+            signature_inception: 0x6900_0000,
+            key_tag: 12345,
+            signer_name: Labels::encode("dog").unwrap(),
+            signature: vec![ 0xab, 0xcd, 0xef ],
+        };
+
+        assert_eq!(record.to_presentation(),
+                   "A 13 2 3600 20260510034816 20251027232800 12345 dog. q83v");
+    }
+}
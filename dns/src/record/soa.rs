@@ -1,6 +1,7 @@
 use log::*;
 
-use crate::strings::{Labels, ReadLabels};
+use crate::presentation::name;
+use crate::strings::{Labels, ReadLabels, WriteLabels};
 use crate::wire::*;
 
 
@@ -12,7 +13,8 @@ use crate::wire::*;
 ///
 /// - [RFC 1035 §3.3.13](https://tools.ietf.org/html/rfc1035) — Domain Names,
 ///   Implementation and Specification (November 1987)
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SOA {
 
     /// The primary master name for this server.
@@ -83,6 +85,34 @@ impl Wire for SOA {
             Err(WireError::WrongLabelLength { stated_length, length_after_labels })
         }
     }
+
+    fn write(&self, bytes: &mut Vec<u8>) -> io::Result<()> {
+        bytes.write_labels(&self.mname)?;
+        bytes.write_labels(&self.rname)?;
+        bytes.write_u32::<BigEndian>(self.serial)?;
+        bytes.write_u32::<BigEndian>(self.refresh_interval)?;
+        bytes.write_u32::<BigEndian>(self.retry_interval)?;
+        bytes.write_u32::<BigEndian>(self.expire_limit)?;
+        bytes.write_u32::<BigEndian>(self.minimum_ttl)?;
+        Ok(())
+    }
+}
+
+impl SOA {
+
+    /// Returns the record’s data in presentation format, with the intervals
+    /// in seconds.
+    pub fn to_presentation(&self) -> String {
+        format!("{} {} {} {} {} {} {}",
+            name(&self.mname),
+            name(&self.rname),
+            self.serial,
+            self.refresh_interval,
+            self.retry_interval,
+            self.expire_limit,
+            self.minimum_ttl,
+        )
+    }
 }
 
 
@@ -150,4 +180,24 @@ mod test {
         assert_eq!(SOA::read(23, &mut Cursor::new(buf)),
                    Err(WireError::IO));
     }
+
+    #[test]
+    fn round_trip() {
+        let buf = &[
+            0x05, 0x62, 0x73, 0x61, 0x67, 0x6f, 0x02, 0x6d, 0x65,  // mname
+            0x00,  // mname terminator
+            0x05, 0x62, 0x73, 0x61, 0x67, 0x6f, 0x02, 0x6d, 0x65,  // rname
+            0x00,  // rname terminator
+            0x5d, 0x3c, 0xef, 0x02,  // Serial
+            0x00, 0x01, 0x51, 0x80,  // Refresh interval
+            0x00, 0x00, 0x1c, 0x20,  // Retry interval
+            0x00, 0x09, 0x3a, 0x80,  // Expire limit
+            0x00, 0x00, 0x01, 0x2c,  // Minimum TTL
+        ];
+
+        let record = SOA::read(buf.len() as _, &mut Cursor::new(buf)).unwrap();
+        let mut bytes = Vec::new();
+        record.write(&mut bytes).unwrap();
+        assert_eq!(bytes, buf);
+    }
 }
@@ -1,6 +1,7 @@
 use log::*;
 
-use crate::strings::{Labels, ReadLabels};
+use crate::presentation::name;
+use crate::strings::{Labels, ReadLabels, WriteLabels};
 use crate::wire::*;
 
 
@@ -11,7 +12,8 @@ use crate::wire::*;
 ///
 /// - [RFC 2782](https://tools.ietf.org/html/rfc2782) — A DNS RR for
 ///   specifying the location of services (February 2000)
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SRV {
 
     /// The priority of this host among all that get returned. Lower values
@@ -57,6 +59,22 @@ impl Wire for SRV {
             Err(WireError::WrongLabelLength { stated_length, length_after_labels })
         }
     }
+
+    fn write(&self, bytes: &mut Vec<u8>) -> io::Result<()> {
+        bytes.write_u16::<BigEndian>(self.priority)?;
+        bytes.write_u16::<BigEndian>(self.weight)?;
+        bytes.write_u16::<BigEndian>(self.port)?;
+        bytes.write_labels(&self.target)
+    }
+}
+
+impl SRV {
+
+    /// Returns the record’s data in presentation format, with the port before
+    /// the target.
+    pub fn to_presentation(&self) -> String {
+        format!("{} {} {} {}", self.priority, self.weight, self.port, name(&self.target))
+    }
 }
 
 
@@ -115,4 +133,22 @@ mod test {
         assert_eq!(SRV::read(23, &mut Cursor::new(buf)),
                    Err(WireError::IO));
     }
+
+    #[test]
+    fn round_trip() {
+        let buf = &[
+            0x00, 0x01,  // priority
+            0x00, 0x01,  // weight
+            0x92, 0x7c,  // port
+            0x03, 0x61, 0x74, 0x61, 0x05, 0x6c, 0x6f, 0x63, 0x61, 0x6c, 0x04,
+            0x6e, 0x6f, 0x64, 0x65, 0x03, 0x64, 0x63, 0x31, 0x06, 0x63, 0x6f,
+            0x6e, 0x73, 0x75, 0x6c,  // target
+            0x00,  // target terminator
+        ];
+
+        let record = SRV::read(buf.len() as _, &mut Cursor::new(buf)).unwrap();
+        let mut bytes = Vec::new();
+        record.write(&mut bytes).unwrap();
+        assert_eq!(bytes, buf);
+    }
 }
@@ -1,5 +1,6 @@
 use log::*;
 
+use crate::presentation::hex;
 use crate::wire::*;
 
 
@@ -10,7 +11,8 @@ use crate::wire::*;
 ///
 /// - [RFC 4255](https://tools.ietf.org/html/rfc4255) — Using DNS to Securely
 ///   Publish Secure Shell (SSH) Key Fingerprints (January 2006)
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SSHFP {
 
     /// The algorithm of the public key. This is a number with several defined
@@ -50,6 +52,13 @@ impl Wire for SSHFP {
 
         Ok(Self { algorithm, fingerprint_type, fingerprint })
     }
+
+    fn write(&self, bytes: &mut Vec<u8>) -> io::Result<()> {
+        bytes.write_u8(self.algorithm)?;
+        bytes.write_u8(self.fingerprint_type)?;
+        bytes.extend_from_slice(&self.fingerprint);
+        Ok(())
+    }
 }
 
 impl SSHFP {
@@ -60,6 +69,12 @@ impl SSHFP {
             .map(|byte| format!("{:02x}", byte))
             .collect()
     }
+
+    /// Returns the record’s data in presentation format, with the fingerprint
+    /// in hexadecimal.
+    pub fn to_presentation(&self) -> String {
+        format!("{} {} {}", self.algorithm, self.fingerprint_type, hex(&self.fingerprint))
+    }
 }
 
 
@@ -137,4 +152,18 @@ mod test {
         assert_eq!(sshfp.hex_fingerprint(),
                    String::from("f348cdc9"));
     }
+
+    #[test]
+    fn round_trip() {
+        let buf = &[
+            0x01,  // algorithm
+            0x01,  // fingerprint type
+            0x21, 0x22, 0x23, 0x24, 0x25, 0x26,  // a short fingerprint
+        ];
+
+        let record = SSHFP::read(buf.len() as _, &mut Cursor::new(buf)).unwrap();
+        let mut bytes = Vec::new();
+        record.write(&mut bytes).unwrap();
+        assert_eq!(bytes, buf);
+    }
 }
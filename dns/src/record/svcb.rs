@@ -0,0 +1,779 @@
+use std::convert::TryFrom;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use log::*;
+
+use crate::presentation::{name, quoted};
+use crate::strings::{Labels, ReadLabels, WriteLabels};
+use crate::wire::*;
+
+
+/// A **SVCB** _(service binding)_ record, which tells clients about the
+/// alternative endpoints for a service, along with the parameters they need
+/// to connect to each one.
+///
+/// # References
+///
+/// - [RFC 9460](https://tools.ietf.org/html/rfc9460) — Service Binding and
+///   Parameter Specification via the DNS (SVCB and HTTPS Resource Records)
+///   (November 2023)
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SVCB {
+
+    /// The priority of this record. Zero means this record is in “alias
+    /// mode”, and the other values are for “service mode”, with lower values
+    /// being preferred.
+    pub priority: u16,
+
+    /// The domain name of the alternative endpoint, or of the name to alias
+    /// to in alias mode.
+    pub target: Labels,
+
+    /// The list of parameters that clients must use to connect.
+    pub parameters: Vec<SvcParam>,
+}
+
+/// An **HTTPS** record, which is a SVCB record specifically for HTTPS
+/// origins. It uses the same wire format as the SVCB record.
+///
+/// # References
+///
+/// - [RFC 9460 §9](https://tools.ietf.org/html/rfc9460#section-9) — Service
+///   Binding and Parameter Specification via the DNS (SVCB and HTTPS Resource
+///   Records) (November 2023)
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HTTPS(pub SVCB);
+
+/// One of the parameters in a SVCB or HTTPS record.
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SvcParam {
+
+    /// The keys of the parameters that clients must understand in order to
+    /// use this record.
+    Mandatory(Vec<u16>),
+
+    /// The ALPN protocol identifiers supported by the endpoint.
+    Alpn(Vec<Box<[u8]>>),
+
+    /// The endpoint does _not_ support the default protocol.
+    NoDefaultAlpn,
+
+    /// The port that the endpoint is listening on.
+    Port(u16),
+
+    /// IPv4 addresses that clients may use to reach the endpoint.
+    Ipv4Hint(Vec<Ipv4Addr>),
+
+    /// The Encrypted ClientHello configuration list.
+    Ech(EchConfigList),
+
+    /// IPv6 addresses that clients may use to reach the endpoint.
+    Ipv6Hint(Vec<Ipv6Addr>),
+
+    /// The URI template for DNS-over-HTTPS queries.
+    DohPath(Box<[u8]>),
+
+    /// A parameter with a key that we don’t recognise.
+    Other {
+
+        /// The number of the parameter key.
+        key: u16,
+
+        /// The undecodable bytes of the parameter value.
+        value: Vec<u8>,
+    },
+}
+
+impl Wire for SVCB {
+    const NAME: &'static str = "SVCB";
+    const RR_TYPE: u16 = 64;
+
+    #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+    fn read(stated_length: u16, c: &mut Cursor<&[u8]>) -> Result<Self, WireError> {
+        let priority = c.read_u16::<BigEndian>()?;
+        trace!("Parsed priority -> {:?}", priority);
+
+        let (target, target_length) = c.read_labels()?;
+        trace!("Parsed target -> {:?}", target);
+
+        let length_after_labels = 2 + target_length;
+        if length_after_labels > stated_length {
+            return Err(WireError::WrongLabelLength { stated_length, length_after_labels });
+        }
+
+        let mut length_read = u32::from(length_after_labels);
+        let mut parameters = Vec::new();
+
+        while length_read < u32::from(stated_length) {
+            let key = c.read_u16::<BigEndian>()?;
+            trace!("Parsed parameter key -> {:?}", key);
+
+            let value_length = c.read_u16::<BigEndian>()?;
+            trace!("Parsed parameter value length -> {:?}", value_length);
+
+            length_read += 2 + 2 + u32::from(value_length);
+            if length_read > u32::from(stated_length) {
+                let mandated_length = MandatedLength::AtLeast(u16::try_from(length_read).unwrap_or(u16::MAX));
+                return Err(WireError::WrongRecordLength { stated_length, mandated_length });
+            }
+
+            let mut value = vec![0_u8; usize::from(value_length)];
+            c.read_exact(&mut value)?;
+            trace!("Parsed parameter value -> {:#x?}", value);
+
+            parameters.push(SvcParam::from_key_and_value(key, value)?);
+        }
+
+        Ok(Self { priority, target, parameters })
+    }
+
+    fn write(&self, bytes: &mut Vec<u8>) -> io::Result<()> {
+        bytes.write_u16::<BigEndian>(self.priority)?;
+        bytes.write_labels(&self.target)?;
+
+        for parameter in &self.parameters {
+            let value = parameter.value_bytes()?;
+            let value_length = u16::try_from(value.len())
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "service parameter is longer than 65535 bytes"))?;
+
+            bytes.write_u16::<BigEndian>(parameter.key())?;
+            bytes.write_u16::<BigEndian>(value_length)?;
+            bytes.extend(value);
+        }
+
+        Ok(())
+    }
+}
+
+impl SVCB {
+
+    /// Returns the record’s data in presentation format, with each parameter
+    /// in its `key=value` form.
+    pub fn to_presentation(&self) -> String {
+        let mut parts = vec![ self.priority.to_string(), name(&self.target) ];
+        parts.extend(self.parameters.iter().map(SvcParam::to_presentation));
+        parts.join(" ")
+    }
+}
+
+impl Wire for HTTPS {
+    const NAME: &'static str = "HTTPS";
+    const RR_TYPE: u16 = 65;
+
+    #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+    fn read(stated_length: u16, c: &mut Cursor<&[u8]>) -> Result<Self, WireError> {
+        SVCB::read(stated_length, c).map(Self)
+    }
+
+    fn write(&self, bytes: &mut Vec<u8>) -> io::Result<()> {
+        self.0.write(bytes)
+    }
+}
+
+impl HTTPS {
+
+    /// Returns the record’s data in presentation format, which is the same as
+    /// a SVCB record’s.
+    pub fn to_presentation(&self) -> String {
+        self.0.to_presentation()
+    }
+}
+
+impl SvcParam {
+
+    /// Interprets the value of a parameter based on its key.
+    fn from_key_and_value(key: u16, value: Vec<u8>) -> Result<Self, WireError> {
+        let mut c = Cursor::new(&value[..]);
+
+        let param = match key {
+            0 => {
+                let keys = read_list(&value, 2, |c| c.read_u16::<BigEndian>())?;
+                Self::Mandatory(keys)
+            }
+            1 => {
+                let mut protocols = Vec::new();
+                while usize::try_from(c.position()).unwrap_or(usize::MAX) < value.len() {
+                    let length = c.read_u8()?;
+                    let mut protocol = vec![0_u8; usize::from(length)].into_boxed_slice();
+                    c.read_exact(&mut protocol)?;
+                    protocols.push(protocol);
+                }
+                Self::Alpn(protocols)
+            }
+            2 => {
+                read_exactly(&value, 0)?;
+                Self::NoDefaultAlpn
+            }
+            3 => {
+                read_exactly(&value, 2)?;
+                Self::Port(c.read_u16::<BigEndian>()?)
+            }
+            4 => {
+                let addresses = read_list(&value, 4, |c| c.read_u32::<BigEndian>().map(Ipv4Addr::from))?;
+                Self::Ipv4Hint(addresses)
+            }
+            5 => {
+                Self::Ech(EchConfigList(value))
+            }
+            6 => {
+                let addresses = read_list(&value, 16, |c| {
+                    let mut buf = [0_u8; 16];
+                    c.read_exact(&mut buf).map(|_| Ipv6Addr::from(buf))
+                })?;
+                Self::Ipv6Hint(addresses)
+            }
+            7 => {
+                Self::DohPath(value.into_boxed_slice())
+            }
+            _ => {
+                Self::Other { key, value }
+            }
+        };
+
+        trace!("Parsed parameter -> {:?}", param);
+        Ok(param)
+    }
+
+    /// Returns the number of this parameter’s key.
+    pub fn key(&self) -> u16 {
+        match self {
+            Self::Mandatory(_)     => 0,
+            Self::Alpn(_)          => 1,
+            Self::NoDefaultAlpn    => 2,
+            Self::Port(_)          => 3,
+            Self::Ipv4Hint(_)      => 4,
+            Self::Ech(_)           => 5,
+            Self::Ipv6Hint(_)      => 6,
+            Self::DohPath(_)       => 7,
+            Self::Other { key, .. } => *key,
+        }
+    }
+
+    /// Returns this parameter in the `key=value` form it is written in
+    /// zone files, with lists separated by commas and strings quoted. The
+    /// `no-default-alpn` parameter has no value, so it is just the key.
+    pub fn to_presentation(&self) -> String {
+        let key = svc_param_key_name(self.key());
+
+        match self {
+            Self::Mandatory(keys) => {
+                let names = keys.iter().map(|k| svc_param_key_name(*k)).collect::<Vec<_>>();
+                format!("{}={}", key, names.join(","))
+            }
+            Self::Alpn(protocols) => {
+                let protocols = protocols.iter().map(|p| &p[..]).collect::<Vec<_>>();
+                format!("{}={}", key, quoted(&protocols.join(&b',')))
+            }
+            Self::NoDefaultAlpn => {
+                key
+            }
+            Self::Port(port) => {
+                format!("{}={}", key, port)
+            }
+            Self::Ipv4Hint(addresses) => {
+                let addresses = addresses.iter().map(ToString::to_string).collect::<Vec<_>>();
+                format!("{}={}", key, addresses.join(","))
+            }
+            Self::Ech(list) => {
+                format!("{}={}", key, list.base64())
+            }
+            Self::Ipv6Hint(addresses) => {
+                let addresses = addresses.iter().map(ToString::to_string).collect::<Vec<_>>();
+                format!("{}={}", key, addresses.join(","))
+            }
+            Self::DohPath(path) => {
+                format!("{}={}", key, quoted(path))
+            }
+            Self::Other { value, .. } => {
+                format!("{}={}", key, quoted(value))
+            }
+        }
+    }
+
+    /// Returns the bytes of this parameter’s value, in the form that it gets
+    /// read from.
+    fn value_bytes(&self) -> io::Result<Vec<u8>> {
+        let mut value = Vec::new();
+
+        match self {
+            Self::Mandatory(keys) => {
+                for key in keys {
+                    value.write_u16::<BigEndian>(*key)?;
+                }
+            }
+            Self::Alpn(protocols) => {
+                for protocol in protocols {
+                    write_character_string(&mut value, protocol)?;
+                }
+            }
+            Self::NoDefaultAlpn => {}
+            Self::Port(port) => {
+                value.write_u16::<BigEndian>(*port)?;
+            }
+            Self::Ipv4Hint(addresses) => {
+                for address in addresses {
+                    value.extend_from_slice(&address.octets());
+                }
+            }
+            Self::Ech(list) => {
+                value.extend_from_slice(&list.0);
+            }
+            Self::Ipv6Hint(addresses) => {
+                for address in addresses {
+                    value.extend_from_slice(&address.octets());
+                }
+            }
+            Self::DohPath(path) => {
+                value.extend_from_slice(path);
+            }
+            Self::Other { value: other, .. } => {
+                value.extend_from_slice(other);
+            }
+        }
+
+        Ok(value)
+    }
+}
+
+/// Returns the presentation name of the parameter key with the given number.
+pub fn svc_param_key_name(key: u16) -> String {
+    match key {
+        0 => "mandatory".into(),
+        1 => "alpn".into(),
+        2 => "no-default-alpn".into(),
+        3 => "port".into(),
+        4 => "ipv4hint".into(),
+        5 => "ech".into(),
+        6 => "ipv6hint".into(),
+        7 => "dohpath".into(),
+        _ => format!("key{}", key),
+    }
+}
+
+/// Checks that a parameter value has exactly the given length.
+fn read_exactly(value: &[u8], length: u16) -> Result<(), WireError> {
+    if value.len() == usize::from(length) {
+        Ok(())
+    }
+    else {
+        let stated_length = u16::try_from(value.len()).unwrap_or(u16::MAX);
+        let mandated_length = MandatedLength::Exactly(length);
+        Err(WireError::WrongRecordLength { stated_length, mandated_length })
+    }
+}
+
+/// Reads a parameter value that consists of a non-empty list of fixed-size
+/// items.
+fn read_list<T>(value: &[u8], item_size: u16, read_item: impl Fn(&mut Cursor<&[u8]>) -> io::Result<T>) -> Result<Vec<T>, WireError> {
+    if value.is_empty() || value.len() % usize::from(item_size) != 0 {
+        let stated_length = u16::try_from(value.len()).unwrap_or(u16::MAX);
+        let mandated_length = MandatedLength::AtLeast(item_size);
+        return Err(WireError::WrongRecordLength { stated_length, mandated_length });
+    }
+
+    let mut c = Cursor::new(value);
+    let mut items = Vec::new();
+    for _ in 0 .. value.len() / usize::from(item_size) {
+        items.push(read_item(&mut c)?);
+    }
+
+    Ok(items)
+}
+
+
+/// The value of the Encrypted ClientHello parameter, which is an
+/// `ECHConfigList` structure. It is kept as bytes when the record is read, so
+/// that a list we cannot decode does not stop the rest of the record from
+/// being displayed.
+///
+/// # References
+///
+/// - [draft-ietf-tls-esni](https://tools.ietf.org/html/draft-ietf-tls-esni-18#section-4) —
+///   TLS Encrypted Client Hello (March 2024)
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EchConfigList(pub Vec<u8>);
+
+/// One of the configurations in an `ECHConfigList`.
+#[derive(PartialEq, Debug, Clone)]
+pub struct EchConfig {
+
+    /// The version of the ECH configuration structure.
+    pub version: u16,
+
+    /// The decoded contents of the configuration, if it uses a version that
+    /// we know how to decode.
+    pub contents: Option<EchConfigContents>,
+}
+
+/// The contents of a version `0xfe0d` ECH configuration.
+#[derive(PartialEq, Debug, Clone)]
+pub struct EchConfigContents {
+
+    /// The identifier that the client sends back to pick this configuration.
+    pub config_id: u8,
+
+    /// The HPKE key encapsulation mechanism of the public key.
+    pub kem_id: u16,
+
+    /// The HPKE public key, as unencoded bytes.
+    pub public_key: Vec<u8>,
+
+    /// The HPKE cipher suites that the server supports.
+    pub cipher_suites: Vec<HpkeCipherSuite>,
+
+    /// The longest server name that the server expects clients to use, used
+    /// to pad the ClientHello.
+    pub maximum_name_length: u8,
+
+    /// The name that is sent in the outer, unencrypted ClientHello.
+    pub public_name: Box<[u8]>,
+
+    /// Any extensions to the configuration, as unencoded bytes.
+    pub extensions: Vec<u8>,
+}
+
+/// A pair of HPKE algorithms that an ECH configuration supports.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub struct HpkeCipherSuite {
+
+    /// The key derivation function.
+    pub kdf_id: u16,
+
+    /// The authenticated encryption algorithm.
+    pub aead_id: u16,
+}
+
+/// The only ECH configuration version that we know how to decode.
+const ECH_VERSION: u16 = 0xfe0d;
+
+impl EchConfigList {
+
+    /// The base64-encoded configuration list, as it appears in zone files.
+    pub fn base64(&self) -> String {
+        base64::encode(&self.0)
+    }
+
+    /// Decodes the configurations in the list. Configurations with versions
+    /// we do not understand are still returned, but without their contents.
+    pub fn configs(&self) -> Result<Vec<EchConfig>, WireError> {
+        read_ech_configs(&self.0)
+    }
+}
+
+fn read_ech_configs(value: &[u8]) -> Result<Vec<EchConfig>, WireError> {
+    let mut c = Cursor::new(value);
+
+    let list_length = c.read_u16::<BigEndian>()?;
+    trace!("Parsed ECH config list length -> {:?}", list_length);
+
+    if usize::from(list_length) + 2 != value.len() {
+        let stated_length = u16::try_from(value.len()).unwrap_or(u16::MAX);
+        let mandated_length = MandatedLength::Exactly(list_length.saturating_add(2));
+        return Err(WireError::WrongRecordLength { stated_length, mandated_length });
+    }
+
+    let mut configs = Vec::new();
+    while usize::try_from(c.position()).unwrap_or(usize::MAX) < value.len() {
+        let version = c.read_u16::<BigEndian>()?;
+        trace!("Parsed ECH config version -> {:#x?}", version);
+
+        let length = c.read_u16::<BigEndian>()?;
+        let mut contents_bytes = vec![0_u8; usize::from(length)];
+        c.read_exact(&mut contents_bytes)?;
+
+        let contents = if version == ECH_VERSION {
+            Some(EchConfigContents::read(&contents_bytes)?)
+        }
+        else {
+            debug!("Skipping ECH config with unknown version {:#x?}", version);
+            None
+        };
+
+        configs.push(EchConfig { version, contents });
+    }
+
+    Ok(configs)
+}
+
+impl EchConfigContents {
+    fn read(bytes: &[u8]) -> Result<Self, WireError> {
+        let mut c = Cursor::new(bytes);
+
+        let config_id = c.read_u8()?;
+        trace!("Parsed ECH config ID -> {:?}", config_id);
+
+        let kem_id = c.read_u16::<BigEndian>()?;
+        trace!("Parsed ECH KEM ID -> {:#x?}", kem_id);
+
+        let public_key_length = c.read_u16::<BigEndian>()?;
+        let mut public_key = vec![0_u8; usize::from(public_key_length)];
+        c.read_exact(&mut public_key)?;
+        trace!("Parsed ECH public key -> {:#x?}", public_key);
+
+        let cipher_suites_length = c.read_u16::<BigEndian>()?;
+        if cipher_suites_length == 0 || cipher_suites_length % 4 != 0 {
+            warn!("Length of ECH cipher suites is not a multiple of four ({})", cipher_suites_length);
+            let mandated_length = MandatedLength::AtLeast(4);
+            return Err(WireError::WrongRecordLength { stated_length: cipher_suites_length, mandated_length });
+        }
+
+        let mut cipher_suites = Vec::new();
+        for _ in 0 .. cipher_suites_length / 4 {
+            let kdf_id = c.read_u16::<BigEndian>()?;
+            let aead_id = c.read_u16::<BigEndian>()?;
+            cipher_suites.push(HpkeCipherSuite { kdf_id, aead_id });
+        }
+        trace!("Parsed ECH cipher suites -> {:?}", cipher_suites);
+
+        let maximum_name_length = c.read_u8()?;
+        trace!("Parsed ECH maximum name length -> {:?}", maximum_name_length);
+
+        let public_name_length = c.read_u8()?;
+        let mut public_name = vec![0_u8; usize::from(public_name_length)].into_boxed_slice();
+        c.read_exact(&mut public_name)?;
+        trace!("Parsed ECH public name -> {:?}", String::from_utf8_lossy(&public_name));
+
+        let extensions_length = c.read_u16::<BigEndian>()?;
+        let mut extensions = vec![0_u8; usize::from(extensions_length)];
+        c.read_exact(&mut extensions)?;
+        trace!("Parsed ECH extensions -> {:#x?}", extensions);
+
+        Ok(Self { config_id, kem_id, public_key, cipher_suites, maximum_name_length, public_name, extensions })
+    }
+
+    /// The base64-encoded HPKE public key.
+    pub fn base64_public_key(&self) -> String {
+        base64::encode(&self.public_key)
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parses_alias_mode() {
+        let buf = &[
+            0x00, 0x00,  // priority
+            0x03, 0x64, 0x6f, 0x67, 0x00,  // target
+        ];
+
+        assert_eq!(SVCB::read(buf.len() as _, &mut Cursor::new(buf)).unwrap(),
+                   SVCB {
+                       priority: 0,
+                       target: Labels::encode("dog").unwrap(),
+                       parameters: vec![],
+                   });
+    }
+
+    #[test]
+    fn parses_service_mode() {
+        let buf = &[
+            0x00, 0x01,  // priority
+            0x00,  // target
+            0x00, 0x01, 0x00, 0x06, 0x02, 0x68, 0x32, 0x02, 0x68, 0x33,  // alpn
+            0x00, 0x02, 0x00, 0x00,  // no-default-alpn
+            0x00, 0x03, 0x00, 0x02, 0x20, 0xfb,  // port
+            0x00, 0x04, 0x00, 0x04, 0xc0, 0x00, 0x02, 0x01,  // ipv4hint
+            0xfd, 0xe9, 0x00, 0x01, 0x99,  // unknown key
+        ];
+
+        assert_eq!(HTTPS::read(buf.len() as _, &mut Cursor::new(buf)).unwrap(),
+                   HTTPS(SVCB {
+                       priority: 1,
+                       target: Labels::root(),
+                       parameters: vec![
+                           SvcParam::Alpn(vec![ Box::new(*b"h2"), Box::new(*b"h3") ]),
+                           SvcParam::NoDefaultAlpn,
+                           SvcParam::Port(8443),
+                           SvcParam::Ipv4Hint(vec![ Ipv4Addr::new(192, 0, 2, 1) ]),
+                           SvcParam::Other { key: 65001, value: vec![ 0x99 ] },
+                       ],
+                   }));
+    }
+
+    #[test]
+    fn parameter_overflows() {
+        let buf = &[
+            0x00, 0x01,  // priority
+            0x00,  // target
+            0x00, 0x03, 0x00, 0x02, 0x20, 0xfb,  // port
+        ];
+
+        assert_eq!(SVCB::read(7, &mut Cursor::new(buf)),
+                   Err(WireError::WrongRecordLength { stated_length: 7, mandated_length: MandatedLength::AtLeast(9) }));
+    }
+
+    #[test]
+    fn wrong_port_length() {
+        let buf = &[
+            0x00, 0x01,  // priority
+            0x00,  // target
+            0x00, 0x03, 0x00, 0x01, 0x20,  // port
+        ];
+
+        assert_eq!(SVCB::read(buf.len() as _, &mut Cursor::new(buf)),
+                   Err(WireError::WrongRecordLength { stated_length: 1, mandated_length: MandatedLength::Exactly(2) }));
+    }
+
+    #[test]
+    fn record_empty() {
+        assert_eq!(SVCB::read(0, &mut Cursor::new(&[])),
+                   Err(WireError::IO));
+    }
+
+    #[test]
+    fn buffer_ends_abruptly() {
+        let buf = &[
+            0x00, 0x01,  // priority
+            0x00,  // target
+            0x00, 0x03, 0x00,  // half a parameter
+        ];
+
+        assert_eq!(SVCB::read(9, &mut Cursor::new(buf)),
+                   Err(WireError::IO));
+    }
+
+    #[test]
+    fn parses_ech_config_list() {
+        let buf = &[
+            0x00, 0x20,  // list length
+            0xfe, 0x0d,  // version
+            0x00, 0x18,  // contents length
+            0x2a,  // config ID
+            0x00, 0x20,  // KEM ID
+            0x00, 0x02, 0xab, 0xcd,  // public key
+            0x00, 0x04, 0x00, 0x01, 0x00, 0x01,  // cipher suites
+            0x00,  // maximum name length
+            0x07, 0x65, 0x78, 0x61, 0x6d, 0x70, 0x6c, 0x65,  // public name
+            0x00, 0x00,  // extensions
+            0xfe, 0x0c,  // unknown version
+            0x00, 0x00,  // contents length
+        ];
+
+        assert_eq!(EchConfigList(buf.to_vec()).configs().unwrap(),
+                   vec![
+                       EchConfig {
+                           version: 0xfe0d,
+                           contents: Some(EchConfigContents {
+                               config_id: 42,
+                               kem_id: 0x0020,
+                               public_key: vec![ 0xab, 0xcd ],
+                               cipher_suites: vec![ HpkeCipherSuite { kdf_id: 1, aead_id: 1 } ],
+                               maximum_name_length: 0,
+                               public_name: Box::new(*b"example"),
+                               extensions: vec![],
+                           }),
+                       },
+                       EchConfig {
+                           version: 0xfe0c,
+                           contents: None,
+                       },
+                   ]);
+    }
+
+    #[test]
+    fn ech_config_list_wrong_length() {
+        let buf = &[
+            0x00, 0x10,  // list length
+            0xfe, 0x0d,  // version
+        ];
+
+        assert_eq!(EchConfigList(buf.to_vec()).configs(),
+                   Err(WireError::WrongRecordLength { stated_length: 4, mandated_length: MandatedLength::Exactly(18) }));
+    }
+
+    #[test]
+    fn ech_config_truncated() {
+        let buf = &[
+            0x00, 0x06,  // list length
+            0xfe, 0x0d,  // version
+            0x00, 0x02,  // contents length
+            0x2a, 0x00,  // config ID and half a KEM ID
+        ];
+
+        assert_eq!(EchConfigList(buf.to_vec()).configs(),
+                   Err(WireError::IO));
+    }
+
+    #[test]
+    fn ech_cipher_suites_wrong_length() {
+        let buf = &[
+            0x00, 0x1d,  // list length
+            0xfe, 0x0d,  // version
+            0x00, 0x19,  // contents length
+            0x2a,  // config ID
+            0x00, 0x20,  // KEM ID
+            0x00, 0x02, 0xab, 0xcd,  // public key
+            0x00, 0x05, 0x00, 0x01, 0x00, 0x01, 0x00,  // cipher suites, one byte too long
+            0x00,  // maximum name length
+            0x07, 0x65, 0x78, 0x61, 0x6d, 0x70, 0x6c, 0x65,  // public name
+            0x00, 0x00,  // extensions
+        ];
+
+        assert_eq!(EchConfigList(buf.to_vec()).configs(),
+                   Err(WireError::WrongRecordLength { stated_length: 5, mandated_length: MandatedLength::AtLeast(4) }));
+    }
+
+    #[test]
+    fn round_trip_alias_mode() {
+        let buf = &[
+            0x00, 0x00,  // priority
+            0x03, 0x64, 0x6f, 0x67, 0x00,  // target
+        ];
+
+        let record = SVCB::read(buf.len() as _, &mut Cursor::new(buf)).unwrap();
+        let mut bytes = Vec::new();
+        record.write(&mut bytes).unwrap();
+        assert_eq!(bytes, buf);
+    }
+
+    #[test]
+    fn round_trip_service_mode() {
+        let buf = &[
+            0x00, 0x01,  // priority
+            0x00,  // target
+            0x00, 0x00, 0x00, 0x02, 0x00, 0x01,  // mandatory
+            0x00, 0x01, 0x00, 0x06, 0x02, 0x68, 0x32, 0x02, 0x68, 0x33,  // alpn
+            0x00, 0x02, 0x00, 0x00,  // no-default-alpn
+            0x00, 0x03, 0x00, 0x02, 0x20, 0xfb,  // port
+            0x00, 0x04, 0x00, 0x04, 0xc0, 0x00, 0x02, 0x01,  // ipv4hint
+            0x00, 0x05, 0x00, 0x02, 0x00, 0x00,  // ech
+            0x00, 0x06, 0x00, 0x10, 0x20, 0x01, 0x0d, 0xb8, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01,  // ipv6hint
+            0x00, 0x07, 0x00, 0x02, 0x2f, 0x71,  // dohpath
+            0xfd, 0xe9, 0x00, 0x01, 0x99,  // unknown key
+        ];
+
+        let record = HTTPS::read(buf.len() as _, &mut Cursor::new(buf)).unwrap();
+        let mut bytes = Vec::new();
+        record.write(&mut bytes).unwrap();
+        assert_eq!(bytes, buf);
+    }
+
+    #[test]
+    fn presentation() {
+        let record = SVCB {
+            priority: 1,
+            target: Labels::root(),
+            parameters: vec![
+                SvcParam::Mandatory(vec![ 1, 3 ]),
+                SvcParam::Alpn(vec![ Box::new(*b"h2"), Box::new(*b"h3") ]),
+                SvcParam::NoDefaultAlpn,
+                SvcParam::Port(8443),
+                SvcParam::Ipv4Hint(vec![ Ipv4Addr::new(192, 0, 2, 1), Ipv4Addr::new(192, 0, 2, 2) ]),
+                SvcParam::DohPath(Box::new(*b"/dns-query{?dns}")),
+                SvcParam::Other { key: 65001, value: vec![ 0x99 ] },
+            ],
+        };
+
+        assert_eq!(record.to_presentation(),
+                   "1 . mandatory=alpn,port alpn=\"h2,h3\" no-default-alpn port=8443 ipv4hint=192.0.2.1,192.0.2.2 dohpath=\"/dns-query{?dns}\" key65001=\"\\153\"");
+    }
+}
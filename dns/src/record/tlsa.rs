@@ -1,5 +1,6 @@
 use log::*;
 
+use crate::presentation::hex;
 use crate::wire::*;
 
 
@@ -11,7 +12,8 @@ use crate::wire::*;
 /// - [RFC 6698](https://tools.ietf.org/html/rfc6698) — The DNS-Based
 ///   Authentication of Named Entities (DANE) Transport Layer Security
 ///   Protocol: TLSA (August 2012)
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TLSA {
 
     /// A number representing the purpose of the certificate.
@@ -58,6 +60,14 @@ impl Wire for TLSA {
 
         Ok(Self { certificate_usage, selector, matching_type, certificate_data })
     }
+
+    fn write(&self, bytes: &mut Vec<u8>) -> io::Result<()> {
+        bytes.write_u8(self.certificate_usage)?;
+        bytes.write_u8(self.selector)?;
+        bytes.write_u8(self.matching_type)?;
+        bytes.extend_from_slice(&self.certificate_data);
+        Ok(())
+    }
 }
 
 impl TLSA {
@@ -68,6 +78,17 @@ impl TLSA {
             .map(|byte| format!("{:02x}", byte))
             .collect()
     }
+
+    /// Returns the record’s data in presentation format, with the certificate
+    /// data in hexadecimal.
+    pub fn to_presentation(&self) -> String {
+        format!("{} {} {} {}",
+            self.certificate_usage,
+            self.selector,
+            self.matching_type,
+            hex(&self.certificate_data),
+        )
+    }
 }
 
 
@@ -138,5 +159,20 @@ mod test {
         assert_eq!(TLSA::read(6, &mut Cursor::new(buf)),
                    Err(WireError::IO));
     }
+
+    #[test]
+    fn round_trip() {
+        let buf = &[
+            0x03,  // certificate usage
+            0x01,  // selector
+            0x01,  // matching type
+            0x05, 0x95, 0x98, 0x11, 0x22, 0x33,  // data
+        ];
+
+        let record = TLSA::read(buf.len() as _, &mut Cursor::new(buf)).unwrap();
+        let mut bytes = Vec::new();
+        record.write(&mut bytes).unwrap();
+        assert_eq!(bytes, buf);
+    }
 }
 
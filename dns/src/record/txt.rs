@@ -1,5 +1,6 @@
 use log::*;
 
+use crate::presentation::{quoted, hex};
 use crate::wire::*;
 
 
@@ -7,20 +8,49 @@ use crate::wire::*;
 ///
 /// # Encoding
 ///
-/// The text encoding is not specified, but this crate treats it as UTF-8.
-/// Invalid bytes are turned into the replacement character.
+/// The text encoding is not specified, so the messages are kept as bytes.
+/// The `decode` method turns them into text, with a `TextDecoding` saying
+/// what to do with bytes that are not valid UTF-8.
 ///
 /// # References
 ///
 /// - [RFC 1035 §3.3.14](https://tools.ietf.org/html/rfc1035) — Domain Names,
 ///   Implementation and Specification (November 1987)
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TXT {
 
     /// The messages contained in the record.
     pub messages: Vec<Box<[u8]>>,
 }
 
+/// How to turn the bytes of a TXT record’s messages into text.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum TextDecoding {
+
+    /// Decode each message as UTF-8, and write any message that is not
+    /// valid UTF-8 in hexadecimal instead.
+    Strict,
+
+    /// Decode each message as UTF-8, turning invalid bytes into the
+    /// replacement character.
+    Lossy,
+
+    /// Write every message in hexadecimal, whatever is in it.
+    Hex,
+}
+
+/// One of a TXT record’s messages, after being decoded.
+#[derive(PartialEq, Debug, Clone)]
+pub enum DecodedMessage {
+
+    /// The message as text.
+    Text(String),
+
+    /// The bytes of the message in hexadecimal.
+    Hex(String),
+}
+
 impl Wire for TXT {
     const NAME: &'static str = "TXT";
     const RR_TYPE: u16 = 16;
@@ -68,6 +98,60 @@ impl Wire for TXT {
             Err(WireError::WrongLabelLength { stated_length, length_after_labels: total_length })
         }
     }
+
+    fn write(&self, bytes: &mut Vec<u8>) -> io::Result<()> {
+        for message in &self.messages {
+            for chunk in message.chunks(255) {
+                write_character_string(bytes, chunk)?;
+            }
+
+            // A chunk of exactly 255 bytes means the message continues into
+            // the next one, so messages that fill their last chunk need an
+            // empty chunk after it to end them.
+            if message.len() % 255 == 0 {
+                bytes.write_u8(0)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl TXT {
+
+    /// Returns the record’s data in presentation format, with each of its
+    /// strings quoted.
+    pub fn to_presentation(&self) -> String {
+        self.messages.iter()
+            .map(|message| quoted(message))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Decodes each of the record’s messages into text, using the given
+    /// method.
+    pub fn decode(&self, decoding: TextDecoding) -> Vec<DecodedMessage> {
+        self.messages.iter()
+            .map(|message| decode_message(message, decoding))
+            .collect()
+    }
+}
+
+fn decode_message(message: &[u8], decoding: TextDecoding) -> DecodedMessage {
+    match decoding {
+        TextDecoding::Strict => {
+            match std::str::from_utf8(message) {
+                Ok(text)  => DecodedMessage::Text(text.into()),
+                Err(_)    => DecodedMessage::Hex(hex(message)),
+            }
+        }
+        TextDecoding::Lossy => {
+            DecodedMessage::Text(String::from_utf8_lossy(message).into_owned())
+        }
+        TextDecoding::Hex => {
+            DecodedMessage::Hex(hex(message))
+        }
+    }
 }
 
 
@@ -228,4 +312,62 @@ mod test {
         assert_eq!(TXT::read(23, &mut Cursor::new(buf)),
                    Err(WireError::IO));
     }
+
+    #[test]
+    fn round_trip() {
+        let buf = &[
+            0x06,  // message chunk length
+            0x74, 0x78, 0x74, 0x20, 0x6d, 0x65,  // message chunk
+            0x06,  // message chunk length
+            0x79, 0x61, 0x20, 0x62, 0x65, 0x62,  // message chunk
+        ];
+
+        let record = TXT::read(buf.len() as _, &mut Cursor::new(buf)).unwrap();
+        let mut bytes = Vec::new();
+        record.write(&mut bytes).unwrap();
+        assert_eq!(bytes, buf);
+    }
+
+    #[test]
+    fn round_trip_long_messages() {
+        let record = TXT {
+            messages: vec![
+                vec![ 0x41; 259 ].into_boxed_slice(),
+                vec![ 0x42; 255 ].into_boxed_slice(),
+                Box::new(*b""),
+            ],
+        };
+
+        let mut bytes = Vec::new();
+        record.write(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), 1 + 255 + 1 + 4 + 1 + 255 + 1 + 1);
+        assert_eq!(TXT::read(bytes.len() as _, &mut Cursor::new(&bytes)).unwrap(),
+                   record);
+    }
+
+    #[test]
+    fn decode_strict() {
+        let record = TXT { messages: vec![ Box::new(*b"v=DKIM1"), Box::new([ 0x6b, 0xff, 0x00 ]) ] };
+        assert_eq!(record.decode(TextDecoding::Strict), vec![
+            DecodedMessage::Text("v=DKIM1".into()),
+            DecodedMessage::Hex("6BFF00".into()),
+        ]);
+    }
+
+    #[test]
+    fn decode_lossy() {
+        let record = TXT { messages: vec![ Box::new([ 0x6b, 0xff ]) ] };
+        assert_eq!(record.decode(TextDecoding::Lossy), vec![
+            DecodedMessage::Text("k\u{fffd}".into()),
+        ]);
+    }
+
+    #[test]
+    fn decode_hex() {
+        let record = TXT { messages: vec![ Box::new(*b"txt"), Box::new(*b"") ] };
+        assert_eq!(record.decode(TextDecoding::Hex), vec![
+            DecodedMessage::Hex("747874".into()),
+            DecodedMessage::Hex("".into()),
+        ]);
+    }
 }
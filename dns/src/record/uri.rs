@@ -1,5 +1,6 @@
 use log::*;
 
+use crate::presentation::quoted;
 use crate::wire::*;
 
 
@@ -12,7 +13,8 @@ use crate::wire::*;
 ///   Identifier (URI) DNS Resource Record (June 2015)
 /// - [RFC 3986](https://tools.ietf.org/html/rfc3986) — Uniform Resource
 ///   Identifier (URI): Generic Syntax (January 2005)
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct URI {
 
     /// The priority of the URI. Clients are supposed to contact the URI with
@@ -53,6 +55,22 @@ impl Wire for URI {
 
         Ok(Self { priority, weight, target })
     }
+
+    fn write(&self, bytes: &mut Vec<u8>) -> io::Result<()> {
+        bytes.write_u16::<BigEndian>(self.priority)?;
+        bytes.write_u16::<BigEndian>(self.weight)?;
+        bytes.extend_from_slice(&self.target);
+        Ok(())
+    }
+}
+
+impl URI {
+
+    /// Returns the record’s data in presentation format, with the target as a
+    /// quoted string.
+    pub fn to_presentation(&self) -> String {
+        format!("{} {} {}", self.priority, self.weight, quoted(&self.target))
+    }
 }
 
 
@@ -120,4 +138,19 @@ mod test {
         assert_eq!(URI::read(23, &mut Cursor::new(buf)),
                    Err(WireError::IO));
     }
+
+    #[test]
+    fn round_trip() {
+        let buf = &[
+            0x00, 0x0A,  // priority
+            0x00, 0x10,  // weight
+            0x68, 0x74, 0x74, 0x70, 0x73, 0x3a, 0x2f, 0x2f, 0x72, 0x66, 0x63,
+            0x73, 0x2e, 0x69, 0x6f, 0x2f,  // uri
+        ];
+
+        let record = URI::read(buf.len() as _, &mut Cursor::new(buf)).unwrap();
+        let mut bytes = Vec::new();
+        record.write(&mut bytes).unwrap();
+        assert_eq!(bytes, buf);
+    }
 }
@@ -0,0 +1,204 @@
+//! Grouping records into sets with the same owner name, class, and type.
+
+use std::cmp::Ordering;
+
+use crate::record::{Record, RecordType};
+use crate::strings::Labels;
+use crate::types::*;
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+
+/// A **resource record set** is every record with the same owner name,
+/// class, and type. A set is what gets signed, cached, and transferred as
+/// a unit, so its records all share one TTL.
+///
+/// # References
+///
+/// - [RFC 2181 §5](https://tools.ietf.org/html/rfc2181#section-5) —
+///   Clarifications to the DNS Specification (July 1997)
+#[derive(PartialEq, Debug, Clone)]
+pub struct RRset {
+
+    /// The owner name of every record in the set.
+    pub owner: Labels,
+
+    /// The class of every record in the set.
+    pub qclass: QClass,
+
+    /// The type of every record in the set.
+    pub record_type: RecordType,
+
+    /// The TTL of the set. If the records were received with different
+    /// TTLs, this is the lowest of them.
+    pub ttl: u32,
+
+    /// The records in the set, in the order they were added.
+    pub records: Vec<Record>,
+}
+
+impl RRset {
+
+    /// Creates a new set with the given record as its only member.
+    pub fn new(owner: Labels, qclass: QClass, ttl: u32, record: Record) -> Self {
+        Self { owner, qclass, record_type: record.record_type(), ttl, records: vec![ record ] }
+    }
+
+    /// Whether a record with the given owner, class, and type belongs in
+    /// this set. Owner names are compared ignoring case.
+    pub fn matches(&self, owner: &Labels, qclass: QClass, record_type: RecordType) -> bool {
+        self.qclass == qclass
+            && self.record_type == record_type
+            && self.owner.canonical_cmp(owner) == Ordering::Equal
+    }
+
+    /// Adds a record to the set, lowering the set’s TTL to the record’s if
+    /// it is lower, as RFC 2181 says to when they differ.
+    pub fn push(&mut self, ttl: u32, record: Record) {
+        self.ttl = self.ttl.min(ttl);
+        self.records.push(record);
+    }
+
+    /// Groups the standard records in a section of a response into sets,
+    /// which appear in the order their first record did. OPT
+    /// pseudo-records are left out, and RRSIG records form sets of their
+    /// own, whatever types they cover.
+    pub fn group(answers: &[Answer]) -> Vec<Self> {
+        let mut rrsets: Vec<Self> = Vec::new();
+
+        for answer in answers {
+            if let Answer::Standard { qname, qclass, ttl, record } = answer {
+                let record_type = record.record_type();
+
+                match rrsets.iter_mut().find(|rrset| rrset.matches(qname, *qclass, record_type)) {
+                    Some(rrset)  => rrset.push(*ttl, record.clone()),
+                    None         => rrsets.push(Self::new(qname.clone(), *qclass, *ttl, record.clone())),
+                }
+            }
+        }
+
+        rrsets
+    }
+
+    /// Returns the set as separate answers, one for each record, all with
+    /// the set’s TTL.
+    pub fn to_answers(&self) -> Vec<Answer> {
+        self.records.iter()
+            .map(|record| Answer::Standard {
+                qname: self.owner.clone(),
+                qclass: self.qclass,
+                ttl: self.ttl,
+                record: record.clone(),
+            })
+            .collect()
+    }
+}
+
+
+impl Response {
+
+    /// Returns the records in the answer section grouped into sets.
+    pub fn answer_rrsets(&self) -> Vec<RRset> {
+        RRset::group(&self.answers)
+    }
+
+    /// Returns the records in the authority section grouped into sets.
+    pub fn authority_rrsets(&self) -> Vec<RRset> {
+        RRset::group(&self.authorities)
+    }
+
+    /// Returns the records in the additional section grouped into sets,
+    /// leaving out the OPT record.
+    pub fn additional_rrsets(&self) -> Vec<RRset> {
+        RRset::group(&self.additionals)
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::record::{A, AAAA};
+    use pretty_assertions::assert_eq;
+
+    fn a(owner: &str, ttl: u32, last: u8) -> Answer {
+        Answer::Standard {
+            qname: Labels::encode(owner).unwrap(),
+            qclass: QClass::IN,
+            ttl,
+            record: Record::A(A { address: [ 192, 0, 2, last ].into() }),
+        }
+    }
+
+    fn aaaa(owner: &str) -> Answer {
+        Answer::Standard {
+            qname: Labels::encode(owner).unwrap(),
+            qclass: QClass::IN,
+            ttl: 300,
+            record: Record::AAAA(AAAA { address: "2001:db8::1".parse().unwrap() }),
+        }
+    }
+
+    #[test]
+    fn groups_by_owner_and_type() {
+        let rrsets = RRset::group(&[
+            a("lookup.dog", 300, 1),
+            aaaa("lookup.dog"),
+            a("LOOKUP.dog", 300, 2),
+            a("www.lookup.dog", 300, 3),
+        ]);
+
+        assert_eq!(rrsets.len(), 3);
+        assert_eq!(rrsets[0].record_type, RecordType::A);
+        assert_eq!(rrsets[0].records.len(), 2);
+        assert_eq!(rrsets[1].record_type, RecordType::AAAA);
+        assert_eq!(rrsets[2].owner, Labels::encode("www.lookup.dog").unwrap());
+    }
+
+    #[test]
+    fn separates_classes() {
+        let chaos = Answer::Standard {
+            qname: Labels::encode("lookup.dog").unwrap(),
+            qclass: QClass::CH,
+            ttl: 300,
+            record: Record::A(A { address: [ 192, 0, 2, 2 ].into() }),
+        };
+
+        assert_eq!(RRset::group(&[ a("lookup.dog", 300, 1), chaos ]).len(), 2);
+    }
+
+    #[test]
+    fn lowest_ttl() {
+        let rrsets = RRset::group(&[ a("lookup.dog", 300, 1), a("lookup.dog", 60, 2), a("lookup.dog", 600, 3) ]);
+        assert_eq!(rrsets[0].ttl, 60);
+    }
+
+    #[test]
+    fn leaves_out_opt() {
+        let opt = Answer::Pseudo { qname: Labels::root(), opt: Request::additional_record() };
+        assert_eq!(RRset::group(&[ opt ]), vec![]);
+    }
+
+    #[test]
+    fn back_to_answers() {
+        let rrsets = RRset::group(&[ a("lookup.dog", 300, 1), a("lookup.dog", 60, 2) ]);
+        assert_eq!(rrsets[0].to_answers(), vec![ a("lookup.dog", 60, 1), a("lookup.dog", 60, 2) ]);
+    }
+
+    #[test]
+    fn from_response() {
+        let response = Response {
+            transaction_id: 0,
+            flags: Flags::standard_response(),
+            queries: Vec::new(),
+            answers: vec![ a("lookup.dog", 300, 1) ],
+            authorities: Vec::new(),
+            additionals: vec![ aaaa("ns.lookup.dog"), aaaa("ns.lookup.dog") ],
+        };
+
+        assert_eq!(response.answer_rrsets().len(), 1);
+        assert_eq!(response.authority_rrsets(), vec![]);
+        assert_eq!(response.additional_rrsets()[0].records.len(), 2);
+    }
+}
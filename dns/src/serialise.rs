@@ -0,0 +1,108 @@
+//! Serde implementations for the types that cannot derive them. Everything
+//! else in the crate derives `Serialize` and `Deserialize` when the
+//! `with_serde` feature is enabled, so responses can be stored and read
+//! back without going through the wire format.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::de::Error;
+
+use crate::record::UnknownQtype;
+use crate::strings::Labels;
+
+#[cfg(not(feature = "std"))]
+use alloc::string::String;
+
+
+/// Domain names are written as their dotted, escaped text, rather than as
+/// the list of segments they are stored as.
+impl Serialize for Labels {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Labels {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let input = String::deserialize(deserializer)?;
+        Self::encode(&input).map_err(|label| D::Error::custom(format_args!("invalid label {:?}", label)))
+    }
+}
+
+/// Unknown types are written as their type number, as the names of the
+/// types that dog has heard of are static strings that cannot be read back.
+/// Reading the number looks the name up again.
+impl Serialize for UnknownQtype {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u16(self.type_number())
+    }
+}
+
+impl<'de> Deserialize<'de> for UnknownQtype {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        u16::deserialize(deserializer).map(Self::from)
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    use crate::record::{Record, MX, TXT};
+    use crate::types::*;
+
+    #[test]
+    fn labels_as_strings() {
+        let labels = Labels::encode("dns.lookup.dog").unwrap();
+        assert_eq!(serde_json::to_string(&labels).unwrap(), "\"dns.lookup.dog.\"");
+        assert_eq!(serde_json::from_str::<Labels>("\"dns.lookup.dog.\"").unwrap(), labels);
+    }
+
+    #[test]
+    fn escaped_labels() {
+        let labels = Labels::encode("a\\.b.lookup.dog").unwrap();
+        let json = serde_json::to_string(&labels).unwrap();
+        assert_eq!(serde_json::from_str::<Labels>(&json).unwrap(), labels);
+    }
+
+    #[test]
+    fn unknown_types_as_numbers() {
+        assert_eq!(serde_json::to_string(&UnknownQtype::from(65280)).unwrap(), "65280");
+        assert_eq!(serde_json::from_str::<UnknownQtype>("99").unwrap(), UnknownQtype::from(99));
+    }
+
+    #[test]
+    fn response_round_trip() {
+        let response = Response {
+            transaction_id: 0x1234,
+            flags: Flags::standard_response(),
+            queries: vec![
+                Query {
+                    qname: Labels::encode("lookup.dog").unwrap(),
+                    qclass: QClass::IN,
+                    qtype: crate::record::RecordType::MX,
+                },
+            ],
+            answers: vec![
+                Answer::Standard {
+                    qname: Labels::encode("lookup.dog").unwrap(),
+                    qclass: QClass::IN,
+                    ttl: 300,
+                    record: Record::MX(MX { preference: 10, exchange: Labels::encode("mail.lookup.dog").unwrap() }),
+                },
+                Answer::Standard {
+                    qname: Labels::encode("lookup.dog").unwrap(),
+                    qclass: QClass::IN,
+                    ttl: 300,
+                    record: Record::TXT(TXT { messages: vec![ Box::new(*b"v=spf1 -all") ] }),
+                },
+            ],
+            authorities: vec![],
+            additionals: vec![],
+        };
+
+        let json = serde_json::to_string(&response).unwrap();
+        assert_eq!(serde_json::from_str::<Response>(&json).unwrap(), response);
+    }
+}
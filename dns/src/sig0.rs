@@ -0,0 +1,254 @@
+//! Signing requests with SIG(0) transaction signatures, which use a
+//! private key instead of a secret shared with the server. The server
+//! checks the signature against the public half of the key, which it looks
+//! up in a KEY record, so nothing secret has to be set up on the server.
+//!
+//! A signature is a SIG record with the root as its owner, added to the end
+//! of the additional section of a message. It signs its own fields, apart
+//! from the signature, followed by the whole message as it was before the
+//! record was added. Only the algorithms that ring can sign with are
+//! supported, which leaves out RSA keys.
+//!
+//! # References
+//!
+//! - [RFC 2931](https://tools.ietf.org/html/rfc2931) — DNS Request and
+//!   Transaction Signatures (SIG(0)s) (September 2000)
+//! - [RFC 2535 §4.1](https://tools.ietf.org/html/rfc2535#section-4.1) —
+//!   Domain Name System Security Extensions (March 1999)
+
+// Without the feature, only the key and error types are needed, so that
+// the options can still be parsed and then rejected.
+#![cfg_attr(not(feature = "with_sig0"), allow(dead_code))]
+
+#[cfg(feature = "with_sig0")]
+use std::convert::TryFrom;
+use std::fmt;
+
+#[cfg(feature = "with_sig0")]
+use ring::signature::{self, EcdsaKeyPair, Ed25519KeyPair};
+
+use crate::record::DNSKEY;
+use crate::strings::{Labels, WriteLabels};
+use crate::wire::*;
+
+
+/// The record type number of SIG records.
+pub const RR_TYPE: u16 = 24;
+
+/// The class of SIG(0) records, which is always ANY.
+const CLASS_ANY: u16 = 255;
+
+/// How many seconds either side of the current time a signature is valid
+/// for, which allows for the clocks here and on the server being off.
+pub const VALIDITY: u32 = 300;
+
+
+/// A key pair that requests get signed with, along with the name of the
+/// KEY record the server can find the public key in.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Sig0Key {
+
+    /// The owner of the KEY record with the public key.
+    pub signer: Labels,
+
+    /// The public key, in the same format as the data of the KEY record,
+    /// which is the same as that of a DNSKEY record.
+    pub public: DNSKEY,
+
+    /// The private key, in the format used by the key’s algorithm: the
+    /// seed for Ed25519 keys, and the scalar for ECDSA keys.
+    pub private_key: Vec<u8>,
+}
+
+impl Sig0Key {
+
+    /// Signs a request that has already been serialised, by appending a SIG
+    /// record with the signature to its additional section. The signature
+    /// is valid from a few minutes before the given time, in seconds since
+    /// the Unix epoch, until a few minutes after it.
+    #[cfg(feature = "with_sig0")]
+    pub fn sign_request(&self, message: &mut Vec<u8>, now: u32) -> Result<(), Sig0Error> {
+        let rdata = self.unsigned_rdata(now.wrapping_sub(VALIDITY), now.wrapping_add(VALIDITY))?;
+
+        let mut data = rdata.clone();
+        data.extend_from_slice(message);
+        let signature = self.sign(&data)?;
+
+        message.write_u8(0)?;  // the root name
+        message.write_u16::<BigEndian>(RR_TYPE)?;
+        message.write_u16::<BigEndian>(CLASS_ANY)?;
+        message.write_u32::<BigEndian>(0)?;  // TTL
+        message.write_u16::<BigEndian>(u16::try_from(rdata.len() + signature.len()).expect("SIG record too long"))?;
+        message.extend(rdata);
+        message.extend(signature);
+
+        let additional_count = u16::from_be_bytes([ message[10], message[11] ]);
+        message[10 .. 12].copy_from_slice(&(additional_count + 1).to_be_bytes());
+        Ok(())
+    }
+
+    /// Writes the fields of the SIG record that come before the signature,
+    /// which get signed along with the message.
+    fn unsigned_rdata(&self, inception: u32, expiration: u32) -> io::Result<Vec<u8>> {
+        let mut rdata = Vec::new();
+        rdata.write_u16::<BigEndian>(0)?;  // type covered
+        rdata.write_u8(self.public.algorithm)?;
+        rdata.write_u8(0)?;  // labels
+        rdata.write_u32::<BigEndian>(0)?;  // original TTL
+        rdata.write_u32::<BigEndian>(expiration)?;
+        rdata.write_u32::<BigEndian>(inception)?;
+        rdata.write_u16::<BigEndian>(self.public.key_tag())?;
+        rdata.write_labels(&self.signer.to_canonical())?;
+        Ok(rdata)
+    }
+
+    /// Signs the data with the private key, using the key’s algorithm.
+    #[cfg(feature = "with_sig0")]
+    fn sign(&self, data: &[u8]) -> Result<Vec<u8>, Sig0Error> {
+        let ecdsa = |algorithm| {
+            // ring wants the public point in uncompressed form, which
+            // starts with a byte that the DNS format leaves out.
+            let mut public_point = vec![ 0x04 ];
+            public_point.extend_from_slice(&self.public.public_key);
+
+            let key_pair = EcdsaKeyPair::from_private_key_and_public_key(algorithm, &self.private_key, &public_point)
+                .map_err(|_| Sig0Error::KeyMismatch)?;
+            let signature = key_pair.sign(&ring::rand::SystemRandom::new(), data)
+                .map_err(|_| Sig0Error::KeyMismatch)?;
+            Ok(signature.as_ref().to_vec())
+        };
+
+        match self.public.algorithm {
+            13 => ecdsa(&signature::ECDSA_P256_SHA256_FIXED_SIGNING),
+            14 => ecdsa(&signature::ECDSA_P384_SHA384_FIXED_SIGNING),
+            15 => {
+                let key_pair = Ed25519KeyPair::from_seed_and_public_key(&self.private_key, &self.public.public_key)
+                    .map_err(|_| Sig0Error::KeyMismatch)?;
+                Ok(key_pair.sign(data).as_ref().to_vec())
+            }
+            n => Err(Sig0Error::UnsupportedAlgorithm(n)),
+        }
+    }
+}
+
+
+/// Something that stops a request from being signed.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum Sig0Error {
+
+    /// The key uses an algorithm that dog cannot sign with.
+    UnsupportedAlgorithm(u8),
+
+    /// The private key was rejected, or does not go with the public key.
+    KeyMismatch,
+
+    /// The message could not be written to.
+    IO,
+}
+
+impl From<io::Error> for Sig0Error {
+    fn from(_: io::Error) -> Self {
+        Self::IO
+    }
+}
+
+impl fmt::Display for Sig0Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnsupportedAlgorithm(n)  => write!(f, "cannot sign with algorithm {}", n),
+            Self::KeyMismatch              => write!(f, "the private key does not match the public key"),
+            Self::IO                       => write!(f, "failed to write the signature"),
+        }
+    }
+}
+
+
+#[cfg(test)]
+#[cfg(feature = "with_sig0")]
+mod test {
+    use super::*;
+    use crate::record::RecordType;
+    use crate::types::{Flags, Query, QClass, Request};
+    use pretty_assertions::assert_eq;
+
+    /// The Ed25519 key pair from RFC 8080 §6.1.
+    fn key() -> Sig0Key {
+        Sig0Key {
+            signer: Labels::encode("update.lookup.dog").unwrap(),
+            public: DNSKEY {
+                flags: 512,
+                protocol: 3,
+                algorithm: 15,
+                public_key: base64::decode("l02Woi0iS8Aa25FQkUd9RMzZHJpBoRQwAQEX1SxZJA4=").unwrap(),
+            },
+            private_key: base64::decode("ODIyNjAzODQ2MjgwODAxMjI2NDUxOTAyMDQxNDIyNjI=").unwrap(),
+        }
+    }
+
+    fn request_bytes() -> Vec<u8> {
+        let query = Query {
+            qname: Labels::encode("lookup.dog").unwrap(),
+            qtype: RecordType::SOA,
+            qclass: QClass::IN,
+        };
+
+        Request { transaction_id: 0xceac, flags: Flags::query(), query, additional: None }.to_bytes().unwrap()
+    }
+
+    #[test]
+    fn signed_request() {
+        let key = key();
+        let unsigned = request_bytes();
+        let mut message = unsigned.clone();
+        key.sign_request(&mut message, 1_600_000_000).unwrap();
+
+        assert_eq!(&message[.. 10], &unsigned[.. 10]);
+        assert_eq!(&message[10 .. 12], &[ 0x00, 0x01 ]);  // additional count
+        assert_eq!(&message[12 .. unsigned.len()], &unsigned[12 ..]);
+
+        let record = &message[unsigned.len() ..];
+        let rdata = key.unsigned_rdata(1_599_999_700, 1_600_000_300).unwrap();
+        assert_eq!(&record[.. 11], &[
+            0x00,  // the root
+            0x00, 0x18,  // type SIG
+            0x00, 0xff,  // class ANY
+            0x00, 0x00, 0x00, 0x00,  // TTL
+            0x00, u8::try_from(rdata.len() + 64).unwrap(),  // data length
+        ]);
+        assert_eq!(&record[11 .. 11 + rdata.len()], &rdata[..]);
+        assert_eq!(record.len(), 11 + rdata.len() + 64);
+    }
+
+    #[test]
+    fn unsigned_rdata() {
+        let rdata = key().unsigned_rdata(0x5f5e_0f00, 0x5f5e_1100).unwrap();
+        assert_eq!(rdata, vec![
+            0x00, 0x00,  // type covered
+            0x0f,  // algorithm
+            0x00,  // labels
+            0x00, 0x00, 0x00, 0x00,  // original TTL
+            0x5f, 0x5e, 0x11, 0x00,  // expiration
+            0x5f, 0x5e, 0x0f, 0x00,  // inception
+            0x0f, 0x1c,  // key tag
+            0x06, b'u', b'p', b'd', b'a', b't', b'e',
+            0x06, b'l', b'o', b'o', b'k', b'u', b'p',
+            0x03, b'd', b'o', b'g', 0x00,  // signer
+        ]);
+    }
+
+    #[test]
+    fn unsupported_algorithm() {
+        let mut key = key();
+        key.public.algorithm = 8;
+        assert_eq!(key.sign_request(&mut request_bytes(), 1_600_000_000),
+                   Err(Sig0Error::UnsupportedAlgorithm(8)));
+    }
+
+    #[test]
+    fn short_private_key() {
+        let mut key = key();
+        key.private_key.truncate(16);
+        assert_eq!(key.sign_request(&mut request_bytes(), 1_600_000_000),
+                   Err(Sig0Error::KeyMismatch));
+    }
+}
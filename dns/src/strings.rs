@@ -1,34 +1,64 @@
 //! Reading strings from the DNS wire protocol.
 
+use std::cmp::Ordering;
 use std::convert::TryFrom;
 use std::fmt;
-use std::io::{self, Write};
 
-use byteorder::{ReadBytesExt, WriteBytesExt};
+use alloc::collections::BTreeMap;
 use log::*;
 
 use crate::wire::*;
 
 
 /// Domain names in the DNS protocol are encoded as **Labels**, which are
-/// segments of ASCII characters prefixed by their length. When written out,
-/// each segment is followed by a dot.
+/// segments of bytes prefixed by their length. The bytes are usually ASCII
+/// characters, but they can be anything. When written out, each segment is
+/// followed by a dot.
 ///
 /// The maximum length of a segment is 255 characters.
 #[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Clone)]
 pub struct Labels {
-    segments: Vec<(u8, String)>,
+    segments: Vec<(u8, Vec<u8>)>,
 }
 
+/// The IDNA options to use. Transitional processing is turned off, so that
+/// characters such as ‘ß’ are kept as they are, as IDNA2008 requires,
+/// instead of being mapped to other characters as IDNA2003 did.
+#[cfg(feature = "with_idna")]
+const IDNA_FLAGS: unic_idna::Flags = unic_idna::Flags {
+    use_std3_ascii_rules: false,
+    transitional_processing: false,
+    verify_dns_length: true,
+};
+
 #[cfg(feature = "with_idna")]
 fn label_to_ascii(label: &str) -> Result<String, unic_idna::Errors> {
-    let flags = unic_idna::Flags{use_std3_ascii_rules: false, transitional_processing: false, verify_dns_length: true};
-    unic_idna::to_ascii(label, flags)
+    unic_idna::to_ascii(label, IDNA_FLAGS)
 }
 
+/// Without IDNA support, labels that are not already ASCII cannot be
+/// converted, and sending their UTF-8 bytes as they are would query for
+/// a different name to the one that was meant, so they are rejected.
 #[cfg(not(feature = "with_idna"))]
 fn label_to_ascii(label: &str) -> Result<String, ()> {
-    Ok(label.to_owned())
+    if label.is_ascii() { Ok(label.to_owned()) }
+                   else { Err(()) }
+}
+
+#[cfg(feature = "with_idna")]
+fn label_to_unicode(label: &str) -> String {
+    match unic_idna::to_unicode(label, IDNA_FLAGS) {
+        (unicode, Ok(()))  => unicode,
+        (_, Err(e))        => {
+            warn!("Could not decode label {:?}: {:?}", label, e);
+            label.to_owned()
+        }
+    }
+}
+
+#[cfg(not(feature = "with_idna"))]
+fn label_to_unicode(label: &str) -> String {
+    label.to_owned()
 }
 
 impl Labels {
@@ -39,25 +69,40 @@ impl Labels {
         Self { segments: Vec::new() }
     }
 
-    /// Encodes the given input string as labels. If any segment is too long,
-    /// returns that segment as an error.
+    /// Encodes the given input string as labels, separated by dots. A
+    /// backslash escapes the character after it, so ‘`\.`’ puts a dot inside
+    /// a label, and a backslash followed by three digits gives the byte with
+    /// that decimal value, as described in RFC 1035 §5.1.
+    /// If any segment is too long or has an invalid escape, returns that
+    /// segment as an error.
     pub fn encode(input: &str) -> Result<Self, &str> {
         let mut segments = Vec::new();
 
-        for label in input.split('.') {
+        for label in split_labels(input) {
             if label.is_empty() {
                 continue;
             }
 
-            let label_idn = label_to_ascii(label)
+            // Labels with escapes in are taken as they are, as they are
+            // not going to be valid international domain names.
+            let label_ascii = if label.contains('\\') {
+                unescape_label(label).ok_or_else(|| {
+                    warn!("Could not encode label {:?}: invalid escape", label);
+                    label
+                })?
+            }
+            else {
+                label_to_ascii(label)
                     .map_err(|e| {
                         warn!("Could not encode label {:?}: {:?}", label, e);
                         label
-                    })?;
+                    })?
+                    .into_bytes()
+            };
 
-            match u8::try_from(label_idn.len()) {
+            match u8::try_from(label_ascii.len()) {
                 Ok(length) => {
-                    segments.push((length, label_idn));
+                    segments.push((length, label_ascii));
                 }
                 Err(e) => {
                     warn!("Could not encode label {:?}: {}", label, e);
@@ -69,11 +114,110 @@ impl Labels {
         Ok(Self { segments })
     }
 
+    /// Returns the name with each of its labels converted from the ASCII
+    /// form used on the wire to Unicode, so ‘`xn--bcher-kva.example.`’
+    /// becomes ‘`bücher.example.`’. Labels that are not valid IDNA are
+    /// left as they are, as are all labels if IDNA support is disabled.
+    pub fn to_unicode(&self) -> String {
+        let mut output = String::new();
+
+        for (_, segment) in &self.segments {
+            output.push_str(&label_to_unicode(&String::from_utf8_lossy(segment)));
+            output.push('.');
+        }
+
+        output
+    }
+
     /// Returns the number of segments.
     pub fn len(&self) -> usize {
         self.segments.len()
     }
 
+    /// Returns the number of bytes the name takes up in a packet without
+    /// compression, including each segment’s length byte and the final zero.
+    fn wire_length(&self) -> usize {
+        self.segments.iter().map(|(length, _)| usize::from(*length) + 1).sum::<usize>() + 1
+    }
+
+    /// Returns an iterator over the bytes of each segment.
+    pub(crate) fn segments(&self) -> impl DoubleEndedIterator<Item = &[u8]> + ExactSizeIterator {
+        self.segments.iter().map(|(_, label)| label.as_slice())
+    }
+
+    /// Adds a segment read from the wire to the end.
+    #[cfg(feature = "with_zero_copy")]
+    pub(crate) fn push_wire_segment(&mut self, length: u8, label: &[u8]) {
+        self.segments.push((length, label.to_vec()));
+    }
+
+    /// Returns the name in its canonical form, with every uppercase ASCII
+    /// letter made lowercase, as DNSSEC signatures are made over it.
+    ///
+    /// # References
+    ///
+    /// - [RFC 4034 §6.2](https://tools.ietf.org/html/rfc4034#section-6.2) —
+    ///   Resource Records for the DNS Security Extensions (March 2005)
+    pub fn to_canonical(&self) -> Self {
+        let segments = self.segments.iter()
+            .map(|(length, label)| (*length, label.to_ascii_lowercase()))
+            .collect();
+
+        Self { segments }
+    }
+
+    /// Compares two names in canonical order, which sorts them by their
+    /// labels from the rightmost one leftwards, comparing each as a string
+    /// of lowercase bytes. A name that runs out of labels first sorts
+    /// before the other one, so a zone’s apex sorts before its children.
+    ///
+    /// # References
+    ///
+    /// - [RFC 4034 §6.1](https://tools.ietf.org/html/rfc4034#section-6.1) —
+    ///   Resource Records for the DNS Security Extensions (March 2005)
+    pub fn canonical_cmp(&self, other: &Self) -> Ordering {
+        let mine = self.segments.iter().rev().map(|(_, label)| label);
+        let theirs = other.segments.iter().rev().map(|(_, label)| label);
+
+        for pair in mine.zip(theirs) {
+            let ordering = pair.0.iter().map(u8::to_ascii_lowercase)
+                .cmp(pair.1.iter().map(u8::to_ascii_lowercase));
+
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+        }
+
+        self.segments.len().cmp(&other.segments.len())
+    }
+
+    /// Returns the ancestor of the name made up of its rightmost `count`
+    /// labels, or the whole name if it has fewer than that.
+    pub fn ancestor(&self, count: usize) -> Self {
+        let skip = self.segments.len().saturating_sub(count);
+        Self { segments: self.segments[skip ..].to_vec() }
+    }
+
+    /// Whether the name is the same as the given one, or a subdomain of
+    /// it, ignoring case.
+    pub fn is_within(&self, ancestor: &Self) -> bool {
+        ancestor.segments.len() <= self.segments.len()
+            && self.ancestor(ancestor.segments.len()).canonical_cmp(ancestor) == Ordering::Equal
+    }
+
+    /// Whether the leftmost label is the ‘`*`’ wildcard label.
+    pub fn is_wildcard(&self) -> bool {
+        self.segments.first().map_or(false, |(_, label)| label == b"*")
+    }
+
+    /// Returns the wildcard name directly beneath this one, which has ‘`*`’
+    /// as an extra label on the left.
+    pub fn wildcard(&self) -> Self {
+        let mut segments = vec![ (1, b"*".to_vec()) ];
+        segments.extend_from_slice(&self.segments);
+        Self { segments }
+    }
+
     /// Returns a new set of labels concatenating two names.
     pub fn extend(&self, other: &Self) -> Self {
         let mut segments = self.segments.clone();
@@ -82,10 +226,88 @@ impl Labels {
     }
 }
 
+/// Splits a domain name into its labels at each dot that is not escaped.
+fn split_labels(input: &str) -> Vec<&str> {
+    let mut labels = Vec::new();
+    let mut start = 0;
+    let mut escaped = false;
+
+    for (index, byte) in input.bytes().enumerate() {
+        if escaped {
+            escaped = false;
+        }
+        else if byte == b'\\' {
+            escaped = true;
+        }
+        else if byte == b'.' {
+            labels.push(&input[start .. index]);
+            start = index + 1;
+        }
+    }
+
+    labels.push(&input[start ..]);
+    labels
+}
+
+/// Replaces the escape sequences in a label with the bytes they stand for,
+/// returning `None` if any of them are invalid.
+fn unescape_label(label: &str) -> Option<Vec<u8>> {
+    let mut output = Vec::new();
+    let mut chars = label.chars();
+    let mut buf = [0; 4];
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            output.extend(c.encode_utf8(&mut buf).as_bytes());
+            continue;
+        }
+
+        match chars.next()? {
+            digit if digit.is_ascii_digit() => {
+                let digits = [ digit, chars.next()?, chars.next()? ];
+                let mut number = 0;
+                for digit in &digits {
+                    number = number * 10 + digit.to_digit(10)?;
+                }
+
+                output.push(u8::try_from(number).ok()?);
+            }
+            other => {
+                output.extend(other.encode_utf8(&mut buf).as_bytes());
+            }
+        }
+    }
+
+    Some(output)
+}
+
+/// Writes the bytes of a label, escaping the ones that would make the name
+/// ambiguous or that cannot be printed. Spaces, control characters, and
+/// bytes outside ASCII are written as a backslash and their three-digit
+/// decimal value.
+fn write_escaped_label(f: &mut fmt::Formatter<'_>, label: &[u8]) -> fmt::Result {
+    for byte in label.iter().copied() {
+        match byte {
+            b'.' | b'\\' | b'"' | b'(' | b')' | b';' | b'@' | b'$' => {
+                write!(f, "\\{}", char::from(byte))?;
+            }
+            0x21 ..= 0x7e => {
+                write!(f, "{}", char::from(byte))?;
+            }
+            _ => {
+                write!(f, "\\{:03}", byte)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
 impl fmt::Display for Labels {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         for (_, segment) in &self.segments {
-            write!(f, "{}.", segment)?;
+            write_escaped_label(f, segment)?;
+            write!(f, ".")?;
         }
 
         Ok(())
@@ -128,7 +350,7 @@ impl<W: Write> WriteLabels for W {
         for (length, label) in &input.segments {
             self.write_u8(*length)?;
 
-            for b in label.as_bytes() {
+            for b in label {
                 self.write_u8(*b)?;
             }
         }
@@ -139,7 +361,56 @@ impl<W: Write> WriteLabels for W {
 }
 
 
-const RECURSION_LIMIT: usize = 8;
+/// Remembers where in a message each domain name has been written, so that
+/// names written later can point back to a name they end with instead of
+/// repeating it, as described in RFC 1035 §4.1.4.
+///
+/// The positions are offsets from the start of the buffer being written to,
+/// so that buffer must start with the message header.
+#[derive(PartialEq, Debug, Default)]
+pub(crate) struct NameCompressor {
+    offsets: BTreeMap<Vec<Vec<u8>>, u16>,
+}
+
+impl NameCompressor {
+
+    /// Writes a domain name to the end of the given buffer, ending it with
+    /// a pointer to an earlier name if one matches any of its suffixes.
+    /// Names are compared case-insensitively.
+    pub(crate) fn write_labels(&mut self, bytes: &mut Vec<u8>, input: &Labels) -> io::Result<()> {
+        for index in 0 .. input.segments.len() {
+            let suffix = input.segments[index ..].iter()
+                .map(|(_, label)| label.to_ascii_lowercase())
+                .collect::<Vec<_>>();
+
+            if let Some(offset) = self.offsets.get(&suffix) {
+                return bytes.write_u16::<BigEndian>(0b_1100_0000_0000_0000 | offset);
+            }
+
+            // Pointers only have 14 bits for the offset, so names further
+            // into the message than that cannot be pointed to.
+            if let Ok(offset) = u16::try_from(bytes.len()) {
+                if offset <= 0b_0011_1111_1111_1111 {
+                    self.offsets.insert(suffix, offset);
+                }
+            }
+
+            let (length, label) = &input.segments[index];
+            bytes.write_u8(*length)?;
+            bytes.extend(label);
+        }
+
+        bytes.write_u8(0)  // terminate the string
+    }
+}
+
+
+/// The most pointers that get followed while reading one domain name.
+pub(crate) const RECURSION_LIMIT: usize = 8;
+
+/// The most bytes a domain name can take up once it has been expanded,
+/// counting the length bytes and the final zero.
+pub(crate) const MAX_NAME_LENGTH: usize = 255;
 
 /// Reads bytes from the given cursor into the given buffer, using the list of
 /// recursions to track backtracking positions. Returns the count of bytes
@@ -147,6 +418,7 @@ const RECURSION_LIMIT: usize = 8;
 /// backtracking, but not including the bytes read _during_ backtracking.
 #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
 fn read_string_recursive(labels: &mut Labels, c: &mut Cursor<&[u8]>, recursions: &mut Vec<u16>) -> Result<u16, WireError> {
+    let start = c.position();
     let mut bytes_read = 0;
 
     loop {
@@ -158,14 +430,20 @@ fn read_string_recursive(labels: &mut Labels, c: &mut Cursor<&[u8]>, recursions:
         }
 
         else if byte >= 0b_1100_0000 {
+            let position = c.position() - 1;
             let name_one = byte - 0b1100_0000;
             let name_two = c.read_u8()?;
             bytes_read += 1;
             let offset = u16::from_be_bytes([name_one, name_two]);
 
-            if recursions.contains(&offset) {
-                warn!("Hit previous offset ({}) decoding string", offset);
-                return Err(WireError::TooMuchRecursion(recursions.clone().into_boxed_slice()));
+            // Pointers have to point before the start of the name being
+            // decoded, rather than just before themselves, so each one
+            // followed lands strictly earlier than the last and they can
+            // never loop.
+            if u64::from(offset) >= start {
+                warn!("Pointer at {} points forward to {} decoding string", position, offset);
+                let position = u16::try_from(position).unwrap_or(u16::MAX);
+                return Err(WireError::NameLimit(NameLimit::ForwardPointer { position, offset }));
             }
 
             recursions.push(offset);
@@ -197,8 +475,12 @@ fn read_string_recursive(labels: &mut Labels, c: &mut Cursor<&[u8]>, recursions:
                 name_buf.push(c);
             }
 
-            let string = String::from_utf8_lossy(&*name_buf).to_string();
-            labels.segments.push((byte, string));
+            labels.segments.push((byte, name_buf));
+
+            if labels.wire_length() > MAX_NAME_LENGTH {
+                warn!("Hit name length limit ({}) decoding string", MAX_NAME_LENGTH);
+                return Err(WireError::NameLimit(NameLimit::TooLong));
+            }
         }
     }
 
@@ -255,16 +537,19 @@ mod test {
     #[test]
     fn label_followed_by_backtrack() {
         let buf: &[u8] = &[
-            0x03,  // label of length 3
-            b'o', b'n', b'e',  // label
-            0xc0, 0x06,  // skip to position 6 (the next byte)
-
             0x03,  // label of length 3
             b't', b'w', b'o',  // label
             0x00,  // end reading
+
+            0x03,  // label of length 3
+            b'o', b'n', b'e',  // label
+            0xc0, 0x00,  // skip to position 0
         ];
 
-        assert_eq!(Cursor::new(buf).read_labels(),
+        let mut cursor = Cursor::new(buf);
+        cursor.set_position(5);
+
+        assert_eq!(cursor.read_labels(),
                    Ok((Labels::encode("one.two.").unwrap(), 6)));
     }
 
@@ -287,7 +572,7 @@ mod test {
         ];
 
         assert_eq!(Cursor::new(buf).read_labels(),
-                   Err(WireError::TooMuchRecursion(Box::new([ 0 ]))));
+                   Err(WireError::NameLimit(NameLimit::ForwardPointer { position: 0, offset: 0 })));
     }
 
     #[test]
@@ -300,26 +585,282 @@ mod test {
         let mut cursor = Cursor::new(buf);
 
         assert_eq!(cursor.read_labels(),
-                   Err(WireError::TooMuchRecursion(Box::new([ 2, 0 ]))));
+                   Err(WireError::NameLimit(NameLimit::ForwardPointer { position: 0, offset: 2 })));
     }
 
     #[test]
-    fn too_much_recursion() {
+    fn pointers_leading_back_to_each_other() {
         let buf: &[u8] = &[
-            0xc0, 0x02,  // skip to position 2
-            0xc0, 0x04,  // skip to position 4
+            0x02, 0xc0, 0x00,  // label of length 2, or a skip to position 0
+            0xc0, 0x01,  // skip to position 1
+        ];
+
+        // Both pointers point before themselves, but the label at position 0
+        // runs over the pointer at position 1 and back into the one at
+        // position 3, which would go round in circles.
+        let mut cursor = Cursor::new(buf);
+        cursor.set_position(3);
+
+        assert_eq!(cursor.read_labels(),
+                   Err(WireError::NameLimit(NameLimit::ForwardPointer { position: 3, offset: 1 })));
+    }
+
+    #[test]
+    fn forward_pointer_after_label() {
+        let buf: &[u8] = &[
+            0x03, 0x6f, 0x6e, 0x65,  // label of length 3
             0xc0, 0x06,  // skip to position 6
-            0xc0, 0x08,  // skip to position 8
-            0xc0, 0x0A,  // skip to position 10
-            0xc0, 0x0C,  // skip to position 12
-            0xc0, 0x0E,  // skip to position 14
-            0xc0, 0x10,  // skip to position 16
+            0x00,  // end reading
+        ];
+
+        assert_eq!(Cursor::new(buf).read_labels(),
+                   Err(WireError::NameLimit(NameLimit::ForwardPointer { position: 4, offset: 6 })));
+    }
+
+    #[test]
+    fn too_much_recursion() {
+        let buf: &[u8] = &[
             0x00,        // no label
+            0xc0, 0x00,  // skip to position 0
+            0xc0, 0x01,  // skip to position 1
+            0xc0, 0x03,  // skip to position 3
+            0xc0, 0x05,  // skip to position 5
+            0xc0, 0x07,  // skip to position 7
+            0xc0, 0x09,  // skip to position 9
+            0xc0, 0x0B,  // skip to position 11
+            0xc0, 0x0D,  // skip to position 13
         ];
 
         let mut cursor = Cursor::new(buf);
+        cursor.set_position(15);
 
         assert_eq!(cursor.read_labels(),
-                   Err(WireError::TooMuchRecursion(Box::new([ 2, 4, 6, 8, 10, 12, 14, 16 ]))));
+                   Err(WireError::TooMuchRecursion(Box::new([ 13, 11, 9, 7, 5, 3, 1, 0 ]))));
+    }
+
+    #[test]
+    fn longest_name() {
+        // 63 + 63 + 63 + 61, plus four length bytes and the final zero,
+        // is exactly 255 bytes.
+        let mut buf = Vec::new();
+        for length in &[ 63, 63, 63, 61 ] {
+            buf.push(*length);
+            buf.extend(vec![0x65; usize::from(*length)]);
+        }
+        buf.push(0x00);  // end reading
+
+        assert_eq!(Cursor::new(&*buf).read_labels().unwrap().1, 255);
+    }
+
+    #[test]
+    fn name_too_long() {
+        let mut buf = Vec::new();
+        for length in &[ 63, 63, 63, 62 ] {
+            buf.push(*length);
+            buf.extend(vec![0x65; usize::from(*length)]);
+        }
+        buf.push(0x00);  // end reading
+
+        assert_eq!(Cursor::new(&*buf).read_labels(),
+                   Err(WireError::NameLimit(NameLimit::TooLong)));
+    }
+
+    #[test]
+    fn name_too_long_through_pointers() {
+        // A 200-byte name, then a name that adds another 100 bytes of
+        // labels in front of it.
+        let mut buf = Vec::new();
+        for length in &[ 99, 99 ] {
+            buf.push(*length);
+            buf.extend(vec![0x65; usize::from(*length)]);
+        }
+        buf.push(0x00);  // end reading
+
+        let start = buf.len();
+        buf.push(99);
+        buf.extend(vec![0x65; 99]);
+        buf.extend(&[ 0xc0, 0x00 ]);  // skip to position 0
+
+        let mut cursor = Cursor::new(&*buf);
+        cursor.set_position(start as u64);
+
+        assert_eq!(cursor.read_labels(),
+                   Err(WireError::NameLimit(NameLimit::TooLong)));
+    }
+
+    #[test]
+    fn compress_repeated_name() {
+        let mut compressor = NameCompressor::default();
+        let mut bytes = Vec::new();
+        compressor.write_labels(&mut bytes, &Labels::encode("lookup.dog").unwrap()).unwrap();
+        compressor.write_labels(&mut bytes, &Labels::encode("LOOKUP.dog").unwrap()).unwrap();
+
+        assert_eq!(bytes, &[
+            0x06, b'l', b'o', b'o', b'k', b'u', b'p',  // label of length 6
+            0x03, b'd', b'o', b'g',  // label of length 3
+            0x00,  // end reading
+            0xc0, 0x00,  // skip to position 0
+        ]);
+    }
+
+    #[test]
+    fn compress_suffix() {
+        let mut compressor = NameCompressor::default();
+        let mut bytes = Vec::new();
+        compressor.write_labels(&mut bytes, &Labels::encode("dog").unwrap()).unwrap();
+        compressor.write_labels(&mut bytes, &Labels::encode("ns.dog").unwrap()).unwrap();
+
+        assert_eq!(bytes, &[
+            0x03, b'd', b'o', b'g',  // label of length 3
+            0x00,  // end reading
+            0x02, b'n', b's',  // label of length 2
+            0xc0, 0x00,  // skip to position 0
+        ]);
+
+        let mut cursor = Cursor::new(&bytes[..]);
+        cursor.set_position(5);
+        assert_eq!(cursor.read_labels(),
+                   Ok((Labels::encode("ns.dog").unwrap(), 5)));
+    }
+
+    #[test]
+    fn compress_root() {
+        let mut compressor = NameCompressor::default();
+        let mut bytes = Vec::new();
+        compressor.write_labels(&mut bytes, &Labels::root()).unwrap();
+        compressor.write_labels(&mut bytes, &Labels::root()).unwrap();
+
+        assert_eq!(bytes, &[ 0x00, 0x00 ]);
+    }
+
+    #[test]
+    fn ascii_to_unicode() {
+        assert_eq!(Labels::encode("dns.lookup.dog").unwrap().to_unicode(),
+                   "dns.lookup.dog.");
+    }
+
+    #[test]
+    fn root_to_unicode() {
+        assert_eq!(Labels::root().to_unicode(),
+                   "");
+    }
+
+    #[test]
+    #[cfg(not(feature = "with_idna"))]
+    fn non_ascii_without_idna() {
+        assert_eq!(Labels::encode("bücher.example"),
+                   Err("bücher"));
+    }
+
+    #[test]
+    fn encode_escaped_dot() {
+        let labels = Labels::encode("one\\.two.three").unwrap();
+        assert_eq!(labels.len(), 2);
+        assert_eq!(labels.to_string(), "one\\.two.three.");
+    }
+
+    #[test]
+    fn encode_decimal_escape() {
+        let labels = Labels::encode("one\\032two.three").unwrap();
+        assert_eq!(labels.segments, vec![ (7, "one two".into()), (5, "three".into()) ]);
+        assert_eq!(labels.to_string(), "one\\032two.three.");
+    }
+
+    #[test]
+    fn encode_escaped_backslash() {
+        let labels = Labels::encode("one\\\\.two").unwrap();
+        assert_eq!(labels.segments, vec![ (4, "one\\".into()), (3, "two".into()) ]);
+        assert_eq!(labels.to_string(), "one\\\\.two.");
+    }
+
+    #[test]
+    fn encode_high_byte_escapes() {
+        let labels = Labels::encode("caf\\233.\\128\\255").unwrap();
+        assert_eq!(labels.segments, vec![ (4, b"caf\xe9".to_vec()), (2, b"\x80\xff".to_vec()) ]);
+        assert_eq!(labels.to_string(), "caf\\233.\\128\\255.");
+    }
+
+    #[test]
+    fn high_bytes_round_trip() {
+        let labels = Labels::encode("caf\\233.\\200.example").unwrap();
+
+        let mut buf = Vec::new();
+        buf.write_labels(&labels).unwrap();
+        let (read, _) = Cursor::new(&buf[..]).read_labels().unwrap();
+
+        assert_eq!(read, labels);
+        assert_eq!(Labels::encode(&read.to_string()), Ok(labels));
+    }
+
+    #[test]
+    fn encode_invalid_escapes() {
+        assert_eq!(Labels::encode("one\\03.two"), Err("one\\03"));
+        assert_eq!(Labels::encode("one\\256.two"), Err("one\\256"));
+        assert_eq!(Labels::encode("one\\"), Err("one\\"));
+    }
+
+    #[test]
+    fn display_control_characters() {
+        let buf: &[u8] = &[
+            0x05, b'a', 0x00, b'b', b'"', 0x7f,  // label of length 5
+            0x00,  // end reading
+        ];
+
+        let (labels, _) = Cursor::new(buf).read_labels().unwrap();
+        assert_eq!(labels.to_string(), "a\\000b\\\"\\127.");
+    }
+
+    #[test]
+    fn canonical_form() {
+        let labels = Labels::encode("WWW.Lookup.dog").unwrap();
+        assert_eq!(labels.to_canonical().to_string(), "www.lookup.dog.");
+    }
+
+    #[test]
+    fn canonical_order() {
+        // The example from RFC 4034 §6.1.
+        let names = [
+            "example", "a.example", "yljkjljk.a.example", "Z.a.example",
+            "zABC.a.EXAMPLE", "z.example", "\\001.z.example", "*.z.example",
+            "\\200.z.example",
+        ];
+
+        for pair in names.windows(2) {
+            let one = Labels::encode(pair[0]).unwrap();
+            let other = Labels::encode(pair[1]).unwrap();
+            assert_eq!(one.canonical_cmp(&other), Ordering::Less, "{} < {}", pair[0], pair[1]);
+            assert_eq!(other.canonical_cmp(&one), Ordering::Greater, "{} > {}", pair[1], pair[0]);
+        }
+    }
+
+    #[test]
+    fn canonical_order_ignores_case() {
+        let one = Labels::encode("LOOKUP.dog").unwrap();
+        let other = Labels::encode("lookup.DOG").unwrap();
+        assert_eq!(one.canonical_cmp(&other), Ordering::Equal);
+    }
+
+    #[test]
+    fn ancestors() {
+        let labels = Labels::encode("www.lookup.dog").unwrap();
+        assert_eq!(labels.ancestor(2).to_string(), "lookup.dog.");
+        assert_eq!(labels.ancestor(0), Labels::root());
+        assert_eq!(labels.ancestor(5).to_string(), "www.lookup.dog.");
+    }
+
+    #[test]
+    fn within() {
+        let labels = Labels::encode("www.LOOKUP.dog").unwrap();
+        assert!(labels.is_within(&Labels::encode("lookup.dog").unwrap()));
+        assert!(labels.is_within(&labels));
+        assert!(labels.is_within(&Labels::root()));
+        assert!(! labels.is_within(&Labels::encode("okup.dog").unwrap()));
+        assert!(! Labels::root().is_within(&labels));
+    }
+
+    #[test]
+    fn wildcards() {
+        let labels = Labels::encode("lookup.dog").unwrap();
+        assert_eq!(labels.wildcard().to_string(), "*.lookup.dog.");
     }
 }
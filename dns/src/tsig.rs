@@ -0,0 +1,672 @@
+//! Signing requests and verifying responses with transaction signatures
+//! (TSIG), which use a secret shared with the server to prove that a
+//! message came from someone holding it, and was not changed on the way.
+//! Servers usually require these before they allow zone transfers or
+//! dynamic updates.
+//!
+//! A signature is a TSIG record added to the end of the additional section
+//! of a message, holding an HMAC of the message as it was before the record
+//! was added, followed by some of the record’s own fields. A response’s MAC
+//! also covers the MAC of the request it answers, and every message of a
+//! zone transfer after the first one covers the MAC of the one before it,
+//! so the messages cannot be swapped around or replayed.
+//!
+//! # References
+//!
+//! - [RFC 8945](https://tools.ietf.org/html/rfc8945) — Secret Key
+//!   Transaction Authentication for DNS (TSIG) (November 2020)
+
+// Without the feature, only the key and error types are needed, so that
+// the options can still be parsed and then rejected.
+#![cfg_attr(not(feature = "with_tsig"), allow(dead_code))]
+
+use std::convert::TryFrom;
+use std::fmt;
+
+#[cfg(feature = "with_tsig")]
+use ring::hmac;
+
+use crate::strings::{Labels, ReadLabels, WriteLabels};
+use crate::wire::*;
+
+
+/// The record type number of TSIG records.
+pub const RR_TYPE: u16 = 250;
+
+/// The class of TSIG records, which is always ANY.
+const CLASS_ANY: u16 = 255;
+
+/// The number of seconds that the time in a signature is allowed to differ
+/// from the clock of whoever checks it, as recommended by RFC 8945 §10.
+pub const FUDGE: u16 = 300;
+
+
+/// A key shared with a server, which is used to sign requests to it and
+/// to check the signatures on its responses.
+#[derive(PartialEq, Debug, Clone)]
+pub struct TsigKey {
+
+    /// The name of the key, which the server uses to find its copy.
+    pub name: Labels,
+
+    /// The algorithm that MACs are made with.
+    pub algorithm: TsigAlgorithm,
+
+    /// The shared secret.
+    pub secret: Vec<u8>,
+}
+
+/// An HMAC algorithm that TSIG signatures can be made with.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum TsigAlgorithm {
+
+    /// HMAC with SHA-1, which older servers may still use.
+    HmacSha1,
+
+    /// HMAC with SHA-256, which every server has to support.
+    HmacSha256,
+}
+
+impl TsigAlgorithm {
+
+    /// Returns the algorithm with the given name, which is matched
+    /// case-insensitively and with or without a trailing dot.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match &*name.trim_end_matches('.').to_ascii_lowercase() {
+            "hmac-sha1"    => Some(Self::HmacSha1),
+            "hmac-sha256"  => Some(Self::HmacSha256),
+            _              => None,
+        }
+    }
+
+    /// The name of this algorithm, as it appears in TSIG records.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::HmacSha1    => "hmac-sha1",
+            Self::HmacSha256  => "hmac-sha256",
+        }
+    }
+
+    /// The name of this algorithm as labels.
+    fn labels(self) -> Labels {
+        Labels::encode(self.name()).expect("Invalid algorithm name")
+    }
+
+    /// The HMAC algorithm in ring that this corresponds to.
+    #[cfg(feature = "with_tsig")]
+    fn hmac_algorithm(self) -> hmac::Algorithm {
+        match self {
+            Self::HmacSha1    => hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY,
+            Self::HmacSha256  => hmac::HMAC_SHA256,
+        }
+    }
+}
+
+
+/// The fields of a TSIG record that was read from a message.
+#[derive(PartialEq, Debug)]
+struct TsigRecord {
+
+    /// The offset into the message where the record starts.
+    offset: usize,
+
+    /// The name of the key the message was signed with.
+    key_name: Labels,
+
+    /// The name of the algorithm the MAC was made with.
+    algorithm: Labels,
+
+    /// The time the message was signed, in seconds since the Unix epoch.
+    time_signed: u64,
+
+    /// How many seconds the time is allowed to be off by.
+    fudge: u16,
+
+    /// The MAC itself.
+    mac: Vec<u8>,
+
+    /// The ID of the message before anything could have changed it.
+    original_id: u16,
+
+    /// The extended error code, which is zero when there was no error.
+    error: u16,
+
+    /// Any other data, which servers only send alongside a BADTIME error.
+    other: Vec<u8>,
+}
+
+
+impl TsigKey {
+
+    /// Signs a request that has already been serialised, by appending a TSIG
+    /// record with the MAC of the request to its additional section, and
+    /// returns the MAC, which is needed to check the response.
+    #[cfg(feature = "with_tsig")]
+    pub fn sign_request(&self, message: &mut Vec<u8>, time_signed: u64) -> io::Result<Vec<u8>> {
+        let mut digest = message.clone();
+        digest.extend(self.variables(time_signed, FUDGE, 0, &[])?);
+
+        let mac = self.mac(&digest);
+        let original_id = read_id(message)?;
+        self.append_record(message, time_signed, &mac, original_id)?;
+        Ok(mac)
+    }
+
+    /// Computes the MAC of the given data with this key.
+    #[cfg(feature = "with_tsig")]
+    fn mac(&self, data: &[u8]) -> Vec<u8> {
+        let key = hmac::Key::new(self.algorithm.hmac_algorithm(), &self.secret);
+        hmac::sign(&key, data).as_ref().to_vec()
+    }
+
+    /// Writes the TSIG variables that get digested along with a message:
+    /// the key name, class, TTL, and algorithm name, then the timers, the
+    /// error, and the other data.
+    fn variables(&self, time_signed: u64, fudge: u16, error: u16, other: &[u8]) -> io::Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        bytes.write_labels(&self.name.to_canonical())?;
+        bytes.write_u16::<BigEndian>(CLASS_ANY)?;
+        bytes.write_u32::<BigEndian>(0)?;  // TTL
+        bytes.write_labels(&self.algorithm.labels())?;
+        write_timers(&mut bytes, time_signed, fudge)?;
+        bytes.write_u16::<BigEndian>(error)?;
+        bytes.write_u16::<BigEndian>(u16::try_from(other.len()).map_err(|_| io::Error::from(io::ErrorKind::InvalidInput))?)?;
+        bytes.extend_from_slice(other);
+        Ok(bytes)
+    }
+
+    /// Appends a TSIG record with the given MAC to the message, and
+    /// increments its additional record count.
+    fn append_record(&self, message: &mut Vec<u8>, time_signed: u64, mac: &[u8], original_id: u16) -> io::Result<()> {
+        let mut rdata = Vec::new();
+        rdata.write_labels(&self.algorithm.labels())?;
+        write_timers(&mut rdata, time_signed, FUDGE)?;
+        rdata.write_u16::<BigEndian>(u16::try_from(mac.len()).expect("MAC too long"))?;
+        rdata.extend_from_slice(mac);
+        rdata.write_u16::<BigEndian>(original_id)?;
+        rdata.write_u16::<BigEndian>(0)?;  // error
+        rdata.write_u16::<BigEndian>(0)?;  // other length
+
+        message.write_labels(&self.name)?;
+        message.write_u16::<BigEndian>(RR_TYPE)?;
+        message.write_u16::<BigEndian>(CLASS_ANY)?;
+        message.write_u32::<BigEndian>(0)?;  // TTL
+        message.write_u16::<BigEndian>(u16::try_from(rdata.len()).expect("TSIG record too long"))?;
+        message.extend(rdata);
+
+        let additional_count = read_additional_count(message)?;
+        set_additional_count(message, additional_count + 1)
+    }
+}
+
+
+/// Checks the signatures on the responses to a signed request. A single
+/// response has to be signed, but the messages of a zone transfer after the
+/// first one are allowed to leave their signatures out, as long as one in
+/// every hundred has one, and the last one does.
+#[derive(Debug)]
+pub struct TsigVerifier<'key> {
+
+    /// The key that the responses should be signed with.
+    key: &'key TsigKey,
+
+    /// The MAC of the request, or the last signed response, which the next
+    /// signature covers.
+    prior_mac: Vec<u8>,
+
+    /// The bytes of the unsigned messages since the last signed one.
+    unsigned: Vec<u8>,
+
+    /// Whether any message has been checked yet.
+    started: bool,
+}
+
+impl<'key> TsigVerifier<'key> {
+
+    /// Creates a new verifier for the responses to the request that was
+    /// signed with the given key and got the given MAC.
+    pub fn new(key: &'key TsigKey, request_mac: Vec<u8>) -> Self {
+        Self { key, prior_mac: request_mac, unsigned: Vec::new(), started: false }
+    }
+
+    /// Checks the signature on the next response, at the given time in
+    /// seconds since the Unix epoch, and returns the message with its TSIG
+    /// record removed so it can be parsed as normal.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the message should have been signed but was not,
+    /// if it was signed with a different key, if the MAC is wrong, if the
+    /// time it was signed is too far from now, or if the server said that
+    /// it could not check the request’s signature.
+    #[cfg(feature = "with_tsig")]
+    pub fn verify(&mut self, message: &[u8], now: u64) -> Result<Vec<u8>, TsigError> {
+        let first = ! self.started;
+        self.started = true;
+
+        let record = match find_record(message)? {
+            Some(record)  => record,
+            None if first => return Err(TsigError::Unsigned),
+            None => {
+                self.unsigned.extend_from_slice(message);
+                if self.unsigned.len() > usize::from(u16::MAX) * 99 {
+                    return Err(TsigError::Unsigned);
+                }
+                return Ok(message.to_vec());
+            }
+        };
+
+        if record.key_name.to_canonical() != self.key.name.to_canonical()
+        || record.algorithm.to_canonical() != self.key.algorithm.labels() {
+            return Err(TsigError::WrongKey);
+        }
+
+        if record.error != 0 {
+            return Err(TsigError::ServerError(record.error));
+        }
+
+        let stripped = strip_record(message, &record)?;
+
+        let mut digest = Vec::new();
+        digest.write_u16::<BigEndian>(u16::try_from(self.prior_mac.len()).map_err(|_| TsigError::BadSignature)?)?;
+        digest.extend_from_slice(&self.prior_mac);
+        digest.extend(std::mem::take(&mut self.unsigned));
+        digest.extend_from_slice(&stripped);
+        if first {
+            digest.extend(self.key.variables(record.time_signed, record.fudge, record.error, &record.other)?);
+        }
+        else {
+            write_timers(&mut digest, record.time_signed, record.fudge)?;
+        }
+
+        let key = hmac::Key::new(self.key.algorithm.hmac_algorithm(), &self.key.secret);
+        hmac::verify(&key, &digest, &record.mac).map_err(|_| TsigError::BadSignature)?;
+
+        if now.max(record.time_signed) - now.min(record.time_signed) > u64::from(record.fudge) {
+            return Err(TsigError::BadTime);
+        }
+
+        self.prior_mac = record.mac;
+        Ok(stripped)
+    }
+
+    /// Checks that the last message was signed, once every message has been
+    /// passed to `verify`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there were any unsigned messages after the last
+    /// signed one.
+    pub fn finish(&self) -> Result<(), TsigError> {
+        if self.unsigned.is_empty() {
+            Ok(())
+        }
+        else {
+            Err(TsigError::Unsigned)
+        }
+    }
+}
+
+
+/// Something wrong with the signature on a response.
+#[derive(PartialEq, Debug)]
+pub enum TsigError {
+
+    /// The response should have been signed, but was not.
+    Unsigned,
+
+    /// The response was signed with a key with a different name or
+    /// algorithm to the one the request was signed with.
+    WrongKey,
+
+    /// The MAC in the response is not the MAC of the response.
+    BadSignature,
+
+    /// The time the response was signed is further from now than the fudge
+    /// allows.
+    BadTime,
+
+    /// The server could not check the request’s signature, and sent back
+    /// this TSIG error code.
+    ServerError(u16),
+
+    /// The TSIG record could not be read.
+    Malformed(WireError),
+}
+
+impl From<WireError> for TsigError {
+    fn from(inner: WireError) -> Self {
+        Self::Malformed(inner)
+    }
+}
+
+impl From<io::Error> for TsigError {
+    fn from(inner: io::Error) -> Self {
+        Self::Malformed(WireError::from(inner))
+    }
+}
+
+impl fmt::Display for TsigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unsigned         => write!(f, "response was not signed"),
+            Self::WrongKey         => write!(f, "response was signed with a different key"),
+            Self::BadSignature     => write!(f, "response signature is wrong"),
+            Self::BadTime          => write!(f, "response was signed too far from the current time"),
+            Self::ServerError(16)  => write!(f, "server rejected the request signature (BADSIG)"),
+            Self::ServerError(17)  => write!(f, "server does not know the key (BADKEY)"),
+            Self::ServerError(18)  => write!(f, "server thinks the request was signed at the wrong time (BADTIME)"),
+            Self::ServerError(22)  => write!(f, "server rejected the truncated signature (BADTRUNC)"),
+            Self::ServerError(n)   => write!(f, "server returned TSIG error {}", n),
+            Self::Malformed(_)     => write!(f, "malformed TSIG record"),
+        }
+    }
+}
+
+
+/// Writes the time signed, as a 48-bit number, followed by the fudge.
+fn write_timers(bytes: &mut Vec<u8>, time_signed: u64, fudge: u16) -> io::Result<()> {
+    bytes.write_u48::<BigEndian>(time_signed)?;
+    bytes.write_u16::<BigEndian>(fudge)
+}
+
+/// Reads the ID from the header of a message.
+fn read_id(message: &[u8]) -> io::Result<u16> {
+    Cursor::new(message).read_u16::<BigEndian>()
+}
+
+/// Reads the additional record count from the header of a message.
+fn read_additional_count(message: &[u8]) -> io::Result<u16> {
+    let mut c = Cursor::new(message);
+    c.set_position(10);
+    c.read_u16::<BigEndian>()
+}
+
+/// Overwrites the additional record count in the header of a message.
+fn set_additional_count(message: &mut [u8], count: u16) -> io::Result<()> {
+    let field = message.get_mut(10 .. 12).ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))?;
+    field.copy_from_slice(&count.to_be_bytes());
+    Ok(())
+}
+
+/// Finds the TSIG record in a message, which has to be the last record in
+/// the additional section, returning `None` if it is not there.
+fn find_record(message: &[u8]) -> Result<Option<TsigRecord>, WireError> {
+    let mut c = Cursor::new(message);
+    c.set_position(4);
+
+    let query_count = c.read_u16::<BigEndian>()?;
+    let record_count = u32::from(c.read_u16::<BigEndian>()?)
+                     + u32::from(c.read_u16::<BigEndian>()?)
+                     + u32::from(c.read_u16::<BigEndian>()?);
+
+    if record_count == 0 {
+        return Ok(None);
+    }
+
+    for _ in 0 .. query_count {
+        c.read_labels()?;
+        c.set_position(c.position() + 4);  // type and class
+    }
+
+    for _ in 1 .. record_count {
+        c.read_labels()?;
+        c.set_position(c.position() + 8);  // type, class, and TTL
+        let length = c.read_u16::<BigEndian>()?;
+        c.set_position(c.position() + u64::from(length));
+    }
+
+    let offset = usize::try_from(c.position()).map_err(|_| WireError::IO)?;
+    let (key_name, _) = c.read_labels()?;
+    if c.read_u16::<BigEndian>()? != RR_TYPE {
+        return Ok(None);
+    }
+
+    c.read_u16::<BigEndian>()?;  // class
+    c.read_u32::<BigEndian>()?;  // TTL
+    c.read_u16::<BigEndian>()?;  // length
+
+    let (algorithm, _) = c.read_labels()?;
+    let time_signed = c.read_u48::<BigEndian>()?;
+    let fudge = c.read_u16::<BigEndian>()?;
+
+    let mut mac = vec![0; usize::from(c.read_u16::<BigEndian>()?)];
+    c.read_exact(&mut mac)?;
+
+    let original_id = c.read_u16::<BigEndian>()?;
+    let error = c.read_u16::<BigEndian>()?;
+
+    let mut other = vec![0; usize::from(c.read_u16::<BigEndian>()?)];
+    c.read_exact(&mut other)?;
+
+    Ok(Some(TsigRecord { offset, key_name, algorithm, time_signed, fudge, mac, original_id, error, other }))
+}
+
+/// Returns the message as it was before the TSIG record was added: without
+/// the record, with one less additional record, and with the original ID.
+fn strip_record(message: &[u8], record: &TsigRecord) -> io::Result<Vec<u8>> {
+    let mut stripped = message[.. record.offset].to_vec();
+    let additional_count = read_additional_count(&stripped)?;
+    set_additional_count(&mut stripped, additional_count - 1)?;
+    stripped[.. 2].copy_from_slice(&record.original_id.to_be_bytes());
+    Ok(stripped)
+}
+
+
+#[cfg(test)]
+#[cfg(feature = "with_tsig")]
+mod test {
+    use super::*;
+    use crate::record::RecordType;
+    use crate::types::{Flags, Query, QClass, Request};
+    use pretty_assertions::assert_eq;
+
+    fn key() -> TsigKey {
+        TsigKey {
+            name: Labels::encode("transfer.lookup.dog").unwrap(),
+            algorithm: TsigAlgorithm::HmacSha256,
+            secret: b"shared secret".to_vec(),
+        }
+    }
+
+    fn request_bytes() -> Vec<u8> {
+        let query = Query {
+            qname: Labels::encode("lookup.dog").unwrap(),
+            qtype: RecordType::SOA,
+            qclass: QClass::IN,
+        };
+
+        Request { transaction_id: 0xceac, flags: Flags::query(), query, additional: None }.to_bytes().unwrap()
+    }
+
+    /// Signs a response the way a server would, covering the request MAC
+    /// and, when it is not the first, only the timers.
+    fn sign_response(key: &TsigKey, message: &mut Vec<u8>, prior_mac: &[u8], unsigned: &[u8], first: bool, time_signed: u64) -> Vec<u8> {
+        let mut digest = Vec::new();
+        digest.write_u16::<BigEndian>(u16::try_from(prior_mac.len()).unwrap()).unwrap();
+        digest.extend_from_slice(prior_mac);
+        digest.extend_from_slice(unsigned);
+        digest.extend_from_slice(message);
+        if first {
+            digest.extend(key.variables(time_signed, FUDGE, 0, &[]).unwrap());
+        }
+        else {
+            write_timers(&mut digest, time_signed, FUDGE).unwrap();
+        }
+
+        let mac = key.mac(&digest);
+        let original_id = read_id(message).unwrap();
+        key.append_record(message, time_signed, &mac, original_id).unwrap();
+        mac
+    }
+
+    fn response_bytes() -> Vec<u8> {
+        let mut response = request_bytes();
+        response[2] |= 0x80;  // QR bit
+        response
+    }
+
+    #[test]
+    fn algorithm_names() {
+        assert_eq!(TsigAlgorithm::from_name("hmac-sha256"), Some(TsigAlgorithm::HmacSha256));
+        assert_eq!(TsigAlgorithm::from_name("HMAC-SHA1."), Some(TsigAlgorithm::HmacSha1));
+        assert_eq!(TsigAlgorithm::from_name("hmac-md5"), None);
+    }
+
+    #[test]
+    fn signed_request() {
+        let mut message = request_bytes();
+        let unsigned_len = message.len();
+        let mac = key().sign_request(&mut message, 1_600_000_000).unwrap();
+
+        assert_eq!(read_additional_count(&message).unwrap(), 1);
+
+        let record = find_record(&message).unwrap().unwrap();
+        assert_eq!(record.offset, unsigned_len);
+        assert_eq!(record.key_name, Labels::encode("transfer.lookup.dog").unwrap());
+        assert_eq!(record.algorithm, Labels::encode("hmac-sha256").unwrap());
+        assert_eq!(record.time_signed, 1_600_000_000);
+        assert_eq!(record.fudge, FUDGE);
+        assert_eq!(record.mac, mac);
+        assert_eq!(record.original_id, 0xceac);
+        assert_eq!(record.error, 0);
+    }
+
+    #[test]
+    fn request_mac() {
+        let mut message = request_bytes();
+        let mac = key().sign_request(&mut message, 1_600_000_000).unwrap();
+
+        assert_eq!(mac, vec![
+            0x71, 0xff, 0xbe, 0x70, 0x7a, 0x9f, 0xa5, 0xb9, 0x93, 0xb1, 0xdb, 0xca, 0xe3, 0xbe, 0x8e, 0xa7,
+            0x83, 0x17, 0x7e, 0xe2, 0x07, 0xba, 0x3a, 0x67, 0x51, 0xd2, 0x67, 0xc9, 0x6d, 0x0a, 0x34, 0xd4,
+        ]);
+    }
+
+    #[test]
+    fn unsigned_request_has_no_record() {
+        assert_eq!(find_record(&request_bytes()), Ok(None));
+    }
+
+    #[test]
+    fn good_response() {
+        let key = key();
+        let mut request = request_bytes();
+        let request_mac = key.sign_request(&mut request, 1_600_000_000).unwrap();
+
+        let mut response = response_bytes();
+        let original = response.clone();
+        sign_response(&key, &mut response, &request_mac, &[], true, 1_600_000_001);
+
+        let mut verifier = TsigVerifier::new(&key, request_mac);
+        assert_eq!(verifier.verify(&response, 1_600_000_002), Ok(original));
+        assert_eq!(verifier.finish(), Ok(()));
+    }
+
+    #[test]
+    fn unsigned_response() {
+        let key = key();
+        let mut verifier = TsigVerifier::new(&key, vec![1, 2, 3]);
+        assert_eq!(verifier.verify(&response_bytes(), 1_600_000_000), Err(TsigError::Unsigned));
+    }
+
+    #[test]
+    fn tampered_response() {
+        let key = key();
+        let mut response = response_bytes();
+        sign_response(&key, &mut response, &[1, 2, 3], &[], true, 1_600_000_000);
+        response[3] ^= 0x01;
+
+        let mut verifier = TsigVerifier::new(&key, vec![1, 2, 3]);
+        assert_eq!(verifier.verify(&response, 1_600_000_000), Err(TsigError::BadSignature));
+    }
+
+    #[test]
+    fn response_to_another_request() {
+        let key = key();
+        let mut response = response_bytes();
+        sign_response(&key, &mut response, &[1, 2, 3], &[], true, 1_600_000_000);
+
+        let mut verifier = TsigVerifier::new(&key, vec![4, 5, 6]);
+        assert_eq!(verifier.verify(&response, 1_600_000_000), Err(TsigError::BadSignature));
+    }
+
+    #[test]
+    fn different_key() {
+        let mut other_key = key();
+        other_key.name = Labels::encode("other.lookup.dog").unwrap();
+
+        let mut response = response_bytes();
+        sign_response(&other_key, &mut response, &[1, 2, 3], &[], true, 1_600_000_000);
+
+        let key = key();
+        let mut verifier = TsigVerifier::new(&key, vec![1, 2, 3]);
+        assert_eq!(verifier.verify(&response, 1_600_000_000), Err(TsigError::WrongKey));
+    }
+
+    #[test]
+    fn different_secret() {
+        let mut other_key = key();
+        other_key.secret = b"another secret".to_vec();
+
+        let mut response = response_bytes();
+        sign_response(&other_key, &mut response, &[1, 2, 3], &[], true, 1_600_000_000);
+
+        let key = key();
+        let mut verifier = TsigVerifier::new(&key, vec![1, 2, 3]);
+        assert_eq!(verifier.verify(&response, 1_600_000_000), Err(TsigError::BadSignature));
+    }
+
+    #[test]
+    fn stale_response() {
+        let key = key();
+        let mut response = response_bytes();
+        sign_response(&key, &mut response, &[1, 2, 3], &[], true, 1_600_000_000);
+
+        let mut verifier = TsigVerifier::new(&key, vec![1, 2, 3]);
+        assert_eq!(verifier.verify(&response, 1_600_001_000), Err(TsigError::BadTime));
+    }
+
+    #[test]
+    fn server_error() {
+        let key = key();
+        let mut response = response_bytes();
+        key.append_record(&mut response, 1_600_000_000, &[], 0xceac).unwrap();
+        let length = response.len();
+        response[length - 3] = 17;  // BADKEY
+
+        let mut verifier = TsigVerifier::new(&key, vec![1, 2, 3]);
+        assert_eq!(verifier.verify(&response, 1_600_000_000), Err(TsigError::ServerError(17)));
+    }
+
+    #[test]
+    fn zone_transfer() {
+        let key = key();
+        let mut first = response_bytes();
+        let first_mac = sign_response(&key, &mut first, &[1, 2, 3], &[], true, 1_600_000_000);
+
+        let middle = response_bytes();
+
+        let mut last = response_bytes();
+        sign_response(&key, &mut last, &first_mac, &middle, false, 1_600_000_001);
+
+        let mut verifier = TsigVerifier::new(&key, vec![1, 2, 3]);
+        assert!(verifier.verify(&first, 1_600_000_000).is_ok());
+        assert_eq!(verifier.verify(&middle, 1_600_000_000), Ok(middle.clone()));
+        assert_eq!(verifier.finish(), Err(TsigError::Unsigned));
+        assert!(verifier.verify(&last, 1_600_000_000).is_ok());
+        assert_eq!(verifier.finish(), Ok(()));
+    }
+
+    #[test]
+    fn zone_transfer_ending_unsigned() {
+        let key = key();
+        let mut first = response_bytes();
+        sign_response(&key, &mut first, &[1, 2, 3], &[], true, 1_600_000_000);
+
+        let mut verifier = TsigVerifier::new(&key, vec![1, 2, 3]);
+        assert!(verifier.verify(&first, 1_600_000_000).is_ok());
+        assert!(verifier.verify(&response_bytes(), 1_600_000_000).is_ok());
+        assert_eq!(verifier.finish(), Err(TsigError::Unsigned));
+    }
+}
@@ -3,12 +3,19 @@
 //! with the request packet having zero answer fields, and the response packet
 //! having at least one record in its answer fields.
 
-use crate::record::{Record, RecordType, OPT};
+use log::*;
+
+use crate::record::{Record, RecordType, OPT, EdnsOption};
 use crate::strings::Labels;
+use crate::wire::{Cursor, WireError};
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
 
 
 /// A request that gets sent out over a transport.
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Request {
 
     /// The transaction ID of this request. This is used to make sure
@@ -29,7 +36,8 @@ pub struct Request {
 
 
 /// A response obtained from a DNS server.
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Response {
 
     /// The transaction ID, which should match the ID of the request.
@@ -52,8 +60,85 @@ pub struct Response {
 }
 
 
-/// A DNS query section.
+/// A response that was read leniently: every record that could be read,
+/// along with the errors for the ones that could not.
+#[derive(PartialEq, Debug)]
+pub struct PartialResponse {
+
+    /// The response, without the records that could not be read.
+    pub response: Response,
+
+    /// Where each record that could not be read was, and what was wrong
+    /// with it.
+    pub errors: Vec<RecordError>,
+}
+
+/// A response whose records are read one at a time, as they are iterated
+/// over, instead of all being collected into vectors first. The header and
+/// the queries are read straight away. Each item is a record along with the
+/// section it was in; the first error ends the iteration, as there is no
+/// way to tell where the next record starts.
+///
+/// As the OPT record has not been read when the header is, the error code
+/// in the flags is only the lower four bits from the header.
+#[derive(Debug, Clone)]
+pub struct ResponseStream<'a> {
+
+    /// The transaction ID, which should match the ID of the request.
+    pub transaction_id: u16,
+
+    /// The flags that accompany every DNS packet.
+    pub flags: Flags,
+
+    /// The queries section.
+    pub queries: Vec<Query>,
+
+    /// The cursor over the message, positioned at the next record.
+    pub(crate) c: Cursor<&'a [u8]>,
+
+    /// The number of records left to read in the answer, authority, and
+    /// additional sections.
+    pub(crate) counts: [u16; 3],
+
+    /// The position of the next record in its section.
+    pub(crate) index: u16,
+}
+
+/// A record in a response that could not be read.
 #[derive(PartialEq, Debug)]
+pub struct RecordError {
+
+    /// The section the record was in.
+    pub section: Section,
+
+    /// The position of the record in its section, counting from zero.
+    pub index: u16,
+
+    /// What was wrong with the record.
+    pub error: WireError,
+}
+
+/// One of the four sections of a DNS message.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum Section {
+
+    /// The question section, which holds the queries.
+    Question,
+
+    /// The answer section.
+    Answer,
+
+    /// The authority section.
+    Authority,
+
+    /// The additional section.
+    Additional,
+}
+
+
+/// A DNS query section.
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Query {
 
     /// The domain name being queried, in human-readable dotted notation.
@@ -68,7 +153,8 @@ pub struct Query {
 
 
 /// A DNS answer section.
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Answer {
 
     /// This is a standard answer with every field.
@@ -103,6 +189,7 @@ pub enum Answer {
 /// A DNS record class. Of these, the only one that’s in regular use anymore
 /// is the Internet class.
 #[derive(PartialEq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum QClass {
 
     /// The **Internet** class.
@@ -121,6 +208,7 @@ pub enum QClass {
 
 /// The flags that accompany every DNS packet.
 #[derive(PartialEq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Flags {
 
     /// Whether this packet is a response packet.
@@ -155,26 +243,64 @@ pub struct Flags {
 
 
 /// A number representing the operation being performed.
+///
+/// # References
+///
+/// - [RFC 1035 §4.1.1](https://tools.ietf.org/html/rfc1035) — Domain Names,
+///   Implementation and Specification (November 1987)
+/// - [RFC 1996](https://tools.ietf.org/html/rfc1996) — A Mechanism for
+///   Prompt Notification of Zone Changes (DNS NOTIFY) (August 1996)
+/// - [RFC 2136](https://tools.ietf.org/html/rfc2136) — Dynamic Updates in
+///   the Domain Name System (DNS UPDATE) (April 1997)
+/// - [RFC 8490](https://tools.ietf.org/html/rfc8490) — DNS Stateful
+///   Operations (March 2019)
 #[derive(PartialEq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Opcode {
 
     /// This request is a standard query, or this response is answering a
     /// standard query.
     Query,
 
+    /// A request for the server’s status. Its format was never defined, so
+    /// servers answer it with `NOTIMP`.
+    Status,
+
+    /// A primary nameserver telling a secondary that a zone has changed.
+    /// The question section holds the zone’s SOA record, and the answer
+    /// section may hold its new SOA record.
+    Notify,
+
+    /// A request to add or remove records in a zone. Its sections are used
+    /// differently from a query’s: the question section names the zone, the
+    /// answer section holds the prerequisites, and the authority section
+    /// holds the updates.
+    Update,
+
+    /// A DNS Stateful Operations message, sent over a TCP or TLS session.
+    /// All four sections are empty, and the message holds a list of TLVs
+    /// instead, which the `DsoMessage` type reads.
+    Dso,
+
     /// Any other opcode. This can be from 1 to 15, as the opcode field is
     /// four bits wide, and 0 is taken.
     Other(u8),
 }
 
 
-/// A code indicating an error.
+/// A code indicating an error. The header only has room for the lower four
+/// bits of the code; when the response has an OPT record, its higher eight
+/// bits are in there, and the two get put back together when the response
+/// is read.
 ///
 /// # References
 ///
 /// - [RFC 6895 §2.3](https://tools.ietf.org/html/rfc6895#section-2.3) — Domain
 ///   Name System (DNS) IANA Considerations (April 2013)
+/// - [RFC 6891 §6.1.3](https://tools.ietf.org/html/rfc6891#section-6.1.3) —
+///   Extension Mechanisms for DNS (EDNS(0)) (April 2013)
 #[derive(PartialEq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ErrorCode {
 
     /// `FormErr` — The server was unable to interpret the query.
@@ -197,6 +323,10 @@ pub enum ErrorCode {
     /// or failed to verify a signature. The same code is used for both.
     BadVersion,
 
+    /// `BADCOOKIE` — The server rejected the client’s cookie, and sent back
+    /// a server cookie to use instead.
+    BadCookie,
+
     /// An error code with no currently-defined meaning.
     Other(u16),
 
@@ -205,6 +335,52 @@ pub enum ErrorCode {
 }
 
 
+impl Response {
+
+    /// Returns the options in every OPT record in the response. If the
+    /// options in a record fail to parse, none are returned for it.
+    pub fn edns_options(&self) -> Vec<EdnsOption> {
+        let opts = self.answers.iter()
+            .chain(&self.authorities)
+            .chain(&self.additionals)
+            .filter_map(|answer| match answer {
+                Answer::Pseudo { opt, .. }  => Some(opt),
+                Answer::Standard { .. }     => None,
+            });
+
+        let mut options = Vec::new();
+        for opt in opts {
+            match opt.options() {
+                Ok(os)  => options.extend(os),
+                Err(e)  => warn!("Failed to parse EDNS options: {:?}", e),
+            }
+        }
+
+        options
+    }
+
+    /// Whether the server rejected the request’s cookie with a `BADCOOKIE`
+    /// error.
+    pub fn has_bad_cookie(&self) -> bool {
+        self.flags.error_code == Some(ErrorCode::BadCookie)
+    }
+
+    /// Returns the highest EDNS version the server supports if it rejected
+    /// the request’s EDNS version with a `BADVERS` error. The version is in
+    /// the OPT record that carried the error code.
+    pub fn bad_version(&self) -> Option<u8> {
+        if self.flags.error_code != Some(ErrorCode::BadVersion) {
+            return None;
+        }
+
+        self.additionals.iter().find_map(|answer| match answer {
+            Answer::Pseudo { opt, .. }  => Some(opt.edns0_version),
+            Answer::Standard { .. }     => None,
+        })
+    }
+}
+
+
 impl Answer {
 
     /// Whether this Answer holds a standard record, not a pseudo record.
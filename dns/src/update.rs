@@ -0,0 +1,340 @@
+//! Building DNS UPDATE messages, which ask a primary nameserver to add
+//! records to a zone or remove them from it.
+//!
+//! An UPDATE message uses the four sections of a DNS message differently
+//! from a query. The question section names the zone, the answer section
+//! holds prerequisites that must be true for the update to go ahead, and the
+//! authority section holds the changes to make. Most prerequisites and
+//! deletions have no record data, and say what they mean through the class
+//! and type of their records instead.
+//!
+//! # References
+//!
+//! - [RFC 2136](https://tools.ietf.org/html/rfc2136) — Dynamic Updates in
+//!   the Domain Name System (DNS UPDATE) (April 1997)
+
+use crate::builder::RequestBuilder;
+use crate::record::{Record, RecordType, UnknownQtype};
+use crate::strings::Labels;
+use crate::types::*;
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+
+/// The class that marks a prerequisite or deletion as applying to any
+/// record set or record (RFC 2136 §1.3).
+const CLASS_ANY: QClass = QClass::Other(255);
+
+/// The class that marks a prerequisite as requiring something to not exist,
+/// or a deletion as being of one record (RFC 2136 §1.3).
+const CLASS_NONE: QClass = QClass::Other(254);
+
+/// The type number that stands for every record type.
+const TYPE_ANY: u16 = 255;
+
+
+/// An UPDATE message for one zone, with the prerequisites it has and the
+/// changes it makes. Start one with `Update::new`, then turn it into a
+/// message with `to_builder`.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Update {
+    zone: Labels,
+    class: QClass,
+    prerequisites: Vec<Prerequisite>,
+    operations: Vec<Operation>,
+}
+
+/// Something that must be true of the zone for the update to be made. If
+/// any prerequisite fails, the server makes none of the changes.
+#[derive(PartialEq, Debug, Clone)]
+pub enum Prerequisite {
+
+    /// There is at least one record of this type at this name.
+    RRsetExists {
+
+        /// The name that must have records of the type.
+        name: Labels,
+
+        /// The type of the records.
+        record_type: RecordType,
+    },
+
+    /// The records at a name of the type of these records are exactly these
+    /// records. Every record must have the same type.
+    RRsetEquals {
+
+        /// The name that must have these records.
+        name: Labels,
+
+        /// The records that must be there, and no others of their type.
+        records: Vec<Record>,
+    },
+
+    /// There are no records of this type at this name.
+    RRsetDoesNotExist {
+
+        /// The name that must not have records of the type.
+        name: Labels,
+
+        /// The type of the records.
+        record_type: RecordType,
+    },
+
+    /// There is at least one record of any type at this name.
+    NameInUse(Labels),
+
+    /// There are no records of any type at this name.
+    NameNotInUse(Labels),
+}
+
+/// A change to make to the zone.
+#[derive(PartialEq, Debug, Clone)]
+pub enum Operation {
+
+    /// Adds a record to the zone, or does nothing if it is already there.
+    Add {
+
+        /// The name to add the record to.
+        name: Labels,
+
+        /// The time-to-live of the record, in seconds.
+        ttl: u32,
+
+        /// The record to add.
+        record: Record,
+    },
+
+    /// Deletes every record of this type at this name.
+    DeleteRRset {
+
+        /// The name to delete the records from.
+        name: Labels,
+
+        /// The type of the records to delete.
+        record_type: RecordType,
+    },
+
+    /// Deletes every record of every type at this name.
+    DeleteName(Labels),
+
+    /// Deletes one record, the one with the same type and data as this.
+    DeleteRecord {
+
+        /// The name to delete the record from.
+        name: Labels,
+
+        /// The record to delete.
+        record: Record,
+    },
+}
+
+
+impl Update {
+
+    /// Starts building an update to the zone with the given name, in the
+    /// Internet class, with no prerequisites or changes.
+    pub fn new(zone: Labels) -> Self {
+        Self {
+            zone,
+            class: QClass::IN,
+            prerequisites: Vec::new(),
+            operations: Vec::new(),
+        }
+    }
+
+    /// Sets the class of the zone.
+    pub fn class(mut self, class: QClass) -> Self {
+        self.class = class;
+        self
+    }
+
+    /// Adds a prerequisite that must be true for the update to be made.
+    pub fn prerequisite(mut self, prerequisite: Prerequisite) -> Self {
+        self.prerequisites.push(prerequisite);
+        self
+    }
+
+    /// Adds a change to make to the zone. Changes are made in order.
+    pub fn operation(mut self, operation: Operation) -> Self {
+        self.operations.push(operation);
+        self
+    }
+
+    /// Returns a builder for the UPDATE message, with the zone, the
+    /// prerequisites, and the changes in their sections. It has a
+    /// transaction ID of zero until one gets set on the builder, and
+    /// recursion is not requested, as updates are sent to the zone’s
+    /// primary nameserver directly.
+    pub fn to_builder(&self) -> RequestBuilder {
+        let flags = Flags {
+            opcode: Opcode::Update,
+            recursion_desired: false,
+            .. Flags::query()
+        };
+
+        let zone = Query { qname: self.zone.clone(), qclass: self.class, qtype: RecordType::SOA };
+        let mut builder = Request::builder().flags(flags).query(zone);
+
+        for prerequisite in &self.prerequisites {
+            for answer in prerequisite.to_answers(self.class) {
+                builder = builder.answer(answer);
+            }
+        }
+
+        for operation in &self.operations {
+            builder = builder.authority(operation.to_answer(self.class));
+        }
+
+        builder
+    }
+}
+
+impl Prerequisite {
+
+    /// Returns the records that express this prerequisite, in a zone of the
+    /// given class (RFC 2136 §2.4).
+    fn to_answers(&self, zone_class: QClass) -> Vec<Answer> {
+        match self {
+            Self::RRsetExists { name, record_type } => {
+                vec![ answer(name, CLASS_ANY, 0, no_data(record_type.type_number())) ]
+            }
+            Self::RRsetEquals { name, records } => {
+                records.iter()
+                       .map(|record| answer(name, zone_class, 0, record.clone()))
+                       .collect()
+            }
+            Self::RRsetDoesNotExist { name, record_type } => {
+                vec![ answer(name, CLASS_NONE, 0, no_data(record_type.type_number())) ]
+            }
+            Self::NameInUse(name) => {
+                vec![ answer(name, CLASS_ANY, 0, no_data(TYPE_ANY)) ]
+            }
+            Self::NameNotInUse(name) => {
+                vec![ answer(name, CLASS_NONE, 0, no_data(TYPE_ANY)) ]
+            }
+        }
+    }
+}
+
+impl Operation {
+
+    /// Returns the record that expresses this change, in a zone of the given
+    /// class (RFC 2136 §2.5).
+    fn to_answer(&self, zone_class: QClass) -> Answer {
+        match self {
+            Self::Add { name, ttl, record } => {
+                answer(name, zone_class, *ttl, record.clone())
+            }
+            Self::DeleteRRset { name, record_type } => {
+                answer(name, CLASS_ANY, 0, no_data(record_type.type_number()))
+            }
+            Self::DeleteName(name) => {
+                answer(name, CLASS_ANY, 0, no_data(TYPE_ANY))
+            }
+            Self::DeleteRecord { name, record } => {
+                answer(name, CLASS_NONE, 0, record.clone())
+            }
+        }
+    }
+}
+
+fn answer(name: &Labels, qclass: QClass, ttl: u32, record: Record) -> Answer {
+    Answer::Standard { qname: name.clone(), qclass, ttl, record }
+}
+
+/// Returns a record of the given type with no data, which is how most
+/// prerequisites and deletions refer to a whole record set.
+fn no_data(type_number: u16) -> Record {
+    Record::Other { type_number: UnknownQtype::UnheardOf(type_number), bytes: Vec::new() }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    use crate::record::A;
+
+    fn name(input: &str) -> Labels {
+        Labels::encode(input).unwrap()
+    }
+
+    #[test]
+    fn add_if_not_in_use() {
+        let update = Update::new(name("lookup.dog"))
+            .prerequisite(Prerequisite::NameNotInUse(name("new.lookup.dog")))
+            .operation(Operation::Add {
+                name: name("new.lookup.dog"),
+                ttl: 300,
+                record: Record::A(A { address: "192.0.2.1".parse().unwrap() }),
+            });
+
+        let bytes = update.to_builder().transaction_id(0x1234).compress_names(false).to_bytes().unwrap();
+
+        assert_eq!(bytes, vec![
+            0x12, 0x34,  // transaction ID
+            0x28, 0x00,  // flags (UPDATE)
+            0x00, 0x01, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00,  // counts (1, 1, 1, 0)
+
+            // zone:
+            0x06, 0x6c, 0x6f, 0x6f, 0x6b, 0x75, 0x70, 0x03, 0x64, 0x6f, 0x67, 0x00,
+            0x00, 0x06,  // type SOA
+            0x00, 0x01,  // class IN
+
+            // prerequisite:
+            0x03, 0x6e, 0x65, 0x77, 0x06, 0x6c, 0x6f, 0x6f, 0x6b, 0x75, 0x70, 0x03, 0x64, 0x6f, 0x67, 0x00,
+            0x00, 0xff,  // type ANY
+            0x00, 0xfe,  // class NONE
+            0x00, 0x00, 0x00, 0x00,  // TTL
+            0x00, 0x00,  // no data
+
+            // update:
+            0x03, 0x6e, 0x65, 0x77, 0x06, 0x6c, 0x6f, 0x6f, 0x6b, 0x75, 0x70, 0x03, 0x64, 0x6f, 0x67, 0x00,
+            0x00, 0x01,  // type A
+            0x00, 0x01,  // class IN
+            0x00, 0x00, 0x01, 0x2c,  // TTL
+            0x00, 0x04,  // data length
+            0xc0, 0x00, 0x02, 0x01,  // address
+        ]);
+    }
+
+    #[test]
+    fn prerequisites() {
+        let a = Record::A(A { address: "192.0.2.1".parse().unwrap() });
+
+        assert_eq!(Prerequisite::RRsetExists { name: name("lookup.dog"), record_type: RecordType::MX }.to_answers(QClass::IN),
+                   vec![ answer(&name("lookup.dog"), CLASS_ANY, 0, no_data(15)) ]);
+
+        assert_eq!(Prerequisite::RRsetEquals { name: name("lookup.dog"), records: vec![ a.clone() ] }.to_answers(QClass::IN),
+                   vec![ answer(&name("lookup.dog"), QClass::IN, 0, a) ]);
+
+        assert_eq!(Prerequisite::RRsetDoesNotExist { name: name("lookup.dog"), record_type: RecordType::MX }.to_answers(QClass::IN),
+                   vec![ answer(&name("lookup.dog"), CLASS_NONE, 0, no_data(15)) ]);
+
+        assert_eq!(Prerequisite::NameInUse(name("lookup.dog")).to_answers(QClass::IN),
+                   vec![ answer(&name("lookup.dog"), CLASS_ANY, 0, no_data(255)) ]);
+    }
+
+    #[test]
+    fn deletions() {
+        let a = Record::A(A { address: "192.0.2.1".parse().unwrap() });
+
+        assert_eq!(Operation::DeleteRRset { name: name("lookup.dog"), record_type: RecordType::A }.to_answer(QClass::IN),
+                   answer(&name("lookup.dog"), CLASS_ANY, 0, no_data(1)));
+
+        assert_eq!(Operation::DeleteName(name("lookup.dog")).to_answer(QClass::IN),
+                   answer(&name("lookup.dog"), CLASS_ANY, 0, no_data(255)));
+
+        assert_eq!(Operation::DeleteRecord { name: name("lookup.dog"), record: a.clone() }.to_answer(QClass::IN),
+                   answer(&name("lookup.dog"), CLASS_NONE, 0, a));
+    }
+
+    #[test]
+    fn other_class() {
+        let builder = Update::new(name("lookup.dog")).class(QClass::CH).to_builder();
+        let bytes = builder.to_bytes().unwrap();
+        assert_eq!(&bytes[bytes.len() - 2 ..], &[ 0x00, 0x03 ]);
+    }
+}
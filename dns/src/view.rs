@@ -0,0 +1,390 @@
+//! Borrowed views of DNS responses, which point into the buffer the
+//! response was received in instead of copying each label into a `String`
+//! and each record into its own structure.
+//!
+//! Parsing a response this way allocates one vector per section and
+//! nothing else. Names and record data are only decoded when asked for,
+//! which saves time for programs that only look at a few fields of each
+//! response, such as when resolving a large batch of names.
+
+use std::convert::TryFrom;
+use std::fmt;
+
+use log::*;
+
+use crate::record::{Record, RecordType};
+use crate::strings::{Labels, MAX_NAME_LENGTH, RECURSION_LIMIT};
+use crate::types::*;
+use crate::wire::*;
+
+
+/// A response that borrows its names and record data from the buffer it
+/// was parsed from.
+#[derive(PartialEq, Debug, Clone)]
+pub struct ResponseView<'a> {
+
+    /// The transaction ID of the response.
+    pub transaction_id: u16,
+
+    /// The flags of the response.
+    pub flags: Flags,
+
+    /// The queries section.
+    pub queries: Vec<QueryView<'a>>,
+
+    /// The answers section.
+    pub answers: Vec<RecordView<'a>>,
+
+    /// The authoritative nameservers section.
+    pub authorities: Vec<RecordView<'a>>,
+
+    /// The additional records section, including any OPT pseudo-record.
+    pub additionals: Vec<RecordView<'a>>,
+}
+
+/// A query that borrows its name from the buffer it was parsed from.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub struct QueryView<'a> {
+
+    /// The domain name being queried.
+    pub qname: NameView<'a>,
+
+    /// The type number of the record being queried.
+    pub qtype: RecordType,
+
+    /// The class number of the record being queried.
+    pub qclass: QClass,
+}
+
+/// A record that borrows its name and data from the buffer it was parsed
+/// from. OPT pseudo-records are kept as they are, so their class and TTL
+/// fields hold the payload size and extended flags instead.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub struct RecordView<'a> {
+
+    /// The domain name that owns the record.
+    pub qname: NameView<'a>,
+
+    /// The record’s type number.
+    pub type_number: u16,
+
+    /// The record’s class number.
+    pub class_number: u16,
+
+    /// The record’s time-to-live, in seconds.
+    pub ttl: u32,
+
+    /// The record’s data, still in its wire format.
+    pub data: &'a [u8],
+
+    /// The position of the data in the message, needed to follow any
+    /// compression pointers in it.
+    data_offset: usize,
+
+    /// The whole message.
+    message: &'a [u8],
+}
+
+/// A domain name inside a message, which is only decoded when its labels
+/// are asked for. It has already been checked for pointer loops and for
+/// running off the end of the message.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub struct NameView<'a> {
+    message: &'a [u8],
+    offset: usize,
+}
+
+/// An iterator over the labels of a domain name, following compression
+/// pointers as it goes.
+#[derive(Debug, Clone)]
+pub struct LabelsIter<'a> {
+    message: &'a [u8],
+    offset: Option<usize>,
+}
+
+
+impl<'a> ResponseView<'a> {
+
+    /// Parses the header and sections of a response, borrowing from the
+    /// given slice. Every name is checked, but no record data is parsed.
+    pub fn from_bytes(bytes: &'a [u8]) -> Result<Self, WireError> {
+        info!("Parsing response view");
+        let mut c = Cursor::new(bytes);
+
+        let transaction_id = c.read_u16::<BigEndian>()?;
+        let flags = Flags::from_u16(c.read_u16::<BigEndian>()?);
+
+        let query_count      = c.read_u16::<BigEndian>()?;
+        let answer_count     = c.read_u16::<BigEndian>()?;
+        let authority_count  = c.read_u16::<BigEndian>()?;
+        let additional_count = c.read_u16::<BigEndian>()?;
+
+        // The same capping as in `Response::from_bytes`, as the counts
+        // cannot be trusted.
+        let mut queries = Vec::with_capacity(usize::from(query_count.min(9)));
+        for _ in 0 .. query_count {
+            let qname = NameView::read(bytes, &mut c)?;
+            let qtype = RecordType::from(c.read_u16::<BigEndian>()?);
+            let qclass = QClass::from_u16(c.read_u16::<BigEndian>()?);
+            queries.push(QueryView { qname, qtype, qclass });
+        }
+
+        let mut read_section = |count: u16| {
+            let mut records = Vec::with_capacity(usize::from(count.min(9)));
+            for _ in 0 .. count {
+                records.push(RecordView::read(bytes, &mut c)?);
+            }
+            Ok::<_, WireError>(records)
+        };
+
+        let answers = read_section(answer_count)?;
+        let authorities = read_section(authority_count)?;
+        let additionals = read_section(additional_count)?;
+
+        Ok(Self { transaction_id, flags, queries, answers, authorities, additionals })
+    }
+}
+
+impl<'a> RecordView<'a> {
+
+    /// Reads a record starting at the cursor’s position, leaving the cursor
+    /// after the end of its data.
+    fn read(message: &'a [u8], c: &mut Cursor<&[u8]>) -> Result<Self, WireError> {
+        let qname = NameView::read(message, c)?;
+        let type_number = c.read_u16::<BigEndian>()?;
+        let class_number = c.read_u16::<BigEndian>()?;
+        let ttl = c.read_u32::<BigEndian>()?;
+        let data_length = usize::from(c.read_u16::<BigEndian>()?);
+
+        let data_offset = position(c)?;
+        let data = message.get(data_offset .. data_offset + data_length).ok_or(WireError::IO)?;
+        c.set_position(c.position() + data_length as u64);
+
+        Ok(Self { qname, type_number, class_number, ttl, data, data_offset, message })
+    }
+
+    /// The type of the record.
+    pub fn record_type(&self) -> RecordType {
+        RecordType::from(self.type_number)
+    }
+
+    /// Parses the record’s data into a record structure, the same as
+    /// `Response::from_bytes` would have. This allocates.
+    pub fn to_record(&self) -> Result<Record, WireError> {
+        let length = u16::try_from(self.data.len()).map_err(|_| WireError::IO)?;
+        let mut c = Cursor::new(self.message);
+        c.set_position(self.data_offset as u64);
+        Record::from_bytes(self.record_type(), length, &mut c)
+    }
+}
+
+impl<'a> NameView<'a> {
+
+    /// Checks the name starting at the cursor’s position, leaving the cursor
+    /// after the end of it. Pointers are followed to make sure they lead
+    /// somewhere valid, using the same limits as when reading labels.
+    fn read(message: &'a [u8], c: &mut Cursor<&[u8]>) -> Result<Self, WireError> {
+        let offset = position(c)?;
+        let mut recursions = Vec::new();
+        let mut reading = offset;
+        let mut start = offset;
+        let mut end = None;
+        let mut name_length = 1;
+
+        loop {
+            let byte = *message.get(reading).ok_or(WireError::IO)?;
+
+            if byte == 0 {
+                end.get_or_insert(reading + 1);
+                break;
+            }
+            else if byte >= 0b_1100_0000 {
+                let low = *message.get(reading + 1).ok_or(WireError::IO)?;
+                let pointer = u16::from_be_bytes([ byte - 0b_1100_0000, low ]);
+                end.get_or_insert(reading + 2);
+
+                if usize::from(pointer) >= start {
+                    warn!("Pointer at {} points forward to {} decoding name view", reading, pointer);
+                    let position = u16::try_from(reading).unwrap_or(u16::MAX);
+                    return Err(WireError::NameLimit(NameLimit::ForwardPointer { position, offset: pointer }));
+                }
+
+                recursions.push(pointer);
+
+                if recursions.len() >= RECURSION_LIMIT {
+                    warn!("Hit recursion limit ({}) decoding name view", RECURSION_LIMIT);
+                    return Err(WireError::TooMuchRecursion(recursions.into_boxed_slice()));
+                }
+
+                reading = usize::from(pointer);
+                start = reading;
+            }
+            else {
+                reading += 1 + usize::from(byte);
+                name_length += 1 + usize::from(byte);
+
+                if name_length > MAX_NAME_LENGTH {
+                    warn!("Hit name length limit ({}) decoding name view", MAX_NAME_LENGTH);
+                    return Err(WireError::NameLimit(NameLimit::TooLong));
+                }
+            }
+        }
+
+        c.set_position(end.unwrap() as u64);
+        Ok(Self { message, offset })
+    }
+
+    /// Returns an iterator over the name’s labels, as they appear in the
+    /// message, without any allocation.
+    pub fn labels(&self) -> LabelsIter<'a> {
+        LabelsIter { message: self.message, offset: Some(self.offset) }
+    }
+
+    /// Copies the name into a set of labels.
+    pub fn to_labels(&self) -> Labels {
+        let mut labels = Labels::root();
+        for label in self.labels() {
+            if let Ok(length) = u8::try_from(label.len()) {
+                labels.push_wire_segment(length, label);
+            }
+        }
+        labels
+    }
+
+    /// Whether this is the same name as the given labels, ignoring case,
+    /// without allocating.
+    pub fn eq_labels(&self, other: &Labels) -> bool {
+        let mut theirs = other.segments();
+
+        self.labels().all(|label| theirs.next().map_or(false, |segment| label.eq_ignore_ascii_case(segment)))
+            && theirs.next().is_none()
+    }
+}
+
+impl<'a> Iterator for LabelsIter<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut offset = self.offset?;
+
+        // The name was checked when it was read, so pointers all lead
+        // somewhere and never loop.
+        loop {
+            let byte = self.message[offset];
+
+            if byte == 0 {
+                self.offset = None;
+                return None;
+            }
+            else if byte >= 0b_1100_0000 {
+                offset = usize::from(u16::from_be_bytes([ byte - 0b_1100_0000, self.message[offset + 1] ]));
+            }
+            else {
+                let start = offset + 1;
+                let end = start + usize::from(byte);
+                self.offset = Some(end);
+                return Some(&self.message[start .. end]);
+            }
+        }
+    }
+}
+
+impl fmt::Display for NameView<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for label in self.labels() {
+            write!(f, "{}.", String::from_utf8_lossy(label))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the cursor’s position as an index into the message.
+fn position(c: &Cursor<&[u8]>) -> Result<usize, WireError> {
+    usize::try_from(c.position()).map_err(|_| WireError::IO)
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::record::A;
+    use pretty_assertions::assert_eq;
+
+    const RESPONSE: &[u8] = &[
+        0xce, 0xac,  // transaction ID
+        0x81, 0x80,  // flags (standard query, response, no error)
+        0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00,  // counts (1, 1, 0, 0)
+
+        // query:
+        0x03, 0x64, 0x6e, 0x73, 0x06, 0x6c, 0x6f, 0x6f, 0x6b, 0x75, 0x70, 0x03, 0x64, 0x6f, 0x67, 0x00,  // name
+        0x00, 0x01,  // type A
+        0x00, 0x01,  // class IN
+
+        // answer:
+        0xc0, 0x0c,  // name (backreference)
+        0x00, 0x01,  // type A
+        0x00, 0x01,  // class IN
+        0x00, 0x00, 0x03, 0xa5,  // TTL
+        0x00, 0x04,  // data length 4
+        0x8a, 0x44, 0x75, 0x5e,  // IP address
+    ];
+
+    #[test]
+    fn parses() {
+        let view = ResponseView::from_bytes(RESPONSE).unwrap();
+        assert_eq!(view.transaction_id, 0xceac);
+        assert_eq!(view.flags, Flags::standard_response());
+        assert_eq!(view.queries.len(), 1);
+        assert_eq!(view.queries[0].qtype, RecordType::A);
+        assert_eq!(view.answers.len(), 1);
+
+        let answer = view.answers[0];
+        assert_eq!(answer.record_type(), RecordType::A);
+        assert_eq!(answer.ttl, 933);
+        assert_eq!(answer.data, &[ 0x8a, 0x44, 0x75, 0x5e ]);
+        assert_eq!(answer.to_record(),
+                   Ok(Record::A(A { address: std::net::Ipv4Addr::new(138, 68, 117, 94) })));
+    }
+
+    #[test]
+    fn names_follow_pointers() {
+        let view = ResponseView::from_bytes(RESPONSE).unwrap();
+        let name = view.answers[0].qname;
+
+        assert_eq!(name.labels().collect::<Vec<_>>(), vec![ &b"dns"[..], b"lookup", b"dog" ]);
+        assert_eq!(name.to_string(), "dns.lookup.dog.");
+        assert_eq!(name.to_labels(), Labels::encode("dns.lookup.dog").unwrap());
+        assert!(name.eq_labels(&Labels::encode("DNS.lookup.dog").unwrap()));
+        assert!(! name.eq_labels(&Labels::encode("lookup.dog").unwrap()));
+    }
+
+    #[test]
+    fn matches_owned_parsing() {
+        let view = ResponseView::from_bytes(RESPONSE).unwrap();
+        let response = Response::from_bytes(RESPONSE).unwrap();
+
+        assert_eq!(view.queries[0].qname.to_labels(), response.queries[0].qname);
+        assert_eq!(view.answers.iter().map(RecordView::to_record).collect::<Result<Vec<_>, _>>().unwrap().len(),
+                   response.answers.len());
+    }
+
+    #[test]
+    fn pointer_loop() {
+        let buf: &[u8] = &[
+            0x00, 0x00, 0x00, 0x00,  // transaction ID and flags
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,  // counts (1, 0, 0, 0)
+            0xc0, 0x0c,  // name pointing to itself
+            0x00, 0x01, 0x00, 0x01,  // type and class
+        ];
+
+        assert_eq!(ResponseView::from_bytes(buf),
+                   Err(WireError::NameLimit(NameLimit::ForwardPointer { position: 12, offset: 12 })));
+    }
+
+    #[test]
+    fn truncated_data() {
+        assert_eq!(ResponseView::from_bytes(&RESPONSE[.. RESPONSE.len() - 1]),
+                   Err(WireError::IO));
+    }
+}
@@ -1,13 +1,22 @@
 //! Parsing the DNS wire protocol.
 
-pub(crate) use std::io::{Cursor, Read};
-pub(crate) use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+#[cfg(feature = "std")]
+pub(crate) use std::io::{self, Cursor, Read, Write};
+#[cfg(feature = "std")]
+pub(crate) use byteorder::{ReadBytesExt, WriteBytesExt};
 
-use std::io;
+#[cfg(not(feature = "std"))]
+pub(crate) use crate::bytes::{self as io, Cursor, Read, Write, ReadBytesExt, WriteBytesExt};
+#[cfg(not(feature = "std"))]
+pub(crate) use alloc::{boxed::Box, borrow::ToOwned, string::{String, ToString}, vec::Vec};
+
+pub(crate) use byteorder::BigEndian;
+
+use std::convert::TryFrom;
 use log::*;
 
-use crate::record::{Record, RecordType, OPT};
-use crate::strings::{Labels, ReadLabels, WriteLabels};
+use crate::record::{Record, RecordType, OPT, EdnsOption, Padding};
+use crate::strings::{Labels, NameCompressor, ReadLabels, WriteLabels};
 use crate::types::*;
 
 
@@ -25,9 +34,7 @@ impl Request {
         bytes.write_u16::<BigEndian>(0)?;  // authority RR count
         bytes.write_u16::<BigEndian>(if self.additional.is_some() { 1 } else { 0 })?;  // additional RR count
 
-        bytes.write_labels(&self.query.qname)?;
-        bytes.write_u16::<BigEndian>(self.query.qtype.type_number())?;
-        bytes.write_u16::<BigEndian>(self.query.qclass.to_u16())?;
+        self.query.write(&mut bytes)?;
 
         if let Some(opt) = &self.additional {
             bytes.write_u8(0)?;  // usually a name
@@ -38,6 +45,27 @@ impl Request {
         Ok(bytes)
     }
 
+    /// Adds a Padding option to the end of this request’s OPT record, so
+    /// that the whole request is a multiple of the given number of bytes
+    /// long. Any padding already in the record is replaced. Requests
+    /// without an OPT record are left alone.
+    pub fn pad_to_block(&mut self, block_size: usize) -> io::Result<()> {
+        match &mut self.additional {
+            Some(opt) => opt.replace_option(&EdnsOption::Padding(Padding(0)))?,
+            None      => return Ok(()),
+        }
+
+        let unpadded_len = self.to_bytes()?.len();
+        let padding_len = (block_size - unpadded_len % block_size) % block_size;
+        let padding = Padding(u16::try_from(padding_len).expect("Padding too long"));
+
+        if let Some(opt) = &mut self.additional {
+            opt.replace_option(&EdnsOption::Padding(padding))?;
+        }
+
+        Ok(())
+    }
+
     /// Returns the OPT record to be sent as part of requests.
     pub fn additional_record() -> OPT {
         OPT {
@@ -63,7 +91,7 @@ impl Response {
         let transaction_id = c.read_u16::<BigEndian>()?;
         trace!("Read txid -> {:?}", transaction_id);
 
-        let flags = Flags::from_u16(c.read_u16::<BigEndian>()?);
+        let mut flags = Flags::from_u16(c.read_u16::<BigEndian>()?);
         trace!("Read flags -> {:#?}", flags);
 
         let query_count      = c.read_u16::<BigEndian>()?;
@@ -105,8 +133,165 @@ impl Response {
             additionals.push(Answer::from_bytes(qname, &mut c)?);
         }
 
+        flags.error_code = extended_error_code(flags.error_code, &additionals);
+
         Ok(Self { transaction_id, flags, queries, answers, authorities, additionals })
     }
+
+    /// Reads bytes off of the given slice like `from_bytes`, but rather than
+    /// giving up at the first record that fails to parse, skips over it
+    /// using the length of its data and carries on with the next one. The
+    /// response holds every record that could be read, and the errors say
+    /// which ones could not. Reading stops early if a record’s name or
+    /// length cannot be read, as there is then no way to tell where the
+    /// next record starts. An error is only returned if the header is cut
+    /// short.
+    pub fn from_bytes_lenient(bytes: &[u8]) -> Result<PartialResponse, WireError> {
+        info!("Parsing response leniently");
+        trace!("Bytes -> {:?}", bytes);
+        let mut c = Cursor::new(bytes);
+
+        let transaction_id = c.read_u16::<BigEndian>()?;
+        let flags = Flags::from_u16(c.read_u16::<BigEndian>()?);
+
+        let mut counts = [0; 4];
+        for count in &mut counts {
+            *count = c.read_u16::<BigEndian>()?;
+        }
+
+        let mut response = Self { transaction_id, flags, queries: Vec::new(), answers: Vec::new(), authorities: Vec::new(), additionals: Vec::new() };
+        let mut errors = Vec::new();
+
+        if let Err(error) = read_sections_leniently(&mut c, counts, &mut response, &mut errors) {
+            warn!("Stopped reading at {:?} record {} -> {:?}", error.section, error.index, error.error);
+            errors.push(error);
+        }
+
+        response.flags.error_code = extended_error_code(response.flags.error_code, &response.additionals);
+        Ok(PartialResponse { response, errors })
+    }
+
+    /// Reads the header and queries off of the given slice, returning a
+    /// stream that reads the records after them one at a time, only when
+    /// they are asked for. Nothing is read past the record that was last
+    /// asked for, so a program that stops early never parses the rest.
+    pub fn stream(bytes: &[u8]) -> Result<ResponseStream<'_>, WireError> {
+        info!("Parsing response as a stream");
+        trace!("Bytes -> {:?}", bytes);
+        let mut c = Cursor::new(bytes);
+
+        let transaction_id = c.read_u16::<BigEndian>()?;
+        let flags = Flags::from_u16(c.read_u16::<BigEndian>()?);
+
+        let query_count = c.read_u16::<BigEndian>()?;
+        let mut counts = [0; 3];
+        for count in &mut counts {
+            *count = c.read_u16::<BigEndian>()?;
+        }
+
+        let mut queries = Vec::with_capacity(usize::from(query_count.min(9)));
+        for _ in 0 .. query_count {
+            let (qname, _) = c.read_labels()?;
+            queries.push(Query::from_bytes(qname, &mut c)?);
+        }
+
+        Ok(ResponseStream { transaction_id, flags, queries, c, counts, index: 0 })
+    }
+}
+
+impl Iterator for ResponseStream<'_> {
+    type Item = Result<(Section, Answer), RecordError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        const SECTIONS: [Section; 3] = [ Section::Answer, Section::Authority, Section::Additional ];
+
+        let position = self.counts.iter().position(|count| *count > 0)?;
+        let section = SECTIONS[position];
+        let index = self.index;
+
+        self.counts[position] -= 1;
+        self.index = if self.counts[position] == 0 { 0 } else { index + 1 };
+
+        let answer = self.c.read_labels()
+            .and_then(|(qname, _)| Answer::from_bytes(qname, &mut self.c));
+
+        match answer {
+            Ok(answer) => {
+                Some(Ok((section, answer)))
+            }
+            Err(error) => {
+                warn!("Stopped streaming at {:?} record {} -> {:?}", section, index, error);
+                self.counts = [0; 3];
+                Some(Err(RecordError { section, index, error }))
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.counts.iter().map(|count| usize::from(*count)).sum();
+        (0, Some(remaining))
+    }
+}
+
+/// Reads the four sections of a message into the given response, skipping
+/// over any record whose data fails to parse and adding an error for it to
+/// the list. Returns an error for the record that reading stopped at, if
+/// it could not be skipped.
+fn read_sections_leniently(c: &mut Cursor<&[u8]>, counts: [u16; 4], response: &mut Response, errors: &mut Vec<RecordError>) -> Result<(), RecordError> {
+    let [query_count, answer_count, authority_count, additional_count] = counts;
+
+    for index in 0 .. query_count {
+        let at = move |error| RecordError { section: Section::Question, index, error };
+        let (qname, _) = c.read_labels().map_err(at)?;
+        response.queries.push(Query::from_bytes(qname, c).map_err(at)?);
+    }
+
+    read_records_leniently(c, Section::Answer, answer_count, &mut response.answers, errors)?;
+    read_records_leniently(c, Section::Authority, authority_count, &mut response.authorities, errors)?;
+    read_records_leniently(c, Section::Additional, additional_count, &mut response.additionals, errors)
+}
+
+/// Reads the given number of records from one section of a message, as
+/// `read_sections_leniently` does.
+fn read_records_leniently(c: &mut Cursor<&[u8]>, section: Section, count: u16, records: &mut Vec<Answer>, errors: &mut Vec<RecordError>) -> Result<(), RecordError> {
+    for index in 0 .. count {
+        let at = move |error| RecordError { section, index, error };
+        let (qname, _) = c.read_labels().map_err(at)?;
+
+        match Answer::read_skippable(qname, c).map_err(at)? {
+            Ok(answer) => {
+                records.push(answer);
+            }
+            Err(error) => {
+                warn!("Skipping {:?} record {} -> {:?}", section, index, error);
+                errors.push(at(error));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+
+/// Puts the lower four bits of the error code from the header together with
+/// the higher eight bits from the OPT record, if there is one, to get the
+/// full twelve-bit extended error code (RFC 6891 §6.1.3). Without an OPT
+/// record, the code in the header is all there is.
+fn extended_error_code(header_code: Option<ErrorCode>, additionals: &[Answer]) -> Option<ErrorCode> {
+    let higher_bits = additionals.iter().find_map(|answer| match answer {
+        Answer::Pseudo { opt, .. }  => Some(opt.higher_bits),
+        Answer::Standard { .. }     => None,
+    });
+
+    match higher_bits {
+        Some(higher_bits) if higher_bits != 0 => {
+            let lower_bits = header_code.map_or(0, ErrorCode::to_bits);
+            let code = ErrorCode::from_bits((u16::from(higher_bits) << 4) | lower_bits);
+            trace!("Assembled extended rcode -> {:?}", code);
+            code
+        }
+        _ => header_code,
+    }
 }
 
 
@@ -127,6 +312,24 @@ impl Query {
 
         Ok(Self { qtype, qclass, qname })
     }
+
+    /// Writes this query to the end of the given buffer.
+    pub fn write(&self, bytes: &mut Vec<u8>) -> io::Result<()> {
+        bytes.write_labels(&self.qname)?;
+        self.write_type_and_class(bytes)
+    }
+
+    /// Writes this query to the end of the given message, compressing its
+    /// name against the names already written.
+    pub(crate) fn write_compressed(&self, bytes: &mut Vec<u8>, names: &mut NameCompressor) -> io::Result<()> {
+        names.write_labels(bytes, &self.qname)?;
+        self.write_type_and_class(bytes)
+    }
+
+    fn write_type_and_class(&self, bytes: &mut Vec<u8>) -> io::Result<()> {
+        bytes.write_u16::<BigEndian>(self.qtype.type_number())?;
+        bytes.write_u16::<BigEndian>(self.qclass.to_u16())
+    }
 }
 
 
@@ -134,14 +337,23 @@ impl Answer {
 
     /// Reads bytes from the given cursor, and parses them into an answer with
     /// the given domain name.
-    #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
     fn from_bytes(qname: Labels, c: &mut Cursor<&[u8]>) -> Result<Self, WireError> {
+        Self::read_skippable(qname, c)?
+    }
+
+    /// Reads an answer like `from_bytes`, but if the record’s data fails to
+    /// parse, moves the cursor past it using the stated length of the data,
+    /// so the records after it can still be read. The inner error means
+    /// only this record was bad; the outer one means the record could not
+    /// be skipped, and nothing after it can be read.
+    #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+    fn read_skippable(qname: Labels, c: &mut Cursor<&[u8]>) -> Result<Result<Self, WireError>, WireError> {
         let qtype_number = c.read_u16::<BigEndian>()?;
         trace!("Read qtype number -> {:?}", qtype_number );
 
         if qtype_number == OPT::RR_TYPE {
             let opt = OPT::read(c)?;
-            Ok(Self::Pseudo { qname, opt })
+            Ok(Ok(Self::Pseudo { qname, opt }))
         }
         else {
             let qtype = RecordType::from(qtype_number);
@@ -156,10 +368,65 @@ impl Answer {
             let record_length = c.read_u16::<BigEndian>()?;
             trace!("Read record length -> {:?}", record_length);
 
-            let record = Record::from_bytes(qtype, record_length, c)?;
-            Ok(Self::Standard { qclass, qname, record, ttl })
+            let data_start = c.position();
+            match Record::from_bytes(qtype, record_length, c) {
+                Ok(record) => {
+                    Ok(Ok(Self::Standard { qclass, qname, record, ttl }))
+                }
+                Err(e) => {
+                    let data_end = data_start + u64::from(record_length);
+                    if data_end > c.get_ref().len() as u64 {
+                        return Err(e);
+                    }
+
+                    c.set_position(data_end);
+                    Ok(Err(e))
+                }
+            }
         }
     }
+
+    /// Writes this answer to the end of the given buffer, including its
+    /// name, type, class, TTL, and data length. Domain names are written out
+    /// in full, without compression.
+    pub fn write(&self, bytes: &mut Vec<u8>) -> io::Result<()> {
+        self.write_with_name(bytes, |bytes, qname| bytes.write_labels(qname))
+    }
+
+    /// Writes this answer to the end of the given message, compressing its
+    /// name against the names already written. The domain names inside the
+    /// record data are still written out in full, as only a few record
+    /// types allow them to be compressed.
+    pub(crate) fn write_compressed(&self, bytes: &mut Vec<u8>, names: &mut NameCompressor) -> io::Result<()> {
+        self.write_with_name(bytes, |bytes, qname| names.write_labels(bytes, qname))
+    }
+
+    fn write_with_name<F>(&self, bytes: &mut Vec<u8>, mut write_name: F) -> io::Result<()>
+    where F: FnMut(&mut Vec<u8>, &Labels) -> io::Result<()>
+    {
+        match self {
+            Self::Standard { qname, qclass, ttl, record } => {
+                let mut data = Vec::new();
+                record.write(&mut data)?;
+                let data_length = u16::try_from(data.len())
+                    .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "record data is longer than 65535 bytes"))?;
+
+                write_name(bytes, qname)?;
+                bytes.write_u16::<BigEndian>(record.record_type().type_number())?;
+                bytes.write_u16::<BigEndian>(qclass.to_u16())?;
+                bytes.write_u32::<BigEndian>(*ttl)?;
+                bytes.write_u16::<BigEndian>(data_length)?;
+                bytes.extend(data);
+            }
+            Self::Pseudo { qname, opt } => {
+                write_name(bytes, qname)?;
+                bytes.write_u16::<BigEndian>(OPT::RR_TYPE)?;
+                bytes.extend(opt.to_bytes()?);
+            }
+        }
+
+        Ok(())
+    }
 }
 
 
@@ -168,7 +435,7 @@ impl Record {
     /// Reads at most `len` bytes from the given curser, and parses them into
     /// a record structure depending on the type number, which has already been read.
     #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
-    fn from_bytes(record_type: RecordType, len: u16, c: &mut Cursor<&[u8]>) -> Result<Self, WireError> {
+    pub(crate) fn from_bytes(record_type: RecordType, len: u16, c: &mut Cursor<&[u8]>) -> Result<Self, WireError> {
         if cfg!(feature = "with_mutagen") {
             warn!("Mutation is enabled!");
         }
@@ -183,20 +450,33 @@ impl Record {
         match record_type {
             RecordType::A           => read_record!(A),
             RecordType::AAAA        => read_record!(AAAA),
+            RecordType::AMTRELAY    => read_record!(AMTRELAY),
             RecordType::CAA         => read_record!(CAA),
             RecordType::CNAME       => read_record!(CNAME),
+            RecordType::DLV         => read_record!(DLV),
+            RecordType::DNSKEY      => read_record!(DNSKEY),
+            RecordType::DOA         => read_record!(DOA),
+            RecordType::DS          => read_record!(DS),
             RecordType::EUI48       => read_record!(EUI48),
             RecordType::EUI64       => read_record!(EUI64),
             RecordType::HINFO       => read_record!(HINFO),
+            RecordType::HIP         => read_record!(HIP),
+            RecordType::HTTPS       => read_record!(HTTPS),
             RecordType::LOC         => read_record!(LOC),
             RecordType::MX          => read_record!(MX),
             RecordType::NAPTR       => read_record!(NAPTR),
             RecordType::NS          => read_record!(NS),
+            RecordType::NSEC        => read_record!(NSEC),
+            RecordType::NSEC3       => read_record!(NSEC3),
+            RecordType::NSEC3PARAM  => read_record!(NSEC3PARAM),
             RecordType::OPENPGPKEY  => read_record!(OPENPGPKEY),
             RecordType::PTR         => read_record!(PTR),
+            RecordType::RRSIG       => read_record!(RRSIG),
             RecordType::SSHFP       => read_record!(SSHFP),
             RecordType::SOA         => read_record!(SOA),
             RecordType::SRV         => read_record!(SRV),
+            RecordType::SVCB        => read_record!(SVCB),
+            RecordType::TA          => read_record!(TA),
             RecordType::TLSA        => read_record!(TLSA),
             RecordType::TXT         => read_record!(TXT),
             RecordType::URI         => read_record!(URI),
@@ -215,7 +495,7 @@ impl Record {
 
 
 impl QClass {
-    fn from_u16(uu: u16) -> Self {
+    pub(crate) fn from_u16(uu: u16) -> Self {
         match uu {
             0x0001 => Self::IN,
             0x0003 => Self::CH,
@@ -224,7 +504,7 @@ impl QClass {
         }
     }
 
-    fn to_u16(self) -> u16 {
+    pub(crate) fn to_u16(self) -> u16 {
         match self {
             Self::IN        => 0x0001,
             Self::CH        => 0x0003,
@@ -251,10 +531,7 @@ impl Flags {
     pub fn to_u16(self) -> u16 {                 // 0123 4567 89AB CDEF
         let mut                          bits  = 0b_0000_0000_0000_0000;
         if self.response               { bits |= 0b_1000_0000_0000_0000; }
-        match self.opcode {
-            Opcode::Query     =>       { bits |= 0b_0000_0000_0000_0000; }
-            Opcode::Other(_)  =>       { unimplemented!(); }
-        }
+                                         bits |= u16::from(self.opcode.to_bits()) << 11;
         if self.authoritative          { bits |= 0b_0000_0100_0000_0000; }
         if self.truncated              { bits |= 0b_0000_0010_0000_0000; }
         if self.recursion_desired      { bits |= 0b_0000_0001_0000_0000; }
@@ -262,6 +539,7 @@ impl Flags {
         // (the Z bit is reserved)               0b_0000_0000_0100_0000
         if self.authentic_data         { bits |= 0b_0000_0000_0010_0000; }
         if self.checking_disabled      { bits |= 0b_0000_0000_0001_0000; }
+        if let Some(rcode) = self.error_code { bits |= rcode.to_bits() & 0b_1111; }
 
         bits
     }
@@ -290,12 +568,28 @@ impl Opcode {
     /// Extracts the opcode from this four-bit number, which should have been
     /// extracted from the packet and shifted to be in the range 0–15.
     fn from_bits(bits: u8) -> Self {
-        if bits == 0 {
-            Self::Query
+        assert!(bits <= 15, "bits {:#08b} out of range", bits);
+
+        match bits {
+            0 => Self::Query,
+            2 => Self::Status,
+            4 => Self::Notify,
+            5 => Self::Update,
+            6 => Self::Dso,
+            n => Self::Other(n),
         }
-        else {
-            assert!(bits <= 15, "bits {:#08b} out of range", bits);
-            Self::Other(bits)
+    }
+
+    /// Returns the four-bit number for this opcode, which gets shifted into
+    /// place in the flags field.
+    fn to_bits(self) -> u8 {
+        match self {
+            Self::Query       => 0,
+            Self::Status      => 2,
+            Self::Notify      => 4,
+            Self::Update      => 5,
+            Self::Dso         => 6,
+            Self::Other(num)  => num & 0b_1111,
         }
     }
 }
@@ -303,7 +597,8 @@ impl Opcode {
 
 impl ErrorCode {
 
-    /// Extracts the rcode from the last four bits of the flags field.
+    /// Extracts the rcode from the last four bits of the flags field, or
+    /// from the full twelve bits once the OPT record has been read.
     fn from_bits(bits: u16) -> Option<Self> {
         if (0x0F01 .. 0x0FFF).contains(&bits) {
             return Some(Self::Private(bits));
@@ -317,13 +612,31 @@ impl ErrorCode {
             4 => Some(Self::NotImplemented),
             5 => Some(Self::QueryRefused),
            16 => Some(Self::BadVersion),
+           23 => Some(Self::BadCookie),
             n => Some(Self::Other(n)),
         }
     }
+
+    /// Returns the number for this rcode. Only the last four bits fit in the
+    /// flags field; the rest go in the OPT record, if there is one.
+    fn to_bits(self) -> u16 {
+        match self {
+            Self::FormatError     => 1,
+            Self::ServerFailure   => 2,
+            Self::NXDomain        => 3,
+            Self::NotImplemented  => 4,
+            Self::QueryRefused    => 5,
+            Self::BadVersion      => 16,
+            Self::BadCookie       => 23,
+            Self::Other(num)      |
+            Self::Private(num)    => num,
+        }
+    }
 }
 
 
-/// Trait for decoding DNS record structures from bytes read over the wire.
+/// Trait for decoding DNS record structures from bytes read over the wire,
+/// and encoding them back into bytes to send.
 pub trait Wire: Sized {
 
     /// This record’s type as a string, such as `"A"` or `"CNAME"`.
@@ -337,6 +650,23 @@ pub trait Wire: Sized {
     /// throughout the complete data — by this point, we have read the entire
     /// response into a buffer.
     fn read(len: u16, c: &mut Cursor<&[u8]>) -> Result<Self, WireError>;
+
+    /// Write this record’s data to the end of the given buffer, in the form
+    /// that `read` reads it in. Domain names are written out in full, as
+    /// they are not compressed in the data of most record types.
+    fn write(&self, bytes: &mut Vec<u8>) -> io::Result<()>;
+}
+
+/// Writes a `<character-string>`, which is a byte giving its length followed
+/// by that many bytes of data, returning an error if the data is too long
+/// for its length to fit in the byte.
+pub(crate) fn write_character_string(bytes: &mut Vec<u8>, string: &[u8]) -> io::Result<()> {
+    let length = u8::try_from(string.len())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "character string is longer than 255 bytes"))?;
+
+    bytes.write_u8(length)?;
+    bytes.extend_from_slice(string);
+    Ok(())
 }
 
 
@@ -413,6 +743,11 @@ pub enum WireError {
     /// the packet. Contains the invalid index.
     OutOfBounds(u16),
 
+    /// When the data contained a domain name that broke one of the limits
+    /// that stop a crafted packet from making name decompression do a lot
+    /// of pointless work.
+    NameLimit(NameLimit),
+
     /// When a record in the packet contained a version field that specifies
     /// the format of its remaining fields, but this version is too recent to
     /// be supported, so we cannot parse it.
@@ -426,6 +761,27 @@ pub enum WireError {
     }
 }
 
+/// The limit that a compressed domain name in a packet broke.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum NameLimit {
+
+    /// A pointer pointed into the name it is part of, or to later in the
+    /// packet. Names can only point back to names that came before them,
+    /// which also means pointers can never loop.
+    ForwardPointer {
+
+        /// The position of the pointer in the packet.
+        position: u16,
+
+        /// The position the pointer pointed to.
+        offset: u16,
+    },
+
+    /// The name expanded to more than the 255 bytes that a domain name is
+    /// allowed to take up, including its length bytes.
+    TooLong,
+}
+
 /// The rule for how long a record in a packet should be.
 #[derive(PartialEq, Debug, Copy, Clone)]
 pub enum MandatedLength {
@@ -0,0 +1,724 @@
+//! Reading records out of zone files, in the master file format described
+//! in RFC 1035 §5. Only the parts of the format that zone files commonly
+//! use are supported: the `$ORIGIN` and `$TTL` directives, relative names,
+//! parentheses, comments, and quoted strings, and the data of the most
+//! common record types. The data of any other type can be given in the
+//! generic format from RFC 3597, and records of other types that are not
+//! are skipped.
+//!
+//! Files are read a line at a time, so a zone with millions of records does
+//! not have to be held in memory as text as well as records.
+//!
+//! # References
+//!
+//! - [RFC 1035 §5](https://tools.ietf.org/html/rfc1035#section-5) — Domain
+//!   Names - Implementation and Specification (November 1987)
+//! - [RFC 3597 §5](https://tools.ietf.org/html/rfc3597#section-5) —
+//!   Handling of Unknown DNS Resource Record (RR) Types (September 2003)
+
+use std::fmt;
+use std::io::{self, BufRead, Cursor};
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use crate::record::*;
+use crate::strings::Labels;
+
+
+/// One record read from a zone file.
+#[derive(PartialEq, Debug, Clone)]
+pub struct ZoneRecord {
+
+    /// The domain name that owns the record.
+    pub owner: Labels,
+
+    /// The record’s time-to-live, in seconds.
+    pub ttl: u32,
+
+    /// The record itself.
+    pub record: Record,
+}
+
+/// Everything read from a zone file.
+#[derive(PartialEq, Debug, Default)]
+pub struct Zone {
+
+    /// The records that were read, in the order they appear in the file.
+    pub records: Vec<ZoneRecord>,
+
+    /// The line numbers and type names of the records that were skipped,
+    /// because the data of records of that type cannot be read.
+    pub skipped: Vec<(usize, String)>,
+}
+
+/// Something wrong with a zone file, along with the line it is on.
+#[derive(PartialEq, Debug)]
+pub struct ZoneFileError {
+
+    /// The line number of the entry with the problem, starting from 1.
+    pub line: usize,
+
+    /// What the problem is.
+    pub reason: String,
+}
+
+/// One entry in a zone file, which may have been spread over several lines
+/// using parentheses.
+struct Entry {
+
+    /// The line number the entry starts on.
+    line: usize,
+
+    /// Whether the entry starts with whitespace, which means it has no
+    /// owner of its own and uses the previous one.
+    continues_owner: bool,
+
+    /// The entry’s tokens, with any quotes and escapes removed.
+    tokens: Vec<String>,
+}
+
+
+/// Reads every record in the given zone file contents.
+pub fn parse(input: &str) -> Result<Zone, ZoneFileError> {
+    read(input.as_bytes())
+}
+
+/// Reads every record from the given reader, one entry at a time. A line
+/// that cannot be read, such as one that is not valid UTF-8, is an error
+/// on that line.
+pub fn read(reader: impl BufRead) -> Result<Zone, ZoneFileError> {
+    let mut zone = Zone::default();
+    let mut origin: Option<Labels> = None;
+    let mut default_ttl: Option<u32> = None;
+    let mut last_ttl: Option<u32> = None;
+    let mut last_owner: Option<Labels> = None;
+
+    for entry in entries(reader) {
+        let entry = entry?;
+        let line = entry.line;
+        let error = |reason: String| ZoneFileError { line, reason };
+        let mut tokens = entry.tokens.iter().map(String::as_str);
+
+        if ! entry.continues_owner && entry.tokens[0].starts_with('$') {
+            let directive = tokens.next().unwrap();
+            let argument = tokens.next().ok_or_else(|| error(format!("{} needs an argument", directive)))?;
+
+            if directive.eq_ignore_ascii_case("$ORIGIN") {
+                origin = Some(absolute_name(argument, origin.as_ref()).map_err(error)?);
+            }
+            else if directive.eq_ignore_ascii_case("$TTL") {
+                default_ttl = Some(parse_ttl(argument).ok_or_else(|| error(format!("Invalid TTL {:?}", argument)))?);
+            }
+            else {
+                return Err(error(format!("Unsupported directive {}", directive)));
+            }
+
+            continue;
+        }
+
+        let owner = if entry.continues_owner {
+            last_owner.clone().ok_or_else(|| error("Record has no owner".into()))?
+        }
+        else {
+            absolute_name(tokens.next().unwrap(), origin.as_ref()).map_err(error)?
+        };
+        last_owner = Some(owner.clone());
+
+        // The TTL and class can come in either order, and both are optional.
+        let mut ttl = None;
+        let type_name = loop {
+            let token = tokens.next().ok_or_else(|| error("Record has no type".into()))?;
+
+            if let Some(number) = parse_ttl(token) {
+                ttl = Some(number);
+            }
+            else if ["CH", "CS", "HS"].iter().any(|class| token.eq_ignore_ascii_case(class)) {
+                return Err(error(format!("Unsupported class {}", token)));
+            }
+            else if ! token.eq_ignore_ascii_case("IN") {
+                break token.to_ascii_uppercase();
+            }
+        };
+
+        let ttl = ttl.or(default_ttl).or(last_ttl)
+                     .ok_or_else(|| error("Record has no TTL, and there is no $TTL directive".into()))?;
+        last_ttl = Some(ttl);
+
+        let data = tokens.collect::<Vec<_>>();
+        match parse_record(&type_name, &data, origin.as_ref()).map_err(error)? {
+            Some(record) => zone.records.push(ZoneRecord { owner, ttl, record }),
+            None         => zone.skipped.push((line, type_name)),
+        }
+    }
+
+    Ok(zone)
+}
+
+/// Reads the data of one record of the given type, written the way it
+/// would be in a zone file, such as `10 mx.lookup.dog.` for an MX record.
+/// All names have to be absolute, as there is no origin. Returns `None` if
+/// records of that type cannot be read from zone files.
+pub fn parse_record_data(type_name: &str, data: &str) -> Result<Option<Record>, String> {
+    let mut depth = 0;
+    let tokens = tokenise(data, &mut depth)?;
+    let tokens = tokens.iter().map(String::as_str).collect::<Vec<_>>();
+    parse_record(&type_name.to_ascii_uppercase(), &tokens, None)
+}
+
+/// Splits the input into entries, joining together the lines inside
+/// parentheses, and removing comments and blank lines. Entries are read
+/// from the input as they are needed.
+fn entries<R: BufRead>(reader: R) -> Entries<R> {
+    Entries { lines: reader.lines().enumerate(), depth: 0 }
+}
+
+/// The iterator returned by `entries`.
+struct Entries<R> {
+
+    /// The lines of the input, numbered from zero.
+    lines: std::iter::Enumerate<io::Lines<R>>,
+
+    /// How many parentheses are open at the end of the last line read.
+    depth: usize,
+}
+
+impl<R: BufRead> Iterator for Entries<R> {
+    type Item = Result<Entry, ZoneFileError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut current: Option<Entry> = None;
+
+        for (index, text) in &mut self.lines {
+            let line = index + 1;
+            let text = match text {
+                Ok(text)  => text,
+                Err(e)    => return Some(Err(ZoneFileError { line, reason: format!("Failed to read line: {}", e) })),
+            };
+
+            let tokens = match tokenise(&text, &mut self.depth) {
+                Ok(tokens)   => tokens,
+                Err(reason)  => return Some(Err(ZoneFileError { line, reason })),
+            };
+
+            let entry = current.get_or_insert_with(|| Entry {
+                line,
+                continues_owner: text.starts_with([' ', '\t']),
+                tokens: Vec::new(),
+            });
+            entry.tokens.extend(tokens);
+
+            if self.depth == 0 {
+                if let Some(entry) = current.take() {
+                    if ! entry.tokens.is_empty() {
+                        return Some(Ok(entry));
+                    }
+                }
+            }
+        }
+
+        current.map(|entry| Err(ZoneFileError { line: entry.line, reason: "Unclosed parenthesis".into() }))
+    }
+}
+
+/// Splits one line into tokens, keeping track of how many parentheses are
+/// open, and stopping at a comment.
+fn tokenise(text: &str, depth: &mut usize) -> Result<Vec<String>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            ' ' | '\t' => {}
+            ';' => break,
+            '(' => *depth += 1,
+            ')' => {
+                *depth = depth.checked_sub(1).ok_or("Unopened parenthesis")?;
+            }
+            '"' => {
+                let mut token = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"')   => break,
+                        Some('\\')  => token.push(unescape(&mut chars)?),
+                        Some(c)     => token.push(c),
+                        None        => return Err("Unclosed quote".into()),
+                    }
+                }
+                tokens.push(token);
+            }
+            _ => {
+                let mut token = String::new();
+                let mut next = Some(c);
+                while let Some(c) = next {
+                    if c == '\\' {
+                        token.push(unescape(&mut chars)?);
+                    }
+                    else {
+                        token.push(c);
+                    }
+
+                    next = chars.next_if(|c| ! matches!(c, ' ' | '\t' | ';' | '(' | ')' | '"'));
+                }
+                tokens.push(token);
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Reads the rest of an escape sequence after its backslash, which is
+/// either three decimal digits giving an ASCII character, or a character
+/// to be taken literally.
+fn unescape(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) -> Result<char, String> {
+    match chars.next() {
+        Some(c) if c.is_ascii_digit() => {
+            let digits = [ Some(c), chars.next(), chars.next() ];
+            let number = digits.iter()
+                .map(|digit| digit.and_then(|d| d.to_digit(10)))
+                .try_fold(0, |total, digit| digit.map(|d| total * 10 + d))
+                .ok_or("Invalid escape sequence")?;
+
+            char::from_u32(number).filter(char::is_ascii)
+                .ok_or_else(|| format!("Unsupported escape sequence \\{:03}", number))
+        }
+        Some(c) => Ok(c),
+        None    => Err("Escape at end of line".into()),
+    }
+}
+
+/// Turns a name from a zone file into an absolute domain name, appending
+/// the origin to relative names, and replacing `@` with the origin itself.
+fn absolute_name(name: &str, origin: Option<&Labels>) -> Result<Labels, String> {
+    let encode = |name| Labels::encode(name).map_err(|label| format!("Invalid label {:?}", label));
+
+    if name.ends_with('.') {
+        encode(name)
+    }
+    else {
+        let origin = origin.ok_or_else(|| format!("Relative name {:?} with no $ORIGIN", name))?;
+
+        if name == "@" {
+            Ok(origin.clone())
+        }
+        else {
+            Ok(encode(name)?.extend(origin))
+        }
+    }
+}
+
+/// Parses a TTL, which is either a number of seconds or a sequence of
+/// numbers with units, such as `1h30m`.
+fn parse_ttl(input: &str) -> Option<u32> {
+    if input.is_empty() {
+        return None;
+    }
+    else if let Ok(seconds) = input.parse() {
+        return Some(seconds);
+    }
+
+    let mut total: u32 = 0;
+    let mut number: Option<u32> = None;
+
+    for c in input.chars() {
+        if let Some(digit) = c.to_digit(10) {
+            number = Some(number.unwrap_or(0).checked_mul(10)?.checked_add(digit)?);
+        }
+        else {
+            let multiplier = match c.to_ascii_lowercase() {
+                's' => 1,
+                'm' => 60,
+                'h' => 60 * 60,
+                'd' => 60 * 60 * 24,
+                'w' => 60 * 60 * 24 * 7,
+                _   => return None,
+            };
+
+            total = total.checked_add(number.take()?.checked_mul(multiplier)?)?;
+        }
+    }
+
+    if number.is_some() { None } else { Some(total) }
+}
+
+/// Parses the data of a record of the given type, returning `None` if
+/// records of that type cannot be read from zone files.
+fn parse_record(type_name: &str, data: &[&str], origin: Option<&Labels>) -> Result<Option<Record>, String> {
+    if data.first() == Some(&"#") {
+        return parse_generic_record(type_name, &data[1 ..]).map(Some);
+    }
+
+    let name = |input: &str| absolute_name(input, origin);
+    let number = |input: &str| input.parse::<u16>().map_err(|_| format!("Invalid number {:?}", input));
+    let fields = |count: usize| {
+        if data.len() == count { Ok(data) }
+                           else { Err(format!("{} record needs {} fields, not {}", type_name, count, data.len())) }
+    };
+
+    let record = match type_name {
+        "A" => {
+            let address = fields(1)?[0].parse::<Ipv4Addr>().map_err(|_| format!("Invalid IPv4 address {:?}", data[0]))?;
+            Record::A(A { address })
+        }
+        "AAAA" => {
+            let address = fields(1)?[0].parse::<Ipv6Addr>().map_err(|_| format!("Invalid IPv6 address {:?}", data[0]))?;
+            Record::AAAA(AAAA { address })
+        }
+        "CAA" => {
+            let data = fields(3)?;
+            let flags = data[0].parse::<u8>().map_err(|_| format!("Invalid flags {:?}", data[0]))?;
+            Record::CAA(CAA {
+                critical: flags & 0b_1000_0000 != 0,
+                tag: data[1].as_bytes().into(),
+                value: data[2].as_bytes().into(),
+            })
+        }
+        "CNAME" => {
+            Record::CNAME(CNAME { domain: name(fields(1)?[0])? })
+        }
+        "HINFO" => {
+            let data = fields(2)?;
+            Record::HINFO(HINFO { cpu: data[0].as_bytes().into(), os: data[1].as_bytes().into() })
+        }
+        "MX" => {
+            let data = fields(2)?;
+            Record::MX(MX { preference: number(data[0])?, exchange: name(data[1])? })
+        }
+        "NS" => {
+            Record::NS(NS { nameserver: name(fields(1)?[0])? })
+        }
+        "PTR" => {
+            Record::PTR(PTR { cname: name(fields(1)?[0])? })
+        }
+        "SOA" => {
+            let data = fields(7)?;
+            let interval = |input: &str| parse_ttl(input).ok_or_else(|| format!("Invalid interval {:?}", input));
+            Record::SOA(SOA {
+                mname: name(data[0])?,
+                rname: name(data[1])?,
+                serial: data[2].parse().map_err(|_| format!("Invalid serial number {:?}", data[2]))?,
+                refresh_interval: interval(data[3])?,
+                retry_interval: interval(data[4])?,
+                expire_limit: interval(data[5])?,
+                minimum_ttl: interval(data[6])?,
+            })
+        }
+        "SRV" => {
+            let data = fields(4)?;
+            Record::SRV(SRV {
+                priority: number(data[0])?,
+                weight: number(data[1])?,
+                port: number(data[2])?,
+                target: name(data[3])?,
+            })
+        }
+        "TXT" => {
+            if data.is_empty() {
+                return Err("TXT record needs at least one string".into());
+            }
+
+            if let Some(long) = data.iter().find(|message| message.len() > 255) {
+                return Err(format!("TXT string {:?} is longer than 255 bytes", long));
+            }
+
+            Record::TXT(TXT { messages: data.iter().map(|message| message.as_bytes().into()).collect() })
+        }
+        _ => {
+            return parse_binary_record(type_name, data);
+        }
+    };
+
+    Ok(Some(record))
+}
+
+/// Parses the data of a record of one of the types that end with a field
+/// of binary data, which can be split over several tokens, returning
+/// `None` if the type is not one of them.
+fn parse_binary_record(type_name: &str, data: &[&str]) -> Result<Option<Record>, String> {
+    let number = |input: &str| input.parse::<u16>().map_err(|_| format!("Invalid number {:?}", input));
+    let byte = |input: &str| input.parse::<u8>().map_err(|_| format!("Invalid number {:?}", input));
+
+    let record = match type_name {
+        "DNSKEY" => {
+            if data.len() < 4 {
+                return Err(format!("DNSKEY record needs at least 4 fields, not {}", data.len()));
+            }
+
+            Record::DNSKEY(DNSKEY {
+                flags: number(data[0])?,
+                protocol: byte(data[1])?,
+                algorithm: byte(data[2])?,
+                public_key: base64::decode(data[3 ..].concat()).map_err(|_| "Invalid base64 public key".to_string())?,
+            })
+        }
+        "DS" => {
+            if data.len() < 4 {
+                return Err(format!("DS record needs at least 4 fields, not {}", data.len()));
+            }
+
+            Record::DS(DS {
+                key_tag: number(data[0])?,
+                algorithm: byte(data[1])?,
+                digest_type: byte(data[2])?,
+                digest: parse_hex(&data[3 ..].concat())?,
+            })
+        }
+        "SSHFP" => {
+            if data.len() < 3 {
+                return Err(format!("SSHFP record needs at least 3 fields, not {}", data.len()));
+            }
+
+            Record::SSHFP(SSHFP {
+                algorithm: byte(data[0])?,
+                fingerprint_type: byte(data[1])?,
+                fingerprint: parse_hex(&data[2 ..].concat())?,
+            })
+        }
+        "TLSA" => {
+            if data.len() < 4 {
+                return Err(format!("TLSA record needs at least 4 fields, not {}", data.len()));
+            }
+
+            Record::TLSA(TLSA {
+                certificate_usage: byte(data[0])?,
+                selector: byte(data[1])?,
+                matching_type: byte(data[2])?,
+                certificate_data: parse_hex(&data[3 ..].concat())?,
+            })
+        }
+        _ => {
+            return Ok(None);
+        }
+    };
+
+    Ok(Some(record))
+}
+
+/// Parses record data in the generic format, which is the length of the
+/// data followed by the data itself in hexadecimal, and can be used for
+/// records of any type, including types with no name, such as `TYPE65280`.
+fn parse_generic_record(type_name: &str, data: &[&str]) -> Result<Record, String> {
+    let record_type = match type_name.strip_prefix("TYPE").and_then(|number| number.parse::<u16>().ok()) {
+        Some(number)  => RecordType::from(number),
+        None          => RecordType::from_type_name(type_name).ok_or_else(|| format!("Unknown record type {}", type_name))?,
+    };
+
+    let (length, hex) = data.split_first().ok_or("Generic record data needs a length")?;
+    let length = length.parse::<u16>().map_err(|_| format!("Invalid length {:?}", length))?;
+    let bytes = parse_hex(&hex.concat())?;
+
+    if bytes.len() != usize::from(length) {
+        return Err(format!("Generic record data is {} bytes long, not {}", bytes.len(), length));
+    }
+
+    Record::from_bytes(record_type, length, &mut Cursor::new(&bytes))
+        .map_err(|e| format!("Invalid {} record data: {:?}", type_name, e))
+}
+
+/// Decodes a string of hexadecimal digits into bytes.
+fn parse_hex(input: &str) -> Result<Vec<u8>, String> {
+    let invalid = || format!("Invalid hexadecimal {:?}", input);
+
+    if input.len() % 2 != 0 {
+        return Err(invalid());
+    }
+
+    (0 .. input.len()).step_by(2)
+        .map(|index| input.get(index .. index + 2).and_then(|pair| u8::from_str_radix(pair, 16).ok()).ok_or_else(invalid))
+        .collect()
+}
+
+
+impl fmt::Display for ZoneFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.reason)
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn record(owner: &str, ttl: u32, record: Record) -> ZoneRecord {
+        ZoneRecord { owner: Labels::encode(owner).unwrap(), ttl, record }
+    }
+
+    #[test]
+    fn simple_zone() {
+        let zone = parse("\
+            $ORIGIN lookup.dog.\n\
+            $TTL 1h\n\
+            @       IN  SOA  ns1 hostmaster ( 2024010101 ; serial\n\
+                                 7200 3600 1209600 300 )\n\
+            @           NS   ns1\n\
+            ns1     60  A    192.0.2.53\n\
+            www     IN 300 AAAA 2001:db8::1\n\
+            \x20           TXT  \"v=spf1 -all\" \"second \\\"string\\\"\"\n\
+            mail.example.  MX   10 mx.lookup.dog.\n\
+        ").unwrap();
+
+        assert_eq!(zone.records, vec![
+            record("lookup.dog", 3600, Record::SOA(SOA {
+                mname: Labels::encode("ns1.lookup.dog").unwrap(),
+                rname: Labels::encode("hostmaster.lookup.dog").unwrap(),
+                serial: 2024010101,
+                refresh_interval: 7200,
+                retry_interval: 3600,
+                expire_limit: 1209600,
+                minimum_ttl: 300,
+            })),
+            record("lookup.dog", 3600, Record::NS(NS { nameserver: Labels::encode("ns1.lookup.dog").unwrap() })),
+            record("ns1.lookup.dog", 60, Record::A(A { address: Ipv4Addr::new(192, 0, 2, 53) })),
+            record("www.lookup.dog", 300, Record::AAAA(AAAA { address: "2001:db8::1".parse().unwrap() })),
+            record("www.lookup.dog", 3600, Record::TXT(TXT { messages: vec![
+                Box::new(*b"v=spf1 -all"),
+                Box::new(*b"second \"string\""),
+            ] })),
+            record("mail.example", 3600, Record::MX(MX { preference: 10, exchange: Labels::encode("mx.lookup.dog").unwrap() })),
+        ]);
+        assert_eq!(zone.skipped, vec![]);
+    }
+
+    #[test]
+    fn ttl_carries_on_without_default() {
+        let zone = parse("a.dog. 30 A 192.0.2.1\nb.dog. A 192.0.2.2\n").unwrap();
+        assert_eq!(zone.records[1].ttl, 30);
+    }
+
+    #[test]
+    fn unsupported_types_are_skipped() {
+        let zone = parse("$TTL 60\nlookup.dog. NAPTR 100 10 \"S\" \"SIP+D2U\" \"\" _sip._udp.lookup.dog.\n").unwrap();
+        assert_eq!(zone.records, vec![]);
+        assert_eq!(zone.skipped, vec![ (2, "NAPTR".into()) ]);
+    }
+
+    #[test]
+    fn dnssec_records() {
+        let zone = parse("\
+            $ORIGIN lookup.dog.\n\
+            $TTL 60\n\
+            @  DS      12345 13 2 ( 0123456789abcdef\n\
+                                    FEDCBA9876543210 )\n\
+            @  DNSKEY  257 3 13 ( AQID\n\
+                                  BAU= )\n\
+        ").unwrap();
+
+        assert_eq!(zone.records, vec![
+            record("lookup.dog", 60, Record::DS(DS {
+                key_tag: 12345,
+                algorithm: 13,
+                digest_type: 2,
+                digest: vec![ 0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef, 0xfe, 0xdc, 0xba, 0x98, 0x76, 0x54, 0x32, 0x10 ],
+            })),
+            record("lookup.dog", 60, Record::DNSKEY(DNSKEY {
+                flags: 257,
+                protocol: 3,
+                algorithm: 13,
+                public_key: vec![ 1, 2, 3, 4, 5 ],
+            })),
+        ]);
+    }
+
+    #[test]
+    fn fingerprints() {
+        let zone = parse("$TTL 60\nlookup.dog. SSHFP 4 2 abcd\n_443._tcp.lookup.dog. TLSA 3 1 1 ( 12 34 )\nlookup.dog. HINFO \"PDP-11\" UNIX\n").unwrap();
+        assert_eq!(zone.records, vec![
+            record("lookup.dog", 60, Record::SSHFP(SSHFP { algorithm: 4, fingerprint_type: 2, fingerprint: vec![ 0xab, 0xcd ] })),
+            record("_443._tcp.lookup.dog", 60, Record::TLSA(TLSA { certificate_usage: 3, selector: 1, matching_type: 1, certificate_data: vec![ 0x12, 0x34 ] })),
+            record("lookup.dog", 60, Record::HINFO(HINFO { cpu: Box::new(*b"PDP-11"), os: Box::new(*b"UNIX") })),
+        ]);
+    }
+
+    #[test]
+    fn generic_data() {
+        let zone = parse("$TTL 60\nlookup.dog. A \\# 4 C0000201\nlookup.dog. TYPE65280 \\# 3 ( 0102\n 03 )\n").unwrap();
+        assert_eq!(zone.records, vec![
+            record("lookup.dog", 60, Record::A(A { address: Ipv4Addr::new(192, 0, 2, 1) })),
+            record("lookup.dog", 60, Record::Other { type_number: UnknownQtype::from(65280), bytes: vec![ 1, 2, 3 ] }),
+        ]);
+    }
+
+    #[test]
+    fn generic_data_of_wrong_length() {
+        assert_eq!(parse("$TTL 60\nlookup.dog. A \\# 5 C0000201\n"),
+                   Err(ZoneFileError { line: 2, reason: "Generic record data is 4 bytes long, not 5".into() }));
+    }
+
+    #[test]
+    fn bad_hex() {
+        assert_eq!(parse("$TTL 60\nlookup.dog. SSHFP 4 2 abc\n"),
+                   Err(ZoneFileError { line: 2, reason: "Invalid hexadecimal \"abc\"".into() }));
+    }
+
+    #[test]
+    fn relative_name_without_origin() {
+        assert_eq!(parse("$TTL 60\nwww A 192.0.2.1\n"),
+                   Err(ZoneFileError { line: 2, reason: "Relative name \"www\" with no $ORIGIN".into() }));
+    }
+
+    #[test]
+    fn missing_ttl() {
+        assert_eq!(parse("lookup.dog. A 192.0.2.1\n"),
+                   Err(ZoneFileError { line: 1, reason: "Record has no TTL, and there is no $TTL directive".into() }));
+    }
+
+    #[test]
+    fn bad_address() {
+        assert_eq!(parse("lookup.dog. 60 A 192.0.2\n"),
+                   Err(ZoneFileError { line: 1, reason: "Invalid IPv4 address \"192.0.2\"".into() }));
+    }
+
+    #[test]
+    fn unclosed_parenthesis() {
+        assert_eq!(parse("$TTL 60\nlookup.dog. SOA a. b. ( 1 2 3 4\n"),
+                   Err(ZoneFileError { line: 2, reason: "Unclosed parenthesis".into() }));
+    }
+
+    #[test]
+    fn read_in_small_pieces() {
+        let input = "$TTL 60\nlookup.dog. SOA a. b. ( 1\n 2 3 4 5 )\nlookup.dog. A 192.0.2.1\n";
+        let zone = read(io::BufReader::with_capacity(4, input.as_bytes())).unwrap();
+        assert_eq!(zone, parse(input).unwrap());
+        assert_eq!(zone.records.len(), 2);
+    }
+
+    #[test]
+    fn crlf_line_endings() {
+        let zone = parse("$TTL 60\r\nlookup.dog. A 192.0.2.1\r\n").unwrap();
+        assert_eq!(zone.records, vec![
+            record("lookup.dog", 60, Record::A(A { address: Ipv4Addr::new(192, 0, 2, 1) })),
+        ]);
+    }
+
+    #[test]
+    fn invalid_utf8() {
+        let zone = read(&b"$TTL 60\nlookup.dog. TXT \"\xff\"\n"[..]);
+        assert_eq!(zone.map_err(|e| e.line), Err(2));
+    }
+
+    #[test]
+    fn record_data() {
+        assert_eq!(parse_record_data("mx", "10 mx.lookup.dog."),
+                   Ok(Some(Record::MX(MX { preference: 10, exchange: Labels::encode("mx.lookup.dog").unwrap() }))));
+    }
+
+    #[test]
+    fn record_data_relative_name() {
+        assert!(parse_record_data("CNAME", "www").is_err());
+    }
+
+    #[test]
+    fn record_data_unsupported_type() {
+        assert_eq!(parse_record_data("LOC", "51 30 12.748 N 0 7 39.611 W 0.00m"), Ok(None));
+    }
+
+    #[test]
+    fn ttl_units() {
+        assert_eq!(parse_ttl("300"), Some(300));
+        assert_eq!(parse_ttl("1h30m"), Some(5400));
+        assert_eq!(parse_ttl("1W"), Some(604_800));
+        assert_eq!(parse_ttl("1h30"), None);
+        assert_eq!(parse_ttl("A"), None);
+    }
+}
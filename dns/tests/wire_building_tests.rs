@@ -1,5 +1,7 @@
-use dns::{Request, Flags, Query, Labels, QClass};
-use dns::record::RecordType;
+use std::net::Ipv4Addr;
+
+use dns::{Request, Response, Flags, Opcode, ErrorCode, Query, Answer, Labels, QClass};
+use dns::record::{Record, RecordType, A, NS};
 
 use pretty_assertions::assert_eq;
 
@@ -39,3 +41,194 @@ fn build_request() {
 
     assert_eq!(request.to_bytes().unwrap(), result);
 }
+
+
+#[test]
+fn build_padded_request() {
+    let mut request = Request {
+        transaction_id: 0xceac,
+        flags: Flags::query(),
+        query: Query {
+            qname: Labels::encode("rfcs.io").unwrap(),
+            qclass: QClass::IN,
+            qtype: RecordType::A,
+        },
+        additional: Some(Request::additional_record()),
+    };
+
+    request.pad_to_block(128).unwrap();
+    let bytes = request.to_bytes().unwrap();
+    assert_eq!(bytes.len(), 128);
+
+    // padding it again replaces the padding rather than adding more
+    request.pad_to_block(128).unwrap();
+    assert_eq!(request.to_bytes().unwrap(), bytes);
+}
+
+#[test]
+fn build_padded_request_without_opt() {
+    let mut request = Request {
+        transaction_id: 0xceac,
+        flags: Flags::query(),
+        query: Query {
+            qname: Labels::encode("rfcs.io").unwrap(),
+            qclass: QClass::IN,
+            qtype: RecordType::A,
+        },
+        additional: None,
+    };
+
+    request.pad_to_block(128).unwrap();
+    assert_eq!(request.to_bytes().unwrap().len(), 25);
+}
+
+
+#[test]
+fn build_message() {
+    let flags = Flags { authoritative: true, ..Flags::standard_response() };
+
+    let bytes = Request::builder()
+        .transaction_id(0x1234)
+        .flags(flags)
+        .opcode(Opcode::Update)
+        .query(Query { qname: Labels::encode("lookup.dog").unwrap(), qclass: QClass::IN, qtype: RecordType::A })
+        .query(Query { qname: Labels::encode("lookup.dog").unwrap(), qclass: QClass::IN, qtype: RecordType::MX })
+        .answer(Answer::Standard {
+            qname: Labels::encode("lookup.dog").unwrap(),
+            qclass: QClass::IN,
+            ttl: 300,
+            record: Record::A(A { address: Ipv4Addr::new(192, 0, 2, 1) }),
+        })
+        .authority(Answer::Standard {
+            qname: Labels::encode("dog").unwrap(),
+            qclass: QClass::IN,
+            ttl: 3600,
+            record: Record::NS(NS { nameserver: Labels::encode("ns.dog").unwrap() }),
+        })
+        .opt(Request::additional_record())
+        .compress_names(false)
+        .to_bytes()
+        .unwrap();
+
+    let result = vec![
+        0x12, 0x34,  // transaction ID
+        0xad, 0x80,  // flags (response, UPDATE, authoritative, recursion desired and available)
+        0x00, 0x02, 0x00, 0x01, 0x00, 0x01, 0x00, 0x01,  // counts (2, 1, 1, 1)
+
+        // queries:
+        0x06, 0x6c, 0x6f, 0x6f, 0x6b, 0x75, 0x70, 0x03, 0x64, 0x6f, 0x67, 0x00,  // qname
+        0x00, 0x01,  // type A
+        0x00, 0x01,  // class IN
+        0x06, 0x6c, 0x6f, 0x6f, 0x6b, 0x75, 0x70, 0x03, 0x64, 0x6f, 0x67, 0x00,  // qname
+        0x00, 0x0f,  // type MX
+        0x00, 0x01,  // class IN
+
+        // answer:
+        0x06, 0x6c, 0x6f, 0x6f, 0x6b, 0x75, 0x70, 0x03, 0x64, 0x6f, 0x67, 0x00,  // name
+        0x00, 0x01,  // type A
+        0x00, 0x01,  // class IN
+        0x00, 0x00, 0x01, 0x2c,  // TTL
+        0x00, 0x04,  // data length
+        0xc0, 0x00, 0x02, 0x01,  // address
+
+        // authority:
+        0x03, 0x64, 0x6f, 0x67, 0x00,  // name
+        0x00, 0x02,  // type NS
+        0x00, 0x01,  // class IN
+        0x00, 0x00, 0x0e, 0x10,  // TTL
+        0x00, 0x08,  // data length
+        0x02, 0x6e, 0x73, 0x03, 0x64, 0x6f, 0x67, 0x00,  // nameserver
+
+        // OPT record:
+        0x00,  // name
+        0x00, 0x29,  // type OPT
+        0x02, 0x00,  // UDP payload size
+        0x00,  // higher bits
+        0x00,  // EDNS(0) version
+        0x00, 0x00,  // more flags
+        0x00, 0x00,  // no data
+    ];
+
+    assert_eq!(bytes, result);
+}
+
+#[test]
+fn build_compressed_message() {
+    let authority = Answer::Standard {
+        qname: Labels::encode("dog").unwrap(),
+        qclass: QClass::IN,
+        ttl: 3600,
+        record: Record::NS(NS { nameserver: Labels::encode("ns.dog").unwrap() }),
+    };
+
+    let bytes = Request::builder()
+        .transaction_id(0x1234)
+        .query(Query { qname: Labels::encode("lookup.dog").unwrap(), qclass: QClass::IN, qtype: RecordType::A })
+        .query(Query { qname: Labels::encode("lookup.dog").unwrap(), qclass: QClass::IN, qtype: RecordType::MX })
+        .authority(authority.clone())
+        .to_bytes()
+        .unwrap();
+
+    let result = vec![
+        0x12, 0x34,  // transaction ID
+        0x01, 0x00,  // flags (standard query)
+        0x00, 0x02, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00,  // counts (2, 0, 1, 0)
+
+        // queries:
+        0x06, 0x6c, 0x6f, 0x6f, 0x6b, 0x75, 0x70, 0x03, 0x64, 0x6f, 0x67, 0x00,  // qname
+        0x00, 0x01,  // type A
+        0x00, 0x01,  // class IN
+        0xc0, 0x0c,  // qname (pointer to the first query)
+        0x00, 0x0f,  // type MX
+        0x00, 0x01,  // class IN
+
+        // authority:
+        0xc0, 0x13,  // name (pointer to the end of the first query)
+        0x00, 0x02,  // type NS
+        0x00, 0x01,  // class IN
+        0x00, 0x00, 0x0e, 0x10,  // TTL
+        0x00, 0x08,  // data length
+        0x02, 0x6e, 0x73, 0x03, 0x64, 0x6f, 0x67, 0x00,  // nameserver
+    ];
+
+    assert_eq!(bytes, result);
+
+    let response = Response::from_bytes(&bytes).unwrap();
+    assert_eq!(response.queries[1].qname, Labels::encode("lookup.dog").unwrap());
+    assert_eq!(response.authorities, vec![ authority ]);
+}
+
+#[test]
+fn built_message_parses() {
+    let answer = Answer::Standard {
+        qname: Labels::encode("lookup.dog").unwrap(),
+        qclass: QClass::IN,
+        ttl: 60,
+        record: Record::NS(NS { nameserver: Labels::encode("ns.dog").unwrap() }),
+    };
+
+    let mut flags = Flags::standard_response();
+    flags.error_code = Some(ErrorCode::NXDomain);
+
+    let bytes = Request::builder()
+        .transaction_id(0xabcd)
+        .flags(flags)
+        .query(Query { qname: Labels::encode("lookup.dog").unwrap(), qclass: QClass::IN, qtype: RecordType::NS })
+        .answer(answer.clone())
+        .to_bytes()
+        .unwrap();
+
+    let response = Response::from_bytes(&bytes).unwrap();
+    assert_eq!(response.transaction_id, 0xabcd);
+    assert_eq!(response.flags, flags);
+    assert_eq!(response.answers, vec![ answer ]);
+}
+
+
+#[test]
+fn build_opcodes() {
+    for opcode in &[ Opcode::Query, Opcode::Status, Opcode::Notify, Opcode::Update, Opcode::Dso, Opcode::Other(7) ] {
+        let flags = Flags { opcode: *opcode, ..Flags::query() };
+        assert_eq!(Flags::from_u16(flags.to_u16()), flags);
+    }
+}
@@ -1,6 +1,7 @@
 use std::net::Ipv4Addr;
 
-use dns::{Response, Query, Answer, Labels, Flags, Opcode, QClass};
+use dns::{Response, Query, Answer, Labels, Flags, Opcode, QClass, ErrorCode};
+use dns::{PartialResponse, RecordError, Section, WireError, MandatedLength};
 use dns::record::{Record, A, CNAME, OPT, SOA, UnknownQtype, RecordType};
 
 use pretty_assertions::assert_eq;
@@ -12,6 +13,17 @@ fn parse_nothing() {
 }
 
 
+#[test]
+fn parse_opcodes() {
+    assert_eq!(Flags::from_u16(0b_0000_0000_0000_0000).opcode, Opcode::Query);
+    assert_eq!(Flags::from_u16(0b_0001_0000_0000_0000).opcode, Opcode::Status);
+    assert_eq!(Flags::from_u16(0b_0010_0000_0000_0000).opcode, Opcode::Notify);
+    assert_eq!(Flags::from_u16(0b_0010_1000_0000_0000).opcode, Opcode::Update);
+    assert_eq!(Flags::from_u16(0b_0011_0000_0000_0000).opcode, Opcode::Dso);
+    assert_eq!(Flags::from_u16(0b_0011_1000_0000_0000).opcode, Opcode::Other(7));
+}
+
+
 #[test]
 fn parse_response_standard() {
     let buf = &[
@@ -269,3 +281,288 @@ fn parse_response_with_multiple_additionals() {
 
     assert_eq!(Response::from_bytes(buf), Ok(response));
 }
+
+
+#[test]
+fn parse_response_with_multiple_questions() {
+    let buf = &[
+        0x12, 0x34,  // transaction ID
+        0x81, 0x81,  // flags (standard query, response, FORMERR)
+        0x00, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,  // counts (2, 0, 0, 0)
+
+        // the first question:
+        0x06, 0x6c, 0x6f, 0x6f, 0x6b, 0x75, 0x70, 0x03, 0x64, 0x6f, 0x67, 0x00,  // "lookup.dog."
+        0x00, 0x01,  // type A
+        0x00, 0x01,  // class IN
+
+        // the second question:
+        0xc0, 0x0c,  // a pointer to "lookup.dog."
+        0x00, 0x0f,  // type MX
+        0x00, 0x01,  // class IN
+    ];
+
+    let response = Response::from_bytes(buf).unwrap();
+    assert_eq!(response.queries, vec![
+        Query { qname: Labels::encode("lookup.dog").unwrap(), qclass: QClass::IN, qtype: RecordType::A },
+        Query { qname: Labels::encode("lookup.dog").unwrap(), qclass: QClass::IN, qtype: RecordType::MX },
+    ]);
+    assert_eq!(response.flags.error_code, Some(ErrorCode::FormatError));
+}
+
+#[test]
+fn parse_response_bad_version() {
+    let buf = &[
+        0xab, 0xcd,  // transaction ID
+        0x81, 0x80,  // flags (standard query, response, no error in the header)
+        0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01,  // counts (1, 0, 0, 1)
+
+        // the query:
+        0x03, 0x64, 0x6f, 0x67, 0x00,  // "dog."
+        0x00, 0x01,  // type A
+        0x00, 0x01,  // class IN
+
+        // the additional:
+        0x00,        // no name
+        0x00, 0x29,  // type OPT
+        0x04, 0xd0,  // UDP payload size (1232)
+        0x01,        // higher bits (BADVERS)
+        0x00,        // EDNS version
+        0x00, 0x00,  // extra bits (DO bit unset)
+        0x00, 0x00,  // data length 0
+    ];
+
+    let response = Response::from_bytes(buf).unwrap();
+    assert_eq!(response.flags.error_code, Some(ErrorCode::BadVersion));
+    assert_eq!(response.bad_version(), Some(0));
+}
+
+
+#[test]
+fn parse_response_bad_cookie() {
+    let buf = &[
+        0xab, 0xcd,  // transaction ID
+        0x81, 0x87,  // flags (standard query, response, lower bits of BADCOOKIE)
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01,  // counts (0, 0, 0, 1)
+
+        // the additional:
+        0x00,        // no name
+        0x00, 0x29,  // type OPT
+        0x04, 0xd0,  // UDP payload size (1232)
+        0x01,        // higher bits of BADCOOKIE
+        0x00,        // EDNS version
+        0x00, 0x00,  // extra bits (DO bit unset)
+        0x00, 0x00,  // data length 0
+    ];
+
+    let response = Response::from_bytes(buf).unwrap();
+    assert_eq!(response.flags.error_code, Some(ErrorCode::BadCookie));
+    assert!(response.has_bad_cookie());
+    assert_eq!(response.bad_version(), None);
+}
+
+
+#[test]
+fn parse_response_header_rcode_without_opt() {
+    let buf = &[
+        0xab, 0xcd,  // transaction ID
+        0x81, 0x87,  // flags (standard query, response, rcode 7)
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,  // counts (0, 0, 0, 0)
+    ];
+
+    let response = Response::from_bytes(buf).unwrap();
+    assert_eq!(response.flags.error_code, Some(ErrorCode::Other(7)));
+    assert!(! response.has_bad_cookie());
+}
+
+
+#[test]
+fn parse_response_extended_rcode_with_lower_bits() {
+    let buf = &[
+        0xab, 0xcd,  // transaction ID
+        0x81, 0x82,  // flags (standard query, response, lower bits 2)
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01,  // counts (0, 0, 0, 1)
+
+        // the additional:
+        0x00,        // no name
+        0x00, 0x29,  // type OPT
+        0x04, 0xd0,  // UDP payload size (1232)
+        0x01,        // higher bits
+        0x00,        // EDNS version
+        0x00, 0x00,  // extra bits (DO bit unset)
+        0x00, 0x00,  // data length 0
+    ];
+
+    // 1 << 4 | 2 is 18, which is BADTIME, rather than SERVFAIL.
+    let response = Response::from_bytes(buf).unwrap();
+    assert_eq!(response.flags.error_code, Some(ErrorCode::Other(18)));
+}
+
+
+#[test]
+fn parse_lenient_skips_bad_record() {
+    let buf = &[
+        0xab, 0xcd,  // transaction ID
+        0x81, 0x80,  // flags (standard query, response, no error)
+        0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00,  // counts (0, 2, 0, 0)
+
+        // the first answer:
+        0x00,        // no name
+        0x00, 0x01,  // type A
+        0x00, 0x01,  // class IN
+        0x00, 0x00, 0x00, 0x3c,  // TTL (60 seconds)
+        0x00, 0x03,  // record data length 3, which is too short
+        0x7f, 0x00, 0x00,
+
+        // the second answer:
+        0x00,        // no name
+        0x00, 0x01,  // type A
+        0x00, 0x01,  // class IN
+        0x00, 0x00, 0x00, 0x3c,  // TTL (60 seconds)
+        0x00, 0x04,  // record data length 4
+        0x7f, 0x00, 0x00, 0x01,  // record data (127.0.0.1)
+    ];
+
+    assert!(Response::from_bytes(buf).is_err());
+
+    let partial = Response::from_bytes_lenient(buf).unwrap();
+    assert_eq!(partial.response.answers, vec![
+        Answer::Standard {
+            qname: Labels::root(),
+            qclass: QClass::IN,
+            ttl: 60,
+            record: Record::A(A {
+                address: Ipv4Addr::new(127, 0, 0, 1),
+            }),
+        },
+    ]);
+    assert_eq!(partial.errors, vec![
+        RecordError {
+            section: Section::Answer,
+            index: 0,
+            error: WireError::WrongRecordLength { stated_length: 3, mandated_length: MandatedLength::Exactly(4) },
+        },
+    ]);
+}
+
+
+#[test]
+fn parse_lenient_stops_at_cut_off_record() {
+    let buf = &[
+        0xab, 0xcd,  // transaction ID
+        0x81, 0x80,  // flags (standard query, response, no error)
+        0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00,  // counts (0, 2, 0, 0)
+
+        // the first answer:
+        0x00,        // no name
+        0x00, 0x01,  // type A
+        0x00, 0x01,  // class IN
+        0x00, 0x00, 0x00, 0x3c,  // TTL (60 seconds)
+        0x00, 0x04,  // record data length 4
+        0x7f, 0x00, 0x00, 0x01,  // record data (127.0.0.1)
+
+        // the second answer, which has been cut off:
+        0x00,        // no name
+        0x00, 0x01,  // type A
+    ];
+
+    let partial = Response::from_bytes_lenient(buf).unwrap();
+    assert_eq!(partial, PartialResponse {
+        response: Response {
+            transaction_id: 0xabcd,
+            flags: Flags::from_u16(0x8180),
+            queries: vec![],
+            answers: vec![
+                Answer::Standard {
+                    qname: Labels::root(),
+                    qclass: QClass::IN,
+                    ttl: 60,
+                    record: Record::A(A {
+                        address: Ipv4Addr::new(127, 0, 0, 1),
+                    }),
+                },
+            ],
+            authorities: vec![],
+            additionals: vec![],
+        },
+        errors: vec![
+            RecordError { section: Section::Answer, index: 1, error: WireError::IO },
+        ],
+    });
+}
+
+
+#[test]
+fn parse_lenient_header_cut_off() {
+    assert_eq!(Response::from_bytes_lenient(&[ 0xab, 0xcd, 0x81 ]),
+               Err(WireError::IO));
+}
+
+
+#[test]
+fn stream_reads_records_in_order() {
+    let buf = &[
+        0xab, 0xcd,  // transaction ID
+        0x81, 0x80,  // flags (standard query, response, no error)
+        0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01,  // counts (0, 1, 0, 1)
+
+        // the answer:
+        0x00,        // no name
+        0x00, 0x01,  // type A
+        0x00, 0x01,  // class IN
+        0x00, 0x00, 0x00, 0x3c,  // TTL (60 seconds)
+        0x00, 0x04,  // record data length 4
+        0x7f, 0x00, 0x00, 0x01,  // record data (127.0.0.1)
+
+        // the additional record:
+        0x00,        // no name
+        0x00, 0x01,  // type A
+        0x00, 0x01,  // class IN
+        0x00, 0x00, 0x00, 0x3c,  // TTL (60 seconds)
+        0x00, 0x04,  // record data length 4
+        0x7f, 0x00, 0x00, 0x02,  // record data (127.0.0.2)
+    ];
+
+    let answer = |last| Answer::Standard {
+        qname: Labels::root(),
+        qclass: QClass::IN,
+        ttl: 60,
+        record: Record::A(A {
+            address: Ipv4Addr::new(127, 0, 0, last),
+        }),
+    };
+
+    let stream = Response::stream(buf).unwrap();
+    assert_eq!(stream.transaction_id, 0xabcd);
+    assert_eq!(stream.size_hint(), (0, Some(2)));
+    assert_eq!(stream.collect::<Vec<_>>(), vec![
+        Ok((Section::Answer, answer(1))),
+        Ok((Section::Additional, answer(2))),
+    ]);
+}
+
+
+#[test]
+fn stream_stops_at_cut_off_record() {
+    let buf = &[
+        0xab, 0xcd,  // transaction ID
+        0x81, 0x80,  // flags (standard query, response, no error)
+        0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x00,  // counts (0, 3, 0, 0)
+
+        // the first answer:
+        0x00,        // no name
+        0x00, 0x01,  // type A
+        0x00, 0x01,  // class IN
+        0x00, 0x00, 0x00, 0x3c,  // TTL (60 seconds)
+        0x00, 0x04,  // record data length 4
+        0x7f, 0x00, 0x00, 0x01,  // record data (127.0.0.1)
+
+        // the second answer, which has been cut off:
+        0x00,        // no name
+        0x00, 0x01,  // type A
+    ];
+
+    let mut stream = Response::stream(buf).unwrap();
+    assert!(matches!(stream.next(), Some(Ok((Section::Answer, _)))));
+    assert_eq!(stream.next(), Some(Err(RecordError { section: Section::Answer, index: 1, error: WireError::IO })));
+    assert_eq!(stream.next(), None);
+}
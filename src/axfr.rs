@@ -0,0 +1,377 @@
+//! Transferring a zone from every one of its nameservers at once, and
+//! checking that they all serve the same records, so that secondaries that
+//! have fallen behind the primary or stopped updating can be found.
+
+use std::fmt;
+use std::thread;
+
+use dns::{Answer, AxfrParser, Labels, Response, TransferError};
+use dns::record::{Record, RecordType};
+use dns_transport::TcpTransport;
+use log::*;
+
+use crate::output::status_mnemonic;
+use crate::requests::{RequestGenerator, ProtocolTweaks};
+use crate::resolve::ResolverLookupError;
+
+
+/// The type number of a zone transfer query.
+const AXFR: u16 = 252;
+
+
+/// The results of transferring a zone from each of its nameservers.
+#[derive(PartialEq, Debug)]
+pub struct AxfrReport {
+
+    /// The zone that was transferred.
+    pub zone: Labels,
+
+    /// The nameserver whose copy of the zone the others were compared
+    /// with, if any transfer worked.
+    pub reference: Option<String>,
+
+    /// Each nameserver, along with what it sent and how that compares.
+    pub results: Vec<(String, Result<Transfer, String>, Verdict)>,
+}
+
+/// The records sent by one nameserver in a zone transfer.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Transfer {
+
+    /// The serial number from the zone’s SOA record.
+    pub serial: u32,
+
+    /// Every record in the zone, including the opening SOA record but not
+    /// the closing one.
+    pub records: Vec<Answer>,
+}
+
+/// How one nameserver’s copy of a zone compares with the reference copy.
+#[derive(PartialEq, Debug)]
+pub enum Verdict {
+
+    /// It has the latest serial, and the same records as the reference.
+    Consistent,
+
+    /// Its serial is older than the latest one.
+    Stale {
+        latest: u32,
+    },
+
+    /// It has the latest serial, but different records to the reference:
+    /// this many of the reference’s records are missing, and this many
+    /// records are not in the reference.
+    Divergent {
+        missing: usize,
+        extra: usize,
+    },
+
+    /// The transfer failed, so there is nothing to compare.
+    Failed,
+}
+
+/// Something that went wrong before any transfers could start.
+pub enum AxfrError {
+
+    /// A resolver to look up the zone’s nameservers could not be found.
+    Resolver(ResolverLookupError),
+
+    /// The lookup of the zone’s nameservers failed.
+    Lookup(String),
+
+    /// The zone has no nameservers.
+    NoNameservers,
+}
+
+
+/// Looks up the nameservers of the first domain in the inputs, using the
+/// first nameserver and transport type, then transfers the zone from every
+/// one of them at the same time, and compares the results.
+pub fn run_transfers(requests: RequestGenerator) -> Result<AxfrReport, AxfrError> {
+    let zone = requests.inputs.domains.first().cloned().expect("No zone");
+    let txid_generator = requests.txid_generator;
+    let protocol_tweaks = requests.protocol_tweaks.clone();
+    let source = requests.source.clone();
+    let transport = requests.first_transport().map_err(AxfrError::Resolver)?;
+
+    let query = dns::Query { qname: zone.clone(), qtype: RecordType::NS, qclass: dns::QClass::IN };
+    let request = dns::Request { transaction_id: txid_generator.generate(), flags: dns::Flags::query(), query, additional: None };
+    let response = transport.send(&request).map_err(|e| AxfrError::Lookup(format!("{:?}", e)))?;
+
+    let nameservers = nameserver_names(&response);
+    if nameservers.is_empty() {
+        return Err(AxfrError::NoNameservers);
+    }
+
+    let threads = nameservers.into_iter().map(|nameserver| {
+        let query = dns::Query { qname: zone.clone(), qtype: RecordType::from(AXFR), qclass: dns::QClass::IN };
+        let request = dns::Request { transaction_id: txid_generator.generate(), flags: dns::Flags::query(), query, additional: None };
+
+        let address = nameserver.trim_end_matches('.').to_owned();
+        let protocol_tweaks = protocol_tweaks.clone();
+        let source = source.clone();
+        let thread = thread::spawn(move || {
+            let transport = TcpTransport::new(address).with_source(source);
+            match zone_transfer(&transport, &request, &protocol_tweaks) {
+                Ok(responses) => transfer(responses),
+                Err(e) => {
+                    warn!("Zone transfer failed: {:?}", e);
+                    Err(format!("{:?}", e))
+                }
+            }
+        });
+
+        (nameserver, thread)
+    }).collect::<Vec<_>>();
+
+    let transfers = threads.into_iter()
+        .map(|(nameserver, thread)| {
+            let transfer = thread.join().unwrap_or_else(|_| Err("transfer thread panicked".into()));
+            (nameserver, transfer)
+        })
+        .collect();
+
+    Ok(compare(zone, transfers))
+}
+
+/// Transfers the zone from one nameserver, signing the request if there is
+/// a key to sign it with.
+#[cfg_attr(not(any(feature = "with_tsig", feature = "with_sig0")), allow(unused_variables))]
+fn zone_transfer(transport: &TcpTransport, request: &dns::Request, protocol_tweaks: &ProtocolTweaks) -> Result<Vec<Response>, dns_transport::Error> {
+    #[cfg(feature = "with_tsig")]
+    if let Some(key) = &protocol_tweaks.tsig_key {
+        return crate::tsig::signed_zone_transfer(transport, request, key);
+    }
+
+    #[cfg(feature = "with_sig0")]
+    if let Some(key) = &protocol_tweaks.sig0_key {
+        return crate::sig0::signed_zone_transfer(transport, request, key);
+    }
+
+    transport.zone_transfer(request)
+}
+
+/// Returns the names of the nameservers in the answer section of a
+/// response to an NS query.
+fn nameserver_names(response: &Response) -> Vec<String> {
+    response.answers.iter()
+        .filter_map(|answer| match answer {
+            Answer::Standard { record: Record::NS(ns), .. }  => Some(ns.nameserver.to_string()),
+            _                                               => None,
+        })
+        .collect()
+}
+
+/// Collects the records from the messages of a zone transfer, checking
+/// that it begins and ends with an SOA record.
+fn transfer(responses: Vec<Response>) -> Result<Transfer, String> {
+    let mut parser = AxfrParser::new();
+    let mut records = Vec::new();
+
+    for response in &responses {
+        match parser.push(response) {
+            Ok(answers)                             => records.extend(answers),
+            Err(TransferError::ErrorCode(rcode))    => return Err(status_mnemonic(Some(rcode))),
+            Err(e)                                  => return Err(e.to_string()),
+        }
+    }
+
+    match parser.serial() {
+        Some(serial) if parser.is_finished()  => Ok(Transfer { serial, records }),
+        _                                     => Err("transfer ended before the closing SOA record".into()),
+    }
+}
+
+/// Compares every transfer with the first one that has the latest serial.
+fn compare(zone: Labels, transfers: Vec<(String, Result<Transfer, String>)>) -> AxfrReport {
+    let latest = transfers.iter()
+        .filter_map(|(_, transfer)| transfer.as_ref().ok())
+        .map(|transfer| transfer.serial)
+        .max();
+
+    let reference = transfers.iter()
+        .find(|(_, transfer)| matches!(transfer, Ok(t) if Some(t.serial) == latest))
+        .map(|(nameserver, transfer)| (nameserver.clone(), transfer.as_ref().unwrap().records.clone()));
+
+    let results = transfers.into_iter()
+        .map(|(nameserver, transfer)| {
+            let verdict = match (&transfer, &reference, latest) {
+                (Ok(t), Some((_, reference_records)), Some(latest)) => {
+                    if t.serial == latest {
+                        let missing = reference_records.iter().filter(|r| ! t.records.contains(r)).count();
+                        let extra = t.records.iter().filter(|r| ! reference_records.contains(r)).count();
+
+                        if missing == 0 && extra == 0 { Verdict::Consistent }
+                                                 else { Verdict::Divergent { missing, extra } }
+                    }
+                    else {
+                        Verdict::Stale { latest }
+                    }
+                }
+                _ => Verdict::Failed,
+            };
+
+            (nameserver, transfer, verdict)
+        })
+        .collect();
+
+    AxfrReport { zone, reference: reference.map(|(nameserver, _)| nameserver), results }
+}
+
+
+impl AxfrReport {
+
+    /// Whether every nameserver sent the same copy of the zone.
+    pub fn is_consistent(&self) -> bool {
+        self.results.iter().all(|(_, _, verdict)| *verdict == Verdict::Consistent)
+    }
+
+    /// Whether any transfer failed.
+    pub fn any_failed(&self) -> bool {
+        self.results.iter().any(|(_, _, verdict)| *verdict == Verdict::Failed)
+    }
+}
+
+impl fmt::Display for AxfrReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self.results.iter().map(|(nameserver, _, _)| nameserver.len()).max().unwrap_or(0);
+
+        for (nameserver, transfer, verdict) in &self.results {
+            match transfer {
+                Ok(t) => {
+                    write!(f, "{:<width$}  serial {}  {} records  ", nameserver, t.serial, t.records.len(), width = width)?;
+
+                    match verdict {
+                        Verdict::Consistent => {
+                            writeln!(f, "ok")?;
+                        }
+                        Verdict::Stale { latest } => {
+                            writeln!(f, "stale ({} is the latest)", latest)?;
+                        }
+                        Verdict::Divergent { missing, extra } => {
+                            let reference = self.reference.as_deref().unwrap_or_default();
+                            writeln!(f, "divergent from {}: {} missing, {} extra", reference, missing, extra)?;
+                        }
+                        Verdict::Failed => {
+                            writeln!(f)?;
+                        }
+                    }
+                }
+                Err(why) => {
+                    writeln!(f, "{:<width$}  failed: {}", nameserver, why, width = width)?;
+                }
+            }
+        }
+
+        let consistent = self.results.iter().filter(|(_, _, verdict)| *verdict == Verdict::Consistent).count();
+        writeln!(f, "{} of {} nameservers for {} serve the same zone", consistent, self.results.len(), self.zone)
+    }
+}
+
+impl fmt::Display for AxfrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Resolver(e)    => write!(f, "Unable to obtain resolver: {}", e),
+            Self::Lookup(why)    => write!(f, "Unable to look up the zone's nameservers: {}", why),
+            Self::NoNameservers  => write!(f, "The zone has no nameservers"),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use dns::{Flags, QClass};
+    use dns::record::{A, SOA};
+    use pretty_assertions::assert_eq;
+
+    fn soa(serial: u32) -> Answer {
+        Answer::Standard {
+            qname: Labels::encode("lookup.dog").unwrap(),
+            qclass: QClass::IN,
+            ttl: 3600,
+            record: Record::SOA(SOA {
+                mname: Labels::encode("ns1.lookup.dog").unwrap(),
+                rname: Labels::encode("hostmaster.lookup.dog").unwrap(),
+                serial,
+                refresh_interval: 7200,
+                retry_interval: 3600,
+                expire_limit: 1_209_600,
+                minimum_ttl: 300,
+            }),
+        }
+    }
+
+    fn a(last: u8) -> Answer {
+        Answer::Standard {
+            qname: Labels::encode("lookup.dog").unwrap(),
+            qclass: QClass::IN,
+            ttl: 60,
+            record: Record::A(A { address: std::net::Ipv4Addr::new(192, 0, 2, last) }),
+        }
+    }
+
+    fn response(answers: Vec<Answer>) -> Response {
+        Response { transaction_id: 1, flags: Flags::standard_response(), queries: vec![], answers, authorities: vec![], additionals: vec![] }
+    }
+
+    #[test]
+    fn transfer_across_messages() {
+        let responses = vec![ response(vec![ soa(5), a(1) ]), response(vec![ a(2), soa(5) ]) ];
+        assert_eq!(transfer(responses),
+                   Ok(Transfer { serial: 5, records: vec![ soa(5), a(1), a(2) ] }));
+    }
+
+    #[test]
+    fn refused_transfer() {
+        let mut refused = response(vec![]);
+        refused.flags.error_code = Some(dns::ErrorCode::QueryRefused);
+        assert_eq!(transfer(vec![ refused ]), Err("REFUSED".into()));
+    }
+
+    #[test]
+    fn unfinished_transfer() {
+        assert_eq!(transfer(vec![ response(vec![ soa(5), a(1) ]) ]),
+                   Err("transfer ended before the closing SOA record".into()));
+    }
+
+    #[test]
+    fn transfer_without_soa() {
+        assert_eq!(transfer(vec![ response(vec![ a(1) ]) ]),
+                   Err("transfer did not start with an SOA record".into()));
+    }
+
+    #[test]
+    fn comparison() {
+        let zone = Labels::encode("lookup.dog").unwrap();
+        let report = compare(zone, vec![
+            ("ns1.lookup.dog.".into(), Ok(Transfer { serial: 5, records: vec![ soa(5), a(1), a(2) ] })),
+            ("ns2.lookup.dog.".into(), Ok(Transfer { serial: 5, records: vec![ soa(5), a(2), a(1) ] })),
+            ("ns3.lookup.dog.".into(), Ok(Transfer { serial: 4, records: vec![ soa(4), a(1) ] })),
+            ("ns4.lookup.dog.".into(), Ok(Transfer { serial: 5, records: vec![ soa(5), a(1), a(3) ] })),
+            ("ns5.lookup.dog.".into(), Err("REFUSED".into())),
+        ]);
+
+        let verdicts = report.results.iter().map(|(_, _, verdict)| verdict).collect::<Vec<_>>();
+        assert_eq!(verdicts, vec![
+            &Verdict::Consistent,
+            &Verdict::Consistent,
+            &Verdict::Stale { latest: 5 },
+            &Verdict::Divergent { missing: 1, extra: 1 },
+            &Verdict::Failed,
+        ]);
+
+        assert_eq!(report.reference, Some("ns1.lookup.dog.".into()));
+        assert!(! report.is_consistent());
+        assert!(report.any_failed());
+
+        assert_eq!(report.to_string(), "\
+            ns1.lookup.dog.  serial 5  3 records  ok\n\
+            ns2.lookup.dog.  serial 5  3 records  ok\n\
+            ns3.lookup.dog.  serial 4  2 records  stale (5 is the latest)\n\
+            ns4.lookup.dog.  serial 5  3 records  divergent from ns1.lookup.dog.: 1 missing, 1 extra\n\
+            ns5.lookup.dog.  failed: REFUSED\n\
+            2 of 5 nameservers for lookup.dog. serve the same zone\n");
+    }
+}
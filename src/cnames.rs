@@ -0,0 +1,184 @@
+//! Checking the chains of CNAME records in responses for loops, and for
+//! being longer than resolvers are willing to follow, as either one makes
+//! the name impossible to resolve.
+
+use std::fmt;
+
+use dns::{Answer, Response};
+use dns::record::Record;
+
+
+/// The most CNAME records that a chain can have before resolvers start
+/// giving up on it. Resolvers pick their own limits, and the lowest ones
+/// in common use stop following after this many.
+const MAX_CHAIN_LENGTH: usize = 8;
+
+
+/// Something wrong with the chain of CNAME records in a response.
+#[derive(PartialEq, Debug)]
+pub enum ChainProblem {
+
+    /// The chain leads back to a name already in it. The chain contains
+    /// every name in order, ending with the repeated one, along with the
+    /// index of the first time the repeated name appears.
+    Loop {
+        chain: Vec<String>,
+        loop_start: usize,
+    },
+
+    /// The chain has more CNAME records than resolvers will follow.
+    TooLong {
+        chain: Vec<String>,
+    },
+}
+
+
+/// Returns the problem with the chain of CNAME records that starts at each
+/// response’s query name, for the responses that have one.
+pub fn problems(responses: &[Response]) -> Vec<ChainProblem> {
+    responses.iter().filter_map(check).collect()
+}
+
+/// Follows the chain of CNAME records in the answer section of a response,
+/// starting from the name that was queried.
+fn check(response: &Response) -> Option<ChainProblem> {
+    let mut chain = vec![ response.queries.first()?.qname.to_string() ];
+
+    while let Some(target) = cname_target(response, chain.last().unwrap()) {
+        let repeat = chain.iter().position(|name| name.eq_ignore_ascii_case(&target));
+        chain.push(target);
+
+        if let Some(loop_start) = repeat {
+            return Some(ChainProblem::Loop { chain, loop_start });
+        }
+    }
+
+    if chain.len() - 1 > MAX_CHAIN_LENGTH {
+        Some(ChainProblem::TooLong { chain })
+    }
+    else {
+        None
+    }
+}
+
+/// Finds the target of the CNAME record for the given name in the answer
+/// section of a response.
+fn cname_target(response: &Response, name: &str) -> Option<String> {
+    response.answers.iter().find_map(|answer| match answer {
+        Answer::Standard { qname, record: Record::CNAME(cname), .. }
+            if qname.to_string().eq_ignore_ascii_case(name)
+            => Some(cname.domain.to_string()),
+        _   => None,
+    })
+}
+
+
+impl fmt::Display for ChainProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Loop { chain, loop_start } => {
+                // The name the chain loops back to is put in brackets both
+                // times it appears.
+                let names = chain.iter().enumerate()
+                    .map(|(index, name)| {
+                        if index == *loop_start || index == chain.len() - 1 { format!("[{}]", name) }
+                                                                       else { name.clone() }
+                    })
+                    .collect::<Vec<_>>();
+
+                write!(f, "CNAME loop: {}", names.join(" -> "))
+            }
+            Self::TooLong { chain } => {
+                write!(f, "CNAME chain of {} records is longer than resolvers follow ({} at most): {}",
+                       chain.len() - 1, MAX_CHAIN_LENGTH, chain.join(" -> "))
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use dns::{Flags, Labels, QClass, Query};
+    use dns::record::{A, CNAME, RecordType};
+    use pretty_assertions::assert_eq;
+
+    fn cname(from: &str, to: &str) -> Answer {
+        Answer::Standard {
+            qname: Labels::encode(from).unwrap(),
+            qclass: QClass::IN,
+            ttl: 60,
+            record: Record::CNAME(CNAME { domain: Labels::encode(to).unwrap() }),
+        }
+    }
+
+    fn response(qname: &str, answers: Vec<Answer>) -> Response {
+        Response {
+            transaction_id: 0x1234,
+            flags: Flags::standard_response(),
+            queries: vec![ Query { qname: Labels::encode(qname).unwrap(), qclass: QClass::IN, qtype: RecordType::A } ],
+            answers,
+            authorities: vec![],
+            additionals: vec![],
+        }
+    }
+
+    #[test]
+    fn short_chain() {
+        let answers = vec![
+            cname("a.lookup.dog", "b.lookup.dog"),
+            Answer::Standard {
+                qname: Labels::encode("b.lookup.dog").unwrap(),
+                qclass: QClass::IN,
+                ttl: 60,
+                record: Record::A(A { address: std::net::Ipv4Addr::new(192, 0, 2, 1) }),
+            },
+        ];
+
+        assert_eq!(check(&response("a.lookup.dog", answers)), None);
+    }
+
+    #[test]
+    fn loop_detected() {
+        let answers = vec![
+            cname("a.lookup.dog", "b.lookup.dog"),
+            cname("b.lookup.dog", "c.lookup.dog"),
+            cname("c.lookup.dog", "B.lookup.dog"),
+        ];
+
+        let problem = check(&response("a.lookup.dog", answers)).unwrap();
+        assert_eq!(problem, ChainProblem::Loop {
+            chain: vec![ "a.lookup.dog.".into(), "b.lookup.dog.".into(), "c.lookup.dog.".into(), "B.lookup.dog.".into() ],
+            loop_start: 1,
+        });
+        assert_eq!(problem.to_string(),
+                   "CNAME loop: a.lookup.dog. -> [b.lookup.dog.] -> c.lookup.dog. -> [B.lookup.dog.]");
+    }
+
+    #[test]
+    fn self_loop() {
+        let problem = check(&response("a.lookup.dog", vec![ cname("a.lookup.dog", "a.lookup.dog") ])).unwrap();
+        assert_eq!(problem.to_string(), "CNAME loop: [a.lookup.dog.] -> [a.lookup.dog.]");
+    }
+
+    #[test]
+    fn too_long() {
+        let answers = (0 .. 9)
+            .map(|n| cname(&format!("{}.lookup.dog", n), &format!("{}.lookup.dog", n + 1)))
+            .collect();
+
+        let problem = check(&response("0.lookup.dog", answers)).unwrap();
+        assert!(matches!(&problem, ChainProblem::TooLong { chain } if chain.len() == 10));
+        assert!(problem.to_string().starts_with("CNAME chain of 9 records is longer than resolvers follow (8 at most): 0.lookup.dog. -> "));
+    }
+
+    #[test]
+    fn longest_allowed() {
+        let answers = (0 .. 8)
+            .map(|n| cname(&format!("{}.lookup.dog", n), &format!("{}.lookup.dog", n + 1)))
+            .collect();
+
+        assert_eq!(check(&response("0.lookup.dog", answers)), None);
+    }
+}
@@ -11,7 +11,7 @@ pub enum TransportType {
     /// Send packets over UDP or TCP.
     /// UDP is used by default. If the request packet would be too large, send
     /// a TCP packet instead; if a UDP _response_ packet is truncated, try
-    /// again with TCP.
+    /// again with TCP, unless truncation is being ignored.
     Automatic,
 
     /// Send packets over UDP only.
@@ -27,20 +27,52 @@ pub enum TransportType {
 
     /// Send encrypted DNS-over-HTTPS packets.
     HTTPS,
+
+    /// Send encrypted DNS-over-HTTPS packets over HTTP/3, using QUIC.
+    HTTP3,
+
+    /// Send encrypted DNS-over-QUIC packets.
+    QUIC,
 }
 
 impl TransportType {
 
+    /// Whether this transport type encrypts the messages it sends.
+    pub fn is_encrypted(self) -> bool {
+        matches!(self, Self::TLS | Self::HTTPS | Self::HTTP3 | Self::QUIC)
+    }
+
+    /// Whether this transport type sends DNS messages down a TCP connection
+    /// that can be kept open between queries.
+    pub fn is_stream(self) -> bool {
+        matches!(self, Self::TCP | Self::TLS)
+    }
+
     /// Creates a boxed `Transport` depending on the transport type. The
-    /// parameter will be a URL for the HTTPS transport type, and a
-    /// stringified address for the others.
-    pub fn make_transport(self, param: String) -> Box<dyn Transport> {
+    /// parameter will be a URL for the HTTPS transport types, and a
+    /// stringified address for the others. The headers are only used by the
+    /// HTTPS transport types, and the checks by the ones that use UDP, as the
+    /// others leave checking responses to `check_response`. Whether to
+    /// ignore truncation only matters to the automatic transport type, and
+    /// the connect timeout to the ones that open connections. Every type
+    /// sends from the given source.
+    pub fn make_transport(self, param: String, http_headers: &HttpHeaders, checks: ResponseChecks, ignore_truncation: bool, timeouts: Timeouts, source: &Source) -> Box<dyn Transport> {
+        let source = source.clone();
         match self {
-            Self::Automatic  => Box::new(AutoTransport::new(param)),
-            Self::UDP        => Box::new(UdpTransport::new(param)),
-            Self::TCP        => Box::new(TcpTransport::new(param)),
-            Self::TLS        => Box::new(TlsTransport::new(param)),
-            Self::HTTPS      => Box::new(HttpsTransport::new(param)),
+            Self::Automatic  => Box::new(AutoTransport::new(param).with_checks(checks).ignore_truncation(ignore_truncation).with_timeouts(timeouts).with_source(source)),
+            Self::UDP        => {
+                let transport = match timeouts.read {
+                    Some(timeout)  => UdpTransport::with_timeout(param, timeout),
+                    None           => UdpTransport::new(param),
+                };
+
+                Box::new(transport.with_checks(checks).with_source(source))
+            }
+            Self::TCP        => Box::new(TcpTransport::new(param).with_timeouts(timeouts).with_source(source)),
+            Self::TLS        => Box::new(TlsTransport::new(param).with_timeouts(timeouts).with_source(source)),
+            Self::HTTPS      => Box::new(HttpsTransport::new(param, http_headers.clone()).with_timeouts(timeouts).with_source(source)),
+            Self::HTTP3      => Box::new(HttpsTransport::new(param, http_headers.clone()).with_http3().with_timeouts(timeouts).with_source(source)),
+            Self::QUIC       => Box::new(QuicTransport::new(param).with_timeouts(timeouts).with_source(source)),
         }
     }
 }
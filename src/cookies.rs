@@ -0,0 +1,204 @@
+//! Remembering the cookies that servers send back, so they can be sent
+//! with any further requests to the same server.
+
+use std::collections::HashMap;
+
+use dns::record::{Cookie, EdnsOption};
+use log::*;
+
+use crate::requests::PADDING_BLOCK_SIZE;
+
+
+/// A **cookie jar** holds the most recent server cookie from one server,
+/// alongside the client cookie it was issued for.
+#[derive(PartialEq, Debug, Default)]
+pub struct CookieJar {
+    cookie: Option<Cookie>,
+}
+
+impl CookieJar {
+
+    /// Stores the server cookie in a response, if it has one. Returns
+    /// whether a cookie was stored.
+    pub fn store(&mut self, response: &dns::Response) -> bool {
+        let server_cookie = response.edns_options().into_iter().find_map(|option| match option {
+            EdnsOption::Cookie(cookie) if ! cookie.server.is_empty() => Some(cookie),
+            _ => None,
+        });
+
+        match server_cookie {
+            Some(cookie) => {
+                debug!("Storing server cookie -> {:?}", cookie);
+                self.cookie = Some(cookie);
+                true
+            }
+            None => {
+                false
+            }
+        }
+    }
+
+    /// Adds the stored server cookie to a request, if there is one and the
+    /// request already carries the client cookie it was issued for.
+    pub fn attach(&self, request: &mut dns::Request) {
+        let (cookie, opt) = match (&self.cookie, &mut request.additional) {
+            (Some(cookie), Some(opt)) => (cookie, opt),
+            _ => return,
+        };
+
+        let sends_same_client = opt.options().unwrap_or_default().iter().any(|option| {
+            matches!(option, EdnsOption::Cookie(c) if c.client == cookie.client)
+        });
+
+        if ! sends_same_client {
+            return;
+        }
+
+        opt.replace_option(&EdnsOption::Cookie(cookie.clone()))
+           .expect("Failed to add cookie option");
+
+        // The cookie has changed the request’s length, so any padding has
+        // to be worked out again
+        let is_padded = opt.options().unwrap_or_default().iter().any(|option| matches!(option, EdnsOption::Padding(_)));
+        if is_padded {
+            request.pad_to_block(PADDING_BLOCK_SIZE).expect("Failed to pad request");
+        }
+    }
+}
+
+
+/// The **cookie jars** hold a cookie jar for each server, which last for
+/// the whole run, so a server cookie learnt from the response to one query
+/// gets sent with every later query to the same server.
+#[derive(PartialEq, Debug, Default)]
+pub struct CookieJars {
+    jars: HashMap<String, CookieJar>,
+}
+
+impl CookieJars {
+
+    /// Returns the cookie jar for the given nameserver, which starts off
+    /// empty the first time.
+    pub fn for_server(&mut self, nameserver: &str) -> &mut CookieJar {
+        self.jars.entry(nameserver.to_owned()).or_default()
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn request(client: [u8; 8]) -> dns::Request {
+        let mut opt = dns::Request::additional_record();
+        opt.push_option(&EdnsOption::Cookie(Cookie::new(client))).unwrap();
+
+        dns::Request {
+            transaction_id: 0x1234,
+            flags: dns::Flags::query(),
+            query: dns::Query {
+                qname: dns::Labels::encode("lookup.dog").unwrap(),
+                qtype: dns::record::RecordType::A,
+                qclass: dns::QClass::IN,
+            },
+            additional: Some(opt),
+        }
+    }
+
+    fn response(cookie: Cookie) -> dns::Response {
+        let mut opt = dns::Request::additional_record();
+        opt.push_option(&EdnsOption::Cookie(cookie)).unwrap();
+
+        dns::Response {
+            transaction_id: 0x1234,
+            flags: dns::Flags::query(),
+            queries: vec![],
+            answers: vec![],
+            authorities: vec![],
+            additionals: vec![
+                dns::Answer::Pseudo { qname: dns::Labels::root(), opt },
+            ],
+        }
+    }
+
+    fn sent_cookie(request: &dns::Request) -> Vec<EdnsOption> {
+        request.additional.as_ref().unwrap().options().unwrap()
+    }
+
+    #[test]
+    fn attaches_server_cookie() {
+        let server_cookie = Cookie { client: [ 0xaa; 8 ], server: vec![ 0xbb; 8 ] };
+
+        let mut jar = CookieJar::default();
+        assert!(jar.store(&response(server_cookie.clone())));
+
+        let mut request = request([ 0xaa; 8 ]);
+        jar.attach(&mut request);
+        assert_eq!(sent_cookie(&request),
+                   vec![ EdnsOption::Cookie(server_cookie) ]);
+    }
+
+    #[test]
+    fn pads_again() {
+        let mut jar = CookieJar::default();
+        jar.store(&response(Cookie { client: [ 0xaa; 8 ], server: vec![ 0xbb; 20 ] }));
+
+        let mut request = request([ 0xaa; 8 ]);
+        request.pad_to_block(PADDING_BLOCK_SIZE).unwrap();
+        jar.attach(&mut request);
+        assert_eq!(request.to_bytes().unwrap().len(), PADDING_BLOCK_SIZE);
+    }
+
+    #[test]
+    fn ignores_missing_server_cookie() {
+        let mut jar = CookieJar::default();
+        assert!(! jar.store(&response(Cookie::new([ 0xaa; 8 ]))));
+
+        let mut request = request([ 0xaa; 8 ]);
+        jar.attach(&mut request);
+        assert_eq!(sent_cookie(&request),
+                   vec![ EdnsOption::Cookie(Cookie::new([ 0xaa; 8 ])) ]);
+    }
+
+    #[test]
+    fn ignores_other_client() {
+        let mut jar = CookieJar::default();
+        jar.store(&response(Cookie { client: [ 0xaa; 8 ], server: vec![ 0xbb; 8 ] }));
+
+        let mut request = request([ 0xcc; 8 ]);
+        jar.attach(&mut request);
+        assert_eq!(sent_cookie(&request),
+                   vec![ EdnsOption::Cookie(Cookie::new([ 0xcc; 8 ])) ]);
+    }
+
+    #[test]
+    fn two_lists_to_the_same_server() {
+        let server_cookie = Cookie { client: [ 0xaa; 8 ], server: vec![ 0xbb; 8 ] };
+        let mut jars = CookieJars::default();
+
+        // The first list learns the server cookie...
+        let mut first = request([ 0xaa; 8 ]);
+        jars.for_server("192.0.2.1").attach(&mut first);
+        assert_eq!(sent_cookie(&first),
+                   vec![ EdnsOption::Cookie(Cookie::new([ 0xaa; 8 ])) ]);
+        jars.for_server("192.0.2.1").store(&response(server_cookie.clone()));
+
+        // ...and the second list to the same server sends it back
+        let mut second = request([ 0xaa; 8 ]);
+        jars.for_server("192.0.2.1").attach(&mut second);
+        assert_eq!(sent_cookie(&second),
+                   vec![ EdnsOption::Cookie(server_cookie) ]);
+    }
+
+    #[test]
+    fn other_servers_get_their_own_jar() {
+        let mut jars = CookieJars::default();
+        jars.for_server("192.0.2.1").store(&response(Cookie { client: [ 0xaa; 8 ], server: vec![ 0xbb; 8 ] }));
+
+        let mut request = request([ 0xaa; 8 ]);
+        jars.for_server("192.0.2.2").attach(&mut request);
+        assert_eq!(sent_cookie(&request),
+                   vec![ EdnsOption::Cookie(Cookie::new([ 0xaa; 8 ])) ]);
+    }
+}
@@ -0,0 +1,634 @@
+//! Checking the NSEC and NSEC3 records that come with a response saying
+//! that a name, or a type of record at a name, does not exist, to make sure
+//! that they actually prove it, including that there is no wildcard that
+//! could have matched the name instead.
+//!
+//! This only checks what the records say. Their signatures have to be
+//! verified separately before what they say can be trusted.
+//!
+//! # References
+//!
+//! - [RFC 4035 §5.4](https://tools.ietf.org/html/rfc4035#section-5.4) —
+//!   Protocol Modifications for the DNS Security Extensions (March 2005)
+//! - [RFC 5155 §8](https://tools.ietf.org/html/rfc5155#section-8) — DNS
+//!   Security (DNSSEC) Hashed Authenticated Denial of Existence (March 2008)
+
+use std::cmp::Ordering;
+use std::fmt;
+
+use dns::{Answer, Labels};
+use dns::dnssec::{nsec_covers, nsec3_covers, nsec3_hash};
+use dns::record::{Record, RecordType, NSEC, NSEC3};
+
+use crate::output::record_type_name;
+
+
+/// What a set of NSEC or NSEC3 records proves about a name.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum Denial {
+
+    /// The name does not exist, and neither does a wildcard that could
+    /// have matched it.
+    NoName,
+
+    /// The name exists, but has no records of the type.
+    NoType,
+
+    /// The name does not exist, and the wildcard that matches it has no
+    /// records of the type.
+    WildcardNoType,
+
+    /// The NSEC3 record that covers the name has the Opt-Out flag set, so
+    /// there could be an unsigned delegation there that the zone does not
+    /// prove anything about.
+    OptOut,
+
+    /// The NSEC3 records use a hash algorithm that dog cannot compute, so
+    /// they cannot prove anything either way.
+    UnsupportedHash(u8),
+}
+
+impl Denial {
+
+    /// Whether the records prove the denial, rather than leaving it
+    /// unproven in a way the zone is allowed to.
+    pub fn is_proven(self) -> bool {
+        matches!(self, Self::NoName | Self::NoType | Self::WildcardNoType)
+    }
+}
+
+impl fmt::Display for Denial {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoName              => write!(f, "the name does not exist"),
+            Self::NoType              => write!(f, "the name has no records of that type"),
+            Self::WildcardNoType      => write!(f, "the name does not exist, and the wildcard matching it has no records of that type"),
+            Self::OptOut              => write!(f, "the name is covered by an NSEC3 record that opts out of signing delegations"),
+            Self::UnsupportedHash(n)  => write!(f, "the NSEC3 records use unsupported hash algorithm {}", n),
+        }
+    }
+}
+
+
+/// Checks that the NSEC or NSEC3 records in a response’s authority section
+/// prove that the name does not exist, if `nxdomain` is set, or that it
+/// has no records of the given type otherwise.
+///
+/// # Errors
+///
+/// Returns the reason if the records do not prove it.
+pub fn check_denial(name: &Labels, qtype: RecordType, nxdomain: bool, authorities: &[Answer]) -> Result<Denial, String> {
+    let hashed = nsec3_records(authorities);
+    if ! hashed.is_empty() {
+        return nsec3_denial(name, qtype, nxdomain, &HashedRecords::new(&hashed));
+    }
+
+    let nsecs = nsec_records(authorities);
+    if ! nsecs.is_empty() {
+        return nsec_denial(name, qtype, nxdomain, &nsecs);
+    }
+
+    Err(format!("no NSEC or NSEC3 records prove that {} does not exist", name))
+}
+
+/// Checks that the NSEC or NSEC3 records in a response’s authority section
+/// prove that an answer synthesised from a wildcard was correct, because
+/// the name in the question does not exist, so there was no closer match
+/// than the wildcard. `labels` is the number of labels in the wildcard’s
+/// owner name without the ‘`*`’, as the signature over the answer gives.
+///
+/// # Errors
+///
+/// Returns the reason if the records do not prove it.
+pub fn check_wildcard_expansion(name: &Labels, labels: u8, authorities: &[Answer]) -> Result<Denial, String> {
+    let hashed = nsec3_records(authorities);
+    if ! hashed.is_empty() {
+        let records = HashedRecords::new(&hashed);
+        if let Some(algorithm) = records.unsupported_hash() {
+            return Ok(Denial::UnsupportedHash(algorithm));
+        }
+
+        let next_closer = name.ancestor(usize::from(labels) + 1);
+        return match records.covering(&next_closer)? {
+            Some(cover) if cover.opt_out()  => Ok(Denial::OptOut),
+            Some(_)                         => Ok(Denial::NoName),
+            None                            => Err(format!("no NSEC3 record proves that {} does not exist", next_closer)),
+        };
+    }
+
+    let nsecs = nsec_records(authorities);
+    if nsecs.iter().any(|(owner, nsec)| nsec_covers(owner, nsec, name)) {
+        return Ok(Denial::NoName);
+    }
+
+    Err(format!("{} was synthesised from a wildcard, but no NSEC or NSEC3 record proves that it does not exist", name))
+}
+
+
+/// Checks a denial using NSEC records.
+fn nsec_denial(name: &Labels, qtype: RecordType, nxdomain: bool, nsecs: &[(&Labels, &NSEC)]) -> Result<Denial, String> {
+    if let Some((_, nsec)) = nsecs.iter().find(|(owner, _)| same_name(owner, name)) {
+        if nxdomain {
+            return Err(format!("an NSEC record shows that {} exists", name));
+        }
+
+        return no_type("NSEC", name, qtype, &nsec.types).map(|()| Denial::NoType);
+    }
+
+    let (owner, nsec) = nsecs.iter()
+        .find(|(owner, nsec)| nsec_covers(owner, nsec, name))
+        .ok_or_else(|| format!("no NSEC record covers {}", name))?;
+
+    if is_delegation(&nsec.types) && name.is_within(owner) {
+        return Err(format!("{} is beneath the delegation at {}", name, owner));
+    }
+
+    // An empty non-terminal has no NSEC record of its own, but the next
+    // name after it is one of its descendants.
+    if ! nxdomain && nsec.next_domain.is_within(name) {
+        return Ok(Denial::NoType);
+    }
+
+    let wildcard = closest_encloser(name, owner, &nsec.next_domain).wildcard();
+
+    if let Some((_, nsec)) = nsecs.iter().find(|(owner, _)| same_name(owner, &wildcard)) {
+        if nxdomain {
+            return Err(format!("an NSEC record shows that the wildcard {} exists", wildcard));
+        }
+
+        return no_type("NSEC", &wildcard, qtype, &nsec.types).map(|()| Denial::WildcardNoType);
+    }
+
+    if ! nsecs.iter().any(|(owner, nsec)| nsec_covers(owner, nsec, &wildcard)) {
+        return Err(format!("no NSEC record proves that the wildcard {} does not exist", wildcard));
+    }
+
+    if nxdomain {
+        Ok(Denial::NoName)
+    }
+    else {
+        Err(format!("NSEC records prove that {} does not exist, but the response says it does", name))
+    }
+}
+
+/// Checks a denial using NSEC3 records.
+fn nsec3_denial(name: &Labels, qtype: RecordType, nxdomain: bool, records: &HashedRecords<'_>) -> Result<Denial, String> {
+    if let Some(algorithm) = records.unsupported_hash() {
+        return Ok(Denial::UnsupportedHash(algorithm));
+    }
+
+    if let Some(nsec3) = records.matching(name)? {
+        if nxdomain {
+            return Err(format!("an NSEC3 record shows that {} exists", name));
+        }
+
+        return no_type("NSEC3", name, qtype, &nsec3.types).map(|()| Denial::NoType);
+    }
+
+    let (encloser, cover) = records.closest_encloser(name)?;
+    let wildcard = encloser.wildcard();
+
+    if let Some(nsec3) = records.matching(&wildcard)? {
+        if nxdomain {
+            return Err(format!("an NSEC3 record shows that the wildcard {} exists", wildcard));
+        }
+
+        return no_type("NSEC3", &wildcard, qtype, &nsec3.types).map(|()| Denial::WildcardNoType);
+    }
+
+    if nxdomain {
+        if records.covering(&wildcard)?.is_none() {
+            return Err(format!("no NSEC3 record proves that the wildcard {} does not exist", wildcard));
+        }
+
+        Ok(if cover.opt_out() { Denial::OptOut } else { Denial::NoName })
+    }
+    else if cover.opt_out() {
+        Ok(Denial::OptOut)
+    }
+    else {
+        Err(format!("no NSEC3 record matches {}", name))
+    }
+}
+
+/// Checks that the types listed in an NSEC or NSEC3 record for a name show
+/// that it has no records of the type in the question, and that the name
+/// is not an alias or a delegation, which would have been followed instead.
+fn no_type(kind: &str, name: &Labels, qtype: RecordType, types: &[u16]) -> Result<(), String> {
+    let type_number = qtype.type_number();
+
+    if types.contains(&type_number) {
+        return Err(format!("an {} record shows that {} has {} records", kind, name, record_type_name(type_number)));
+    }
+
+    if types.contains(&RecordType::CNAME.type_number()) {
+        return Err(format!("an {} record shows that {} has a CNAME record", kind, name));
+    }
+
+    // Only the DS records for a delegation come from the parent zone.
+    if qtype != RecordType::DS && is_delegation(types) {
+        return Err(format!("the {} record for {} is from the parent side of a delegation", kind, name));
+    }
+
+    Ok(())
+}
+
+/// Whether the types show a delegation to a child zone: there are NS
+/// records, but no SOA record, as the apex of the zone itself would have.
+fn is_delegation(types: &[u16]) -> bool {
+    types.contains(&RecordType::NS.type_number()) && ! types.contains(&RecordType::SOA.type_number())
+}
+
+/// Works out the closest encloser of a name that an NSEC record covers,
+/// which is the longest ancestor that it shares with either the owner name
+/// or the next name, as both of those exist.
+fn closest_encloser(name: &Labels, owner: &Labels, next: &Labels) -> Labels {
+    let count = common_labels(name, owner).max(common_labels(name, next));
+    name.ancestor(count)
+}
+
+/// Returns how many labels at the end two names have in common.
+fn common_labels(one: &Labels, other: &Labels) -> usize {
+    (0 ..= one.len().min(other.len())).rev()
+        .find(|count| same_name(&one.ancestor(*count), &other.ancestor(*count)))
+        .unwrap_or(0)
+}
+
+/// Whether two names are the same, ignoring case.
+fn same_name(one: &Labels, other: &Labels) -> bool {
+    one.canonical_cmp(other) == Ordering::Equal
+}
+
+/// Returns the NSEC records in a section, with their owner names.
+fn nsec_records(answers: &[Answer]) -> Vec<(&Labels, &NSEC)> {
+    answers.iter()
+        .filter_map(|answer| match answer {
+            Answer::Standard { qname, record: Record::NSEC(nsec), .. }  => Some((qname, nsec)),
+            _                                                          => None,
+        })
+        .collect()
+}
+
+/// Returns the NSEC3 records in a section, with their owner names.
+fn nsec3_records(answers: &[Answer]) -> Vec<(&Labels, &NSEC3)> {
+    answers.iter()
+        .filter_map(|answer| match answer {
+            Answer::Standard { qname, record: Record::NSEC3(nsec3), .. }  => Some((qname, nsec3)),
+            _                                                            => None,
+        })
+        .collect()
+}
+
+
+/// The NSEC3 records for one zone, along with the hash from the first
+/// label of each one’s owner name.
+struct HashedRecords<'a> {
+
+    /// The zone the records are in.
+    zone: Labels,
+
+    /// The record whose hash parameters are used for hashing names.
+    params: &'a NSEC3,
+
+    /// Each record with the same zone and parameters, with its hash.
+    records: Vec<(String, &'a NSEC3)>,
+}
+
+impl<'a> HashedRecords<'a> {
+
+    /// Collects the records that are in the same zone, and use the same
+    /// parameters, as the first one. There has to be at least one record.
+    fn new(nsec3s: &[(&Labels, &'a NSEC3)]) -> Self {
+        let zone_of = |owner: &Labels| owner.ancestor(owner.len().saturating_sub(1));
+
+        let (first_owner, params) = nsec3s[0];
+        let zone = zone_of(first_owner);
+
+        let records = nsec3s.iter()
+            .filter(|(owner, _)| same_name(&zone_of(owner), &zone))
+            .filter(|(_, nsec3)| nsec3.hash_algorithm == params.hash_algorithm
+                                 && nsec3.iterations == params.iterations
+                                 && nsec3.salt == params.salt)
+            .map(|(owner, nsec3)| {
+                let owner = owner.to_string();
+                let hash = owner.split('.').next().unwrap_or_default().to_ascii_lowercase();
+                (hash, *nsec3)
+            })
+            .collect();
+
+        Self { zone, params, records }
+    }
+
+    /// Returns the hash algorithm if it is one that dog cannot compute.
+    fn unsupported_hash(&self) -> Option<u8> {
+        Some(self.params.hash_algorithm).filter(|algorithm| *algorithm != 1)
+    }
+
+    /// Hashes a name with the zone’s parameters.
+    fn hash(&self, name: &Labels) -> Result<String, String> {
+        nsec3_hash(name, self.params).map_err(|e| format!("could not hash {}: {}", name, e))
+    }
+
+    /// Returns the record whose owner name is the hash of the name.
+    fn matching(&self, name: &Labels) -> Result<Option<&'a NSEC3>, String> {
+        let hash = self.hash(name)?;
+        Ok(self.records.iter().find(|(owner_hash, _)| *owner_hash == hash).map(|(_, nsec3)| *nsec3))
+    }
+
+    /// Returns the record that covers the hash of the name.
+    fn covering(&self, name: &Labels) -> Result<Option<&'a NSEC3>, String> {
+        let hash = self.hash(name)?;
+        Ok(self.records.iter().find(|(owner_hash, nsec3)| nsec3_covers(owner_hash, nsec3, &hash)).map(|(_, nsec3)| *nsec3))
+    }
+
+    /// Finds the closest encloser of a name that does not exist, which is
+    /// its longest ancestor that has a matching record, checking that the
+    /// next closer name beneath it is covered by a record to show that the
+    /// encloser really is the closest. Returns the closest encloser along
+    /// with the record that covers the next closer name.
+    fn closest_encloser(&self, name: &Labels) -> Result<(Labels, &'a NSEC3), String> {
+        if ! name.is_within(&self.zone) {
+            return Err(format!("{} is not in the zone {} that the NSEC3 records are for", name, self.zone));
+        }
+
+        for count in (self.zone.len() .. name.len()).rev() {
+            let encloser = name.ancestor(count);
+
+            if let Some(nsec3) = self.matching(&encloser)? {
+                if is_delegation(&nsec3.types) {
+                    return Err(format!("{} is beneath the delegation at {}", name, encloser));
+                }
+
+                let next_closer = name.ancestor(count + 1);
+                return match self.covering(&next_closer)? {
+                    Some(cover)  => Ok((encloser, cover)),
+                    None         => Err(format!("no NSEC3 record covers {}", next_closer)),
+                };
+            }
+        }
+
+        Err(format!("no NSEC3 record proves the closest encloser of {}", name))
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::convert::TryFrom;
+    use dns::QClass;
+    use pretty_assertions::assert_eq;
+
+    const A: u16 = 1;
+    const NS: u16 = 2;
+    const CNAME: u16 = 5;
+    const SOA: u16 = 6;
+    const MX: u16 = 15;
+    const TXT: u16 = 16;
+
+    fn name(input: &str) -> Labels {
+        Labels::encode(input).unwrap()
+    }
+
+    fn nsec(owner: &str, next_domain: &str, types: &[u16]) -> Answer {
+        let record = Record::NSEC(NSEC { next_domain: name(next_domain), types: types.to_vec() });
+        Answer::Standard { qname: name(owner), qclass: QClass::IN, ttl: 300, record }
+    }
+
+    // The zone lookup.dog has the names lookup.dog, a.lookup.dog,
+    // c.lookup.dog, and y.x.lookup.dog, with x.lookup.dog being an empty
+    // non-terminal, and sub.lookup.dog is delegated.
+    fn nsec_zone() -> Vec<Answer> {
+        vec![
+            nsec("lookup.dog",       "a.lookup.dog",     &[ NS, SOA ]),
+            nsec("a.lookup.dog",     "c.lookup.dog",     &[ A ]),
+            nsec("c.lookup.dog",     "sub.lookup.dog",   &[ A, TXT ]),
+            nsec("sub.lookup.dog",   "y.x.lookup.dog",   &[ NS ]),
+            nsec("y.x.lookup.dog",   "lookup.dog",       &[ A ]),
+        ]
+    }
+
+    #[test]
+    fn nsec_no_name() {
+        assert_eq!(check_denial(&name("b.lookup.dog"), RecordType::A, true, &nsec_zone()),
+                   Ok(Denial::NoName));
+    }
+
+    #[test]
+    fn nsec_no_name_without_wildcard_proof() {
+        let authorities = vec![ nsec("a.lookup.dog", "c.lookup.dog", &[ A ]) ];
+        assert_eq!(check_denial(&name("b.lookup.dog"), RecordType::A, true, &authorities),
+                   Err(String::from("no NSEC record proves that the wildcard *.lookup.dog. does not exist")));
+    }
+
+    #[test]
+    fn nsec_no_name_but_it_exists() {
+        assert_eq!(check_denial(&name("a.lookup.dog"), RecordType::A, true, &nsec_zone()),
+                   Err(String::from("an NSEC record shows that a.lookup.dog. exists")));
+    }
+
+    #[test]
+    fn nsec_nothing_covering() {
+        let authorities = vec![ nsec("a.lookup.dog", "c.lookup.dog", &[ A ]) ];
+        assert_eq!(check_denial(&name("d.lookup.dog"), RecordType::A, true, &authorities),
+                   Err(String::from("no NSEC record covers d.lookup.dog.")));
+    }
+
+    #[test]
+    fn nsec_no_type() {
+        assert_eq!(check_denial(&name("c.lookup.dog"), RecordType::MX, false, &nsec_zone()),
+                   Ok(Denial::NoType));
+    }
+
+    #[test]
+    fn nsec_no_type_but_it_exists() {
+        assert_eq!(check_denial(&name("c.lookup.dog"), RecordType::TXT, false, &nsec_zone()),
+                   Err(String::from("an NSEC record shows that c.lookup.dog. has TXT records")));
+    }
+
+    #[test]
+    fn nsec_no_type_at_alias() {
+        let authorities = vec![ nsec("c.lookup.dog", "d.lookup.dog", &[ CNAME ]) ];
+        assert_eq!(check_denial(&name("c.lookup.dog"), RecordType::A, false, &authorities),
+                   Err(String::from("an NSEC record shows that c.lookup.dog. has a CNAME record")));
+    }
+
+    #[test]
+    fn nsec_empty_non_terminal() {
+        assert_eq!(check_denial(&name("x.lookup.dog"), RecordType::A, false, &nsec_zone()),
+                   Ok(Denial::NoType));
+    }
+
+    #[test]
+    fn nsec_no_ds_at_delegation() {
+        assert_eq!(check_denial(&name("sub.lookup.dog"), RecordType::DS, false, &nsec_zone()),
+                   Ok(Denial::NoType));
+    }
+
+    #[test]
+    fn nsec_no_type_from_parent_side() {
+        assert_eq!(check_denial(&name("sub.lookup.dog"), RecordType::A, false, &nsec_zone()),
+                   Err(String::from("the NSEC record for sub.lookup.dog. is from the parent side of a delegation")));
+    }
+
+    #[test]
+    fn nsec_beneath_delegation() {
+        assert_eq!(check_denial(&name("www.sub.lookup.dog"), RecordType::A, true, &nsec_zone()),
+                   Err(String::from("www.sub.lookup.dog. is beneath the delegation at sub.lookup.dog.")));
+    }
+
+    #[test]
+    fn nsec_wildcard_no_type() {
+        let mut authorities = nsec_zone();
+        authorities.push(nsec("*.lookup.dog", "a.lookup.dog", &[ TXT ]));
+
+        assert_eq!(check_denial(&name("b.lookup.dog"), RecordType::A, false, &authorities),
+                   Ok(Denial::WildcardNoType));
+        assert_eq!(check_denial(&name("b.lookup.dog"), RecordType::A, true, &authorities),
+                   Err(String::from("an NSEC record shows that the wildcard *.lookup.dog. exists")));
+    }
+
+    #[test]
+    fn nsec_wildcard_expansion() {
+        assert_eq!(check_wildcard_expansion(&name("b.lookup.dog"), 2, &nsec_zone()),
+                   Ok(Denial::NoName));
+        assert_eq!(check_wildcard_expansion(&name("c.lookup.dog"), 2, &nsec_zone()),
+                   Err(String::from("c.lookup.dog. was synthesised from a wildcard, but no NSEC or NSEC3 record proves that it does not exist")));
+    }
+
+    #[test]
+    fn no_proof() {
+        assert_eq!(check_denial(&name("b.lookup.dog"), RecordType::A, true, &[]),
+                   Err(String::from("no NSEC or NSEC3 records prove that b.lookup.dog. does not exist")));
+    }
+
+    /// Decodes base32 with the extended hex alphabet.
+    fn base32hex(input: &str) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let mut buffer = 0_u32;
+        let mut bits = 0;
+
+        for c in input.chars() {
+            buffer = (buffer << 5) | c.to_digit(32).unwrap();
+            bits += 5;
+
+            if bits >= 8 {
+                bits -= 8;
+                bytes.push(u8::try_from((buffer >> bits) & 0xff).unwrap());
+            }
+        }
+
+        bytes
+    }
+
+    /// Builds the NSEC3 records for a zone with the given names, using the
+    /// parameters from the example zone in RFC 5155.
+    fn nsec3_zone(names: &[(&str, &[u16])], flags: u8) -> Vec<Answer> {
+        let params = NSEC3 { hash_algorithm: 1, flags, iterations: 12, salt: vec![ 0xaa, 0xbb, 0xcc, 0xdd ], next_hashed_owner: Vec::new(), types: Vec::new() };
+
+        let mut hashes = names.iter()
+            .map(|(owner, types)| (nsec3_hash(&name(owner), &params).unwrap(), types.to_vec()))
+            .collect::<Vec<_>>();
+        hashes.sort();
+
+        (0 .. hashes.len())
+            .map(|index| {
+                let (hash, types) = hashes[index].clone();
+                let next = &hashes[(index + 1) % hashes.len()].0;
+                let record = Record::NSEC3(NSEC3 { next_hashed_owner: base32hex(next), types, .. params.clone() });
+                Answer::Standard { qname: name(&format!("{}.example", hash)), qclass: QClass::IN, ttl: 300, record }
+            })
+            .collect()
+    }
+
+    fn example_zone() -> Vec<Answer> {
+        nsec3_zone(&[
+            ("example",        &[ NS, SOA ]),
+            ("a.example",      &[ A ]),
+            ("w.example",      &[]),
+            ("*.w.example",    &[ TXT ]),
+            ("x.w.example",    &[ MX ]),
+            ("xx.example",     &[ A ]),
+        ], 0)
+    }
+
+    #[test]
+    fn nsec3_no_name() {
+        assert_eq!(check_denial(&name("b.example"), RecordType::A, true, &example_zone()),
+                   Ok(Denial::NoName));
+        assert_eq!(check_denial(&name("c.b.example"), RecordType::A, true, &example_zone()),
+                   Ok(Denial::NoName));
+    }
+
+    #[test]
+    fn nsec3_no_name_but_it_exists() {
+        assert_eq!(check_denial(&name("A.Example"), RecordType::A, true, &example_zone()),
+                   Err(String::from("an NSEC3 record shows that A.Example. exists")));
+    }
+
+    #[test]
+    fn nsec3_no_type() {
+        assert_eq!(check_denial(&name("a.example"), RecordType::MX, false, &example_zone()),
+                   Ok(Denial::NoType));
+        assert_eq!(check_denial(&name("w.example"), RecordType::A, false, &example_zone()),
+                   Ok(Denial::NoType));
+    }
+
+    #[test]
+    fn nsec3_no_type_but_it_exists() {
+        assert_eq!(check_denial(&name("x.w.example"), RecordType::MX, false, &example_zone()),
+                   Err(String::from("an NSEC3 record shows that x.w.example. has MX records")));
+    }
+
+    #[test]
+    fn nsec3_wildcard_no_type() {
+        assert_eq!(check_denial(&name("y.w.example"), RecordType::A, false, &example_zone()),
+                   Ok(Denial::WildcardNoType));
+        assert_eq!(check_denial(&name("y.w.example"), RecordType::A, true, &example_zone()),
+                   Err(String::from("an NSEC3 record shows that the wildcard *.w.example. exists")));
+    }
+
+    #[test]
+    fn nsec3_no_closest_encloser() {
+        let authorities = nsec3_zone(&[ ("a.example", &[ A ]), ("xx.example", &[ A ]) ], 0);
+        assert_eq!(check_denial(&name("b.example"), RecordType::A, true, &authorities),
+                   Err(String::from("no NSEC3 record proves the closest encloser of b.example.")));
+    }
+
+    #[test]
+    fn nsec3_outside_zone() {
+        assert_eq!(check_denial(&name("lookup.dog"), RecordType::A, true, &example_zone()),
+                   Err(String::from("lookup.dog. is not in the zone example. that the NSEC3 records are for")));
+    }
+
+    #[test]
+    fn nsec3_opt_out() {
+        let authorities = nsec3_zone(&[ ("example", &[ NS, SOA ]), ("a.example", &[ A ]) ], 1);
+        assert_eq!(check_denial(&name("b.example"), RecordType::DS, false, &authorities),
+                   Ok(Denial::OptOut));
+        assert!(! Denial::OptOut.is_proven());
+    }
+
+    #[test]
+    fn nsec3_unsupported_hash() {
+        let authorities = example_zone().into_iter()
+            .map(|answer| match answer {
+                Answer::Standard { qname, qclass, ttl, record: Record::NSEC3(nsec3) } => {
+                    Answer::Standard { qname, qclass, ttl, record: Record::NSEC3(NSEC3 { hash_algorithm: 7, .. nsec3 }) }
+                }
+                other => other,
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(check_denial(&name("b.example"), RecordType::A, true, &authorities),
+                   Ok(Denial::UnsupportedHash(7)));
+    }
+
+    #[test]
+    fn nsec3_wildcard_expansion() {
+        assert_eq!(check_wildcard_expansion(&name("y.w.example"), 2, &example_zone()),
+                   Ok(Denial::NoName));
+        assert_eq!(check_wildcard_expansion(&name("x.w.example"), 2, &example_zone()),
+                   Err(String::from("no NSEC3 record proves that x.w.example. does not exist")));
+    }
+}
@@ -0,0 +1,316 @@
+//! Retrying UDP queries with smaller EDNS payload sizes, for when large
+//! responses get truncated or lost on the way back, which is what happens
+//! when a network drops fragmented packets.
+
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::io;
+use std::time::Duration;
+
+use dns_transport::{Transport, AutoTransport, UdpTransport, TcpTransport, Error, ResponseChecks, Mismatch, Timeouts, Source};
+use log::*;
+
+
+/// The payload size recommended by DNS Flag Day 2020, which avoids
+/// fragmentation on nearly every network.
+const FLAG_DAY_SIZE: u16 = 1232;
+
+/// The largest payload size every DNS server has to support.
+const MINIMUM_SIZE: u16 = 512;
+
+/// How long to wait for each UDP response before trying a smaller size, if
+/// no timeout has been given.
+const UDP_TIMEOUT: Duration = Duration::from_secs(3);
+
+
+/// The **downgrading transport** sends a request over UDP with the payload
+/// size the user asked for, and if the response comes back truncated or
+/// not at all, tries again with smaller payload sizes, and then with TCP if
+/// it is allowed to. It prints a note saying which size worked in the end.
+pub struct DowngradingTransport {
+    addr: String,
+    fall_back_to_tcp: bool,
+    checks: ResponseChecks,
+    ignore_truncation: bool,
+    timeouts: Timeouts,
+    source: Source,
+    ignored: Cell<usize>,
+    fallbacks: Cell<usize>,
+    mismatches: RefCell<Vec<Mismatch>>,
+}
+
+impl DowngradingTransport {
+
+    /// Creates a new downgrading transport that connects to the given host,
+    /// which switches to TCP once every payload size has failed if
+    /// `fall_back_to_tcp` is set.
+    pub fn new(addr: String, fall_back_to_tcp: bool) -> Self {
+        Self {
+            addr,
+            fall_back_to_tcp,
+            checks: ResponseChecks::Strict,
+            ignore_truncation: false,
+            timeouts: Timeouts::default(),
+            source: Source::default(),
+            ignored: Cell::new(0),
+            fallbacks: Cell::new(0),
+            mismatches: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Sets how closely a UDP packet has to match the query to be taken as
+    /// its response.
+    pub fn with_checks(mut self, checks: ResponseChecks) -> Self {
+        self.checks = checks;
+        self
+    }
+
+    /// Sets whether a response that is still truncated at the smallest
+    /// payload size gets returned as it is, rather than the query being
+    /// sent again over TCP.
+    pub fn ignore_truncation(mut self, ignore_truncation: bool) -> Self {
+        self.ignore_truncation = ignore_truncation;
+        self
+    }
+
+    /// Sets how long to wait for each response before trying a smaller
+    /// size, and how long to wait to connect over TCP.
+    pub fn with_timeouts(mut self, timeouts: Timeouts) -> Self {
+        self.timeouts = timeouts;
+        self
+    }
+
+    /// Sets the local address and interface to send queries from.
+    pub fn with_source(mut self, source: Source) -> Self {
+        self.source = source;
+        self
+    }
+}
+
+impl Transport for DowngradingTransport {
+    fn send(&self, request: &dns::Request) -> Result<dns::Response, Error> {
+        let requested_size = request.additional.as_ref().map_or(MINIMUM_SIZE, |opt| opt.udp_payload_size);
+        let mut report = Downgrade { failures: Vec::new(), success: None };
+        let mut last_result = None;
+
+        for size in payload_sizes(requested_size) {
+            let mut request = request.clone();
+            if let Some(opt) = &mut request.additional {
+                opt.udp_payload_size = size;
+            }
+
+            info!("Trying UDP with a payload size of {}", size);
+            let udp_transport = UdpTransport::with_timeout(self.addr.clone(), self.timeouts.read.unwrap_or(UDP_TIMEOUT)).with_checks(self.checks).with_source(self.source.clone());
+            let result = udp_transport.send(&request);
+            self.ignored.set(self.ignored.get() + udp_transport.ignored_packets());
+            self.mismatches.borrow_mut().extend(udp_transport.take_mismatches());
+
+            match result {
+                Ok(response) if ! response.flags.truncated => {
+                    report.success = Some(Success::Udp(size));
+                    report.print_if_downgraded();
+                    return Ok(response);
+                }
+                Ok(response) => {
+                    report.failures.push((size, Failure::Truncated));
+                    last_result = Some(Ok(response));
+                }
+                Err(Error::NetworkError(e)) if is_timeout(&e) => {
+                    report.failures.push((size, Failure::TimedOut));
+                    last_result = Some(Err(Error::NetworkError(e)));
+                }
+                Err(e) => {
+                    return Err(e);
+                }
+            }
+        }
+
+        let truncated = matches!(&last_result, Some(Ok(response)) if response.flags.truncated);
+        if ! self.fall_back_to_tcp || (truncated && self.ignore_truncation) {
+            report.print_if_downgraded();
+            return last_result.expect("no payload sizes");
+        }
+
+        debug!("Every payload size failed, so switching to TCP");
+        if truncated {
+            self.fallbacks.set(self.fallbacks.get() + 1);
+        }
+
+        let tcp_transport = TcpTransport::new(self.addr.clone()).with_timeouts(self.timeouts).with_source(self.source.clone());
+        let response = tcp_transport.send(request)?;
+        report.success = Some(Success::Tcp);
+        report.print_if_downgraded();
+        Ok(response)
+    }
+
+    fn send_raw(&self, bytes_to_send: &[u8]) -> Result<Vec<u8>, Error> {
+        let transport: Box<dyn Transport> = if self.fall_back_to_tcp {
+            Box::new(AutoTransport::new(self.addr.clone()).with_checks(self.checks).ignore_truncation(self.ignore_truncation).with_timeouts(self.timeouts).with_source(self.source.clone()))
+        }
+        else {
+            let udp_transport = match self.timeouts.read {
+                Some(timeout)  => UdpTransport::with_timeout(self.addr.clone(), timeout),
+                None           => UdpTransport::new(self.addr.clone()),
+            };
+
+            Box::new(udp_transport.with_checks(self.checks).with_source(self.source.clone()))
+        };
+
+        let result = transport.send_raw(bytes_to_send);
+        self.ignored.set(self.ignored.get() + transport.ignored_packets());
+        self.fallbacks.set(self.fallbacks.get() + transport.tcp_fallbacks());
+        self.mismatches.borrow_mut().extend(transport.take_mismatches());
+        result
+    }
+
+    fn ignored_packets(&self) -> usize {
+        self.ignored.get()
+    }
+
+    fn tcp_fallbacks(&self) -> usize {
+        self.fallbacks.get()
+    }
+
+    fn take_mismatches(&self) -> Vec<Mismatch> {
+        self.mismatches.take()
+    }
+}
+
+/// Returns the payload sizes to try in turn, starting with the one that
+/// was asked for, and then each smaller standard size.
+fn payload_sizes(requested_size: u16) -> Vec<u16> {
+    let mut sizes = vec![ requested_size ];
+
+    for size in [ FLAG_DAY_SIZE, MINIMUM_SIZE ] {
+        if size < requested_size {
+            sizes.push(size);
+        }
+    }
+
+    sizes
+}
+
+/// Whether an I/O error came from a read timing out, which is reported
+/// differently on different platforms.
+fn is_timeout(error: &io::Error) -> bool {
+    matches!(error.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut)
+}
+
+
+/// What happened while downgrading the payload size of one request.
+#[derive(PartialEq, Debug)]
+struct Downgrade {
+
+    /// Each payload size that did not work, and why.
+    failures: Vec<(u16, Failure)>,
+
+    /// What worked in the end, if anything did.
+    success: Option<Success>,
+}
+
+/// Why a payload size did not work.
+#[derive(PartialEq, Debug, Copy, Clone)]
+enum Failure {
+
+    /// The response had the truncated flag set.
+    Truncated,
+
+    /// No response arrived in time.
+    TimedOut,
+}
+
+/// How a response eventually arrived.
+#[derive(PartialEq, Debug, Copy, Clone)]
+enum Success {
+
+    /// Over UDP, with this payload size.
+    Udp(u16),
+
+    /// Over TCP.
+    Tcp,
+}
+
+impl Downgrade {
+
+    /// Prints a note to standard error if any payload size failed.
+    fn print_if_downgraded(&self) {
+        if ! self.failures.is_empty() {
+            eprintln!("note: {}", self);
+        }
+    }
+}
+
+impl fmt::Display for Downgrade {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "UDP payload size")?;
+
+        for (index, (size, failure)) in self.failures.iter().enumerate() {
+            let separator = if index == 0 { "" } else { "," };
+            match failure {
+                Failure::Truncated  => write!(f, "{} {} was truncated", separator, size)?,
+                Failure::TimedOut   => write!(f, "{} {} timed out", separator, size)?,
+            }
+        }
+
+        match self.success {
+            Some(Success::Udp(size))  => write!(f, "; succeeded with {}", size),
+            Some(Success::Tcp)        => write!(f, "; succeeded over TCP"),
+            None                      => write!(f, "; gave up"),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn sizes_from_large() {
+        assert_eq!(payload_sizes(4096), vec![ 4096, 1232, 512 ]);
+    }
+
+    #[test]
+    fn sizes_from_flag_day() {
+        assert_eq!(payload_sizes(1232), vec![ 1232, 512 ]);
+    }
+
+    #[test]
+    fn sizes_from_small() {
+        assert_eq!(payload_sizes(800), vec![ 800, 512 ]);
+    }
+
+    #[test]
+    fn report_udp() {
+        let report = Downgrade {
+            failures: vec![ (4096, Failure::TimedOut), (1232, Failure::Truncated) ],
+            success: Some(Success::Udp(512)),
+        };
+
+        assert_eq!(report.to_string(),
+                   "UDP payload size 4096 timed out, 1232 was truncated; succeeded with 512");
+    }
+
+    #[test]
+    fn report_tcp() {
+        let report = Downgrade {
+            failures: vec![ (1232, Failure::Truncated), (512, Failure::Truncated) ],
+            success: Some(Success::Tcp),
+        };
+
+        assert_eq!(report.to_string(),
+                   "UDP payload size 1232 was truncated, 512 was truncated; succeeded over TCP");
+    }
+
+    #[test]
+    fn report_gave_up() {
+        let report = Downgrade {
+            failures: vec![ (512, Failure::TimedOut) ],
+            success: None,
+        };
+
+        assert_eq!(report.to_string(),
+                   "UDP payload size 512 timed out; gave up");
+    }
+}
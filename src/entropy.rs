@@ -0,0 +1,282 @@
+//! Auditing the randomness of the transaction IDs and local source ports
+//! used by outgoing queries.
+//!
+//! The ports are the ones the operating system picked for each socket. A
+//! NAT device between here and the nameserver can rewrite them to ports of
+//! its own, and nothing in a normal response says which ports those were,
+//! so any randomness lost that way does not show up in the report.
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::time::Duration;
+
+use dns_transport::Source;
+use log::*;
+
+use crate::requests::RequestGenerator;
+use crate::resolve::ResolverLookupError;
+
+
+/// How long to wait for each response before counting the query as failed.
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+
+/// The results of sending a number of queries and measuring how much the
+/// source port and transaction ID varied between them.
+#[derive(PartialEq, Debug)]
+pub struct EntropyReport {
+
+    /// The number of queries that were attempted.
+    pub sent: usize,
+
+    /// The number of queries whose source port and transaction ID made it
+    /// on to the wire, and so were measured.
+    pub measured: usize,
+
+    /// The number of queries that did not receive a response.
+    pub unanswered: usize,
+
+    /// The number of responses that did not echo the transaction ID back.
+    pub mismatched: usize,
+
+    /// The distribution of the local source ports, before any NAT device
+    /// has had a chance to rewrite them.
+    pub ports: Distribution,
+
+    /// The distribution of the transaction IDs.
+    pub txids: Distribution,
+}
+
+/// A summary of a set of 16-bit values.
+#[derive(PartialEq, Debug, Default)]
+pub struct Distribution {
+
+    /// The number of distinct values that were seen.
+    pub unique: usize,
+
+    /// The lowest value that was seen.
+    pub min: u16,
+
+    /// The highest value that was seen.
+    pub max: u16,
+
+    /// The Shannon entropy of the observed values, in bits.
+    pub entropy_bits: f64,
+}
+
+
+/// Sends the given number of queries over UDP, using the first domain, type,
+/// class, and nameserver in the inputs, and records the local source port
+/// and transaction ID of each one. Queries that go unanswered are still
+/// measured, as the values were still put on the wire.
+pub fn run_check(requests: RequestGenerator, count: usize) -> Result<EntropyReport, ResolverLookupError> {
+    let resolver = requests.inputs.resolver_types.into_iter().next()
+        .expect("no resolver types")
+        .obtain()?;
+    let nameserver = resolver.nameserver();
+
+    let qname = requests.inputs.domains[0].clone();
+    let qtype = requests.inputs.record_types[0];
+    let qclass = requests.inputs.classes[0];
+
+    let mut ports = Vec::with_capacity(count);
+    let mut txids = Vec::with_capacity(count);
+    let mut unanswered = 0;
+    let mut mismatched = 0;
+
+    for _ in 0 .. count {
+        let transaction_id = requests.txid_generator.generate();
+        let mut flags = dns::Flags::query();
+        requests.protocol_tweaks.set_request_flags(&mut flags);
+
+        let mut additional = None;
+        if requests.edns.should_send() {
+            let mut opt = dns::Request::additional_record();
+            requests.protocol_tweaks.set_request_opt_fields(&mut opt);
+            additional = Some(opt);
+        }
+
+        let query = dns::Query { qname: qname.clone(), qtype, qclass };
+        let request = dns::Request { transaction_id, flags, query, additional };
+
+        match send_and_measure(&request, &nameserver, &requests.source) {
+            Ok((port, response_txid)) => {
+                ports.push(port);
+                txids.push(transaction_id);
+
+                match response_txid {
+                    Some(id) if id == transaction_id => {},
+                    Some(id) => {
+                        warn!("Response had transaction ID {:?}, expected {:?}", id, transaction_id);
+                        mismatched += 1;
+                    }
+                    None => {
+                        unanswered += 1;
+                    }
+                }
+            }
+            Err(e) => {
+                warn!("Failed to send query: {}", e);
+                unanswered += 1;
+            }
+        }
+    }
+
+    Ok(EntropyReport {
+        sent: count,
+        measured: ports.len(),
+        unanswered,
+        mismatched,
+        ports: Distribution::of(&ports),
+        txids: Distribution::of(&txids),
+    })
+}
+
+/// Sends one request from a fresh socket bound to the source, as the UDP
+/// transport does, and returns the local port it was sent from, along with
+/// the transaction ID of the response if one arrived.
+fn send_and_measure(request: &dns::Request, nameserver: &str, source: &Source) -> io::Result<(u16, Option<u16>)> {
+    let server = server_address(nameserver, source)?;
+    let socket = source.udp_socket(server)?;
+    socket.set_read_timeout(Some(RESPONSE_TIMEOUT))?;
+    socket.connect(server)?;
+
+    let port = socket.local_addr()?.port();
+    debug!("Sending query {:?} from port {:?}", request.transaction_id, port);
+
+    let bytes_to_send = request.to_bytes().expect("failed to serialise request");
+    socket.send(&bytes_to_send)?;
+
+    let mut buf = vec![0; 4096];
+    let response_txid = match socket.recv(&mut buf) {
+        Ok(received_len) => {
+            dns::Response::from_bytes(&buf[.. received_len]).ok().map(|r| r.transaction_id)
+        }
+        Err(e) => {
+            warn!("No response received: {}", e);
+            None
+        }
+    };
+
+    Ok((port, response_txid))
+}
+
+/// Looks up the address to send queries to, using port 53 if the
+/// nameserver did not say which one, and skipping any address that cannot
+/// be reached from the source.
+fn server_address(nameserver: &str, source: &Source) -> io::Result<SocketAddr> {
+    let mut addresses = if nameserver.contains(':') {
+        nameserver.to_socket_addrs()?
+    }
+    else {
+        (nameserver, 53).to_socket_addrs()?
+    };
+
+    addresses.find(|address| source.reaches(address))
+             .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "nameserver has no usable addresses"))
+}
+
+
+impl Distribution {
+
+    /// Summarises the given list of values.
+    fn of(values: &[u16]) -> Self {
+        if values.is_empty() {
+            return Self::default();
+        }
+
+        let mut counts = HashMap::new();
+        for value in values {
+            *counts.entry(value).or_insert(0_u32) += 1;
+        }
+
+        let total = as_float(values.len());
+        let entropy_bits = counts.values()
+            .map(|count| {
+                let probability = f64::from(*count) / total;
+                -probability * probability.log2()
+            })
+            .sum::<f64>()
+            .abs();
+
+        Self {
+            unique: counts.len(),
+            min: values.iter().copied().min().unwrap_or_default(),
+            max: values.iter().copied().max().unwrap_or_default(),
+            entropy_bits,
+        }
+    }
+}
+
+impl EntropyReport {
+
+    /// The most entropy that could possibly have been observed with this
+    /// many samples, in bits, which happens when every value is different.
+    fn max_entropy_bits(&self) -> f64 {
+        if self.measured == 0 {
+            0.0
+        }
+        else {
+            as_float(self.measured).log2()
+        }
+    }
+}
+
+/// Converts a count to a float, saturating at counts that would never be
+/// reached in practice.
+fn as_float(count: usize) -> f64 {
+    f64::from(u32::try_from(count).unwrap_or(u32::MAX))
+}
+
+impl fmt::Display for EntropyReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let max_bits = self.max_entropy_bits();
+
+        writeln!(f, "Sent {} queries ({} unanswered, {} with mismatched IDs)", self.sent, self.unanswered, self.mismatched)?;
+
+        for (name, dist) in &[ ("Local source ports", &self.ports), ("Transaction IDs", &self.txids) ] {
+            writeln!(f, "{}: {} unique, range {}–{}, {:.2} of {:.2} bits of entropy",
+                name, dist.unique, dist.min, dist.max, dist.entropy_bits, max_bits)?;
+        }
+
+        if self.measured > 1 && self.ports.unique == 1 {
+            writeln!(f, "warning: every query was sent from the same local port")?;
+        }
+
+        if self.measured > 1 && self.txids.unique == 1 {
+            writeln!(f, "warning: every query used the same transaction ID")?;
+        }
+
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn all_different() {
+        let dist = Distribution::of(&[ 1, 2, 3, 4 ]);
+        assert_eq!(dist.unique, 4);
+        assert_eq!((dist.min, dist.max), (1, 4));
+        assert!((dist.entropy_bits - 2.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn all_the_same() {
+        let dist = Distribution::of(&[ 53, 53, 53 ]);
+        assert_eq!(dist.unique, 1);
+        assert!(dist.entropy_bits.abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn nothing() {
+        assert_eq!(Distribution::of(&[]), Distribution::default());
+    }
+}
@@ -0,0 +1,272 @@
+//! Expanding patterns with brace ranges and lists in them, such as
+//! ‘`node{1..50}.example.net`’, into the sequence of names they stand for,
+//! so a batch of similar names can be looked up in one go.
+//!
+//! A pair of braces holds either a range, written ‘`{1..50}`’, or a list of
+//! alternatives separated by commas, written ‘`{www,mail}`’. Ranges can be
+//! of numbers or of single letters, can count downwards, and can have a
+//! step, as in ‘`{0..100..10}`’. If either end of a number range has a
+//! leading zero, every number is padded to the same width, as in
+//! ‘`{01..10}`’. A pattern with several pairs of braces expands to every
+//! combination of them, with the first pair changing the slowest, the same
+//! as in a shell.
+
+use std::convert::TryFrom;
+use std::fmt;
+
+
+/// The most names that a pattern is allowed to expand to, to stop a typo
+/// from sending millions of queries.
+pub const MAX_NAMES: usize = 10_000;
+
+
+/// Expands the pattern into the list of names it stands for.
+///
+/// # Errors
+///
+/// Returns an error if the braces are unbalanced or nested, if something in
+/// braces is neither a range nor a list, or if the pattern expands to more
+/// than `MAX_NAMES` names.
+pub fn expand(pattern: &str) -> Result<Vec<String>, ExpandError> {
+    let parts = parse(pattern)?;
+
+    let fits = parts.iter()
+        .try_fold(1_usize, |count, part| count.checked_mul(part.len()))
+        .map_or(false, |count| count <= MAX_NAMES);
+
+    if ! fits {
+        return Err(ExpandError::TooManyNames);
+    }
+
+    let mut names = vec![ String::new() ];
+    for part in &parts {
+        names = names.iter()
+            .flat_map(|prefix| part.iter().map(move |piece| format!("{}{}", prefix, piece)))
+            .collect();
+    }
+
+    Ok(names)
+}
+
+
+/// Something wrong with a pattern.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum ExpandError {
+
+    /// There is an opening brace without a closing one, or the other way
+    /// around.
+    UnbalancedBraces,
+
+    /// There is a pair of braces inside another pair.
+    NestedBraces,
+
+    /// The text in a pair of braces is neither a range nor a list.
+    NotRangeOrList,
+
+    /// A range has a step of zero.
+    ZeroStep,
+
+    /// The pattern expands to more than `MAX_NAMES` names.
+    TooManyNames,
+}
+
+impl fmt::Display for ExpandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnbalancedBraces  => write!(f, "unbalanced braces"),
+            Self::NestedBraces      => write!(f, "braces cannot be nested"),
+            Self::NotRangeOrList    => write!(f, "braces must hold a range or a comma-separated list"),
+            Self::ZeroStep          => write!(f, "the step of a range cannot be zero"),
+            Self::TooManyNames      => write!(f, "expands to more than {} names", MAX_NAMES),
+        }
+    }
+}
+
+
+/// Splits the pattern into the alternatives for each part of it: a
+/// single piece of text for each part outside braces, and the expansion
+/// of what is inside for each part in them.
+fn parse(pattern: &str) -> Result<Vec<Vec<String>>, ExpandError> {
+    let mut parts = Vec::new();
+    let mut rest = pattern;
+
+    while let Some(open) = rest.find(['{', '}']) {
+        if rest[open ..].starts_with('}') {
+            return Err(ExpandError::UnbalancedBraces);
+        }
+
+        let close = rest[open ..].find('}').ok_or(ExpandError::UnbalancedBraces)? + open;
+        let inside = &rest[open + 1 .. close];
+        if inside.contains('{') {
+            return Err(ExpandError::NestedBraces);
+        }
+
+        parts.push(vec![ rest[.. open].to_owned() ]);
+        parts.push(expand_braces(inside)?);
+        rest = &rest[close + 1 ..];
+    }
+
+    parts.push(vec![ rest.to_owned() ]);
+    Ok(parts)
+}
+
+/// Expands the text inside a pair of braces.
+fn expand_braces(inside: &str) -> Result<Vec<String>, ExpandError> {
+    if let Some(range) = expand_range(inside) {
+        return range;
+    }
+
+    if inside.contains(',') {
+        return Ok(inside.split(',').map(str::to_owned).collect());
+    }
+
+    Err(ExpandError::NotRangeOrList)
+}
+
+/// Expands a range of numbers or letters, returning `None` if the text is
+/// not a range at all.
+fn expand_range(inside: &str) -> Option<Result<Vec<String>, ExpandError>> {
+    let fields = inside.split("..").collect::<Vec<_>>();
+    let (start, end, step) = match fields[..] {
+        [ start, end ]        => (start, end, 1),
+        [ start, end, step ]  => (start, end, step.parse::<u64>().ok()?),
+        _                     => return None,
+    };
+
+    if step == 0 {
+        return Some(Err(ExpandError::ZeroStep));
+    }
+
+    if let (Ok(first), Ok(last)) = (start.parse::<u64>(), end.parse::<u64>()) {
+        let padded = [ start, end ].iter().any(|n| n.len() > 1 && n.starts_with('0'));
+        let width = if padded { start.len().max(end.len()) } else { 0 };
+
+        let numbers = stepped(first, last, step)?;
+        return Some(numbers.map(|numbers| numbers.into_iter().map(|n| format!("{:0width$}", n, width = width)).collect()));
+    }
+
+    let (first, last) = (single_letter(start)?, single_letter(end)?);
+    if first.is_ascii_lowercase() != last.is_ascii_lowercase() {
+        return None;
+    }
+
+    let letters = stepped(u64::from(first), u64::from(last), step)?;
+    Some(letters.map(|letters| letters.into_iter().filter_map(|n| u8::try_from(n).ok()).map(|c| char::from(c).to_string()).collect()))
+}
+
+/// Returns the numbers from the first to the last, counting up or down by
+/// the step, or an error if there would be too many of them.
+fn stepped(first: u64, last: u64, step: u64) -> Option<Result<Vec<u64>, ExpandError>> {
+    let count = first.max(last) - first.min(last);
+    if usize::try_from(count / step).map_or(true, |count| count >= MAX_NAMES) {
+        return Some(Err(ExpandError::TooManyNames));
+    }
+
+    let numbers = (0 ..= count / step).map(|i| if first <= last { first + i * step } else { first - i * step });
+    Some(Ok(numbers.collect()))
+}
+
+/// Returns the character if the text is a single ASCII letter.
+fn single_letter(input: &str) -> Option<u8> {
+    match input.as_bytes() {
+        [ c ] if c.is_ascii_alphabetic()  => Some(*c),
+        _                                 => None,
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn names(input: &[&str]) -> Vec<String> {
+        input.iter().map(|s| String::from(*s)).collect()
+    }
+
+    #[test]
+    fn no_braces() {
+        assert_eq!(expand("lookup.dog"), Ok(names(&[ "lookup.dog" ])));
+    }
+
+    #[test]
+    fn number_range() {
+        assert_eq!(expand("node{1..3}.lookup.dog"),
+                   Ok(names(&[ "node1.lookup.dog", "node2.lookup.dog", "node3.lookup.dog" ])));
+    }
+
+    #[test]
+    fn fifty_nodes() {
+        let expanded = expand("node{1..50}.lookup.dog").unwrap();
+        assert_eq!(expanded.len(), 50);
+        assert_eq!(expanded[49], "node50.lookup.dog");
+    }
+
+    #[test]
+    fn downwards() {
+        assert_eq!(expand("{3..1}.lookup.dog"),
+                   Ok(names(&[ "3.lookup.dog", "2.lookup.dog", "1.lookup.dog" ])));
+    }
+
+    #[test]
+    fn stepped_range() {
+        assert_eq!(expand("{0..10..5}.lookup.dog"),
+                   Ok(names(&[ "0.lookup.dog", "5.lookup.dog", "10.lookup.dog" ])));
+    }
+
+    #[test]
+    fn padded() {
+        assert_eq!(expand("web{08..10}"),
+                   Ok(names(&[ "web08", "web09", "web10" ])));
+    }
+
+    #[test]
+    fn letters() {
+        assert_eq!(expand("{a..c}.lookup.dog"),
+                   Ok(names(&[ "a.lookup.dog", "b.lookup.dog", "c.lookup.dog" ])));
+    }
+
+    #[test]
+    fn list() {
+        assert_eq!(expand("{www,mail}.lookup.dog"),
+                   Ok(names(&[ "www.lookup.dog", "mail.lookup.dog" ])));
+    }
+
+    #[test]
+    fn combinations() {
+        assert_eq!(expand("{a,b}{1..2}.lookup.dog"),
+                   Ok(names(&[ "a1.lookup.dog", "a2.lookup.dog", "b1.lookup.dog", "b2.lookup.dog" ])));
+    }
+
+    #[test]
+    fn unclosed() {
+        assert_eq!(expand("node{1..3.lookup.dog"), Err(ExpandError::UnbalancedBraces));
+    }
+
+    #[test]
+    fn unopened() {
+        assert_eq!(expand("node1..3}.lookup.dog"), Err(ExpandError::UnbalancedBraces));
+    }
+
+    #[test]
+    fn nested() {
+        assert_eq!(expand("{a,{b,c}}.lookup.dog"), Err(ExpandError::NestedBraces));
+    }
+
+    #[test]
+    fn neither_range_nor_list() {
+        assert_eq!(expand("{node}.lookup.dog"), Err(ExpandError::NotRangeOrList));
+        assert_eq!(expand("{a..9}.lookup.dog"), Err(ExpandError::NotRangeOrList));
+    }
+
+    #[test]
+    fn zero_step() {
+        assert_eq!(expand("{1..3..0}.lookup.dog"), Err(ExpandError::ZeroStep));
+    }
+
+    #[test]
+    fn too_many() {
+        assert_eq!(expand("{1..100000}.lookup.dog"), Err(ExpandError::TooManyNames));
+        assert_eq!(expand("{1..200}{1..200}.lookup.dog"), Err(ExpandError::TooManyNames));
+    }
+}
@@ -0,0 +1,195 @@
+//! Filter mode, where dog reads DNS messages from standard input, sends
+//! them to a nameserver, and writes the responses to standard output.
+//!
+//! Messages are prefixed with their length as a big-endian `u16` in both
+//! directions, in the same way they are over TCP, so other programs can use
+//! dog to send their queries over an encrypted transport.
+
+use std::convert::TryFrom;
+use std::io::{self, Read, Write};
+
+use dns_transport::{Transport, Error as TransportError};
+use log::*;
+
+
+/// The number of messages that were forwarded in filter mode, and how many
+/// of them failed to get a response.
+#[derive(PartialEq, Debug, Default, Copy, Clone)]
+pub struct FilterStats {
+
+    /// The number of messages read from the input.
+    pub forwarded: usize,
+
+    /// The number of messages that failed to get a response, and got
+    /// answered with a server failure instead.
+    pub failed: usize,
+}
+
+/// Reads every length-prefixed message from the input, sends each one over
+/// the transport, and writes each response to the output with its length
+/// in front. If a message fails to get a response, the error gets passed
+/// to `on_error`, and a `SERVFAIL` response is written in its place, so the
+/// program at the other end is not left waiting.
+///
+/// # Errors
+///
+/// Returns an error if the input could not be read from or the output could
+/// not be written to, including when the input ends partway through a
+/// message.
+pub fn run(transport: &dyn Transport, mut input: impl Read, mut output: impl Write, mut on_error: impl FnMut(TransportError)) -> io::Result<FilterStats> {
+    let mut stats = FilterStats::default();
+
+    while let Some(message) = read_message(&mut input)? {
+        stats.forwarded += 1;
+        debug!("Forwarding message of {} bytes", message.len());
+
+        let response = match transport.send_raw(&message) {
+            Ok(response) => {
+                response
+            }
+            Err(e) => {
+                stats.failed += 1;
+                on_error(e);
+
+                match server_failure(&message) {
+                    Some(response) => response,
+                    None => {
+                        warn!("Message is too short to respond to");
+                        continue;
+                    }
+                }
+            }
+        };
+
+        let length = u16::try_from(response.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "response too long"))?;
+
+        output.write_all(&length.to_be_bytes())?;
+        output.write_all(&response)?;
+        output.flush()?;
+    }
+
+    Ok(stats)
+}
+
+/// Reads one length-prefixed message from the input, returning `None` if
+/// the input has ended cleanly between messages.
+fn read_message(input: &mut impl Read) -> io::Result<Option<Vec<u8>>> {
+    let mut length_bytes = [0_u8; 2];
+
+    match input.read(&mut length_bytes[.. 1])? {
+        0 => return Ok(None),
+        _ => input.read_exact(&mut length_bytes[1 ..])?,
+    }
+
+    let mut message = vec![0_u8; usize::from(u16::from_be_bytes(length_bytes))];
+    input.read_exact(&mut message)?;
+    Ok(Some(message))
+}
+
+/// Builds a `SERVFAIL` response to a query, consisting of its header with
+/// the response flag and error code set and every section left empty.
+/// Returns `None` if the query is too short to have a header.
+fn server_failure(query: &[u8]) -> Option<Vec<u8>> {
+    let mut response = query.get(.. 12)?.to_vec();
+
+    response[2] |= 0b_1000_0000;   // response
+    response[3] = (response[3] & 0b_1111_0000) | 2;   // server failure
+
+    for count in &mut response[4 ..] {
+        *count = 0;
+    }
+
+    Some(response)
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    /// A transport that answers every message by reversing it, or fails to
+    /// answer any message.
+    struct MirrorTransport {
+        works: bool,
+    }
+
+    impl Transport for MirrorTransport {
+        fn send_raw(&self, bytes_to_send: &[u8]) -> Result<Vec<u8>, TransportError> {
+            if self.works {
+                Ok(bytes_to_send.iter().rev().copied().collect())
+            }
+            else {
+                Err(TransportError::TruncatedResponse)
+            }
+        }
+    }
+
+    fn filter(works: bool, input: &[u8]) -> (io::Result<FilterStats>, Vec<u8>, usize) {
+        let mut output = Vec::new();
+        let mut errors = 0;
+        let result = run(&MirrorTransport { works }, input, &mut output, |_| errors += 1);
+        (result, output, errors)
+    }
+
+    #[test]
+    fn forwards_messages() {
+        let (result, output, errors) = filter(true, &[
+            0x00, 0x03, 0x01, 0x02, 0x03,
+            0x00, 0x02, 0x04, 0x05,
+        ]);
+
+        assert_eq!(result.unwrap(), FilterStats { forwarded: 2, failed: 0 });
+        assert_eq!(output, vec![
+            0x00, 0x03, 0x03, 0x02, 0x01,
+            0x00, 0x02, 0x05, 0x04,
+        ]);
+        assert_eq!(errors, 0);
+    }
+
+    #[test]
+    fn no_messages() {
+        let (result, output, _) = filter(true, &[]);
+        assert_eq!(result.unwrap(), FilterStats::default());
+        assert_eq!(output, Vec::<u8>::new());
+    }
+
+    #[test]
+    fn input_ends_abruptly() {
+        let (result, output, _) = filter(true, &[ 0x00, 0x03, 0x01 ]);
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+        assert_eq!(output, Vec::<u8>::new());
+    }
+
+    #[test]
+    fn answers_failures() {
+        let query = &[
+            0x00, 0x0c,  // length
+            0xce, 0xac,  // transaction ID
+            0x01, 0x00,  // flags (recursion desired)
+            0x00, 0x01,  // query count
+            0x00, 0x00,  // answer count
+            0x00, 0x00,  // authority RR count
+            0x00, 0x01,  // additional RR count
+        ];
+
+        let (result, output, errors) = filter(false, query);
+        assert_eq!(result.unwrap(), FilterStats { forwarded: 1, failed: 1 });
+        assert_eq!(output, vec![
+            0x00, 0x0c,
+            0xce, 0xac,
+            0x81, 0x02,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ]);
+        assert_eq!(errors, 1);
+    }
+
+    #[test]
+    fn skips_short_failures() {
+        let (result, output, errors) = filter(false, &[ 0x00, 0x01, 0xff ]);
+        assert_eq!(result.unwrap(), FilterStats { forwarded: 1, failed: 1 });
+        assert_eq!(output, Vec::<u8>::new());
+        assert_eq!(errors, 1);
+    }
+}
@@ -0,0 +1,387 @@
+//! Checking whether a resolver filters or redirects domains in the
+//! categories that filtering resolvers commonly block, by comparing their
+//! answers with the answer for a domain that nobody blocks.
+
+use std::fmt;
+use std::net::IpAddr;
+
+use dns::{Answer, ErrorCode, Response};
+use dns::record::{EdnsOption, Record, RecordType};
+use dns_transport::Transport;
+use log::*;
+
+use crate::requests::RequestGenerator;
+use crate::resolve::ResolverLookupError;
+
+
+/// A domain that should resolve normally on every resolver, used to make
+/// sure that the resolver works at all.
+const CONTROL_DOMAIN: &str = "example.com";
+
+/// The domains to probe, along with the category that filtering resolvers
+/// put them in. These are test domains that the providers of filtering
+/// resolvers publish so people can check their filters work.
+const PROBES: &[(&str, &str)] = &[
+    ("malware",   "malware.testcategory.com"),
+    ("adult",     "nudity.testcategory.com"),
+    ("phishing",  "internetbadguys.com"),
+    ("malware",   "examplemalwaredomain.com"),
+];
+
+/// Addresses that filtering resolvers answer with instead of the real
+/// address of a blocked domain.
+const SINKHOLE_ADDRESSES: &[&str] = &[
+    "0.0.0.0", "127.0.0.1", "::", "::1",
+    "146.112.61.104", "146.112.61.105", "146.112.61.106",   // Cisco Umbrella
+    "146.112.61.107", "146.112.61.108", "146.112.61.110",
+];
+
+/// The info codes of the extended errors that say a response was filtered.
+const FILTERING_INFO_CODES: &[u16] = &[ 15, 16, 17, 18 ];
+
+
+/// The results of checking a resolver for filtering.
+#[derive(PartialEq, Debug)]
+pub struct FilterReport {
+
+    /// What happened to the query for the control domain.
+    pub control: Verdict,
+
+    /// What happened to the query for each probe domain, along with its
+    /// category and the domain itself.
+    pub probes: Vec<(&'static str, &'static str, Verdict)>,
+}
+
+/// What a resolver did with the query for one domain.
+#[derive(PartialEq, Debug)]
+pub enum Verdict {
+
+    /// The domain resolved to these addresses, with nothing to suggest it
+    /// was tampered with.
+    Resolved(Vec<IpAddr>),
+
+    /// The response says the domain was filtered, for this reason.
+    Filtered(String),
+
+    /// The domain does not exist, according to the resolver, which is what
+    /// some filtering resolvers say about blocked domains.
+    NoSuchDomain,
+
+    /// No usable response arrived.
+    Failed(String),
+}
+
+
+/// Sends an `A` query for the control domain and each probe domain using
+/// the first nameserver and transport type in the inputs, and works out
+/// what the resolver did with each one.
+pub fn run_check(requests: RequestGenerator) -> Result<FilterReport, ResolverLookupError> {
+    let edns = requests.edns;
+    let protocol_tweaks = requests.protocol_tweaks.clone();
+    let txid_generator = requests.txid_generator;
+    let transport = requests.first_transport()?;
+
+    let query = |domain: &str| {
+        let mut flags = dns::Flags::query();
+        protocol_tweaks.set_request_flags(&mut flags);
+
+        let mut additional = None;
+        if edns.should_send() {
+            let mut opt = dns::Request::additional_record();
+            protocol_tweaks.set_request_opt_fields(&mut opt);
+            additional = Some(opt);
+        }
+
+        let qname = dns::Labels::encode(domain).expect("invalid probe domain");
+        let query = dns::Query { qname, qtype: RecordType::A, qclass: dns::QClass::IN };
+        let request = dns::Request { transaction_id: txid_generator.generate(), flags, query, additional };
+        send_and_judge(&*transport, &request)
+    };
+
+    let control = query(CONTROL_DOMAIN);
+    let mut probes = PROBES.iter()
+        .map(|(category, domain)| (*category, *domain, query(domain)))
+        .collect::<Vec<_>>();
+
+    mark_common_redirects(&mut probes, &control);
+    Ok(FilterReport { control, probes })
+}
+
+/// Sends one request and judges the response.
+fn send_and_judge(transport: &dyn Transport, request: &dns::Request) -> Verdict {
+    match transport.send(request) {
+        Ok(response) => {
+            judge(&response)
+        }
+        Err(e) => {
+            warn!("Probe failed: {:?}", e);
+            Verdict::Failed(format!("{:?}", e))
+        }
+    }
+}
+
+/// Works out what the resolver did with a query from its response.
+fn judge(response: &Response) -> Verdict {
+    for option in response.edns_options() {
+        if let EdnsOption::ExtendedError(error) = option {
+            if FILTERING_INFO_CODES.contains(&error.info_code) {
+                let name = error.info_code_name().unwrap_or("Unknown");
+                return Verdict::Filtered(format!("extended error {} ({})", name, error.info_code));
+            }
+        }
+    }
+
+    if let Some(zone) = rpz_zone(response) {
+        return Verdict::Filtered(format!("answered from policy zone {}", zone));
+    }
+
+    match response.flags.error_code {
+        None                            => {},
+        Some(ErrorCode::NXDomain)       => return Verdict::NoSuchDomain,
+        Some(ErrorCode::QueryRefused)   => return Verdict::Filtered("query refused".into()),
+        Some(other)                     => return Verdict::Failed(format!("{:?}", other)),
+    }
+
+    let addresses = response.answers.iter().filter_map(|answer| match answer {
+        Answer::Standard { record, .. }  => record.as_address(),
+        _                                => None,
+    }).collect::<Vec<_>>();
+
+    if let Some(sinkhole) = addresses.iter().find(|address| is_sinkhole(**address)) {
+        return Verdict::Filtered(format!("sinkhole address {}", sinkhole));
+    }
+
+    Verdict::Resolved(addresses)
+}
+
+/// Returns the name of the zone in the authority section of a response if
+/// it looks like a response policy zone, as an RPZ answer carries the SOA
+/// of the policy zone rather than the zone the domain is in.
+fn rpz_zone(response: &Response) -> Option<String> {
+    response.authorities.iter().find_map(|answer| match answer {
+        Answer::Standard { qname, record: Record::SOA(_), .. } => {
+            let zone = qname.to_string();
+            let is_rpz = zone.split('.').any(|label| label.eq_ignore_ascii_case("rpz") || label.to_ascii_lowercase().starts_with("rpz-"));
+            if is_rpz { Some(zone) } else { None }
+        }
+        _ => None,
+    })
+}
+
+/// Whether an address is one that filtering resolvers use in place of the
+/// real address of a blocked domain.
+fn is_sinkhole(address: IpAddr) -> bool {
+    SINKHOLE_ADDRESSES.iter().any(|sinkhole| sinkhole.parse() == Ok(address))
+}
+
+/// Marks probes as filtered when more than one of them resolved to the
+/// same address, which the control domain did not resolve to, as that is
+/// most likely a block page that every filtered domain gets sent to.
+fn mark_common_redirects(probes: &mut [(&'static str, &'static str, Verdict)], control: &Verdict) {
+    let control_addresses = match control {
+        Verdict::Resolved(addresses) => addresses.clone(),
+        _                            => Vec::new(),
+    };
+
+    let resolved = probes.iter().filter_map(|(_, _, verdict)| match verdict {
+        Verdict::Resolved(addresses) => Some(addresses.clone()),
+        _                            => None,
+    }).collect::<Vec<_>>();
+
+    for (_, _, verdict) in probes.iter_mut() {
+        let shared = match verdict {
+            Verdict::Resolved(addresses) => {
+                addresses.iter().copied().find(|address| {
+                    ! control_addresses.contains(address)
+                        && resolved.iter().filter(|other| other.contains(address)).count() > 1
+                })
+            }
+            _ => None,
+        };
+
+        if let Some(address) = shared {
+            *verdict = Verdict::Filtered(format!("redirected to shared address {}", address));
+        }
+    }
+}
+
+
+impl FilterReport {
+
+    /// The number of probes that look like they were filtered.
+    pub fn filtered_count(&self) -> usize {
+        self.probes.iter().filter(|(_, _, verdict)| matches!(verdict, Verdict::Filtered(_))).count()
+    }
+
+    /// Whether the control domain resolved, without which the other
+    /// results mean nothing.
+    pub fn control_resolved(&self) -> bool {
+        matches!(self.control, Verdict::Resolved(_))
+    }
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Resolved(addresses) if addresses.is_empty() => {
+                write!(f, "resolved (no addresses)")
+            }
+            Self::Resolved(addresses) => {
+                let addresses = addresses.iter().map(ToString::to_string).collect::<Vec<_>>();
+                write!(f, "resolved ({})", addresses.join(", "))
+            }
+            Self::Filtered(reason) => {
+                write!(f, "filtered: {}", reason)
+            }
+            Self::NoSuchDomain => {
+                write!(f, "no such domain (possibly filtered)")
+            }
+            Self::Failed(why) => {
+                write!(f, "failed: {}", why)
+            }
+        }
+    }
+}
+
+impl fmt::Display for FilterReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:<10} {:<26} {}", "control", CONTROL_DOMAIN, self.control)?;
+
+        for (category, domain, verdict) in &self.probes {
+            writeln!(f, "{:<10} {:<26} {}", category, domain, verdict)?;
+        }
+
+        if ! self.control_resolved() {
+            writeln!(f, "warning: the control domain did not resolve, so these results cannot be trusted")?;
+        }
+        else if self.filtered_count() == 0 {
+            writeln!(f, "The resolver does not appear to filter any of these domains")?;
+        }
+        else {
+            writeln!(f, "The resolver appears to filter {} of {} domains", self.filtered_count(), self.probes.len())?;
+        }
+
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use dns::record::{A, ExtendedError, SOA};
+    use pretty_assertions::assert_eq;
+
+    fn response(error_code: Option<ErrorCode>, answers: Vec<Answer>) -> Response {
+        let mut flags = dns::Flags::query();
+        flags.error_code = error_code;
+
+        Response {
+            transaction_id: 0x1234,
+            flags,
+            queries: vec![],
+            answers,
+            authorities: vec![],
+            additionals: vec![],
+        }
+    }
+
+    fn a(address: &str) -> Answer {
+        Answer::Standard {
+            qname: dns::Labels::encode("lookup.dog").unwrap(),
+            qclass: dns::QClass::IN,
+            ttl: 300,
+            record: Record::A(A { address: address.parse().unwrap() }),
+        }
+    }
+
+    #[test]
+    fn resolved() {
+        assert_eq!(judge(&response(None, vec![ a("192.0.2.1") ])),
+                   Verdict::Resolved(vec![ "192.0.2.1".parse().unwrap() ]));
+    }
+
+    #[test]
+    fn sinkhole() {
+        assert_eq!(judge(&response(None, vec![ a("0.0.0.0") ])),
+                   Verdict::Filtered("sinkhole address 0.0.0.0".into()));
+    }
+
+    #[test]
+    fn nxdomain() {
+        assert_eq!(judge(&response(Some(ErrorCode::NXDomain), vec![])),
+                   Verdict::NoSuchDomain);
+    }
+
+    #[test]
+    fn extended_error() {
+        let mut opt = dns::Request::additional_record();
+        opt.push_option(&EdnsOption::ExtendedError(ExtendedError { info_code: 15, extra_text: vec![] })).unwrap();
+
+        let mut response = response(Some(ErrorCode::NXDomain), vec![]);
+        response.additionals.push(Answer::Pseudo { qname: dns::Labels::root(), opt });
+
+        assert_eq!(judge(&response),
+                   Verdict::Filtered("extended error Blocked (15)".into()));
+    }
+
+    #[test]
+    fn policy_zone() {
+        let mut response = response(Some(ErrorCode::NXDomain), vec![]);
+        response.authorities.push(Answer::Standard {
+            qname: dns::Labels::encode("rpz.example.net").unwrap(),
+            qclass: dns::QClass::IN,
+            ttl: 300,
+            record: Record::SOA(SOA {
+                mname: dns::Labels::encode("localhost").unwrap(),
+                rname: dns::Labels::encode("root.localhost").unwrap(),
+                serial: 1, refresh_interval: 3600, retry_interval: 600, expire_limit: 86400, minimum_ttl: 60,
+            }),
+        });
+
+        assert_eq!(judge(&response),
+                   Verdict::Filtered("answered from policy zone rpz.example.net.".into()));
+    }
+
+    #[test]
+    fn shared_redirect() {
+        let control = Verdict::Resolved(vec![ "192.0.2.1".parse().unwrap() ]);
+        let mut probes = vec![
+            ("malware", "one.example", Verdict::Resolved(vec![ "198.51.100.7".parse().unwrap() ])),
+            ("adult",   "two.example", Verdict::Resolved(vec![ "198.51.100.7".parse().unwrap() ])),
+            ("other",   "three.example", Verdict::Resolved(vec![ "203.0.113.9".parse().unwrap() ])),
+        ];
+
+        mark_common_redirects(&mut probes, &control);
+        assert_eq!(probes[0].2, Verdict::Filtered("redirected to shared address 198.51.100.7".into()));
+        assert_eq!(probes[1].2, Verdict::Filtered("redirected to shared address 198.51.100.7".into()));
+        assert_eq!(probes[2].2, Verdict::Resolved(vec![ "203.0.113.9".parse().unwrap() ]));
+    }
+
+    #[test]
+    fn report_with_filtering() {
+        let report = FilterReport {
+            control: Verdict::Resolved(vec![ "192.0.2.1".parse().unwrap() ]),
+            probes: vec![
+                ("malware", "malware.testcategory.com", Verdict::Filtered("sinkhole address 0.0.0.0".into())),
+                ("adult", "nudity.testcategory.com", Verdict::NoSuchDomain),
+            ],
+        };
+
+        assert_eq!(report.to_string(), "\
+            control    example.com                resolved (192.0.2.1)\n\
+            malware    malware.testcategory.com   filtered: sinkhole address 0.0.0.0\n\
+            adult      nudity.testcategory.com    no such domain (possibly filtered)\n\
+            The resolver appears to filter 1 of 2 domains\n");
+    }
+
+    #[test]
+    fn report_without_control() {
+        let report = FilterReport {
+            control: Verdict::Failed("TruncatedResponse".into()),
+            probes: vec![],
+        };
+
+        assert!(report.to_string().contains("cannot be trusted"));
+    }
+}
@@ -0,0 +1,283 @@
+//! Keeping track of how response times change over time in monitor mode,
+//! by putting the responses from each round of queries into buckets, and
+//! drawing the rounds as a sparkline or a heatmap in plain ASCII, so that a
+//! nameserver that is only slow some of the time stands out.
+
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+use std::time::Duration;
+
+use json::{object, JsonValue};
+
+
+/// The upper bounds of the latency buckets, in milliseconds. Responses
+/// that take at least as long as the last bound go in a bucket of their
+/// own, so there is one more bucket than there are bounds.
+pub const BUCKET_BOUNDS_MS: [u64; 7] = [ 10, 20, 50, 100, 200, 500, 1000 ];
+
+/// The number of latency buckets.
+const BUCKET_COUNT: usize = BUCKET_BOUNDS_MS.len() + 1;
+
+/// The most rounds that are kept, so a long-running monitor does not keep
+/// on using more memory. This is a day’s worth at one round a minute.
+const MAX_ROUNDS: usize = 1440;
+
+/// The characters a sparkline is drawn with, from the quickest mean
+/// response time to the slowest.
+const SPARK_LEVELS: &[u8] = b"_.-=+*#@";
+
+/// The character for a round in a sparkline where nothing was answered.
+const SPARK_FAILED: char = 'x';
+
+/// The characters a heatmap is drawn with, from an empty bucket to the
+/// fullest one.
+const HEAT_LEVELS: &[u8] = b" .:-=+*#%@";
+
+
+/// The responses from one round of queries, sorted into latency buckets.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Round {
+
+    /// The number of this round, counting from one.
+    pub number: usize,
+
+    /// How many responses fell into each latency bucket.
+    pub buckets: [usize; BUCKET_COUNT],
+
+    /// How many queries did not get a response.
+    pub failed: usize,
+
+    /// The time taken by every response put together, for working out the
+    /// mean.
+    pub total_time: Duration,
+}
+
+impl Round {
+
+    /// Creates a round with nothing in it yet.
+    pub fn new(number: usize) -> Self {
+        Self { number, buckets: [0; BUCKET_COUNT], failed: 0, total_time: Duration::default() }
+    }
+
+    /// Puts one query that got a response after the given time in its
+    /// bucket, or counts it as failed if there is no time.
+    pub fn record(&mut self, outcome: Option<Duration>) {
+        match outcome {
+            Some(elapsed) => {
+                let millis = u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX);
+                let bucket = BUCKET_BOUNDS_MS.iter().position(|bound| millis < *bound).unwrap_or(BUCKET_COUNT - 1);
+                self.buckets[bucket] += 1;
+                self.total_time += elapsed;
+            }
+            None => {
+                self.failed += 1;
+            }
+        }
+    }
+
+    /// The number of queries in this round that got a response.
+    pub fn answered(&self) -> usize {
+        self.buckets.iter().sum()
+    }
+
+    /// The mean time that a response in this round took to arrive.
+    pub fn mean(&self) -> Option<Duration> {
+        let answered = u32::try_from(self.answered()).ok().filter(|a| *a > 0)?;
+        Some(self.total_time / answered)
+    }
+
+    /// Serialises this round as JSON, for the stats file.
+    pub fn to_json(&self) -> JsonValue {
+        object! {
+            "round": self.number,
+            "buckets": self.buckets.to_vec(),
+            "failed": self.failed,
+            "mean_ms": self.mean().map(|t| t.as_secs_f64() * 1000.0),
+        }
+    }
+}
+
+
+/// The most recent rounds of queries, oldest first.
+#[derive(PartialEq, Debug, Default)]
+pub struct Series {
+    rounds: VecDeque<Round>,
+}
+
+impl Series {
+
+    /// Adds a round to the end of the series, forgetting the oldest one if
+    /// there are too many.
+    pub fn push(&mut self, round: Round) {
+        if self.rounds.len() == MAX_ROUNDS {
+            self.rounds.pop_front();
+        }
+
+        self.rounds.push_back(round);
+    }
+
+    /// The last `width` rounds, or every round if there are fewer.
+    fn last(&self, width: usize) -> impl Iterator<Item=&Round> {
+        self.rounds.iter().skip(self.rounds.len().saturating_sub(width))
+    }
+
+    /// Serialises the series as JSON, for the stats file, with the bucket
+    /// bounds that the counts in each round go with.
+    pub fn to_json(&self) -> JsonValue {
+        object! {
+            "bucket_bounds_ms": BUCKET_BOUNDS_MS.to_vec(),
+            "rounds": self.rounds.iter().map(Round::to_json).collect::<Vec<_>>(),
+        }
+    }
+
+    /// Draws the mean response time of each of the last `width` rounds as
+    /// one character, scaled so the slowest of them uses the highest level.
+    /// Rounds where nothing was answered are drawn as an `x`.
+    pub fn sparkline(&self, width: usize) -> String {
+        let means = self.last(width).map(Round::mean).collect::<Vec<_>>();
+        let slowest = means.iter().flatten().max().copied().unwrap_or_default();
+
+        means.into_iter()
+            .map(|mean| match mean {
+                Some(mean)  => char::from(SPARK_LEVELS[level(mean.as_secs_f64(), slowest.as_secs_f64(), SPARK_LEVELS.len())]),
+                None        => SPARK_FAILED,
+            })
+            .collect()
+    }
+
+    /// Draws the last `width` rounds as a heatmap, with a column for each
+    /// round and a row for each latency bucket, slowest at the top, and a
+    /// row at the bottom for the queries that failed. Each cell is shaded
+    /// by how many responses it holds, compared to the fullest cell.
+    pub fn heatmap(&self, width: usize) -> String {
+        let rounds = self.last(width).collect::<Vec<_>>();
+        let fullest = rounds.iter()
+            .flat_map(|round| round.buckets.iter().copied().chain(Some(round.failed)))
+            .max()
+            .unwrap_or_default();
+
+        #[allow(clippy::cast_precision_loss)]
+        let shade = |count: usize| {
+            if count == 0 { ' ' }
+            else { char::from(HEAT_LEVELS[level(count as f64, fullest as f64, HEAT_LEVELS.len() - 1) + 1]) }
+        };
+
+        let mut lines = Vec::new();
+
+        for bucket in (0 .. BUCKET_COUNT).rev() {
+            let cells = rounds.iter().map(|round| shade(round.buckets[bucket])).collect::<String>();
+            lines.push(format!("{:>8} |{}", bucket_label(bucket), cells.trim_end()));
+        }
+
+        let cells = rounds.iter().map(|round| shade(round.failed)).collect::<String>();
+        lines.push(format!("{:>8} |{}", "failed", cells.trim_end()));
+
+        let mut output = lines.join("\n");
+        output.push('\n');
+        output
+    }
+}
+
+/// Picks which of `levels` levels a value comes to, out of a maximum, with
+/// the lowest level for zero and the highest for the maximum.
+#[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss, clippy::cast_sign_loss)]
+fn level(value: f64, max: f64, levels: usize) -> usize {
+    if max <= 0.0 {
+        return 0;
+    }
+
+    let top = (levels - 1) as f64;
+    ((value / max * top).round() as usize).min(levels - 1)
+}
+
+/// The label for a latency bucket, giving the bound that responses in it
+/// came in under.
+fn bucket_label(bucket: usize) -> String {
+    match BUCKET_BOUNDS_MS.get(bucket) {
+        Some(bound)  => format!("<{}ms", bound),
+        None         => format!(">={}ms", BUCKET_BOUNDS_MS[BUCKET_COUNT - 2]),
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn round(number: usize, outcomes: &[Option<u64>]) -> Round {
+        let mut round = Round::new(number);
+        for outcome in outcomes {
+            round.record(outcome.map(Duration::from_millis));
+        }
+        round
+    }
+
+    #[test]
+    fn buckets() {
+        let round = round(1, &[ Some(5), Some(10), Some(150), Some(5000), None ]);
+        assert_eq!(round.buckets, [ 1, 1, 0, 0, 1, 0, 0, 1 ]);
+        assert_eq!(round.failed, 1);
+        assert_eq!(round.answered(), 4);
+    }
+
+    #[test]
+    fn empty_round_has_no_mean() {
+        assert_eq!(round(1, &[ None ]).mean(), None);
+    }
+
+    #[test]
+    fn sparkline() {
+        let mut series = Series::default();
+        series.push(round(1, &[ Some(0) ]));
+        series.push(round(2, &[ Some(50) ]));
+        series.push(round(3, &[ None ]));
+        series.push(round(4, &[ Some(100) ]));
+
+        assert_eq!(series.sparkline(60), "_+x@");
+        assert_eq!(series.sparkline(2), "x@");
+    }
+
+    #[test]
+    fn heatmap() {
+        let mut series = Series::default();
+        series.push(round(1, &[ Some(5), Some(5) ]));
+        series.push(round(2, &[ Some(5), Some(1500) ]));
+        series.push(round(3, &[ None, None ]));
+
+        assert_eq!(series.heatmap(60), "\
+ >=1000ms | +
+ <1000ms |
+  <500ms |
+  <200ms |
+  <100ms |
+   <50ms |
+   <20ms |
+   <10ms |@+
+  failed |  @
+");
+    }
+
+    #[test]
+    fn series_forgets_old_rounds() {
+        let mut series = Series::default();
+        for number in 1 ..= MAX_ROUNDS + 1 {
+            series.push(Round::new(number));
+        }
+
+        assert_eq!(series.rounds.len(), MAX_ROUNDS);
+        assert_eq!(series.rounds.front().map(|r| r.number), Some(2));
+    }
+
+    #[test]
+    fn series_json() {
+        let mut series = Series::default();
+        series.push(round(1, &[ Some(30) ]));
+
+        let json = series.to_json();
+        assert_eq!(json["bucket_bounds_ms"][0], 10);
+        assert_eq!(json["rounds"][0]["round"], 1);
+        assert_eq!(json["rounds"][0]["buckets"][2], 1);
+        assert_eq!(json["rounds"][0]["mean_ms"], 30.0);
+    }
+}
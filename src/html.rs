@@ -0,0 +1,317 @@
+//! Standalone HTML reports of the results of a run, for attaching to
+//! tickets. They are built from the same JSON values as the JSON output,
+//! so they contain the same data.
+
+use std::fmt::Write;
+use std::time::Duration;
+
+use dns::{Answer, Response};
+use json::JsonValue;
+
+use crate::output::{json_response, error_code_description};
+
+
+/// The styles embedded in every report, so it can be opened on its own.
+const STYLES: &str = "\
+body { font-family: sans-serif; margin: 2em; color: #222; }
+table { border-collapse: collapse; margin-bottom: 1.5em; }
+th, td { border: 1px solid #ccc; padding: 0.3em 0.6em; text-align: left; vertical-align: top; }
+th { background: #f3f3f3; }
+td.data { font-family: monospace; }
+.status { font-weight: bold; padding: 0.1em 0.4em; border-radius: 0.2em; }
+.ok { background: #d4f4d4; color: #135713; }
+.fail { background: #fbe3b8; color: #6b4100; }
+.error { background: #f8d0d0; color: #7a1010; }
+.bar { background: #4a7bd0; height: 1em; }
+.notes { color: #555; }
+";
+
+
+/// A report on the results of a run, which gets written out as HTML once
+/// the run has finished.
+#[derive(Debug, Default)]
+pub struct HtmlReport {
+    entries: Vec<Entry>,
+}
+
+/// The result of one query, or group of queries sent to one nameserver.
+#[derive(Debug)]
+struct Entry {
+
+    /// The response, serialised in the same way as for JSON output, or
+    /// the error that occurred instead of getting a response.
+    json: JsonValue,
+
+    /// The status of the response, or `None` if there was an error.
+    status: Option<Status>,
+
+    /// How long it took to get the response or the error.
+    elapsed: Duration,
+}
+
+/// Whether a response was successful, and its status in words.
+#[derive(Debug)]
+struct Status {
+    success: bool,
+    description: String,
+}
+
+
+impl HtmlReport {
+
+    /// Adds a response to the report. Its OPT record is only shown if
+    /// the user wants to see it, as in the other output formats.
+    pub fn add_response(&mut self, response: &Response, elapsed: Duration, show_opt: bool) {
+        let options = response.edns_options();
+        let mut response = response.clone();
+
+        if ! show_opt {
+            response.answers.retain(Answer::is_standard);
+            response.authorities.retain(Answer::is_standard);
+            response.additionals.retain(Answer::is_standard);
+        }
+
+        let status = match response.flags.error_code {
+            None         => Status { success: true, description: "NoError".into() },
+            Some(rcode)  => Status { success: false, description: error_code_description(rcode) },
+        };
+
+        let json = json_response(response, &options);
+        self.entries.push(Entry { json, status: Some(status), elapsed });
+    }
+
+    /// Adds an error, serialised by `json_error`, to the report.
+    pub fn add_error(&mut self, error: JsonValue, elapsed: Duration) {
+        self.entries.push(Entry { json: error, status: None, elapsed });
+    }
+
+    /// Renders the report as a standalone HTML document, with a summary
+    /// table, a chart of how long each query took, and the records in
+    /// each response.
+    pub fn render(&self, command_line: &str) -> String {
+        let mut out = String::new();
+
+        out.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+        let _ = writeln!(out, "<title>dog report: {}</title>", escape(command_line));
+        let _ = writeln!(out, "<style>\n{}</style>\n</head>\n<body>", STYLES);
+        let _ = writeln!(out, "<h1>dog report</h1>\n<p><code>{}</code></p>", escape(command_line));
+
+        self.render_summary(&mut out);
+        self.render_timings(&mut out);
+        self.render_responses(&mut out);
+
+        let _ = writeln!(out, "<p class=\"notes\">Generated by dog v{}</p>", env!("CARGO_PKG_VERSION"));
+        out.push_str("</body>\n</html>\n");
+        out
+    }
+
+    fn render_summary(&self, out: &mut String) {
+        out.push_str("<h2>Summary</h2>\n<table>\n<tr><th>#</th><th>Query</th><th>Status</th><th>Answers</th><th>Time</th></tr>\n");
+
+        for (index, entry) in self.entries.iter().enumerate() {
+            let _ = writeln!(out, "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                index + 1,
+                escape(&entry.query_description()),
+                entry.status_badge(),
+                entry.json["answers"].len(),
+                milliseconds(entry.elapsed));
+        }
+
+        out.push_str("</table>\n");
+    }
+
+    fn render_timings(&self, out: &mut String) {
+        let longest = self.entries.iter().map(|e| e.elapsed).max().unwrap_or_default();
+
+        out.push_str("<h2>Timings</h2>\n<table>\n");
+
+        for (index, entry) in self.entries.iter().enumerate() {
+            let percent = if longest.is_zero() { 0.0 } else { entry.elapsed.as_secs_f64() / longest.as_secs_f64() * 100.0 };
+            let _ = writeln!(out, "<tr><td>{}</td><td style=\"width: 30em\"><div class=\"bar\" style=\"width: {:.1}%\"></div></td><td>{}</td></tr>",
+                index + 1, percent, milliseconds(entry.elapsed));
+        }
+
+        out.push_str("</table>\n");
+    }
+
+    fn render_responses(&self, out: &mut String) {
+        out.push_str("<h2>Responses</h2>\n");
+
+        for (index, entry) in self.entries.iter().enumerate() {
+            let _ = writeln!(out, "<h3>{}. {} {}</h3>", index + 1, escape(&entry.query_description()), entry.status_badge());
+
+            if entry.status.is_none() {
+                let _ = writeln!(out, "<p>Error [{}]: {}</p>",
+                    escape(entry.json["error_phase"].as_str().unwrap_or_default()),
+                    escape(entry.json["error_message"].as_str().unwrap_or_default()));
+                continue;
+            }
+
+            for error in entry.json["extended_errors"].members() {
+                let _ = writeln!(out, "<p class=\"notes\">Extended error: {} ({}) {}</p>",
+                    escape(&plain(&error["name"])), error["info_code"], escape(&plain(&error["extra_text"])));
+            }
+
+            for (section, title) in &[ ("answers", "Answers"), ("authorities", "Authorities"), ("additionals", "Additionals") ] {
+                render_records(out, title, &entry.json[*section]);
+            }
+        }
+    }
+}
+
+/// Renders a section of records as a table, unless it is empty.
+fn render_records(out: &mut String, title: &str, records: &JsonValue) {
+    if records.is_empty() {
+        return;
+    }
+
+    let _ = writeln!(out, "<h4>{}</h4>\n<table>\n<tr><th>Type</th><th>Name</th><th>Class</th><th>TTL</th><th>Data</th></tr>", title);
+
+    for record in records.members() {
+        let data = record["data"].entries()
+            .map(|(key, value)| format!("{}: {}", key, plain(value)))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let _ = writeln!(out, "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td class=\"data\">{}</td></tr>",
+            escape(&plain(&record["type"])),
+            escape(&plain(&record["name"])),
+            escape(&plain(&record["class"])),
+            escape(&plain(&record["ttl"])),
+            escape(&data));
+    }
+
+    out.push_str("</table>\n");
+}
+
+impl Entry {
+
+    /// Describes the query that this entry is for, such as
+    /// `dns.lookup.dog. A`, or a placeholder if it is an error.
+    fn query_description(&self) -> String {
+        let query = &self.json["queries"][0];
+
+        if query.is_null() {
+            "(no response)".into()
+        }
+        else {
+            format!("{} {}", plain(&query["name"]), plain(&query["type"]))
+        }
+    }
+
+    /// Renders the status of this entry, coloured by whether it succeeded.
+    fn status_badge(&self) -> String {
+        match &self.status {
+            Some(status) if status.success => format!("<span class=\"status ok\">{}</span>", escape(&status.description)),
+            Some(status)                   => format!("<span class=\"status fail\">{}</span>", escape(&status.description)),
+            None                           => "<span class=\"status error\">Error</span>".into(),
+        }
+    }
+}
+
+/// Formats a JSON value for display, without quotes around strings and
+/// with nothing for `null`.
+fn plain(value: &JsonValue) -> String {
+    match value {
+        JsonValue::Null  => String::new(),
+        other            => other.as_str().map_or_else(|| other.dump(), String::from),
+    }
+}
+
+/// Formats a duration as a number of milliseconds.
+fn milliseconds(duration: Duration) -> String {
+    format!("{:.1} ms", duration.as_secs_f64() * 1000.0)
+}
+
+/// Escapes the characters that have special meanings in HTML.
+fn escape(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+
+    for c in input.chars() {
+        match c {
+            '&'  => out.push_str("&amp;"),
+            '<'  => out.push_str("&lt;"),
+            '>'  => out.push_str("&gt;"),
+            '"'  => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c    => out.push(c),
+        }
+    }
+
+    out
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use dns::{Flags, Labels, QClass, Query, ErrorCode};
+    use dns::record::{Record, RecordType, A};
+    use json::object;
+    use pretty_assertions::assert_eq;
+
+    fn response(error_code: Option<ErrorCode>) -> Response {
+        let mut flags = Flags::standard_response();
+        flags.error_code = error_code;
+
+        Response {
+            transaction_id: 0x1234,
+            flags,
+            queries: vec![ Query { qname: Labels::encode("lookup.dog").unwrap(), qclass: QClass::IN, qtype: RecordType::A } ],
+            answers: vec![ Answer::Standard {
+                qname: Labels::encode("lookup.dog").unwrap(),
+                qclass: QClass::IN,
+                ttl: 300,
+                record: Record::A(A { address: "192.0.2.1".parse().unwrap() }),
+            } ],
+            authorities: vec![],
+            additionals: vec![],
+        }
+    }
+
+    #[test]
+    fn escaping() {
+        assert_eq!(escape("<a href=\"x\">&'"),
+                   "&lt;a href=&quot;x&quot;&gt;&amp;&#39;");
+    }
+
+    #[test]
+    fn successful_response() {
+        let mut report = HtmlReport::default();
+        report.add_response(&response(None), Duration::from_millis(12), false);
+        let html = report.render("dog lookup.dog");
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<title>dog report: dog lookup.dog</title>"));
+        assert!(html.contains("<td>lookup.dog. A</td><td><span class=\"status ok\">NoError</span></td><td>1</td><td>12.0 ms</td>"));
+        assert!(html.contains("<td class=\"data\">address: 192.0.2.1</td>"));
+    }
+
+    #[test]
+    fn failed_response() {
+        let mut report = HtmlReport::default();
+        report.add_response(&response(Some(ErrorCode::NXDomain)), Duration::from_millis(5), false);
+        assert!(report.render("dog").contains("<span class=\"status fail\">NXDomain</span>"));
+    }
+
+    #[test]
+    fn error() {
+        let mut report = HtmlReport::default();
+        report.add_error(object! { "error": true, "error_phase": "network", "error_message": "timed out" }, Duration::from_secs(5));
+
+        let html = report.render("dog");
+        assert!(html.contains("<span class=\"status error\">Error</span>"));
+        assert!(html.contains("<p>Error [network]: timed out</p>"));
+    }
+
+    #[test]
+    fn timing_bars() {
+        let mut report = HtmlReport::default();
+        report.add_response(&response(None), Duration::from_millis(10), false);
+        report.add_response(&response(None), Duration::from_millis(40), false);
+
+        let html = report.render("dog");
+        assert!(html.contains("style=\"width: 25.0%\""));
+        assert!(html.contains("style=\"width: 100.0%\""));
+    }
+}
@@ -0,0 +1,81 @@
+//! Accounting for the time a DNS-over-HTTPS response spent in an HTTP
+//! cache, so the TTLs that get displayed are the ones that are left.
+
+use dns::{Answer, Response};
+
+
+/// Takes the number of seconds a response spent in a cache away from the
+/// TTL of every record in it, as the record has been counting down the
+/// whole time. Returns whether any of the records had expired by the time
+/// the response was served, which means the cache served a stale answer.
+pub fn subtract_age(response: &mut Response, age: u32) -> bool {
+    let mut any_expired = false;
+
+    let sections = response.answers.iter_mut()
+        .chain(response.authorities.iter_mut())
+        .chain(response.additionals.iter_mut());
+
+    for answer in sections {
+        if let Answer::Standard { ttl, .. } = answer {
+            if *ttl < age {
+                any_expired = true;
+            }
+
+            *ttl = ttl.saturating_sub(age);
+        }
+    }
+
+    any_expired
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use dns::record::{Record, A};
+    use pretty_assertions::assert_eq;
+
+    fn response(ttls: &[u32]) -> Response {
+        Response {
+            transaction_id: 0x1234,
+            flags: dns::Flags::query(),
+            queries: vec![],
+            answers: ttls.iter().map(|&ttl| Answer::Standard {
+                qname: dns::Labels::encode("lookup.dog").unwrap(),
+                qclass: dns::QClass::IN,
+                ttl,
+                record: Record::A(A { address: "127.0.0.1".parse().unwrap() }),
+            }).collect(),
+            authorities: vec![],
+            additionals: vec![],
+        }
+    }
+
+    fn ttls(response: &Response) -> Vec<u32> {
+        response.answers.iter().filter_map(|answer| match answer {
+            Answer::Standard { ttl, .. } => Some(*ttl),
+            Answer::Pseudo { .. } => None,
+        }).collect()
+    }
+
+    #[test]
+    fn fresh_records() {
+        let mut response = response(&[ 300, 60 ]);
+        assert!(! subtract_age(&mut response, 30));
+        assert_eq!(ttls(&response), vec![ 270, 30 ]);
+    }
+
+    #[test]
+    fn expired_record() {
+        let mut response = response(&[ 300, 20 ]);
+        assert!(subtract_age(&mut response, 30));
+        assert_eq!(ttls(&response), vec![ 270, 0 ]);
+    }
+
+    #[test]
+    fn no_age() {
+        let mut response = response(&[ 300 ]);
+        assert!(! subtract_age(&mut response, 0));
+        assert_eq!(ttls(&response), vec![ 300 ]);
+    }
+}
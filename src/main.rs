@@ -15,6 +15,7 @@
 #![allow(clippy::enum_glob_use)]
 #![allow(clippy::module_name_repetitions)]
 #![allow(clippy::option_if_let_else)]
+#![allow(clippy::struct_excessive_bools)]
 #![allow(clippy::too_many_lines)]
 #![allow(clippy::upper_case_acronyms)]
 #![allow(clippy::wildcard_imports)]
@@ -23,15 +24,44 @@
 
 use log::*;
 
-mod colours;
+use dns_output::output;
+
+mod axfr;
+mod cnames;
 mod connect;
+mod cookies;
+#[cfg(feature = "with_dnssec")]
+mod denial;
+mod downgrade;
+mod entropy;
+mod expand;
+mod filter;
+mod filtering;
+mod heatmap;
 mod hints;
+mod html;
+mod http_cache;
 mod logger;
-mod output;
+mod monitor;
+mod nsec3;
+mod probe;
 mod requests;
 mod resolve;
-mod table;
+mod retry;
+mod rtt;
+mod snoop;
+#[cfg(feature = "with_doh_server")]
+mod serve;
+mod sig0;
+mod special;
+#[cfg(feature = "with_tsig")]
+mod tsig;
 mod txid;
+mod url;
+#[cfg(feature = "with_dnssec")]
+mod validate;
+mod version;
+mod zonediff;
 
 mod options;
 use self::options::*;
@@ -54,6 +84,52 @@ fn main() {
         OptionsResult::Ok(options) => {
             info!("Running with options -> {:#?}", options);
             disabled_feature_check(&options);
+
+            if options.show_resolvers {
+                exit(run_show_resolvers(&options));
+            }
+
+            if options.filter {
+                exit(run_filter(options));
+            }
+
+            if options.filter_check {
+                exit(run_filter_check(options));
+            }
+
+            if options.probe_port {
+                exit(run_probe_port(options));
+            }
+
+            if options.snoop {
+                exit(run_snoop(options));
+            }
+
+            if options.multi_question {
+                exit(run_multi_question(options));
+            }
+
+            if options.axfr_all {
+                exit(run_axfr_all(options));
+            }
+
+            if options.zone_diff.is_some() {
+                exit(run_zone_diff(options));
+            }
+
+            #[cfg(feature = "with_doh_server")]
+            if options.serve_doh.is_some() {
+                exit(run_serve_doh(options));
+            }
+
+            if let Some(count) = options.entropy_check {
+                exit(run_entropy_check(options, count));
+            }
+
+            if options.monitor.is_some() {
+                exit(run_monitor(options));
+            }
+
             exit(run(options));
         }
 
@@ -84,6 +160,11 @@ fn main() {
             exit(exits::SUCCESS);
         }
 
+        OptionsResult::VersionJson(json_format) => {
+            json_format.println(&version::json());
+            exit(exits::SUCCESS);
+        }
+
         OptionsResult::InvalidOptionsFormat(oe) => {
             eprintln!("dog: Invalid options: {}", oe);
             exit(exits::OPTIONS_ERROR);
@@ -98,8 +179,9 @@ fn main() {
 
 
 /// Runs dog with some options, returning the status to exit with.
-fn run(Options { requests, format, measure_time }: Options) -> i32 {
-    use std::time::Instant;
+#[cfg_attr(not(feature = "with_dnssec"), allow(unused_variables))]
+fn run(Options { requests, format, measure_time, pick_fastest, hide_signatures, validate, rpz, html, lenient, verbose, .. }: Options) -> i32 {
+    use std::time::{Duration, Instant};
 
     let should_show_opt = requests.edns.should_show();
 
@@ -107,6 +189,10 @@ fn run(Options { requests, format, measure_time }: Options) -> i32 {
     let timer = if measure_time { Some(Instant::now()) } else { None };
 
     let mut errored = false;
+    let mut report = html.as_ref().map(|_| html::HtmlReport::default());
+
+    #[cfg(feature = "with_dnssec")]
+    let mut verdicts = Vec::new();
 
     let local_host_hints = match hints::LocalHosts::load() {
         Ok(lh) => lh,
@@ -117,53 +203,223 @@ fn run(Options { requests, format, measure_time }: Options) -> i32 {
     };
 
     for hostname_in_query in &requests.inputs.domains {
-        if local_host_hints.contains(hostname_in_query) {
+        if let Some(special_use) = special::SpecialUse::of(hostname_in_query) {
+            if requests.inputs.resolver_types.iter().any(resolve::ResolverType::is_public) {
+                eprintln!("warning: domain '{}' is under the special-use '{}' domain, and should not be sent to a public resolver", hostname_in_query, special_use);
+            }
+        }
+        else if local_host_hints.contains(hostname_in_query) {
             eprintln!("warning: domain '{}' also exists in hosts file", hostname_in_query);
         }
     }
 
-    let request_tuples = match requests.generate() {
-        Ok(rt) => rt,
-        Err(e) => {
-            eprintln!("Unable to obtain resolver: {}", e);
-            return exits::SYSTEM_ERROR;
+    if ! requests.inputs.record_types.iter().any(|rt| matches!(rt, dns::record::RecordType::HTTPS | dns::record::RecordType::SVCB)) {
+        for url in &requests.inputs.urls {
+            if let Some((qname, qtype)) = url.service_query() {
+                eprintln!("note: for the service parameters of '{}://{}', try 'dog {} {}'", url.scheme, url.host, qname, qtype);
+            }
+        }
+    }
+
+    let txid_generator = requests.txid_generator;
+    let checks = requests.checks;
+    let retry = requests.retry;
+    let mut cookie_jars = cookies::CookieJars::default();
+    let (mut tracker, planned_queries) = if pick_fastest {
+        match requests.generate_pooled() {
+            Ok((nameservers, groups)) => (Some(rtt::RttTracker::new(nameservers)), groups),
+            Err(e) => {
+                eprintln!("Unable to obtain resolver: {}", e);
+                return exits::SYSTEM_ERROR;
+            }
+        }
+    }
+    else {
+        match requests.generate() {
+            Ok(groups) => (None, groups),
+            Err(e) => {
+                eprintln!("Unable to obtain resolver: {}", e);
+                return exits::SYSTEM_ERROR;
+            }
         }
     };
 
-    for (transport, request_list) in request_tuples {
-        let request_list_len = request_list.len();
-        for (i, request) in request_list.into_iter().enumerate() {
-            let result = transport.send(&request);
+    // The first query of every group’s first request set gets written before
+    // any of the responses are read, so the queries that share a TCP or TLS
+    // connection are pipelined down it. Only the first is sent ahead, as any
+    // others are only sent if it fails.
+    if tracker.is_none() {
+        let groups = planned_queries.iter().filter_map(|planned| match planned {
+            requests::PlannedQuery::Send(group)  => group.first(),
+            requests::PlannedQuery::Repeat(_)    => None,
+        });
+
+        for (_, (transport, request_list)) in groups {
+            if let Some(request) = request_list.first() {
+                transport.send_ahead(&request.to_bytes().expect("failed to serialise request"));
+            }
+        }
+    }
+
+    // The outcome of every query so far, so repeated queries can be
+    // answered with the response to the first one.
+    let mut outcomes: Vec<Result<dns::Response, (Option<dns::Query>, json::JsonValue)>> = Vec::new();
+    let mut repeated_count = 0;
+
+    for planned in planned_queries {
+        let group = match planned {
+            requests::PlannedQuery::Send(group) => group,
+            requests::PlannedQuery::Repeat(index) => {
+                let outcome = outcomes[index].clone();
+                repeated_count += 1;
+
+                match &outcome {
+                    Ok(response) => {
+                        if let Some(report) = &mut report {
+                            report.add_response(response, Duration::default(), should_show_opt);
+                        }
+
+                        responses.push(response.clone());
+                    }
+                    Err((query, error)) => {
+                        format.print_query_error_json(query.as_ref(), error);
+                        errored = true;
+
+                        if let Some(report) = &mut report {
+                            report.add_error(error.clone(), Duration::default());
+                        }
+                    }
+                }
+
+                outcomes.push(outcome);
+                continue;
+            }
+        };
+
+        let order = match &tracker {
+            Some(t) => t.preference_order(),
+            None    => (0 .. group.len()).collect(),
+        };
+
+        let query = group.first().and_then(|(_, (_, request_list))| request_list.first()).map(|request| request.query.clone());
+        let mut group = group.into_iter().map(Some).collect::<Vec<_>>();
+        let mut last_error = None;
+        let group_started = Instant::now();
+
+        for index in order {
+            let (nameserver, (transport, request_list)) = group[index].take().expect("request set used twice");
+
+            if verbose && last_error.is_some() {
+                eprintln!("trying the next nameserver, {}", nameserver);
+            }
+
+            let started = Instant::now();
+            let cookie_jar = cookie_jars.for_server(&nameserver);
+            let result = send_with_retries(&*transport, request_list, &nameserver, cookie_jar, lenient, checks, retry, verbose);
 
             match result {
                 Ok(mut response) => {
-                    if response.flags.error_code.is_some() && i != request_list_len - 1 {
-                        continue;
+                    if let Some(t) = &mut tracker {
+                        t.record_success(index, started.elapsed());
+                    }
+
+                    #[cfg(feature = "with_dnssec")]
+                    if validate {
+                        verdicts.push(validate_response(&*transport, &response, txid_generator, checks));
+                    }
+
+                    if hide_signatures {
+                        response.answers.retain(|a| ! is_signature(a));
+                        response.authorities.retain(|a| ! is_signature(a));
+                        response.additionals.retain(|a| ! is_signature(a));
                     }
 
-                    if ! should_show_opt {
-                        response.answers.retain(dns::Answer::is_standard);
-                        response.authorities.retain(dns::Answer::is_standard);
-                        response.additionals.retain(dns::Answer::is_standard);
+                    if let Some(report) = &mut report {
+                        report.add_response(&response, started.elapsed(), should_show_opt);
                     }
 
+                    outcomes.push(Ok(response.clone()));
                     responses.push(response);
+                    last_error = None;
                     break;
                 }
                 Err(e) => {
-                    format.print_error(e);
-                    errored = true;
-                    break;
+                    if let Some(t) = &mut tracker {
+                        warn!("Nameserver {} failed: {:?}", t.servers()[index].nameserver, e);
+                        t.record_failure(index);
+                    }
+
+                    last_error = Some(e);
                 }
             }
         }
+
+        if let Some(e) = last_error {
+            let error = output::json_error(e);
+            format.print_query_error_json(query.as_ref(), &error);
+            errored = true;
+
+            if let Some(report) = &mut report {
+                report.add_error(error.clone(), group_started.elapsed());
+            }
+
+            outcomes.push(Err((query, error)));
+        }
+    }
+
+    if repeated_count > 0 {
+        eprintln!("note: answered {} duplicate {} from earlier responses", repeated_count, if repeated_count == 1 { "query" } else { "queries" });
+    }
+
+    if let Some(t) = &tracker {
+        eprintln!("Nameserver statistics:");
+        for server in t.servers() {
+            eprintln!("  {}", server);
+        }
     }
 
+    if let (Some(path), Some(report)) = (html, report) {
+        let command_line = std::env::args().collect::<Vec<_>>().join(" ");
+
+        if let Err(e) = std::fs::write(&path, report.render(&command_line)) {
+            eprintln!("dog: Failed to write HTML report to {}: {}", path.display(), e);
+            return exits::SYSTEM_ERROR;
+        }
+    }
+
+    for warning in nsec3::warnings(&responses) {
+        eprintln!("warning: {}", warning);
+    }
+
+    let chain_problems = cnames::problems(&responses);
+    for problem in &chain_problems {
+        eprintln!("warning: {}", problem);
+    }
+
+    #[cfg(feature = "with_dnssec")]
+    let any_bogus = print_verdicts(&verdicts);
+
+    #[cfg(not(feature = "with_dnssec"))]
+    let any_bogus = false;
+
     let duration = timer.map(|t| t.elapsed());
-    if format.print(responses, duration) {
+    let printed = if rpz {
+        format.print_policy_rules(responses)
+    }
+    else {
+        format.print(responses, duration, should_show_opt)
+    };
+
+    if printed {
         if errored {
             exits::NETWORK_ERROR
         }
+        else if ! chain_problems.is_empty() {
+            exits::BAD_CNAME_CHAIN
+        }
+        else if any_bogus {
+            exits::DNSSEC_BOGUS
+        }
         else {
             exits::SUCCESS
         }
@@ -174,6 +430,604 @@ fn run(Options { requests, format, measure_time }: Options) -> i32 {
 }
 
 
+/// Sends a request list with `send_request_list`, and if it fails in a way
+/// that might not happen again, sends it again for as many times as the
+/// retry policy allows, waiting longer before each retry. When verbose,
+/// every attempt gets printed, along with how long it took.
+#[allow(clippy::too_many_arguments)]
+fn send_with_retries(transport: &dyn dns_transport::Transport, request_list: Vec<dns::Request>, nameserver: &str, cookie_jar: &mut cookies::CookieJar, lenient: bool, checks: dns_transport::ResponseChecks, retry: retry::RetryPolicy, verbose: bool) -> Result<dns::Response, dns_transport::Error> {
+    use std::time::Instant;
+
+    let mut attempt = 1;
+    loop {
+        let started = Instant::now();
+        let result = send_request_list(transport, request_list.clone(), cookie_jar, lenient, checks);
+        let elapsed = started.elapsed().as_millis();
+
+        match result {
+            Err(e) if attempt <= retry.retries && retry::is_retryable(&e) => {
+                let delay = retry.delay(attempt);
+                if verbose {
+                    eprintln!("attempt {} to {} failed after {}ms: {}; retrying in {}ms", attempt, nameserver, elapsed, output::error_message(e), delay.as_millis());
+                }
+
+                std::thread::sleep(delay);
+                attempt += 1;
+            }
+            Err(e) => {
+                if verbose {
+                    eprintln!("attempt {} to {} failed after {}ms", attempt, nameserver, elapsed);
+                }
+
+                return Err(e);
+            }
+            Ok(response) => {
+                if verbose {
+                    eprintln!("attempt {} to {} got a response after {}ms", attempt, nameserver, elapsed);
+                }
+
+                return Ok(response);
+            }
+        }
+    }
+}
+
+/// Sends each request in a list in turn, stopping at the first one that
+/// gets a response without an error code, and returning the response to the
+/// last request otherwise. The list has more than one request when the
+/// resolver has a search list.
+///
+/// Any server cookie in a response is stored in the server’s cookie jar, and
+/// sent back with the requests after it, including the ones in later lists.
+/// If the server rejects a request’s cookie but sends a new one, the request
+/// gets sent again once with the new cookie.
+///
+/// Responses that were served from an HTTP cache have their TTLs brought
+/// down by the time they spent there.
+fn send_request_list(transport: &dyn dns_transport::Transport, request_list: Vec<dns::Request>, cookie_jar: &mut cookies::CookieJar, lenient: bool, checks: dns_transport::ResponseChecks) -> Result<dns::Response, dns_transport::Error> {
+    let mut requests = request_list.into_iter().peekable();
+
+    while let Some(mut request) = requests.next() {
+        cookie_jar.attach(&mut request);
+        let mut response = send_request(transport, &request, lenient, checks)?;
+
+        if cookie_jar.store(&response) && response.has_bad_cookie() {
+            info!("Retrying with new server cookie");
+            cookie_jar.attach(&mut request);
+            response = send_request(transport, &request, lenient, checks)?;
+            cookie_jar.store(&response);
+        }
+
+        if response.flags.error_code.is_none() || requests.peek().is_none() {
+            return Ok(response);
+        }
+    }
+
+    unreachable!("empty request list");
+}
+
+/// Sends one request, and if the response came out of an HTTP cache,
+/// subtracts its age from its TTLs and says how old it was, warning if the
+/// cache held on to it for longer than it should have. Also warns about
+/// any UDP packets that arrived while waiting that were not the response,
+/// notes when the query had to be sent again over TCP because the response
+/// over UDP was truncated, and warns when the response is still truncated.
+/// When parsing leniently, warns about any records that were skipped.
+///
+/// The response has to match the request, unless the checks say to trust
+/// anything, in which case each way it does not match gets warned about.
+fn send_request(transport: &dyn dns_transport::Transport, request: &dns::Request, lenient: bool, checks: dns_transport::ResponseChecks) -> Result<dns::Response, dns_transport::Error> {
+    let ignored_before = transport.ignored_packets();
+    let fallbacks_before = transport.tcp_fallbacks();
+    let result = if lenient {
+        transport.send_lenient(request).map(|partial| {
+            warn_about_skipped_records(partial.errors);
+            (partial.response, None)
+        })
+    }
+    else {
+        transport.send_via_cache(request)
+    };
+
+    let ignored = transport.ignored_packets() - ignored_before;
+    if ignored == 1 {
+        eprintln!("warning: ignored 1 UDP packet that was not the response to the query for {}", request.query.qname);
+    }
+    else if ignored > 1 {
+        eprintln!("warning: ignored {} UDP packets that were not the response to the query for {}", ignored, request.query.qname);
+    }
+
+    if transport.tcp_fallbacks() > fallbacks_before {
+        eprintln!("note: the response over UDP for {} was truncated, so the query was sent again over TCP", request.query.qname);
+    }
+
+    let (mut response, caching) = result?;
+
+    if response.flags.truncated {
+        eprintln!("warning: the response for {} was truncated, so records may be missing", request.query.qname);
+    }
+
+    let mut mismatches = transport.take_mismatches();
+    if let Err(mismatch) = dns_transport::check_response(request, &response) {
+        if checks == dns_transport::ResponseChecks::Strict {
+            return Err(mismatch.into());
+        }
+
+        mismatches.push(mismatch);
+    }
+
+    for mismatch in mismatches {
+        eprintln!("warning: accepted the response to the query for {} anyway: {}", request.query.qname, mismatch);
+    }
+
+    if let Some(caching) = caching {
+        debug!("HTTP caching -> {:?}", caching);
+
+        if let Some(age) = caching.age {
+            let any_expired = http_cache::subtract_age(&mut response, age);
+
+            match caching.max_age {
+                Some(max_age) => eprintln!("note: response was served from an HTTP cache, {}s old (max-age {}s)", age, max_age),
+                None          => eprintln!("note: response was served from an HTTP cache, {}s old", age),
+            }
+
+            if caching.is_stale() || any_expired {
+                eprintln!("warning: the HTTP cache served a stale DNS answer");
+            }
+        }
+    }
+
+    Ok(response)
+}
+
+/// Warns about each record that was skipped over because it failed to parse,
+/// when parsing leniently.
+fn warn_about_skipped_records(errors: Vec<dns::RecordError>) {
+    for error in errors {
+        let section = match error.section {
+            dns::Section::Question    => "question",
+            dns::Section::Answer      => "answer",
+            dns::Section::Authority   => "authority",
+            dns::Section::Additional  => "additional",
+        };
+
+        eprintln!("warning: skipped {} record {}: {}", section, error.index, output::error_message(dns_transport::Error::WireError(error.error)));
+    }
+}
+
+/// Validates the DNSSEC signatures on a response’s answers, sending the
+/// queries for the DS and DNSKEY records that the chain of trust needs to
+/// the same nameserver, over the same transport, as the response came from.
+/// Returns the query along with the verdict.
+#[cfg(feature = "with_dnssec")]
+fn validate_response(transport: &dyn dns_transport::Transport, response: &dns::Response, txid_generator: txid::TxidGenerator, checks: dns_transport::ResponseChecks) -> (Option<dns::Query>, validate::Verdict) {
+    let lookup = |qname: &dns::Labels, qtype| {
+        // Checking is disabled so that a validating resolver passes on
+        // records it thinks are bogus, for the reason to be found here.
+        let mut flags = dns::Flags::query();
+        flags.checking_disabled = true;
+
+        let mut opt = dns::Request::additional_record();
+        opt.flags |= requests::DNSSEC_OK_FLAG;
+
+        let query = dns::Query { qname: qname.clone(), qclass: dns::QClass::IN, qtype };
+        let request = dns::Request { transaction_id: txid_generator.generate(), flags, query, additional: Some(opt) };
+        send_request(transport, &request, false, checks).map_err(output::error_message)
+    };
+
+    let verdict = validate::Validator::new(lookup, validate::current_time()).validate(response);
+    (response.queries.first().cloned(), verdict)
+}
+
+/// Prints the verdict for each response that was validated, returning
+/// whether any of them were bogus.
+#[cfg(feature = "with_dnssec")]
+fn print_verdicts(verdicts: &[(Option<dns::Query>, validate::Verdict)]) -> bool {
+    use validate::Verdict;
+
+    let mut any_bogus = false;
+
+    for (query, verdict) in verdicts {
+        let what = match query {
+            Some(query)  => format!("{} {}", query.qname, output::record_type_name(query.qtype.type_number())),
+            None         => String::from("response"),
+        };
+
+        match verdict {
+            Verdict::Secure                 => eprintln!("note: {} is secure", what),
+            Verdict::Denied(denial)         => eprintln!("note: {} is secure: {}", what, denial),
+            Verdict::Insecure               => eprintln!("note: {} is insecure, as no chain of trust reaches it", what),
+            Verdict::Indeterminate(reason)  => eprintln!("note: could not validate {}: {}", what, reason),
+            Verdict::Bogus(reason) => {
+                eprintln!("warning: {} is bogus: {}", what, reason);
+                any_bogus = true;
+            }
+        }
+    }
+
+    any_bogus
+}
+
+/// Whether an answer is an RRSIG record, for hiding signatures.
+fn is_signature(answer: &dns::Answer) -> bool {
+    matches!(answer, dns::Answer::Standard { record: dns::record::Record::RRSIG(_), .. })
+}
+
+
+/// Runs dog in entropy-checking mode, sending the same query many times and
+/// reporting how random the local source ports and transaction IDs were.
+fn run_entropy_check(Options { requests, .. }: Options, count: usize) -> i32 {
+    match entropy::run_check(requests, count) {
+        Ok(report) => {
+            print!("{}", report);
+
+            if report.unanswered == report.sent {
+                exits::NETWORK_ERROR
+            }
+            else {
+                exits::SUCCESS
+            }
+        }
+        Err(e) => {
+            eprintln!("Unable to obtain resolver: {}", e);
+            exits::SYSTEM_ERROR
+        }
+    }
+}
+
+
+/// Runs dog in monitor mode, sending the queries again every interval until
+/// it is told to stop. Reloading the settings parses the command-line
+/// arguments again, which picks up any changes to the system’s nameservers
+/// and to any files that were named in them.
+fn run_monitor(Options { requests, monitor, .. }: Options) -> i32 {
+    let settings = monitor.expect("no monitor settings");
+    let reload = || match Options::getopts(std::env::args_os().skip(1)) {
+        OptionsResult::Ok(options) => Some(options.requests),
+        _                          => None,
+    };
+
+    match monitor::run_monitor(requests, &settings, reload) {
+        Ok(stats) => {
+            eprint!("{}", stats.series.heatmap(monitor::DRAWN_ROUNDS));
+            eprintln!("{}", stats);
+
+            if stats.answered > 0 {
+                exits::SUCCESS
+            }
+            else {
+                exits::NETWORK_ERROR
+            }
+        }
+        Err(e) => {
+            eprintln!("dog: {}", e);
+            exits::SYSTEM_ERROR
+        }
+    }
+}
+
+
+/// Runs dog in filter-checking mode, querying domains that filtering
+/// resolvers commonly block and reporting whether the nameserver blocked
+/// or redirected them.
+fn run_filter_check(Options { requests, .. }: Options) -> i32 {
+    match filtering::run_check(requests) {
+        Ok(report) => {
+            print!("{}", report);
+
+            if report.control_resolved() {
+                exits::SUCCESS
+            }
+            else {
+                exits::NETWORK_ERROR
+            }
+        }
+        Err(e) => {
+            eprintln!("Unable to obtain resolver: {}", e);
+            exits::SYSTEM_ERROR
+        }
+    }
+}
+
+
+/// Runs dog in multiple-question mode, sending one message that asks every
+/// query at once to each nameserver, and displaying the responses along with
+/// every question that they echo back.
+fn run_multi_question(Options { requests, format, measure_time, .. }: Options) -> i32 {
+    use std::time::Instant;
+
+    let should_show_opt = requests.edns.should_show();
+    let timer = if measure_time { Some(Instant::now()) } else { None };
+
+    let inputs = &requests.inputs;
+    let asked = inputs.domains.len() * inputs.record_types.len() * inputs.classes.len();
+
+    let messages = match requests.generate_multi_question() {
+        Ok(messages) => messages,
+        Err(e) => {
+            eprintln!("Unable to obtain resolver: {}", e);
+            return exits::SYSTEM_ERROR;
+        }
+    };
+
+    let mut responses = Vec::new();
+    let mut errored = false;
+
+    for (transport, bytes) in messages {
+        let result = transport.send_raw(&bytes)
+            .and_then(|received| Ok(dns::Response::from_bytes(&received)?));
+
+        match result {
+            Ok(response) => {
+                if response.queries.len() != asked {
+                    eprintln!("note: asked {} questions, but the response echoed {}", asked, response.queries.len());
+                }
+
+                responses.push(response);
+            }
+            Err(e) => {
+                format.print_error_json(&output::json_error(e));
+                errored = true;
+            }
+        }
+    }
+
+    let duration = timer.map(|t| t.elapsed());
+    if format.print(responses, duration, should_show_opt) {
+        if errored { exits::NETWORK_ERROR } else { exits::SUCCESS }
+    }
+    else {
+        exits::NO_SHORT_RESULTS
+    }
+}
+
+
+/// Runs dog in cache-snooping mode, sending non-recursive queries for each
+/// domain and reporting which ones the resolver has cached.
+fn run_snoop(Options { requests, .. }: Options) -> i32 {
+    match snoop::run_snoop(requests) {
+        Ok(report) => {
+            print!("{}", report);
+
+            if report.any_answered() {
+                exits::SUCCESS
+            }
+            else {
+                exits::NETWORK_ERROR
+            }
+        }
+        Err(e) => {
+            eprintln!("Unable to obtain resolver: {}", e);
+            exits::SYSTEM_ERROR
+        }
+    }
+}
+
+
+/// Runs dog in port-probing mode, sending a query to the nameserver over
+/// each protocol on its standard port and reporting which ones answered.
+fn run_probe_port(Options { requests, .. }: Options) -> i32 {
+    match probe::run_probe(requests) {
+        Ok(report) => {
+            print!("{}", report);
+
+            if report.answered_count() > 0 {
+                exits::SUCCESS
+            }
+            else {
+                exits::NETWORK_ERROR
+            }
+        }
+        Err(e) => {
+            eprintln!("Unable to obtain resolver: {}", e);
+            exits::SYSTEM_ERROR
+        }
+    }
+}
+
+
+/// Runs dog in zone-transfer-comparing mode, transferring the zone from
+/// each of its nameservers and checking they all serve the same records.
+fn run_axfr_all(Options { requests, .. }: Options) -> i32 {
+    match axfr::run_transfers(requests) {
+        Ok(report) => {
+            print!("{}", report);
+
+            if report.any_failed() {
+                exits::NETWORK_ERROR
+            }
+            else if report.is_consistent() {
+                exits::SUCCESS
+            }
+            else {
+                exits::ZONE_DIFFERENT
+            }
+        }
+        Err(axfr::AxfrError::Resolver(e)) => {
+            eprintln!("Unable to obtain resolver: {}", e);
+            exits::SYSTEM_ERROR
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            exits::NETWORK_ERROR
+        }
+    }
+}
+
+
+/// Runs dog in zone-diffing mode, reading the records in a zone file and
+/// comparing them with the records the nameserver serves.
+fn run_zone_diff(Options { requests, zone_diff, .. }: Options) -> i32 {
+    let path = zone_diff.expect("No zone file");
+
+    let zone = match read_zone_file(&path) {
+        Ok(z) => z,
+        Err(exit_status) => return exit_status,
+    };
+
+    match zonediff::run_diff(requests, zone) {
+        Ok(diff) => {
+            print!("{}", diff);
+
+            if diff.failed_count() > 0 {
+                exits::NETWORK_ERROR
+            }
+            else if diff.is_identical() {
+                exits::SUCCESS
+            }
+            else {
+                exits::ZONE_DIFFERENT
+            }
+        }
+        Err(e) => {
+            eprintln!("Unable to obtain resolver: {}", e);
+            exits::SYSTEM_ERROR
+        }
+    }
+}
+
+
+/// Runs dog in filter mode, forwarding DNS messages from standard input to
+/// the nameserver and writing the responses to standard output. Errors
+/// getting a response are printed to standard error as they happen.
+fn run_filter(Options { requests, format, .. }: Options) -> i32 {
+    use std::io;
+
+    let transport = match requests.first_transport() {
+        Ok(t) => t,
+        Err(e) => {
+            eprintln!("Unable to obtain resolver: {}", e);
+            return exits::SYSTEM_ERROR;
+        }
+    };
+
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    match filter::run(&*transport, stdin.lock(), stdout.lock(), |e| format.print_error(e)) {
+        Ok(stats) => {
+            info!("Forwarded {} messages, {} failed", stats.forwarded, stats.failed);
+
+            if stats.failed > 0 {
+                exits::NETWORK_ERROR
+            }
+            else {
+                exits::SUCCESS
+            }
+        }
+        Err(e) => {
+            eprintln!("dog: Failed to forward messages: {}", e);
+            exits::SYSTEM_ERROR
+        }
+    }
+}
+
+
+/// Runs dog in resolver-showing mode, printing the nameservers from the
+/// command-line and from each of the system sources, in priority order,
+/// and marking the one that queries would be sent to.
+fn run_show_resolvers(options: &Options) -> i32 {
+    use crate::resolve::{ResolverType, Discovery};
+
+    let mut chosen = false;
+
+    for resolver_type in &options.requests.inputs.resolver_types {
+        if let ResolverType::Specific(nameserver) = resolver_type {
+            println!("{} command line: {}", if chosen { " " } else { "*" }, nameserver);
+            chosen = true;
+        }
+    }
+
+    for source in resolve::system_sources() {
+        match source.discover() {
+            Ok(Some(Discovery { nameservers, search_list })) => {
+                let marker = if chosen || nameservers.is_empty() { " " } else { "*" };
+                chosen = chosen || ! nameservers.is_empty();
+
+                if nameservers.is_empty() {
+                    println!("{} {}: no nameservers", marker, source.description());
+                }
+                else if search_list.is_empty() {
+                    println!("{} {}: {}", marker, source.description(), nameservers.join(", "));
+                }
+                else {
+                    println!("{} {}: {} (search: {})", marker, source.description(), nameservers.join(", "), search_list.join(" "));
+                }
+            }
+            Ok(None) => {
+                println!("  {}: not present", source.description());
+            }
+            Err(e) => {
+                println!("  {}: {}", source.description(), e);
+            }
+        }
+    }
+
+    if chosen {
+        exits::SUCCESS
+    }
+    else {
+        exits::SYSTEM_ERROR
+    }
+}
+
+
+/// Answers DNS-over-HTTP queries from the records in a zone file, until
+/// dog gets stopped.
+#[cfg(feature = "with_doh_server")]
+fn run_serve_doh(Options { serve_doh, listen, .. }: Options) -> i32 {
+    use std::net::SocketAddr;
+    use dns_transport::DohServer;
+
+    let path = serve_doh.expect("No zone file");
+
+    let zone = match read_zone_file(&path) {
+        Ok(z) => z,
+        Err(exit_status) => return exit_status,
+    };
+
+    for (line, type_name) in &zone.skipped {
+        eprintln!("dog: Skipping the {} record on line {}, as dog cannot read its data", type_name, line);
+    }
+
+    let address = listen.unwrap_or_else(|| SocketAddr::from(([127, 0, 0, 1], 8053)));
+    let server = match DohServer::bind(address).and_then(|s| s.url().map(|url| (s, url))) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("dog: Failed to listen on {}: {}", address, output::error_message(e));
+            return exits::SYSTEM_ERROR;
+        }
+    };
+
+    let (server, url) = server;
+    eprintln!("dog: Serving {} records from {} at {}", zone.records.len(), path.display(), url);
+
+    match server.serve(|message| serve::answer(&zone, message)) {
+        Ok(()) => exits::SUCCESS,
+        Err(e) => {
+            eprintln!("dog: DoH server failed: {}", output::error_message(e));
+            exits::NETWORK_ERROR
+        }
+    }
+}
+
+/// Reads and parses a zone file, printing the problem and returning the
+/// status to exit with if it cannot be.
+fn read_zone_file(path: &std::path::Path) -> Result<dns::zonefile::Zone, i32> {
+    let file = match std::fs::File::open(path) {
+        Ok(f) => f,
+        Err(e) => {
+            eprintln!("dog: Failed to read zone file {}: {}", path.display(), e);
+            return Err(exits::SYSTEM_ERROR);
+        }
+    };
+
+    dns::zonefile::read(std::io::BufReader::new(file)).map_err(|e| {
+        eprintln!("dog: Invalid zone file {}: {}", path.display(), e);
+        exits::OPTIONS_ERROR
+    })
+}
+
+
 /// Checks whether the options contain parameters that will cause dog to fail
 /// because the feature is disabled by exiting if so.
 #[allow(unused)]
@@ -192,6 +1046,42 @@ fn disabled_feature_check(options: &Options) {
         eprintln!("dog: Cannot use '--https': This version of dog has been compiled without HTTPS support");
         exit(exits::OPTIONS_ERROR);
     }
+
+    #[cfg(not(feature = "with_http3"))]
+    if options.requests.inputs.transport_types.contains(&TransportType::HTTP3) {
+        eprintln!("dog: Cannot use '--http3': This version of dog has been compiled without HTTP/3 support");
+        exit(exits::OPTIONS_ERROR);
+    }
+
+    #[cfg(not(feature = "with_quic"))]
+    if options.requests.inputs.transport_types.contains(&TransportType::QUIC) {
+        eprintln!("dog: Cannot use '--quic': This version of dog has been compiled without QUIC support");
+        exit(exits::OPTIONS_ERROR);
+    }
+
+    #[cfg(not(feature = "with_doh_server"))]
+    if options.serve_doh.is_some() {
+        eprintln!("dog: Cannot use '--serve-doh': This version of dog has been compiled without DoH server support");
+        exit(exits::OPTIONS_ERROR);
+    }
+
+    #[cfg(not(feature = "with_dnssec"))]
+    if options.validate {
+        eprintln!("dog: Cannot use '--validate': This version of dog has been compiled without DNSSEC support");
+        exit(exits::OPTIONS_ERROR);
+    }
+
+    #[cfg(not(feature = "with_tsig"))]
+    if options.requests.protocol_tweaks.tsig_key.is_some() {
+        eprintln!("dog: Cannot use '--tsig': This version of dog has been compiled without TSIG support");
+        exit(exits::OPTIONS_ERROR);
+    }
+
+    #[cfg(not(feature = "with_sig0"))]
+    if options.requests.protocol_tweaks.sig0_key.is_some() {
+        eprintln!("dog: Cannot use '--sig0': This version of dog has been compiled without SIG(0) support (the with_sig0 feature)");
+        exit(exits::OPTIONS_ERROR);
+    }
 }
 
 
@@ -213,4 +1103,16 @@ mod exits {
 
     /// Exit code for when the system network configuration could not be determined.
     pub const SYSTEM_ERROR: i32 = 4;
+
+    /// Exit code for when the records a nameserver serves differ from the
+    /// records in the zone file they were compared with, or from the
+    /// records the zone’s other nameservers serve.
+    pub const ZONE_DIFFERENT: i32 = 5;
+
+    /// Exit code for when a response has a chain of CNAME records that
+    /// loops, or that is too long for resolvers to follow.
+    pub const BAD_CNAME_CHAIN: i32 = 6;
+
+    /// Exit code for when DNSSEC validation found a response to be bogus.
+    pub const DNSSEC_BOGUS: i32 = 7;
 }
@@ -0,0 +1,493 @@
+//! Monitoring a nameserver, by sending the same queries to it over and over
+//! at a fixed interval, and keeping statistics on how it answers them.
+//!
+//! Monitor mode keeps running until it is stopped, so it can be run as a
+//! service. It can write its process ID to a file, it reloads its settings
+//! when it receives SIGHUP, and when it receives SIGINT or SIGTERM, it
+//! finishes the round of queries it is in the middle of and writes its
+//! statistics to a file before exiting. When it has been started by systemd
+//! as a `Type=notify` service, it tells systemd when it is ready, when it is
+//! reloading, and when it is stopping.
+//!
+//! The response times from each round are kept as well, and get drawn by
+//! the `heatmap` module, so slowness that comes and goes stands out.
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use dns::Response;
+use dns_transport::Transport;
+use json::object;
+use log::*;
+
+use crate::heatmap::{Round, Series};
+use crate::output::{error_message, record_type_name, status_mnemonic};
+use crate::requests::{RequestGenerator, ProtocolTweaks, UseEDNS};
+use crate::resolve::ResolverLookupError;
+use crate::txid::TxidGenerator;
+
+
+/// How long to sleep for at a time while waiting for the next round, so
+/// that signals get noticed soon after they arrive.
+const SLEEP_STEP: Duration = Duration::from_millis(100);
+
+/// How many of the most recent rounds get drawn in the terminal.
+pub const DRAWN_ROUNDS: usize = 60;
+
+
+/// Where monitor mode keeps its files, and how often it sends its queries.
+#[derive(PartialEq, Debug)]
+pub struct MonitorSettings {
+
+    /// How long to wait between the start of one round of queries and the
+    /// start of the next.
+    pub interval: Duration,
+
+    /// The file to write the process ID to, if any.
+    pub pidfile: Option<PathBuf>,
+
+    /// The file to write the statistics to when stopping, if any.
+    pub stats_file: Option<PathBuf>,
+}
+
+/// The statistics gathered while monitoring.
+#[derive(PartialEq, Debug, Default)]
+pub struct Statistics {
+
+    /// The number of rounds of queries that were sent.
+    pub rounds: usize,
+
+    /// The number of queries that were sent.
+    pub sent: usize,
+
+    /// The number of queries that got a response.
+    pub answered: usize,
+
+    /// The number of queries that did not get a response.
+    pub failed: usize,
+
+    /// The number of times the settings were reloaded.
+    pub reloads: usize,
+
+    /// The quickest that a response arrived.
+    pub fastest: Option<Duration>,
+
+    /// The slowest that a response arrived.
+    pub slowest: Option<Duration>,
+
+    /// The time taken by every response put together, for working out the
+    /// mean.
+    pub total_time: Duration,
+
+    /// The responses from each of the most recent rounds, sorted into
+    /// latency buckets.
+    pub series: Series,
+}
+
+impl Statistics {
+
+    /// Counts one query that got a response after the given time, or that
+    /// failed if there is no time.
+    fn record(&mut self, outcome: Option<Duration>) {
+        self.sent += 1;
+
+        match outcome {
+            Some(elapsed) => {
+                self.answered += 1;
+                self.total_time += elapsed;
+                self.fastest = Some(self.fastest.map_or(elapsed, |t| t.min(elapsed)));
+                self.slowest = Some(self.slowest.map_or(elapsed, |t| t.max(elapsed)));
+            }
+            None => {
+                self.failed += 1;
+            }
+        }
+    }
+
+    /// The mean time that a response took to arrive.
+    pub fn mean(&self) -> Option<Duration> {
+        let answered = u32::try_from(self.answered).ok().filter(|a| *a > 0)?;
+        Some(self.total_time / answered)
+    }
+
+    /// Serialises these statistics as JSON, for the stats file. Times are
+    /// in milliseconds, and are null when nothing was answered.
+    pub fn to_json(&self) -> json::JsonValue {
+        let millis = |t: Option<Duration>| t.map(|t| t.as_secs_f64() * 1000.0);
+
+        object! {
+            "rounds": self.rounds,
+            "sent": self.sent,
+            "answered": self.answered,
+            "failed": self.failed,
+            "reloads": self.reloads,
+            "latency_ms": object! {
+                "min": millis(self.fastest),
+                "mean": millis(self.mean()),
+                "max": millis(self.slowest),
+            },
+            "series": self.series.to_json(),
+        }
+    }
+}
+
+impl fmt::Display for Statistics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} rounds, {} queries sent, {} answered, {} failed", self.rounds, self.sent, self.answered, self.failed)?;
+
+        if let (Some(fastest), Some(mean), Some(slowest)) = (self.fastest, self.mean(), self.slowest) {
+            write!(f, ", latency min/mean/max {}/{}/{}ms", fastest.as_millis(), mean.as_millis(), slowest.as_millis())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Something that can stop monitor mode from starting, or from finishing
+/// cleanly.
+#[derive(Debug)]
+pub enum MonitorError {
+
+    /// The nameserver to send the queries to could not be found.
+    Lookup(ResolverLookupError),
+
+    /// The signal handlers could not be installed.
+    Signals(io::Error),
+
+    /// The process ID could not be written to the pidfile.
+    Pidfile(PathBuf, io::Error),
+
+    /// The statistics could not be written to the stats file.
+    StatsFile(PathBuf, io::Error),
+}
+
+impl fmt::Display for MonitorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Lookup(e)           => write!(f, "Unable to obtain resolver: {}", e),
+            Self::Signals(e)          => write!(f, "Unable to install signal handlers: {}", e),
+            Self::Pidfile(path, e)    => write!(f, "Unable to write pidfile {}: {}", path.display(), e),
+            Self::StatsFile(path, e)  => write!(f, "Unable to write stats file {}: {}", path.display(), e),
+        }
+    }
+}
+
+
+/// Sends a query for each domain and type in the inputs to the first
+/// nameserver, using the first transport type, once every interval, until
+/// SIGINT or SIGTERM arrives. Each response is printed as a line with its
+/// status and how long it took. When SIGHUP arrives, the settings are
+/// loaded again using the `reload` function, which gets a new set of
+/// requests from the same command-line arguments; if that fails, the old
+/// settings are kept.
+///
+/// Returns the statistics when stopped, after writing them to the stats
+/// file if there is one.
+pub fn run_monitor(requests: RequestGenerator, settings: &MonitorSettings, reload: impl Fn() -> Option<RequestGenerator>) -> Result<Statistics, MonitorError> {
+    let mut target = Target::load(requests).map_err(MonitorError::Lookup)?;
+    let signals = Signals::install().map_err(MonitorError::Signals)?;
+
+    let _pidfile = match &settings.pidfile {
+        Some(path)  => Some(Pidfile::create(path).map_err(|e| MonitorError::Pidfile(path.clone(), e))?),
+        None        => None,
+    };
+
+    let mut stats = Statistics::default();
+    notify("READY=1");
+
+    while ! signals.should_stop() {
+        let round_started = Instant::now();
+        stats.rounds += 1;
+        let mut round = Round::new(stats.rounds);
+
+        for query in &target.queries {
+            let request = target.request(query);
+            let started = Instant::now();
+
+            match target.transport.send(&request) {
+                Ok(response) => {
+                    let elapsed = started.elapsed();
+                    println!("{}\t{}", response_line(&response, query), format_millis(elapsed));
+                    stats.record(Some(elapsed));
+                    round.record(Some(elapsed));
+                }
+                Err(e) if interrupted(&e) => {
+                    info!("Query interrupted by a signal, so not counting it");
+                }
+                Err(e) => {
+                    println!("ERROR\t{}\t{}\t{}", query.qname, record_type_name(query.qtype.type_number()), error_message(e));
+                    stats.record(None);
+                    round.record(None);
+                }
+            }
+        }
+
+        stats.series.push(round);
+        notify(&format!("STATUS={}", stats));
+
+        // The sparkline is only drawn when someone is watching, so that it
+        // stays out of the logs when running as a service.
+        if atty::is(atty::Stream::Stderr) {
+            eprintln!("latency |{}|", stats.series.sparkline(DRAWN_ROUNDS));
+        }
+
+        // Wait out the rest of the interval, in small steps, so a signal
+        // that arrives in the meantime gets dealt with straight away.
+        while round_started.elapsed() < settings.interval && ! signals.should_stop() {
+            if signals.take_reload() {
+                notify("RELOADING=1");
+
+                match reload().map(Target::load) {
+                    Some(Ok(new_target)) => {
+                        info!("Reloaded settings");
+                        target = new_target;
+                        stats.reloads += 1;
+                    }
+                    Some(Err(e)) => {
+                        warn!("Failed to reload settings, keeping the old ones: {}", e);
+                    }
+                    None => {
+                        warn!("Failed to reload settings, keeping the old ones");
+                    }
+                }
+
+                notify("READY=1");
+            }
+
+            thread::sleep(SLEEP_STEP.min(settings.interval.saturating_sub(round_started.elapsed())));
+        }
+    }
+
+    notify("STOPPING=1");
+
+    if let Some(path) = &settings.stats_file {
+        fs::write(path, stats.to_json().pretty(2) + "\n")
+            .map_err(|e| MonitorError::StatsFile(path.clone(), e))?;
+    }
+
+    Ok(stats)
+}
+
+/// Formats a response as the start of a line of monitor mode output: its
+/// status, and the name and type that were queried.
+fn response_line(response: &Response, query: &dns::Query) -> String {
+    format!("{}\t{}\t{}", status_mnemonic(response.flags.error_code), query.qname, record_type_name(query.qtype.type_number()))
+}
+
+/// Whether sending a query failed only because a signal arrived while it
+/// was waiting for the response, in which case it says nothing about the
+/// nameserver.
+fn interrupted(error: &dns_transport::Error) -> bool {
+    matches!(error, dns_transport::Error::NetworkError(e) if e.kind() == io::ErrorKind::Interrupted)
+}
+
+/// Formats a duration as a whole number of milliseconds.
+fn format_millis(duration: Duration) -> String {
+    format!("{}ms", duration.as_millis())
+}
+
+
+/// The nameserver that monitor mode sends its queries to, along with the
+/// queries and how to build the requests for them.
+struct Target {
+    transport: Box<dyn Transport>,
+    queries: Vec<dns::Query>,
+    edns: UseEDNS,
+    protocol_tweaks: ProtocolTweaks,
+    txid_generator: TxidGenerator,
+}
+
+impl Target {
+
+    /// Picks out the queries from the inputs, and creates the transport to
+    /// send them over.
+    fn load(requests: RequestGenerator) -> Result<Self, ResolverLookupError> {
+        let edns = requests.edns;
+        let protocol_tweaks = requests.protocol_tweaks.clone();
+        let txid_generator = requests.txid_generator;
+
+        let mut queries = Vec::new();
+        for qname in &requests.inputs.domains {
+            for qtype in requests.inputs.record_types.iter().copied() {
+                for qclass in requests.inputs.classes.iter().copied() {
+                    queries.push(dns::Query { qname: qname.clone(), qtype, qclass });
+                }
+            }
+        }
+
+        let transport = requests.first_transport()?;
+        Ok(Self { transport, queries, edns, protocol_tweaks, txid_generator })
+    }
+
+    /// Builds a request for a query, with a new transaction ID each time.
+    fn request(&self, query: &dns::Query) -> dns::Request {
+        let mut flags = dns::Flags::query();
+        self.protocol_tweaks.set_request_flags(&mut flags);
+
+        let mut additional = None;
+        if self.edns.should_send() {
+            let mut opt = dns::Request::additional_record();
+            self.protocol_tweaks.set_request_opt_fields(&mut opt);
+            additional = Some(opt);
+        }
+
+        dns::Request { transaction_id: self.txid_generator.generate(), flags, query: query.clone(), additional }
+    }
+}
+
+
+/// Flags that get set when the signals that monitor mode listens for
+/// arrive.
+struct Signals {
+    stop: Arc<AtomicBool>,
+    reload: Arc<AtomicBool>,
+}
+
+impl Signals {
+
+    /// Installs handlers that set the stop flag on SIGINT and SIGTERM, and
+    /// the reload flag on SIGHUP, where there is such a signal.
+    fn install() -> io::Result<Self> {
+        use signal_hook::consts::{SIGINT, SIGTERM};
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let reload = Arc::new(AtomicBool::new(false));
+
+        signal_hook::flag::register(SIGINT, Arc::clone(&stop))?;
+        signal_hook::flag::register(SIGTERM, Arc::clone(&stop))?;
+
+        #[cfg(unix)]
+        signal_hook::flag::register(signal_hook::consts::SIGHUP, Arc::clone(&reload))?;
+
+        Ok(Self { stop, reload })
+    }
+
+    /// Whether monitor mode has been told to stop.
+    fn should_stop(&self) -> bool {
+        self.stop.load(Ordering::Relaxed)
+    }
+
+    /// Whether monitor mode has been told to reload its settings since the
+    /// last time this was checked.
+    fn take_reload(&self) -> bool {
+        self.reload.swap(false, Ordering::Relaxed)
+    }
+}
+
+
+/// A file holding the process ID of dog, which gets removed again when
+/// this is dropped.
+struct Pidfile(PathBuf);
+
+impl Pidfile {
+    fn create(path: &Path) -> io::Result<Self> {
+        fs::write(path, format!("{}\n", std::process::id()))?;
+        Ok(Self(path.to_path_buf()))
+    }
+}
+
+impl Drop for Pidfile {
+    fn drop(&mut self) {
+        if let Err(e) = fs::remove_file(&self.0) {
+            warn!("Failed to remove pidfile {}: {}", self.0.display(), e);
+        }
+    }
+}
+
+
+/// Tells systemd about a change in state, such as `READY=1`, if it started
+/// dog as a `Type=notify` service, in which case it sets `NOTIFY_SOCKET`
+/// to the path of the socket to send the state to. A path beginning with
+/// `@` is an abstract socket, which only exists on Linux. Failing to send
+/// the state is only warned about, as the monitoring can go on without it.
+fn notify(state: &str) {
+    #[cfg(unix)]
+    if let Some(socket_path) = std::env::var_os("NOTIFY_SOCKET") {
+        if let Err(e) = send_notification(&socket_path, state) {
+            warn!("Failed to notify systemd: {}", e);
+        }
+    }
+
+    #[cfg(not(unix))]
+    let _ = state;
+}
+
+/// Sends a state to the notification socket at the given path.
+#[cfg(unix)]
+fn send_notification(socket_path: &std::ffi::OsStr, state: &str) -> io::Result<()> {
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::net::UnixDatagram;
+
+    let socket = UnixDatagram::unbound()?;
+
+    match socket_path.as_bytes().strip_prefix(b"@") {
+        #[cfg(target_os = "linux")]
+        Some(name) => {
+            use std::os::linux::net::SocketAddrExt;
+            let address = std::os::unix::net::SocketAddr::from_abstract_name(name)?;
+            socket.send_to_addr(state.as_bytes(), &address)?;
+        }
+        #[cfg(not(target_os = "linux"))]
+        Some(_) => {
+            return Err(io::Error::new(io::ErrorKind::Unsupported, "abstract sockets are only supported on Linux"));
+        }
+        None => {
+            socket.send_to(state.as_bytes(), socket_path)?;
+        }
+    }
+
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn statistics_of_nothing() {
+        let stats = Statistics::default();
+        assert_eq!(stats.mean(), None);
+        assert_eq!(stats.to_string(), "0 rounds, 0 queries sent, 0 answered, 0 failed");
+    }
+
+    #[test]
+    fn statistics_of_some_queries() {
+        let mut stats = Statistics { rounds: 2, .. Statistics::default() };
+        stats.record(Some(Duration::from_millis(10)));
+        stats.record(None);
+        stats.record(Some(Duration::from_millis(30)));
+
+        assert_eq!(stats.mean(), Some(Duration::from_millis(20)));
+        assert_eq!(stats.to_string(), "2 rounds, 3 queries sent, 2 answered, 1 failed, latency min/mean/max 10/20/30ms");
+    }
+
+    #[test]
+    fn statistics_json() {
+        let mut stats = Statistics { rounds: 1, .. Statistics::default() };
+        stats.record(Some(Duration::from_millis(5)));
+
+        let json = stats.to_json();
+        assert_eq!(json["sent"], 1);
+        assert_eq!(json["latency_ms"]["min"], 5.0);
+        assert_eq!(json["latency_ms"]["max"], 5.0);
+    }
+
+    #[test]
+    fn statistics_json_without_answers() {
+        let mut stats = Statistics::default();
+        stats.record(None);
+
+        let json = stats.to_json();
+        assert_eq!(json["failed"], 1);
+        assert!(json["latency_ms"]["mean"].is_null());
+    }
+}
@@ -0,0 +1,163 @@
+//! Checking the parameters in NSEC3 and NSEC3PARAM records against current
+//! best practice, as many signed zones still use the extra hash iterations
+//! and salts that were once recommended, which cost validators time and
+//! give no extra protection.
+//!
+//! # References
+//!
+//! - [RFC 9276](https://tools.ietf.org/html/rfc9276) — Guidance for NSEC3
+//!   Parameter Settings (August 2022)
+
+use dns::{Answer, Response};
+use dns::record::Record;
+
+
+/// The iteration count above which validators are known to treat a zone
+/// as insecure, or to refuse to validate it at all. RFC 9276 leaves the
+/// exact number to implementations, which have settled on 100 or lower.
+const INSECURE_ITERATIONS: u16 = 100;
+
+
+/// Returns one warning for each problem with the NSEC3 parameters of each
+/// zone that the responses have NSEC3 or NSEC3PARAM records for. A zone
+/// usually has many NSEC3 records with the same parameters, so each set of
+/// parameters only gets warned about once per zone.
+pub fn warnings(responses: &[Response]) -> Vec<String> {
+    let mut seen = Vec::new();
+    let mut warnings = Vec::new();
+
+    let answers = responses.iter()
+        .flat_map(|r| r.answers.iter().chain(&r.authorities).chain(&r.additionals));
+
+    for answer in answers {
+        let (zone, iterations, salt) = match answer {
+            Answer::Standard { qname, record: Record::NSEC3PARAM(param), .. } => {
+                (qname.to_string(), param.iterations, &param.salt)
+            }
+            Answer::Standard { qname, record: Record::NSEC3(nsec3), .. } => {
+                (parent_zone(&qname.to_string()), nsec3.iterations, &nsec3.salt)
+            }
+            _ => continue,
+        };
+
+        if seen.contains(&(zone.clone(), iterations, salt)) {
+            continue;
+        }
+
+        warnings.extend(parameter_warnings(&zone, iterations, salt));
+        seen.push((zone, iterations, salt));
+    }
+
+    warnings
+}
+
+/// Returns the warnings for one zone’s set of NSEC3 parameters.
+fn parameter_warnings(zone: &str, iterations: u16, salt: &[u8]) -> Vec<String> {
+    let mut warnings = Vec::new();
+
+    if iterations > INSECURE_ITERATIONS {
+        warnings.push(format!("zone '{}' uses NSEC3 with {} extra iterations, which validators may treat as insecure (RFC 9276 recommends 0)", zone, iterations));
+    }
+    else if iterations > 0 {
+        warnings.push(format!("zone '{}' uses NSEC3 with {} extra iterations, but RFC 9276 recommends 0", zone, iterations));
+    }
+
+    if ! salt.is_empty() {
+        warnings.push(format!("zone '{}' uses a {}-byte NSEC3 salt, but RFC 9276 recommends no salt", zone, salt.len()));
+    }
+
+    warnings
+}
+
+/// Works out the zone an NSEC3 record belongs to from its owner name, which
+/// is the hashed name as a single label in front of the zone’s name.
+fn parent_zone(owner: &str) -> String {
+    match owner.split_once('.') {
+        Some((_, zone)) if ! zone.is_empty()  => zone.into(),
+        _                                     => ".".into(),
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use dns::{Flags, Labels, QClass};
+    use dns::record::{NSEC3, NSEC3PARAM};
+
+    fn response(answers: Vec<Answer>) -> Response {
+        Response {
+            transaction_id: 0,
+            flags: Flags::query(),
+            queries: Vec::new(),
+            answers,
+            authorities: Vec::new(),
+            additionals: Vec::new(),
+        }
+    }
+
+    fn answer(qname: &str, record: Record) -> Answer {
+        Answer::Standard {
+            qname: Labels::encode(qname).unwrap(),
+            qclass: QClass::IN,
+            ttl: 3600,
+            record,
+        }
+    }
+
+    fn nsec3(iterations: u16, salt: Vec<u8>) -> Record {
+        Record::NSEC3(NSEC3 {
+            hash_algorithm: 1,
+            flags: 0,
+            iterations,
+            salt,
+            next_hashed_owner: vec![ 0x12, 0x34 ],
+            types: vec![ 1 ],
+        })
+    }
+
+    #[test]
+    fn best_practice() {
+        let param = Record::NSEC3PARAM(NSEC3PARAM { hash_algorithm: 1, flags: 0, iterations: 0, salt: vec![] });
+        let responses = vec![ response(vec![ answer("example.com", param) ]) ];
+        assert_eq!(warnings(&responses), Vec::<String>::new());
+    }
+
+    #[test]
+    fn legacy_param() {
+        let param = Record::NSEC3PARAM(NSEC3PARAM { hash_algorithm: 1, flags: 0, iterations: 10, salt: vec![ 0xab, 0xcd ] });
+        let responses = vec![ response(vec![ answer("example.com", param) ]) ];
+        assert_eq!(warnings(&responses), vec![
+            String::from("zone 'example.com.' uses NSEC3 with 10 extra iterations, but RFC 9276 recommends 0"),
+            String::from("zone 'example.com.' uses a 2-byte NSEC3 salt, but RFC 9276 recommends no salt"),
+        ]);
+    }
+
+    #[test]
+    fn too_many_iterations() {
+        let responses = vec![ response(vec![ answer("0p9mhaveqvm6t7vbl5lop2u3t2rp3tom.example.com", nsec3(150, vec![])) ]) ];
+        assert_eq!(warnings(&responses), vec![
+            String::from("zone 'example.com.' uses NSEC3 with 150 extra iterations, which validators may treat as insecure (RFC 9276 recommends 0)"),
+        ]);
+    }
+
+    #[test]
+    fn warned_once_per_zone() {
+        let responses = vec![ response(vec![
+            answer("0p9mhaveqvm6t7vbl5lop2u3t2rp3tom.example.com", nsec3(5, vec![])),
+            answer("35mthgpgcu1qg68fab165klnsnk3dpvl.example.com", nsec3(5, vec![])),
+            answer("b4um86eghhds6nea196smvmlo4ors995.example.org", nsec3(5, vec![])),
+        ]) ];
+
+        assert_eq!(warnings(&responses), vec![
+            String::from("zone 'example.com.' uses NSEC3 with 5 extra iterations, but RFC 9276 recommends 0"),
+            String::from("zone 'example.org.' uses NSEC3 with 5 extra iterations, but RFC 9276 recommends 0"),
+        ]);
+    }
+
+    #[test]
+    fn zones() {
+        assert_eq!(parent_zone("abc.example.com."), "example.com.");
+        assert_eq!(parent_zone("abc."), ".");
+    }
+}
@@ -0,0 +1,280 @@
+//! Checking which protocols a nameserver answers DNS queries over, by
+//! sending the same query to each of the standard DNS ports.
+
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, Instant};
+
+use dns::{ErrorCode, Response};
+use dns::record::RecordType;
+use dns_transport::{Transport, UdpTransport, TcpTransport, TlsTransport, HttpsTransport};
+use log::*;
+
+use crate::output::status_mnemonic;
+use crate::requests::RequestGenerator;
+use crate::resolve::ResolverLookupError;
+
+
+/// How long to wait for a response over UDP before deciding that nothing
+/// is listening, if no timeout has been given. The connection-based
+/// protocols fail on their own.
+const UDP_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// The path that DNS-over-HTTPS servers conventionally answer queries on,
+/// as used in the examples in RFC 8484.
+const DOH_WELL_KNOWN_PATH: &str = "/dns-query";
+
+
+/// One of the protocols that DNS can be carried over, along with the port
+/// that it uses by default.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum Protocol {
+    UDP,
+    TCP,
+    TLS,
+    HTTPS,
+    QUIC,
+}
+
+impl Protocol {
+
+    /// Every protocol, in the order they get probed.
+    pub const ALL: [Self; 5] = [ Self::UDP, Self::TCP, Self::TLS, Self::HTTPS, Self::QUIC ];
+
+    /// The port that DNS is served over using this protocol.
+    pub fn port(self) -> u16 {
+        match self {
+            Self::UDP | Self::TCP    => 53,
+            Self::TLS | Self::QUIC   => 853,
+            Self::HTTPS              => 443,
+        }
+    }
+
+    /// The lowercase name of this protocol.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::UDP    => "udp",
+            Self::TCP    => "tcp",
+            Self::TLS    => "tls",
+            Self::HTTPS  => "https",
+            Self::QUIC   => "quic",
+        }
+    }
+}
+
+/// Whether a nameserver answered over one protocol.
+#[derive(PartialEq, Debug)]
+pub enum Availability {
+
+    /// A DNS response arrived after this long, with this response code.
+    Answered(Duration, Option<ErrorCode>),
+
+    /// No usable response arrived.
+    Failed(String),
+
+    /// dog has no transport for this protocol, so it was not probed.
+    Unsupported,
+}
+
+/// The results of probing a nameserver over every protocol.
+#[derive(PartialEq, Debug)]
+pub struct ProbeReport {
+
+    /// The host that was probed.
+    pub host: String,
+
+    /// How the nameserver responded over each protocol.
+    pub results: Vec<(Protocol, Availability)>,
+}
+
+
+/// Sends a query for the root nameservers to the first nameserver in the
+/// inputs over each protocol on its standard port, and reports which ones
+/// got a response.
+pub fn run_probe(mut requests: RequestGenerator) -> Result<ProbeReport, ResolverLookupError> {
+    let host = host_of(&requests.first_nameserver()?);
+
+    let mut flags = dns::Flags::query();
+    requests.protocol_tweaks.set_request_flags(&mut flags);
+
+    let mut additional = None;
+    if requests.edns.should_send() {
+        let mut opt = dns::Request::additional_record();
+        requests.protocol_tweaks.set_request_opt_fields(&mut opt);
+        additional = Some(opt);
+    }
+
+    let query = dns::Query { qname: dns::Labels::root(), qtype: RecordType::NS, qclass: dns::QClass::IN };
+    let timeouts = requests.timeouts;
+    let source = &requests.source;
+
+    let results = Protocol::ALL.iter().map(|protocol| {
+        let transport: Box<dyn Transport> = match protocol {
+            Protocol::UDP    => Box::new(UdpTransport::with_timeout(with_port(&host, protocol.port()), timeouts.read.unwrap_or(UDP_TIMEOUT)).with_source(source.clone())),
+            Protocol::TCP    => Box::new(TcpTransport::new(with_port(&host, protocol.port())).with_timeouts(timeouts).with_source(source.clone())),
+            Protocol::TLS    => Box::new(TlsTransport::new(with_port(&host, protocol.port())).with_timeouts(timeouts).with_source(source.clone())),
+            Protocol::HTTPS  => Box::new(HttpsTransport::new(doh_url(&host), requests.http_headers.clone()).with_timeouts(timeouts).with_source(source.clone())),
+            Protocol::QUIC   => return (*protocol, Availability::Unsupported),
+        };
+
+        let request = dns::Request {
+            transaction_id: requests.txid_generator.generate(),
+            flags,
+            query: query.clone(),
+            additional: additional.clone(),
+        };
+
+        (*protocol, send_and_time(&*transport, &request))
+    }).collect();
+
+    Ok(ProbeReport { host, results })
+}
+
+/// Sends one request, and times how long it takes for the response.
+fn send_and_time(transport: &dyn Transport, request: &dns::Request) -> Availability {
+    let start = Instant::now();
+
+    match transport.send(request) {
+        Ok(Response { flags, .. }) => {
+            Availability::Answered(start.elapsed(), flags.error_code)
+        }
+        Err(e) => {
+            warn!("Probe failed: {:?}", e);
+            Availability::Failed(format!("{:?}", e))
+        }
+    }
+}
+
+/// Strips any port number from a nameserver address, leaving the host
+/// to probe on each protocol’s own port.
+fn host_of(nameserver: &str) -> String {
+    if let Ok(socket_addr) = nameserver.parse::<SocketAddr>() {
+        socket_addr.ip().to_string()
+    }
+    else if nameserver.parse::<IpAddr>().is_ok() {
+        nameserver.into()
+    }
+    else {
+        nameserver.split(':').next().unwrap_or(nameserver).into()
+    }
+}
+
+/// Joins a host and a port together, bracketing IPv6 addresses.
+fn with_port(host: &str, port: u16) -> String {
+    format!("{}:{}", bracketed(host), port)
+}
+
+/// The URL of the DNS-over-HTTPS endpoint at the well-known path on a host.
+fn doh_url(host: &str) -> String {
+    format!("https://{}{}", bracketed(host), DOH_WELL_KNOWN_PATH)
+}
+
+/// Surrounds a host in square brackets if it’s an IPv6 address.
+fn bracketed(host: &str) -> String {
+    match host.parse::<IpAddr>() {
+        Ok(IpAddr::V6(_))  => format!("[{}]", host),
+        _                  => host.into(),
+    }
+}
+
+
+impl ProbeReport {
+
+    /// The number of protocols that the nameserver answered over.
+    pub fn answered_count(&self) -> usize {
+        self.results.iter().filter(|(_, availability)| matches!(availability, Availability::Answered(..))).count()
+    }
+}
+
+impl fmt::Display for Availability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Answered(duration, rcode) => {
+                write!(f, "available ({}, {}ms)", status_mnemonic(*rcode), duration.as_millis())
+            }
+            Self::Failed(why) => {
+                write!(f, "unavailable: {}", why)
+            }
+            Self::Unsupported => {
+                write!(f, "not checked (dog does not support this protocol)")
+            }
+        }
+    }
+}
+
+impl fmt::Display for ProbeReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (protocol, availability) in &self.results {
+            let label = format!("{}/{}", protocol.name(), protocol.port());
+            writeln!(f, "{:<10} {}", label, availability)?;
+        }
+
+        let checked = self.results.iter().filter(|(_, a)| *a != Availability::Unsupported).count();
+        writeln!(f, "{} answers DNS on {} of {} protocols checked", self.host, self.answered_count(), checked)
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn host_of_address() {
+        assert_eq!(host_of("192.0.2.1"), "192.0.2.1");
+    }
+
+    #[test]
+    fn host_of_address_with_port() {
+        assert_eq!(host_of("192.0.2.1:5353"), "192.0.2.1");
+    }
+
+    #[test]
+    fn host_of_ipv6_address() {
+        assert_eq!(host_of("2001:db8::1"), "2001:db8::1");
+    }
+
+    #[test]
+    fn host_of_ipv6_address_with_port() {
+        assert_eq!(host_of("[2001:db8::1]:5353"), "2001:db8::1");
+    }
+
+    #[test]
+    fn host_of_hostname_with_port() {
+        assert_eq!(host_of("dns.example:53"), "dns.example");
+    }
+
+    #[test]
+    fn ports() {
+        assert_eq!(with_port("192.0.2.1", 853), "192.0.2.1:853");
+        assert_eq!(with_port("2001:db8::1", 53), "[2001:db8::1]:53");
+    }
+
+    #[test]
+    fn doh_urls() {
+        assert_eq!(doh_url("192.0.2.1"), "https://192.0.2.1/dns-query");
+        assert_eq!(doh_url("2001:db8::1"), "https://[2001:db8::1]/dns-query");
+    }
+
+    #[test]
+    fn report() {
+        let report = ProbeReport {
+            host: "192.0.2.1".into(),
+            results: vec![
+                (Protocol::UDP,   Availability::Answered(Duration::from_millis(12), None)),
+                (Protocol::TCP,   Availability::Answered(Duration::from_millis(34), Some(ErrorCode::QueryRefused))),
+                (Protocol::TLS,   Availability::Failed("connection refused".into())),
+                (Protocol::QUIC,  Availability::Unsupported),
+            ],
+        };
+
+        assert_eq!(report.answered_count(), 2);
+        assert_eq!(report.to_string(), "\
+            udp/53     available (NOERROR, 12ms)\n\
+            tcp/53     available (REFUSED, 34ms)\n\
+            tls/853    unavailable: connection refused\n\
+            quic/853   not checked (dog does not support this protocol)\n\
+            192.0.2.1 answers DNS on 2 of 3 protocols checked\n");
+    }
+}
@@ -1,8 +1,19 @@
 //! Request generation based on the user’s input arguments.
 
+use std::net::IpAddr;
+use std::rc::Rc;
+
 use crate::connect::TransportType;
-use crate::resolve::{ResolverType, ResolverLookupError};
+use crate::downgrade::DowngradingTransport;
+use crate::resolve::{Resolver, ResolverType, ResolverLookupError};
+use crate::retry::RetryPolicy;
+use crate::special::SpecialUse;
+#[cfg(feature = "with_sig0")]
+use crate::sig0::Sig0Transport;
+#[cfg(feature = "with_tsig")]
+use crate::tsig::SigningTransport;
 use crate::txid::TxidGenerator;
+use crate::url::UrlTarget;
 
 
 /// All the information necessary to generate requests for one or more
@@ -21,6 +32,27 @@ pub struct RequestGenerator {
 
     /// Other weird protocol options.
     pub protocol_tweaks: ProtocolTweaks,
+
+    /// The headers to send with requests over HTTPS.
+    pub http_headers: dns_transport::HttpHeaders,
+
+    /// How closely responses have to match their requests.
+    pub checks: dns_transport::ResponseChecks,
+
+    /// Whether to keep truncated UDP responses, instead of sending the
+    /// query again over TCP.
+    pub ignore_truncation: bool,
+
+    /// How many times to send queries again when they fail, and how long to
+    /// wait in between.
+    pub retry: RetryPolicy,
+
+    /// How long to wait to connect to each nameserver, and how long to wait
+    /// for each response.
+    pub timeouts: dns_transport::Timeouts,
+
+    /// The local address and interface to send queries from.
+    pub source: dns_transport::Source,
 }
 
 /// Which things the user has specified they want queried.
@@ -41,10 +73,14 @@ pub struct Inputs {
 
     /// The list of transport types to send queries over.
     pub transport_types: Vec<TransportType>,
+
+    /// The URLs that any domains were taken from, which are used to suggest
+    /// where their service parameters could be looked up.
+    pub urls: Vec<UrlTarget>,
 }
 
 /// Weird protocol options that are allowed by the spec but are not common.
-#[derive(PartialEq, Debug, Default, Copy, Clone)]
+#[derive(PartialEq, Debug, Default, Clone)]
 pub struct ProtocolTweaks {
 
     /// Set the `AA` (Authoritative Answer) flag in the header of each request.
@@ -58,8 +94,67 @@ pub struct ProtocolTweaks {
 
     /// Set the buffer size field in the OPT record of each request.
     pub udp_payload_size: Option<u16>,
+
+    /// Set the version field in the OPT record of each request.
+    pub edns_version: Option<u8>,
+
+    /// Ask the server for its name server identifier, by adding an NSID
+    /// option to the OPT record of each request.
+    pub request_nsid: bool,
+
+    /// Ask an authoritative server how long its copy of the zone has left
+    /// before it expires, by adding an EXPIRE option to the OPT record of
+    /// each request.
+    pub request_expire: bool,
+
+    /// The client cookie to send in the OPT record of each request, if
+    /// cookies should be sent at all.
+    pub client_cookie: Option<[u8; 8]>,
+
+    /// The network to send as the client subnet in the OPT record of each
+    /// request, as an address and a prefix length.
+    pub client_subnet: Option<(IpAddr, u8)>,
+
+    /// Whether to pad each request to hide its length.
+    pub padding: UsePadding,
+
+    /// The closest trust point to ask for a chain of DNSSEC records from,
+    /// if a chain should be asked for at all.
+    pub chain_trust_point: Option<dns::Labels>,
+
+    /// Set the DO (DNSSEC OK) bit in the OPT record of each request, so
+    /// the server sends the signatures along with the records.
+    pub request_dnssec: bool,
+
+    /// The key to sign each request with, and to check the signatures on
+    /// the responses with, if requests should be signed at all.
+    pub tsig_key: Option<dns::TsigKey>,
+
+    /// The key pair to sign each request with using SIG(0), if requests
+    /// should be signed that way instead.
+    pub sig0_key: Option<dns::Sig0Key>,
 }
 
+/// Whether to pad requests with an EDNS Padding option.
+#[derive(PartialEq, Debug, Default, Copy, Clone)]
+pub enum UsePadding {
+
+    /// Pad requests sent over encrypted transports only. This is the
+    /// default, as padding unencrypted requests hides nothing.
+    #[default]
+    Automatic,
+
+    /// Pad every request.
+    Always,
+
+    /// Never pad requests.
+    Never,
+}
+
+/// The size of the blocks that padded requests get padded up to, as
+/// recommended by RFC 8467.
+pub const PADDING_BLOCK_SIZE: usize = 128;
+
 /// Whether to send or display OPT packets.
 #[derive(PartialEq, Debug, Copy, Clone)]
 pub enum UseEDNS {
@@ -82,51 +177,306 @@ pub enum UseEDNS {
 /// search path in the resolver.
 pub type RequestSet = (Box<dyn dns_transport::Transport>, Vec<dns::Request>);
 
+/// A group of request sets for the same query, each going to a different
+/// nameserver, which get tried in turn until one of them gets a response.
+/// Each set comes with the address of its nameserver.
+pub type RequestGroup = Vec<(String, RequestSet)>;
+
+/// One query from the matrix of inputs, in the order they were given.
+pub enum PlannedQuery {
+
+    /// A query that has not come up before, and needs sending.
+    Send(RequestGroup),
+
+    /// A query that is the same as the one at this earlier position, which
+    /// gets answered with that query’s response instead of being sent
+    /// again.
+    Repeat(usize),
+}
+
+impl PlannedQuery {
+
+    /// The position of the earlier query that this one repeats, if it does.
+    pub fn repeats(&self) -> Option<usize> {
+        match self {
+            Self::Send(_)         => None,
+            Self::Repeat(index)   => Some(*index),
+        }
+    }
+}
+
+/// The transports that are shared between every request set sent to the
+/// same nameserver over the same transport type, so that their queries go
+/// down one connection instead of opening one each.
+type SharedTransports = Vec<(String, TransportType, Rc<dyn dns_transport::Transport>)>;
+
 impl RequestGenerator {
 
-    /// Iterate through the inputs matrix, returning pairs of DNS request list
-    /// and the details of the transport to send them down.
-    pub fn generate(self) -> Result<Vec<RequestSet>, ResolverLookupError> {
-        let mut requests = Vec::new();
+    /// Iterate through the inputs matrix, returning groups of request sets
+    /// for each query to each resolver. A group has the request set for the
+    /// resolver’s nameserver first, followed by one for each of its fallback
+    /// nameservers, which only get used if the ones before them fail. A
+    /// query that is the same as an earlier one, down to the nameserver and
+    /// transport type, is planned as a repeat of it instead.
+    pub fn generate(mut self) -> Result<Vec<PlannedQuery>, ResolverLookupError> {
+        let mut groups = Vec::new();
+        let mut seen = Vec::new();
 
-        let resolvers = self.inputs.resolver_types.into_iter()
-            .map(ResolverType::obtain)
-            .collect::<Result<Vec<_>, _>>()?;
+        let uses_system_default = self.uses_system_default();
+        let resolvers = self.obtain_resolvers()?;
+        let mut shared = SharedTransports::new();
 
         for domain in &self.inputs.domains {
+            let special_use = SpecialUse::of(domain).filter(|_| uses_system_default);
+
             for qtype in self.inputs.record_types.iter().copied() {
                 for qclass in self.inputs.classes.iter().copied() {
                     for resolver in &resolvers {
                         for transport_type in &self.inputs.transport_types {
+                            let key = (domain, qtype, qclass, resolver.nameserver(), *transport_type);
+                            if let Some(index) = seen.iter().position(|k| *k == key) {
+                                groups.push(PlannedQuery::Repeat(index));
+                                seen.push(key);
+                                continue;
+                            }
 
-                            let mut flags = dns::Flags::query();
-                            self.protocol_tweaks.set_request_flags(&mut flags);
+                            let request_set = self.request_set(domain, special_use, qtype, qclass, resolver, *transport_type, &mut shared);
+                            let mut group = vec![ (resolver.nameserver(), request_set) ];
 
-                            let mut additional = None;
-                            if self.edns.should_send() {
-                                let mut opt = dns::Request::additional_record();
-                                self.protocol_tweaks.set_request_opt_fields(&mut opt);
-                                additional = Some(opt);
+                            // Special-use domains do not go to the resolver
+                            // at all, so there is nothing to fall back to
+                            if special_use.is_none() {
+                                for fallback in resolver.fallback_resolvers() {
+                                    let request_set = self.request_set(domain, special_use, qtype, qclass, &fallback, *transport_type, &mut shared);
+                                    group.push((fallback.nameserver(), request_set));
+                                }
                             }
 
-                            let nameserver = resolver.nameserver();
-                            let transport = transport_type.make_transport(nameserver);
+                            groups.push(PlannedQuery::Send(group));
+                            seen.push(key);
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(groups)
+    }
+
+    /// Like `generate`, but rather than sending every query to every
+    /// resolver, treats the resolvers as a pool that each query only needs
+    /// an answer from one of. Returns the nameserver of each resolver, and a
+    /// group of request sets for each query, with one set per resolver in
+    /// the same order. Repeated queries are planned the same way.
+    pub fn generate_pooled(mut self) -> Result<(Vec<String>, Vec<PlannedQuery>), ResolverLookupError> {
+        let mut groups = Vec::new();
+        let mut seen = Vec::new();
 
-                            let mut request_list = Vec::new();
-                            for qname in resolver.name_list(domain) {
-                                let transaction_id = self.txid_generator.generate();
-                                let query = dns::Query { qname, qtype, qclass };
-                                let request = dns::Request { transaction_id, flags, query, additional: additional.clone() };
-                                request_list.push(request);
-                            }
-                            requests.push((transport, request_list));
+        let uses_system_default = self.uses_system_default();
+        let resolvers = self.obtain_resolvers()?;
+        let nameservers = resolvers.iter().map(Resolver::nameserver).collect();
+        let mut shared = SharedTransports::new();
+
+        for domain in &self.inputs.domains {
+            let special_use = SpecialUse::of(domain).filter(|_| uses_system_default);
+
+            for qtype in self.inputs.record_types.iter().copied() {
+                for qclass in self.inputs.classes.iter().copied() {
+                    for transport_type in &self.inputs.transport_types {
+                        let key = (domain, qtype, qclass, *transport_type);
+                        if let Some(index) = seen.iter().position(|k| *k == key) {
+                            groups.push(PlannedQuery::Repeat(index));
+                            seen.push(key);
+                            continue;
                         }
+
+                        let group = resolvers.iter()
+                            .map(|resolver| (resolver.nameserver(), self.request_set(domain, special_use, qtype, qclass, resolver, *transport_type, &mut shared)))
+                            .collect();
+
+                        groups.push(PlannedQuery::Send(group));
+                        seen.push(key);
                     }
                 }
             }
         }
 
-        Ok(requests)
+        Ok((nameservers, groups))
+    }
+
+    /// Like `generate`, but rather than sending a request for each query,
+    /// puts every query into the question section of one message, which
+    /// gets sent to each resolver over each transport type. Search lists
+    /// are not used, so each domain is asked about exactly as it was given.
+    /// Returns each transport with the bytes of the message to send down it.
+    pub fn generate_multi_question(mut self) -> Result<Vec<(Box<dyn dns_transport::Transport>, Vec<u8>)>, ResolverLookupError> {
+        let resolvers = self.obtain_resolvers()?;
+
+        let mut flags = dns::Flags::query();
+        self.protocol_tweaks.set_request_flags(&mut flags);
+
+        let mut builder = dns::Request::builder().flags(flags);
+        for domain in &self.inputs.domains {
+            for qtype in self.inputs.record_types.iter().copied() {
+                for qclass in self.inputs.classes.iter().copied() {
+                    builder = builder.query(dns::Query { qname: domain.clone(), qtype, qclass });
+                }
+            }
+        }
+
+        if self.edns.should_send() {
+            let mut opt = dns::Request::additional_record();
+            self.protocol_tweaks.set_request_opt_fields(&mut opt);
+            builder = builder.opt(opt);
+        }
+
+        let mut messages = Vec::new();
+        for resolver in &resolvers {
+            for transport_type in &self.inputs.transport_types {
+                let transport = transport_type.make_transport(resolver.nameserver(), &self.http_headers, self.checks, self.ignore_truncation, self.timeouts, &self.source);
+                let bytes = builder.clone().transaction_id(self.txid_generator.generate())
+                                   .to_bytes().expect("failed to serialise request");
+                messages.push((transport, bytes));
+            }
+        }
+
+        Ok(messages)
+    }
+
+    /// Creates a transport to the first resolver using the first transport
+    /// type, for the modes that only talk to one nameserver.
+    pub fn first_transport(mut self) -> Result<Box<dyn dns_transport::Transport>, ResolverLookupError> {
+        let nameserver = self.first_nameserver()?;
+        let transport_type = self.inputs.transport_types.first().copied().expect("No transport types");
+        let transport = transport_type.make_transport(nameserver, &self.http_headers, self.checks, self.ignore_truncation, self.timeouts, &self.source);
+
+        #[cfg(feature = "with_tsig")]
+        let transport: Box<dyn dns_transport::Transport> = match self.protocol_tweaks.tsig_key.take() {
+            Some(key)  => Box::new(SigningTransport::new(transport, key)),
+            None       => transport,
+        };
+
+        #[cfg(feature = "with_sig0")]
+        let transport: Box<dyn dns_transport::Transport> = match self.protocol_tweaks.sig0_key.take() {
+            Some(key)  => Box::new(Sig0Transport::new(transport, key)),
+            None       => transport,
+        };
+
+        Ok(transport)
+    }
+
+    /// Obtains the address of the first resolver, for the modes that pick
+    /// their own transports.
+    pub fn first_nameserver(&mut self) -> Result<String, ResolverLookupError> {
+        let resolvers = self.obtain_resolvers()?;
+        let resolver = resolvers.first().expect("No resolvers");
+        Ok(resolver.nameserver())
+    }
+
+    /// Whether no particular nameserver has been asked for. Special-use
+    /// names are only re-routed in this case, as otherwise they get sent to
+    /// the nameserver the user asked for.
+    fn uses_system_default(&self) -> bool {
+        self.inputs.resolver_types.iter()
+            .all(|rt| *rt == ResolverType::SystemDefault)
+    }
+
+    /// Whether requests sent over this transport type should be retried
+    /// with smaller payload sizes when they fail, which only happens over
+    /// UDP when a size larger than the minimum has been asked for.
+    fn downgrades_payload_size(&self, transport_type: TransportType) -> bool {
+        matches!(transport_type, TransportType::Automatic | TransportType::UDP)
+            && self.edns.should_send()
+            && self.protocol_tweaks.udp_payload_size.map_or(false, |size| size > 512)
+    }
+
+    /// Obtains a resolver for each resolver type in the inputs.
+    fn obtain_resolvers(&mut self) -> Result<Vec<Resolver>, ResolverLookupError> {
+        std::mem::take(&mut self.inputs.resolver_types).into_iter()
+            .map(ResolverType::obtain)
+            .collect()
+    }
+
+    /// Builds the list of requests for one combination of inputs, and the
+    /// transport to send them down. Transports that keep their connections
+    /// open are shared with the other request sets for the same nameserver.
+    #[allow(clippy::too_many_arguments)]
+    fn request_set(&self, domain: &dns::Labels, special_use: Option<SpecialUse>, qtype: dns::record::RecordType, qclass: dns::QClass, resolver: &Resolver, transport_type: TransportType, shared: &mut SharedTransports) -> RequestSet {
+        let mut flags = dns::Flags::query();
+        self.protocol_tweaks.set_request_flags(&mut flags);
+
+        let mut additional = None;
+        if self.edns.should_send() {
+            let mut opt = dns::Request::additional_record();
+            self.protocol_tweaks.set_request_opt_fields(&mut opt);
+
+            // Ask the server to keep the connection open for the next query
+            // in the list, which is only allowed over TCP (RFC 7828 §3.2.1)
+            if special_use.is_none() && transport_type.is_stream() {
+                let keepalive = dns::record::EdnsOption::TcpKeepalive(dns::record::TcpKeepalive { timeout: None });
+                opt.push_option(&keepalive).expect("Failed to add TCP keepalive option");
+            }
+
+            additional = Some(opt);
+        }
+
+        let (transport, qnames) = match special_use.and_then(SpecialUse::special_transport) {
+            Some(transport) => {
+                (transport, vec![ domain.clone() ])
+            }
+            None if self.downgrades_payload_size(transport_type) => {
+                let fall_back_to_tcp = transport_type == TransportType::Automatic;
+                let transport: Box<dyn dns_transport::Transport> = Box::new(DowngradingTransport::new(resolver.nameserver(), fall_back_to_tcp).with_checks(self.checks).ignore_truncation(self.ignore_truncation).with_timeouts(self.timeouts).with_source(self.source.clone()));
+                (transport, resolver.name_list(domain))
+            }
+            None if transport_type.is_stream() => {
+                let nameserver = resolver.nameserver();
+                let transport = match shared.iter().find(|(ns, tt, _)| *ns == nameserver && *tt == transport_type) {
+                    Some((_, _, transport)) => Rc::clone(transport),
+                    None => {
+                        let transport: Rc<dyn dns_transport::Transport> = Rc::from(transport_type.make_transport(nameserver.clone(), &self.http_headers, self.checks, self.ignore_truncation, self.timeouts, &self.source));
+                        shared.push((nameserver, transport_type, Rc::clone(&transport)));
+                        transport
+                    }
+                };
+
+                let transport: Box<dyn dns_transport::Transport> = Box::new(transport);
+                (transport, resolver.name_list(domain))
+            }
+            None => {
+                let nameserver = resolver.nameserver();
+                (transport_type.make_transport(nameserver, &self.http_headers, self.checks, self.ignore_truncation, self.timeouts, &self.source), resolver.name_list(domain))
+            }
+        };
+
+        #[cfg(feature = "with_tsig")]
+        let transport: Box<dyn dns_transport::Transport> = match (&self.protocol_tweaks.tsig_key, special_use) {
+            (Some(key), None)  => Box::new(SigningTransport::new(transport, key.clone())),
+            _                  => transport,
+        };
+
+        #[cfg(feature = "with_sig0")]
+        let transport: Box<dyn dns_transport::Transport> = match (&self.protocol_tweaks.sig0_key, special_use) {
+            (Some(key), None)  => Box::new(Sig0Transport::new(transport, key.clone())),
+            _                  => transport,
+        };
+
+        let should_pad = special_use.is_none() && self.protocol_tweaks.padding.should_pad(transport_type);
+
+        let mut request_list = Vec::new();
+        for qname in qnames {
+            let transaction_id = self.txid_generator.generate();
+            let query = dns::Query { qname, qtype, qclass };
+            let mut request = dns::Request { transaction_id, flags, query, additional: additional.clone() };
+
+            if should_pad {
+                request.pad_to_block(PADDING_BLOCK_SIZE).expect("Failed to pad request");
+            }
+
+            request_list.push(request);
+        }
+
+        (transport, request_list)
     }
 }
 
@@ -143,10 +493,25 @@ impl UseEDNS {
     }
 }
 
+impl UsePadding {
+
+    /// Whether requests sent over the given transport should be padded.
+    pub fn should_pad(self, transport_type: TransportType) -> bool {
+        match self {
+            Self::Automatic  => transport_type.is_encrypted(),
+            Self::Always     => true,
+            Self::Never      => false,
+        }
+    }
+}
+
+/// The DO (DNSSEC OK) bit in the flags of an OPT record.
+pub const DNSSEC_OK_FLAG: u16 = 0b_1000_0000_0000_0000;
+
 impl ProtocolTweaks {
 
     /// Sets fields in the DNS flags based on the user’s requested tweaks.
-    pub fn set_request_flags(self, flags: &mut dns::Flags) {
+    pub fn set_request_flags(&self, flags: &mut dns::Flags) {
         if self.set_authoritative_flag {
             flags.authoritative = true;
         }
@@ -160,11 +525,47 @@ impl ProtocolTweaks {
         }
     }
 
-    /// Set the payload size field in the outgoing OPT record, if the user has
-    /// requested to do so.
-    pub fn set_request_opt_fields(self, opt: &mut dns::record::OPT) {
+    /// Set the payload size field in the outgoing OPT record, and add any
+    /// options to it, if the user has requested to do so.
+    pub fn set_request_opt_fields(&self, opt: &mut dns::record::OPT) {
         if let Some(bufsize) = self.udp_payload_size {
             opt.udp_payload_size = bufsize;
         }
+
+        if let Some(version) = self.edns_version {
+            opt.edns0_version = version;
+        }
+
+        if self.request_nsid {
+            let nsid = dns::record::EdnsOption::Nsid(dns::record::Nsid(Vec::new()));
+            opt.push_option(&nsid).expect("Failed to add NSID option");
+        }
+
+        if self.request_expire {
+            let expire = dns::record::EdnsOption::Expire(dns::record::Expire { timer: None });
+            opt.push_option(&expire).expect("Failed to add EXPIRE option");
+        }
+
+        if self.request_dnssec {
+            opt.flags |= DNSSEC_OK_FLAG;
+        }
+
+        if let Some(client) = self.client_cookie {
+            let cookie = dns::record::EdnsOption::Cookie(dns::record::Cookie::new(client));
+            opt.push_option(&cookie).expect("Failed to add cookie option");
+        }
+
+        if let Some((address, prefix)) = self.client_subnet {
+            let ecs = dns::record::EdnsOption::ClientSubnet(dns::record::ClientSubnet::new(address, prefix));
+            opt.push_option(&ecs).expect("Failed to add client subnet option");
+        }
+
+        if let Some(trust_point) = &self.chain_trust_point {
+            // CHAIN is only answered when the DO bit is set (RFC 7901 §4)
+            opt.flags |= DNSSEC_OK_FLAG;
+
+            let chain = dns::record::EdnsOption::Chain(dns::record::Chain { trust_point: trust_point.clone() });
+            opt.push_option(&chain).expect("Failed to add CHAIN option");
+        }
     }
 }
@@ -2,6 +2,7 @@
 
 use std::fmt;
 use std::io;
+use std::net::{IpAddr, SocketAddr};
 
 use log::*;
 
@@ -32,10 +33,47 @@ impl ResolverType {
             }
             Self::Specific(nameserver) => {
                 let search_list = Vec::new();
-                Ok(Resolver { nameserver, search_list })
+                let fallbacks = Vec::new();
+                Ok(Resolver { nameserver, fallbacks, search_list })
             }
         }
     }
+
+    /// Whether this resolver is likely to be a public one out on the
+    /// Internet, rather than one on the local machine or network. Resolvers
+    /// given by hostname or URL are assumed to be public.
+    pub fn is_public(&self) -> bool {
+        match self {
+            Self::SystemDefault         => false,
+            Self::Specific(nameserver)  => ! is_local_address(nameserver),
+        }
+    }
+}
+
+/// Whether the given nameserver string is an IP address, with or without a
+/// port, that lies in a loopback, private, or link-local range.
+fn is_local_address(nameserver: &str) -> bool {
+    let ip = match nameserver.parse::<SocketAddr>() {
+        Ok(socket_addr)  => socket_addr.ip(),
+        Err(_)           => match nameserver.parse::<IpAddr>() {
+            Ok(ip)   => ip,
+            Err(_)   => return nameserver.eq_ignore_ascii_case("localhost"),
+        },
+    };
+
+    match ip {
+        IpAddr::V4(v4) => {
+            let [first, second, ..] = v4.octets();
+            v4.is_loopback() || v4.is_private() || v4.is_link_local() || v4.is_unspecified()
+                || (first == 100 && second & 0b_1100_0000 == 64)  // shared address space
+        }
+        IpAddr::V6(v6) => {
+            let first_segment = v6.segments()[0];
+            v6.is_loopback() || v6.is_unspecified()
+                || first_segment & 0xfe00 == 0xfc00  // unique local
+                || first_segment & 0xffc0 == 0xfe80  // link-local
+        }
+    }
 }
 
 
@@ -47,6 +85,10 @@ pub struct Resolver {
     /// The address of the nameserver.
     pub nameserver: String,
 
+    /// The addresses of the other nameservers listed alongside it, which
+    /// get tried in order when it fails.
+    pub fallbacks: Vec<String>,
+
     /// The search list for name lookup.
     pub search_list: Vec<String>,
 }
@@ -58,6 +100,16 @@ impl Resolver {
         self.nameserver.clone()
     }
 
+    /// Returns a resolver for each of the fallback nameservers, which share
+    /// this resolver’s search list.
+    pub fn fallback_resolvers(&self) -> Vec<Resolver> {
+        self.fallbacks.iter().map(|nameserver| Resolver {
+            nameserver: nameserver.clone(),
+            fallbacks: Vec::new(),
+            search_list: self.search_list.clone(),
+        }).collect()
+    }
+
     /// Returns a sequence of names to be queried, taking into account
     /// the search list.
     pub fn name_list(&self, name: &Labels) -> Vec<Labels> {
@@ -81,24 +133,142 @@ impl Resolver {
 }
 
 
-/// Looks up the system default nameserver on Unix, by querying
-/// `/etc/resolv.conf` and using the first line that specifies one.
-/// Returns an error if there’s a problem reading the file, or `None` if no
-/// nameserver is specified in the file.
-#[cfg(unix)]
-fn system_nameservers() -> Result<Resolver, ResolverLookupError> {
-    use std::fs::File;
-    use std::io::{BufRead, BufReader};
+/// A **nameserver source** is one of the places on the system that dog can
+/// discover nameservers from, such as a configuration file or an operating
+/// system API.
+pub trait NameserverSource {
+
+    /// Where this source looks, such as the path to the file, for showing
+    /// to the user.
+    fn description(&self) -> String;
+
+    /// Looks for nameservers and a search list in this source. Returns
+    /// `Ok(None)` if the source does not exist on this system, such as when
+    /// its file is missing, rather than treating that as an error.
+    fn discover(&self) -> Result<Option<Discovery>, ResolverLookupError>;
+}
+
+/// The nameservers and search list found in one source.
+#[derive(PartialEq, Debug, Default)]
+pub struct Discovery {
+
+    /// The addresses of the nameservers, in the order they were listed.
+    pub nameservers: Vec<String>,
+
+    /// The search list for name lookup.
+    pub search_list: Vec<String>,
+}
+
+/// Returns the nameserver sources for this platform, in priority order. The
+/// first source to produce a nameserver is the one that gets used.
+///
+/// 1. The file named by the `DOG_RESOLV_CONF` environment variable, if set;
+/// 2. The network adapters, on Windows;
+/// 3. The System Configuration framework, on macOS;
+/// 4. `/etc/resolv.conf`, on Unix;
+/// 5. DHCP client lease files, on Unix.
+///
+/// Nameservers given on the command-line take priority over all of these.
+pub fn system_sources() -> Vec<Box<dyn NameserverSource>> {
+    let mut sources: Vec<Box<dyn NameserverSource>> = Vec::new();
+
+    if let Some(path) = std::env::var_os("DOG_RESOLV_CONF") {
+        sources.push(Box::new(ResolvConf(path.into())));
+    }
+
+    #[cfg(windows)]
+    sources.push(Box::new(WindowsAdapters));
+
+    #[cfg(target_os = "macos")]
+    sources.push(Box::new(SystemConfiguration));
+
+    #[cfg(unix)]
+    sources.push(Box::new(ResolvConf("/etc/resolv.conf".into())));
 
+    #[cfg(unix)]
+    for path in DHCP_LEASE_FILES {
+        sources.push(Box::new(DhcpLeases(path.into())));
+    }
+
+    sources
+}
+
+/// Looks up the system default nameserver by trying each of the system
+/// sources in order, and using the first nameserver found, with the rest
+/// from the same source as its fallbacks. If none of them have one, returns
+/// the last error encountered, if any.
+fn system_nameservers() -> Result<Resolver, ResolverLookupError> {
     if cfg!(test) {
         panic!("system_nameservers() called from test code");
     }
 
-    let f = File::open("/etc/resolv.conf")?;
-    let reader = BufReader::new(f);
+    let mut last_error = None;
+    for source in system_sources() {
+        match source.discover() {
+            Ok(Some(Discovery { nameservers, search_list })) => {
+                let mut nameservers = nameservers.into_iter();
+                if let Some(nameserver) = nameservers.next() {
+                    debug!("Using nameserver {:?} from {}", nameserver, source.description());
+                    let fallbacks = nameservers.collect();
+                    return Ok(Resolver { nameserver, fallbacks, search_list });
+                }
+
+                debug!("No nameservers in {}", source.description());
+            }
+            Ok(None) => {
+                debug!("Nameserver source {} is not present", source.description());
+            }
+            Err(e) => {
+                warn!("Error reading {}: {}", source.description(), e);
+                last_error = Some(e);
+            }
+        }
+    }
+
+    #[cfg(all(not(unix), not(windows)))]
+    warn!("Unable to fetch default nameservers on this platform.");
+
+    #[cfg(all(not(unix), not(windows)))]
+    return Err(last_error.unwrap_or(ResolverLookupError::UnsupportedPlatform));
+
+    #[cfg(any(unix, windows))]
+    Err(last_error.unwrap_or(ResolverLookupError::NoNameserver))
+}
+
+
+/// A file in the `resolv.conf` format, listing nameservers on lines that
+/// begin with `nameserver`, and the search list on a line that begins with
+/// `search`.
+struct ResolvConf(std::path::PathBuf);
+
+impl NameserverSource for ResolvConf {
+    fn description(&self) -> String {
+        self.0.display().to_string()
+    }
+
+    fn discover(&self) -> Result<Option<Discovery>, ResolverLookupError> {
+        use std::fs::File;
+        use std::io::BufReader;
+
+        match File::open(&self.0) {
+            Ok(f) => {
+                Ok(Some(parse_resolv_conf(BufReader::new(f))?))
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                Ok(None)
+            }
+            Err(e) => {
+                Err(e.into())
+            }
+        }
+    }
+}
+
+/// Reads the nameservers and search list out of a file in the `resolv.conf`
+/// format.
+fn parse_resolv_conf(reader: impl io::BufRead) -> io::Result<Discovery> {
+    let mut discovery = Discovery::default();
 
-    let mut nameservers = Vec::new();
-    let mut search_list = Vec::new();
     for line in reader.lines() {
         let line = line?;
 
@@ -107,113 +277,223 @@ fn system_nameservers() -> Result<Resolver, ResolverLookupError> {
             // TODO: This will need to be changed for IPv6 support.
 
             match ip {
-                Ok(_ip) => nameservers.push(nameserver_str.into()),
+                Ok(_ip) => discovery.nameservers.push(nameserver_str.into()),
                 Err(e)  => warn!("Failed to parse nameserver line {:?}: {}", line, e),
             }
         }
 
         if let Some(search_str) = line.strip_prefix("search ") {
-            search_list.clear();
-            search_list.extend(search_str.split_ascii_whitespace().map(|s| s.into()));
+            discovery.search_list.clear();
+            discovery.search_list.extend(search_str.split_ascii_whitespace().map(|s| s.into()));
         }
     }
 
-    if let Some(nameserver) = nameservers.into_iter().next() {
-        Ok(Resolver { nameserver, search_list })
-    }
-    else {
-        Err(ResolverLookupError::NoNameserver)
-    }
+    Ok(discovery)
 }
 
 
-/// Looks up the system default nameserver on Windows, by iterating through
-/// the list of network adapters and returning the first nameserver it finds.
-#[cfg(windows)]
-#[allow(unused)]  // todo: Remove this when the time is right
-fn system_nameservers() -> Result<Resolver, ResolverLookupError> {
-    use std::net::{IpAddr, UdpSocket};
+/// The lease files written by the ISC DHCP client, in the places that
+/// various distributions keep them.
+#[cfg(unix)]
+const DHCP_LEASE_FILES: &[&str] = &[
+    "/var/lib/dhcp/dhclient.leases",
+    "/var/lib/dhclient/dhclient.leases",
+    "/var/db/dhclient.leases",
+];
+
+/// A DHCP client lease file, which contains the nameservers that the DHCP
+/// server handed out. This is only used when nothing else is configured.
+#[cfg(unix)]
+struct DhcpLeases(std::path::PathBuf);
 
-    if cfg!(test) {
-        panic!("system_nameservers() called from test code");
+#[cfg(unix)]
+impl NameserverSource for DhcpLeases {
+    fn description(&self) -> String {
+        self.0.display().to_string()
     }
 
-    // According to the specification, prefer ipv6 by default.
-    // TODO: add control flag to select an ip family.
-    #[derive(Debug, PartialEq)]
-    enum ForceIPFamily {
-        V4,
-        V6,
-        None,
+    fn discover(&self) -> Result<Option<Discovery>, ResolverLookupError> {
+        match std::fs::read_to_string(&self.0) {
+            Ok(contents) => {
+                Ok(Some(parse_dhcp_leases(&contents)))
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                Ok(None)
+            }
+            Err(e) => {
+                Err(e.into())
+            }
+        }
     }
+}
 
-    // get the IP of the Network adapter that is used to access the Internet
-    // https://stackoverflow.com/questions/24661022/getting-ip-adress-associated-to-real-hardware-ethernet-controller-in-windows-c
-    fn get_ipv4() -> io::Result<IpAddr> {
-        let s = UdpSocket::bind("0.0.0.0:0")?;
-        s.connect("8.8.8.8:53")?;
-        let addr = s.local_addr()?;
-        Ok(addr.ip())
+/// Reads the nameservers and search list out of the most recent lease in a
+/// DHCP client lease file. Leases are appended to the file as they are
+/// obtained, so later options override earlier ones.
+#[cfg_attr(not(unix), allow(unused))]
+fn parse_dhcp_leases(contents: &str) -> Discovery {
+    let mut discovery = Discovery::default();
+
+    for line in contents.lines() {
+        let line = line.trim().trim_end_matches(';');
+
+        if let Some(servers) = line.strip_prefix("option domain-name-servers ") {
+            discovery.nameservers = servers.split(',')
+                                           .map(|s| s.trim().to_string())
+                                           .filter(|s| ! s.is_empty())
+                                           .collect();
+        }
+        else if let Some(names) = line.strip_prefix("option domain-search ") {
+            discovery.search_list = names.split(',')
+                                         .map(|s| s.trim().trim_matches('"').to_string())
+                                         .filter(|s| ! s.is_empty())
+                                         .collect();
+        }
     }
 
-    fn get_ipv6() -> io::Result<IpAddr> {
-        let s = UdpSocket::bind("[::1]:0")?;
-        s.connect("[2001:4860:4860::8888]:53")?;
-        let addr = s.local_addr()?;
-        Ok(addr.ip())
-    }
+    discovery
+}
 
-    let force_ip_family: ForceIPFamily = ForceIPFamily::None;
-    let ip = match force_ip_family {
-        ForceIPFamily::V4 => get_ipv4().ok(),
-        ForceIPFamily::V6 => get_ipv6().ok(),
-        ForceIPFamily::None => get_ipv6().or(get_ipv4()).ok(),
-    };
 
-    let search_list = Vec::new();  // todo: implement this
-
-    let adapters = ipconfig::get_adapters()?;
-    let active_adapters = adapters.iter().filter(|a| {
-        a.oper_status() == ipconfig::OperStatus::IfOperStatusUp && !a.gateways().is_empty()
-    });
-
-    if let Some(dns_server) = active_adapters
-        .clone()
-        .find(|a| ip.map(|ip| a.ip_addresses().contains(&ip)).unwrap_or(false))
-        .map(|a| a.dns_servers().first())
-        .flatten()
-    {
-        debug!("Found first nameserver {:?}", dns_server);
-        let nameserver = dns_server.to_string();
-        Ok(Resolver { nameserver, search_list })
+/// The macOS System Configuration framework, queried through the `scutil`
+/// command. This reflects the current network configuration more closely
+/// than `/etc/resolv.conf`, which macOS only keeps around for compatibility.
+#[cfg(target_os = "macos")]
+struct SystemConfiguration;
+
+#[cfg(target_os = "macos")]
+impl NameserverSource for SystemConfiguration {
+    fn description(&self) -> String {
+        "scutil --dns".into()
     }
 
-    // Fallback
-    else if let Some(dns_server) = active_adapters
-        .flat_map(|a| a.dns_servers())
-        .find(|d| (d.is_ipv4() && force_ip_family != ForceIPFamily::V6) || d.is_ipv6())
-    {
-        debug!("Found first fallback nameserver {:?}", dns_server);
-        let nameserver = dns_server.to_string();
-        Ok(Resolver { nameserver, search_list })
+    fn discover(&self) -> Result<Option<Discovery>, ResolverLookupError> {
+        use std::process::Command;
+
+        let output = Command::new("scutil").arg("--dns").output()?;
+        if ! output.status.success() {
+            warn!("scutil exited with {}", output.status);
+            return Ok(None);
+        }
+
+        Ok(Some(parse_scutil_dns(&String::from_utf8_lossy(&output.stdout))))
     }
+}
+
+/// Reads the nameservers and search list of the first resolver in the output
+/// of `scutil --dns`, which is the one used for unscoped queries.
+#[cfg(target_os = "macos")]
+fn parse_scutil_dns(output: &str) -> Discovery {
+    let mut discovery = Discovery::default();
+    let mut resolver_count = 0;
 
-    else {
-        Err(ResolverLookupError::NoNameserver)
+    for line in output.lines() {
+        let line = line.trim();
+
+        if line.starts_with("resolver #") {
+            resolver_count += 1;
+            if resolver_count > 1 {
+                break;
+            }
+        }
+        else if let Some((key, value)) = line.split_once(" : ") {
+            if key.starts_with("nameserver[") {
+                discovery.nameservers.push(value.trim().into());
+            }
+            else if key.starts_with("search domain[") {
+                discovery.search_list.push(value.trim().into());
+            }
+        }
     }
+
+    discovery
 }
 
 
-/// The fall-back system default nameserver determinator that is not very
-/// determined as it returns nothing without actually checking anything.
-#[cfg(all(not(unix), not(windows)))]
-fn system_nameservers() -> Result<Resolver, ResolverLookupError> {
-    warn!("Unable to fetch default nameservers on this platform.");
-    Err(ResolverLookupError::UnsupportedPlatform)
+/// The network adapters on Windows, found by iterating through the list of
+/// adapters and picking the nameserver of the one used to reach the Internet.
+#[cfg(windows)]
+struct WindowsAdapters;
+
+#[cfg(windows)]
+impl NameserverSource for WindowsAdapters {
+    fn description(&self) -> String {
+        "Windows network adapters".into()
+    }
+
+    #[allow(unused)]  // todo: Remove this when the time is right
+    fn discover(&self) -> Result<Option<Discovery>, ResolverLookupError> {
+        use std::net::{IpAddr, UdpSocket};
+
+        // According to the specification, prefer ipv6 by default.
+        // TODO: add control flag to select an ip family.
+        #[derive(Debug, PartialEq)]
+        enum ForceIPFamily {
+            V4,
+            V6,
+            None,
+        }
+
+        // get the IP of the Network adapter that is used to access the Internet
+        // https://stackoverflow.com/questions/24661022/getting-ip-adress-associated-to-real-hardware-ethernet-controller-in-windows-c
+        fn get_ipv4() -> io::Result<IpAddr> {
+            let s = UdpSocket::bind("0.0.0.0:0")?;
+            s.connect("8.8.8.8:53")?;
+            let addr = s.local_addr()?;
+            Ok(addr.ip())
+        }
+
+        fn get_ipv6() -> io::Result<IpAddr> {
+            let s = UdpSocket::bind("[::1]:0")?;
+            s.connect("[2001:4860:4860::8888]:53")?;
+            let addr = s.local_addr()?;
+            Ok(addr.ip())
+        }
+
+        let force_ip_family: ForceIPFamily = ForceIPFamily::None;
+        let ip = match force_ip_family {
+            ForceIPFamily::V4 => get_ipv4().ok(),
+            ForceIPFamily::V6 => get_ipv6().ok(),
+            ForceIPFamily::None => get_ipv6().or(get_ipv4()).ok(),
+        };
+
+        let search_list = Vec::new();  // todo: implement this
+
+        let adapters = ipconfig::get_adapters()?;
+        let active_adapters = adapters.iter().filter(|a| {
+            a.oper_status() == ipconfig::OperStatus::IfOperStatusUp && !a.gateways().is_empty()
+        });
+
+        if let Some(dns_server) = active_adapters
+            .clone()
+            .find(|a| ip.map(|ip| a.ip_addresses().contains(&ip)).unwrap_or(false))
+            .map(|a| a.dns_servers().first())
+            .flatten()
+        {
+            debug!("Found first nameserver {:?}", dns_server);
+            let nameservers = vec![ dns_server.to_string() ];
+            Ok(Some(Discovery { nameservers, search_list }))
+        }
+
+        // Fallback
+        else if let Some(dns_server) = active_adapters
+            .flat_map(|a| a.dns_servers())
+            .find(|d| (d.is_ipv4() && force_ip_family != ForceIPFamily::V6) || d.is_ipv6())
+        {
+            debug!("Found first fallback nameserver {:?}", dns_server);
+            let nameservers = vec![ dns_server.to_string() ];
+            Ok(Some(Discovery { nameservers, search_list }))
+        }
+
+        else {
+            Ok(Some(Discovery { nameservers: Vec::new(), search_list }))
+        }
+    }
 }
 
 
 /// Something that can go wrong while obtaining a `Resolver`.
+#[derive(Debug)]
 pub enum ResolverLookupError {
 
     /// The system information was successfully read, but there was no adapter
@@ -267,3 +547,43 @@ impl fmt::Display for ResolverLookupError {
         }
     }
 }
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn resolv_conf() {
+        let file = "# comment\nnameserver 192.168.1.1\nnameserver 9.9.9.9\nsearch lan example.com\n";
+        assert_eq!(parse_resolv_conf(file.as_bytes()).unwrap(),
+                   Discovery {
+                       nameservers: vec![ "192.168.1.1".into(), "9.9.9.9".into() ],
+                       search_list: vec![ "lan".into(), "example.com".into() ],
+                   });
+    }
+
+    #[test]
+    fn resolv_conf_empty() {
+        assert_eq!(parse_resolv_conf(&b""[..]).unwrap(),
+                   Discovery::default());
+    }
+
+    #[test]
+    fn dhcp_leases_latest() {
+        let file = "lease {\n  interface \"eth0\";\n  option domain-name-servers 10.0.0.1;\n}\n\
+                    lease {\n  interface \"eth0\";\n  option domain-name-servers 10.0.0.2, 10.0.0.3;\n  option domain-search \"lan\", \"example.com\";\n}\n";
+        assert_eq!(parse_dhcp_leases(file),
+                   Discovery {
+                       nameservers: vec![ "10.0.0.2".into(), "10.0.0.3".into() ],
+                       search_list: vec![ "lan".into(), "example.com".into() ],
+                   });
+    }
+
+    #[test]
+    fn dhcp_leases_empty() {
+        assert_eq!(parse_dhcp_leases(""),
+                   Discovery::default());
+    }
+}
@@ -0,0 +1,110 @@
+//! Sending queries again when they fail in a way that might not happen a
+//! second time, waiting longer before each attempt.
+
+use std::io::ErrorKind;
+use std::time::Duration;
+
+
+/// How long to wait before the first retry, if the user does not say.
+const DEFAULT_BACKOFF: Duration = Duration::from_millis(250);
+
+/// The longest to wait between two attempts, however many retries there
+/// have been.
+const MAXIMUM_BACKOFF: Duration = Duration::from_secs(10);
+
+/// How long to wait for each response before giving up on an attempt, when
+/// there are any retries to make and no timeout has been given. Without
+/// retries, dog waits as long as it takes.
+const ATTEMPT_TIMEOUT: Duration = Duration::from_secs(5);
+
+
+/// How many times a query gets sent again after it fails, and how long to
+/// wait before each retry.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub struct RetryPolicy {
+
+    /// The number of times to send a query again after the first attempt.
+    pub retries: u32,
+
+    /// How long to wait before the first retry. The wait doubles for each
+    /// retry after that.
+    pub backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self { retries: 0, backoff: DEFAULT_BACKOFF }
+    }
+}
+
+impl RetryPolicy {
+
+    /// Returns how long to wait before the given retry, counting from one.
+    pub fn delay(self, retry: u32) -> Duration {
+        let factor = 2_u32.checked_pow(retry.saturating_sub(1)).unwrap_or(u32::MAX);
+        self.backoff.checked_mul(factor).map_or(MAXIMUM_BACKOFF, |delay| delay.min(MAXIMUM_BACKOFF))
+    }
+
+    /// Returns how long each attempt to get a response can take before it
+    /// counts as a failure, if no timeout has been given. This is only
+    /// limited when there are retries to make.
+    pub fn default_timeout(self) -> Option<Duration> {
+        if self.retries > 0 { Some(ATTEMPT_TIMEOUT) } else { None }
+    }
+}
+
+/// Whether the given error could go away if the query were sent again,
+/// such as a timeout or a connection that got closed, rather than being
+/// something wrong with the response or the request.
+pub fn is_retryable(error: &dns_transport::Error) -> bool {
+    match error {
+        dns_transport::Error::NetworkError(e) => {
+            matches!(e.kind(), ErrorKind::TimedOut | ErrorKind::WouldBlock | ErrorKind::Interrupted
+                             | ErrorKind::ConnectionReset | ErrorKind::ConnectionAborted | ErrorKind::UnexpectedEof)
+        }
+        dns_transport::Error::TruncatedResponse => true,
+        _ => false,
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy { retries: 3, backoff: Duration::from_millis(100) }
+    }
+
+    #[test]
+    fn doubles() {
+        assert_eq!(policy().delay(1), Duration::from_millis(100));
+        assert_eq!(policy().delay(2), Duration::from_millis(200));
+        assert_eq!(policy().delay(3), Duration::from_millis(400));
+    }
+
+    #[test]
+    fn capped() {
+        assert_eq!(policy().delay(20), MAXIMUM_BACKOFF);
+        assert_eq!(policy().delay(u32::MAX), MAXIMUM_BACKOFF);
+    }
+
+    #[test]
+    fn timeout_only_with_retries() {
+        assert_eq!(policy().default_timeout(), Some(ATTEMPT_TIMEOUT));
+        assert_eq!(RetryPolicy::default().default_timeout(), None);
+    }
+
+    #[test]
+    fn timeouts_are_retryable() {
+        let error = dns_transport::Error::NetworkError(std::io::Error::from(ErrorKind::WouldBlock));
+        assert!(is_retryable(&error));
+    }
+
+    #[test]
+    fn refusals_are_not() {
+        let error = dns_transport::Error::NetworkError(std::io::Error::from(ErrorKind::ConnectionRefused));
+        assert!(! is_retryable(&error));
+    }
+}
@@ -0,0 +1,197 @@
+//! Tracking how quickly nameservers respond, so queries can be sent to the
+//! fastest healthy one when several are available.
+
+use std::fmt;
+use std::time::Duration;
+
+
+/// How many failures in a row it takes for a nameserver to be considered
+/// unhealthy, after which it only gets used when all the others have failed.
+const UNHEALTHY_FAILURES: u32 = 3;
+
+/// The round-trip time to assume for a nameserver that fails before it has
+/// ever responded.
+const FAILURE_PENALTY: Duration = Duration::from_secs(1);
+
+/// The longest that a nameserver’s smoothed round-trip time can grow to
+/// after repeated failures.
+const MAXIMUM_RTT: Duration = Duration::from_secs(10);
+
+
+/// The statistics for every nameserver in a pool, in the order that the
+/// nameservers were given.
+#[derive(PartialEq, Debug)]
+pub struct RttTracker {
+    servers: Vec<ServerStats>,
+}
+
+/// The statistics for one nameserver.
+#[derive(PartialEq, Debug)]
+pub struct ServerStats {
+
+    /// The address of the nameserver.
+    pub nameserver: String,
+
+    /// The smoothed round-trip time, or `None` if the nameserver has not
+    /// been tried yet.
+    pub srtt: Option<Duration>,
+
+    /// The number of queries that got a response.
+    pub successes: u32,
+
+    /// The number of queries that failed.
+    pub failures: u32,
+
+    /// The number of queries that have failed since the last response.
+    pub consecutive_failures: u32,
+}
+
+impl RttTracker {
+
+    /// Creates a tracker for the given nameservers, none of which have been
+    /// tried yet.
+    pub fn new(nameservers: Vec<String>) -> Self {
+        let servers = nameservers.into_iter().map(|nameserver| ServerStats {
+            nameserver,
+            srtt: None,
+            successes: 0,
+            failures: 0,
+            consecutive_failures: 0,
+        }).collect();
+
+        Self { servers }
+    }
+
+    /// Returns the indices of the nameservers in the order they should be
+    /// tried. Healthy nameservers come first, with any that have not been
+    /// tried yet at the front so that every one gets measured, followed by
+    /// the rest from fastest to slowest.
+    pub fn preference_order(&self) -> Vec<usize> {
+        let mut order = (0 .. self.servers.len()).collect::<Vec<_>>();
+
+        order.sort_by_key(|&index| {
+            let server = &self.servers[index];
+            (! server.is_healthy(), server.srtt.is_some(), server.srtt)
+        });
+
+        order
+    }
+
+    /// Records that the nameserver at the given index responded after the
+    /// given time, blending it into the smoothed round-trip time.
+    pub fn record_success(&mut self, index: usize, rtt: Duration) {
+        let server = &mut self.servers[index];
+
+        server.srtt = Some(match server.srtt {
+            Some(srtt)  => (srtt * 7 + rtt) / 8,
+            None        => rtt,
+        });
+
+        server.successes += 1;
+        server.consecutive_failures = 0;
+    }
+
+    /// Records that the nameserver at the given index failed to respond,
+    /// backing off its smoothed round-trip time so it gets tried less.
+    pub fn record_failure(&mut self, index: usize) {
+        let server = &mut self.servers[index];
+
+        server.srtt = Some(match server.srtt {
+            Some(srtt)  => (srtt * 2).min(MAXIMUM_RTT),
+            None        => FAILURE_PENALTY,
+        });
+
+        server.failures += 1;
+        server.consecutive_failures += 1;
+    }
+
+    /// Returns the statistics for each nameserver.
+    pub fn servers(&self) -> &[ServerStats] {
+        &self.servers
+    }
+}
+
+impl ServerStats {
+
+    /// Whether this nameserver has not failed too many times in a row.
+    fn is_healthy(&self) -> bool {
+        self.consecutive_failures < UNHEALTHY_FAILURES
+    }
+}
+
+impl fmt::Display for ServerStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} answered, {} failed", self.nameserver, self.successes, self.failures)?;
+
+        if let Some(srtt) = self.srtt {
+            write!(f, ", smoothed RTT {}ms", srtt.as_millis())?;
+        }
+
+        if ! self.is_healthy() {
+            write!(f, " (unhealthy)")?;
+        }
+
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn tracker() -> RttTracker {
+        RttTracker::new(vec![ "a".into(), "b".into(), "c".into() ])
+    }
+
+    #[test]
+    fn untried_first() {
+        let mut tracker = tracker();
+        tracker.record_success(0, Duration::from_millis(10));
+        assert_eq!(tracker.preference_order(), vec![ 1, 2, 0 ]);
+    }
+
+    #[test]
+    fn fastest_first() {
+        let mut tracker = tracker();
+        tracker.record_success(0, Duration::from_millis(50));
+        tracker.record_success(1, Duration::from_millis(10));
+        tracker.record_success(2, Duration::from_millis(30));
+        assert_eq!(tracker.preference_order(), vec![ 1, 2, 0 ]);
+    }
+
+    #[test]
+    fn smoothing() {
+        let mut tracker = tracker();
+        tracker.record_success(0, Duration::from_millis(80));
+        tracker.record_success(0, Duration::from_millis(160));
+        assert_eq!(tracker.servers()[0].srtt, Some(Duration::from_millis(90)));
+    }
+
+    #[test]
+    fn unhealthy_last() {
+        let mut tracker = tracker();
+        tracker.record_success(0, Duration::from_millis(1));
+        tracker.record_success(1, Duration::from_millis(100));
+        tracker.record_success(2, Duration::from_millis(200));
+
+        for _ in 0 .. UNHEALTHY_FAILURES {
+            tracker.record_failure(0);
+        }
+
+        assert_eq!(tracker.preference_order(), vec![ 1, 2, 0 ]);
+    }
+
+    #[test]
+    fn recovers() {
+        let mut tracker = tracker();
+        for _ in 0 .. UNHEALTHY_FAILURES {
+            tracker.record_failure(0);
+        }
+
+        tracker.record_success(0, Duration::from_millis(5));
+        assert_eq!(tracker.servers()[0].consecutive_failures, 0);
+        assert_eq!(tracker.servers()[0].failures, UNHEALTHY_FAILURES);
+    }
+}
@@ -0,0 +1,191 @@
+//! Answering queries from the records in a zone file, for the local
+//! DNS-over-HTTP server that `--serve-doh` starts.
+
+use dns::{Answer, ErrorCode, Flags, Labels, QClass, Response};
+use dns::record::{Record, RecordType};
+
+use dns::zonefile::{Zone, ZoneRecord};
+
+
+/// Answers a query message with the records in the zone, as the zone’s
+/// authoritative nameserver would. Names that have no records at all get
+/// an NXDOMAIN response, and names without records of the type asked for
+/// get an empty one, with both having the zone’s SOA record as authority.
+pub fn answer(zone: &Zone, message: &Response) -> Response {
+    let mut flags = Flags::standard_response();
+    flags.authoritative = true;
+    flags.recursion_desired = message.flags.recursion_desired;
+    flags.recursion_available = false;
+
+    let mut response = Response {
+        transaction_id: message.transaction_id,
+        flags,
+        queries: message.queries.clone(),
+        answers: Vec::new(),
+        authorities: Vec::new(),
+        additionals: Vec::new(),
+    };
+
+    let query = match message.queries.first() {
+        Some(q) if q.qclass == QClass::IN => q,
+        Some(_) => {
+            response.flags.error_code = Some(ErrorCode::QueryRefused);
+            return response;
+        }
+        None => {
+            response.flags.error_code = Some(ErrorCode::FormatError);
+            return response;
+        }
+    };
+
+    let owned = zone.records.iter()
+        .filter(|r| same_name(&r.owner, &query.qname))
+        .collect::<Vec<_>>();
+
+    response.answers = owned.iter()
+        .filter(|r| r.record.record_type() == query.qtype || matches!(r.record, Record::CNAME(_)))
+        .map(|r| standard_answer(r))
+        .collect();
+
+    if owned.is_empty() {
+        response.flags.error_code = Some(ErrorCode::NXDomain);
+    }
+
+    if response.answers.is_empty() {
+        response.authorities = zone.records.iter()
+            .filter(|r| r.record.record_type() == RecordType::SOA)
+            .take(1)
+            .map(standard_answer)
+            .collect();
+    }
+
+    response
+}
+
+/// Turns a record from the zone file into an answer.
+fn standard_answer(zone_record: &ZoneRecord) -> Answer {
+    Answer::Standard {
+        qname: zone_record.owner.clone(),
+        qclass: QClass::IN,
+        ttl: zone_record.ttl,
+        record: zone_record.record.clone(),
+    }
+}
+
+/// Whether two domain names are the same, ignoring case.
+fn same_name(one: &Labels, other: &Labels) -> bool {
+    dns::Name::from(one.clone()) == dns::Name::from(other.clone())
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use dns::Query;
+    use pretty_assertions::assert_eq;
+
+    const ZONE: &str = "\
+        $ORIGIN lookup.dog.\n\
+        $TTL 300\n\
+        @    IN SOA ns.lookup.dog. hostmaster.lookup.dog. 1 7200 3600 1209600 300\n\
+        @    IN A     192.0.2.1\n\
+        www  IN CNAME lookup.dog.\n";
+
+    fn query(name: &str, qtype: RecordType) -> Response {
+        Response {
+            transaction_id: 0xABCD,
+            flags: Flags::query(),
+            queries: vec![ Query { qname: Labels::encode(name).unwrap(), qclass: QClass::IN, qtype } ],
+            answers: vec![],
+            authorities: vec![],
+            additionals: vec![],
+        }
+    }
+
+    #[test]
+    fn answers_records() {
+        let zone = dns::zonefile::parse(ZONE).unwrap();
+        let response = answer(&zone, &query("LOOKUP.dog", RecordType::A));
+
+        assert_eq!(response.transaction_id, 0xABCD);
+        assert_eq!(response.flags.error_code, None);
+        assert!(response.flags.authoritative);
+        assert_eq!(response.answers.len(), 1);
+        assert!(response.authorities.is_empty());
+    }
+
+    #[test]
+    fn answers_cnames() {
+        let zone = dns::zonefile::parse(ZONE).unwrap();
+        let response = answer(&zone, &query("www.lookup.dog", RecordType::A));
+
+        assert!(matches!(response.answers[..], [ Answer::Standard { record: Record::CNAME(_), .. } ]));
+    }
+
+    #[test]
+    fn no_data() {
+        let zone = dns::zonefile::parse(ZONE).unwrap();
+        let response = answer(&zone, &query("lookup.dog", RecordType::MX));
+
+        assert_eq!(response.flags.error_code, None);
+        assert!(response.answers.is_empty());
+        assert!(matches!(response.authorities[..], [ Answer::Standard { record: Record::SOA(_), .. } ]));
+    }
+
+    #[test]
+    fn nxdomain() {
+        let zone = dns::zonefile::parse(ZONE).unwrap();
+        let response = answer(&zone, &query("nope.lookup.dog", RecordType::A));
+
+        assert_eq!(response.flags.error_code, Some(ErrorCode::NXDomain));
+        assert_eq!(response.authorities.len(), 1);
+    }
+
+    #[test]
+    fn other_classes_refused() {
+        let zone = dns::zonefile::parse(ZONE).unwrap();
+        let mut message = query("lookup.dog", RecordType::TXT);
+        message.queries[0].qclass = QClass::CH;
+
+        assert_eq!(answer(&zone, &message).flags.error_code, Some(ErrorCode::QueryRefused));
+    }
+
+    #[test]
+    fn loopback_round_trip() {
+        use std::io::{Read, Write};
+        use std::net::TcpStream;
+        use dns_transport::DohServer;
+
+        let zone = dns::zonefile::parse(ZONE).unwrap();
+        let server = DohServer::bind("127.0.0.1:0").unwrap();
+        let address = server.local_addr().unwrap();
+
+        let client = std::thread::spawn(move || {
+            let request = dns::Request {
+                transaction_id: 0x1234,
+                flags: Flags::query(),
+                query: Query { qname: Labels::encode("lookup.dog").unwrap(), qclass: QClass::IN, qtype: RecordType::A },
+                additional: None,
+            };
+            let body = request.to_bytes().unwrap();
+
+            let mut stream = TcpStream::connect(address).unwrap();
+            write!(stream, "POST /dns-query HTTP/1.1\r\nHost: {}\r\nContent-Type: application/dns-message\r\nContent-Length: {}\r\n\r\n", address, body.len()).unwrap();
+            stream.write_all(&body).unwrap();
+
+            let mut reply = Vec::new();
+            stream.read_to_end(&mut reply).unwrap();
+            reply
+        });
+
+        server.serve_one(&|message: &Response| answer(&zone, message)).unwrap();
+        let reply = client.join().unwrap();
+
+        let header_end = reply.windows(4).position(|w| w == b"\r\n\r\n").unwrap() + 4;
+        assert!(reply.starts_with(b"HTTP/1.1 200 OK\r\n"));
+
+        let response = Response::from_bytes(&reply[header_end ..]).unwrap();
+        assert_eq!(response.transaction_id, 0x1234);
+        assert_eq!(response.answers.len(), 1);
+    }
+}
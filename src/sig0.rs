@@ -0,0 +1,252 @@
+//! Reading the key pair given with `--sig0`, and signing requests with it.
+//!
+//! Keys are read from the pair of files that BIND’s `dnssec-keygen -T KEY`
+//! writes: a `.key` file with the KEY record holding the public key, and a
+//! `.private` file with the private key. Either of them can be given, and
+//! the other is found next to it.
+
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use dns::{Labels, Sig0Key};
+use dns::record::DNSKEY;
+
+
+/// Reads the key pair from the files with the given path, which should be
+/// the path to either the `.key` or the `.private` file.
+pub fn read_key(path: &Path) -> Result<Sig0Key, String> {
+    let (public_path, private_path) = key_paths(path);
+
+    let read = |path: &Path| fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e));
+
+    let (signer, public) = parse_public_key(&read(&public_path)?)
+        .ok_or_else(|| format!("No KEY record in {}", public_path.display()))?;
+    let (algorithm, private_key) = parse_private_key(&read(&private_path)?)
+        .ok_or_else(|| format!("No private key in {}", private_path.display()))?;
+
+    if algorithm != public.algorithm {
+        return Err(format!("The keys in {} and {} use different algorithms", public_path.display(), private_path.display()));
+    }
+
+    Ok(Sig0Key { signer, public, private_key })
+}
+
+/// Works out the paths of the two files from the path to one of them,
+/// adding the extensions if neither is there.
+fn key_paths(path: &Path) -> (PathBuf, PathBuf) {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("key" | "private") => {
+            (path.with_extension("key"), path.with_extension("private"))
+        }
+        _ => {
+            let with_suffix = |suffix| {
+                let mut name = OsString::from(path.as_os_str());
+                name.push(suffix);
+                PathBuf::from(name)
+            };
+
+            (with_suffix(".key"), with_suffix(".private"))
+        }
+    }
+}
+
+/// Reads the owner and data of the first KEY record in the contents of a
+/// `.key` file.
+fn parse_public_key(contents: &str) -> Option<(Labels, DNSKEY)> {
+    let tokens = contents.lines()
+        .map(|line| line.split(';').next().unwrap_or_default())
+        .flat_map(str::split_whitespace)
+        .filter(|token| *token != "(" && *token != ")")
+        .collect::<Vec<_>>();
+
+    let position = tokens.iter().position(|token| token.eq_ignore_ascii_case("KEY"))?;
+    let signer = Labels::encode(tokens.first()?).ok()?;
+
+    let fields = tokens.get(position + 1 ..)?;
+    if fields.len() < 4 {
+        return None;
+    }
+
+    let public = DNSKEY {
+        flags: fields[0].parse().ok()?,
+        protocol: fields[1].parse().ok()?,
+        algorithm: fields[2].parse().ok()?,
+        public_key: base64::decode(fields[3 ..].concat()).ok()?,
+    };
+
+    Some((signer, public))
+}
+
+/// Reads the algorithm number and the private key from the contents of a
+/// `.private` file.
+fn parse_private_key(contents: &str) -> Option<(u8, Vec<u8>)> {
+    let mut algorithm = None;
+    let mut private_key = None;
+
+    for (field, value) in contents.lines().filter_map(|line| line.split_once(':')) {
+        match field.trim() {
+            "Algorithm"   => algorithm = value.split_whitespace().next().and_then(|n| n.parse().ok()),
+            "PrivateKey"  => private_key = base64::decode(value.trim()).ok(),
+            _             => {}
+        }
+    }
+
+    Some((algorithm?, private_key?))
+}
+
+
+/// The signing transport and zone transfers, which need the feature.
+#[cfg(feature = "with_sig0")]
+mod signing {
+    use std::convert::TryFrom;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use dns::Sig0Key;
+    use dns_transport::{Transport, TcpTransport, Error, Mismatch};
+    use log::*;
+
+    /// The **SIG(0) transport** wraps another transport, signing each
+    /// request before it gets sent down it. The responses are not checked,
+    /// as that would need the server’s public key.
+    pub struct Sig0Transport {
+        inner: Box<dyn Transport>,
+        key: Sig0Key,
+    }
+
+    impl Sig0Transport {
+
+        /// Creates a new SIG(0) transport that sends requests signed with
+        /// the given key over the given transport.
+        pub fn new(inner: Box<dyn Transport>, key: Sig0Key) -> Self {
+            Self { inner, key }
+        }
+
+        /// Signs the request and sends it, returning the bytes of the
+        /// response.
+        fn exchange(&self, request: &dns::Request) -> Result<Vec<u8>, Error> {
+            let mut bytes_to_send = request.to_bytes().expect("failed to serialise request");
+            self.key.sign_request(&mut bytes_to_send, unix_time()).expect("failed to sign request");
+            info!("Signed request with the key for {}", self.key.signer);
+
+            self.inner.send_raw(&bytes_to_send)
+        }
+    }
+
+    impl Transport for Sig0Transport {
+        fn send(&self, request: &dns::Request) -> Result<dns::Response, Error> {
+            let received_bytes = self.exchange(request)?;
+            let response = dns::Response::from_bytes(&received_bytes)?;
+            Ok(response)
+        }
+
+        fn send_lenient(&self, request: &dns::Request) -> Result<dns::PartialResponse, Error> {
+            let received_bytes = self.exchange(request)?;
+            let partial = dns::Response::from_bytes_lenient(&received_bytes)?;
+            Ok(partial)
+        }
+
+        fn send_raw(&self, bytes_to_send: &[u8]) -> Result<Vec<u8>, Error> {
+            self.inner.send_raw(bytes_to_send)
+        }
+
+        fn ignored_packets(&self) -> usize {
+            self.inner.ignored_packets()
+        }
+
+        fn tcp_fallbacks(&self) -> usize {
+            self.inner.tcp_fallbacks()
+        }
+
+        fn take_mismatches(&self) -> Vec<Mismatch> {
+            self.inner.take_mismatches()
+        }
+    }
+
+    /// Transfers a zone with a request signed with the given key.
+    pub fn signed_zone_transfer(transport: &TcpTransport, request: &dns::Request, key: &Sig0Key) -> Result<Vec<dns::Response>, Error> {
+        let mut bytes_to_send = request.to_bytes().expect("failed to serialise request");
+        key.sign_request(&mut bytes_to_send, unix_time()).expect("failed to sign request");
+        transport.zone_transfer_checked(bytes_to_send, |message| Ok(message.to_vec()))
+    }
+
+    /// Checks that the key can sign requests, so any problem with it shows
+    /// up before anything gets sent.
+    pub fn check_key(key: &Sig0Key) -> Result<(), dns::Sig0Error> {
+        let mut message = vec![ 0; 12 ];
+        key.sign_request(&mut message, unix_time())
+    }
+
+    /// The current time, in seconds since the Unix epoch, which signatures
+    /// are valid around. SIG records only have room for 32 bits of it.
+    fn unix_time() -> u32 {
+        let seconds = SystemTime::now().duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs());
+
+        u32::try_from(seconds % (1 << 32)).expect("time out of range")
+    }
+}
+
+#[cfg(feature = "with_sig0")]
+pub use self::signing::{Sig0Transport, signed_zone_transfer, check_key};
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn public_key() {
+        let contents = "\
+            ; This is a key-signing key, keyid 3868, for update.lookup.dog.\n\
+            update.lookup.dog. IN KEY 512 3 15 l02Woi0iS8Aa25FQkUd9RMzZHJpBoRQwAQEX1SxZJA4=\n";
+
+        let (signer, public) = parse_public_key(contents).unwrap();
+        assert_eq!(signer, Labels::encode("update.lookup.dog").unwrap());
+        assert_eq!(public.flags, 512);
+        assert_eq!(public.protocol, 3);
+        assert_eq!(public.algorithm, 15);
+        assert_eq!(public.public_key.len(), 32);
+    }
+
+    #[test]
+    fn public_key_over_several_lines() {
+        let contents = "update.lookup.dog. 3600 IN KEY 512 3 15 (\n    l02Woi0iS8Aa25FQkUd9\n    RMzZHJpBoRQwAQEX1SxZJA4= ) ; comment\n";
+        let (_, public) = parse_public_key(contents).unwrap();
+        assert_eq!(public.public_key.len(), 32);
+    }
+
+    #[test]
+    fn no_public_key() {
+        assert_eq!(parse_public_key("; nothing here\n"), None);
+        assert_eq!(parse_public_key("update.lookup.dog. IN KEY 512 3\n"), None);
+    }
+
+    #[test]
+    fn private_key() {
+        let contents = "\
+            Private-key-format: v1.3\n\
+            Algorithm: 15 (ED25519)\n\
+            PrivateKey: ODIyNjAzODQ2MjgwODAxMjI2NDUxOTAyMDQxNDIyNjI=\n\
+            Created: 20240101000000\n";
+
+        let (algorithm, private_key) = parse_private_key(contents).unwrap();
+        assert_eq!(algorithm, 15);
+        assert_eq!(private_key.len(), 32);
+    }
+
+    #[test]
+    fn no_private_key() {
+        assert_eq!(parse_private_key("Private-key-format: v1.3\nAlgorithm: 15 (ED25519)\n"), None);
+    }
+
+    #[test]
+    fn paths_from_either_file() {
+        let expected = (PathBuf::from("Kupdate.lookup.dog.+015+03868.key"), PathBuf::from("Kupdate.lookup.dog.+015+03868.private"));
+        assert_eq!(key_paths(Path::new("Kupdate.lookup.dog.+015+03868.key")), expected);
+        assert_eq!(key_paths(Path::new("Kupdate.lookup.dog.+015+03868.private")), expected);
+        assert_eq!(key_paths(Path::new("Kupdate.lookup.dog.+015+03868")), expected);
+    }
+}
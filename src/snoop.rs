@@ -0,0 +1,384 @@
+//! Snooping on a recursive resolver’s cache, by sending it non-recursive
+//! queries that it can only answer from what it already has cached, and
+//! looking at how the TTLs in its answers count down to tell cached records
+//! apart from ones it serves from local data.
+
+use std::fmt;
+use std::thread;
+use std::time::Duration;
+
+use dns::{Answer, ErrorCode, Labels, Response};
+use dns::record::{Record, RecordType};
+use dns_transport::Transport;
+use log::*;
+
+use crate::output::record_type_name;
+use crate::requests::RequestGenerator;
+use crate::resolve::ResolverLookupError;
+
+
+/// How many times each name gets queried.
+const SAMPLE_COUNT: usize = 2;
+
+/// How long to wait between each round of queries. This is long enough for
+/// the TTL of a cached record to count down by at least one second.
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(2);
+
+
+/// The results of snooping on a resolver’s cache.
+#[derive(PartialEq, Debug)]
+pub struct SnoopReport {
+
+    /// What was inferred about each name and type that was queried.
+    pub results: Vec<(Labels, RecordType, Verdict)>,
+}
+
+/// What one non-recursive query got back.
+#[derive(PartialEq, Debug, Clone)]
+enum Sample {
+
+    /// The resolver answered, and this was the lowest TTL in the answer.
+    Answered(u32),
+
+    /// The resolver said the name or type does not exist.
+    Absent,
+
+    /// The resolver sent back no answers, or a referral to the nameservers
+    /// it knows about that are closest to the name.
+    Unanswered,
+
+    /// The resolver refused to answer a non-recursive query.
+    Refused,
+
+    /// No usable response arrived.
+    Failed(String),
+}
+
+/// What can be inferred about whether the resolver has a name cached.
+#[derive(PartialEq, Debug)]
+pub enum Verdict {
+
+    /// The resolver answered, and the TTL was counting down, so the answer
+    /// came from its cache, where it has this many seconds left.
+    Cached(u32),
+
+    /// The resolver answered, but the TTL stayed the same, so the answer
+    /// may come from local data rather than from its cache.
+    FixedTtl(u32),
+
+    /// The resolver has cached that the name or type does not exist.
+    CachedAbsent,
+
+    /// The resolver does not have the name cached.
+    NotCached,
+
+    /// The resolver refuses non-recursive queries, so it cannot be snooped
+    /// on.
+    Refused,
+
+    /// No usable response arrived.
+    Failed(String),
+}
+
+
+/// Sends a non-recursive query for each domain and type in the inputs to
+/// the first nameserver, using the first transport type, a few times with a
+/// pause in between, and works out which of them the resolver has cached.
+pub fn run_snoop(requests: RequestGenerator) -> Result<SnoopReport, ResolverLookupError> {
+    let edns = requests.edns;
+    let protocol_tweaks = requests.protocol_tweaks.clone();
+    let txid_generator = requests.txid_generator;
+    let domains = requests.inputs.domains.clone();
+    let record_types = requests.inputs.record_types.clone();
+    let transport = requests.first_transport()?;
+
+    let queries = domains.iter()
+        .flat_map(|domain| record_types.iter().map(move |qtype| (domain.clone(), *qtype)))
+        .collect::<Vec<_>>();
+
+    let mut samples = vec![ Vec::new(); queries.len() ];
+
+    for round in 0 .. SAMPLE_COUNT {
+        if round > 0 {
+            thread::sleep(SAMPLE_INTERVAL);
+        }
+
+        for ((qname, qtype), samples) in queries.iter().zip(&mut samples) {
+            let mut flags = dns::Flags::query();
+            protocol_tweaks.set_request_flags(&mut flags);
+            flags.recursion_desired = false;
+
+            let mut additional = None;
+            if edns.should_send() {
+                let mut opt = dns::Request::additional_record();
+                protocol_tweaks.set_request_opt_fields(&mut opt);
+                additional = Some(opt);
+            }
+
+            let query = dns::Query { qname: qname.clone(), qtype: *qtype, qclass: dns::QClass::IN };
+            let request = dns::Request { transaction_id: txid_generator.generate(), flags, query, additional };
+            samples.push(send_and_sample(&*transport, &request));
+        }
+    }
+
+    let results = queries.into_iter().zip(samples)
+        .map(|((qname, qtype), samples)| (qname, qtype, infer(&samples)))
+        .collect();
+
+    Ok(SnoopReport { results })
+}
+
+/// Sends one request and samples the response.
+fn send_and_sample(transport: &dyn Transport, request: &dns::Request) -> Sample {
+    match transport.send(request) {
+        Ok(response) => {
+            sample(&response)
+        }
+        Err(e) => {
+            warn!("Snooping query failed: {:?}", e);
+            Sample::Failed(format!("{:?}", e))
+        }
+    }
+}
+
+/// Works out what a response to a non-recursive query says.
+fn sample(response: &Response) -> Sample {
+    match response.flags.error_code {
+        None                            => {},
+        Some(ErrorCode::NXDomain)       => return Sample::Absent,
+        Some(ErrorCode::QueryRefused)   => return Sample::Refused,
+        Some(other)                     => return Sample::Failed(format!("{:?}", other)),
+    }
+
+    let lowest_ttl = response.answers.iter()
+        .filter_map(|answer| match answer {
+            Answer::Standard { ttl, .. }  => Some(*ttl),
+            Answer::Pseudo { .. }         => None,
+        })
+        .min();
+
+    if let Some(ttl) = lowest_ttl {
+        return Sample::Answered(ttl);
+    }
+
+    // An empty answer with the zone’s SOA record is a cached “no data”
+    // response, but without one it is a referral, or nothing at all.
+    let has_soa = response.authorities.iter()
+        .any(|answer| matches!(answer, Answer::Standard { record: Record::SOA(_), .. }));
+
+    if has_soa { Sample::Absent } else { Sample::Unanswered }
+}
+
+/// Infers whether a name is cached from the samples for it. An answer
+/// whose TTL went down between samples came out of the cache, as did one
+/// whose TTL went up, which happens when the record expired and was looked
+/// up again in between.
+fn infer(samples: &[Sample]) -> Verdict {
+    let ttls = samples.iter()
+        .filter_map(|sample| if let Sample::Answered(ttl) = sample { Some(*ttl) } else { None })
+        .collect::<Vec<_>>();
+
+    if let (Some(first), Some(last)) = (ttls.first(), ttls.last()) {
+        let fixed = ttls.len() > 1 && ttls.iter().all(|ttl| ttl == first);
+        return if fixed { Verdict::FixedTtl(*last) } else { Verdict::Cached(*last) };
+    }
+
+    if samples.contains(&Sample::Absent) {
+        Verdict::CachedAbsent
+    }
+    else if samples.contains(&Sample::Unanswered) {
+        Verdict::NotCached
+    }
+    else if samples.contains(&Sample::Refused) {
+        Verdict::Refused
+    }
+    else {
+        match samples.first() {
+            Some(Sample::Failed(why))  => Verdict::Failed(why.clone()),
+            _                          => Verdict::Failed(String::from("no queries sent")),
+        }
+    }
+}
+
+
+impl SnoopReport {
+
+    /// The number of names that look like they are in the cache.
+    pub fn cached_count(&self) -> usize {
+        self.results.iter()
+            .filter(|(_, _, verdict)| matches!(verdict, Verdict::Cached(_) | Verdict::CachedAbsent))
+            .count()
+    }
+
+    /// Whether the resolver answered any of the queries at all, without
+    /// which nothing can be inferred about its cache.
+    pub fn any_answered(&self) -> bool {
+        self.results.iter()
+            .any(|(_, _, verdict)| ! matches!(verdict, Verdict::Refused | Verdict::Failed(_)))
+    }
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Cached(ttl)    => write!(f, "cached, expires in {}s", ttl),
+            Self::FixedTtl(ttl)  => write!(f, "answered with a fixed TTL of {}s (possibly local data)", ttl),
+            Self::CachedAbsent   => write!(f, "cached as non-existent"),
+            Self::NotCached      => write!(f, "not cached"),
+            Self::Refused        => write!(f, "refused"),
+            Self::Failed(why)    => write!(f, "failed: {}", why),
+        }
+    }
+}
+
+impl fmt::Display for SnoopReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (qname, qtype, verdict) in &self.results {
+            writeln!(f, "{:<30} {:<6} {}", qname.to_string(), record_type_name(qtype.type_number()), verdict)?;
+        }
+
+        if self.any_answered() {
+            writeln!(f, "The resolver appears to have {} of {} queries cached", self.cached_count(), self.results.len())?;
+        }
+        else {
+            writeln!(f, "warning: the resolver did not answer any non-recursive queries, so its cache cannot be snooped on")?;
+        }
+
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use dns::record::{A, NS, SOA};
+    use pretty_assertions::assert_eq;
+
+    fn response(error_code: Option<ErrorCode>, answers: Vec<Answer>, authorities: Vec<Answer>) -> Response {
+        let mut flags = dns::Flags::standard_response();
+        flags.error_code = error_code;
+
+        Response {
+            transaction_id: 0x1234,
+            flags,
+            queries: vec![],
+            answers,
+            authorities,
+            additionals: vec![],
+        }
+    }
+
+    fn answer(ttl: u32, record: Record) -> Answer {
+        Answer::Standard { qname: Labels::encode("lookup.dog").unwrap(), qclass: dns::QClass::IN, ttl, record }
+    }
+
+    fn a(ttl: u32) -> Answer {
+        answer(ttl, Record::A(A { address: "192.0.2.1".parse().unwrap() }))
+    }
+
+    #[test]
+    fn answered() {
+        assert_eq!(sample(&response(None, vec![ a(300), a(120) ], vec![])),
+                   Sample::Answered(120));
+    }
+
+    #[test]
+    fn referral() {
+        let ns = answer(172800, Record::NS(NS { nameserver: Labels::encode("a.nic.dog").unwrap() }));
+        assert_eq!(sample(&response(None, vec![], vec![ ns ])),
+                   Sample::Unanswered);
+    }
+
+    #[test]
+    fn no_data() {
+        let soa = answer(300, Record::SOA(SOA {
+            mname: Labels::encode("ns.lookup.dog").unwrap(),
+            rname: Labels::encode("hostmaster.lookup.dog").unwrap(),
+            serial: 1, refresh_interval: 3600, retry_interval: 600, expire_limit: 86400, minimum_ttl: 60,
+        }));
+
+        assert_eq!(sample(&response(None, vec![], vec![ soa ])),
+                   Sample::Absent);
+    }
+
+    #[test]
+    fn nxdomain() {
+        assert_eq!(sample(&response(Some(ErrorCode::NXDomain), vec![], vec![])),
+                   Sample::Absent);
+    }
+
+    #[test]
+    fn refused() {
+        assert_eq!(sample(&response(Some(ErrorCode::QueryRefused), vec![], vec![])),
+                   Sample::Refused);
+    }
+
+    #[test]
+    fn counting_down() {
+        assert_eq!(infer(&[ Sample::Answered(240), Sample::Answered(238) ]),
+                   Verdict::Cached(238));
+    }
+
+    #[test]
+    fn looked_up_again() {
+        assert_eq!(infer(&[ Sample::Answered(1), Sample::Answered(300) ]),
+                   Verdict::Cached(300));
+    }
+
+    #[test]
+    fn expired_in_between() {
+        assert_eq!(infer(&[ Sample::Answered(1), Sample::Unanswered ]),
+                   Verdict::Cached(1));
+    }
+
+    #[test]
+    fn fixed_ttl() {
+        assert_eq!(infer(&[ Sample::Answered(3600), Sample::Answered(3600) ]),
+                   Verdict::FixedTtl(3600));
+    }
+
+    #[test]
+    fn not_cached() {
+        assert_eq!(infer(&[ Sample::Unanswered, Sample::Unanswered ]),
+                   Verdict::NotCached);
+    }
+
+    #[test]
+    fn refused_everything() {
+        assert_eq!(infer(&[ Sample::Refused, Sample::Refused ]),
+                   Verdict::Refused);
+    }
+
+    #[test]
+    fn failed() {
+        assert_eq!(infer(&[ Sample::Failed("TruncatedResponse".into()), Sample::Refused ]),
+                   Verdict::Refused);
+        assert_eq!(infer(&[ Sample::Failed("TruncatedResponse".into()) ]),
+                   Verdict::Failed("TruncatedResponse".into()));
+    }
+
+    #[test]
+    fn report() {
+        let report = SnoopReport {
+            results: vec![
+                (Labels::encode("lookup.dog").unwrap(), RecordType::A, Verdict::Cached(238)),
+                (Labels::encode("nothing.dog").unwrap(), RecordType::AAAA, Verdict::NotCached),
+            ],
+        };
+
+        assert_eq!(report.to_string(), "\
+            lookup.dog.                    A      cached, expires in 238s\n\
+            nothing.dog.                   AAAA   not cached\n\
+            The resolver appears to have 1 of 2 queries cached\n");
+    }
+
+    #[test]
+    fn report_all_refused() {
+        let report = SnoopReport {
+            results: vec![ (Labels::encode("lookup.dog").unwrap(), RecordType::A, Verdict::Refused) ],
+        };
+
+        assert!(report.to_string().contains("cannot be snooped on"));
+    }
+}
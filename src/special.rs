@@ -0,0 +1,217 @@
+//! Recognising special-use domain names, which should not be looked up in
+//! the DNS in the usual way.
+
+use std::fmt;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::time::Duration;
+
+use dns::{Labels, QClass};
+use dns::record::{Record, RecordType, A, AAAA};
+use dns_transport::{Transport, MdnsTransport, Error};
+
+
+/// A **special-use** domain name is one that has been reserved for a
+/// particular purpose, rather than delegated as part of the DNS.
+///
+/// # References
+///
+/// - [RFC 6761](https://tools.ietf.org/html/rfc6761) — Special-Use Domain
+///   Names (February 2013)
+/// - [RFC 6762](https://tools.ietf.org/html/rfc6762) — Multicast DNS
+///   (February 2013)
+/// - [RFC 8375](https://tools.ietf.org/html/rfc8375) — Special-Use Domain
+///   'home.arpa.' (May 2018)
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum SpecialUse {
+
+    /// `localhost` and its subdomains, which always refer to the loopback
+    /// address.
+    Localhost,
+
+    /// `.local`, which is resolved using multicast DNS on the local link.
+    Local,
+
+    /// `.test`, which is reserved for testing.
+    Test,
+
+    /// `.invalid`, which is guaranteed never to exist.
+    Invalid,
+
+    /// `.home.arpa`, which is used within residential home networks.
+    HomeArpa,
+
+    /// The reverse-lookup domains for the private IPv4 address ranges, such
+    /// as `10.in-addr.arpa`.
+    PrivateReverse,
+}
+
+/// How long to wait for an mDNS responder before giving up.
+const MDNS_TIMEOUT: Duration = Duration::from_secs(3);
+
+impl SpecialUse {
+
+    /// Determines which special-use domain, if any, the given name falls
+    /// under.
+    pub fn of(domain: &Labels) -> Option<Self> {
+        let name = domain.to_string().to_ascii_lowercase();
+        let is_under = |suffix: &str| {
+            name == suffix || name.ends_with(&format!(".{}", suffix))
+        };
+
+        if is_under("localhost.") {
+            Some(Self::Localhost)
+        }
+        else if is_under("local.") {
+            Some(Self::Local)
+        }
+        else if is_under("test.") {
+            Some(Self::Test)
+        }
+        else if is_under("invalid.") {
+            Some(Self::Invalid)
+        }
+        else if is_under("home.arpa.") {
+            Some(Self::HomeArpa)
+        }
+        else if is_under("10.in-addr.arpa.") || is_under("168.192.in-addr.arpa.")
+             || (16 ..= 31).any(|octet| is_under(&format!("{}.172.in-addr.arpa.", octet)))
+        {
+            Some(Self::PrivateReverse)
+        }
+        else {
+            None
+        }
+    }
+
+    /// Returns the transport that queries for names under this domain should
+    /// be sent over instead of the user’s resolver, if they should be
+    /// handled differently.
+    pub fn special_transport(self) -> Option<Box<dyn Transport>> {
+        match self {
+            Self::Localhost  => Some(Box::new(LocalhostTransport)),
+            Self::Local      => Some(Box::new(MdnsTransport::new(MDNS_TIMEOUT))),
+            _                => None,
+        }
+    }
+}
+
+impl fmt::Display for SpecialUse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Localhost       => write!(f, "localhost"),
+            Self::Local           => write!(f, "local"),
+            Self::Test            => write!(f, "test"),
+            Self::Invalid         => write!(f, "invalid"),
+            Self::HomeArpa        => write!(f, "home.arpa"),
+            Self::PrivateReverse  => write!(f, "private address reverse lookup"),
+        }
+    }
+}
+
+
+/// The **localhost transport**, which does not touch the network at all,
+/// and instead answers queries for `localhost` names with the loopback
+/// addresses, as RFC 6761 requires.
+struct LocalhostTransport;
+
+impl Transport for LocalhostTransport {
+    fn send(&self, request: &dns::Request) -> Result<dns::Response, Error> {
+        let query = &request.query;
+
+        let record = match (query.qclass, query.qtype) {
+            (QClass::IN, RecordType::A)     => Some(Record::A(A { address: Ipv4Addr::LOCALHOST })),
+            (QClass::IN, RecordType::AAAA)  => Some(Record::AAAA(AAAA { address: Ipv6Addr::LOCALHOST })),
+            _                               => None,
+        };
+
+        let answers = record.into_iter()
+            .map(|record| dns::Answer::Standard { qname: query.qname.clone(), qclass: query.qclass, ttl: 0, record })
+            .collect();
+
+        let mut flags = dns::Flags::standard_response();
+        flags.authoritative = true;
+
+        Ok(dns::Response {
+            transaction_id: request.transaction_id,
+            flags,
+            queries: vec![ dns::Query { qname: query.qname.clone(), qclass: query.qclass, qtype: query.qtype } ],
+            answers,
+            authorities: Vec::new(),
+            additionals: Vec::new(),
+        })
+    }
+
+    fn send_raw(&self, _bytes_to_send: &[u8]) -> Result<Vec<u8>, Error> {
+        unreachable!("the localhost transport only answers parsed requests")
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn special(input: &str) -> Option<SpecialUse> {
+        SpecialUse::of(&Labels::encode(input).unwrap())
+    }
+
+    #[test]
+    fn localhost() {
+        assert_eq!(special("localhost"),      Some(SpecialUse::Localhost));
+        assert_eq!(special("dog.LOCALHOST."), Some(SpecialUse::Localhost));
+    }
+
+    #[test]
+    fn local() {
+        assert_eq!(special("printer.local"),  Some(SpecialUse::Local));
+    }
+
+    #[test]
+    fn reserved() {
+        assert_eq!(special("lookup.test"),    Some(SpecialUse::Test));
+        assert_eq!(special("lookup.invalid"), Some(SpecialUse::Invalid));
+        assert_eq!(special("nas.home.arpa"),  Some(SpecialUse::HomeArpa));
+    }
+
+    #[test]
+    fn private_reverse() {
+        assert_eq!(special("1.0.0.10.in-addr.arpa"),   Some(SpecialUse::PrivateReverse));
+        assert_eq!(special("1.1.168.192.in-addr.arpa"), Some(SpecialUse::PrivateReverse));
+        assert_eq!(special("1.1.20.172.in-addr.arpa"),  Some(SpecialUse::PrivateReverse));
+        assert_eq!(special("1.1.32.172.in-addr.arpa"),  None);
+    }
+
+    #[test]
+    fn ordinary() {
+        assert_eq!(special("lookup.dog"),        None);
+        assert_eq!(special("localhost.dog"),     None);
+        assert_eq!(special("notlocalhost"),      None);
+        assert_eq!(special("110.in-addr.arpa"),  None);
+    }
+
+    #[test]
+    fn localhost_answer() {
+        let request = dns::Request {
+            transaction_id: 0xABCD,
+            flags: dns::Flags::query(),
+            query: dns::Query {
+                qname: Labels::encode("localhost").unwrap(),
+                qclass: QClass::IN,
+                qtype: RecordType::AAAA,
+            },
+            additional: None,
+        };
+
+        let response = LocalhostTransport.send(&request).unwrap();
+        assert_eq!(response.transaction_id, 0xABCD);
+        assert_eq!(response.answers, vec![
+            dns::Answer::Standard {
+                qname: Labels::encode("localhost").unwrap(),
+                qclass: QClass::IN,
+                ttl: 0,
+                record: Record::AAAA(AAAA { address: Ipv6Addr::LOCALHOST }),
+            },
+        ]);
+    }
+}
@@ -0,0 +1,182 @@
+//! Signing requests with a TSIG key given with `--tsig`, and checking the
+//! signatures on the responses, which servers require before they allow
+//! zone transfers and updates.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use dns::TsigKey;
+use dns::tsig::TsigVerifier;
+use dns_transport::{Transport, TcpTransport, Error, Mismatch};
+use log::*;
+
+
+/// The **signing transport** wraps another transport, signing each request
+/// before it gets sent down it, and rejecting any response that does not
+/// have a valid signature made with the same key.
+pub struct SigningTransport {
+    inner: Box<dyn Transport>,
+    key: TsigKey,
+}
+
+impl SigningTransport {
+
+    /// Creates a new signing transport that sends requests signed with the
+    /// given key over the given transport.
+    pub fn new(inner: Box<dyn Transport>, key: TsigKey) -> Self {
+        Self { inner, key }
+    }
+
+    /// Signs the request and sends it, then checks the signature on the
+    /// response, returning its bytes with the signature taken off.
+    fn exchange(&self, request: &dns::Request) -> Result<Vec<u8>, Error> {
+        let mut bytes_to_send = request.to_bytes().expect("failed to serialise request");
+        let request_mac = self.key.sign_request(&mut bytes_to_send, unix_time()).expect("failed to sign request");
+        info!("Signed request with key {}", self.key.name);
+
+        let received_bytes = self.inner.send_raw(&bytes_to_send)?;
+
+        let mut verifier = TsigVerifier::new(&self.key, request_mac);
+        Ok(verifier.verify(&received_bytes, unix_time())?)
+    }
+}
+
+impl Transport for SigningTransport {
+    fn send(&self, request: &dns::Request) -> Result<dns::Response, Error> {
+        let stripped_bytes = self.exchange(request)?;
+        let response = dns::Response::from_bytes(&stripped_bytes)?;
+        Ok(response)
+    }
+
+    fn send_lenient(&self, request: &dns::Request) -> Result<dns::PartialResponse, Error> {
+        let stripped_bytes = self.exchange(request)?;
+        let partial = dns::Response::from_bytes_lenient(&stripped_bytes)?;
+        Ok(partial)
+    }
+
+    fn send_raw(&self, bytes_to_send: &[u8]) -> Result<Vec<u8>, Error> {
+        self.inner.send_raw(bytes_to_send)
+    }
+
+    fn ignored_packets(&self) -> usize {
+        self.inner.ignored_packets()
+    }
+
+    fn tcp_fallbacks(&self) -> usize {
+        self.inner.tcp_fallbacks()
+    }
+
+    fn take_mismatches(&self) -> Vec<Mismatch> {
+        self.inner.take_mismatches()
+    }
+}
+
+
+/// Transfers a zone with a signed request, checking the signatures on
+/// the messages that come back, which have to end with a signed one.
+pub fn signed_zone_transfer(transport: &TcpTransport, request: &dns::Request, key: &TsigKey) -> Result<Vec<dns::Response>, Error> {
+    let mut bytes_to_send = request.to_bytes().expect("failed to serialise request");
+    let request_mac = key.sign_request(&mut bytes_to_send, unix_time()).expect("failed to sign request");
+
+    let mut verifier = TsigVerifier::new(key, request_mac);
+    let responses = transport.zone_transfer_checked(bytes_to_send, |message| {
+        Ok(verifier.verify(message, unix_time())?)
+    })?;
+
+    verifier.finish()?;
+    Ok(responses)
+}
+
+/// The current time, in seconds since the Unix epoch, which is what goes in
+/// and gets checked against the time in signatures.
+fn unix_time() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use dns::TsigError;
+    use pretty_assertions::assert_eq;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    /// A transport that answers every message with the same bytes.
+    struct FixedTransport(Vec<u8>);
+
+    impl Transport for FixedTransport {
+        fn send_raw(&self, _bytes_to_send: &[u8]) -> Result<Vec<u8>, Error> {
+            Ok(self.0.clone())
+        }
+    }
+
+    /// A transport that remembers the last message sent down it, and
+    /// answers with the same bytes every time.
+    struct RecordingTransport(Vec<u8>, Rc<RefCell<Vec<u8>>>);
+
+    impl Transport for RecordingTransport {
+        fn send_raw(&self, bytes_to_send: &[u8]) -> Result<Vec<u8>, Error> {
+            *self.1.borrow_mut() = bytes_to_send.to_vec();
+            Ok(self.0.clone())
+        }
+    }
+
+    fn key() -> TsigKey {
+        TsigKey {
+            name: dns::Labels::encode("transfer.lookup.dog").unwrap(),
+            algorithm: dns::TsigAlgorithm::HmacSha256,
+            secret: b"shared secret".to_vec(),
+        }
+    }
+
+    fn request() -> dns::Request {
+        let query = dns::Query {
+            qname: dns::Labels::encode("lookup.dog").unwrap(),
+            qtype: dns::record::RecordType::A,
+            qclass: dns::QClass::IN,
+        };
+
+        dns::Request { transaction_id: 0x1234, flags: dns::Flags::query(), query, additional: None }
+    }
+
+    #[test]
+    fn unsigned_response_is_rejected() {
+        let unsigned = request().to_bytes().unwrap();
+        let transport = SigningTransport::new(Box::new(FixedTransport(unsigned)), key());
+
+        match transport.send(&request()) {
+            Err(Error::TsigError(e)) => assert_eq!(e, TsigError::Unsigned),
+            _ => panic!("response should have been rejected"),
+        }
+    }
+
+    #[test]
+    fn unsigned_response_is_rejected_leniently() {
+        let unsigned = request().to_bytes().unwrap();
+        let transport = SigningTransport::new(Box::new(FixedTransport(unsigned)), key());
+
+        match transport.send_lenient(&request()) {
+            Err(Error::TsigError(e)) => assert_eq!(e, TsigError::Unsigned),
+            _ => panic!("response should have been rejected"),
+        }
+    }
+
+    #[test]
+    fn lenient_requests_are_signed() {
+        let unsigned = request().to_bytes().unwrap();
+        let sent = Rc::new(RefCell::new(Vec::new()));
+        let transport = SigningTransport::new(Box::new(RecordingTransport(unsigned.clone(), Rc::clone(&sent))), key());
+
+        let _ = transport.send_lenient(&request());
+        let sent = sent.borrow();
+        assert!(sent.len() > unsigned.len());
+        assert_eq!(&sent[10 .. 12], &[ 0, 1 ]);  // one additional record
+    }
+
+    #[test]
+    fn raw_messages_are_not_signed() {
+        let transport = SigningTransport::new(Box::new(FixedTransport(vec![1, 2, 3])), key());
+        assert_eq!(transport.send_raw(&[4, 5, 6]).unwrap(), vec![1, 2, 3]);
+    }
+}
@@ -0,0 +1,791 @@
+//! Validating the DNSSEC signatures on the answers in responses, by
+//! following the chain of DS and DNSKEY records from the root zone’s trust
+//! anchor down to the zone that signed each answer, and looking up whatever
+//! records the chain needs along the way.
+//!
+//! When a response says that the name in the question does not exist, or
+//! has no records of the type, the NSEC or NSEC3 records in its authority
+//! section have to prove it, and the same goes for answers synthesised from
+//! a wildcard and for zones whose parents have no DS records for them.
+//!
+//! # References
+//!
+//! - [RFC 4035 §5](https://tools.ietf.org/html/rfc4035#section-5) — Protocol
+//!   Modifications for the DNS Security Extensions (March 2005)
+
+use std::collections::HashMap;
+use std::fmt;
+
+use log::*;
+
+use dns::{Answer, ErrorCode, Labels, RRset, Response};
+use dns::dnssec::{ds_matches, supports_digest, verify_rrsig, Algorithm, SigningKey, VerifyError};
+use dns::record::{Record, RecordType, DNSKEY, DS, RRSIG};
+
+use crate::denial::{check_denial, check_wildcard_expansion, Denial};
+use crate::output::record_type_name;
+
+
+/// The DS records for the root zone’s key-signing keys, which every chain
+/// of trust starts from, as published by IANA: the 2017 key and the 2024
+/// key that replaces it.
+///
+/// # References
+///
+/// - [Root Zone Trust Anchors](https://data.iana.org/root-anchors/root-anchors.xml)
+const ROOT_TRUST_ANCHORS: &[(u16, u8, u8, &str)] = &[
+    (20326, 8, 2, "E06D44B80B8F1D39A95C0B0D7C65D08458E880409BBC683457104237C7F8EC8D"),
+    (38696, 8, 2, "683D2D0ACB8C9B712A1948B27F741219298D0A450D612C483AF444A4C0FB2B16"),
+];
+
+
+/// The outcome of validating a response.
+#[derive(PartialEq, Debug, Clone)]
+pub enum Verdict {
+
+    /// Every answer has a signature that verifies with a key that the chain
+    /// of trust leads to.
+    Secure,
+
+    /// The name or type in the question does not exist, and the signed
+    /// NSEC or NSEC3 records in the response prove it.
+    Denied(Denial),
+
+    /// At least one answer is in a zone that the chain of trust does not
+    /// reach, because a zone above it is not signed.
+    Insecure,
+
+    /// At least one answer should have been signed, but its signature, or a
+    /// link in the chain of trust above it, is missing or does not verify.
+    Bogus(String),
+
+    /// The response could not be validated either way, because it has no
+    /// answers and does not say that anything does not exist, or because a
+    /// record needed for the chain could not be looked up.
+    Indeterminate(String),
+}
+
+impl Verdict {
+
+    /// How bad the verdict is, for picking the worst one out of several.
+    fn severity(&self) -> u8 {
+        match self {
+            Self::Secure            => 0,
+            Self::Denied(_)         => 0,
+            Self::Insecure          => 1,
+            Self::Indeterminate(_)  => 2,
+            Self::Bogus(_)          => 3,
+        }
+    }
+
+    /// Returns whichever of the two verdicts is worse.
+    fn worst(self, other: Self) -> Self {
+        if other.severity() > self.severity() { other } else { self }
+    }
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Secure                 => write!(f, "secure"),
+            Self::Denied(denial)         => write!(f, "secure: {}", denial),
+            Self::Insecure               => write!(f, "insecure"),
+            Self::Bogus(reason)          => write!(f, "bogus: {}", reason),
+            Self::Indeterminate(reason)  => write!(f, "indeterminate: {}", reason),
+        }
+    }
+}
+
+
+/// How far a zone’s keys can be trusted.
+#[derive(PartialEq, Debug, Clone)]
+enum Trust {
+
+    /// The chain of trust reaches the zone, and these are its keys.
+    Keys(Vec<DNSKEY>),
+
+    /// The chain of trust stops above the zone.
+    Insecure,
+
+    /// A link in the chain of trust down to the zone is broken.
+    Bogus(String),
+
+    /// A record needed for the chain could not be looked up.
+    Unknown(String),
+}
+
+impl Trust {
+
+    /// The verdict for records that are in a zone with this level of trust
+    /// but that have no signatures.
+    fn unsigned_verdict(&self, what: impl fmt::Display) -> Verdict {
+        match self {
+            Self::Keys(_)          => Verdict::Bogus(format!("{} is not signed", what)),
+            Self::Insecure         => Verdict::Insecure,
+            Self::Bogus(reason)    => Verdict::Bogus(reason.clone()),
+            Self::Unknown(reason)  => Verdict::Indeterminate(reason.clone()),
+        }
+    }
+}
+
+
+/// Validates responses, using a function to look up the DS and DNSKEY
+/// records it needs, and remembering how far each zone can be trusted.
+pub struct Validator<F> {
+
+    /// Looks up a name and type, returning the response or the reason
+    /// it failed.
+    lookup: F,
+
+    /// The current time, as a DNSSEC signature timestamp.
+    now: u32,
+
+    /// The DS records that the root zone’s keys are checked against.
+    anchors: Vec<DS>,
+
+    /// How far each zone that has been looked at can be trusted, keyed by
+    /// its name in canonical form.
+    zones: HashMap<String, Trust>,
+}
+
+impl<F> Validator<F>
+where F: FnMut(&Labels, RecordType) -> Result<Response, String>
+{
+
+    /// Creates a new validator that starts its chains of trust from the
+    /// built-in root trust anchors.
+    pub fn new(lookup: F, now: u32) -> Self {
+        Self::with_anchors(lookup, now, root_trust_anchors())
+    }
+
+    /// Creates a new validator that starts its chains of trust from the
+    /// given DS records for the root zone.
+    fn with_anchors(lookup: F, now: u32, anchors: Vec<DS>) -> Self {
+        Self { lookup, now, anchors, zones: HashMap::new() }
+    }
+
+    /// Validates every set of records in the response’s answer section, and
+    /// the proof that the name or type in the question does not exist if
+    /// the response says so, returning the worst verdict out of all of them.
+    pub fn validate(&mut self, response: &Response) -> Verdict {
+        let rrsets = record_sets(&response.answers);
+
+        let verdict = rrsets.iter()
+            .map(|rrset| {
+                let rrsigs = signatures(&response.answers, &rrset.owner, rrset.record_type);
+                self.validate_rrset(rrset, &rrsigs, &response.authorities)
+            })
+            .fold(Verdict::Secure, Verdict::worst);
+
+        match denied_question(response) {
+            Some((name, qtype, nxdomain))  => self.validate_denial(&name, qtype, nxdomain, &response.authorities).worst(verdict),
+            None if rrsets.is_empty()      => Verdict::Indeterminate(String::from("no answers to validate")),
+            None                           => verdict,
+        }
+    }
+
+    /// Validates one set of records, with the signatures over it, using
+    /// the records in the authority section to check answers synthesised
+    /// from a wildcard.
+    fn validate_rrset(&mut self, rrset: &RRset, rrsigs: &[RRSIG], authorities: &[Answer]) -> Verdict {
+        let what = describe(&rrset.owner, rrset.record_type);
+
+        if rrsigs.is_empty() {
+            return match self.zone_of(&rrset.owner) {
+                Ok(zone)    => self.trust(&zone).unsigned_verdict(what),
+                Err(error)  => Verdict::Indeterminate(error),
+            };
+        }
+
+        let mut verdict = Verdict::Bogus(format!("{} has no signature from its own zone", what));
+
+        for rrsig in rrsigs {
+            if ! rrset.owner.is_within(&rrsig.signer_name) {
+                continue;
+            }
+
+            match self.trust(&rrsig.signer_name) {
+                Trust::Keys(keys) => {
+                    match verify_with_keys(rrset, rrsig, &rrsig.signer_name, &keys, self.now) {
+                        Ok(())  => return self.validate_expansion(&rrset.owner, rrsig, authorities),
+                        Err(e)  => verdict = Verdict::Bogus(format!("{}: {}", what, e)),
+                    }
+                }
+                Trust::Insecure         => return Verdict::Insecure,
+                Trust::Bogus(reason)    => return Verdict::Bogus(reason),
+                Trust::Unknown(reason)  => return Verdict::Indeterminate(reason),
+            }
+        }
+
+        verdict
+    }
+
+    /// Checks whether an answer with a valid signature was synthesised from
+    /// a wildcard, which it was if the signature says its owner name has
+    /// fewer labels than the answer’s, and if so, validates the proof that
+    /// there was no closer match for the name than the wildcard.
+    fn validate_expansion(&mut self, owner: &Labels, rrsig: &RRSIG, authorities: &[Answer]) -> Verdict {
+        let owner_labels = owner.len() - usize::from(owner.is_wildcard());
+        if usize::from(rrsig.labels) >= owner_labels {
+            return Verdict::Secure;
+        }
+
+        match self.validate_proofs(authorities) {
+            Some(Verdict::Secure) | None  => {}
+            Some(verdict)                 => return verdict,
+        }
+
+        match proof_verdict(check_wildcard_expansion(owner, rrsig.labels, authorities)) {
+            Verdict::Denied(_)  => Verdict::Secure,
+            other               => other,
+        }
+    }
+
+    /// Validates the proof that a name, or a type of record at it, does not
+    /// exist, from the NSEC or NSEC3 records in the authority section. Their
+    /// signatures have to verify, and they have to cover the name.
+    fn validate_denial(&mut self, name: &Labels, qtype: RecordType, nxdomain: bool, authorities: &[Answer]) -> Verdict {
+        match self.validate_proofs(authorities) {
+            Some(Verdict::Secure)  => proof_verdict(check_denial(name, qtype, nxdomain, authorities)),
+            Some(verdict)          => verdict,
+            None                   => match self.zone_of(name) {
+                Ok(zone)    => self.trust(&zone).unsigned_verdict(format!("the denial of {}", describe(name, qtype))),
+                Err(error)  => Verdict::Indeterminate(error),
+            },
+        }
+    }
+
+    /// Validates the signatures over the NSEC and NSEC3 records in the
+    /// authority section, returning the worst verdict out of all of them,
+    /// or nothing if there are none.
+    fn validate_proofs(&mut self, authorities: &[Answer]) -> Option<Verdict> {
+        let proofs = record_sets(authorities).into_iter()
+            .filter(|rrset| matches!(rrset.record_type, RecordType::NSEC | RecordType::NSEC3))
+            .collect::<Vec<_>>();
+
+        if proofs.is_empty() {
+            return None;
+        }
+
+        // These records are never synthesised from a wildcard themselves,
+        // so there is nothing to check one against if it says it was.
+        let verdict = proofs.into_iter()
+            .map(|rrset| {
+                let rrsigs = signatures(authorities, &rrset.owner, rrset.record_type);
+                self.validate_rrset(&rrset, &rrsigs, &[])
+            })
+            .fold(Verdict::Secure, Verdict::worst);
+
+        Some(verdict)
+    }
+
+    /// Works out how far the zone with the given apex can be trusted,
+    /// checking its DS records against its parent’s keys and its DNSKEY
+    /// records against its DS records, or returns the answer from last
+    /// time if the zone has already been looked at.
+    fn trust(&mut self, zone: &Labels) -> Trust {
+        let key = zone.to_canonical().to_string();
+        if let Some(trust) = self.zones.get(&key) {
+            return trust.clone();
+        }
+
+        let trust = self.establish_trust(zone);
+        debug!("Trust for zone {:?} -> {:?}", zone.to_string(), trust);
+        self.zones.insert(key, trust.clone());
+        trust
+    }
+
+    /// Does the work of `trust`, without the remembering.
+    fn establish_trust(&mut self, zone: &Labels) -> Trust {
+        let ds_records = if zone.len() == 0 {
+            self.anchors.clone()
+        }
+        else {
+            match self.delegation_signers(zone) {
+                Ok(ds_records)  => ds_records,
+                Err(trust)      => return trust,
+            }
+        };
+
+        // DS records for algorithms or digests that cannot be checked get
+        // treated as though they were not there at all (RFC 4035 §5.2)
+        let usable = ds_records.iter()
+            .filter(|ds| Algorithm::from_number(ds.algorithm).is_some())
+            .filter(|ds| supports_digest(ds.digest_type))
+            .collect::<Vec<_>>();
+
+        if usable.is_empty() {
+            return Trust::Insecure;
+        }
+
+        let response = match (self.lookup)(zone, RecordType::DNSKEY) {
+            Ok(response)  => response,
+            Err(error)    => return Trust::Unknown(format!("could not look up DNSKEY records for {}: {}", display_zone(zone), error)),
+        };
+
+        let rrset = rrset_of(&response.answers, zone, RecordType::DNSKEY);
+        let keys = rrset.iter().flat_map(|rrset| &rrset.records)
+            .filter_map(|record| if let Record::DNSKEY(dnskey) = record { Some(dnskey.clone()) } else { None })
+            .collect::<Vec<_>>();
+
+        let entry_points = keys.iter()
+            .filter(|dnskey| usable.iter().any(|ds| ds_matches(ds, zone, dnskey) == Ok(true)))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        let rrset = match rrset {
+            Some(rrset) if ! entry_points.is_empty()  => rrset,
+            _ => return Trust::Bogus(format!("no DNSKEY record for {} matches its DS records", display_zone(zone))),
+        };
+
+        let mut error = VerifyError::BadSignature;
+
+        for rrsig in signatures(&response.answers, zone, RecordType::DNSKEY) {
+            match verify_with_keys(&rrset, &rrsig, zone, &entry_points, self.now) {
+                Ok(())  => return Trust::Keys(keys),
+                Err(e)  => error = e,
+            }
+        }
+
+        Trust::Bogus(format!("DNSKEY records for {}: {}", display_zone(zone), error))
+    }
+
+    /// Looks up the DS records for a zone that is not the root, and checks
+    /// them against the keys of the zone that signed them. Returns the
+    /// records if they can be trusted, and how far the zone can be trusted
+    /// otherwise.
+    fn delegation_signers(&mut self, zone: &Labels) -> Result<Vec<DS>, Trust> {
+        let what = describe(zone, RecordType::DS);
+
+        let response = (self.lookup)(zone, RecordType::DS)
+            .map_err(|error| Trust::Unknown(format!("could not look up {}: {}", what, error)))?;
+
+        let rrset = rrset_of(&response.answers, zone, RecordType::DS);
+        let rrsigs = signatures(&response.answers, zone, RecordType::DS);
+
+        let rrset = match rrset {
+            Some(rrset) if ! rrsigs.is_empty()  => rrset,
+            rrset => {
+                // The DS records are in the parent zone, not the zone itself.
+                let parent = self.zone_of(&zone.ancestor(zone.len() - 1)).map_err(Trust::Unknown)?;
+
+                return Err(match self.trust(&parent) {
+                    Trust::Keys(_) if rrset.is_none()  => self.no_delegation_signers(zone, &response),
+                    Trust::Keys(_)                     => Trust::Bogus(format!("{} is not signed", what)),
+                    other                              => other,
+                });
+            }
+        };
+
+        let mut error = VerifyError::WrongSigner;
+
+        for rrsig in &rrsigs {
+            let signer = &rrsig.signer_name;
+            if signer.len() >= zone.len() || ! zone.is_within(signer) {
+                continue;
+            }
+
+            match self.trust(signer) {
+                Trust::Keys(keys) => {
+                    match verify_with_keys(&rrset, rrsig, signer, &keys, self.now) {
+                        Ok(())  => return Ok(rrset.records.into_iter().filter_map(|r| if let Record::DS(ds) = r { Some(ds) } else { None }).collect()),
+                        Err(e)  => error = e,
+                    }
+                }
+                other => return Err(other),
+            }
+        }
+
+        Err(Trust::Bogus(format!("{}: {}", what, error)))
+    }
+
+    /// Works out how far a zone can be trusted when its signed parent has
+    /// no DS records for it, which makes it insecure, as long as the NSEC
+    /// or NSEC3 records in the response to the DS query prove it.
+    fn no_delegation_signers(&mut self, zone: &Labels, response: &Response) -> Trust {
+        let nxdomain = response.flags.error_code == Some(ErrorCode::NXDomain);
+
+        match self.validate_proofs(&response.authorities) {
+            Some(Verdict::Secure)  => {}
+            Some(Verdict::Bogus(reason))          => return Trust::Bogus(reason),
+            Some(Verdict::Indeterminate(reason))  => return Trust::Unknown(reason),
+            Some(_)                               => return Trust::Insecure,
+            None                                  => return Trust::Bogus(format!("no NSEC or NSEC3 records prove that {} has no DS records", display_zone(zone))),
+        }
+
+        match proof_verdict(check_denial(zone, RecordType::DS, nxdomain, &response.authorities)) {
+            Verdict::Bogus(reason)  => Trust::Bogus(reason),
+            _                       => Trust::Insecure,
+        }
+    }
+
+    /// Finds the apex of the zone that a name is in, from the SOA record
+    /// that comes back when looking it up.
+    fn zone_of(&mut self, name: &Labels) -> Result<Labels, String> {
+        let response = (self.lookup)(name, RecordType::SOA)
+            .map_err(|error| format!("could not look up the zone of {}: {}", display_zone(name), error))?;
+
+        response.answers.iter().chain(&response.authorities)
+            .find_map(|answer| match answer {
+                Answer::Standard { qname, record: Record::SOA(_), .. } if name.is_within(qname) => Some(qname.clone()),
+                _ => None,
+            })
+            .ok_or_else(|| format!("could not find the zone of {}", display_zone(name)))
+    }
+}
+
+
+/// Returns the current time as a DNSSEC signature timestamp, which is the
+/// number of seconds since the Unix epoch, modulo 2³².
+pub fn current_time() -> u32 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let [_, _, _, _, a, b, c, d] = seconds.to_be_bytes();
+    u32::from_be_bytes([ a, b, c, d ])
+}
+
+/// Returns the built-in root trust anchors as DS records.
+fn root_trust_anchors() -> Vec<DS> {
+    ROOT_TRUST_ANCHORS.iter()
+        .map(|(key_tag, algorithm, digest_type, digest)| {
+            let digest = (0 .. digest.len()).step_by(2)
+                .map(|i| u8::from_str_radix(&digest[i .. i + 2], 16).expect("Invalid trust anchor"))
+                .collect();
+
+            DS { key_tag: *key_tag, algorithm: *algorithm, digest_type: *digest_type, digest }
+        })
+        .collect()
+}
+
+/// Works out what the response says does not exist, if anything. This is
+/// the name at the end of any chain of CNAME records from the name in the
+/// question, along with the type in the question, and whether the name
+/// itself does not exist or only has no records of that type. Responses
+/// without answers or an SOA record are referrals, not denials.
+fn denied_question(response: &Response) -> Option<(Labels, RecordType, bool)> {
+    let query = response.queries.first()?;
+    let mut name = query.qname.clone();
+
+    if query.qtype != RecordType::CNAME {
+        for _ in 0 .. response.answers.len() {
+            match records_of(&response.answers, &name, RecordType::CNAME).first() {
+                Some(Record::CNAME(cname))  => name = cname.domain.clone(),
+                _                           => break,
+            }
+        }
+    }
+
+    let has_soa = response.authorities.iter()
+        .any(|answer| matches!(answer, Answer::Standard { record: Record::SOA(_), .. }));
+
+    match response.flags.error_code {
+        Some(ErrorCode::NXDomain)                                                  => Some((name, query.qtype, true)),
+        None if has_soa && records_of(&response.answers, &name, query.qtype).is_empty()  => Some((name, query.qtype, false)),
+        _                                                                          => None,
+    }
+}
+
+/// Turns the result of checking a proof that something does not exist into
+/// a verdict, for once the signatures over the proof have been verified.
+fn proof_verdict(result: Result<Denial, String>) -> Verdict {
+    match result {
+        Ok(denial) if denial.is_proven()  => Verdict::Denied(denial),
+        Ok(_)                             => Verdict::Insecure,
+        Err(reason)                       => Verdict::Bogus(reason),
+    }
+}
+
+/// Groups the records in a section into sets with the same owner name,
+/// class, and type, leaving out the signatures.
+fn record_sets(answers: &[Answer]) -> Vec<RRset> {
+    RRset::group(answers).into_iter()
+        .filter(|rrset| rrset.record_type != RecordType::RRSIG)
+        .collect()
+}
+
+/// Returns the set of records of the given type with the given owner name,
+/// in whichever class they are in, if there are any.
+fn rrset_of(answers: &[Answer], owner: &Labels, record_type: RecordType) -> Option<RRset> {
+    RRset::group(answers).into_iter()
+        .find(|rrset| rrset.matches(owner, rrset.qclass, record_type))
+}
+
+/// Tries to verify the signature with each of the keys it could have been
+/// made with, returning the error from the last one if none of them work.
+fn verify_with_keys(rrset: &RRset, rrsig: &RRSIG, owner: &Labels, keys: &[DNSKEY], now: u32) -> Result<(), VerifyError> {
+    let mut error = VerifyError::KeyMismatch;
+
+    for dnskey in keys.iter().filter(|dnskey| dnskey.key_tag() == rrsig.key_tag) {
+        match verify_rrsig(rrset, rrsig, SigningKey { owner, dnskey }, now) {
+            Ok(())  => return Ok(()),
+            Err(e)  => error = e,
+        }
+    }
+
+    Err(error)
+}
+
+/// Returns the records of the given type with the given owner name.
+fn records_of(answers: &[Answer], owner: &Labels, record_type: RecordType) -> Vec<Record> {
+    answers.iter()
+        .filter_map(|answer| match answer {
+            Answer::Standard { qname, record, .. }
+                if record.record_type() == record_type && qname.to_canonical() == owner.to_canonical()
+                => Some(record.clone()),
+            _   => None,
+        })
+        .collect()
+}
+
+/// Returns the signatures over the records of the given type with the
+/// given owner name.
+fn signatures(answers: &[Answer], owner: &Labels, record_type: RecordType) -> Vec<RRSIG> {
+    records_of(answers, owner, RecordType::RRSIG).into_iter()
+        .filter_map(|record| match record {
+            Record::RRSIG(rrsig) if RecordType::from(rrsig.type_covered) == record_type => Some(rrsig),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Describes a set of records for a message, such as “lookup.dog. A”.
+fn describe(owner: &Labels, record_type: RecordType) -> String {
+    format!("{} {}", display_zone(owner), record_type_name(record_type.type_number()))
+}
+
+/// Displays a name, showing the root as a dot instead of nothing.
+fn display_zone(name: &Labels) -> String {
+    if name.len() == 0 { String::from(".") } else { name.to_string() }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::net::Ipv4Addr;
+    use dns::{Flags, QClass, Query};
+    use dns::record::{A, CNAME, NSEC, SOA};
+    use pretty_assertions::assert_eq;
+
+    const NOW: u32 = 1_650_000_000;
+
+    fn name(input: &str) -> Labels {
+        Labels::encode(input).unwrap()
+    }
+
+    fn answer(owner: &str, record: Record) -> Answer {
+        Answer::Standard { qname: name(owner), qclass: QClass::IN, ttl: 300, record }
+    }
+
+    fn a(owner: &str) -> Answer {
+        answer(owner, Record::A(A { address: Ipv4Addr::new(192, 0, 2, 1) }))
+    }
+
+    fn soa(owner: &str) -> Answer {
+        answer(owner, Record::SOA(SOA {
+            mname: name("ns.lookup.dog"),
+            rname: name("hostmaster.lookup.dog"),
+            serial: 1,
+            refresh_interval: 3600,
+            retry_interval: 600,
+            expire_limit: 86400,
+            minimum_ttl: 300,
+        }))
+    }
+
+    fn dnskey(owner: &str) -> Answer {
+        answer(owner, Record::DNSKEY(DNSKEY { flags: 257, protocol: 3, algorithm: 13, public_key: vec![ 0x42; 64 ] }))
+    }
+
+    fn rrsig(owner: &str, type_covered: u16, signer: &str) -> Answer {
+        answer(owner, Record::RRSIG(RRSIG {
+            type_covered,
+            algorithm: 13,
+            labels: 2,
+            original_ttl: 300,
+            signature_expiration: NOW + 1000,
+            signature_inception: NOW - 1000,
+            key_tag: 19542,
+            signer_name: name(signer),
+            signature: vec![ 0x00; 64 ],
+        }))
+    }
+
+    fn response(qname: &str, answers: Vec<Answer>, authorities: Vec<Answer>) -> Response {
+        Response {
+            transaction_id: 0x1234,
+            flags: Flags::standard_response(),
+            queries: vec![ Query { qname: name(qname), qclass: QClass::IN, qtype: RecordType::A } ],
+            answers,
+            authorities,
+            additionals: Vec::new(),
+        }
+    }
+
+    /// A lookup function for a tree of zones where every zone is its own
+    /// two-label name, and nothing has any DS records.
+    fn unsigned_tree(qname: &Labels, qtype: RecordType) -> Result<Response, String> {
+        let zone = qname.ancestor(2).to_string();
+        match qtype {
+            RecordType::SOA  => Ok(response(&qname.to_string(), Vec::new(), vec![ soa(&zone) ])),
+            _                => Ok(response(&qname.to_string(), Vec::new(), Vec::new())),
+        }
+    }
+
+    #[test]
+    fn no_answers() {
+        let mut validator = Validator::new(unsigned_tree, NOW);
+        assert_eq!(validator.validate(&response("lookup.dog", Vec::new(), Vec::new())),
+                   Verdict::Indeterminate(String::from("no answers to validate")));
+    }
+
+    #[test]
+    fn unsigned_without_anchors() {
+        let mut validator = Validator::with_anchors(unsigned_tree, NOW, Vec::new());
+        assert_eq!(validator.validate(&response("lookup.dog", vec![ a("lookup.dog") ], Vec::new())),
+                   Verdict::Insecure);
+    }
+
+    #[test]
+    fn root_keys_not_matching_anchors() {
+        let lookup = |qname: &Labels, qtype: RecordType| match qtype {
+            RecordType::DNSKEY  => Ok(response(".", vec![ dnskey(".") ], Vec::new())),
+            _                   => unsigned_tree(qname, qtype),
+        };
+
+        let mut validator = Validator::new(lookup, NOW);
+        assert_eq!(validator.validate(&response("lookup.dog", vec![ a("lookup.dog") ], Vec::new())),
+                   Verdict::Bogus(String::from("no DNSKEY record for . matches its DS records")));
+    }
+
+    #[test]
+    fn signature_from_another_zone() {
+        let mut validator = Validator::with_anchors(unsigned_tree, NOW, Vec::new());
+        let answers = vec![ a("lookup.dog"), rrsig("lookup.dog", 1, "lookup.cat") ];
+        assert_eq!(validator.validate(&response("lookup.dog", answers, Vec::new())),
+                   Verdict::Bogus(String::from("lookup.dog. A has no signature from its own zone")));
+    }
+
+    #[test]
+    fn signed_in_insecure_zone() {
+        let mut validator = Validator::with_anchors(unsigned_tree, NOW, Vec::new());
+        let answers = vec![ a("lookup.dog"), rrsig("lookup.dog", 1, "lookup.dog") ];
+        assert_eq!(validator.validate(&response("lookup.dog", answers, Vec::new())),
+                   Verdict::Insecure);
+    }
+
+    #[test]
+    fn lookup_failure() {
+        let lookup = |_: &Labels, _: RecordType| Err(String::from("timed out"));
+        let mut validator = Validator::new(lookup, NOW);
+        assert_eq!(validator.validate(&response("lookup.dog", vec![ a("lookup.dog") ], Vec::new())),
+                   Verdict::Indeterminate(String::from("could not look up the zone of lookup.dog.: timed out")));
+    }
+
+    #[test]
+    fn zones_are_remembered() {
+        let mut lookups = 0;
+        let lookup = |qname: &Labels, qtype: RecordType| {
+            lookups += 1;
+            unsigned_tree(qname, qtype)
+        };
+
+        let mut validator = Validator::with_anchors(lookup, NOW, Vec::new());
+        let answers = vec![ a("lookup.dog"), a("www.lookup.dog") ];
+        assert_eq!(validator.validate(&response("lookup.dog", answers, Vec::new())),
+                   Verdict::Insecure);
+        drop(validator);
+
+        // an SOA lookup for each of the two names, then a DS lookup and an
+        // SOA lookup for the parent of each of lookup.dog and dog, but
+        // nothing more for the second name, as its zone is already known
+        assert_eq!(lookups, 6);
+    }
+
+    fn nxdomain(qname: &str, answers: Vec<Answer>, authorities: Vec<Answer>) -> Response {
+        let mut response = response(qname, answers, authorities);
+        response.flags.error_code = Some(ErrorCode::NXDomain);
+        response
+    }
+
+    fn cname(owner: &str, target: &str) -> Answer {
+        answer(owner, Record::CNAME(CNAME { domain: name(target) }))
+    }
+
+    fn nsec(owner: &str, next_domain: &str) -> Answer {
+        answer(owner, Record::NSEC(NSEC { next_domain: name(next_domain), types: vec![ 1 ] }))
+    }
+
+    #[test]
+    fn denial_in_insecure_zone() {
+        let mut validator = Validator::with_anchors(unsigned_tree, NOW, Vec::new());
+        assert_eq!(validator.validate(&nxdomain("nope.lookup.dog", Vec::new(), vec![ soa("lookup.dog") ])),
+                   Verdict::Insecure);
+    }
+
+    #[test]
+    fn unsigned_proof_in_insecure_zone() {
+        let mut validator = Validator::with_anchors(unsigned_tree, NOW, Vec::new());
+        let authorities = vec![ soa("lookup.dog"), nsec("a.lookup.dog", "z.lookup.dog") ];
+        assert_eq!(validator.validate(&nxdomain("nope.lookup.dog", Vec::new(), authorities)),
+                   Verdict::Insecure);
+    }
+
+    #[test]
+    fn denial_in_bogus_zone() {
+        let lookup = |qname: &Labels, qtype: RecordType| match qtype {
+            RecordType::DNSKEY  => Ok(response(".", vec![ dnskey(".") ], Vec::new())),
+            _                   => unsigned_tree(qname, qtype),
+        };
+
+        let mut validator = Validator::new(lookup, NOW);
+        assert_eq!(validator.validate(&nxdomain("nope.lookup.dog", Vec::new(), vec![ soa("lookup.dog") ])),
+                   Verdict::Bogus(String::from("no DNSKEY record for . matches its DS records")));
+    }
+
+    #[test]
+    fn denied_after_aliases() {
+        let answers = vec![ cname("www.lookup.dog", "web.lookup.dog"), cname("web.lookup.dog", "nope.lookup.dog") ];
+        assert_eq!(denied_question(&nxdomain("www.lookup.dog", answers, vec![ soa("lookup.dog") ])),
+                   Some((name("nope.lookup.dog"), RecordType::A, true)));
+    }
+
+    #[test]
+    fn denied_type() {
+        let answers = vec![ cname("www.lookup.dog", "web.lookup.dog") ];
+        assert_eq!(denied_question(&response("www.lookup.dog", answers, vec![ soa("lookup.dog") ])),
+                   Some((name("web.lookup.dog"), RecordType::A, false)));
+    }
+
+    #[test]
+    fn nothing_denied() {
+        assert_eq!(denied_question(&response("lookup.dog", vec![ a("lookup.dog") ], vec![ soa("lookup.dog") ])),
+                   None);
+        assert_eq!(denied_question(&response("lookup.dog", Vec::new(), Vec::new())),
+                   None);
+    }
+
+    #[test]
+    fn proof_verdicts() {
+        assert_eq!(proof_verdict(Ok(Denial::NoName)), Verdict::Denied(Denial::NoName));
+        assert_eq!(proof_verdict(Ok(Denial::OptOut)), Verdict::Insecure);
+        assert_eq!(proof_verdict(Err(String::from("no"))), Verdict::Bogus(String::from("no")));
+    }
+
+    #[test]
+    fn worst_verdict() {
+        let bogus = Verdict::Bogus(String::from("bad"));
+        assert_eq!(Verdict::Secure.worst(Verdict::Insecure), Verdict::Insecure);
+        assert_eq!(bogus.clone().worst(Verdict::Secure), bogus);
+        assert_eq!(Verdict::Indeterminate(String::from("?")).worst(bogus.clone()), bogus);
+    }
+
+    #[test]
+    fn trust_anchors_parse() {
+        let anchors = root_trust_anchors();
+        assert_eq!(anchors.len(), 2);
+        assert_eq!(anchors[0].key_tag, 20326);
+        assert_eq!(anchors[0].digest.len(), 32);
+    }
+}
@@ -0,0 +1,81 @@
+//! The machine-readable version of `--version`, which lists the features
+//! dog was compiled with, so other programs can check what it can do
+//! before running it.
+
+use json::{object, JsonValue};
+
+use dns::record::RecordType;
+
+use crate::output::json_record_type_name;
+
+
+/// Returns the version number and capabilities of this build of dog as a
+/// JSON document.
+pub fn json() -> JsonValue {
+    let record_types = RecordType::known_types().iter()
+        .map(|rt| json_record_type_name(*rt))
+        .collect::<Vec<_>>();
+
+    object! {
+        "name": "dog",
+        "version": env!("CARGO_PKG_VERSION"),
+        "features": features(),
+        "protocols": protocols(),
+        "edns": {
+            "versions": [ 0 ],
+            "options": [ "nsid", "expire", "cookie", "client_subnet", "tcp_keepalive", "padding", "chain", "extended_error", "report_channel" ],
+        },
+        "record_types": record_types,
+    }
+}
+
+/// Returns which optional features were compiled in.
+fn features() -> JsonValue {
+    let tls_backend: JsonValue =
+        if cfg!(feature = "with_nativetls") {
+            "native-tls".into()
+        }
+        else if cfg!(feature = "with_rustls") {
+            "rustls".into()
+        }
+        else {
+            JsonValue::Null
+        };
+
+    object! {
+        "tls": cfg!(feature = "with_tls"),
+        "https": cfg!(feature = "with_https"),
+        "tls_backend": tls_backend,
+        "idna": cfg!(feature = "with_idna"),
+        "dnssec": cfg!(feature = "with_dnssec"),
+        "tsig": cfg!(feature = "with_tsig"),
+        "sig0": cfg!(feature = "with_sig0"),
+        "doh_server": cfg!(feature = "with_doh_server"),
+        "quic": cfg!(feature = "with_quic"),
+        "http3": cfg!(feature = "with_http3"),
+        "geoip": false,
+    }
+}
+
+/// Returns the transport protocols that queries can be sent over.
+fn protocols() -> JsonValue {
+    let mut protocols = vec![ "udp", "tcp" ];
+
+    if cfg!(feature = "with_tls") {
+        protocols.push("tls");
+    }
+
+    if cfg!(feature = "with_https") {
+        protocols.push("https");
+    }
+
+    if cfg!(feature = "with_http3") {
+        protocols.push("http3");
+    }
+
+    if cfg!(feature = "with_quic") {
+        protocols.push("quic");
+    }
+
+    protocols.into()
+}
@@ -0,0 +1,319 @@
+//! Comparing the records in a zone file with the records a nameserver
+//! serves, by querying it for every owner and type in the file.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use dns::{Answer, ErrorCode, Labels, Response};
+use dns::record::{Record, RecordType};
+use dns_transport::Transport;
+use log::*;
+
+use crate::output::{record_type_name, status_mnemonic, CoordinateFormat, TextFormat};
+use crate::requests::RequestGenerator;
+use crate::resolve::ResolverLookupError;
+use dns::zonefile::{Zone, ZoneRecord};
+
+
+/// The differences between a zone file and a nameserver.
+#[derive(PartialEq, Debug)]
+pub struct ZoneDiff {
+
+    /// The number of record sets that were checked.
+    pub checked: usize,
+
+    /// The record sets that differ, in the order they appear in the file.
+    pub sets: Vec<RecordSetDiff>,
+
+    /// The line numbers and type names of the records in the file that
+    /// could not be read, and so were not checked.
+    pub skipped: Vec<(usize, String)>,
+}
+
+/// How one record set differs between a zone file and a nameserver.
+#[derive(PartialEq, Debug)]
+pub struct RecordSetDiff {
+
+    /// The domain name that owns the record set.
+    pub owner: Labels,
+
+    /// The type of the records in the set.
+    pub record_type: RecordType,
+
+    /// What is different about it.
+    pub difference: Difference,
+}
+
+/// What is different about one record set.
+#[derive(PartialEq, Debug)]
+pub enum Difference {
+
+    /// These records, with their TTLs, are in the zone file but were not
+    /// served, and these records were served but are not in the zone file.
+    /// Records with the same data but different TTLs appear in both.
+    Records {
+        missing: Vec<(u32, Record)>,
+        extra: Vec<(u32, Record)>,
+    },
+
+    /// The records could not be compared because no usable response arrived.
+    Failed(String),
+}
+
+
+/// Sends a query for every record set in the zone to the first nameserver
+/// and transport type in the inputs, and compares what it serves with what
+/// is in the file.
+pub fn run_diff(requests: RequestGenerator, zone: Zone) -> Result<ZoneDiff, ResolverLookupError> {
+    let edns = requests.edns;
+    let protocol_tweaks = requests.protocol_tweaks.clone();
+    let txid_generator = requests.txid_generator;
+    let transport = requests.first_transport()?;
+
+    let record_sets = group(zone.records);
+    let checked = record_sets.len();
+
+    let sets = record_sets.into_iter().filter_map(|(owner, record_type, expected)| {
+        let mut flags = dns::Flags::query();
+        protocol_tweaks.set_request_flags(&mut flags);
+
+        let mut additional = None;
+        if edns.should_send() {
+            let mut opt = dns::Request::additional_record();
+            protocol_tweaks.set_request_opt_fields(&mut opt);
+            additional = Some(opt);
+        }
+
+        let query = dns::Query { qname: owner.clone(), qtype: record_type, qclass: dns::QClass::IN };
+        let request = dns::Request { transaction_id: txid_generator.generate(), flags, query, additional };
+
+        let difference = match served_records(&*transport, &request) {
+            Ok(served)  => compare(expected, served)?,
+            Err(why)    => Difference::Failed(why),
+        };
+
+        Some(RecordSetDiff { owner, record_type, difference })
+    }).collect();
+
+    Ok(ZoneDiff { checked, sets, skipped: zone.skipped })
+}
+
+/// The records with one owner and type, along with their TTLs.
+type RecordSet = (Labels, RecordType, Vec<(u32, Record)>);
+
+/// Groups the records from a zone file into record sets by owner and type,
+/// keeping the order the sets first appear in. The sets are indexed by
+/// their lowercased owner, as large zones have millions of them.
+fn group(records: Vec<ZoneRecord>) -> Vec<RecordSet> {
+    let mut sets: Vec<RecordSet> = Vec::new();
+    let mut indices: HashMap<(String, u16), usize> = HashMap::new();
+
+    for ZoneRecord { owner, ttl, record } in records {
+        let record_type = record.record_type();
+        let key = (owner.to_string().to_ascii_lowercase(), record_type.type_number());
+
+        match indices.get(&key) {
+            Some(&index) => {
+                sets[index].2.push((ttl, record));
+            }
+            None => {
+                indices.insert(key, sets.len());
+                sets.push((owner, record_type, vec![ (ttl, record) ]));
+            }
+        }
+    }
+
+    sets
+}
+
+/// Sends one request, and returns the records in the response that have
+/// the name and type that were asked for. Referrals put the nameservers
+/// for a delegated name in the authority section, so it gets searched too.
+fn served_records(transport: &dyn Transport, request: &dns::Request) -> Result<Vec<(u32, Record)>, String> {
+    let response = transport.send(request).map_err(|e| {
+        warn!("Zone diff query failed: {:?}", e);
+        format!("{:?}", e)
+    })?;
+
+    match response.flags.error_code {
+        None | Some(ErrorCode::NXDomain) => {}
+        rcode => return Err(status_mnemonic(rcode)),
+    }
+
+    let Response { answers, authorities, .. } = response;
+    let served = answers.into_iter().chain(authorities)
+        .filter_map(|answer| match answer {
+            Answer::Standard { qname, ttl, record, .. }
+                if same_name(&qname, &request.query.qname) && record.record_type() == request.query.qtype
+                => Some((ttl, record)),
+            _   => None,
+        })
+        .collect();
+
+    Ok(served)
+}
+
+/// Compares the records from the zone file with the records that were
+/// served, returning `None` if they are the same, ignoring their order.
+fn compare(expected: Vec<(u32, Record)>, served: Vec<(u32, Record)>) -> Option<Difference> {
+    let missing = expected.iter().filter(|record| ! served.contains(record)).cloned().collect::<Vec<_>>();
+    let extra = served.into_iter().filter(|record| ! expected.contains(record)).collect::<Vec<_>>();
+
+    if missing.is_empty() && extra.is_empty() {
+        None
+    }
+    else {
+        Some(Difference::Records { missing, extra })
+    }
+}
+
+/// Whether two domain names are the same, ignoring case.
+fn same_name(one: &Labels, other: &Labels) -> bool {
+    dns::Name::from(one.clone()) == dns::Name::from(other.clone())
+}
+
+
+impl ZoneDiff {
+
+    /// Whether the nameserver serves exactly what is in the zone file.
+    pub fn is_identical(&self) -> bool {
+        self.sets.is_empty()
+    }
+
+    /// The number of record sets that have records in the zone file that
+    /// were not served, and no records that were served instead.
+    pub fn missing_count(&self) -> usize {
+        self.sets.iter().filter(|set| matches!(&set.difference, Difference::Records { extra, .. } if extra.is_empty())).count()
+    }
+
+    /// The number of record sets that had records served that are not in
+    /// the zone file, and are not missing anything.
+    pub fn extra_count(&self) -> usize {
+        self.sets.iter().filter(|set| matches!(&set.difference, Difference::Records { missing, .. } if missing.is_empty())).count()
+    }
+
+    /// The number of record sets that are both missing records and have
+    /// extra ones.
+    pub fn different_count(&self) -> usize {
+        self.sets.iter().filter(|set| matches!(&set.difference, Difference::Records { missing, extra } if ! missing.is_empty() && ! extra.is_empty())).count()
+    }
+
+    /// The number of record sets that could not be checked.
+    pub fn failed_count(&self) -> usize {
+        self.sets.iter().filter(|set| matches!(set.difference, Difference::Failed(_))).count()
+    }
+}
+
+impl fmt::Display for ZoneDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let tf = TextFormat { format_durations: false, coordinates: CoordinateFormat::DegreesMinutesSeconds, max_answers: None, language: None, txt_decoding: None };
+
+        for set in &self.sets {
+            let type_name = record_type_name(set.record_type.type_number());
+
+            match &set.difference {
+                Difference::Records { missing, extra } => {
+                    for (sign, records) in &[ ('-', missing), ('+', extra) ] {
+                        for (ttl, record) in *records {
+                            writeln!(f, "{} {} {} IN {} {}", sign, set.owner, ttl, type_name, tf.record_payload_summary(record.clone()))?;
+                        }
+                    }
+                }
+                Difference::Failed(why) => {
+                    writeln!(f, "! {} {}: {}", set.owner, type_name, why)?;
+                }
+            }
+        }
+
+        for (line, type_name) in &self.skipped {
+            writeln!(f, "warning: skipped the {} record on line {}, as dog cannot read its data", type_name, line)?;
+        }
+
+        write!(f, "{} record sets checked: ", self.checked)?;
+        if self.is_identical() {
+            writeln!(f, "no differences")
+        }
+        else {
+            writeln!(f, "{} missing, {} extra, {} different, {} failed",
+                     self.missing_count(), self.extra_count(), self.different_count(), self.failed_count())
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use dns::record::{A, NS};
+    use pretty_assertions::assert_eq;
+
+    fn a(ttl: u32, last: u8) -> (u32, Record) {
+        (ttl, Record::A(A { address: std::net::Ipv4Addr::new(192, 0, 2, last) }))
+    }
+
+    #[test]
+    fn same_records_in_any_order() {
+        assert_eq!(compare(vec![ a(60, 1), a(60, 2) ], vec![ a(60, 2), a(60, 1) ]),
+                   None);
+    }
+
+    #[test]
+    fn missing_record() {
+        assert_eq!(compare(vec![ a(60, 1), a(60, 2) ], vec![ a(60, 1) ]),
+                   Some(Difference::Records { missing: vec![ a(60, 2) ], extra: vec![] }));
+    }
+
+    #[test]
+    fn different_ttl() {
+        assert_eq!(compare(vec![ a(60, 1) ], vec![ a(300, 1) ]),
+                   Some(Difference::Records { missing: vec![ a(60, 1) ], extra: vec![ a(300, 1) ] }));
+    }
+
+    #[test]
+    fn grouping() {
+        let owner = |name| Labels::encode(name).unwrap();
+        let records = vec![
+            ZoneRecord { owner: owner("lookup.dog"), ttl: 60, record: a(60, 1).1 },
+            ZoneRecord { owner: owner("lookup.dog"), ttl: 60, record: Record::NS(NS { nameserver: owner("ns.dog") }) },
+            ZoneRecord { owner: owner("LOOKUP.dog"), ttl: 60, record: a(60, 2).1 },
+        ];
+
+        let sets = group(records);
+        assert_eq!(sets.len(), 2);
+        assert_eq!(sets[0].2, vec![ a(60, 1), a(60, 2) ]);
+    }
+
+    #[test]
+    fn report() {
+        let owner = Labels::encode("lookup.dog").unwrap();
+        let diff = ZoneDiff {
+            checked: 3,
+            sets: vec![
+                RecordSetDiff {
+                    owner: owner.clone(),
+                    record_type: RecordType::A,
+                    difference: Difference::Records { missing: vec![ a(60, 1) ], extra: vec![ a(60, 2) ] },
+                },
+                RecordSetDiff {
+                    owner,
+                    record_type: RecordType::NS,
+                    difference: Difference::Failed("REFUSED".into()),
+                },
+            ],
+            skipped: vec![ (7, "DS".into()) ],
+        };
+
+        assert_eq!(diff.to_string(), "\
+            - lookup.dog. 60 IN A 192.0.2.1\n\
+            + lookup.dog. 60 IN A 192.0.2.2\n\
+            ! lookup.dog. NS: REFUSED\n\
+            warning: skipped the DS record on line 7, as dog cannot read its data\n\
+            3 record sets checked: 0 missing, 0 extra, 1 different, 1 failed\n");
+    }
+
+    #[test]
+    fn identical_report() {
+        let diff = ZoneDiff { checked: 2, sets: vec![], skipped: vec![] };
+        assert_eq!(diff.to_string(), "2 record sets checked: no differences\n");
+    }
+}